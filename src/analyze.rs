@@ -0,0 +1,197 @@
+use crate::{Deserializer, Node, Result};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use halfbrown::HashMap;
+
+/// Finds every key that appears more than once in the same object, for
+/// auditing documents that other parsers might read differently: some
+/// keep the first value of a key, some the last. The document is only
+/// parsed into a tape, no DOM is built.
+///
+/// Each duplicated key is reported once per object, with the JSON
+/// pointer of the object. Objects are reported in the order they start
+/// in the document, so an object comes before the objects it holds. The pointer uses the syntax of `scan::find_field`,
+/// with `~0` and `~1` escaping `~` and `/` and the empty pointer for the
+/// document itself. The keys point into `input`, which is de-escaped in
+/// place.
+///
+/// ```rust
+/// use simd_json::analyze::duplicate_keys;
+/// let mut d = br#"{"role": "user", "a": [{"x": 1, "x": 2}], "role": "admin"}"#.to_vec();
+/// let dups = duplicate_keys(&mut d).unwrap();
+/// assert_eq!(dups, [("".to_string(), "role"), ("/a/0".to_string(), "x")]);
+/// ```
+///
+/// # Errors
+///
+/// Will return `Err` if `input` is invalid JSON.
+pub fn duplicate_keys(input: &mut [u8]) -> Result<Vec<(String, &str)>> {
+    let tape = Deserializer::from_slice(input)?.tape;
+    let mut res = Vec::new();
+    // the containers we are in and the step into each of them, they are
+    // kept on a stack so deeply nested documents can't overflow the
+    // thread stack
+    let mut levels: Vec<Level> = Vec::new();
+    let mut path: Vec<Step> = Vec::new();
+    // the first node of a tape is a placeholder, the document starts after it
+    let mut idx = 1;
+    loop {
+        match tape[idx] {
+            Node::Object(len, _) if len > 0 => {
+                check_object(&tape, idx, len, &path, &mut res);
+                levels.push(Level::Object(len));
+                path.push(Step::Key(key_at(&tape, idx + 1)));
+                idx += 2;
+                continue;
+            }
+            Node::Array(len, _) if len > 0 => {
+                levels.push(Level::Array(len));
+                path.push(Step::Index(0));
+                idx += 1;
+                continue;
+            }
+            _ => idx += 1,
+        }
+        // the value is done, move on to the next one in its container or
+        // leave every container that is done with it
+        loop {
+            let missing = match levels.last_mut() {
+                None => return Ok(res),
+                Some(Level::Object(missing) | Level::Array(missing)) => {
+                    *missing -= 1;
+                    *missing
+                }
+            };
+            let step = path.pop();
+            if missing > 0 {
+                match step {
+                    Some(Step::Key(_)) => {
+                        path.push(Step::Key(key_at(&tape, idx)));
+                        idx += 1;
+                    }
+                    Some(Step::Index(i)) => path.push(Step::Index(i + 1)),
+                    None => unreachable!(),
+                }
+                break;
+            }
+            levels.pop();
+        }
+    }
+}
+
+/// A container we are in, with the number of values still missing
+enum Level {
+    Array(usize),
+    Object(usize),
+}
+
+/// A step of the pointer to the current value
+#[derive(Clone, Copy)]
+enum Step<'de> {
+    Key(&'de str),
+    Index(usize),
+}
+
+fn key_at<'de>(tape: &[Node<'de>], idx: usize) -> &'de str {
+    if let Node::String(key) = tape[idx] {
+        key
+    } else {
+        unreachable!()
+    }
+}
+
+/// Reports the duplicate keys of the object of `len` entries at `idx`
+fn check_object<'de>(
+    tape: &[Node<'de>],
+    idx: usize,
+    len: usize,
+    path: &[Step],
+    res: &mut Vec<(String, &'de str)>,
+) {
+    let mut seen: HashMap<&str, usize> = HashMap::with_capacity(len);
+    let mut entry = idx + 1;
+    for _ in 0..len {
+        let key = key_at(tape, entry);
+        let count = seen.entry(key).or_insert(0);
+        *count += 1;
+        if *count == 2 {
+            res.push((pointer(path), key));
+        }
+        // the value after the key, containers know where they end
+        entry = match tape[entry + 1] {
+            Node::Array(_, end) | Node::Object(_, end) => end,
+            _ => entry + 2,
+        };
+    }
+}
+
+/// The JSON pointer of `path`
+fn pointer(path: &[Step]) -> String {
+    let mut res = String::new();
+    for step in path {
+        res.push('/');
+        match step {
+            Step::Key(key) => {
+                for c in key.chars() {
+                    match c {
+                        '~' => res.push_str("~0"),
+                        '/' => res.push_str("~1"),
+                        c => res.push(c),
+                    }
+                }
+            }
+            Step::Index(i) => {
+                let _ = write!(res, "{i}");
+            }
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::string::ToString;
+
+    fn dups(s: &str) -> Vec<(String, String)> {
+        let mut d = s.as_bytes().to_vec();
+        duplicate_keys(&mut d)
+            .expect("failed to parse")
+            .into_iter()
+            .map(|(p, k)| (p, k.to_string()))
+            .collect()
+    }
+
+    fn pair(p: &str, k: &str) -> (String, String) {
+        (p.to_string(), k.to_string())
+    }
+
+    #[test]
+    fn finds() {
+        assert!(dups(r#"{"a": 1, "b": {"a": 2}, "c": [{"a": 3}, {"a": 4}]}"#).is_empty());
+        assert_eq!(
+            dups(r#"{"a": 1, "a": 2, "a": 3, "b": [{}, [], {"c": {"d": 0, "d": 1}, "c": 2}]}"#),
+            [pair("", "a"), pair("/b/2", "c"), pair("/b/2/c", "d")]
+        );
+        // escapes are compared de-escaped, pointers are escaped
+        assert_eq!(
+            dups(r#"{"a/b~": {"x": 1, "\u0078": 2}, "k": "v"}"#),
+            [pair("/a~1b~0", "x")]
+        );
+        assert_eq!(dups(r#"[[1, [{"z": 1, "z": 1}]]]"#), [pair("/0/1/0", "z")]);
+        assert!(dups("1").is_empty());
+        assert!(dups("[]").is_empty());
+        let mut d = br#"{"a": 1, "a": }"#.to_vec();
+        assert!(duplicate_keys(&mut d).is_err());
+    }
+
+    #[test]
+    fn deep_nesting() {
+        use crate::value::deep::{nested, DEPTH};
+        let found = dups(&nested(DEPTH, "{\"k\": 1, \"k\": 2}"));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0.len(), DEPTH * 4);
+        assert_eq!(found[0].1, "k");
+    }
+}
@@ -0,0 +1,446 @@
+use crate::{Deserializer, Error, ErrorType, Node, Result, StaticNode};
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use arrow_array::{
+    ArrayRef, BooleanArray, Float64Array, Int64Array, NullArray, RecordBatch, RecordBatchOptions,
+    StringArray, UInt64Array,
+};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+use core::convert::TryFrom;
+use halfbrown::HashMap;
+
+/// Parses a JSON array of flat objects into an arrow `RecordBatch`, one
+/// row per object and one column per key in the order the keys are first
+/// seen. The tape is read directly into the columns, no DOM is built in
+/// between. Like `to_borrowed_value` this rewrites the input to de-escape
+/// strings.
+///
+/// The schema is inferred from the values, every column is nullable and
+/// rows that lack a key or have it set to `null` are null in that column:
+///
+/// * `true` and `false` become `Boolean`
+/// * strings become `Utf8`
+/// * integers become `Int64`, or `UInt64` if one of them doesn't fit an
+///   `i64` and none is negative
+/// * floats, and integers mixed with floats or that don't share an
+///   integer type, become `Float64`
+/// * columns with only nulls become `Null`
+///
+/// ```rust
+/// let mut d = br#"[{"id": 1, "name": "a"}, {"id": 2, "score": 0.5}]"#.to_vec();
+/// let batch = simd_json::to_record_batch(&mut d).unwrap();
+/// assert_eq!(batch.num_rows(), 2);
+/// assert_eq!(batch.num_columns(), 3);
+/// assert_eq!(batch.schema().field(2).name(), "score");
+/// ```
+///
+/// # Errors
+///
+/// Will return `Err` if `input` is invalid JSON, isn't an array of
+/// objects, holds nested arrays or objects as values or mixes values of
+/// incompatible types in one column.
+pub fn to_record_batch(input: &mut [u8]) -> Result<RecordBatch> {
+    let columns = Columns::read(input, None)?;
+    let fields = columns
+        .names
+        .iter()
+        .zip(&columns.values)
+        .map(|(name, values)| Ok(Field::new(name.as_str(), infer(name, values)?, true)))
+        .collect::<Result<Vec<_>>>()?;
+    columns.finish(Arc::new(Schema::new(fields)))
+}
+
+/// Parses a JSON array of flat objects into an arrow `RecordBatch` with
+/// the given `schema`. Keys that aren't fields of the schema are ignored,
+/// whatever their value, fields missing from a row or set to `null` are
+/// null. The supported data types are `Null`, `Boolean`, `Int64`,
+/// `UInt64`, `Float64` and `Utf8`, integers are accepted for `Float64`
+/// fields.
+///
+/// ```rust
+/// use arrow_schema::{DataType, Field, Schema};
+/// use std::sync::Arc;
+/// let schema = Arc::new(Schema::new(vec![
+///     Field::new("id", DataType::UInt64, false),
+///     Field::new("score", DataType::Float64, true),
+/// ]));
+/// let mut d = br#"[{"id": 1, "score": 2}, {"id": 2, "tags": ["x"]}]"#.to_vec();
+/// let batch = simd_json::to_record_batch_with_schema(&mut d, schema).unwrap();
+/// assert_eq!(batch.num_rows(), 2);
+/// assert_eq!(batch.column(1).null_count(), 1);
+/// ```
+///
+/// # Errors
+///
+/// Will return `Err` if `input` is invalid JSON, isn't an array of
+/// objects, a value doesn't fit the data type of its field, a non
+/// nullable field is null or the schema uses an unsupported data type.
+pub fn to_record_batch_with_schema(input: &mut [u8], schema: SchemaRef) -> Result<RecordBatch> {
+    let columns = Columns::read(input, Some(&schema))?;
+    columns.finish(schema)
+}
+
+/// The values of every column as tape nodes, `None` where a row doesn't
+/// have the key
+struct Columns<'de> {
+    index: HashMap<String, usize>,
+    names: Vec<String>,
+    values: Vec<Vec<Option<Node<'de>>>>,
+    rows: usize,
+}
+
+impl<'de> Columns<'de> {
+    /// Reads the rows of `input`, with a `schema` only the keys that are
+    /// fields of it become columns, without one every key does
+    fn read(input: &'de mut [u8], schema: Option<&Schema>) -> Result<Self> {
+        let mut de = Deserializer::from_slice(input)?;
+        let rows = match de.next_() {
+            Node::Array(len, _) => len,
+            _ => return Err(Error::generic(ErrorType::ExpectedArray)),
+        };
+        let mut columns = Self {
+            index: HashMap::new(),
+            names: Vec::new(),
+            values: Vec::new(),
+            rows,
+        };
+        if let Some(schema) = schema {
+            for field in schema.fields() {
+                columns.add(field.name());
+            }
+        }
+        for row in 0..rows {
+            let len = match de.next_() {
+                Node::Object(len, _) => len,
+                _ => return Err(Error::generic(ErrorType::ExpectedMap)),
+            };
+            for _ in 0..len {
+                let key = match de.next_() {
+                    Node::String(key) => key,
+                    _ => unreachable!(),
+                };
+                let value = de.next_();
+                let column = match columns.index.get(key) {
+                    Some(column) => *column,
+                    None if schema.is_none() => {
+                        let column = columns.add(key);
+                        columns.values[column].resize(row, None);
+                        column
+                    }
+                    None => {
+                        // skip past the nodes of the value
+                        if let Node::Array(_, end) | Node::Object(_, end) = value {
+                            de.idx = end - 1;
+                        }
+                        continue;
+                    }
+                };
+                if let Node::Array(..) | Node::Object(..) = value {
+                    return Err(Error::generic(ErrorType::Arrow(ArrowError::JsonError(
+                        format!("column `{}` holds a nested value", key),
+                    ))));
+                }
+                let values = &mut columns.values[column];
+                // the last value wins for keys repeated within an object
+                values.truncate(row);
+                values.push(Some(value));
+            }
+            for values in &mut columns.values {
+                values.resize(row + 1, None);
+            }
+        }
+        Ok(columns)
+    }
+
+    fn add(&mut self, name: &str) -> usize {
+        let column = self.names.len();
+        self.index.insert(name.into(), column);
+        self.names.push(name.into());
+        self.values.push(Vec::with_capacity(self.rows));
+        column
+    }
+
+    /// Builds the arrays for the fields of `schema`, in order
+    fn finish(self, schema: SchemaRef) -> Result<RecordBatch> {
+        let arrays = schema
+            .fields()
+            .iter()
+            .zip(&self.values)
+            .map(|(field, values)| array(field, values))
+            .collect::<Result<Vec<_>>>()?;
+        let options = RecordBatchOptions::new().with_row_count(Some(self.rows));
+        RecordBatch::try_new_with_options(schema, arrays, &options)
+            .map_err(|e| Error::generic(ErrorType::Arrow(e)))
+    }
+}
+
+/// Infers the data type of the column `name` from its values
+fn infer(name: &str, values: &[Option<Node>]) -> Result<DataType> {
+    let mut data_type = DataType::Null;
+    // positive integers that fit an `i64` also fit an `u64`
+    let mut negative = false;
+    for value in values.iter().flatten() {
+        let found = match value {
+            Node::Static(StaticNode::Null) => continue,
+            Node::Static(StaticNode::Bool(_)) => DataType::Boolean,
+            Node::Static(StaticNode::I64(i)) => {
+                negative |= *i < 0;
+                DataType::Int64
+            }
+            Node::Static(StaticNode::U64(u)) if i64::try_from(*u).is_ok() => DataType::Int64,
+            Node::Static(StaticNode::U64(_)) => DataType::UInt64,
+            Node::Static(StaticNode::F64(_)) => DataType::Float64,
+            Node::String(_) => DataType::Utf8,
+            _ => {
+                return Err(Error::generic(ErrorType::Arrow(ArrowError::JsonError(
+                    format!("column `{}` holds a value without an arrow type", name),
+                ))))
+            }
+        };
+        data_type = match (data_type, found) {
+            (DataType::Null, found) => found,
+            (known, found) if known == found => known,
+            (DataType::Int64 | DataType::UInt64, DataType::Int64 | DataType::UInt64)
+                if !negative =>
+            {
+                DataType::UInt64
+            }
+            (
+                DataType::Int64 | DataType::UInt64 | DataType::Float64,
+                DataType::Int64 | DataType::UInt64 | DataType::Float64,
+            ) => DataType::Float64,
+            (known, found) => {
+                return Err(Error::generic(ErrorType::Arrow(ArrowError::JsonError(
+                    format!("column `{}` mixes {} and {} values", name, known, found),
+                ))))
+            }
+        };
+    }
+    Ok(data_type)
+}
+
+/// Builds the array for `field` from its values
+#[allow(clippy::cast_precision_loss)]
+fn array(field: &Field, values: &[Option<Node>]) -> Result<ArrayRef> {
+    Ok(match field.data_type() {
+        DataType::Null => {
+            convert(values, ErrorType::ExpectedNull, |_| None::<()>)?;
+            Arc::new(NullArray::new(values.len()))
+        }
+        DataType::Boolean => Arc::new(BooleanArray::from(convert(
+            values,
+            ErrorType::ExpectedBoolean,
+            |v| match v {
+                Node::Static(StaticNode::Bool(b)) => Some(b),
+                _ => None,
+            },
+        )?)),
+        DataType::Int64 => Arc::new(Int64Array::from(convert(
+            values,
+            ErrorType::ExpectedSigned,
+            |v| match v {
+                Node::Static(StaticNode::I64(i)) => Some(i),
+                Node::Static(StaticNode::U64(u)) => i64::try_from(u).ok(),
+                _ => None,
+            },
+        )?)),
+        DataType::UInt64 => Arc::new(UInt64Array::from(convert(
+            values,
+            ErrorType::ExpectedUnsigned,
+            |v| match v {
+                Node::Static(StaticNode::I64(i)) => u64::try_from(i).ok(),
+                Node::Static(StaticNode::U64(u)) => Some(u),
+                _ => None,
+            },
+        )?)),
+        DataType::Float64 => Arc::new(Float64Array::from(convert(
+            values,
+            ErrorType::ExpectedFloat,
+            |v| match v {
+                Node::Static(StaticNode::F64(f)) => Some(f),
+                Node::Static(StaticNode::I64(i)) => Some(i as f64),
+                Node::Static(StaticNode::U64(u)) => Some(u as f64),
+                _ => None,
+            },
+        )?)),
+        DataType::Utf8 => Arc::new(StringArray::from(convert(
+            values,
+            ErrorType::ExpectedString,
+            |v| match v {
+                Node::String(s) => Some(s),
+                _ => None,
+            },
+        )?)),
+        other => {
+            return Err(Error::generic(ErrorType::Arrow(
+                ArrowError::NotYetImplemented(format!(
+                    "field `{}` has the unsupported data type {}",
+                    field.name(),
+                    other
+                )),
+            )))
+        }
+    })
+}
+
+/// Converts the non null `values` of a column, failing with `expected`
+/// for the first one `f` rejects
+fn convert<'de, T>(
+    values: &[Option<Node<'de>>],
+    expected: ErrorType,
+    f: impl Fn(Node<'de>) -> Option<T>,
+) -> Result<Vec<Option<T>>> {
+    let mut res = Vec::with_capacity(values.len());
+    for value in values {
+        res.push(match value {
+            None | Some(Node::Static(StaticNode::Null)) => None,
+            Some(value) => match f(*value) {
+                Some(v) => Some(v),
+                None => return Err(Error::generic(expected)),
+            },
+        });
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use arrow_array::Array;
+
+    fn batch(input: &str) -> Result<RecordBatch> {
+        to_record_batch(&mut input.as_bytes().to_vec())
+    }
+
+    fn batch_with_schema(input: &str, fields: Vec<Field>) -> Result<RecordBatch> {
+        to_record_batch_with_schema(
+            &mut input.as_bytes().to_vec(),
+            Arc::new(Schema::new(fields)),
+        )
+    }
+
+    #[test]
+    fn infer_schema() {
+        let b = batch(
+            r#"[
+                {"i": 1, "f": 1, "s": "a\n", "b": true, "n": null, "u": 1},
+                {"i": -2, "f": 2.5, "s": null, "b": false, "u": 18446744073709551615},
+                {"x": 3, "i": 3, "i": 4}
+            ]"#,
+        )
+        .unwrap();
+        let schema = b.schema();
+        let types: Vec<_> = schema
+            .fields()
+            .iter()
+            .map(|f| (f.name().as_str(), f.data_type().clone()))
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                ("i", DataType::Int64),
+                ("f", DataType::Float64),
+                ("s", DataType::Utf8),
+                ("b", DataType::Boolean),
+                ("n", DataType::Null),
+                ("u", DataType::UInt64),
+                ("x", DataType::Int64),
+            ]
+        );
+        assert_eq!(b.num_rows(), 3);
+        assert_eq!(
+            b.column(0).as_any().downcast_ref::<Int64Array>().unwrap(),
+            &Int64Array::from(vec![1, -2, 4])
+        );
+        assert_eq!(
+            b.column(1).as_any().downcast_ref::<Float64Array>().unwrap(),
+            &Float64Array::from(vec![Some(1.0), Some(2.5), None])
+        );
+        assert_eq!(
+            b.column(2).as_any().downcast_ref::<StringArray>().unwrap(),
+            &StringArray::from(vec![Some("a\n"), None, None])
+        );
+        assert_eq!(
+            b.column(6).as_any().downcast_ref::<Int64Array>().unwrap(),
+            &Int64Array::from(vec![None, None, Some(3)])
+        );
+        assert_eq!(b.column(4).len(), 3);
+    }
+
+    #[test]
+    fn infer_mixed_integers() {
+        let b = batch(r#"[{"a": -1}, {"a": 18446744073709551615}]"#).unwrap();
+        assert_eq!(b.schema().field(0).data_type(), &DataType::Float64);
+    }
+
+    #[test]
+    fn empty() {
+        let b = batch("[]").unwrap();
+        assert_eq!((b.num_rows(), b.num_columns()), (0, 0));
+        let b = batch("[{}, {}]").unwrap();
+        assert_eq!((b.num_rows(), b.num_columns()), (2, 0));
+    }
+
+    #[test]
+    fn infer_errors() {
+        assert_eq!(batch("{}").unwrap_err().error(), &ErrorType::ExpectedArray);
+        assert_eq!(batch("[1]").unwrap_err().error(), &ErrorType::ExpectedMap);
+        assert!(batch(r#"[{"a": [1]}]"#).is_err());
+        assert!(batch(r#"[{"a": 1}, {"a": "b"}]"#).is_err());
+        assert!(batch(r#"[{"a": 1"#).is_err());
+    }
+
+    #[test]
+    fn with_schema() {
+        let b = batch_with_schema(
+            r#"[{"id": 1, "v": 2, "skip": {"a": [1, {}]}}, {"v": null, "id": 2}, {"id": 3, "v": 1.5}]"#,
+            vec![
+                Field::new("id", DataType::UInt64, false),
+                Field::new("v", DataType::Float64, true),
+                Field::new("missing", DataType::Utf8, true),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            b.column(0).as_any().downcast_ref::<UInt64Array>().unwrap(),
+            &UInt64Array::from(vec![1, 2, 3])
+        );
+        assert_eq!(
+            b.column(1).as_any().downcast_ref::<Float64Array>().unwrap(),
+            &Float64Array::from(vec![Some(2.0), None, Some(1.5)])
+        );
+        assert_eq!(b.column(2).null_count(), 3);
+    }
+
+    #[test]
+    fn with_schema_errors() {
+        let field = |t| vec![Field::new("a", t, true)];
+        assert_eq!(
+            batch_with_schema(r#"[{"a": -1}]"#, field(DataType::UInt64))
+                .unwrap_err()
+                .error(),
+            &ErrorType::ExpectedUnsigned
+        );
+        assert_eq!(
+            batch_with_schema(r#"[{"a": 1.5}]"#, field(DataType::Int64))
+                .unwrap_err()
+                .error(),
+            &ErrorType::ExpectedSigned
+        );
+        assert_eq!(
+            batch_with_schema(r#"[{"a": 1}]"#, field(DataType::Utf8))
+                .unwrap_err()
+                .error(),
+            &ErrorType::ExpectedString
+        );
+        assert!(batch_with_schema(r#"[{"a": 1}]"#, field(DataType::Int32)).is_err());
+        assert!(batch_with_schema(r#"[{"a": [1]}]"#, field(DataType::Utf8)).is_err());
+        assert!(batch_with_schema(
+            r#"[{"b": 1}]"#,
+            vec![Field::new("a", DataType::Int64, false)]
+        )
+        .is_err());
+    }
+}
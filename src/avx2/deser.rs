@@ -1,15 +1,15 @@
 #[cfg(target_arch = "x86")]
-use std::arch::x86::{
+use core::arch::x86::{
     __m256i, _mm256_cmpeq_epi8, _mm256_loadu_si256, _mm256_movemask_epi8, _mm256_set1_epi8,
     _mm256_storeu_si256,
 };
 #[cfg(target_arch = "x86_64")]
-use std::arch::x86_64::{
+use core::arch::x86_64::{
     __m256i, _mm256_cmpeq_epi8, _mm256_loadu_si256, _mm256_movemask_epi8, _mm256_set1_epi8,
     _mm256_storeu_si256,
 };
 
-use std::mem;
+use core::mem;
 
 pub use crate::error::{Error, ErrorType};
 use crate::stringparse::{handle_unicode_codepoint, ESCAPE_MAP};
@@ -35,7 +35,7 @@ impl<'de> Deserializer<'de> {
         mut idx: usize,
     ) -> Result<&'de str> {
         use ErrorType::{InvalidEscape, InvlaidUnicodeCodepoint};
-        let input: &mut [u8] = unsafe { std::mem::transmute(input) };
+        let input: &mut [u8] = unsafe { core::mem::transmute(input) };
         // Add 1 to skip the initial "
         idx += 1;
         //let mut read: usize = 0;
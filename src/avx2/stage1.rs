@@ -4,8 +4,9 @@ use crate::{
     static_cast_i32, static_cast_i64, static_cast_i8, static_cast_u32, ProcessedUtfBytes,
     Stage1Parse, Utf8CheckingState,
 };
+use alloc::vec::Vec;
 #[cfg(target_arch = "x86")]
-use std::arch::x86::{
+use core::arch::x86::{
     __m256i, _mm256_add_epi32, _mm256_and_si256, _mm256_cmpeq_epi8, _mm256_cmpgt_epi8,
     _mm256_loadu_si256, _mm256_max_epu8, _mm256_movemask_epi8, _mm256_or_si256, _mm256_set1_epi8,
     _mm256_set_epi32, _mm256_setr_epi8, _mm256_setzero_si256, _mm256_shuffle_epi8,
@@ -13,7 +14,7 @@ use std::arch::x86::{
     _mm_cvtsi128_si64, _mm_set1_epi8, _mm_set_epi64x,
 };
 #[cfg(target_arch = "x86_64")]
-use std::arch::x86_64::{
+use core::arch::x86_64::{
     __m256i, _mm256_add_epi32, _mm256_and_si256, _mm256_cmpeq_epi8, _mm256_cmpgt_epi8,
     _mm256_loadu_si256, _mm256_max_epu8, _mm256_movemask_epi8, _mm256_or_si256, _mm256_set1_epi8,
     _mm256_set_epi32, _mm256_setr_epi8, _mm256_setzero_si256, _mm256_shuffle_epi8,
@@ -21,7 +22,7 @@ use std::arch::x86_64::{
     _mm_cvtsi128_si64, _mm_set1_epi8, _mm_set_epi64x,
 };
 
-use std::mem;
+use core::mem;
 
 macro_rules! low_nibble_mask {
     () => {
@@ -50,10 +51,10 @@ pub(crate) struct SimdInput {
     v1: __m256i,
 }
 
-impl SimdInput {
+impl Stage1Parse<__m256i> for SimdInput {
     #[cfg_attr(not(feature = "no-inline"), inline)]
     #[allow(clippy::cast_ptr_alignment)]
-    pub(crate) fn new(ptr: &[u8]) -> Self {
+    fn new(ptr: &[u8]) -> Self {
         unsafe {
             Self {
                 v0: _mm256_loadu_si256(ptr.as_ptr() as *const __m256i),
@@ -61,9 +62,7 @@ impl SimdInput {
             }
         }
     }
-}
 
-impl Stage1Parse<__m256i> for SimdInput {
     #[cfg_attr(not(feature = "no-inline"), inline(always))]
     fn new_utf8_checking_state() -> Utf8CheckingState<__m256i> {
         Utf8CheckingState {
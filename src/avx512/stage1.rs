@@ -0,0 +1,329 @@
+#![allow(dead_code)]
+use crate::utf8check::Utf8Check;
+use crate::{Deserializer, ErrorType, ProcessedUtfBytes, Stage1Parse, Utf8CheckingState};
+#[cfg(target_arch = "x86")]
+use core::arch::x86::{
+    __m256i, __m512i, _mm256_cmpgt_epi8, _mm256_or_si256, _mm256_set1_epi8, _mm256_setr_epi8,
+    _mm256_setzero_si256, _mm256_testz_si256, _mm512_add_epi32, _mm512_and_si512,
+    _mm512_broadcast_i32x4, _mm512_broadcast_i64x4, _mm512_castsi512_si128, _mm512_castsi512_si256,
+    _mm512_cmpeq_epi8_mask, _mm512_cmple_epu8_mask, _mm512_cvtepu8_epi32,
+    _mm512_extracti32x4_epi32, _mm512_extracti64x4_epi64, _mm512_loadu_si512,
+    _mm512_maskz_compress_epi8, _mm512_movepi8_mask, _mm512_set1_epi32, _mm512_set1_epi8,
+    _mm512_set_epi32, _mm512_shuffle_epi8, _mm512_srli_epi32, _mm512_storeu_si512,
+    _mm512_test_epi8_mask, _mm_clmulepi64_si128, _mm_cvtsi128_si64, _mm_set1_epi8, _mm_set_epi64x,
+    _mm_setr_epi8,
+};
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::{
+    __m256i, __m512i, _mm256_cmpgt_epi8, _mm256_or_si256, _mm256_set1_epi8, _mm256_setr_epi8,
+    _mm256_setzero_si256, _mm256_testz_si256, _mm512_add_epi32, _mm512_and_si512,
+    _mm512_broadcast_i32x4, _mm512_broadcast_i64x4, _mm512_castsi512_si128, _mm512_castsi512_si256,
+    _mm512_cmpeq_epi8_mask, _mm512_cmple_epu8_mask, _mm512_cvtepu8_epi32,
+    _mm512_extracti32x4_epi32, _mm512_extracti64x4_epi64, _mm512_loadu_si512,
+    _mm512_maskz_compress_epi8, _mm512_movepi8_mask, _mm512_set1_epi32, _mm512_set1_epi8,
+    _mm512_set_epi32, _mm512_shuffle_epi8, _mm512_srli_epi32, _mm512_storeu_si512,
+    _mm512_test_epi8_mask, _mm_clmulepi64_si128, _mm_cvtsi128_si64, _mm_set1_epi8, _mm_set_epi64x,
+    _mm_setr_epi8,
+};
+
+use core::mem;
+
+/// A stage 1 implementation picked at runtime
+pub(crate) type FindStructuralBits = unsafe fn(&[u8], &mut Vec<u32>) -> Result<(), ErrorType>;
+
+/// Returns the best AVX-512 stage 1 the CPU we're running on supports, if
+/// it supports AVX-512BW at all.
+pub(crate) fn detect() -> Option<FindStructuralBits> {
+    if !is_x86_feature_detected!("avx512f") || !is_x86_feature_detected!("avx512bw") {
+        None
+    } else if is_x86_feature_detected!("avx512vbmi2") {
+        Some(Deserializer::find_structural_bits_avx512_vbmi2)
+    } else {
+        Some(Deserializer::find_structural_bits_avx512)
+    }
+}
+
+impl Deserializer<'_> {
+    #[target_feature(enable = "avx512f,avx512bw")]
+    unsafe fn find_structural_bits_avx512(
+        input: &[u8],
+        structural_indexes: &mut Vec<u32>,
+    ) -> Result<(), ErrorType> {
+        Self::find_structural_bits_simd::<SimdInput, _>(input, structural_indexes)
+    }
+
+    #[target_feature(enable = "avx512f,avx512bw,avx512vbmi2")]
+    unsafe fn find_structural_bits_avx512_vbmi2(
+        input: &[u8],
+        structural_indexes: &mut Vec<u32>,
+    ) -> Result<(), ErrorType> {
+        Self::find_structural_bits_simd::<SimdInputVbmi2, _>(input, structural_indexes)
+    }
+}
+
+// Without VBMI2 we flatten the bits the same way the AVX2 backend does
+#[cfg_attr(not(feature = "no-inline"), inline(always))]
+fn flatten_bits_tzcnt(base: &mut Vec<u32>, idx: u32, bits: u64) {
+    crate::avx2::stage1::SimdInput::flatten_bits(base, idx, bits);
+}
+
+// With VBMI2 we compress the positions of all set bits to the front of a
+// vector and widen them to 32 bit, 16 at a time
+#[cfg_attr(not(feature = "no-inline"), inline(always))]
+#[allow(clippy::cast_ptr_alignment)]
+fn flatten_bits_compress(base: &mut Vec<u32>, idx: u32, bits: u64) {
+    if bits == 0 {
+        return;
+    }
+    let cnt: usize = bits.count_ones() as usize;
+    let l = base.len();
+
+    // We reserve 64 extra entries, because we've at most 64 bit to set.
+    // We write 16 entries at a time, possibly past `cnt`, but that's OK
+    // since we ensure we reserve the needed space
+    base.reserve(64);
+    unsafe {
+        base.set_len(l + cnt);
+
+        // byte i of this vector is i
+        let positions: __m512i = _mm512_set_epi32(
+            0x3f3e_3d3c,
+            0x3b3a_3938,
+            0x3736_3534,
+            0x3332_3130,
+            0x2f2e_2d2c,
+            0x2b2a_2928,
+            0x2726_2524,
+            0x2322_2120,
+            0x1f1e_1d1c,
+            0x1b1a_1918,
+            0x1716_1514,
+            0x1312_1110,
+            0x0f0e_0d0c,
+            0x0b0a_0908,
+            0x0706_0504,
+            0x0302_0100,
+        );
+        let positions: __m512i = _mm512_maskz_compress_epi8(bits, positions);
+        let idx_64_v: __m512i = _mm512_set1_epi32(static_cast_i32!(idx.wrapping_sub(64)));
+        let out = base.as_mut_ptr().add(l) as *mut __m512i;
+
+        let v = _mm512_cvtepu8_epi32(_mm512_castsi512_si128(positions));
+        _mm512_storeu_si512(out, _mm512_add_epi32(idx_64_v, v));
+        if cnt > 16 {
+            let v = _mm512_cvtepu8_epi32(_mm512_extracti32x4_epi32(positions, 1));
+            _mm512_storeu_si512(out.add(1), _mm512_add_epi32(idx_64_v, v));
+        }
+        if cnt > 32 {
+            let v = _mm512_cvtepu8_epi32(_mm512_extracti32x4_epi32(positions, 2));
+            _mm512_storeu_si512(out.add(2), _mm512_add_epi32(idx_64_v, v));
+        }
+        if cnt > 48 {
+            let v = _mm512_cvtepu8_epi32(_mm512_extracti32x4_epi32(positions, 3));
+            _mm512_storeu_si512(out.add(3), _mm512_add_epi32(idx_64_v, v));
+        }
+    }
+}
+
+// The two inputs only differ in how they flatten the structural bits, the
+// UTF-8 check is the AVX2 one run over both halves of the input.
+macro_rules! simd_input {
+    ($name:ident, $flatten_bits:ident) => {
+        #[derive(Debug)]
+        pub(crate) struct $name {
+            v: __m512i,
+        }
+
+        impl Stage1Parse<__m256i> for $name {
+            #[cfg_attr(not(feature = "no-inline"), inline)]
+            #[allow(clippy::cast_ptr_alignment)]
+            fn new(ptr: &[u8]) -> Self {
+                unsafe {
+                    Self {
+                        v: _mm512_loadu_si512(ptr.as_ptr() as *const __m512i),
+                    }
+                }
+            }
+
+            #[cfg_attr(not(feature = "no-inline"), inline(always))]
+            fn new_utf8_checking_state() -> Utf8CheckingState<__m256i> {
+                Utf8CheckingState {
+                    has_error: Self::zero(),
+                    previous: ProcessedUtfBytes::default(),
+                }
+            }
+
+            #[cfg_attr(not(feature = "no-inline"), inline(always))]
+            #[allow(clippy::cast_sign_loss)]
+            fn compute_quote_mask(quote_bits: u64) -> u64 {
+                unsafe {
+                    _mm_cvtsi128_si64(_mm_clmulepi64_si128(
+                        _mm_set_epi64x(0, static_cast_i64!(quote_bits)),
+                        _mm_set1_epi8(-1_i8 /* 0xFF */),
+                        0,
+                    )) as u64
+                }
+            }
+
+            #[cfg_attr(not(feature = "no-inline"), inline(always))]
+            fn check_utf8(&self, state: &mut Utf8CheckingState<__m256i>) {
+                unsafe {
+                    if _mm512_movepi8_mask(self.v) == 0 {
+                        // it is ascii, we just check continuation
+                        state.has_error = _mm256_or_si256(
+                            _mm256_cmpgt_epi8(
+                                state.previous.carried_continuations,
+                                _mm256_setr_epi8(
+                                    9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9,
+                                    9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 1,
+                                ),
+                            ),
+                            state.has_error,
+                        );
+                    } else {
+                        // it is not ascii so we have to do heavy work
+                        state.previous = ProcessedUtfBytes::<__m256i>::check_utf8_bytes(
+                            _mm512_castsi512_si256(self.v),
+                            &state.previous,
+                            &mut state.has_error,
+                        );
+                        state.previous = ProcessedUtfBytes::<__m256i>::check_utf8_bytes(
+                            _mm512_extracti64x4_epi64(self.v, 1),
+                            &state.previous,
+                            &mut state.has_error,
+                        );
+                    }
+                }
+            }
+
+            /// a straightforward comparison of a mask against input
+            #[cfg_attr(not(feature = "no-inline"), inline(always))]
+            #[allow(clippy::cast_possible_wrap)]
+            fn cmp_mask_against_input(&self, m: u8) -> u64 {
+                unsafe { _mm512_cmpeq_epi8_mask(self.v, _mm512_set1_epi8(m as i8)) }
+            }
+
+            // find all values less than or equal than the content of maxval (using unsigned arithmetic)
+            #[cfg_attr(not(feature = "no-inline"), inline(always))]
+            fn unsigned_lteq_against_input(&self, maxval: __m256i) -> u64 {
+                unsafe { _mm512_cmple_epu8_mask(self.v, _mm512_broadcast_i64x4(maxval)) }
+            }
+
+            #[cfg_attr(not(feature = "no-inline"), inline(always))]
+            fn find_whitespace_and_structurals(&self, whitespace: &mut u64, structurals: &mut u64) {
+                unsafe {
+                    // do a 'shufti' to detect structural JSON characters
+                    // they are
+                    // * `{` 0x7b
+                    // * `}` 0x7d
+                    // * `:` 0x3a
+                    // * `[` 0x5b
+                    // * `]` 0x5d
+                    // * `,` 0x2c
+                    // these go into the first 3 buckets of the comparison (1/2/4)
+
+                    // we are also interested in the four whitespace characters:
+                    // * space 0x20
+                    // * linefeed 0x0a
+                    // * horizontal tab 0x09
+                    // * carriage return 0x0d
+                    // these go into the next 2 buckets of the comparison (8/16)
+
+                    // the shuffle works on 128 bit lanes so every lane gets the table
+                    let low_nibble_mask: __m512i = _mm512_broadcast_i32x4(_mm_setr_epi8(
+                        16, 0, 0, 0, 0, 0, 0, 0, 0, 8, 12, 1, 2, 9, 0, 0,
+                    ));
+                    let high_nibble_mask: __m512i = _mm512_broadcast_i32x4(_mm_setr_epi8(
+                        8, 0, 18, 4, 0, 1, 0, 1, 0, 0, 0, 3, 2, 1, 0, 0,
+                    ));
+
+                    let v: __m512i = _mm512_and_si512(
+                        _mm512_shuffle_epi8(low_nibble_mask, self.v),
+                        _mm512_shuffle_epi8(
+                            high_nibble_mask,
+                            _mm512_and_si512(_mm512_srli_epi32(self.v, 4), _mm512_set1_epi8(0x7f)),
+                        ),
+                    );
+
+                    *structurals = _mm512_test_epi8_mask(v, _mm512_set1_epi8(0x7));
+                    *whitespace = _mm512_test_epi8_mask(v, _mm512_set1_epi8(0x18));
+                }
+            }
+
+            #[cfg_attr(not(feature = "no-inline"), inline(always))]
+            fn flatten_bits(base: &mut Vec<u32>, idx: u32, bits: u64) {
+                $flatten_bits(base, idx, bits);
+            }
+
+            #[cfg_attr(not(feature = "no-inline"), inline(always))]
+            fn check_utf8_errors(state: &Utf8CheckingState<__m256i>) -> bool {
+                unsafe { _mm256_testz_si256(state.has_error, state.has_error) == 0 }
+            }
+
+            #[cfg_attr(not(feature = "no-inline"), inline(always))]
+            fn fill_s8(n: i8) -> __m256i {
+                unsafe { _mm256_set1_epi8(n) }
+            }
+
+            #[cfg_attr(not(feature = "no-inline"), inline(always))]
+            fn zero() -> __m256i {
+                unsafe { _mm256_setzero_si256() }
+            }
+        }
+    };
+}
+
+simd_input!(SimdInput, flatten_bits_tzcnt);
+simd_input!(SimdInputVbmi2, flatten_bits_compress);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    // every kernel the CPU supports has to find the same structurals as AVX2
+    fn assert_kernels_agree(input: &[u8]) {
+        let run = |find_structural_bits: FindStructuralBits| {
+            let mut structural_indexes = Vec::new();
+            unsafe { find_structural_bits(input, &mut structural_indexes) }
+                .map(|()| structural_indexes)
+        };
+        let expected =
+            run(Deserializer::find_structural_bits_simd::<crate::avx2::stage1::SimdInput, _>);
+        if is_x86_feature_detected!("avx512f") && is_x86_feature_detected!("avx512bw") {
+            assert_eq!(run(Deserializer::find_structural_bits_avx512), expected);
+            if is_x86_feature_detected!("avx512vbmi2") {
+                assert_eq!(
+                    run(Deserializer::find_structural_bits_avx512_vbmi2),
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn kernels_agree() {
+        assert_kernels_agree(br#"{"a": [1, true, null, "b\"c"], "d": {"e": -1.5e3}}"#);
+        assert_kernels_agree("[\"ü\", \"€\", \"😀\"]".repeat(20).as_bytes());
+        assert_kernels_agree(
+            b"[1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23,24,25]",
+        );
+        assert_kernels_agree(&[b','; 200]);
+        assert_kernels_agree(b"   ");
+        assert_kernels_agree(b"[\"unterminated");
+        assert_kernels_agree(b"[\"a\nb\"]");
+        assert_kernels_agree(b"[\"\xff\xfe\"]");
+    }
+
+    proptest! {
+        #[test]
+        fn prop_kernels_agree(d in "[\\[\\]{},: 1tn\\\\\"\t\nü😀]{0,300}") {
+            assert_kernels_agree(d.as_bytes());
+        }
+
+        #[test]
+        fn prop_kernels_agree_junk(d in prop::collection::vec(any::<u8>(), 0..300)) {
+            assert_kernels_agree(&d);
+        }
+    }
+}
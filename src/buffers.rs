@@ -0,0 +1,81 @@
+use alloc::vec::Vec;
+
+/// Scratch space the parser needs while building the tape, none of it is
+/// referenced by the result so it can be reused for the next document.
+#[derive(Debug, Default)]
+pub(crate) struct Buffers {
+    /// Padded and aligned copy of the input stage 1 runs over
+    pub(crate) input: Vec<u8>,
+    /// Space strings are de-escaped into before being copied back
+    pub(crate) string: Vec<u8>,
+    /// The structural indexes found by stage 1
+    pub(crate) structural_indexes: Vec<u32>,
+}
+
+#[cfg(feature = "buffer-pool")]
+std::thread_local! {
+    static BUFFERS: core::cell::Cell<Buffers> = core::cell::Cell::default();
+}
+
+/// Runs `f` with this threads pooled buffers, the buffers are taken out of
+/// the pool for the duration so a parse started from within `f` gets
+/// fresh ones instead of aliasing them.
+#[cfg(feature = "buffer-pool")]
+#[cfg_attr(not(feature = "no-inline"), inline(always))]
+pub(crate) fn with_buffers<R>(f: impl FnOnce(&mut Buffers) -> R) -> R {
+    let mut buffers = BUFFERS.with(core::cell::Cell::take);
+    let res = f(&mut buffers);
+    BUFFERS.with(|b| b.set(buffers));
+    res
+}
+
+/// Runs `f` with fresh buffers, enable the `buffer-pool` feature to reuse
+/// them between calls
+#[cfg(not(feature = "buffer-pool"))]
+#[cfg_attr(not(feature = "no-inline"), inline(always))]
+pub(crate) fn with_buffers<R>(f: impl FnOnce(&mut Buffers) -> R) -> R {
+    f(&mut Buffers::default())
+}
+
+/// Frees the parse buffers pooled for the current thread. The pool keeps
+/// buffers large enough for the biggest document parsed on a thread so
+/// far, this gives that memory back after parsing an unusually large one.
+#[cfg(feature = "buffer-pool")]
+pub fn release_thread_buffers() {
+    BUFFERS.with(|b| drop(b.take()));
+}
+
+#[cfg(all(test, feature = "buffer-pool"))]
+mod test {
+    use super::*;
+
+    fn capacity() -> usize {
+        with_buffers(|b| b.input.capacity())
+    }
+
+    #[test]
+    fn reuse() {
+        release_thread_buffers();
+        assert_eq!(capacity(), 0);
+        let mut d = br#"{"a": ["b", 1, 2.5, null]}"#.to_vec();
+        crate::to_owned_value(&mut d).expect("failed to parse");
+        let reused = capacity();
+        assert!(reused > 0);
+        let mut d = b"[1]".to_vec();
+        crate::to_borrowed_value(&mut d).expect("failed to parse");
+        assert_eq!(capacity(), reused);
+        release_thread_buffers();
+        assert_eq!(capacity(), 0);
+    }
+
+    #[test]
+    fn nested() {
+        let outer = with_buffers(|b| {
+            b.input.reserve(1024);
+            // a parse started while the buffers are in use can't see them
+            assert_eq!(capacity(), 0);
+            b.input.capacity()
+        });
+        assert!(capacity() >= outer);
+    }
+}
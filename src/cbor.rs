@@ -0,0 +1,641 @@
+use crate::cow::Cow;
+use crate::value::emit::{emit, Emit, Emitter};
+use crate::{BorrowedValue, Error, ErrorType, Result, StaticNode};
+use alloc::borrow::Cow as BytesCow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::{Infallible, TryFrom};
+
+// Major types, in the top three bits of the initial byte
+const UINT: u8 = 0;
+const NINT: u8 = 1;
+const BYTES: u8 = 2;
+const TEXT: u8 = 3;
+const ARRAY: u8 = 4;
+const MAP: u8 = 5;
+const TAG: u8 = 6;
+
+const FALSE: u8 = 0xf4;
+const TRUE: u8 = 0xf5;
+const NULL: u8 = 0xf6;
+const F32: u8 = 0xfa;
+const F64: u8 = 0xfb;
+const BREAK: u8 = 0xff;
+
+/// Additional information of items with an indefinite length
+const INDEFINITE: u8 = 31;
+
+// Tags for integers that don't fit into 64 bits
+const POSITIVE_BIGNUM: u64 = 2;
+const NEGATIVE_BIGNUM: u64 = 3;
+
+/// Encodes an `OwnedValue` or `BorrowedValue` as CBOR (RFC 8949).
+///
+/// Every item has a definite length and integers take the fewest bytes
+/// they fit in. Floats are written as single precision when that doesn't
+/// lose anything and as double precision otherwise, `Bytes` values as
+/// byte strings. With the `128bit` feature integers beyond 64 bits are
+/// written as bignums. Object entries are in the order of the object.
+///
+/// ```rust
+/// let v = simd_json::json!({"a": [1, -2.5, null]});
+/// let cbor = simd_json::to_cbor_vec(&v);
+/// assert_eq!(cbor, [0xa1, 0x61, b'a', 0x83, 0x01, 0xfa, 0xc0, 0x20, 0x00, 0x00, 0xf6]);
+/// assert_eq!(simd_json::from_cbor_slice(&cbor).unwrap(), v);
+/// ```
+#[must_use]
+pub fn to_cbor_vec<V: Emit>(value: &V) -> Vec<u8> {
+    let mut out = Vec::new();
+    let _ = emit(&mut Encoder(&mut out), value);
+    out
+}
+
+/// Writes the CBOR items of the values `emit` walks
+struct Encoder<'o>(&'o mut Vec<u8>);
+
+impl Emitter for Encoder<'_> {
+    type Error = Infallible;
+
+    fn static_node(&mut self, s: StaticNode) -> core::result::Result<(), Infallible> {
+        let out = &mut *self.0;
+        match s {
+            StaticNode::Null => out.push(NULL),
+            StaticNode::Bool(true) => out.push(TRUE),
+            StaticNode::Bool(false) => out.push(FALSE),
+            StaticNode::I64(i) => write_i128(out, i128::from(i)),
+            StaticNode::U64(u) => write_header(out, UINT, u),
+            #[cfg(feature = "128bit")]
+            StaticNode::I128(i) => write_i128(out, i),
+            #[cfg(feature = "128bit")]
+            StaticNode::U128(u) => write_u128(out, u),
+            StaticNode::F64(f) => write_f64(out, f),
+        }
+        Ok(())
+    }
+
+    fn string(&mut self, s: &str) -> core::result::Result<(), Infallible> {
+        write_header(self.0, TEXT, s.len() as u64);
+        self.0.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+
+    fn bytes(&mut self, b: &[u8]) -> core::result::Result<(), Infallible> {
+        write_header(self.0, BYTES, b.len() as u64);
+        self.0.extend_from_slice(b);
+        Ok(())
+    }
+
+    fn start_array(&mut self, len: usize) -> core::result::Result<usize, Infallible> {
+        write_header(self.0, ARRAY, len as u64);
+        Ok(len)
+    }
+
+    fn start_object(&mut self, len: usize) -> core::result::Result<usize, Infallible> {
+        write_header(self.0, MAP, len as u64);
+        Ok(len)
+    }
+
+    fn key(&mut self, key: &str, _first: bool) -> core::result::Result<(), Infallible> {
+        self.string(key)
+    }
+}
+
+/// Writes the initial byte of an item along with its argument in as few
+/// bytes as it fits in
+fn write_header(out: &mut Vec<u8>, major: u8, arg: u64) {
+    let major = major << 5;
+    match u8::try_from(arg) {
+        Ok(arg) if arg < 24 => out.push(major | arg),
+        Ok(arg) => out.extend_from_slice(&[major | 0x18, arg]),
+        Err(_) => {
+            if let Ok(arg) = u16::try_from(arg) {
+                out.push(major | 0x19);
+                out.extend_from_slice(&arg.to_be_bytes());
+            } else if let Ok(arg) = u32::try_from(arg) {
+                out.push(major | 0x1a);
+                out.extend_from_slice(&arg.to_be_bytes());
+            } else {
+                out.push(major | 0x1b);
+                out.extend_from_slice(&arg.to_be_bytes());
+            }
+        }
+    }
+}
+
+#[allow(clippy::cast_sign_loss)]
+fn write_i128(out: &mut Vec<u8>, i: i128) {
+    if i >= 0 {
+        write_u128(out, i as u128);
+    } else {
+        // negative integers are stored as `-1 - n`
+        let n = !i as u128;
+        if let Ok(n) = u64::try_from(n) {
+            write_header(out, NINT, n);
+        } else {
+            write_bignum(out, true, n);
+        }
+    }
+}
+
+fn write_u128(out: &mut Vec<u8>, u: u128) {
+    if let Ok(u) = u64::try_from(u) {
+        write_header(out, UINT, u);
+    } else {
+        write_bignum(out, false, u);
+    }
+}
+
+/// Integers beyond 64 bits are tagged byte strings of their magnitude
+fn write_bignum(out: &mut Vec<u8>, negative: bool, n: u128) {
+    let tag = if negative {
+        NEGATIVE_BIGNUM
+    } else {
+        POSITIVE_BIGNUM
+    };
+    write_header(out, TAG, tag);
+    let bytes = n.to_be_bytes();
+    let start = bytes.iter().take_while(|b| **b == 0).count();
+    write_header(out, BYTES, (bytes.len() - start) as u64);
+    out.extend_from_slice(&bytes[start..]);
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::float_cmp)]
+fn write_f64(out: &mut Vec<u8>, f: f64) {
+    let single = f as f32;
+    if f64::from(single) == f || f.is_nan() {
+        out.push(F32);
+        out.extend_from_slice(&single.to_bits().to_be_bytes());
+    } else {
+        out.push(F64);
+        out.extend_from_slice(&f.to_bits().to_be_bytes());
+    }
+}
+
+/// Decodes a single CBOR (RFC 8949) item into a `BorrowedValue` that
+/// borrows its strings and byte strings from `data` where it can.
+///
+/// Integers become `I64` when they fit and `U64` otherwise, negative
+/// integers below `i64::MIN` and bignums need the `128bit` feature, without
+/// it the former are an error and the latter stay byte strings. Floats of
+/// all three widths become `F64`, `undefined` becomes `null`. Items of
+/// indefinite length are accepted, other tags are skipped. Map keys have
+/// to be text strings.
+///
+/// ```rust
+/// use simd_json::prelude::*;
+/// let cbor = [0xa2, 0x61, b'a', 0x19, 0x01, 0x00, 0x61, b'b', 0x42, 0x01, 0x02];
+/// let v = simd_json::from_cbor_slice(&cbor).unwrap();
+/// assert_eq!(v["a"], 256);
+/// assert_eq!(v["b"].as_bytes(), Some(&[1, 2][..]));
+/// ```
+///
+/// # Errors
+///
+/// if `data` isn't a single well-formed CBOR item, or holds items that
+/// have no counterpart in a value like simple values, non-text map keys
+/// or integers that are out of range
+pub fn from_cbor_slice(data: &[u8]) -> Result<BorrowedValue<'_>> {
+    let mut decoder = Decoder { data, idx: 0 };
+    let value = decoder.parse()?;
+    if decoder.idx < data.len() {
+        return Err(decoder.error(decoder.idx, ErrorType::TrailingCharacters));
+    }
+    Ok(value)
+}
+
+/// A container that is being decoded, `remaining` is `None` for
+/// indefinite lengths
+enum Frame<'v> {
+    Array {
+        values: Vec<BorrowedValue<'v>>,
+        remaining: Option<u64>,
+    },
+    Object {
+        object: crate::borrowed::Object<'v>,
+        key: Option<Cow<'v, str>>,
+        remaining: Option<u64>,
+    },
+}
+
+impl<'v> Frame<'v> {
+    fn is_done(&self) -> bool {
+        match self {
+            Self::Array { remaining, .. } => *remaining == Some(0),
+            Self::Object { remaining, key, .. } => *remaining == Some(0) && key.is_none(),
+        }
+    }
+
+    fn into_value(self) -> BorrowedValue<'v> {
+        match self {
+            Self::Array { values, .. } => BorrowedValue::Array(values),
+            Self::Object { object, .. } => BorrowedValue::from(object),
+        }
+    }
+}
+
+struct Decoder<'v> {
+    data: &'v [u8],
+    idx: usize,
+}
+
+impl<'v> Decoder<'v> {
+    fn error(&self, idx: usize, error: ErrorType) -> Error {
+        let character = self.data.get(idx).map_or('\0', |b| char::from(*b));
+        Error::new(idx, character, error)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'v [u8]> {
+        let end = self
+            .idx
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| self.error(self.data.len(), ErrorType::EOF))?;
+        let bytes = &self.data[self.idx..end];
+        self.idx = end;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Reads the argument that follows the initial byte, `None` for
+    /// indefinite lengths
+    fn argument(&mut self, info: u8) -> Result<Option<u64>> {
+        Ok(Some(match info {
+            0..=23 => u64::from(info),
+            24 => u64::from(self.byte()?),
+            25 => u64::from(u16::from_be_bytes(self.array()?)),
+            26 => u64::from(u32::from_be_bytes(self.array()?)),
+            27 => u64::from_be_bytes(self.array()?),
+            INDEFINITE => return Ok(None),
+            _ => return Err(self.error(self.idx - 1, ErrorType::UnexpectedCharacter)),
+        }))
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut bytes = [0; N];
+        bytes.copy_from_slice(self.take(N)?);
+        Ok(bytes)
+    }
+
+    /// Capacity for `len` items, bounded by what is left of the input so
+    /// a bogus length can't allocate much
+    fn capacity(&self, len: u64) -> usize {
+        usize::try_from(len).map_or(usize::MAX, |len| len.min(self.data.len() - self.idx))
+    }
+
+    /// Reads the contents of a byte or text string, joining the chunks of
+    /// indefinite length strings
+    fn string(&mut self, major: u8, len: Option<u64>) -> Result<BytesCow<'v, [u8]>> {
+        if let Some(len) = len {
+            let len = usize::try_from(len).map_err(|_| self.error(self.idx, ErrorType::EOF))?;
+            return self.take(len).map(BytesCow::Borrowed);
+        }
+        let mut joined = Vec::new();
+        loop {
+            let start = self.idx;
+            let initial = self.byte()?;
+            if initial == BREAK {
+                return Ok(BytesCow::Owned(joined));
+            }
+            match self.argument(initial & 0x1f)? {
+                Some(len) if initial >> 5 == major => {
+                    joined.extend_from_slice(self.string(major, Some(len))?.as_ref());
+                }
+                _ => return Err(self.error(start, ErrorType::UnexpectedCharacter)),
+            }
+        }
+    }
+
+    fn text(&mut self, len: Option<u64>) -> Result<Cow<'v, str>> {
+        let start = self.idx;
+        let text = match self.string(TEXT, len)? {
+            BytesCow::Borrowed(b) => core::str::from_utf8(b).ok().map(Cow::from),
+            BytesCow::Owned(b) => String::from_utf8(b).ok().map(Cow::from),
+        };
+        text.ok_or_else(|| self.error(start, ErrorType::InvalidUTF8))
+    }
+
+    /// Decodes one item, containers are kept on an explicit stack so deep
+    /// nesting can't overflow the call stack
+    #[allow(clippy::cast_possible_wrap, clippy::too_many_lines)]
+    fn parse(&mut self) -> Result<BorrowedValue<'v>> {
+        let mut stack: Vec<Frame<'v>> = Vec::new();
+        let mut tag: Option<u64> = None;
+        loop {
+            let start = self.idx;
+            let initial = self.byte()?;
+            let (major, info) = (initial >> 5, initial & 0x1f);
+            let mut value = match major {
+                UINT | NINT | TAG if info == INDEFINITE => {
+                    return Err(self.error(start, ErrorType::UnexpectedCharacter));
+                }
+                UINT => {
+                    let n = self.argument(info)?.unwrap_or_default();
+                    i64::try_from(n).map_or(BorrowedValue::from(n), BorrowedValue::from)
+                }
+                NINT => {
+                    let n = self.argument(info)?.unwrap_or_default();
+                    match i64::try_from(n) {
+                        Ok(n) => BorrowedValue::from(!n),
+                        #[cfg(feature = "128bit")]
+                        Err(_) => BorrowedValue::from(!i128::from(n)),
+                        #[cfg(not(feature = "128bit"))]
+                        Err(_) => return Err(self.error(start, ErrorType::InvalidNumber)),
+                    }
+                }
+                BYTES => {
+                    let len = self.argument(info)?;
+                    let bytes = self.string(BYTES, len)?;
+                    bignum(tag, &bytes).unwrap_or(BorrowedValue::Bytes(bytes))
+                }
+                TEXT => {
+                    let len = self.argument(info)?;
+                    BorrowedValue::String(self.text(len)?)
+                }
+                ARRAY | MAP => {
+                    let remaining = self.argument(info)?;
+                    let capacity = remaining.map_or(0, |len| self.capacity(len));
+                    let frame = if major == ARRAY {
+                        Frame::Array {
+                            values: Vec::with_capacity(capacity),
+                            remaining,
+                        }
+                    } else {
+                        Frame::Object {
+                            object: crate::borrowed::Object::with_capacity(capacity),
+                            key: None,
+                            remaining,
+                        }
+                    };
+                    tag = None;
+                    if frame.is_done() {
+                        frame.into_value()
+                    } else {
+                        stack.push(frame);
+                        continue;
+                    }
+                }
+                TAG => {
+                    tag = self.argument(info)?;
+                    continue;
+                }
+                _ => match initial {
+                    FALSE => BorrowedValue::from(false),
+                    TRUE => BorrowedValue::from(true),
+                    // `undefined` has no counterpart in JSON
+                    NULL | 0xf7 => BorrowedValue::Static(StaticNode::Null),
+                    0xf9 => BorrowedValue::from(f16_to_f64(u16::from_be_bytes(self.array()?))),
+                    F32 => BorrowedValue::from(f64::from(f32::from_bits(u32::from_be_bytes(
+                        self.array()?,
+                    )))),
+                    F64 => BorrowedValue::from(f64::from_bits(u64::from_be_bytes(self.array()?))),
+                    BREAK => match stack.pop() {
+                        Some(
+                            frame @ (Frame::Array {
+                                remaining: None, ..
+                            }
+                            | Frame::Object {
+                                remaining: None,
+                                key: None,
+                                ..
+                            }),
+                        ) => frame.into_value(),
+                        _ => return Err(self.error(start, ErrorType::UnexpectedCharacter)),
+                    },
+                    _ => return Err(self.error(start, ErrorType::UnexpectedCharacter)),
+                },
+            };
+            tag = None;
+
+            // Hand the value to the containers it completes
+            loop {
+                match stack.last_mut() {
+                    None => return Ok(value),
+                    Some(Frame::Array { values, remaining }) => {
+                        values.push(value);
+                        if let Some(remaining) = remaining {
+                            *remaining -= 1;
+                        }
+                    }
+                    Some(Frame::Object {
+                        object,
+                        key,
+                        remaining,
+                    }) => {
+                        if let Some(key) = key.take() {
+                            object.insert(key, value);
+                            if let Some(remaining) = remaining {
+                                *remaining -= 1;
+                            }
+                        } else if let BorrowedValue::String(s) = value {
+                            *key = Some(s);
+                        } else {
+                            return Err(self.error(start, ErrorType::KeyMustBeAString));
+                        }
+                    }
+                }
+                match stack.pop() {
+                    Some(frame) if frame.is_done() => value = frame.into_value(),
+                    Some(frame) => {
+                        stack.push(frame);
+                        break;
+                    }
+                    None => unreachable!(),
+                }
+            }
+        }
+    }
+}
+
+/// Turns a byte string tagged as a bignum back into an integer
+#[cfg(feature = "128bit")]
+fn bignum<'v>(tag: Option<u64>, bytes: &[u8]) -> Option<BorrowedValue<'v>> {
+    let negative = match tag {
+        Some(POSITIVE_BIGNUM) => false,
+        Some(NEGATIVE_BIGNUM) => true,
+        _ => return None,
+    };
+    let start = bytes.iter().take_while(|b| **b == 0).count();
+    let bytes = &bytes[start..];
+    if bytes.len() > 16 {
+        return None;
+    }
+    let mut be = [0; 16];
+    be[16 - bytes.len()..].copy_from_slice(bytes);
+    let n = u128::from_be_bytes(be);
+    if negative {
+        i128::try_from(n).ok().map(|n| BorrowedValue::from(!n))
+    } else {
+        Some(BorrowedValue::from(n))
+    }
+}
+
+#[cfg(not(feature = "128bit"))]
+fn bignum<'v>(_tag: Option<u64>, _bytes: &[u8]) -> Option<BorrowedValue<'v>> {
+    None
+}
+
+/// Widens a half precision float
+fn f16_to_f64(half: u16) -> f64 {
+    let exponent = i32::from((half >> 10) & 0x1f);
+    let mantissa = f64::from(half & 0x3ff);
+    let magnitude = match exponent {
+        0 => mantissa * 2f64.powi(-24),
+        31 if mantissa == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        _ => (mantissa + 1024.0) * 2f64.powi(exponent - 25),
+    };
+    if half & 0x8000 == 0 {
+        magnitude
+    } else {
+        -magnitude
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{json, OwnedValue};
+
+    fn decode(cbor: &[u8]) -> Result<BorrowedValue<'_>> {
+        from_cbor_slice(cbor)
+    }
+
+    #[test]
+    fn encoding() {
+        let cases: &[(OwnedValue, &[u8])] = &[
+            (json!(0), &[0x00]),
+            (json!(23), &[0x17]),
+            (json!(24), &[0x18, 0x18]),
+            (json!(1000), &[0x19, 0x03, 0xe8]),
+            (json!(1_000_000), &[0x1a, 0x00, 0x0f, 0x42, 0x40]),
+            (json!(-1), &[0x20]),
+            (json!(-1000), &[0x39, 0x03, 0xe7]),
+            (
+                OwnedValue::from(u64::MAX),
+                &[0x1b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+            ),
+            (
+                json!(i64::MIN),
+                &[0x3b, 0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+            ),
+            (json!(1.5), &[0xfa, 0x3f, 0xc0, 0x00, 0x00]),
+            (
+                json!(1.1),
+                &[0xfb, 0x3f, 0xf1, 0x99, 0x99, 0x99, 0x99, 0x99, 0x9a],
+            ),
+            (json!(null), &[0xf6]),
+            (json!(true), &[0xf5]),
+            (json!(""), &[0x60]),
+            (json!("ü"), &[0x62, 0xc3, 0xbc]),
+            (json!([]), &[0x80]),
+            (json!([1, [2]]), &[0x82, 0x01, 0x81, 0x02]),
+            (json!({}), &[0xa0]),
+            (OwnedValue::Bytes(vec![1, 2]), &[0x42, 0x01, 0x02]),
+        ];
+        for (value, cbor) in cases {
+            assert_eq!(&to_cbor_vec(value), cbor, "{}", value);
+            assert_eq!(&to_cbor_vec(&BorrowedValue::from(value.clone())), cbor);
+            assert_eq!(&decode(cbor).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn decoding() {
+        // half precision floats
+        assert_eq!(decode(&[0xf9, 0x3c, 0x00]).unwrap(), 1.0);
+        assert_eq!(decode(&[0xf9, 0xc4, 0x00]).unwrap(), -4.0);
+        assert_eq!(
+            decode(&[0xf9, 0x00, 0x01]).unwrap(),
+            5.960_464_477_539_063e-8
+        );
+        assert_eq!(decode(&[0xf9, 0x7c, 0x00]).unwrap(), f64::INFINITY);
+        // undefined and tags
+        assert_eq!(decode(&[0xf7]).unwrap(), ());
+        assert_eq!(
+            decode(&[0xc1, 0x1a, 0x51, 0x4b, 0x67, 0xb0]).unwrap(),
+            1_363_896_240
+        );
+        // indefinite lengths
+        let v = decode(&[
+            0xbf, 0x61, b'a', 0x9f, 0x01, 0x7f, 0x61, b'x', 0x62, b'y', b'z', 0xff, 0xff, 0x61,
+            b'b', 0x5f, 0x41, 0x01, 0x41, 0x02, 0xff, 0xff,
+        ])
+        .unwrap();
+        assert_eq!(v["a"], json!([1, "xyz"]));
+        assert_eq!(v["b"].as_bytes(), Some(&[1, 2][..]));
+        // definite strings are borrowed
+        let data = [0x63, b'a', b'b', b'c'];
+        assert!(matches!(
+            decode(&data).unwrap(),
+            BorrowedValue::String(s) if s.as_ptr() == data[1..].as_ptr()
+        ));
+        // deep nesting doesn't recurse, neither decoding nor encoding
+        let mut deep = vec![0x81; 100_000];
+        deep.push(0xf6);
+        let v = decode(&deep).unwrap();
+        assert_eq!(to_cbor_vec(&v), deep);
+        v.dismantle();
+    }
+
+    #[test]
+    fn errors() {
+        let error = |cbor: &[u8]| decode(cbor).unwrap_err();
+        assert_eq!(error(&[]).error(), &ErrorType::EOF);
+        assert_eq!(error(&[0x82, 0x01]).error(), &ErrorType::EOF);
+        assert_eq!(
+            error(&[0x5a, 0xff, 0xff, 0xff, 0xff]).error(),
+            &ErrorType::EOF
+        );
+        assert_eq!(error(&[0x01, 0x02]).error(), &ErrorType::TrailingCharacters);
+        assert_eq!(
+            error(&[0xa1, 0x01, 0x02]).error(),
+            &ErrorType::KeyMustBeAString
+        );
+        assert_eq!(error(&[0x62, 0xc3, 0x28]).error(), &ErrorType::InvalidUTF8);
+        assert_eq!(
+            error(&[0x7f, 0x41, 0x00, 0xff]).error(),
+            &ErrorType::UnexpectedCharacter
+        );
+        assert_eq!(error(&[0xff]).error(), &ErrorType::UnexpectedCharacter);
+        assert_eq!(
+            error(&[0x81, 0xff]).error(),
+            &ErrorType::UnexpectedCharacter
+        );
+        assert_eq!(
+            error(&[0xbf, 0x61, b'a', 0xff]).error(),
+            &ErrorType::UnexpectedCharacter
+        );
+        assert_eq!(error(&[0xe0]).error(), &ErrorType::UnexpectedCharacter);
+        assert_eq!(error(&[0x1c]).error(), &ErrorType::UnexpectedCharacter);
+        assert_eq!(error(&[0x1f]).error(), &ErrorType::UnexpectedCharacter);
+        #[cfg(not(feature = "128bit"))]
+        assert_eq!(
+            error(&[0x3b, 0x80, 0, 0, 0, 0, 0, 0, 0]).error(),
+            &ErrorType::InvalidNumber
+        );
+    }
+
+    #[cfg(feature = "128bit")]
+    #[test]
+    fn bignums() {
+        for v in [
+            OwnedValue::from(u128::MAX),
+            OwnedValue::from(i128::MIN),
+            OwnedValue::from(-1 - i128::from(u64::MAX)),
+        ] {
+            let cbor = to_cbor_vec(&v);
+            assert_eq!(decode(&cbor).unwrap(), v);
+        }
+        assert_eq!(
+            to_cbor_vec(&OwnedValue::from(u128::from(u64::MAX) + 1)),
+            [0xc2, 0x49, 0x01, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            decode(&[0x3b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]).unwrap(),
+            -1 - i128::from(u64::MAX)
+        );
+    }
+}
@@ -22,14 +22,15 @@ const STRUCTURAL_OR_WHITESPACE: [u32; 256] = [
 
 #[cfg_attr(not(feature = "no-inline"), inline(always))]
 pub fn is_not_structural_or_whitespace(c: u8) -> u32 {
-    unsafe { *STRUCTURAL_OR_WHITESPACE_NEGATED.get_unchecked(c as usize) }
+    STRUCTURAL_OR_WHITESPACE_NEGATED[c as usize]
 }
 
 #[cfg_attr(not(feature = "no-inline"), inline(always))]
 pub fn is_structural_or_whitespace(c: u8) -> u32 {
-    unsafe { *STRUCTURAL_OR_WHITESPACE.get_unchecked(c as usize) }
+    STRUCTURAL_OR_WHITESPACE[c as usize]
 }
 
+#[cfg(not(feature = "safe"))]
 const DIGITTOVAL: [i8; 256] = [
     -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1,
     -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1,
@@ -47,6 +48,7 @@ const DIGITTOVAL: [i8; 256] = [
 // returns a value with the high 16 bits set if not valid
 // otherwise returns the conversion of the 4 hex digits at src into the bottom 16 bits of the 32-bit
 // return register
+#[cfg(not(feature = "safe"))]
 #[cfg_attr(not(feature = "no-inline"), inline)]
 #[allow(clippy::cast_sign_loss)]
 pub fn hex_to_u32_nocheck(src: &[u8]) -> u32 {
@@ -75,6 +77,7 @@ pub fn hex_to_u32_nocheck(src: &[u8]) -> u32 {
 //
 // Note: we assume that surrogates are treated separately
 //
+#[cfg(not(feature = "safe"))]
 #[cfg_attr(not(feature = "no-inline"), inline)]
 #[allow(clippy::cast_possible_truncation)]
 pub fn codepoint_to_utf8(cp: u32, c: &mut [u8]) -> usize {
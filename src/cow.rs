@@ -1,7 +1,7 @@
 //! Reexport of Cow
 
 #[cfg(not(feature = "beef"))]
-pub use std::borrow::Cow;
+pub use alloc::borrow::Cow;
 
 #[cfg(feature = "beef")]
 pub use beef::lean::Cow;
@@ -0,0 +1,236 @@
+use crate::prelude::*;
+use crate::{CsvOptions, Error, ErrorType};
+use core::borrow::Borrow;
+use halfbrown::HashMap;
+use std::io::{self, Write};
+use value_trait::Value;
+
+/// Writes a document that is an array of objects as CSV, one line per
+/// object. Nested objects are flattened into one column per field, named
+/// by joining the keys with the key separator of `options` (`a.b` by
+/// default), the columns are in the order their fields are first seen
+/// and fields a row doesn't have are left empty.
+///
+/// Strings are written as they are, numbers and booleans in their JSON
+/// form, `null` as an empty field and arrays and empty objects as JSON.
+/// Fields that hold the delimiter, a `"` or a line break are quoted.
+///
+/// ```rust
+/// use simd_json::prelude::*;
+/// let mut d = br#"[
+///     {"id": 1, "user": {"name": "a, b", "age": 3}},
+///     {"id": 2, "user": {"name": "c"}, "tags": ["x"]}
+/// ]"#.to_vec();
+/// let v = simd_json::to_borrowed_value(&mut d).unwrap();
+/// let mut out = Vec::new();
+/// simd_json::to_csv(&v, &mut out, simd_json::CsvOptions::default()).unwrap();
+/// assert_eq!(
+///     String::from_utf8(out).unwrap(),
+///     "id,user.name,user.age,tags\n1,\"a, b\",3,\n2,c,,\"[\"\"x\"\"]\"\n"
+/// );
+/// ```
+///
+/// # Errors
+///
+/// if the write fails or `value` isn't an array of objects, the latter
+/// fails with `io::ErrorKind::InvalidData`
+pub fn to_csv<V, W>(value: &V, w: &mut W, options: CsvOptions) -> io::Result<()>
+where
+    V: Value + Writable,
+    V::Key: Borrow<str>,
+    W: Write,
+{
+    let rows = value
+        .as_array()
+        .ok_or_else(|| Error::generic(ErrorType::ExpectedArray))?;
+    let mut columns = Columns {
+        index: HashMap::new(),
+        names: Vec::new(),
+        separator: options.key_separator,
+    };
+    let mut name = String::new();
+    for row in rows.iter() {
+        if !row.is_object() {
+            return Err(Error::generic(ErrorType::ExpectedMap).into());
+        }
+        columns.row(row, &mut name, &mut |columns, name, _| {
+            if !columns.index.contains_key(name) {
+                columns.index.insert(name.to_string(), columns.names.len());
+                columns.names.push(name.to_string());
+            }
+        });
+    }
+
+    let delimiter = [options.delimiter];
+    if options.header && !columns.names.is_empty() {
+        for (i, name) in columns.names.iter().enumerate() {
+            if i > 0 {
+                w.write_all(&delimiter)?;
+            }
+            write_field(w, name, options.delimiter)?;
+        }
+        w.write_all(b"\n")?;
+    }
+    let mut fields: Vec<Option<&V>> = Vec::new();
+    for row in rows.iter() {
+        fields.clear();
+        fields.resize(columns.names.len(), None);
+        columns.row(row, &mut name, &mut |columns, name, value| {
+            if let Some(column) = columns.index.get(name) {
+                fields[*column] = Some(value);
+            }
+        });
+        for (i, field) in fields.iter().enumerate() {
+            if i > 0 {
+                w.write_all(&delimiter)?;
+            }
+            match field {
+                Some(v) if v.is_null() => {}
+                Some(v) => match v.as_str() {
+                    Some(s) => write_field(w, s, options.delimiter)?,
+                    None => write_field(w, &v.encode(), options.delimiter)?,
+                },
+                None => {}
+            }
+        }
+        w.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// The flattened column names, in the order they were first seen
+struct Columns {
+    index: HashMap<String, usize>,
+    names: Vec<String>,
+    separator: char,
+}
+
+impl Columns {
+    /// Calls `f` with the flattened name of every leaf of the object `row`
+    fn row<'v, V>(
+        &mut self,
+        row: &'v V,
+        name: &mut String,
+        f: &mut impl FnMut(&mut Self, &str, &'v V),
+    ) where
+        V: Value,
+        V::Key: Borrow<str>,
+    {
+        if let Some(object) = row.as_object() {
+            for (key, value) in object.iter() {
+                name.clear();
+                name.push_str(key.borrow());
+                self.flatten(value, name, f);
+            }
+        }
+    }
+
+    /// Calls `f` with the flattened name of every leaf of `value`, `name`
+    /// holds the name of `value` itself and is restored before returning
+    fn flatten<'v, V>(
+        &mut self,
+        value: &'v V,
+        name: &mut String,
+        f: &mut impl FnMut(&mut Self, &str, &'v V),
+    ) where
+        V: Value,
+        V::Key: Borrow<str>,
+    {
+        match value.as_object() {
+            Some(object) if !object.is_empty() => {
+                let len = name.len();
+                for (key, value) in object.iter() {
+                    name.push(self.separator);
+                    name.push_str(key.borrow());
+                    self.flatten(value, name, f);
+                    name.truncate(len);
+                }
+            }
+            _ => f(self, name, value),
+        }
+    }
+}
+
+/// Writes `s`, quoted if it needs to be
+fn write_field<W: Write>(w: &mut W, s: &str, delimiter: u8) -> io::Result<()> {
+    let needs_quotes = s
+        .bytes()
+        .any(|c| c == delimiter || matches!(c, b'"' | b'\n' | b'\r'));
+    if !needs_quotes {
+        return w.write_all(s.as_bytes());
+    }
+    w.write_all(b"\"")?;
+    for (i, part) in s.split('"').enumerate() {
+        if i > 0 {
+            w.write_all(b"\"\"")?;
+        }
+        w.write_all(part.as_bytes())?;
+    }
+    w.write_all(b"\"")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{to_borrowed_value, to_owned_value};
+
+    fn csv<V>(v: &V, options: CsvOptions) -> io::Result<String>
+    where
+        V: Value + Writable,
+        V::Key: Borrow<str>,
+    {
+        let mut out = Vec::new();
+        to_csv(v, &mut out, options)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn flatten() {
+        let d = r#"[
+            {"a": {"b": {"c": 1.5}, "d": true}, "e": null},
+            {"e": "x\"y", "a": {}, "f": {"g": "line\nbreak"}, "h": [1, {"i": 2}]},
+            {}
+        ]"#;
+        let expected = concat!(
+            "a.b.c,a.d,e,a,f.g,h\n",
+            "1.5,true,,,,\n",
+            ",,\"x\"\"y\",{},\"line\nbreak\",\"[1,{\"\"i\"\":2}]\"\n",
+            ",,,,,\n"
+        );
+        let v = to_owned_value(&mut d.as_bytes().to_vec()).unwrap();
+        assert_eq!(csv(&v, CsvOptions::default()).unwrap(), expected);
+        let mut d = d.as_bytes().to_vec();
+        let v = to_borrowed_value(&mut d).unwrap();
+        assert_eq!(csv(&v, CsvOptions::default()).unwrap(), expected);
+    }
+
+    #[test]
+    fn options() {
+        let v = to_owned_value(&mut br#"[{"a": {"b": "1;2"}, "c": "3,4"}]"#.to_vec()).unwrap();
+        let options = CsvOptions::default()
+            .delimiter(b';')
+            .key_separator('/')
+            .header(false);
+        assert_eq!(csv(&v, options).unwrap(), "\"1;2\";3,4\n");
+        let v = to_owned_value(&mut b"[{\"a\\tb\": 1}]".to_vec()).unwrap();
+        let options = CsvOptions::default().delimiter(b'\t');
+        assert_eq!(csv(&v, options).unwrap(), "\"a\tb\"\n1\n");
+    }
+
+    #[test]
+    fn empty() {
+        let v = to_owned_value(&mut b"[]".to_vec()).unwrap();
+        assert_eq!(csv(&v, CsvOptions::default()).unwrap(), "");
+        let v = to_owned_value(&mut b"[{}, {\"a\": {}}]".to_vec()).unwrap();
+        assert_eq!(csv(&v, CsvOptions::default()).unwrap(), "a\n\n{}\n");
+    }
+
+    #[test]
+    fn not_records() {
+        let v = to_owned_value(&mut br#"{"a": 1}"#.to_vec()).unwrap();
+        let err = csv(&v, CsvOptions::default()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let v = to_owned_value(&mut br#"[{"a": 1}, 2]"#.to_vec()).unwrap();
+        assert!(csv(&v, CsvOptions::default()).is_err());
+    }
+}
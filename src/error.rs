@@ -1,4 +1,6 @@
-use std::fmt;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use core::fmt;
 
 /// Error types encountered while parsing
 #[derive(Debug)]
@@ -79,10 +81,29 @@ pub enum ErrorType {
     ExpectedObjectKey,
     /// Overflow of a limited buffer
     Overflow,
+    /// The input ended before the document was complete, more input
+    /// could still turn it into valid JSON
+    Incomplete {
+        /// The minimum number of bytes that are missing, if known
+        needed: Option<usize>,
+    },
+    /// The input starts with a UTF-8 byte order mark, see
+    /// `ParseOptions::skip_bom`
+    ByteOrderMark,
+    /// Arrays and objects are nested deeper than
+    /// `ParseOptions::max_depth` allows
+    TooDeep,
+    /// The bytes aren't a tape written by `Tape::to_bytes`
+    InvalidSnapshot,
     /// IO error
+    #[cfg(feature = "std")]
     IO(std::io::Error),
+    /// The document doesn't fit an arrow `RecordBatch`
+    #[cfg(feature = "arrow")]
+    Arrow(arrow_schema::ArrowError),
 }
 
+#[cfg(feature = "std")]
 impl From<std::io::Error> for Error {
     fn from(e: std::io::Error) -> Self {
         Self::generic(ErrorType::IO(e))
@@ -94,8 +115,11 @@ impl PartialEq for ErrorType {
     #[must_use]
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
-            (Self::IO(_), Self::IO(_))
-            | (Self::BadKeyType, Self::BadKeyType)
+            #[cfg(feature = "std")]
+            (Self::IO(_), Self::IO(_)) => true,
+            #[cfg(feature = "arrow")]
+            (Self::Arrow(e1), Self::Arrow(e2)) => e1.to_string() == e2.to_string(),
+            (Self::BadKeyType, Self::BadKeyType)
             | (Self::EarlyEnd, Self::EarlyEnd)
             | (Self::ExpectedArray, Self::ExpectedArray)
             | (Self::ExpectedArrayComma, Self::ExpectedArrayComma)
@@ -131,8 +155,12 @@ impl PartialEq for ErrorType {
             | (Self::ExpectedArrayContent, Self::ExpectedArrayContent)
             | (Self::ExpectedObjectContent, Self::ExpectedObjectContent)
             | (Self::ExpectedObjectKey, Self::ExpectedObjectKey)
-            | (Self::Overflow, Self::Overflow) => true,
+            | (Self::Overflow, Self::Overflow)
+            | (Self::ByteOrderMark, Self::ByteOrderMark)
+            | (Self::TooDeep, Self::TooDeep)
+            | (Self::InvalidSnapshot, Self::InvalidSnapshot) => true,
             (Self::Serde(s1), Self::Serde(s2)) => s1 == s2,
+            (Self::Incomplete { needed: n1 }, Self::Incomplete { needed: n2 }) => n1 == n2,
             _ => false,
         }
     }
@@ -146,14 +174,29 @@ pub struct Error {
     character: char,
     /// Tyep of error
     error: ErrorType,
+    /// Where in the input the error happened, only known for errors
+    /// raised while parsing
+    context: Option<Box<Context>>,
 }
 
+/// Location of an error in the input
+#[derive(Debug, PartialEq)]
+struct Context {
+    line: usize,
+    column: usize,
+    snippet: String,
+}
+
+/// Number of bytes shown on each side of the error in the snippet
+const SNIPPET_RADIUS: usize = 12;
+
 impl Error {
     pub(crate) fn new(index: usize, character: char, error: ErrorType) -> Self {
         Self {
             index,
             character,
             error,
+            context: None,
         }
     }
     pub(crate) fn generic(t: ErrorType) -> Self {
@@ -161,21 +204,124 @@ impl Error {
             index: 0,
             character: '💩', //this is the poop emoji
             error: t,
+            context: None,
+        }
+    }
+
+    /// Moves the error to `index`, for errors found in a copy of the input
+    #[cfg(feature = "safe")]
+    pub(crate) fn at(mut self, index: usize) -> Self {
+        self.index = index;
+        self
+    }
+
+    /// Attaches line, column and a snippet of `input` around the error
+    #[cold]
+    pub(crate) fn with_context(mut self, input: &[u8]) -> Self {
+        let index = self.index.min(input.len());
+        let before = &input[..index];
+        let line_start = before
+            .iter()
+            .rposition(|&c| c == b'\n')
+            .map_or(0, |i| i + 1);
+        let line = before.split(|&c| c == b'\n').count();
+        // count characters, not bytes, by skipping UTF-8 continuation bytes
+        let column = before[line_start..]
+            .iter()
+            .filter(|&&c| c & 0xC0 != 0x80)
+            .count()
+            + 1;
+
+        let mut start = index.saturating_sub(SNIPPET_RADIUS);
+        while start < index && input[start] & 0xC0 == 0x80 {
+            start += 1;
         }
+        let mut end = (index + SNIPPET_RADIUS).min(input.len());
+        while end > index && end < input.len() && input[end] & 0xC0 == 0x80 {
+            end -= 1;
+        }
+        let snippet = String::from_utf8_lossy(&input[start..end])
+            .trim()
+            .escape_debug()
+            .to_string();
+
+        self.context = Some(Box::new(Context {
+            line,
+            column,
+            snippet,
+        }));
+        self
+    }
+
+    /// Byte offset in the input the error was encountered at
+    #[must_use]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The character the error was encountered at
+    #[must_use]
+    pub fn character(&self) -> char {
+        self.character
+    }
+
+    /// The type of the error
+    #[must_use]
+    pub fn error(&self) -> &ErrorType {
+        &self.error
+    }
+
+    /// Returns true if the input was cut off but is valid so far, so a
+    /// streaming consumer should wait for more bytes instead of
+    /// rejecting it
+    #[must_use]
+    pub fn is_incomplete(&self) -> bool {
+        matches!(self.error, ErrorType::Incomplete { .. })
+    }
+
+    /// The 1-based line the error was encountered on, if known
+    #[must_use]
+    pub fn line(&self) -> Option<usize> {
+        self.context.as_ref().map(|c| c.line)
+    }
+
+    /// The 1-based column (in characters) the error was encountered
+    /// at, if known
+    #[must_use]
+    pub fn column(&self) -> Option<usize> {
+        self.context.as_ref().map(|c| c.column)
+    }
+
+    /// A short excerpt of the input surrounding the error, if known
+    #[must_use]
+    pub fn snippet(&self) -> Option<&str> {
+        self.context.as_ref().map(|c| c.snippet.as_str())
     }
 }
 
 #[cfg_attr(tarpaulin, skip)]
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{:?} at character {} ('{}')",
-            self.error, self.index, self.character
-        )
+        if let Some(context) = &self.context {
+            write!(
+                f,
+                "{:?} at line {} column {} near '{}'",
+                self.error, context.line, context.column, context.snippet
+            )
+        } else {
+            write!(
+                f,
+                "{:?} at character {} ('{}')",
+                self.error, self.index, self.character
+            )
+        }
     }
 }
 
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+#[cfg(feature = "std")]
 #[cfg_attr(tarpaulin, skip)]
 impl From<Error> for std::io::Error {
     fn from(e: Error) -> Self {
@@ -194,4 +340,21 @@ mod test {
             "InternalError at character 0 ('\u{1f4a9}')"
         )
     }
+
+    #[test]
+    fn context() {
+        let input = b"{\n  \"id\": 1,\n  \"user\" 42\n}";
+        let e = Error::new(22, '4', ErrorType::ExpectedObjectColon).with_context(input);
+        assert_eq!(e.index(), 22);
+        assert_eq!(e.line(), Some(3));
+        assert_eq!(e.column(), Some(10));
+        assert_eq!(
+            e.to_string(),
+            "ExpectedObjectColon at line 3 column 10 near '1,\\n  \\\"user\\\" 42\\n}'"
+        );
+        // the snippet doesn't split characters
+        let e = Error::new(15, 'x', ErrorType::Syntax).with_context("ééééééé x".as_bytes());
+        assert_eq!(e.column(), Some(9));
+        assert_eq!(e.snippet(), Some("ééééé x"));
+    }
 }
@@ -0,0 +1,320 @@
+use crate::stringparse::ESCAPE_MAP;
+#[cfg(feature = "std")]
+use crate::value::encoder::OptionsGenerator;
+use crate::{Deserializer, Error, ErrorType, InvalidEscapes, Result, SIMDJSON_PADDING};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+#[cfg(feature = "std")]
+use value_trait::generator::BaseGenerator;
+
+/// Writes `string` escaped the way the serializer writes strings, without
+/// the quotes around it, for protocols that embed JSON strings. `"`, `\`
+/// and control characters are escaped, everything else is written as it
+/// is.
+///
+/// ```rust
+/// let mut out = Vec::new();
+/// simd_json::write_escaped(&mut out, "say \"hi\"\n").unwrap();
+/// assert_eq!(out, br#"say \"hi\"\n"#);
+/// ```
+///
+/// # Errors
+///
+/// if the write fails
+#[cfg(feature = "std")]
+pub fn write_escaped<W: Write>(writer: &mut W, string: &str) -> io::Result<()> {
+    OptionsGenerator::new(writer, crate::EncodeOptions::default()).write_string_content(string)
+}
+
+/// Escapes `string` like `write_escaped` does.
+///
+/// ```rust
+/// assert_eq!(simd_json::escape_str("tab\there"), r#"tab\there"#);
+/// ```
+#[cfg(feature = "std")]
+#[must_use]
+pub fn escape_str(string: &str) -> String {
+    let mut out = Vec::with_capacity(string.len() + 2);
+    // writing to a `Vec` can't fail
+    let _ = write_escaped(&mut out, string);
+    crate::value::encoder::into_string(out)
+}
+
+/// De-escapes the content of a JSON string, given without the quotes
+/// around it, with the same validation and vectorized de-escaping the
+/// parser uses for strings.
+///
+/// ```rust
+/// let s = simd_json::unescape_str(r#"snow ☃ \"man\"\n"#.as_bytes()).unwrap();
+/// assert_eq!(s, "snow ☃ \"man\"\n");
+/// let err = simd_json::unescape_str(br#"a \x"#).unwrap_err();
+/// assert_eq!(err.index(), 2);
+/// ```
+///
+/// # Errors
+///
+/// Will return `Err` if `input` has an invalid escape, an unescaped `"`
+/// or control character, ends in the middle of an escape or isn't UTF-8.
+pub fn unescape_str(input: &[u8]) -> Result<String> {
+    let len = input.len();
+    // the string with its quotes, padded for the vectorized loads
+    let mut data = Vec::with_capacity(len + 2 + SIMDJSON_PADDING);
+    data.push(b'"');
+    data.extend_from_slice(input);
+    data.push(b'"');
+    // stage 1 checks what the string parser relies on: that there is
+    // exactly one string, ending at the closing quote, without control
+    // characters and that it is UTF-8
+    let mut structural_indexes = Vec::new();
+    match Deserializer::find_structural_bits(&data, &mut structural_indexes) {
+        Ok(()) if structural_indexes.len() == 2 => {}
+        Ok(()) => return Err(scan_error(input, ErrorType::UnexpectedCharacter)),
+        Err(e) => return Err(scan_error(input, e)),
+    }
+    let out = data.clone();
+    data.resize(len + 2 + SIMDJSON_PADDING, 0);
+    // the vectorized string parsers report errors at their index in the
+    // string, the scalar one at their index in `data`
+    #[cfg(not(feature = "safe"))]
+    let string = {
+        let mut buffer = alloc::vec![0; len + SIMDJSON_PADDING];
+        Deserializer::parse_str_(&out, &data, &mut buffer, 0)
+    };
+    #[cfg(feature = "safe")]
+    let mut out = out;
+    #[cfg(feature = "safe")]
+    let string = Deserializer::parse_str_(&mut out.as_mut_slice(), &mut 0, &data, 0).map_err(|e| {
+        let idx = e.index() - 1;
+        e.at(idx)
+    });
+    string.map(ToString::to_string)
+}
+
+/// Finds where in `input` the error stage 1 found is, it only tells what
+/// kind of error there is
+#[cold]
+fn scan_error(input: &[u8], error: ErrorType) -> Error {
+    let mut escaped = false;
+    for (idx, &c) in input.iter().enumerate() {
+        match c {
+            // stage 1 doesn't let escapes get away with control characters
+            c if c < 0x20 && matches!(error, ErrorType::Syntax) => {
+                return Error::new(idx, char::from(c), error);
+            }
+            _ if escaped => escaped = false,
+            b'\\' => escaped = true,
+            // a quote in the middle ends the string early
+            b'"' => return Error::new(idx, '"', ErrorType::UnexpectedCharacter),
+            _ => {}
+        }
+    }
+    match (error, core::str::from_utf8(input)) {
+        (ErrorType::InvalidUTF8, Err(e)) => {
+            let idx = e.valid_up_to();
+            Error::new(idx, char::from(input[idx]), ErrorType::InvalidUTF8)
+        }
+        // a backslash at the end escapes the closing quote
+        (error, _) => Error::new(input.len().saturating_sub(1), '\\', error),
+    }
+}
+
+/// Rewrites the escapes in `input` the parser rejects, unknown escapes and
+/// `\u` escapes of surrogates that aren't part of a pair, as `policy`
+/// says. `None` if there are none.
+pub(crate) fn repair_escapes(input: &[u8], policy: InvalidEscapes) -> Option<Vec<u8>> {
+    let mut repaired: Option<Vec<u8>> = None;
+    // `input` up to here is in `repaired`
+    let mut copied = 0;
+    let mut idx = 0;
+    while let Some(offset) = input[idx..].iter().position(|c| *c == b'\\') {
+        let start = idx + offset;
+        let (len, valid) = escape_len(&input[start..]);
+        // this runs before the input is checked to be UTF-8, an escaped
+        // character can be cut off by the end
+        idx = (start + len).min(input.len());
+        if valid {
+            continue;
+        }
+        let out = repaired.get_or_insert_with(|| Vec::with_capacity(input.len() + 16));
+        out.extend_from_slice(&input[copied..start]);
+        match policy {
+            InvalidEscapes::Error => return None,
+            InvalidEscapes::Replace => out.extend_from_slice(br"\uFFFD"),
+            InvalidEscapes::Keep => {
+                out.push(b'\\');
+                out.extend_from_slice(&input[start..idx]);
+            }
+        }
+        copied = idx;
+    }
+    repaired.map(|mut out| {
+        out.extend_from_slice(&input[copied..]);
+        out
+    })
+}
+
+/// The length of the escape at the start of `escape` and whether the
+/// parser accepts it. A `\u` escape with fewer than four hex digits
+/// takes the ones it has, the length never goes past the end of `escape`.
+fn escape_len(escape: &[u8]) -> (usize, bool) {
+    let hex = |at: usize| {
+        let digits = escape.get(at..).unwrap_or_default();
+        let n = digits
+            .iter()
+            .take(4)
+            .take_while(|c| c.is_ascii_hexdigit())
+            .count();
+        let code_point = digits[..n].iter().fold(0, |acc, c| {
+            acc << 4 | char::from(*c).to_digit(16).unwrap_or(0)
+        });
+        (n, code_point)
+    };
+    match escape.get(1) {
+        // the parser reports the end of the input
+        None => (1, true),
+        Some(b'u') => match hex(2) {
+            (4, 0xd800..=0xdbff) => match (escape.get(6..8), hex(8)) {
+                (Some(br"\u"), (4, 0xdc00..=0xdfff)) => (12, true),
+                _ => (6, false),
+            },
+            (4, 0xdc00..=0xdfff) => (6, false),
+            (4, _) => (6, true),
+            (n, _) => (2 + n, false),
+        },
+        Some(c) if ESCAPE_MAP[usize::from(*c)] != 0 => (2, true),
+        // the whole escaped character, which can take more than one byte
+        Some(c) => ((1 + utf8_len(*c)).min(escape.len()), false),
+    }
+}
+
+/// The length of the UTF-8 sequence starting with `first`, `1` for bytes
+/// that can't start one
+fn utf8_len(first: u8) -> usize {
+    match first {
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn unescape() {
+        assert_eq!(unescape_str(b""), Ok(String::new()));
+        assert_eq!(
+            unescape_str(br"\\\/\b\f\n\r\t"),
+            Ok("\\/\u{8}\u{c}\n\r\t".to_string())
+        );
+        assert_eq!(unescape_str("é😀".as_bytes()), Ok("é😀".to_string()));
+        // long enough to take more than one vector
+        let long = "x\\\"y ".repeat(40);
+        assert_eq!(unescape_str(long.as_bytes()), Ok("x\"y ".repeat(40)));
+        let err = |input: &[u8]| unescape_str(input).expect_err("unescaped");
+        let e = err(br#"ab"c"#);
+        assert_eq!((e.index(), e.error()), (2, &ErrorType::UnexpectedCharacter));
+        let e = err(br#"ab\"c" "#);
+        assert_eq!((e.index(), e.error()), (5, &ErrorType::UnexpectedCharacter));
+        let e = err(b"a\\\x01");
+        assert_eq!((e.index(), e.error()), (2, &ErrorType::Syntax));
+        let e = err(b"a\nb");
+        assert_eq!((e.index(), e.error()), (1, &ErrorType::Syntax));
+        let e = err(b"ab\xff");
+        assert_eq!((e.index(), e.error()), (2, &ErrorType::InvalidUTF8));
+        let e = err(br"ab\");
+        assert_eq!(
+            (e.index(), e.error()),
+            (2, &ErrorType::Incomplete { needed: None })
+        );
+        let e = err(br"ab\q");
+        assert_eq!((e.index(), e.error()), (2, &ErrorType::InvalidEscape));
+        let e = err(br"\ud83d");
+        assert_eq!(e.error(), &ErrorType::InvlaidUnicodeCodepoint);
+    }
+
+    #[test]
+    fn repair() {
+        use crate::ParseOptions;
+        let repair = |input: &str, policy| {
+            repair_escapes(input.as_bytes(), policy)
+                .map(|out| String::from_utf8(out).expect("UTF-8"))
+        };
+        let replace = |input: &str| repair(input, InvalidEscapes::Replace);
+        let keep = |input: &str| repair(input, InvalidEscapes::Keep);
+        for valid in &[
+            r#"["a\"\\\/\b\f\n\r\t", "\u00e9\ud83d\ude00"]"#,
+            r"\\q \\ud800",
+            "",
+            "\\",
+        ] {
+            assert_eq!(replace(valid), None, "{valid}");
+        }
+        assert_eq!(replace(r"a\qb\x"), Some(r"a\uFFFDb\uFFFD".to_string()));
+        assert_eq!(keep(r"a\qb\x"), Some(r"a\\qb\\x".to_string()));
+        assert_eq!(replace("\\é!"), Some(r"\uFFFD!".to_string()));
+        assert_eq!(keep("\\é!"), Some("\\\\é!".to_string()));
+        // lone surrogates, high ones at the end and before something else
+        assert_eq!(replace(r"\ud800"), Some(r"\uFFFD".to_string()));
+        assert_eq!(replace(r"\udc00\ud83d"), Some(r"\uFFFD\uFFFD".to_string()));
+        assert_eq!(replace(r"\uD800\u0041"), Some(r"\uFFFD\u0041".to_string()));
+        assert_eq!(
+            keep(r"x\udbff\ud800\udc00"),
+            Some(r"x\\udbff\ud800\udc00".to_string())
+        );
+        // `\u` without four hex digits
+        assert_eq!(replace(r"\u12G4"), Some(r"\uFFFDG4".to_string()));
+        assert_eq!(keep(r"\u12G4 \u"), Some(r"\\u12G4 \\u".to_string()));
+        assert_eq!(replace(r"a\u12"), Some(r"a\uFFFD".to_string()));
+        assert_eq!(keep(r"a\u12"), Some(r"a\\u12".to_string()));
+        // an escaped character cut off by the end, before UTF-8 is checked
+        let cut = |policy| repair_escapes(b"a\\\xe2", policy);
+        assert_eq!(cut(InvalidEscapes::Replace), Some(br"a\uFFFD".to_vec()));
+        assert_eq!(cut(InvalidEscapes::Keep), Some(b"a\\\\\xe2".to_vec()));
+        let cut_off = ParseOptions::default()
+            .invalid_escapes(InvalidEscapes::Replace)
+            .to_owned_value(&mut b"\"\\\xe2".to_vec());
+        assert!(cut_off.is_err());
+
+        let d = r#"{"\q": ["\udc00", "a\ud83d\ude00\x"]}"#;
+        let parse = |options: ParseOptions| options.to_owned_value(&mut d.as_bytes().to_vec());
+        let e = parse(ParseOptions::default()).expect_err("parsed");
+        assert_eq!(e.error(), &ErrorType::InvalidEscape);
+        for lone in &[r#"["\udc00"]"#, r#"["\ud800"]"#, r#"["\ud800A"]"#] {
+            assert!(crate::to_owned_value(&mut lone.as_bytes().to_vec()).is_err());
+        }
+        let replaced = json!({"\u{fffd}": ["\u{fffd}", "a😀\u{fffd}"]});
+        let options = ParseOptions::default().invalid_escapes(InvalidEscapes::Replace);
+        assert_eq!(parse(options).ok(), Some(replaced.clone()));
+        assert_eq!(parse(ParseOptions::lenient()).ok(), Some(replaced));
+        let mut borrowed = d.as_bytes().to_vec();
+        let kept = ParseOptions::default()
+            .invalid_escapes(InvalidEscapes::Keep)
+            .to_borrowed_value(&mut borrowed)
+            .expect("failed to parse");
+        assert_eq!(kept, json!({"\\q": ["\\udc00", "a😀\\x"]}));
+        // with lossy UTF-8 as well
+        let mut d = b"[\"\\q\xff\"]".to_vec();
+        let v = ParseOptions::lenient()
+            .to_owned_value(&mut d)
+            .expect("failed to parse");
+        assert_eq!(v, json!(["\u{fffd}\u{fffd}"]));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn escape() {
+        let s = "\"\\\u{0}\u{1f}\u{7f} é/ 😀";
+        assert_eq!(escape_str(s), "\\\"\\\\\\u0000\\u001f\u{7f} é/ 😀");
+        assert_eq!(unescape_str(escape_str(s).as_bytes()), Ok(s.to_string()));
+        let long = "a\"".repeat(100);
+        assert_eq!(escape_str(&long), "a\\\"".repeat(100));
+        let mut out = b"x".to_vec();
+        assert!(write_escaped(&mut out, "\t").is_ok());
+        assert_eq!(out, br"x\t");
+    }
+}
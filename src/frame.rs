@@ -0,0 +1,510 @@
+use crate::buffers::Buffers;
+use crate::prelude::*;
+use crate::value::borrowed::to_value_with_buffers;
+use crate::{BorrowedValue, Error, ErrorType, Result};
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use std::io::{self, Read, Write};
+#[cfg(feature = "tokio")]
+use tokio_crate::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// The length prefix in front of every frame, the number of bytes of
+/// JSON after it as an unsigned integer
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum LengthPrefix {
+    /// Two bytes, big endian
+    U16Be,
+    /// Two bytes, little endian
+    U16Le,
+    /// Four bytes, big endian
+    #[default]
+    U32Be,
+    /// Four bytes, little endian
+    U32Le,
+    /// Eight bytes, big endian
+    U64Be,
+    /// Eight bytes, little endian
+    U64Le,
+}
+
+impl LengthPrefix {
+    fn width(self) -> usize {
+        match self {
+            Self::U16Be | Self::U16Le => 2,
+            Self::U32Be | Self::U32Le => 4,
+            Self::U64Be | Self::U64Le => 8,
+        }
+    }
+
+    fn big_endian(self) -> bool {
+        matches!(self, Self::U16Be | Self::U32Be | Self::U64Be)
+    }
+
+    fn decode(self, prefix: &[u8]) -> u64 {
+        let mut bytes = [0; 8];
+        if self.big_endian() {
+            bytes[8 - prefix.len()..].copy_from_slice(prefix);
+            u64::from_be_bytes(bytes)
+        } else {
+            bytes[..prefix.len()].copy_from_slice(prefix);
+            u64::from_le_bytes(bytes)
+        }
+    }
+
+    /// Writes `len` into `prefix`, `None` if it doesn't fit
+    fn encode(self, len: usize, prefix: &mut [u8]) -> Option<()> {
+        let width = prefix.len();
+        let len = len as u64;
+        if width < 8 && len >> (width * 8) != 0 {
+            return None;
+        }
+        if self.big_endian() {
+            prefix.copy_from_slice(&len.to_be_bytes()[8 - width..]);
+        } else {
+            prefix.copy_from_slice(&len.to_le_bytes()[..width]);
+        }
+        Some(())
+    }
+}
+
+/// The longest frame `FrameReader` reads unless told otherwise, 16 MiB
+const DEFAULT_MAX_LEN: usize = 16 * 1024 * 1024;
+
+fn io_error(kind: io::ErrorKind, msg: &'static str) -> Error {
+    Error::generic(ErrorType::IO(io::Error::new(kind, msg)))
+}
+
+/// Reads length prefixed JSON frames from `reader`, one document per
+/// frame, reusing one frame buffer and the parser's scratch buffers for
+/// all of them like `JsonLines` does.
+///
+/// ```rust
+/// use simd_json::{json, prelude::*, FrameReader, FrameWriter};
+/// let mut writer = FrameWriter::new(Vec::new());
+/// writer.write_value(&json!({"id": 1}))?;
+/// writer.write_value(&json!({"id": 2}))?;
+/// let stream = writer.into_inner();
+/// assert_eq!(&stream[..4], &[0, 0, 0, 8]);
+///
+/// let mut reader = FrameReader::new(&stream[..]);
+/// let mut ids = 0;
+/// while let Some(frame) = reader.next()? {
+///     ids += frame["id"].as_u64().unwrap();
+/// }
+/// assert_eq!(ids, 3);
+/// # Ok::<(), simd_json::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct FrameReader<R> {
+    reader: R,
+    prefix: LengthPrefix,
+    max_len: usize,
+    frame: Vec<u8>,
+    buffers: Buffers,
+}
+
+impl<R> FrameReader<R> {
+    /// Reads frames with a `LengthPrefix::U32Be` prefix from `reader`,
+    /// frames longer than 16 MiB are refused, see `max_frame_len`
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            prefix: LengthPrefix::default(),
+            max_len: DEFAULT_MAX_LEN,
+            frame: Vec::new(),
+            buffers: Buffers::default(),
+        }
+    }
+
+    /// Sets the prefix the frames start with
+    #[must_use]
+    pub fn prefix(mut self, prefix: LengthPrefix) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    /// Fail on frames longer than `len` bytes before reading them, to
+    /// not allocate what a broken or hostile peer announces. The limit is
+    /// 16 MiB by default, raise it for peers that send larger documents.
+    #[must_use]
+    pub fn max_frame_len(mut self, len: usize) -> Self {
+        self.max_len = len;
+        self
+    }
+
+    /// The reader, with the frames read so far taken out of it
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Sizes the frame buffer for the frame after `prefix`
+    fn start_frame(&mut self, prefix: &[u8]) -> Result<()> {
+        match usize::try_from(self.prefix.decode(prefix)) {
+            Ok(len) if len <= self.max_len => {
+                self.frame.clear();
+                self.frame.resize(len, 0);
+                Ok(())
+            }
+            _ => Err(io_error(
+                io::ErrorKind::InvalidData,
+                "the frame is longer than the maximum",
+            )),
+        }
+    }
+
+    fn parse_frame(&mut self) -> Result<Option<BorrowedValue<'_>>> {
+        to_value_with_buffers(&mut self.frame, &mut self.buffers).map(Some)
+    }
+}
+
+fn prefix_eof() -> Error {
+    io_error(
+        io::ErrorKind::UnexpectedEof,
+        "the input ends inside a length prefix",
+    )
+}
+
+impl<R: Read> FrameReader<R> {
+    /// Parses the next frame, `None` if the input ends before it.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if reading fails, the input ends inside a frame,
+    /// the frame is too long or if it is invalid JSON.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result<Option<BorrowedValue<'_>>> {
+        let mut prefix = [0; 8];
+        let width = self.prefix.width();
+        let mut read = 0;
+        while read < width {
+            match self.reader.read(&mut prefix[read..width]) {
+                Ok(0) if read == 0 => return Ok(None),
+                Ok(0) => return Err(prefix_eof()),
+                Ok(n) => read += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(Error::generic(ErrorType::IO(e))),
+            }
+        }
+        stry!(self.start_frame(&prefix[..width]));
+        if let Err(e) = self.reader.read_exact(&mut self.frame) {
+            return Err(Error::generic(ErrorType::IO(e)));
+        }
+        self.parse_frame()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R: AsyncRead + Unpin> FrameReader<R> {
+    /// Parses the next frame from an async reader, like `next`.
+    ///
+    /// This is not cancel safe: if the future is dropped before it is
+    /// done, as the losing branch of a `select!` for example, the part of
+    /// the frame it read is lost and the reader is left in the middle of
+    /// a frame. Later frames can't be read from it.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if reading fails, the input ends inside a frame,
+    /// the frame is too long or if it is invalid JSON.
+    pub async fn next_async(&mut self) -> Result<Option<BorrowedValue<'_>>> {
+        let mut prefix = [0; 8];
+        let width = self.prefix.width();
+        let mut read = 0;
+        while read < width {
+            match self.reader.read(&mut prefix[read..width]).await {
+                Ok(0) if read == 0 => return Ok(None),
+                Ok(0) => return Err(prefix_eof()),
+                Ok(n) => read += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(Error::generic(ErrorType::IO(e))),
+            }
+        }
+        stry!(self.start_frame(&prefix[..width]));
+        if let Err(e) = self.reader.read_exact(&mut self.frame).await {
+            return Err(Error::generic(ErrorType::IO(e)));
+        }
+        self.parse_frame()
+    }
+}
+
+/// Writes JSON documents to `writer` as length prefixed frames, the twin
+/// of `FrameReader`. Every frame is encoded into a buffer that is reused
+/// for all of them and written with its prefix in one go.
+///
+/// With the `tokio` feature both also work over tokio's `AsyncRead` and
+/// `AsyncWrite`, with the `_async` variants of their methods.
+#[derive(Debug)]
+pub struct FrameWriter<W> {
+    writer: W,
+    prefix: LengthPrefix,
+    buffer: Vec<u8>,
+}
+
+impl<W> FrameWriter<W> {
+    /// Writes frames with a `LengthPrefix::U32Be` prefix to `writer`
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            prefix: LengthPrefix::default(),
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Sets the prefix the frames start with
+    #[must_use]
+    pub fn prefix(mut self, prefix: LengthPrefix) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    /// The writer
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// The writer, the frames written so far are not flushed
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Encodes `value` with its prefix into the buffer
+    fn encode_value<V>(&mut self, value: &V) -> Result<()>
+    where
+        V: Writable + ?Sized,
+    {
+        self.start_frame();
+        if let Err(e) = value.write(&mut self.buffer) {
+            return Err(Error::generic(ErrorType::IO(e)));
+        }
+        self.end_frame()
+    }
+
+    /// Serializes `value` with its prefix into the buffer
+    #[cfg(feature = "serde_impl")]
+    fn encode_serialize<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: serde_ext::Serialize + ?Sized,
+    {
+        self.start_frame();
+        stry!(crate::to_writer(&mut self.buffer, value));
+        self.end_frame()
+    }
+
+    fn start_frame(&mut self) {
+        self.buffer.clear();
+        self.buffer.resize(self.prefix.width(), 0);
+    }
+
+    fn end_frame(&mut self) -> Result<()> {
+        let (prefix, frame) = self.buffer.split_at_mut(self.prefix.width());
+        match self.prefix.encode(frame.len(), prefix) {
+            Some(()) => Ok(()),
+            None => Err(io_error(
+                io::ErrorKind::InvalidInput,
+                "the frame is too long for the length prefix",
+            )),
+        }
+    }
+}
+
+impl<W: Write> FrameWriter<W> {
+    /// Writes `value` as one frame
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the value is too long for the prefix or if
+    /// writing fails.
+    pub fn write_value<V>(&mut self, value: &V) -> Result<()>
+    where
+        V: Writable + ?Sized,
+    {
+        stry!(self.encode_value(value));
+        self.write_frame()
+    }
+
+    /// Serializes `value` with serde as one frame
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `value` can't be serialized, is too long for
+    /// the prefix or if writing fails.
+    #[cfg(feature = "serde_impl")]
+    pub fn serialize<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: serde_ext::Serialize + ?Sized,
+    {
+        stry!(self.encode_serialize(value));
+        self.write_frame()
+    }
+
+    fn write_frame(&mut self) -> Result<()> {
+        self.writer
+            .write_all(&self.buffer)
+            .map_err(|e| Error::generic(ErrorType::IO(e)))
+    }
+
+    /// Flushes the writer
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if flushing fails.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<W: AsyncWrite + Unpin> FrameWriter<W> {
+    /// Writes `value` as one frame to an async writer, like `write_value`
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the value is too long for the prefix or if
+    /// writing fails.
+    pub async fn write_value_async<V>(&mut self, value: &V) -> Result<()>
+    where
+        V: Writable + ?Sized,
+    {
+        stry!(self.encode_value(value));
+        self.write_frame_async().await
+    }
+
+    /// Serializes `value` with serde as one frame to an async writer,
+    /// like `serialize`
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `value` can't be serialized, is too long for
+    /// the prefix or if writing fails.
+    #[cfg(feature = "serde_impl")]
+    pub async fn serialize_async<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: serde_ext::Serialize + ?Sized,
+    {
+        stry!(self.encode_serialize(value));
+        self.write_frame_async().await
+    }
+
+    async fn write_frame_async(&mut self) -> Result<()> {
+        self.writer
+            .write_all(&self.buffer)
+            .await
+            .map_err(|e| Error::generic(ErrorType::IO(e)))
+    }
+
+    /// Flushes the async writer
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if flushing fails.
+    pub async fn flush_async(&mut self) -> io::Result<()> {
+        self.writer.flush().await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn prefixes() {
+        for prefix in &[
+            LengthPrefix::U16Be,
+            LengthPrefix::U16Le,
+            LengthPrefix::U32Be,
+            LengthPrefix::U32Le,
+            LengthPrefix::U64Be,
+            LengthPrefix::U64Le,
+        ] {
+            let mut writer = FrameWriter::new(Vec::new()).prefix(*prefix);
+            writer
+                .write_value(&json!([1, "a"]))
+                .expect("failed to write");
+            writer.serialize(&"é").expect("failed to write");
+            writer.write_value(&json!({})).expect("failed to write");
+            let stream = writer.into_inner();
+            let width = prefix.width();
+            assert_eq!(stream.len(), 3 * width + 7 + 4 + 2);
+            assert_eq!(prefix.decode(&stream[..width]), 7);
+            let mut reader = FrameReader::new(&stream[..]).prefix(*prefix);
+            assert_eq!(
+                reader.next().expect("failed to read"),
+                Some(json!([1, "a"]).into())
+            );
+            assert_eq!(reader.next().expect("failed to read"), Some("é".into()));
+            assert_eq!(
+                reader.next().expect("failed to read"),
+                Some(json!({}).into())
+            );
+            assert_eq!(reader.next().expect("failed to read"), None);
+        }
+        assert_eq!(LengthPrefix::U16Le.decode(&[1, 2]), 0x201);
+        assert_eq!(LengthPrefix::U32Be.decode(&[0, 0, 1, 2]), 0x102);
+        let mut prefix = [0; 2];
+        assert_eq!(LengthPrefix::U16Be.encode(0x1_0000, &mut prefix), None);
+        assert_eq!(LengthPrefix::U16Be.encode(0xffff, &mut prefix), Some(()));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn async_frames() {
+        let runtime = tokio_crate::runtime::Builder::new_current_thread()
+            .build()
+            .expect("failed to start a runtime");
+        runtime.block_on(async {
+            let (client, server) = tokio_crate::io::duplex(16);
+            let writing = tokio_crate::spawn(async move {
+                let mut writer = FrameWriter::new(client).prefix(LengthPrefix::U16Le);
+                for i in 0..10 {
+                    let v = json!({"i": i, "pad": "x".repeat(i * 10)});
+                    writer.write_value_async(&v).await.expect("failed to write");
+                }
+                writer.serialize_async(&[1]).await.expect("failed to write");
+                writer.flush_async().await.expect("failed to flush");
+            });
+            let mut reader = FrameReader::new(server).prefix(LengthPrefix::U16Le);
+            let mut n = 0;
+            while let Some(v) = reader.next_async().await.expect("failed to read") {
+                if n < 10 {
+                    assert_eq!(v["i"], n);
+                } else {
+                    assert_eq!(v, json!([1]));
+                }
+                n += 1;
+            }
+            assert_eq!(n, 11);
+            writing.await.expect("failed to write");
+        });
+    }
+
+    fn read_err(input: &[u8], max_len: usize) -> Error {
+        let mut reader = FrameReader::new(input).max_frame_len(max_len);
+        reader.next().expect_err("read a frame")
+    }
+
+    #[test]
+    fn errors() {
+        let e = read_err(&[0, 0], usize::MAX);
+        assert!(matches!(e.error(), ErrorType::IO(_)));
+        let e = read_err(&[0, 0, 0, 3, b'[', b'1'], usize::MAX);
+        assert!(matches!(e.error(), ErrorType::IO(_)));
+        let e = read_err(&[0, 0, 0, 2, b'[', b']'], 1);
+        assert!(matches!(e.error(), ErrorType::IO(_)));
+        let e = read_err(&[0, 0, 0, 2, b'[', b','], usize::MAX);
+        assert_eq!(e.index(), 1);
+        let e = FrameReader::new(&[1, 0, 0, 1, b'1'][..])
+            .next()
+            .expect_err("read a frame");
+        assert!(matches!(e.error(), ErrorType::IO(_)));
+
+        let long = "x".repeat(300);
+        let mut writer = FrameWriter::new(Vec::new()).prefix(LengthPrefix::U16Be);
+        writer
+            .write_value(&json!(long.as_str()))
+            .expect("failed to write");
+        let mut writer = FrameWriter::new(Vec::new()).prefix(LengthPrefix::U16Le);
+        let long = "x".repeat(70_000);
+        assert!(writer.write_value(&json!(long.as_str())).is_err());
+        assert!(writer.get_ref().is_empty());
+    }
+}
@@ -1,9 +1,11 @@
 use crate::cow::Cow;
 use crate::prelude::*;
 use crate::BorrowedValue as Value;
+use crate::OwnedValue;
+use alloc::string::String;
+use core::fmt;
+use core::hash::{BuildHasher, Hash, Hasher};
 use halfbrown::RawEntryMut;
-use std::fmt;
-use std::hash::{BuildHasher, Hash, Hasher};
 
 /// Well known key that can be looked up in a `Value` faster.
 /// It achives this by memorizing the hash.
@@ -28,6 +30,8 @@ impl fmt::Display for Error {
         }
     }
 }
+
+#[cfg(feature = "std")]
 impl std::error::Error for Error {}
 
 impl<'key, S> From<S> for KnownKey<'key>
@@ -119,6 +123,66 @@ impl<'key> KnownKey<'key> {
         })
     }
 
+    /// Looks up this key in an `OwnedValue`, returns None if the
+    /// key wasn't present or `target` isn't an object
+    ///
+    /// ```rust
+    /// use simd_json::*;
+    /// let object: OwnedValue = json!({
+    ///   "answer": 42,
+    ///   "key": 7
+    /// });
+    /// let known_key = KnownKey::from("answer");
+    /// assert_eq!(known_key.lookup_owned(&object).unwrap(), &42);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn lookup_owned<'borrow>(
+        &self,
+        target: &'borrow OwnedValue,
+    ) -> Option<&'borrow OwnedValue> {
+        target
+            .as_object()
+            .and_then(|m| {
+                m.raw_entry()
+                    .from_key_hashed_nocheck(self.hash, self.key.as_ref())
+            })
+            .map(|kv| kv.1)
+    }
+
+    /// Looks up this key in an `OwnedValue`, returns None if the
+    /// key wasn't present or `target` isn't an object
+    ///
+    /// ```rust
+    /// use simd_json::*;
+    /// let mut object: OwnedValue = json!({
+    ///   "answer": 23,
+    ///   "key": 7
+    /// });
+    /// let known_key = KnownKey::from("answer");
+    ///
+    /// if let Some(answer) = known_key.lookup_owned_mut(&mut object) {
+    ///   *answer = OwnedValue::from(42);
+    /// }
+    ///
+    /// assert_eq!(object["answer"], 42);
+    /// ```
+    #[inline]
+    pub fn lookup_owned_mut<'borrow>(
+        &self,
+        target: &'borrow mut OwnedValue,
+    ) -> Option<&'borrow mut OwnedValue> {
+        target.as_object_mut().and_then(|m| {
+            match m
+                .raw_entry_mut()
+                .from_key_hashed_nocheck(self.hash, self.key.as_ref())
+            {
+                RawEntryMut::Occupied(e) => Some(e.into_mut()),
+                RawEntryMut::Vacant(_e) => None,
+            }
+        })
+    }
+
     /// Looks up this key in a `Value`, inserts `with` when the key
     ///  when wasn't present returns None if the `target` isn't an object
     ///
@@ -172,6 +236,54 @@ impl<'key> KnownKey<'key> {
             .ok_or(Error::NotAnObject(ValueType::Null))
     }
 
+    /// Looks up this key in an `OwnedValue`, inserts `with` when the key
+    /// wasn't present, errors if `target` isn't an object
+    ///
+    /// ```rust
+    /// use simd_json::*;
+    /// let mut object: OwnedValue = json!({
+    ///   "answer": 23,
+    ///   "key": 7
+    /// });
+    /// let known_key = KnownKey::from("answer");
+    ///
+    /// if let Ok(answer) = known_key.lookup_or_insert_owned_mut(&mut object, || 17.into()) {
+    ///   assert_eq!(*answer, 23);
+    ///   *answer = OwnedValue::from(42);
+    /// }
+    ///
+    /// assert_eq!(object["answer"], 42);
+    ///
+    /// let known_key2 = KnownKey::from("also the answer");
+    /// if let Ok(answer) = known_key2.lookup_or_insert_owned_mut(&mut object, || 8.into()) {
+    ///   assert_eq!(*answer, 8);
+    /// }
+    ///
+    /// assert_eq!(object["also the answer"], 8);
+    /// ```
+    #[inline]
+    pub fn lookup_or_insert_owned_mut<'borrow, F>(
+        &self,
+        target: &'borrow mut OwnedValue,
+        with: F,
+    ) -> Result<&'borrow mut OwnedValue, Error>
+    where
+        F: FnOnce() -> OwnedValue,
+    {
+        if !target.is_object() {
+            return Err(Error::NotAnObject(target.value_type()));
+        }
+        target
+            .as_object_mut()
+            .map(|m| {
+                m.raw_entry_mut()
+                    .from_key_hashed_nocheck(self.hash, self.key.as_ref())
+                    .or_insert_with(|| (String::from(&*self.key), with()))
+                    .1
+            })
+            .ok_or(Error::NotAnObject(ValueType::Null))
+    }
+
     /// Inserts a value key into  `Value`, returns None if the
     /// key wasn't present otherwise Some(`old value`).
     /// Errors if `target` isn't an object
@@ -316,6 +428,45 @@ mod tests {
         assert_eq!(v["cake"], 3);
     }
 
+    #[test]
+    fn known_key_owned() {
+        let mut v = OwnedValue::object();
+        v.insert("key", 1).unwrap();
+        let key1 = KnownKey::from("key");
+        let key2 = KnownKey::from("cake");
+
+        assert!(key1.lookup_owned(&OwnedValue::null()).is_none());
+        assert!(key1.lookup_owned_mut(&mut OwnedValue::null()).is_none());
+        assert_eq!(key1.lookup_owned(&v), Some(&OwnedValue::from(1)));
+        assert!(key2.lookup_owned(&v).is_none());
+        *key1.lookup_owned_mut(&mut v).unwrap() = 2.into();
+        assert!(key2.lookup_owned_mut(&mut v).is_none());
+        assert_eq!(v["key"], 2);
+    }
+
+    #[test]
+    fn lookup_or_insert_owned_mut() {
+        let mut v = OwnedValue::object();
+        v.insert("key", 1).unwrap();
+        let key1 = KnownKey::from("key");
+        let key2 = KnownKey::from("cake");
+
+        let mut v1 = OwnedValue::null();
+        assert!(key1
+            .lookup_or_insert_owned_mut(&mut v1, || 2.into())
+            .is_err());
+
+        let r1 = key1
+            .lookup_or_insert_owned_mut(&mut v, || 2.into())
+            .unwrap();
+        assert_eq!(r1.as_u8(), Some(1));
+        let r2 = key2
+            .lookup_or_insert_owned_mut(&mut v, || 3.into())
+            .unwrap();
+        assert_eq!(r2.as_u8(), Some(3));
+        assert_eq!(v["cake"], 3);
+    }
+
     #[test]
     fn known_key_get_key() {
         let key1 = KnownKey::from("snot");
@@ -15,6 +15,7 @@
     )
 )]
 #![cfg_attr(feature = "hints", feature(core_intrinsics))]
+#![cfg_attr(feature = "portable", feature(portable_simd))]
 #![forbid(warnings)]
 #![warn(unused_extern_crates)]
 #![deny(
@@ -26,6 +27,8 @@
 // We might want to revisit inline_always
 #![allow(clippy::module_name_repetitions, clippy::inline_always)]
 #![deny(missing_docs)]
+#![cfg_attr(all(feature = "safe", not(test)), deny(unsafe_code))]
+#![cfg_attr(all(not(feature = "std"), not(test)), no_std)]
 
 //! simd-json is a rust port of the simdjson c++ library. It follows
 //! most of the design closely with a few exceptions to make it better
@@ -94,7 +97,175 @@
 //! bucket. fxhash on the other hand allows for repeatable hashing
 //! results, that allows memorizing hashes for well know keys and saving
 //! time on lookups. In workloads that are heavy at accessing some well
-//! known keys this can be a performance advantage.
+//! known keys this can be a performance advantage. `KnownKey` does the
+//! memorizing, for both the borrowed and the owned DOM.
+//!
+//! ### `ahash`
+//!
+//! Adds `AHashMaps`, a backend for `GenericOwnedValue` and
+//! `GenericBorrowedValue` that stores objects in maps hashed with a
+//! randomly keyed [ahash](https://docs.rs/ahash), whether `known-key` is
+//! on or not. `SipHashMaps` does the same with std's `SipHash` and
+//! `HashedMaps` takes any other hasher.
+//!
+//! ### `arbitrary`
+//!
+//! Implements [arbitrary](https://docs.rs/arbitrary)'s `Arbitrary` for
+//! `OwnedValue` and `BorrowedValue` so code that consumes values can be
+//! fuzzed with `cargo fuzz`. The generated values look like parsed
+//! ones: integers are only `I64` when negative and floats are finite.
+//! `StaticNode` comes from `value-trait` so it can't get an
+//! implementation here, generate the values instead.
+//!
+//! ### `arrow`
+//!
+//! Adds `to_record_batch` and `to_record_batch_with_schema` which turn a
+//! document that is an array of flat objects, like an export of records,
+//! into an [arrow](https://docs.rs/arrow) `RecordBatch` with a column per
+//! key. The columns are filled straight from the tape, the schema is
+//! either inferred from the values or supplied.
+//!
+//! ### `base64`
+//!
+//! Adds `as_base64` and `as_hex` to `OwnedValue` and `BorrowedValue`,
+//! which decode string values holding binary data into bytes. Base64 is
+//! decoded with the [base64](https://docs.rs/base64) crate and accepts
+//! the standard and the URL safe alphabet, padded or not.
+//!
+//! ### `bytes`
+//!
+//! Adds a `Bytes` variant to `OwnedValue` and `BorrowedValue` for binary
+//! data coming from or going to binary formats like CBOR, its
+//! `value_type` is `ValueType::Custom("bytes")`. Serde serializers for
+//! binary formats get the bytes as they are, JSON gets them as a base64
+//! string. Implies `base64`.
+//!
+//! ### `bytes-buffer`
+//!
+//! Adds `BytesDoc`, a parsed document that owns the `bytes::Bytes` it
+//! was parsed from, like `OwnedDoc` does with a `Vec`. Cloning it shares
+//! the buffer and `BytesDoc::bytes_of` hands out strings of the value as
+//! `Bytes` without copying them, so documents and their strings can be
+//! sent to other tasks without `into_static`.
+//!
+//! ### `bumpalo`
+//!
+//! Adds `ArenaValue`, a DOM that allocates its arrays and objects from a
+//! caller provided [bumpalo](https://docs.rs/bumpalo) arena. Strings are
+//! referenced from the input like in the borrowed DOM, so building a
+//! document is a handful of bump allocations and dropping it is free.
+//! Object lookups scan the entries instead of hashing, arrays made up of
+//! only numbers are stored packed.
+//!
+//! ### `cbor`
+//!
+//! Adds `to_cbor_vec` and `from_cbor_slice` to write `OwnedValue` and
+//! `BorrowedValue` as [CBOR](https://www.rfc-editor.org/rfc/rfc8949) and
+//! to read CBOR into a `BorrowedValue` that borrows its strings from the
+//! input, for storing or sending parsed documents in a compact binary
+//! form. Byte strings become `Bytes` values, so this implies `bytes`.
+//!
+//! ### `color`
+//!
+//! Adds `Colors` and `EncodeOptions::colors`, so `encode_with` and
+//! `write_with` highlight keys, strings, numbers, booleans and `null`
+//! with ANSI escape codes. Combined with `EncodeOptions::pretty` this
+//! gives `jq` like output for command line tools.
+//!
+//! ### `digest`
+//!
+//! Adds `canonical_hash` to `OwnedValue` and `BorrowedValue`, which
+//! computes a digest with any [RustCrypto](https://docs.rs/digest) hash
+//! over a canonical form of the value. Documents with the same content
+//! get the same digest regardless of the order of their keys, their
+//! whitespace or how their numbers were written.
+//!
+//! ### `fxhash`
+//!
+//! Adds `FxHashMaps`, a backend storing objects in maps hashed with
+//! [fxhash](https://docs.rs/fxhash). It is faster for lookup heavy
+//! workloads but not keyed, so only use it for trusted documents.
+//!
+//! ### `msgpack`
+//!
+//! Adds `to_msgpack_vec` and `from_msgpack_slice` to write `OwnedValue`
+//! and `BorrowedValue` as [MessagePack](https://msgpack.org) and to read
+//! it into a `BorrowedValue` that borrows its strings from the input,
+//! without going through serde. `bin` items become `Bytes` values, so
+//! this implies `bytes`.
+//!
+//! ### `proptest`
+//!
+//! Adds the `proptest` module with [proptest](https://docs.rs/proptest)
+//! strategies that generate `OwnedValue`s and `BorrowedValue`s, so code
+//! that consumes values can be property tested. How deep and large the
+//! documents get and whether they contain floats or `NaN` can be
+//! configured.
+//!
+//! ### `buffer-pool`
+//!
+//! Keeps the scratch buffers the parser needs (the padded copy of the
+//! input, the string buffer and the structural indexes) in a thread
+//! local pool so the convenience functions like `to_owned_value` or
+//! `serde::from_slice` stop allocating them for every document. The
+//! pool grows to the largest document parsed on a thread,
+//! `release_thread_buffers` frees it again. The tape is part of the
+//! result and still allocated per call.
+//!
+//! ### `rayon`
+//!
+//! Adds `parse_lines_parallel` which parses newline delimited JSON on
+//! the [rayon](https://docs.rs/rayon) thread pool, reusing the scratch
+//! buffers of the parser within each worker, and `parse_array_parallel`
+//! which does the same for the elements of a document that is a single
+//! large array.
+//!
+//! ### `safe`
+//!
+//! Replaces the SIMD stages with a scalar implementation that doesn't use
+//! any `unsafe` code, the only exception being `serde::from_str` which
+//! has to turn a `&mut str` into bytes. This is a lot slower but can be
+//! checked with Miri, works on every target and fits policies that don't
+//! allow `unsafe` in dependencies. Since it needs no SIMD it doesn't
+//! require a SIMD capable `target-cpu` either.
+//!
+//! ### `schema`
+//!
+//! Adds `Schema`, which compiles a [JSON Schema](https://json-schema.org)
+//! (the keywords of draft 2020-12 that constrain values) and validates
+//! owned and borrowed values or tapes against it. Validating the tape
+//! rejects invalid documents without building a DOM for them. `JtdSchema`
+//! does the same for the simpler [JSON Type Definitions](https://jsontypedef.com)
+//! of RFC 8927. `infer_schema` goes the other way and writes either kind
+//! of schema for the structure of sample values.
+//!
+//! ### `tokio`
+//!
+//! Adds `_async` variants of the methods of `FrameReader` and
+//! `FrameWriter` that read and write length prefixed frames over tokio's
+//! `AsyncRead` and `AsyncWrite`.
+//!
+//! ### `tracing`
+//!
+//! Emits a `tracing` debug span for every parse with the length of the
+//! input, and an event when it is done with the number of structural
+//! characters, the size of the tape and the time stage 1 and stage 2
+//! took, or with the error and where it is for invalid input. The target
+//! is `simd_json`.
+//!
+//! ### `unicode`
+//!
+//! Adds `ParseOptions::normalization`, which normalizes the keys, the
+//! string values or both of the values the DOM entry points build to
+//! unicode NFC, so a key written with a combining accent is found by a
+//! lookup with the composed character and the other way around.
+//!
+//! ### `yaml`
+//!
+//! Adds `to_yaml_string`, which writes `OwnedValue` and `BorrowedValue` as
+//! a block style YAML document, quoting only the strings that would
+//! otherwise be read back as something else. Reading YAML isn't
+//! supported.
 //!
 //! ## Usage
 //!
@@ -132,6 +303,9 @@
 //! let v: Value = simd_json::serde::from_slice(&mut d).unwrap();
 //! ```
 
+// we use `alloc` directly so the same paths work with and without `std`
+extern crate alloc;
+
 #[cfg(feature = "serde_impl")]
 extern crate serde as serde_ext;
 
@@ -141,102 +315,257 @@ pub mod serde;
 
 #[cfg(feature = "serde_impl")]
 pub use crate::serde::{
-    from_reader, from_slice, from_str, to_string, to_string_pretty, to_vec, to_vec_pretty,
-    to_writer, to_writer_pretty,
+    from_file, from_reader, from_slice, from_slice_with_options, from_str, to_file, to_string,
+    to_string_pretty, to_vec, to_vec_pretty, to_writer, to_writer_pretty,
 };
 
 /// Default trait imports;
 pub mod prelude;
 
+#[cfg(feature = "arrow")]
+mod arrow;
+mod buffers;
+#[cfg(feature = "cbor")]
+mod cbor;
 mod charutils;
+#[cfg(feature = "std")]
+mod csv;
 #[macro_use]
 mod macros;
 mod error;
+mod escape;
+#[cfg(feature = "std")]
+mod frame;
+#[cfg(feature = "std")]
+mod lines;
+#[cfg(feature = "msgpack")]
+mod msgpack;
+mod number;
 mod numberparse;
+mod options;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(any(test, feature = "proptest"))]
+pub mod proptest;
+mod recover;
+mod stats;
 mod stringparse;
+mod structural;
+mod trace;
+#[cfg(not(feature = "safe"))]
 mod utf8check;
+#[cfg(feature = "yaml")]
+mod yaml;
 
 /// Reexport of Cow
 pub mod cow;
 
-#[cfg(target_feature = "avx2")]
+/// Finding values in documents without parsing them
+pub mod scan;
+
+/// Auditing documents without building a DOM
+pub mod analyze;
+
+#[cfg(all(target_feature = "avx2", not(feature = "safe")))]
 mod avx2;
-#[cfg(target_feature = "avx2")]
+#[cfg(all(target_feature = "avx2", not(feature = "safe")))]
 pub use crate::avx2::deser::*;
-#[cfg(target_feature = "avx2")]
+#[cfg(all(target_feature = "avx2", not(feature = "safe")))]
 use crate::avx2::stage1::{SimdInput, SIMDINPUT_LENGTH, SIMDJSON_PADDING};
+// AVX-512 stage 1, selected at runtime on top of the AVX2 backend, the
+// CPU detection needs `std`
+#[cfg(all(target_feature = "avx2", feature = "std", not(feature = "safe")))]
+mod avx512;
 
-#[cfg(all(target_feature = "sse4.2", not(target_feature = "avx2")))]
+#[cfg(all(
+    target_feature = "sse4.2",
+    not(target_feature = "avx2"),
+    not(feature = "safe")
+))]
 mod sse42;
-#[cfg(all(target_feature = "sse4.2", not(target_feature = "avx2")))]
+#[cfg(all(
+    target_feature = "sse4.2",
+    not(target_feature = "avx2"),
+    not(feature = "safe")
+))]
 pub use crate::sse42::deser::*;
-#[cfg(all(target_feature = "sse4.2", not(target_feature = "avx2")))]
+#[cfg(all(
+    target_feature = "sse4.2",
+    not(target_feature = "avx2"),
+    not(feature = "safe")
+))]
 use crate::sse42::stage1::{SimdInput, SIMDINPUT_LENGTH, SIMDJSON_PADDING};
 
-#[cfg(all(target_feature = "neon", feature = "neon"))]
+#[cfg(all(target_feature = "neon", feature = "neon", not(feature = "safe")))]
 mod neon;
-#[cfg(all(target_feature = "neon", feature = "neon"))]
+#[cfg(all(target_feature = "neon", feature = "neon", not(feature = "safe")))]
 pub use crate::neon::deser::*;
-#[cfg(all(target_feature = "neon", feature = "neon"))]
+#[cfg(all(target_feature = "neon", feature = "neon", not(feature = "safe")))]
 use crate::neon::stage1::{SimdInput, SIMDINPUT_LENGTH, SIMDJSON_PADDING};
 
+#[cfg(all(
+    target_arch = "wasm32",
+    target_feature = "simd128",
+    not(feature = "safe")
+))]
+mod simd128;
+#[cfg(all(
+    target_arch = "wasm32",
+    target_feature = "simd128",
+    not(feature = "safe")
+))]
+pub use crate::simd128::deser::*;
+#[cfg(all(
+    target_arch = "wasm32",
+    target_feature = "simd128",
+    not(feature = "safe")
+))]
+use crate::simd128::stage1::{SimdInput, SIMDINPUT_LENGTH, SIMDJSON_PADDING};
+
+// The std::simd backend, used on targets without a hand written one
+#[cfg(all(feature = "portable", not(feature = "safe")))]
+mod portable;
+#[cfg(all(
+    feature = "portable",
+    not(any(
+        target_feature = "sse4.2",
+        target_feature = "avx2",
+        target_feature = "neon",
+        target_feature = "simd128"
+    )),
+    not(feature = "safe")
+))]
+pub use crate::portable::deser::*;
+#[cfg(all(
+    feature = "portable",
+    not(any(
+        target_feature = "sse4.2",
+        target_feature = "avx2",
+        target_feature = "neon",
+        target_feature = "simd128"
+    )),
+    not(feature = "safe")
+))]
+use crate::portable::stage1::{SimdInput, SIMDINPUT_LENGTH, SIMDJSON_PADDING};
+
 // We import this as generics
-#[cfg(all(not(any(
-    target_feature = "sse4.2",
-    target_feature = "avx2",
-    target_feature = "neon"
-))))]
+#[cfg(all(
+    not(any(
+        target_feature = "sse4.2",
+        target_feature = "avx2",
+        target_feature = "neon",
+        target_feature = "simd128"
+    )),
+    not(feature = "portable"),
+    not(feature = "safe")
+))]
 mod sse42;
-#[cfg(all(not(any(
-    target_feature = "sse4.2",
-    target_feature = "avx2",
-    target_feature = "neon"
-))))]
-#[cfg(all(not(any(
-    target_feature = "sse4.2",
-    target_feature = "avx2",
-    target_feature = "neon"
-))))]
+#[cfg(all(
+    not(any(
+        target_feature = "sse4.2",
+        target_feature = "avx2",
+        target_feature = "neon",
+        target_feature = "simd128"
+    )),
+    not(feature = "portable"),
+    not(feature = "safe")
+))]
 pub use crate::sse42::deser::*;
-#[cfg(all(not(any(
-    target_feature = "sse4.2",
-    target_feature = "avx2",
-    target_feature = "neon"
-))))]
+#[cfg(all(
+    not(any(
+        target_feature = "sse4.2",
+        target_feature = "avx2",
+        target_feature = "neon",
+        target_feature = "simd128"
+    )),
+    not(feature = "portable"),
+    not(feature = "safe")
+))]
 use crate::sse42::stage1::{SimdInput, SIMDINPUT_LENGTH, SIMDJSON_PADDING};
 
 #[cfg(all(
     not(feature = "allow-non-simd"),
+    not(feature = "portable"),
+    not(feature = "safe"),
     not(any(
         target_feature = "sse4.2",
         target_feature = "avx2",
-        target_feature = "neon"
+        target_feature = "neon",
+        target_feature = "simd128"
     ))
 ))]
 fn please_compile_with_a_simd_compatible_cpu_setting_read_the_simdjonsrs_readme() -> ! {}
 
+// The scalar backend without any unsafe code, it works on every target
+#[cfg(feature = "safe")]
+mod safe;
+#[cfg(feature = "safe")]
+use crate::safe::stage1::SIMDJSON_PADDING;
+
+#[cfg(not(feature = "safe"))]
 use crate::utf8check::ProcessedUtfBytes;
 
 mod stage2;
 /// simd-json JSON-DOM value
 pub mod value;
 
-use std::mem;
-use std::str;
+use crate::buffers::Buffers;
+use crate::stats::Stopwatch;
+use crate::trace::ParseTrace;
+use alloc::vec::Vec;
+#[cfg(not(feature = "safe"))]
+use core::mem;
+#[cfg(feature = "serde_impl")]
+use core::str;
 pub use value_trait::StaticNode;
 
 pub use crate::error::{Error, ErrorType};
+#[cfg(feature = "color")]
+pub use crate::options::Colors;
+#[cfg(feature = "unicode")]
+pub use crate::options::Normalization;
+pub use crate::options::{
+    CsvOptions, EncodeOptions, Escape, FloatFormat, IntegerOverflow, InvalidEscapes, NegativeZero,
+    NonFiniteFloats, ParseOptions,
+};
 pub use crate::value::*;
 pub use value_trait::ValueType;
 
 /// simd-json Result type
-pub type Result<T> = std::result::Result<T, Error>;
+pub type Result<T> = core::result::Result<T, Error>;
 
 #[cfg(feature = "known-key")]
 mod known_key;
 #[cfg(feature = "known-key")]
 pub use known_key::{Error as KnownKeyError, KnownKey};
 
+#[cfg(feature = "arrow")]
+pub use crate::arrow::{to_record_batch, to_record_batch_with_schema};
+#[cfg(feature = "buffer-pool")]
+pub use crate::buffers::release_thread_buffers;
+#[cfg(feature = "cbor")]
+pub use crate::cbor::{from_cbor_slice, to_cbor_vec};
+#[cfg(feature = "std")]
+pub use crate::csv::to_csv;
+pub use crate::escape::unescape_str;
+#[cfg(feature = "std")]
+pub use crate::escape::{escape_str, write_escaped};
+#[cfg(feature = "std")]
+pub use crate::frame::{FrameReader, FrameWriter, LengthPrefix};
+#[cfg(feature = "std")]
+pub use crate::lines::{JsonLines, NdjsonWriter};
+#[cfg(feature = "msgpack")]
+pub use crate::msgpack::{from_msgpack_slice, to_msgpack_vec};
+pub use crate::number::parse_number;
+#[cfg(feature = "std")]
+pub use crate::number::{format_number, write_number};
+#[cfg(feature = "rayon")]
+pub use crate::parallel::{parse_array_parallel, parse_lines_parallel};
+#[cfg(feature = "yaml")]
+pub use crate::yaml::to_yaml_string;
+
+pub use crate::stats::ParseStats;
+pub use crate::structural::{find_structurals, StructuralIndex, StructuralIter};
 pub use crate::tape::{Node, Tape};
 
 /// Creates a tape from the input for later consumption
@@ -247,12 +576,57 @@ pub fn to_tape<'input>(s: &'input mut [u8]) -> Result<Vec<Node<'input>>> {
     Deserializer::from_slice(s).map(|de| de.tape)
 }
 
+/// Creates a tape from the input for later consumption using the
+/// given parse `options`
+/// # Errors
+///
+/// Will return `Err` if `s` is invalid JSON.
+pub fn to_tape_with_options<'input>(
+    s: &'input mut [u8],
+    options: ParseOptions,
+) -> Result<Vec<Node<'input>>> {
+    Deserializer::from_slice_with_options(s, options).map(|de| de.tape)
+}
+
+/// Checks that `input` is UTF-8 with the same vectorized validation the
+/// parser runs over JSON, for buffers that aren't JSON.
+///
+/// ```rust
+/// assert_eq!(simd_json::validate_utf8(b"snow \xe2\x98\x83"), Ok("snow ☃"));
+/// let err = simd_json::validate_utf8(b"snow \xe2\x98").unwrap_err();
+/// assert_eq!(err.index(), 5);
+/// ```
+///
+/// # Errors
+///
+/// Will return `Err` if `input` isn't UTF-8, with the index of the first
+/// byte of the invalid sequence.
+pub fn validate_utf8(input: &[u8]) -> Result<&str> {
+    #[cfg(not(feature = "safe"))]
+    {
+        if Deserializer::is_valid_utf8(input) {
+            // the input was just validated
+            return Ok(unsafe { core::str::from_utf8_unchecked(input) });
+        }
+    }
+    // the vectorized check only tells whether there is an error, this
+    // finds where it is
+    core::str::from_utf8(input).map_err(|e| {
+        let idx = e.valid_up_to();
+        Error::new(idx, char::from(input[idx]), ErrorType::InvalidUTF8)
+    })
+}
+
+#[cfg(not(feature = "safe"))]
 pub(crate) struct Utf8CheckingState<T> {
     has_error: T,
     previous: ProcessedUtfBytes<T>,
 }
 
+#[cfg(not(feature = "safe"))]
 pub(crate) trait Stage1Parse<T> {
+    fn new(ptr: &[u8]) -> Self;
+
     fn new_utf8_checking_state() -> Utf8CheckingState<T>;
 
     fn compute_quote_mask(quote_bits: u64) -> u64;
@@ -397,6 +771,14 @@ pub(crate) trait Stage1Parse<T> {
     fn zero() -> T;
 }
 
+/// The UTF-8 byte order mark some (mostly Windows) tools put in front
+/// of their output
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Why a parse failed, along with the input if it failed because the
+/// input isn't UTF-8, see `Deserializer::from_slice_or_input`
+type Failure<'de> = (Error, Option<&'de mut [u8]>);
+
 pub(crate) struct Deserializer<'de> {
     // Note: we use the 2nd part as both index and lenght since only one is ever
     // used (array / object use len) everything else uses idx
@@ -420,23 +802,129 @@ impl<'de> Deserializer<'de> {
     // `serde_json::from_str(...)` while advanced use cases that require a
     // deserializer can make one with `serde_json::Deserializer::from_str(...)`.
     pub fn from_slice(input: &'de mut [u8]) -> Result<Self> {
-        let len = input.len();
-
-        let mut string_buffer: Vec<u8> = Vec::with_capacity(len + SIMDJSON_PADDING);
-        unsafe {
-            string_buffer.set_len(len + SIMDJSON_PADDING);
-        };
-
-        Deserializer::from_slice_with_buffer(input, &mut string_buffer)
+        Deserializer::from_slice_with_options(input, ParseOptions::default())
+    }
+
+    pub fn from_slice_with_options(input: &'de mut [u8], options: ParseOptions) -> Result<Self> {
+        Deserializer::from_slice_with_string_buffer(input, options, None, None)
+    }
+
+    /// Like `from_slice_with_options` but also returns the span of every
+    /// structural element, indexed the same way as the structural indexes.
+    pub(crate) fn from_slice_with_spans(
+        input: &'de mut [u8],
+        options: ParseOptions,
+    ) -> Result<(Self, Vec<Span>)> {
+        let mut spans = Vec::new();
+        let de =
+            Deserializer::from_slice_with_string_buffer(input, options, Some(&mut spans), None)?;
+        Ok((de, spans))
+    }
+
+    /// Like `from_slice_with_options` but also records `stats`
+    pub(crate) fn from_slice_with_stats(
+        input: &'de mut [u8],
+        options: ParseOptions,
+        stats: &mut ParseStats,
+    ) -> Result<Self> {
+        Deserializer::from_slice_with_string_buffer(input, options, None, Some(stats))
+    }
+
+    fn from_slice_with_string_buffer(
+        input: &'de mut [u8],
+        options: ParseOptions,
+        spans: Option<&mut Vec<Span>>,
+        stats: Option<&mut ParseStats>,
+    ) -> Result<Self> {
+        buffers::with_buffers(move |buffers| {
+            Deserializer::from_slice_with_buffers_and_stats(input, options, spans, stats, buffers)
+        })
+        .map_err(|(e, _)| e)
+    }
+
+    /// Like `from_slice_with_options` but when `input` isn't UTF-8 it is
+    /// handed back along with the error, so lossy UTF-8 mode can parse a
+    /// repaired copy of it instead.
+    pub(crate) fn from_slice_or_input(
+        input: &'de mut [u8],
+        options: ParseOptions,
+    ) -> core::result::Result<Self, Failure<'de>> {
+        buffers::with_buffers(move |buffers| {
+            Deserializer::from_slice_with_buffers_and_stats(input, options, None, None, buffers)
+        })
+    }
+
+    /// Like `from_slice_with_options` but uses `buffers` as scratch space
+    #[cfg(feature = "std")]
+    pub(crate) fn from_slice_with_buffers(
+        input: &'de mut [u8],
+        options: ParseOptions,
+        spans: Option<&mut Vec<Span>>,
+        buffers: &mut Buffers,
+    ) -> Result<Self> {
+        Deserializer::from_slice_with_buffers_and_stats(input, options, spans, None, buffers)
+            .map_err(|(e, _)| e)
+    }
+
+    fn from_slice_with_buffers_and_stats(
+        input: &'de mut [u8],
+        options: ParseOptions,
+        spans: Option<&mut Vec<Span>>,
+        stats: Option<&mut ParseStats>,
+        buffers: &mut Buffers,
+    ) -> core::result::Result<Self, Failure<'de>> {
+        let string_buffer = &mut buffers.string;
+        // stage 2 only reads back what it wrote to the string buffer
+        // so we don't need to initialize it
+        #[cfg(not(feature = "safe"))]
+        #[allow(clippy::uninit_vec)]
+        {
+            string_buffer.clear();
+            string_buffer.reserve(input.len() + SIMDJSON_PADDING);
+            unsafe {
+                string_buffer.set_len(input.len() + SIMDJSON_PADDING);
+            };
+        }
+        // the safe backend de-escapes strings in place
+        Deserializer::parse_slice(
+            input,
+            string_buffer,
+            &mut buffers.input,
+            &mut buffers.structural_indexes,
+            options,
+            spans,
+            stats,
+        )
     }
 
-    // By convention, `Deserializer` constructors are named like `from_xyz`.
-    // That way basic use cases are satisfied by something like
-    // `serde_json::from_str(...)` while advanced use cases that require a
-    // deserializer can make one with `serde_json::Deserializer::from_str(...)`.
-    // this takes an additional buffer to be (re) used for temporary string copying
+    fn parse_slice(
+        input: &'de mut [u8],
+        string_buffer: &mut [u8],
+        buffer: &mut Vec<u8>,
+        structural_indexes: &mut Vec<u32>,
+        options: ParseOptions,
+        spans: Option<&mut Vec<Span>>,
+        mut stats: Option<&mut ParseStats>,
+    ) -> core::result::Result<Self, Failure<'de>> {
+        let mut trace = ParseTrace::start(input.len());
+        let mut watch = Stopwatch::start(stats.is_some());
+        // the length of a byte order mark we skip, spans count it
+        let bom_len = if input.starts_with(UTF8_BOM) {
+            UTF8_BOM.len()
+        } else {
+            0
+        };
+        let input = if bom_len > 0 {
+            if !options.skip_bom {
+                let e = Error::new(0, '\u{feff}', ErrorType::ByteOrderMark).with_context(input);
+                trace.failed(&e);
+                return Err((e, None));
+            }
+            input.split_at_mut(bom_len).1
+        } else {
+            input
+        };
 
-    pub fn from_slice_with_buffer(input: &'de mut [u8], string_buffer: &mut [u8]) -> Result<Self> {
         // We have to pick an initial size of the structural indexes.
         // 6 is a heuristic that seems to work well for the benchmark
         // data and limit re-allocation frequency.
@@ -445,12 +933,18 @@ impl<'de> Deserializer<'de> {
 
         // let buf_start: usize = input.as_ptr() as *const () as usize;
         // let needs_relocation = (buf_start + input.len()) % page_size::get() < SIMDJSON_PADDING;
-        let mut buffer: Vec<u8> = Vec::with_capacity(len + SIMDJSON_PADDING * 2);
+        #[cfg(not(feature = "safe"))]
+        {
+            buffer.clear();
+            buffer.reserve(len + SIMDJSON_PADDING * 2);
+        }
 
+        #[cfg(not(feature = "safe"))]
         let align = buffer
             .as_slice()
             .as_ptr()
             .align_offset(SIMDJSON_PADDING / 2);
+        #[cfg(not(feature = "safe"))]
         unsafe {
             buffer
                 .as_mut_slice()
@@ -460,19 +954,72 @@ impl<'de> Deserializer<'de> {
             buffer.set_len(len + align);
         };
 
-        let s1_result: std::result::Result<Vec<u32>, ErrorType> =
-            unsafe { Deserializer::find_structural_bits(&buffer[align..]) };
+        // the safe backend never reads past the end so it needs neither
+        // padding nor alignment
+        #[cfg(feature = "safe")]
+        let align = {
+            buffer.clear();
+            buffer.extend_from_slice(input);
+            0
+        };
 
-        let structural_indexes = match s1_result {
-            Ok(i) => i,
-            Err(t) => {
-                return Err(Error::generic(t));
+        let s1_result: core::result::Result<(), ErrorType> =
+            Deserializer::find_structural_bits(&buffer[align..], structural_indexes);
+
+        if let Err(t) = s1_result {
+            let input = if t == ErrorType::InvalidUTF8 {
+                Some(input)
+            } else {
+                None
+            };
+            let e = Error::generic(t);
+            trace.failed(&e);
+            return Err((e, input));
+        }
+        trace.stage1_done(structural_indexes.len());
+        if let Some(stats) = stats.as_deref_mut() {
+            stats.input_len = len;
+            stats.stage1_done(&mut watch, structural_indexes.len());
+        }
+
+        let tape = match Deserializer::build_tape(
+            input,
+            &buffer[align..],
+            string_buffer,
+            structural_indexes,
+            options,
+        ) {
+            Ok(tape) => tape,
+            Err(e) => {
+                let e = e.with_context(&buffer[align..]);
+                trace.failed(&e);
+                return Err((e, None));
             }
         };
 
-        let tape =
-            Deserializer::build_tape(input, &buffer[align..], string_buffer, &structural_indexes)?;
+        if let Some(spans) = spans {
+            let input2 = &buffer[align..];
+            spans.reserve(structural_indexes.len());
+            for (i, &start) in structural_indexes.iter().enumerate() {
+                // A scalar ends right before the next structural character,
+                // minus the whitespace in between
+                let next = structural_indexes.get(i + 1).map_or(len, |&n| n as usize);
+                let start = start as usize;
+                let end = input2[..next]
+                    .iter()
+                    .rposition(|&c| !matches!(c, b' ' | b'\t' | b'\n' | b'\r'))
+                    .map_or(start, |p| p + 1);
+                spans.push(Span {
+                    start: start + bom_len,
+                    end: end + bom_len,
+                });
+            }
+        }
 
+        trace.done(tape.len());
+        if let Some(stats) = stats {
+            stats.stage2_done(&mut watch, &tape);
+        }
         Ok(Deserializer { tape, idx: 0 })
     }
 
@@ -482,9 +1029,23 @@ impl<'de> Deserializer<'de> {
         self.idx += 1;
     }
 
+    /// The next `n` nodes without consuming them, fewer if the tape ends
+    /// before that
+    #[cfg(feature = "bumpalo")]
+    fn peek_n(&self, n: usize) -> &[Node<'de>] {
+        let start = (self.idx + 1).min(self.tape.len());
+        &self.tape[start..(start + n).min(self.tape.len())]
+    }
+
+    #[cfg(feature = "bumpalo")]
+    fn skip_n(&mut self, n: usize) {
+        self.idx += n;
+    }
+
     // pull out the check so we don't need to
     // stry every time
     #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    #[cfg(not(feature = "safe"))]
     fn next_(&mut self) -> Node<'de> {
         unsafe {
             self.idx += 1;
@@ -492,18 +1053,67 @@ impl<'de> Deserializer<'de> {
         }
     }
 
-    //#[inline(never)]
+    #[cfg(feature = "safe")]
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn next_(&mut self) -> Node<'de> {
+        self.idx += 1;
+        self.tape[self.idx]
+    }
+
+    /// Runs only the UTF-8 validation part of stage 1 over `input`
+    #[cfg(not(feature = "safe"))]
+    pub(crate) fn is_valid_utf8(input: &[u8]) -> bool {
+        let mut state = SimdInput::new_utf8_checking_state();
+        let mut chunks = input.chunks_exact(SIMDINPUT_LENGTH);
+        for chunk in &mut chunks {
+            SimdInput::new(chunk).check_utf8(&mut state);
+        }
+        let remainder = chunks.remainder();
+        if !remainder.is_empty() {
+            // pad the rest with spaces just like stage 1 does
+            let mut tmpbuf: [u8; SIMDINPUT_LENGTH] = [0x20; SIMDINPUT_LENGTH];
+            tmpbuf[..remainder.len()].copy_from_slice(remainder);
+            SimdInput::new(&tmpbuf).check_utf8(&mut state);
+        }
+        !SimdInput::check_utf8_errors(&state)
+    }
+
+    /// Stage 1, finds the index of every structural character in `input`
+    #[cfg(not(feature = "safe"))]
+    pub(crate) fn find_structural_bits(
+        input: &[u8],
+        structural_indexes: &mut Vec<u32>,
+    ) -> core::result::Result<(), ErrorType> {
+        // the tail of the input is copied into a padded buffer so this is
+        // sound for any input
+        #[cfg(all(target_feature = "avx2", feature = "std"))]
+        {
+            if let Some(find_structural_bits) = crate::avx512::stage1::detect() {
+                return unsafe { find_structural_bits(input, structural_indexes) };
+            }
+        }
+        unsafe {
+            Deserializer::find_structural_bits_simd::<SimdInput, _>(input, structural_indexes)
+        }
+    }
+
+    // this is inlined into every caller so it picks up the target features
+    // of the runtime selected kernels
+    #[cfg(not(feature = "safe"))]
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
     #[allow(clippy::cast_possible_truncation)]
-    pub(crate) unsafe fn find_structural_bits(
+    unsafe fn find_structural_bits_simd<S: Stage1Parse<T>, T>(
         input: &[u8],
-    ) -> std::result::Result<Vec<u32>, ErrorType> {
+        structural_indexes: &mut Vec<u32>,
+    ) -> core::result::Result<(), ErrorType> {
         let len = input.len();
         // 6 is a heuristic number to estimate it turns out a rate of 1/6 structural caracters lears
         // almost never to relocations.
-        let mut structural_indexes = Vec::with_capacity(len / 6);
+        structural_indexes.clear();
+        structural_indexes.reserve(len / 6);
         structural_indexes.push(0); // push extra root element
 
-        let mut state = SimdInput::new_utf8_checking_state();
+        let mut state = S::new_utf8_checking_state();
         // we have padded the input out to 64 byte multiple with the remainder being
         // zeros
 
@@ -538,7 +1148,7 @@ impl<'de> Deserializer<'de> {
               __builtin_prefetch(buf + idx + 128);
             #endif
              */
-            let input = SimdInput::new(input.get_unchecked(idx as usize..));
+            let input = S::new(input.get_unchecked(idx as usize..));
             input.check_utf8(&mut state);
             // detect odd sequences of backslashes
             let odd_ends: u64 =
@@ -557,13 +1167,13 @@ impl<'de> Deserializer<'de> {
             // take the previous iterations structural bits, not our current iteration,
             // and flatten
             #[allow(clippy::cast_possible_truncation)]
-            SimdInput::flatten_bits(&mut structural_indexes, idx as u32, structurals);
+            S::flatten_bits(structural_indexes, idx as u32, structurals);
 
             let mut whitespace: u64 = 0;
             input.find_whitespace_and_structurals(&mut whitespace, &mut structurals);
 
             // fixup structurals to reflect quotes and add pseudo-structural characters
-            structurals = SimdInput::finalize_structurals(
+            structurals = S::finalize_structurals(
                 structurals,
                 whitespace,
                 quote_mask,
@@ -581,7 +1191,7 @@ impl<'de> Deserializer<'de> {
             tmpbuf
                 .as_mut_ptr()
                 .copy_from(input.as_ptr().add(idx), len as usize - idx);
-            let input = SimdInput::new(&tmpbuf);
+            let input = S::new(&tmpbuf);
 
             input.check_utf8(&mut state);
 
@@ -601,13 +1211,13 @@ impl<'de> Deserializer<'de> {
 
             // take the previous iterations structural bits, not our current iteration,
             // and flatten
-            SimdInput::flatten_bits(&mut structural_indexes, idx as u32, structurals);
+            S::flatten_bits(structural_indexes, idx as u32, structurals);
 
             let mut whitespace: u64 = 0;
             input.find_whitespace_and_structurals(&mut whitespace, &mut structurals);
 
             // fixup structurals to reflect quotes and add pseudo-structural characters
-            structurals = SimdInput::finalize_structurals(
+            structurals = S::finalize_structurals(
                 structurals,
                 whitespace,
                 quote_mask,
@@ -618,10 +1228,10 @@ impl<'de> Deserializer<'de> {
         }
         // This test isn't in upstream, for some reason the error mask is et for then.
         if prev_iter_inside_quote != 0 {
-            return Err(ErrorType::Syntax);
+            return Err(ErrorType::Incomplete { needed: None });
         }
         // finally, flatten out the remaining structurals from the last iteration
-        SimdInput::flatten_bits(&mut structural_indexes, idx as u32, structurals);
+        S::flatten_bits(structural_indexes, idx as u32, structurals);
 
         // a valid JSON file cannot have zero structural indexes - we should have
         // found something (note that we compare to 1 as we always add the root!)
@@ -637,10 +1247,10 @@ impl<'de> Deserializer<'de> {
             return Err(ErrorType::Syntax);
         }
 
-        if SimdInput::check_utf8_errors(&state) {
+        if S::check_utf8_errors(&state) {
             Err(ErrorType::InvalidUTF8)
         } else {
-            Ok(structural_indexes)
+            Ok(())
         }
     }
 }
@@ -648,10 +1258,223 @@ impl<'de> Deserializer<'de> {
 #[cfg(test)]
 mod tests {
     #![allow(clippy::unnecessary_operation, clippy::non_ascii_literal)]
-    use super::{owned::Value, to_borrowed_value, to_owned_value, Deserializer};
+    use super::{to_owned_value, Deserializer};
     use crate::tape::*;
-    use proptest::prelude::*;
-    use value_trait::{StaticNode, Writable};
+    #[cfg(feature = "std")]
+    use {
+        super::{owned::Value, to_borrowed_value},
+        proptest::prelude::*,
+        value_trait::{StaticNode, Writable},
+    };
+
+    #[test]
+    fn validate_utf8() {
+        use crate::{validate_utf8, ErrorType};
+        let snow = "snow ☃ man ".repeat(20);
+        assert_eq!(validate_utf8(snow.as_bytes()), Ok(snow.as_str()));
+        assert_eq!(validate_utf8(b""), Ok(""));
+        let mut long = snow.clone().into_bytes();
+        long.extend_from_slice(b"\xe2\x98");
+        let err = validate_utf8(&long).expect_err("truncated sequence");
+        assert_eq!(err.index(), snow.len());
+        assert_eq!(err.error(), &ErrorType::InvalidUTF8);
+        let err = validate_utf8(b"ab\xffcd").expect_err("invalid byte");
+        assert_eq!(err.index(), 2);
+        assert!(validate_utf8(b"\xed\xa0\x80").is_err());
+        assert!(validate_utf8(b"\xc0\xaf").is_err());
+    }
+
+    #[test]
+    fn lossy_utf8_fallback() {
+        use crate::{
+            to_borrowed_value_with_options, to_owned_value_with_options, ErrorType, ParseOptions,
+        };
+        assert!(Deserializer::is_valid_utf8(
+            "snow ☃ man".repeat(20).as_bytes()
+        ));
+        let mut long = "x".repeat(63).into_bytes();
+        long.extend_from_slice(b"\xe2\x98");
+        assert!(!Deserializer::is_valid_utf8(&long));
+
+        let options = ParseOptions::default().lossy_utf8_fallback(true);
+        let mut d = b"{\"k\xff\": [\"a\xc3\", \"\xe2\x98\x83\"]}".to_vec();
+        assert!(to_owned_value(&mut d.clone()).is_err());
+        let v = to_owned_value_with_options(&mut d.clone(), options).expect("failed to parse");
+        assert_eq!(v["k\u{fffd}"][0], "a\u{fffd}");
+        assert_eq!(v["k\u{fffd}"][1], "☃");
+        let b = to_borrowed_value_with_options(&mut d, options).expect("failed to parse");
+        assert_eq!(b, v);
+
+        // the input after a skipped byte order mark is repaired as well
+        let options = options.skip_bom(true);
+        let mut d = b"\xEF\xBB\xBF[\"\xff\"]".to_vec();
+        let v = to_owned_value_with_options(&mut d, options).expect("failed to parse");
+        assert_eq!(v[0], "\u{fffd}");
+
+        // serde reads a repaired copy from an owned value, types that
+        // borrow from the input can't have its strings
+        #[cfg(feature = "serde_impl")]
+        {
+            let v: Vec<String> = options
+                .from_slice(&mut b"[\"a\"]".to_vec())
+                .expect("failed to parse");
+            assert_eq!(v, ["a"]);
+            let v: Vec<String> = options
+                .from_slice(&mut b"[\"a\xff\", \"\xe2\x98\x83\"]".to_vec())
+                .expect("failed to parse");
+            assert_eq!(v, ["a\u{fffd}", "☃"]);
+            let e = options
+                .from_slice::<Vec<&str>>(&mut b"[\"\xff\"]".to_vec())
+                .expect_err("borrowed from a repaired copy");
+            assert!(matches!(e.error(), ErrorType::Serde(_)));
+        }
+    }
+
+    #[test]
+    fn bom() {
+        use crate::{to_owned_value_with_options, ErrorType, ParseOptions};
+        let mut d = b"\xEF\xBB\xBF{\"a\": 1}".to_vec();
+        let e = to_owned_value(&mut d.clone()).expect_err("parsed a BOM");
+        assert_eq!(e.error(), &ErrorType::ByteOrderMark);
+        assert_eq!(e.line(), Some(1));
+        let options = ParseOptions::default().skip_bom(true);
+        let v = to_owned_value_with_options(&mut d, options).expect("failed to parse");
+        assert_eq!(v["a"], 1);
+        // only a leading BOM is skipped
+        let mut d = b"\xEF\xBB\xBF\xEF\xBB\xBF{}".to_vec();
+        assert!(to_owned_value_with_options(&mut d, options).is_err());
+    }
+
+    #[test]
+    fn max_depth() {
+        use crate::{ErrorType, ParseOptions};
+        // the index of the error, which has to be `TooDeep`
+        let err = |depth: usize, s: &str| {
+            ParseOptions::default()
+                .max_depth(depth)
+                .to_tape(&mut s.as_bytes().to_vec())
+                .map(|_| ())
+                .map_err(|e| {
+                    assert_eq!(e.error(), &ErrorType::TooDeep);
+                    e.index()
+                })
+        };
+        assert_eq!(err(0, "1"), Ok(()));
+        assert_eq!(err(0, "[]"), Err(0));
+        assert_eq!(err(3, r#"[{"a": [1]}, []]"#), Ok(()));
+        assert_eq!(err(2, r#"[{"a": 1}, {"b": [1]}]"#), Err(17));
+        assert_eq!(err(2, r#"{"a": {"b": {}}}"#), Err(12));
+        let deep = format!("{}{}", "[".repeat(100), "]".repeat(100));
+        assert_eq!(err(100, &deep), Ok(()));
+        assert_eq!(err(99, &deep), Err(99));
+        // the options can be kept and used again
+        let options = ParseOptions::default().max_depth(1);
+        assert!(options.to_owned_value(&mut b"[[]]".to_vec()).is_err());
+        assert!(options.to_borrowed_value(&mut b"[[]]".to_vec()).is_err());
+        assert!(options.to_owned_value(&mut b"[1]".to_vec()).is_ok());
+    }
+
+    #[test]
+    fn presets() {
+        use crate::{ErrorType, ParseOptions};
+        assert_eq!(ParseOptions::strict().max_depth, 128);
+        assert_eq!(
+            ParseOptions::strict().max_depth(usize::MAX),
+            ParseOptions::default()
+        );
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(ParseOptions::strict()
+            .to_tape(&mut nested(128).into_bytes())
+            .is_ok());
+        let err = ParseOptions::strict()
+            .to_owned_value(&mut nested(129).into_bytes())
+            .expect_err("parsed a too deep document");
+        assert_eq!(err.error(), &ErrorType::TooDeep);
+        assert!(ParseOptions::lenient()
+            .to_borrowed_value(&mut nested(1000).into_bytes())
+            .is_ok());
+        let lenient = ParseOptions::lenient();
+        assert!(lenient.allow_non_finite && lenient.lossy_utf8_fallback && lenient.skip_bom);
+        for options in &[ParseOptions::strict(), ParseOptions::default(), lenient] {
+            for s in &["[1,]", "{\"a\": 1,}", "[1] // one", "/* one */ [1]"] {
+                assert!(options.to_owned_value(&mut s.as_bytes().to_vec()).is_err());
+            }
+        }
+    }
+
+    #[cfg(feature = "unicode")]
+    #[test]
+    fn normalization() {
+        use crate::{prelude::*, Normalization, ParseOptions};
+        // a decomposed key and value, and a composed key
+        let d = "{\"e\u{301}\": \"e\u{301}\", \"\u{e9}t\u{e9}\": [\"a\\u0301\"]}";
+        let parse = |normalization| {
+            ParseOptions::default()
+                .normalization(normalization)
+                .to_owned_value(&mut d.as_bytes().to_vec())
+                .expect("failed to parse")
+        };
+        let v = parse(Normalization::Off);
+        assert_eq!(v["e\u{301}"], "e\u{301}");
+        assert!(v.get("\u{e9}").is_none());
+        let v = parse(Normalization::Keys);
+        assert_eq!(v["\u{e9}"], "e\u{301}");
+        assert_eq!(v["\u{e9}t\u{e9}"][0], "a\u{301}");
+        let v = parse(Normalization::Values);
+        assert_eq!(v["e\u{301}"], "\u{e9}");
+        assert_eq!(v["\u{e9}t\u{e9}"][0], "\u{e1}");
+        let all = json!({"\u{e9}": "\u{e9}", "\u{e9}t\u{e9}": ["\u{e1}"]});
+        assert_eq!(parse(Normalization::All), all);
+        let options = ParseOptions::default().normalization(Normalization::All);
+        let mut input = d.as_bytes().to_vec();
+        let borrowed = options
+            .to_borrowed_value(&mut input)
+            .expect("failed to parse");
+        assert_eq!(borrowed, all);
+        // the options are honoured for repaired input as well
+        let mut input = b"[\"e\xcc\x81\xff\"]".to_vec();
+        let v = options
+            .lossy_utf8_fallback(true)
+            .to_borrowed_value(&mut input)
+            .expect("failed to parse");
+        assert_eq!(v, json!(["\u{e9}\u{fffd}"]));
+    }
+
+    #[test]
+    fn incomplete() {
+        use crate::ErrorType;
+        fn err(s: &str) -> crate::Error {
+            let mut d = s.as_bytes().to_vec();
+            to_owned_value(&mut d).expect_err("parsed incomplete input")
+        }
+        for s in &[
+            r#"{"a": [1, 2"#,
+            r#"{"a""#,
+            r#"{"a":"#,
+            r#"["unterminated"#,
+            "[1.",
+            "[-",
+            "[1e",
+        ] {
+            assert!(err(s).is_incomplete(), "{}", s);
+        }
+        assert_eq!(
+            err("[tr").error(),
+            &ErrorType::Incomplete { needed: Some(2) }
+        );
+        assert_eq!(
+            err("{\"a\": nu").error(),
+            &ErrorType::Incomplete { needed: Some(2) }
+        );
+        assert_eq!(
+            err("[fals").error(),
+            &ErrorType::Incomplete { needed: Some(1) }
+        );
+        // genuine syntax errors are not incomplete
+        for s in &["[1 2", "[tx", "{\"a\" 1", "[1.x", "[1,]", "[nul ]"] {
+            assert!(!err(s).is_incomplete(), "{}", s);
+        }
+    }
 
     #[test]
     fn count1() {
@@ -735,6 +1558,7 @@ mod tests {
     // How much do we care about this, it's within the same range and
     // based on floating point math inprecisions during parsing.
     // Is this a real issue worth improving?
+    #[cfg(feature = "std")]
     #[test]
     fn silly_float1() {
         let v = Value::from(3.090_144_804_232_201_7e305);
@@ -745,6 +1569,7 @@ mod tests {
         assert_eq!(v, parsed);
     }
 
+    #[cfg(feature = "std")]
     #[test]
     #[ignore]
     fn silly_float2() {
@@ -755,7 +1580,7 @@ mod tests {
         let parsed = to_owned_value(&mut bytes).expect("failed to parse gernated float");
         assert_eq!(v, parsed);
     }
-    #[cfg(not(feature = "128bit"))]
+    #[cfg(all(feature = "std", not(feature = "128bit")))]
     fn arb_json_value() -> BoxedStrategy<Value> {
         let leaf = prop_oneof![
             Just(Value::Static(StaticNode::Null)),
@@ -780,7 +1605,7 @@ mod tests {
         .boxed()
     }
 
-    #[cfg(feature = "128bit")]
+    #[cfg(all(feature = "std", feature = "128bit"))]
     fn arb_json_value() -> BoxedStrategy<Value> {
         let leaf = prop_oneof![
             Just(Value::Static(StaticNode::Null)),
@@ -807,6 +1632,7 @@ mod tests {
         .boxed()
     }
 
+    #[cfg(feature = "std")]
     proptest! {
         #![proptest_config(ProptestConfig {
             // Setting both fork and timeout is redundant since timeout implies
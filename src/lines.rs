@@ -0,0 +1,282 @@
+use crate::buffers::Buffers;
+use crate::prelude::*;
+use crate::value::borrowed::to_value_with_buffers;
+use crate::{BorrowedValue, Error, ErrorType, Result};
+use alloc::vec::Vec;
+use std::io::{self, BufRead, Write};
+
+/// Reads newline delimited JSON from `reader` one document per line,
+/// reusing one line buffer and the parser's scratch buffers for all of
+/// them. Each value borrows the line buffer and so lives until the next
+/// call to `next`, which makes this the cheapest way through a log.
+///
+/// Lines that are empty or only hold whitespace are skipped. A line that
+/// isn't valid JSON returns its error, with the index in the line, and
+/// reading goes on with the next line on the next call.
+///
+/// ```rust
+/// use simd_json::prelude::*;
+/// let log = "{\"level\": \"info\"}\n\n{\"level\": \"warn\"}\n{\"level\": \"info\"}\n";
+/// let mut lines = simd_json::JsonLines::new(log.as_bytes());
+/// let mut warnings = 0;
+/// while let Some(value) = lines.next()? {
+///     if value["level"] == "warn" {
+///         warnings += 1;
+///     }
+/// }
+/// assert_eq!(warnings, 1);
+/// # Ok::<(), simd_json::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct JsonLines<R> {
+    reader: R,
+    line: Vec<u8>,
+    buffers: Buffers,
+    line_number: usize,
+}
+
+impl<R: BufRead> JsonLines<R> {
+    /// Reads the lines of `reader`
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: Vec::new(),
+            buffers: Buffers::default(),
+            line_number: 0,
+        }
+    }
+
+    /// Parses the next line that isn't blank, `None` at the end of the
+    /// input.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if reading fails or if the line is invalid JSON.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result<Option<BorrowedValue<'_>>> {
+        loop {
+            self.line.clear();
+            match self.reader.read_until(b'\n', &mut self.line) {
+                Ok(0) => return Ok(None),
+                Ok(_) => self.line_number += 1,
+                Err(e) => return Err(Error::generic(ErrorType::IO(e))),
+            }
+            if !self
+                .line
+                .iter()
+                .all(|c| matches!(c, b' ' | b'\t' | b'\n' | b'\r'))
+            {
+                break;
+            }
+        }
+        to_value_with_buffers(&mut self.line, &mut self.buffers).map(Some)
+    }
+
+    /// The number of the line the last value or error came from,
+    /// counting from 1 and blank lines included
+    #[must_use]
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// The reader, with the lines read so far taken out of it
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// Writes newline delimited JSON to `writer`, the twin of `JsonLines`.
+/// Every value is encoded into a buffer that is reused for all of them
+/// and written with its newline in one go, so a value that fails to
+/// serialize doesn't leave half a line behind.
+///
+/// ```rust
+/// use simd_json::{json, NdjsonWriter};
+/// let mut writer = NdjsonWriter::new(Vec::new());
+/// writer.write_value(&json!({"level": "info"}))?;
+/// writer.write_value(&json!("multi\nline"))?;
+/// assert_eq!(writer.into_inner(), b"{\"level\":\"info\"}\n\"multi\\nline\"\n");
+/// # Ok::<(), simd_json::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct NdjsonWriter<W: Write> {
+    writer: W,
+    buffer: Vec<u8>,
+    flush_lines: bool,
+}
+
+impl<W: Write> NdjsonWriter<W> {
+    /// Writes lines to `writer`, wrap it in a `BufWriter` when writing
+    /// small values to a file or socket
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            buffer: Vec::new(),
+            flush_lines: false,
+        }
+    }
+
+    /// Flush the writer after every line, for consumers that follow the
+    /// output as it is written
+    #[must_use]
+    pub fn flush_lines(mut self, flush: bool) -> Self {
+        self.flush_lines = flush;
+        self
+    }
+
+    /// Writes `value` as one line
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if writing fails.
+    pub fn write_value<V>(&mut self, value: &V) -> Result<()>
+    where
+        V: Writable + ?Sized,
+    {
+        self.buffer.clear();
+        if let Err(e) = value.write(&mut self.buffer) {
+            return Err(Error::generic(ErrorType::IO(e)));
+        }
+        self.write_line()
+    }
+
+    /// Serializes `value` with serde as one line
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `value` can't be serialized or writing fails.
+    #[cfg(feature = "serde_impl")]
+    pub fn serialize<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: serde_ext::Serialize + ?Sized,
+    {
+        self.buffer.clear();
+        crate::to_writer(&mut self.buffer, value)?;
+        self.write_line()
+    }
+
+    fn write_line(&mut self) -> Result<()> {
+        self.buffer.push(b'\n');
+        let mut res = self.writer.write_all(&self.buffer);
+        if res.is_ok() && self.flush_lines {
+            res = self.writer.flush();
+        }
+        res.map_err(|e| Error::generic(ErrorType::IO(e)))
+    }
+
+    /// Flushes the writer
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if flushing fails.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// The writer
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// The writer, the lines written so far are not flushed
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn lines() {
+        let input = "[1]\n\r\n  \n{\"a\": \"\\u00e9\"}\r\n{\"a\": }\n\"last\"";
+        let mut lines = JsonLines::new(input.as_bytes());
+        assert_eq!(
+            lines.next().expect("failed to parse"),
+            Some(json!([1]).into())
+        );
+        assert_eq!(lines.line_number(), 1);
+        let a = lines
+            .next()
+            .expect("failed to parse")
+            .map(|v| v["a"] == "é");
+        assert_eq!(a, Some(true));
+        assert_eq!(lines.line_number(), 4);
+        let e = lines.next().expect_err("parsed");
+        assert_eq!(e.index(), 6);
+        assert_eq!(lines.line_number(), 5);
+        assert_eq!(lines.next().expect("failed to parse"), Some("last".into()));
+        assert_eq!(lines.next().expect("failed to parse"), None);
+        assert_eq!(lines.next().expect("failed to parse"), None);
+        assert_eq!(lines.line_number(), 6);
+    }
+
+    struct Broken;
+
+    impl Read for Broken {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::from(io::ErrorKind::BrokenPipe))
+        }
+    }
+
+    impl Write for Broken {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::from(io::ErrorKind::BrokenPipe))
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct Flushes {
+        data: Vec<u8>,
+        flushed: usize,
+    }
+
+    impl Write for Flushes {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.data.write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            self.flushed = self.data.len();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn writer() {
+        let mut writer = NdjsonWriter::new(Flushes::default());
+        writer
+            .write_value(&json!({"a": "x\ny"}))
+            .expect("failed to write");
+        writer
+            .write_value(&BorrowedValue::from("b"))
+            .expect("failed to write");
+        assert_eq!(writer.get_ref().flushed, 0);
+        let mut writer = writer.flush_lines(true);
+        writer.serialize(&[1, 2]).expect("failed to write");
+        let out = writer.into_inner();
+        assert_eq!(out.data, b"{\"a\":\"x\\ny\"}\n\"b\"\n[1,2]\n");
+        assert_eq!(out.flushed, out.data.len());
+        // and back
+        let mut lines = JsonLines::new(&out.data[..]);
+        let mut n = 0;
+        while lines.next().expect("failed to parse").is_some() {
+            n += 1;
+        }
+        assert_eq!(n, 3);
+        let mut writer = NdjsonWriter::new(Broken);
+        let e = writer.write_value(&json!(1)).expect_err("wrote");
+        assert!(matches!(e.error(), ErrorType::IO(_)));
+    }
+
+    #[test]
+    fn io_error() {
+        let mut lines = JsonLines::new(io::BufReader::new(Broken));
+        let e = lines.next().expect_err("read");
+        assert!(matches!(e.error(), ErrorType::IO(_)));
+    }
+}
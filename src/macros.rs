@@ -544,7 +544,7 @@ macro_rules! json_unexpected {
 #[macro_export]
 macro_rules! likely {
     ($e:expr) => {
-        unsafe { std::intrinsics::likely($e) }
+        unsafe { core::intrinsics::likely($e) }
     };
 }
 
@@ -553,7 +553,7 @@ macro_rules! likely {
 #[macro_export]
 macro_rules! unlikely {
     ($e:expr) => {{
-        unsafe { std::intrinsics::unlikely($e) }
+        unsafe { core::intrinsics::unlikely($e) }
     }};
 }
 
@@ -630,8 +630,8 @@ macro_rules! static_cast_u64 {
 macro_rules! stry {
     ($e:expr) => {
         match $e {
-            ::std::result::Result::Ok(val) => val,
-            ::std::result::Result::Err(err) => return ::std::result::Result::Err(err),
+            ::core::result::Result::Ok(val) => val,
+            ::core::result::Result::Err(err) => return ::core::result::Result::Err(err),
         }
     };
 }
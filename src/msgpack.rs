@@ -0,0 +1,586 @@
+use crate::cow::Cow;
+use crate::value::emit::{emit, Emit, Emitter};
+use crate::{BorrowedValue, Error, ErrorType, Result, StaticNode};
+use alloc::borrow::Cow as BytesCow;
+use alloc::vec::Vec;
+use core::convert::{Infallible, TryFrom};
+
+const NIL: u8 = 0xc0;
+const FALSE: u8 = 0xc2;
+const TRUE: u8 = 0xc3;
+const F32: u8 = 0xca;
+const F64: u8 = 0xcb;
+
+// The first byte of the fix formats, their length or value is in the low
+// bits
+const FIXMAP: u8 = 0x80;
+const FIXARRAY: u8 = 0x90;
+const FIXSTR: u8 = 0xa0;
+
+/// The markers of the 8, 16 and 32 bit length forms of a format
+struct Lengths {
+    fix: Option<(u8, usize)>,
+    sized: [u8; 3],
+}
+
+const STR: Lengths = Lengths {
+    fix: Some((FIXSTR, 32)),
+    sized: [0xd9, 0xda, 0xdb],
+};
+const BIN: Lengths = Lengths {
+    fix: None,
+    sized: [0xc4, 0xc5, 0xc6],
+};
+const ARRAY: Lengths = Lengths {
+    fix: Some((FIXARRAY, 16)),
+    sized: [0, 0xdc, 0xdd],
+};
+const MAP: Lengths = Lengths {
+    fix: Some((FIXMAP, 16)),
+    sized: [0, 0xde, 0xdf],
+};
+
+/// Encodes an `OwnedValue` or `BorrowedValue` as `MessagePack`.
+///
+/// Integers take the fewest bytes they fit in, floats are written as
+/// `float 32` when that doesn't lose anything and as `float 64`
+/// otherwise, `Bytes` values as `bin`. `MessagePack` has no integers
+/// beyond 64 bits, with the `128bit` feature those are written as strings
+/// of their digits. Object entries are in the order of the object.
+///
+/// Strings, binaries, arrays and maps can be at most `u32::MAX` long,
+/// longer ones are cut off at that length since the format has no way to
+/// write them.
+///
+/// ```rust
+/// let v = simd_json::json!({"a": [1, -2.5, null]});
+/// let msgpack = simd_json::to_msgpack_vec(&v);
+/// assert_eq!(msgpack, [0x81, 0xa1, b'a', 0x93, 0x01, 0xca, 0xc0, 0x20, 0x00, 0x00, 0xc0]);
+/// assert_eq!(simd_json::from_msgpack_slice(&msgpack).unwrap(), v);
+/// ```
+#[must_use]
+pub fn to_msgpack_vec<V: Emit>(value: &V) -> Vec<u8> {
+    let mut out = Vec::new();
+    let _ = emit(&mut Encoder(&mut out), value);
+    out
+}
+
+/// Writes the `MessagePack` items of the values `emit` walks
+struct Encoder<'o>(&'o mut Vec<u8>);
+
+impl Emitter for Encoder<'_> {
+    type Error = Infallible;
+
+    fn static_node(&mut self, s: StaticNode) -> core::result::Result<(), Infallible> {
+        let out = &mut *self.0;
+        match s {
+            StaticNode::Null => out.push(NIL),
+            StaticNode::Bool(true) => out.push(TRUE),
+            StaticNode::Bool(false) => out.push(FALSE),
+            StaticNode::I64(i) => write_i64(out, i),
+            StaticNode::U64(u) => write_u64(out, u),
+            #[cfg(feature = "128bit")]
+            StaticNode::I128(i) => match i64::try_from(i) {
+                Ok(i) => write_i64(out, i),
+                Err(_) => write_u128(out, i.unsigned_abs(), i < 0),
+            },
+            #[cfg(feature = "128bit")]
+            StaticNode::U128(u) => write_u128(out, u, false),
+            StaticNode::F64(f) => write_f64(out, f),
+        }
+        Ok(())
+    }
+
+    fn string(&mut self, s: &str) -> core::result::Result<(), Infallible> {
+        write_data(self.0, &STR, s.as_bytes());
+        Ok(())
+    }
+
+    fn bytes(&mut self, b: &[u8]) -> core::result::Result<(), Infallible> {
+        write_data(self.0, &BIN, b);
+        Ok(())
+    }
+
+    fn start_array(&mut self, len: usize) -> core::result::Result<usize, Infallible> {
+        Ok(write_len(self.0, &ARRAY, len))
+    }
+
+    fn start_object(&mut self, len: usize) -> core::result::Result<usize, Infallible> {
+        Ok(write_len(self.0, &MAP, len))
+    }
+
+    fn key(&mut self, key: &str, _first: bool) -> core::result::Result<(), Infallible> {
+        self.string(key)
+    }
+}
+
+/// Writes the marker and length of a string, binary, array or map,
+/// returns the length written
+fn write_len(out: &mut Vec<u8>, format: &Lengths, len: usize) -> usize {
+    let [len8, len16, len32] = format.sized;
+    match format.fix {
+        Some((marker, limit)) if len < limit => {
+            // the limit is at most 32
+            out.push(marker | u8::try_from(len).unwrap_or_default());
+            return len;
+        }
+        _ => (),
+    }
+    if len8 != 0 {
+        if let Ok(len) = u8::try_from(len) {
+            out.extend_from_slice(&[len8, len]);
+            return usize::from(len);
+        }
+    }
+    if let Ok(len) = u16::try_from(len) {
+        out.push(len16);
+        out.extend_from_slice(&len.to_be_bytes());
+        return usize::from(len);
+    }
+    let len = u32::try_from(len).unwrap_or(u32::MAX);
+    out.push(len32);
+    out.extend_from_slice(&len.to_be_bytes());
+    usize::try_from(len).unwrap_or(usize::MAX)
+}
+
+fn write_data(out: &mut Vec<u8>, format: &Lengths, data: &[u8]) {
+    let len = write_len(out, format, data.len());
+    out.extend_from_slice(&data[..len]);
+}
+
+fn write_u64(out: &mut Vec<u8>, u: u64) {
+    if u < 0x80 {
+        out.push(u8::try_from(u).unwrap_or_default());
+    } else if let Ok(u) = u8::try_from(u) {
+        out.extend_from_slice(&[0xcc, u]);
+    } else if let Ok(u) = u16::try_from(u) {
+        out.push(0xcd);
+        out.extend_from_slice(&u.to_be_bytes());
+    } else if let Ok(u) = u32::try_from(u) {
+        out.push(0xce);
+        out.extend_from_slice(&u.to_be_bytes());
+    } else {
+        out.push(0xcf);
+        out.extend_from_slice(&u.to_be_bytes());
+    }
+}
+
+fn write_i64(out: &mut Vec<u8>, i: i64) {
+    if let Ok(u) = u64::try_from(i) {
+        write_u64(out, u);
+    } else if i >= -32 {
+        // negative fixint, the byte is the value in two's complement
+        out.push(i.to_be_bytes()[7]);
+    } else if let Ok(i) = i8::try_from(i) {
+        out.push(0xd0);
+        out.extend_from_slice(&i.to_be_bytes());
+    } else if let Ok(i) = i16::try_from(i) {
+        out.push(0xd1);
+        out.extend_from_slice(&i.to_be_bytes());
+    } else if let Ok(i) = i32::try_from(i) {
+        out.push(0xd2);
+        out.extend_from_slice(&i.to_be_bytes());
+    } else {
+        out.push(0xd3);
+        out.extend_from_slice(&i.to_be_bytes());
+    }
+}
+
+/// Integers that don't fit into 64 bits become strings of their digits
+#[cfg(feature = "128bit")]
+fn write_u128(out: &mut Vec<u8>, u: u128, negative: bool) {
+    use alloc::string::ToString;
+    if let (Ok(u), false) = (u64::try_from(u), negative) {
+        write_u64(out, u);
+    } else {
+        let mut digits = u.to_string();
+        if negative {
+            digits.insert(0, '-');
+        }
+        write_data(out, &STR, digits.as_bytes());
+    }
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::float_cmp)]
+fn write_f64(out: &mut Vec<u8>, f: f64) {
+    let single = f as f32;
+    if f64::from(single) == f || f.is_nan() {
+        out.push(F32);
+        out.extend_from_slice(&single.to_bits().to_be_bytes());
+    } else {
+        out.push(F64);
+        out.extend_from_slice(&f.to_bits().to_be_bytes());
+    }
+}
+
+/// Decodes a single `MessagePack` item into a `BorrowedValue` that borrows
+/// its strings and binaries from `data`.
+///
+/// Integers become `I64` when they fit and `U64` otherwise, both float
+/// widths become `F64` and `bin` becomes `Bytes`. Map keys have to be
+/// strings.
+///
+/// ```rust
+/// use simd_json::prelude::*;
+/// let msgpack = [0x82, 0xa1, b'a', 0xcd, 0x01, 0x00, 0xa1, b'b', 0xc4, 0x02, 0x01, 0x02];
+/// let v = simd_json::from_msgpack_slice(&msgpack).unwrap();
+/// assert_eq!(v["a"], 256);
+/// assert_eq!(v["b"].as_bytes(), Some(&[1, 2][..]));
+/// ```
+///
+/// # Errors
+///
+/// if `data` isn't a single well-formed `MessagePack` item, or holds
+/// extension types, which have no counterpart in a value, or non-string
+/// map keys
+pub fn from_msgpack_slice(data: &[u8]) -> Result<BorrowedValue<'_>> {
+    let mut decoder = Decoder { data, idx: 0 };
+    let value = decoder.parse()?;
+    if decoder.idx < data.len() {
+        return Err(decoder.error(decoder.idx, ErrorType::TrailingCharacters));
+    }
+    Ok(value)
+}
+
+/// A container that is being decoded
+enum Frame<'v> {
+    Array {
+        values: Vec<BorrowedValue<'v>>,
+        remaining: usize,
+    },
+    Object {
+        object: crate::borrowed::Object<'v>,
+        key: Option<Cow<'v, str>>,
+        remaining: usize,
+    },
+}
+
+impl<'v> Frame<'v> {
+    fn is_done(&self) -> bool {
+        match self {
+            Self::Array { remaining, .. } => *remaining == 0,
+            Self::Object { remaining, key, .. } => *remaining == 0 && key.is_none(),
+        }
+    }
+
+    fn into_value(self) -> BorrowedValue<'v> {
+        match self {
+            Self::Array { values, .. } => BorrowedValue::Array(values),
+            Self::Object { object, .. } => BorrowedValue::from(object),
+        }
+    }
+}
+
+struct Decoder<'v> {
+    data: &'v [u8],
+    idx: usize,
+}
+
+impl<'v> Decoder<'v> {
+    fn error(&self, idx: usize, error: ErrorType) -> Error {
+        let character = self.data.get(idx).map_or('\0', |b| char::from(*b));
+        Error::new(idx, character, error)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'v [u8]> {
+        let end = self
+            .idx
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| self.error(self.data.len(), ErrorType::EOF))?;
+        let bytes = &self.data[self.idx..end];
+        self.idx = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut bytes = [0; N];
+        bytes.copy_from_slice(self.take(N)?);
+        Ok(bytes)
+    }
+
+    /// Reads a length of `width` bytes
+    fn len(&mut self, width: u8) -> Result<usize> {
+        let len = match width {
+            1 => u32::from(self.array::<1>()?[0]),
+            2 => u32::from(u16::from_be_bytes(self.array()?)),
+            _ => u32::from_be_bytes(self.array()?),
+        };
+        usize::try_from(len).map_err(|_| self.error(self.idx, ErrorType::EOF))
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<BytesCow<'v, [u8]>> {
+        self.take(len).map(BytesCow::Borrowed)
+    }
+
+    fn text(&mut self, len: usize) -> Result<BorrowedValue<'v>> {
+        let start = self.idx;
+        let text = self.take(len)?;
+        core::str::from_utf8(text)
+            .map(|s| BorrowedValue::String(Cow::from(s)))
+            .map_err(|_| self.error(start, ErrorType::InvalidUTF8))
+    }
+
+    /// Starts decoding an array or map of `len` items, empty ones are
+    /// done right away
+    fn container(&mut self, map: bool, len: usize) -> Frame<'v> {
+        // bound by what is left of the input so a bogus length can't
+        // allocate much
+        let capacity = len.min(self.data.len() - self.idx);
+        if map {
+            Frame::Object {
+                object: crate::borrowed::Object::with_capacity(capacity),
+                key: None,
+                remaining: len,
+            }
+        } else {
+            Frame::Array {
+                values: Vec::with_capacity(capacity),
+                remaining: len,
+            }
+        }
+    }
+
+    /// Decodes one item, containers are kept on an explicit stack so deep
+    /// nesting can't overflow the call stack
+    fn parse(&mut self) -> Result<BorrowedValue<'v>> {
+        let mut stack: Vec<Frame<'v>> = Vec::new();
+        loop {
+            let start = self.idx;
+            let marker = self.array::<1>()?[0];
+            let frame = match marker {
+                0x80..=0x8f => Some(self.container(true, usize::from(marker & 0x0f))),
+                0x90..=0x9f => Some(self.container(false, usize::from(marker & 0x0f))),
+                0xdc..=0xdf => {
+                    let len = self.len(if marker & 1 == 0 { 2 } else { 4 })?;
+                    Some(self.container(marker >= 0xde, len))
+                }
+                _ => None,
+            };
+            let mut value = match frame {
+                Some(frame) if frame.is_done() => frame.into_value(),
+                Some(frame) => {
+                    stack.push(frame);
+                    continue;
+                }
+                None => self.scalar(marker, start)?,
+            };
+
+            // Hand the value to the containers it completes
+            loop {
+                match stack.last_mut() {
+                    None => return Ok(value),
+                    Some(Frame::Array { values, remaining }) => {
+                        values.push(value);
+                        *remaining -= 1;
+                    }
+                    Some(Frame::Object {
+                        object,
+                        key,
+                        remaining,
+                    }) => {
+                        if let Some(key) = key.take() {
+                            object.insert(key, value);
+                            *remaining -= 1;
+                        } else if let BorrowedValue::String(s) = value {
+                            *key = Some(s);
+                        } else {
+                            return Err(self.error(start, ErrorType::KeyMustBeAString));
+                        }
+                    }
+                }
+                match stack.pop() {
+                    Some(frame) if frame.is_done() => value = frame.into_value(),
+                    Some(frame) => {
+                        stack.push(frame);
+                        break;
+                    }
+                    None => unreachable!(),
+                }
+            }
+        }
+    }
+
+    /// Decodes an item that isn't an array or map
+    fn scalar(&mut self, marker: u8, start: usize) -> Result<BorrowedValue<'v>> {
+        Ok(match marker {
+            0x00..=0x7f => BorrowedValue::from(i64::from(marker)),
+            0xa0..=0xbf => self.text(usize::from(marker & 0x1f))?,
+            NIL => BorrowedValue::Static(StaticNode::Null),
+            FALSE => BorrowedValue::from(false),
+            TRUE => BorrowedValue::from(true),
+            0xc4 => {
+                let len = self.len(1)?;
+                BorrowedValue::Bytes(self.bytes(len)?)
+            }
+            0xc5 => {
+                let len = self.len(2)?;
+                BorrowedValue::Bytes(self.bytes(len)?)
+            }
+            0xc6 => {
+                let len = self.len(4)?;
+                BorrowedValue::Bytes(self.bytes(len)?)
+            }
+            F32 => {
+                BorrowedValue::from(f64::from(f32::from_bits(u32::from_be_bytes(self.array()?))))
+            }
+            F64 => BorrowedValue::from(f64::from_bits(u64::from_be_bytes(self.array()?))),
+            0xcc => BorrowedValue::from(i64::from(self.array::<1>()?[0])),
+            0xcd => BorrowedValue::from(i64::from(u16::from_be_bytes(self.array()?))),
+            0xce => BorrowedValue::from(i64::from(u32::from_be_bytes(self.array()?))),
+            0xcf => {
+                let u = u64::from_be_bytes(self.array()?);
+                i64::try_from(u).map_or(BorrowedValue::from(u), BorrowedValue::from)
+            }
+            0xd0 => BorrowedValue::from(i64::from(i8::from_be_bytes(self.array()?))),
+            0xd1 => BorrowedValue::from(i64::from(i16::from_be_bytes(self.array()?))),
+            0xd2 => BorrowedValue::from(i64::from(i32::from_be_bytes(self.array()?))),
+            0xd3 => BorrowedValue::from(i64::from_be_bytes(self.array()?)),
+            0xd9 => {
+                let len = self.len(1)?;
+                self.text(len)?
+            }
+            0xda => {
+                let len = self.len(2)?;
+                self.text(len)?
+            }
+            0xdb => {
+                let len = self.len(4)?;
+                self.text(len)?
+            }
+            0xe0..=0xff => BorrowedValue::from(i64::from(i8::from_be_bytes([marker]))),
+            // `0xc1` is never used, the rest are extension types
+            _ => return Err(self.error(start, ErrorType::UnexpectedCharacter)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::OwnedValue;
+
+    fn decode(msgpack: &[u8]) -> Result<BorrowedValue<'_>> {
+        from_msgpack_slice(msgpack)
+    }
+
+    #[test]
+    fn encoding() {
+        let cases: &[(OwnedValue, &[u8])] = &[
+            (json!(0), &[0x00]),
+            (json!(127), &[0x7f]),
+            (json!(128), &[0xcc, 0x80]),
+            (json!(256), &[0xcd, 0x01, 0x00]),
+            (json!(65_536), &[0xce, 0x00, 0x01, 0x00, 0x00]),
+            (
+                OwnedValue::from(u64::MAX),
+                &[0xcf, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+            ),
+            (json!(-1), &[0xff]),
+            (json!(-32), &[0xe0]),
+            (json!(-33), &[0xd0, 0xdf]),
+            (json!(-129), &[0xd1, 0xff, 0x7f]),
+            (json!(-32_769), &[0xd2, 0xff, 0xff, 0x7f, 0xff]),
+            (
+                json!(i64::MIN),
+                &[0xd3, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            ),
+            (json!(1.5), &[0xca, 0x3f, 0xc0, 0x00, 0x00]),
+            (
+                json!(1.1),
+                &[0xcb, 0x3f, 0xf1, 0x99, 0x99, 0x99, 0x99, 0x99, 0x9a],
+            ),
+            (json!(null), &[0xc0]),
+            (json!(false), &[0xc2]),
+            (json!(""), &[0xa0]),
+            (json!("ü"), &[0xa2, 0xc3, 0xbc]),
+            (json!([]), &[0x90]),
+            (json!([1, [2]]), &[0x92, 0x01, 0x91, 0x02]),
+            (json!({}), &[0x80]),
+            (OwnedValue::Bytes(vec![1, 2]), &[0xc4, 0x02, 0x01, 0x02]),
+        ];
+        for (value, msgpack) in cases {
+            assert_eq!(&to_msgpack_vec(value), msgpack, "{}", value);
+            assert_eq!(
+                &to_msgpack_vec(&BorrowedValue::from(value.clone())),
+                msgpack
+            );
+            assert_eq!(&decode(msgpack).unwrap(), value);
+        }
+
+        // the longer length forms
+        let s = "x".repeat(32);
+        assert_eq!(to_msgpack_vec(&json!(s))[..2], [0xd9, 32]);
+        let s = "x".repeat(256);
+        assert_eq!(to_msgpack_vec(&json!(s))[..3], [0xda, 0x01, 0x00]);
+        let v = OwnedValue::Bytes(vec![0; 65_536]);
+        assert_eq!(to_msgpack_vec(&v)[..5], [0xc6, 0x00, 0x01, 0x00, 0x00]);
+        let v = OwnedValue::Array(vec![OwnedValue::Static(StaticNode::Null); 16]);
+        assert_eq!(to_msgpack_vec(&v)[..3], [0xdc, 0x00, 0x10]);
+        let v: OwnedValue = (0..16).map(|i| (i.to_string(), i)).collect();
+        let msgpack = to_msgpack_vec(&v);
+        assert_eq!(msgpack[..3], [0xde, 0x00, 0x10]);
+        assert_eq!(decode(&msgpack).unwrap(), v);
+    }
+
+    #[test]
+    fn decoding() {
+        let v = decode(&[
+            0xdf, 0x00, 0x00, 0x00, 0x02, 0xd9, 0x01, b'a', 0xdd, 0x00, 0x00, 0x00, 0x01, 0xc3,
+            0xdb, 0x00, 0x00, 0x00, 0x01, b'b', 0xc5, 0x00, 0x01, 0xff,
+        ])
+        .unwrap();
+        assert_eq!(v["a"], json!([true]));
+        assert_eq!(v["b"].as_bytes(), Some(&[0xff][..]));
+        assert_eq!(decode(&[0xcc, 0xff]).unwrap(), 255);
+        assert_eq!(decode(&[0xd0, 0x80]).unwrap(), -128);
+        // strings are borrowed
+        let data = [0xa3, b'a', b'b', b'c'];
+        assert!(matches!(
+            decode(&data).unwrap(),
+            BorrowedValue::String(s) if s.as_ptr() == data[1..].as_ptr()
+        ));
+        // deep nesting doesn't recurse, neither decoding nor encoding
+        let mut deep = vec![0x91; 100_000];
+        deep.push(0xc0);
+        let v = decode(&deep).unwrap();
+        assert_eq!(to_msgpack_vec(&v), deep);
+        v.dismantle();
+    }
+
+    #[test]
+    fn errors() {
+        let error = |msgpack: &[u8]| decode(msgpack).unwrap_err();
+        assert_eq!(error(&[]).error(), &ErrorType::EOF);
+        assert_eq!(error(&[0x92, 0x01]).error(), &ErrorType::EOF);
+        assert_eq!(
+            error(&[0xc6, 0xff, 0xff, 0xff, 0xff]).error(),
+            &ErrorType::EOF
+        );
+        assert_eq!(error(&[0x01, 0x02]).error(), &ErrorType::TrailingCharacters);
+        assert_eq!(
+            error(&[0x81, 0x01, 0x02]).error(),
+            &ErrorType::KeyMustBeAString
+        );
+        assert_eq!(error(&[0xa2, 0xc3, 0x28]).error(), &ErrorType::InvalidUTF8);
+        assert_eq!(error(&[0xc1]).error(), &ErrorType::UnexpectedCharacter);
+        assert_eq!(
+            error(&[0xd4, 0x01, 0x00]).error(),
+            &ErrorType::UnexpectedCharacter
+        );
+    }
+
+    #[cfg(feature = "128bit")]
+    #[test]
+    fn wide_integers() {
+        assert_eq!(
+            to_msgpack_vec(&OwnedValue::from(u128::from(u64::MAX))),
+            to_msgpack_vec(&OwnedValue::from(u64::MAX))
+        );
+        assert_eq!(to_msgpack_vec(&OwnedValue::from(-1_i128)), [0xff]);
+        let v = OwnedValue::from(i128::MIN);
+        assert_eq!(decode(&to_msgpack_vec(&v)).unwrap(), i128::MIN.to_string());
+        let v = OwnedValue::from(u128::MAX);
+        assert_eq!(decode(&to_msgpack_vec(&v)).unwrap(), u128::MAX.to_string());
+    }
+}
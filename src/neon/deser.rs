@@ -52,7 +52,7 @@ impl<'de> Deserializer<'de> {
         mut idx: usize,
     ) -> Result<&'de str> {
         use ErrorType::*;
-        let input: &mut [u8] = unsafe { std::mem::transmute(input) };
+        let input: &mut [u8] = unsafe { core::mem::transmute(input) };
         // Add 1 to skip the initial "
         idx += 1;
         //let mut read: usize = 0;
@@ -1,9 +1,10 @@
 #![allow(dead_code)]
 use crate::utf8check::Utf8Check;
 use crate::*;
+use alloc::vec::Vec;
+use core::mem;
 use simd_lite::aarch64::*;
 use simd_lite::NeonInit;
-use std::mem;
 
 // NEON-SPECIFIC
 #[cfg_attr(not(feature = "no-inline"), inline(always))]
@@ -72,10 +73,10 @@ pub(crate) struct SimdInput {
     v3: uint8x16_t,
 }
 
-impl SimdInput {
+impl Stage1Parse<int8x16_t> for SimdInput {
     #[cfg_attr(not(feature = "no-inline"), inline)]
     #[allow(clippy::cast_ptr_alignment)]
-    pub(crate) fn new(ptr: &[u8]) -> Self {
+    fn new(ptr: &[u8]) -> Self {
         unsafe {
             Self {
                 v0: vld1q_u8(ptr.as_ptr() as *const u8),
@@ -85,9 +86,7 @@ impl SimdInput {
             }
         }
     }
-}
 
-impl Stage1Parse<int8x16_t> for SimdInput {
     #[cfg_attr(not(feature = "no-inline"), inline(always))]
     fn new_utf8_checking_state() -> Utf8CheckingState<int8x16_t> {
         Utf8CheckingState {
@@ -289,7 +288,7 @@ impl Stage1Parse<int8x16_t> for SimdInput {
 
                 let v: int32x4_t = mem::transmute([v0, v1, v2, v3]);
                 let v: int32x4_t = vaddq_s32(idx_64_v, v);
-                std::ptr::write(base.as_mut_ptr().add(l) as *mut int32x4_t, v);
+                core::ptr::write(base.as_mut_ptr().add(l) as *mut int32x4_t, v);
             }
             l += 4;
         }
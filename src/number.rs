@@ -0,0 +1,133 @@
+use crate::charutils::is_structural_or_whitespace;
+use crate::{Deserializer, Error, ErrorType, Result, StaticNode, SIMDJSON_PADDING};
+#[cfg(feature = "std")]
+use crate::{EncodeOptions, OwnedValue};
+#[cfg(feature = "std")]
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+
+/// Parses `input`, all of it, as a JSON number with the parser's number
+/// parsing, for numbers in places other than JSON documents. Integers
+/// become `I64`s if they are negative and `U64`s otherwise, or with the
+/// `128bit` feature `I128`s and `U128`s if they need them. Numbers with a
+/// fraction or an exponent become `F64`s.
+///
+/// ```rust
+/// use simd_json::StaticNode;
+/// assert_eq!(simd_json::parse_number(b"-42"), Ok(StaticNode::I64(-42)));
+/// assert_eq!(simd_json::parse_number(b"1.5e3"), Ok(StaticNode::F64(1500.0)));
+/// assert!(simd_json::parse_number(b"0x1f").is_err());
+/// assert!(simd_json::parse_number(b"1 ").is_err());
+/// ```
+///
+/// # Errors
+///
+/// Will return `Err` if `input` isn't a JSON number, this includes
+/// leading `+`s and `0`s as well as whitespace around the number, or if
+/// it is an integer too large for the integer types.
+pub fn parse_number(input: &[u8]) -> Result<StaticNode> {
+    let first = match input.first() {
+        Some(&c) => c,
+        None => return Err(Error::new(0, '\0', ErrorType::EOF)),
+    };
+    // the parser stops at the first structural character or whitespace,
+    // there mustn't be anything after it
+    if let Some(idx) = input
+        .iter()
+        .position(|&c| is_structural_or_whitespace(c) != 0)
+    {
+        return Err(Error::new(
+            idx,
+            char::from(input[idx]),
+            ErrorType::InvalidNumber,
+        ));
+    }
+    // the parser reads past the end of numbers into the padding
+    let mut buf = Vec::with_capacity(input.len() + SIMDJSON_PADDING);
+    buf.extend_from_slice(input);
+    buf.resize(input.len() + SIMDJSON_PADDING, 0);
+    Deserializer::parse_number(0, &buf, first == b'-')
+}
+
+/// Writes `number` the way the serializer writes numbers, the shortest
+/// form that reads back as the same number. Floats that aren't finite
+/// are written as `null` like `EncodeOptions::default` does, and the
+/// other `StaticNode`s as their literals.
+///
+/// # Errors
+///
+/// if the write fails
+#[cfg(feature = "std")]
+pub fn write_number<W: Write>(writer: &mut W, number: StaticNode) -> io::Result<()> {
+    OwnedValue::Static(number).write_with(writer, EncodeOptions::default())
+}
+
+/// Formats `number` like `write_number` does.
+///
+/// ```rust
+/// use simd_json::StaticNode;
+/// assert_eq!(simd_json::format_number(StaticNode::F64(0.1)), "0.1");
+/// assert_eq!(simd_json::format_number(StaticNode::F64(1e21)), "1e21");
+/// assert_eq!(simd_json::format_number(StaticNode::I64(-7)), "-7");
+/// ```
+#[cfg(feature = "std")]
+#[must_use]
+pub fn format_number(number: StaticNode) -> String {
+    OwnedValue::Static(number).encode_with(EncodeOptions::default())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        // `StaticNode`s of different types compare equal
+        assert!(matches!(parse_number(b"0"), Ok(StaticNode::U64(0))));
+        assert!(matches!(
+            parse_number(b"18446744073709551615"),
+            Ok(StaticNode::U64(u64::MAX))
+        ));
+        assert_eq!(
+            parse_number(b"-9223372036854775808"),
+            Ok(StaticNode::I64(i64::MIN))
+        );
+        assert_eq!(parse_number(b"-0.5"), Ok(StaticNode::F64(-0.5)));
+        #[cfg(feature = "128bit")]
+        assert!(matches!(
+            parse_number(b"12345678901234567890123"),
+            Ok(StaticNode::U128(12_345_678_901_234_567_890_123))
+        ));
+        #[cfg(not(feature = "128bit"))]
+        assert_eq!(
+            parse_number(b"12345678901234567890123").map_err(|e| e.index()),
+            Err(20)
+        );
+        assert_eq!(parse_number(b"2E-2"), Ok(StaticNode::F64(0.02)));
+        let err = |input: &[u8]| parse_number(input).expect_err("parsed");
+        assert_eq!(err(b"").error(), &ErrorType::EOF);
+        for input in &[&b"+1"[..], b"01", b"1.", b".5", b"1e", b"-", b"1x", b"NaN"] {
+            assert!(parse_number(input).is_err(), "{:?}", input);
+        }
+        let e = err(b"12,3");
+        assert_eq!((e.index(), e.error()), (2, &ErrorType::InvalidNumber));
+        assert_eq!(err(b" 1").index(), 0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn format() {
+        for n in &["0", "-1", "18446744073709551615", "0.1", "-0.0025", "1e100"] {
+            let parsed = parse_number(n.as_bytes()).expect("parsed");
+            assert_eq!(format_number(parsed), *n);
+        }
+        assert_eq!(format_number(StaticNode::F64(-2.5e-10)), "-2.5e-10");
+        assert_eq!(format_number(StaticNode::F64(f64::NAN)), "null");
+        assert_eq!(format_number(StaticNode::Bool(true)), "true");
+        let mut out = b"n=".to_vec();
+        assert!(write_number(&mut out, StaticNode::U64(3)).is_ok());
+        assert_eq!(out, b"n=3");
+    }
+}
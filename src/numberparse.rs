@@ -1,19 +1,47 @@
 use crate::charutils::is_structural_or_whitespace;
+use crate::options::NumberSyntax;
 use crate::unlikely;
 use crate::StaticNode;
-use crate::{mem, static_cast_i64, Deserializer, ErrorType, Result};
+use crate::{
+    Deserializer, Error, ErrorType, IntegerOverflow, Node, ParseOptions, Result, SIMDJSON_PADDING,
+};
+use alloc::vec::Vec;
 
-#[cfg(all(target_arch = "x86", feature = "swar-number-parsing"))]
-use std::arch::x86::{
+#[cfg(all(
+    target_arch = "x86",
+    feature = "swar-number-parsing",
+    not(feature = "safe")
+))]
+use core::arch::x86::{
     __m128i, _mm_cvtsi128_si32, _mm_loadu_si128, _mm_madd_epi16, _mm_maddubs_epi16,
     _mm_packus_epi32, _mm_set1_epi8, _mm_setr_epi16, _mm_setr_epi8, _mm_sub_epi8,
 };
-#[cfg(all(target_arch = "x86_64", feature = "swar-number-parsing"))]
-use std::arch::x86_64::{
+#[cfg(all(
+    target_arch = "x86_64",
+    feature = "swar-number-parsing",
+    not(feature = "safe")
+))]
+use core::arch::x86_64::{
     __m128i, _mm_cvtsi128_si32, _mm_loadu_si128, _mm_madd_epi16, _mm_maddubs_epi16,
     _mm_packus_epi32, _mm_set1_epi8, _mm_setr_epi16, _mm_setr_epi8, _mm_sub_epi8,
 };
 
+// The input is followed by zero padding so numbers can be read without
+// bounds checks, the safe version pretends the padding is there instead.
+#[cfg(not(feature = "safe"))]
+macro_rules! byte {
+    ($buf:expr, $i:expr) => {
+        unsafe { *$buf.get_unchecked($i) }
+    };
+}
+
+#[cfg(feature = "safe")]
+macro_rules! byte {
+    ($buf:expr, $i:expr) => {
+        $buf.get($i).copied().unwrap_or(0)
+    };
+}
+
 const POWER_OF_TEN: [f64; 632] = [
     1e-323, 1e-322, 1e-321, 1e-320, 1e-319, 1e-318, 1e-317, 1e-316, 1e-315, 1e-314, 1e-313, 1e-312,
     1e-311, 1e-310, 1e-309, 1e-308, 1e-307, 1e-306, 1e-305, 1e-304, 1e-303, 1e-302, 1e-301, 1e-300,
@@ -98,7 +126,7 @@ const STRUCTURAL_OR_WHITESPACE_OR_EXPONENT_OR_DECIMAL_NEGATED: [bool; 256] = [
 
 #[cfg_attr(not(feature = "no-inline"), inline(always))]
 fn is_not_structural_or_whitespace_or_exponent_or_decimal(c: u8) -> bool {
-    unsafe { *STRUCTURAL_OR_WHITESPACE_OR_EXPONENT_OR_DECIMAL_NEGATED.get_unchecked(c as usize) }
+    STRUCTURAL_OR_WHITESPACE_OR_EXPONENT_OR_DECIMAL_NEGATED[c as usize]
 }
 
 // #ifdef _MSC_VER
@@ -106,7 +134,7 @@ fn is_not_structural_or_whitespace_or_exponent_or_decimal(c: u8) -> bool {
 // at a glance, it looks better than Mula's
 // http://0x80.pl/articles/swar-digits-validate.html
 
-#[cfg(all(feature = "swar-number-parsing"))]
+#[cfg(all(feature = "swar-number-parsing", not(feature = "safe")))]
 #[cfg_attr(not(feature = "no-inline"), inline)]
 #[allow(clippy::cast_ptr_alignment)]
 fn is_made_of_eight_digits_fast(chars: &[u8]) -> bool {
@@ -126,7 +154,8 @@ fn is_made_of_eight_digits_fast(chars: &[u8]) -> bool {
 #[cfg_attr(not(feature = "no-inline"), inline)]
 #[cfg(all(
     any(target_arch = "x86", target_arch = "x86_64"),
-    feature = "swar-number-parsing"
+    feature = "swar-number-parsing",
+    not(feature = "safe")
 ))]
 #[allow(
     clippy::cast_sign_loss,
@@ -155,14 +184,19 @@ fn parse_eight_digits_unrolled(chars: &[u8]) -> u32 {
 }
 
 #[cfg_attr(not(feature = "no-inline"), inline)]
-#[cfg(target_feature = "neon")]
+#[cfg(all(
+    not(any(target_arch = "x86", target_arch = "x86_64")),
+    feature = "swar-number-parsing",
+    not(feature = "safe")
+))]
+#[allow(clippy::cast_ptr_alignment, clippy::cast_possible_truncation)]
 fn parse_eight_digits_unrolled(chars: &[u8]) -> u32 {
     let val: u64 = unsafe { *(chars.as_ptr() as *const u64) };
     //    memcpy(&val, chars, sizeof(u64));
-    let val = (val & 0x0F0F0F0F0F0F0F0F).wrapping_mul(2561) >> 8;
-    let val = (val & 0x00FF00FF00FF00FF).wrapping_mul(6553601) >> 16;
+    let val = (val & 0x0F0F_0F0F_0F0F_0F0F).wrapping_mul(2561) >> 8;
+    let val = (val & 0x00FF_00FF_00FF_00FF).wrapping_mul(6_553_601) >> 16;
 
-    return ((val & 0x0000FFFF0000FFFF).wrapping_mul(42949672960001) >> 32) as u32;
+    ((val & 0x0000_FFFF_0000_FFFF).wrapping_mul(42_949_672_960_001) >> 32) as u32
 }
 
 impl<'de> Deserializer<'de> {
@@ -188,26 +222,26 @@ impl<'de> Deserializer<'de> {
         let mut i: f64;
         let mut digit: u8;
         let mut d;
-        if unsafe { *p.get_unchecked(digitcount) } == b'0' {
+        if byte!(p, digitcount) == b'0' {
             // 0 cannot be followed by an integer
             digitcount += 1;
             i = 0.0;
         } else {
-            digit = unsafe { *p.get_unchecked(digitcount) } - b'0';
+            digit = byte!(p, digitcount) - b'0';
             i = f64::from(digit);
             digitcount += 1;
-            while is_integer(unsafe { *p.get_unchecked(digitcount) }) {
-                digit = unsafe { *p.get_unchecked(digitcount) } - b'0';
+            while is_integer(byte!(p, digitcount)) {
+                digit = byte!(p, digitcount) - b'0';
                 i = 10.0 * i + f64::from(digit);
                 digitcount += 1;
             }
         }
-        if unsafe { *p.get_unchecked(digitcount) } == b'.' {
+        if byte!(p, digitcount) == b'.' {
             let mut fraction: u64 = 0;
             let mut fraction_weight: u64 = 10;
             digitcount += 1;
             //let mut fractionalweight: f64 = 1.0;
-            d = unsafe { *p.get_unchecked(digitcount) };
+            d = byte!(p, digitcount);
             if is_integer(d) {
                 digit = d - b'0';
                 digitcount += 1;
@@ -220,10 +254,9 @@ impl<'de> Deserializer<'de> {
                 ));
             }
 
-            while is_integer(unsafe { *p.get_unchecked(digitcount) })
-                && fraction_weight <= 10_000_000_000_000_000_u64
+            while is_integer(byte!(p, digitcount)) && fraction_weight <= 10_000_000_000_000_000_u64
             {
-                digit = unsafe { *p.get_unchecked(digitcount) } - b'0';
+                digit = byte!(p, digitcount) - b'0';
                 digitcount += 1;
                 fraction_weight *= 10;
                 fraction = fraction * 10 + u64::from(digit);
@@ -232,26 +265,24 @@ impl<'de> Deserializer<'de> {
             let mut fraction_weight = fraction_weight as f64;
             let mut fraction = (fraction as f64) / fraction_weight;
 
-            while is_integer(unsafe { *p.get_unchecked(digitcount) }) {
-                digit = unsafe { *p.get_unchecked(digitcount) } - b'0';
+            while is_integer(byte!(p, digitcount)) {
+                digit = byte!(p, digitcount) - b'0';
                 digitcount += 1;
                 fraction_weight *= 10.0;
                 fraction += f64::from(digit) / fraction_weight;
             }
             i += fraction;
         }
-        if (unsafe { *p.get_unchecked(digitcount) } == b'e')
-            || (unsafe { *p.get_unchecked(digitcount) } == b'E')
-        {
+        if (byte!(p, digitcount) == b'e') || (byte!(p, digitcount) == b'E') {
             digitcount += 1;
             let mut negexp: bool = false;
-            if unsafe { *p.get_unchecked(digitcount) } == b'-' {
+            if byte!(p, digitcount) == b'-' {
                 negexp = true;
                 digitcount += 1;
-            } else if unsafe { *p.get_unchecked(digitcount) } == b'+' {
+            } else if byte!(p, digitcount) == b'+' {
                 digitcount += 1;
             }
-            d = unsafe { *p.get_unchecked(digitcount) };
+            d = byte!(p, digitcount);
             if !is_integer(d) {
                 return Err(Self::raw_error(
                     idx + digitcount,
@@ -259,28 +290,28 @@ impl<'de> Deserializer<'de> {
                     ErrorType::InvalidNumber,
                 ));
             }
-            digit = unsafe { *p.get_unchecked(digitcount) } - b'0';
+            digit = byte!(p, digitcount) - b'0';
             let mut expnumber: u32 = u32::from(digit); // exponential part
             digitcount += 1;
-            d = unsafe { *p.get_unchecked(digitcount) };
+            d = byte!(p, digitcount);
             if is_integer(d) {
                 digit = d - b'0';
                 expnumber = 10 * expnumber + u32::from(digit);
                 digitcount += 1;
             }
-            d = unsafe { *p.get_unchecked(digitcount) };
+            d = byte!(p, digitcount);
             if is_integer(d) {
                 digit = d - b'0';
                 expnumber = 10 * expnumber + u32::from(digit);
                 digitcount += 1;
             }
-            d = unsafe { *p.get_unchecked(digitcount) };
+            d = byte!(p, digitcount);
             if is_integer(d) {
                 digit = d - b'0';
                 expnumber = 10 * expnumber + u32::from(digit);
                 digitcount += 1;
             }
-            d = unsafe { *p.get_unchecked(digitcount) };
+            d = byte!(p, digitcount);
             if is_integer(d) {
                 // we refuse to parse this
                 return Err(Self::raw_error(
@@ -305,7 +336,7 @@ impl<'de> Deserializer<'de> {
             i *= POWER_OF_TEN[(323 + exponent) as usize];
         }
 
-        d = unsafe { *p.get_unchecked(digitcount) };
+        d = byte!(p, digitcount);
         if is_structural_or_whitespace(d) == 0 {
             Err(Self::raw_error(
                 idx + digitcount,
@@ -331,18 +362,18 @@ impl<'de> Deserializer<'de> {
     fn parse_large_integer(idx: usize, buf: &[u8], negative: bool) -> Result<StaticNode> {
         let mut digitcount = if negative { 1 } else { 0 };
         let mut i: u64;
-        let mut d = unsafe { *buf.get_unchecked(digitcount) };
+        let mut d = byte!(buf, digitcount);
         let mut digit: u8;
 
         if d == b'0' {
             digitcount += 1;
-            d = unsafe { *buf.get_unchecked(digitcount) };
+            d = byte!(buf, digitcount);
             i = 0;
         } else {
             digit = d - b'0';
             i = u64::from(digit);
             digitcount += 1;
-            d = unsafe { *buf.get_unchecked(digitcount) };
+            d = byte!(buf, digitcount);
             // the is_made_of_eight_digits_fast routine is unlikely to help here because
             // we rarely see large integer parts like 123456789
             while is_integer(d) {
@@ -360,7 +391,7 @@ impl<'de> Deserializer<'de> {
                     ));
                 }
                 digitcount += 1;
-                d = unsafe { *buf.get_unchecked(digitcount) };
+                d = byte!(buf, digitcount);
             }
         }
 
@@ -380,7 +411,7 @@ impl<'de> Deserializer<'de> {
                 ErrorType::InvalidNumber,
             ))
         } else if negative {
-            unsafe { Ok(StaticNode::I64(static_cast_i64!(i.wrapping_neg()))) }
+            Ok(StaticNode::I64(i.wrapping_neg() as i64))
         } else {
             Ok(StaticNode::U64(i))
         }
@@ -390,21 +421,21 @@ impl<'de> Deserializer<'de> {
     #[inline(never)]
     #[allow(clippy::cast_possible_wrap)]
     fn parse_large_integer(idx: usize, buf: &[u8], negative: bool) -> Result<StaticNode> {
-        use std::convert::TryFrom;
+        use core::convert::TryFrom;
         let mut digitcount = if negative { 1 } else { 0 };
         let mut i: u128;
-        let mut d = unsafe { *buf.get_unchecked(digitcount) };
+        let mut d = byte!(buf, digitcount);
         let mut digit: u8;
 
         if d == b'0' {
             digitcount += 1;
-            d = unsafe { *buf.get_unchecked(digitcount) };
+            d = byte!(buf, digitcount);
             i = 0;
         } else {
             digit = d - b'0';
             i = u128::from(digit);
             digitcount += 1;
-            d = unsafe { *buf.get_unchecked(digitcount) };
+            d = byte!(buf, digitcount);
             // the is_made_of_eight_digits_fast routine is unlikely to help here because
             // we rarely see large integer parts like 123456789
             while is_integer(d) {
@@ -422,7 +453,7 @@ impl<'de> Deserializer<'de> {
                     ));
                 }
                 digitcount += 1;
-                d = unsafe { *buf.get_unchecked(digitcount) };
+                d = byte!(buf, digitcount);
             }
         }
 
@@ -442,7 +473,7 @@ impl<'de> Deserializer<'de> {
                 ErrorType::InvalidNumber,
             ))
         } else if negative {
-            let i = unsafe { static_cast_i128!(i.wrapping_neg()) };
+            let i = i.wrapping_neg() as i128;
             if let Ok(i) = i64::try_from(i) {
                 Ok(StaticNode::I64(i))
             } else {
@@ -470,12 +501,12 @@ impl<'de> Deserializer<'de> {
         let mut ignore_count: u8 = 0;
         //let startdigits: *const u8 = p;
         let mut i: u64;
-        let mut d = unsafe { *buf.get_unchecked(byte_count) };
+        let mut d = byte!(buf, byte_count);
         let mut digit: u8;
         if d == b'0' {
             // 0 cannot be followed by an integer
             byte_count += 1;
-            d = unsafe { *buf.get_unchecked(byte_count) };
+            d = byte!(buf, byte_count);
             if is_not_structural_or_whitespace_or_exponent_or_decimal(d) {
                 return Err(Self::raw_error(
                     idx + byte_count,
@@ -497,7 +528,7 @@ impl<'de> Deserializer<'de> {
             i = u64::from(digit);
             byte_count += 1;
 
-            d = unsafe { *buf.get_unchecked(byte_count) };
+            d = byte!(buf, byte_count);
             // the is_made_of_eight_digits_fast routine is unlikely to help here because
             // we rarely see large integer parts like 123456789
             while is_integer(d) {
@@ -514,14 +545,14 @@ impl<'de> Deserializer<'de> {
                 }
                 //i = 10 * i + u64::from(digit); // might overflow
                 byte_count += 1;
-                d = unsafe { *buf.get_unchecked(byte_count) };
+                d = byte!(buf, byte_count);
             }
         }
 
         let mut exponent: i64 = if d == b'.' {
             ignore_count += 1;
             byte_count += 1;
-            d = unsafe { *buf.get_unchecked(byte_count) };
+            d = byte!(buf, byte_count);
             let firstafterperiod = byte_count;
             if is_integer(d) {
                 digit = d - b'0';
@@ -537,7 +568,7 @@ impl<'de> Deserializer<'de> {
             // this helps if we have lots of decimals!
             // this turns out to be frequent enough.
 
-            #[cfg(feature = "swar-number-parsing")]
+            #[cfg(all(feature = "swar-number-parsing", not(feature = "safe")))]
             {
                 // FIXME
                 // can we omit this: buf.len() - byte_count >= 8
@@ -549,12 +580,12 @@ impl<'de> Deserializer<'de> {
                     byte_count += 8;
                 }
             }
-            d = unsafe { *buf.get_unchecked(byte_count) };
+            d = byte!(buf, byte_count);
             while is_integer(d) {
                 digit = d - b'0';
                 i = i.wrapping_mul(10).wrapping_add(u64::from(digit));
                 byte_count += 1;
-                d = unsafe { *buf.get_unchecked(byte_count) };
+                d = byte!(buf, byte_count);
             }
             firstafterperiod as i64 - byte_count as i64
         } else {
@@ -564,17 +595,17 @@ impl<'de> Deserializer<'de> {
         if (d == b'e') || (d == b'E') {
             ignore_count += 1;
             byte_count += 1;
-            d = unsafe { *buf.get_unchecked(byte_count) };
+            d = byte!(buf, byte_count);
             let mut negexp: bool = false;
             if d == b'-' {
                 negexp = true;
                 ignore_count += 1;
                 byte_count += 1;
-                d = unsafe { *buf.get_unchecked(byte_count) };
+                d = byte!(buf, byte_count);
             } else if d == b'+' {
                 ignore_count += 1;
                 byte_count += 1;
-                d = unsafe { *buf.get_unchecked(byte_count) };
+                d = byte!(buf, byte_count);
             }
             if !is_integer(d) {
                 return Err(Self::raw_error(
@@ -587,20 +618,20 @@ impl<'de> Deserializer<'de> {
             expnumber = i16::from(digit);
             byte_count += 1;
             ignore_count += 1;
-            d = unsafe { *buf.get_unchecked(byte_count) };
+            d = byte!(buf, byte_count);
             if is_integer(d) {
                 digit = d - b'0';
                 expnumber = 10 * expnumber + i16::from(digit);
                 ignore_count += 1;
                 byte_count += 1;
-                d = unsafe { *buf.get_unchecked(byte_count) };
+                d = byte!(buf, byte_count);
             }
             if is_integer(d) {
                 digit = d - b'0';
                 expnumber = 10 * expnumber + i16::from(digit);
                 ignore_count += 1;
                 byte_count += 1;
-                d = unsafe { *buf.get_unchecked(byte_count) };
+                d = byte!(buf, byte_count);
             }
             if is_integer(d) {
                 // we refuse to parse this
@@ -640,7 +671,7 @@ impl<'de> Deserializer<'de> {
                 return Self::parse_large_integer(idx, buf, negative);
             }
             if negative {
-                unsafe { StaticNode::I64(static_cast_i64!(i.wrapping_neg())) }
+                StaticNode::I64(i.wrapping_neg() as i64)
             } else {
                 StaticNode::U64(i)
             }
@@ -655,6 +686,251 @@ impl<'de> Deserializer<'de> {
             Ok(v)
         }
     }
+
+    /// Parses the number at the start of `buf`, numbers cut off by the end
+    /// of the input are reported as `ErrorType::Incomplete`.
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    pub(crate) fn parse_number(idx: usize, buf: &[u8], negative: bool) -> Result<StaticNode> {
+        Self::parse_number_int(idx, buf, negative).map_err(|e| Self::incomplete_number(idx, buf, e))
+    }
+
+    /// Parses the number at the start of `buf` like `parse_number`, when
+    /// that fails and `options` relax the number syntax it is parsed again
+    /// with the relaxed syntax, and integers that are too large are read
+    /// as floats if `options` say so. The error of the strict parse is
+    /// returned if that fails as well.
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    pub(crate) fn parse_number_with(
+        idx: usize,
+        buf: &[u8],
+        negative: bool,
+        options: ParseOptions,
+    ) -> Result<StaticNode> {
+        match Self::parse_number(idx, buf, negative) {
+            Err(e) if e.error() == &ErrorType::Overflow => {
+                Self::parse_overflowed(idx, buf, negative, options).ok_or(e)
+            }
+            Err(e) if options.relaxes_numbers() => {
+                Self::parse_relaxed_number(buf, options).ok_or(e)
+            }
+            res => res,
+        }
+    }
+
+    /// Parses the number at `idx` into a node like `parse_number_with`,
+    /// integers that are too large become strings of their digits if
+    /// `options` say so
+    #[cfg(not(feature = "safe"))]
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    pub(crate) fn parse_number_node(
+        input: &'de [u8],
+        idx: usize,
+        buf: &[u8],
+        negative: bool,
+        options: ParseOptions,
+    ) -> Result<Node<'de>> {
+        match Self::parse_number_with(idx, buf, negative, options) {
+            Ok(n) => Ok(Node::Static(n)),
+            Err(e) => match overflowed_integer_len(&e, buf, options) {
+                // strings never overwrite the numbers between them and the
+                // digits are ASCII
+                Some(len) => Ok(Node::String(unsafe {
+                    core::str::from_utf8_unchecked(input.get_unchecked(idx..idx + len))
+                })),
+                None => Err(e),
+            },
+        }
+    }
+
+    /// Parses the number at `idx` into a node like `parse_number_with`,
+    /// integers that are too large become strings of their digits if
+    /// `options` say so. Those are split off of `input` like strings are.
+    #[cfg(feature = "safe")]
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    #[allow(clippy::mut_mut)]
+    pub(crate) fn parse_number_node(
+        input: &mut &'de mut [u8],
+        offset: &mut usize,
+        idx: usize,
+        buf: &[u8],
+        negative: bool,
+        options: ParseOptions,
+    ) -> Result<Node<'de>> {
+        match Self::parse_number_with(idx, buf, negative, options) {
+            Ok(n) => Ok(Node::Static(n)),
+            Err(e) => match overflowed_integer_len(&e, buf, options) {
+                Some(len) => {
+                    let (head, tail) = core::mem::take(input).split_at_mut(idx + len - *offset);
+                    let head: &'de [u8] = head;
+                    let digits = &head[idx - *offset..];
+                    *input = tail;
+                    *offset = idx + len;
+                    core::str::from_utf8(digits)
+                        .map(Node::String)
+                        .map_err(|_| e)
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    /// Parses a number that is too large for the integer types as the
+    /// closest float, if `options` say so. A float with an integer part
+    /// that is too large is parsed as well unless too large integers are
+    /// errors.
+    #[cold]
+    fn parse_overflowed(
+        idx: usize,
+        buf: &[u8],
+        negative: bool,
+        options: ParseOptions,
+    ) -> Option<StaticNode> {
+        match (options.integer_overflow, integer_len(buf)) {
+            (IntegerOverflow::Error, _) | (IntegerOverflow::String, Some(_)) => None,
+            (IntegerOverflow::Float, Some(len)) => core::str::from_utf8(&buf[..len])
+                .ok()?
+                .parse::<f64>()
+                .ok()
+                .filter(|f| f.is_finite())
+                .map(StaticNode::F64),
+            (_, None) => Self::parse_float(idx, buf, negative).ok(),
+        }
+    }
+
+    /// Rewrites the number at the start of `buf` into one with the strict
+    /// syntax, as far as `options` allow, and parses that
+    #[cold]
+    fn parse_relaxed_number(buf: &[u8], options: ParseOptions) -> Option<StaticNode> {
+        let len = buf
+            .iter()
+            .position(|c| is_structural_or_whitespace(*c) != 0)
+            .unwrap_or(buf.len());
+        let mut strict = Vec::with_capacity(len + 2 + SIMDJSON_PADDING);
+        let (negative, number) = match &buf[..len] {
+            [b'-', number @ ..] => {
+                strict.push(b'-');
+                (true, number)
+            }
+            [b'+', number @ ..] if options.number_syntax.allows(NumberSyntax::LEADING_PLUS) => {
+                (false, number)
+            }
+            number => (false, number),
+        };
+        let digits = number.iter().take_while(|c| c.is_ascii_digit()).count();
+        let (int, rest) = number.split_at(digits);
+        let starts_fraction = matches!(rest, [b'.', c, ..] if c.is_ascii_digit());
+        match int {
+            [] if options.number_syntax.allows(NumberSyntax::BARE_FRACTION) && starts_fraction => {
+                strict.push(b'0')
+            }
+            [] => return None,
+            [b'0', _, ..] if options.number_syntax.allows(NumberSyntax::LEADING_ZEROS) => {
+                let zeros = int.iter().take_while(|c| **c == b'0').count();
+                strict.extend_from_slice(&int[zeros.min(int.len() - 1)..]);
+            }
+            _ => strict.extend_from_slice(int),
+        }
+        match rest {
+            [b'.', tail @ ..]
+                if !starts_fraction && options.number_syntax.allows(NumberSyntax::TRAILING_DOT) =>
+            {
+                strict.extend_from_slice(b".0");
+                strict.extend_from_slice(tail);
+            }
+            _ => strict.extend_from_slice(rest),
+        }
+        strict.resize(strict.len() + SIMDJSON_PADDING, 0);
+        match Self::parse_number_int(0, &strict, negative) {
+            Err(e) if e.error() == &ErrorType::Overflow => {
+                Self::parse_overflowed(0, &strict, negative, options)
+            }
+            res => res.ok(),
+        }
+    }
+
+    #[cold]
+    fn incomplete_number(idx: usize, buf: &[u8], e: Error) -> Error {
+        // `buf` ends with the input, so if the number runs all the way to
+        // the end and stops where more digits are required it was cut off
+        let runs_to_end = buf.iter().all(|c| is_structural_or_whitespace(*c) == 0);
+        let last = buf.last().copied().unwrap_or(b' ');
+        if runs_to_end && matches!(last, b'-' | b'+' | b'.' | b'e' | b'E') {
+            Self::raw_error(
+                idx + buf.len() - 1,
+                last as char,
+                ErrorType::Incomplete { needed: Some(1) },
+            )
+        } else {
+            e
+        }
+    }
+
+    /// Parses the `NaN`, `Infinity` and `-Infinity` literals, they are only
+    /// accepted when `ParseOptions::allow_non_finite` is set.
+    #[cold]
+    pub(crate) fn parse_non_finite(idx: usize, buf: &[u8]) -> Result<StaticNode> {
+        let (atom, n): (&[u8], f64) = match buf.first() {
+            Some(b'N') => (b"NaN", f64::NAN),
+            Some(b'I') => (b"Infinity", f64::INFINITY),
+            _ => (b"-Infinity", f64::NEG_INFINITY),
+        };
+        if buf.starts_with(atom)
+            && buf
+                .get(atom.len())
+                .map_or(true, |c| is_structural_or_whitespace(*c) != 0)
+        {
+            Ok(StaticNode::F64(n))
+        } else if buf.len() < atom.len() && atom.starts_with(buf) {
+            let needed = Some(atom.len() - buf.len());
+            let c = buf.last().map_or('?', |c| *c as char);
+            Err(Self::raw_error(
+                idx + buf.len() - 1,
+                c,
+                ErrorType::Incomplete { needed },
+            ))
+        } else {
+            let c = buf.first().map_or('?', |c| *c as char);
+            Err(Self::raw_error(idx, c, ErrorType::InvalidNumber))
+        }
+    }
+}
+
+/// The length of the integer at the start of `buf` that failed to parse
+/// with `e`, if it is too large and `options` keep those as strings
+pub(crate) fn overflowed_integer_len(
+    e: &Error,
+    buf: &[u8],
+    options: ParseOptions,
+) -> Option<usize> {
+    if options.integer_overflow == IntegerOverflow::String && e.error() == &ErrorType::Overflow {
+        integer_len(buf)
+    } else {
+        None
+    }
+}
+
+/// The length of the number at the start of `buf` if it is an integer,
+/// without a fraction or an exponent
+fn integer_len(buf: &[u8]) -> Option<usize> {
+    let sign = usize::from(buf.first() == Some(&b'-'));
+    let digits = buf[sign..]
+        .iter()
+        .take_while(|c| c.is_ascii_digit())
+        .count();
+    let len = sign + digits;
+    let end = buf.get(len).copied().unwrap_or(b' ');
+    if digits > 0 && is_structural_or_whitespace(end) != 0 {
+        Some(len)
+    } else {
+        None
+    }
+}
+
+/// Checks if `buf` starts like one of the `NaN`, `Infinity` or `-Infinity`
+/// literals.
+#[cfg_attr(not(feature = "no-inline"), inline(always))]
+pub(crate) fn is_non_finite_start(buf: &[u8]) -> bool {
+    matches!(buf, [b'N' | b'I', ..] | [b'-', b'I', ..])
 }
 
 #[cfg(test)]
@@ -701,6 +977,128 @@ mod test {
         assert!(v_too_small.is_err());
     }
 
+    #[test]
+    fn non_finite() {
+        use crate::{to_owned_value_with_options, ParseOptions};
+        let options = ParseOptions::default().allow_non_finite(true);
+        let mut d = br#"[NaN, Infinity, -Infinity, {"a": NaN}]"#.to_vec();
+        let v = to_owned_value_with_options(&mut d, options).expect("failed to parse");
+        assert!(v[0].as_f64().expect("not a float").is_nan());
+        assert_eq!(v[1], f64::INFINITY);
+        assert_eq!(v[2], f64::NEG_INFINITY);
+        assert!(v[3]["a"].as_f64().expect("not a float").is_nan());
+
+        let mut d = b"-Infinity".to_vec();
+        let v = to_owned_value_with_options(&mut d, options).expect("failed to parse");
+        assert_eq!(v, f64::NEG_INFINITY);
+
+        let mut d = b"[NaN]".to_vec();
+        assert!(to_value(&mut d).is_err());
+        let mut d = b"[Nan]".to_vec();
+        assert!(to_owned_value_with_options(&mut d, options).is_err());
+        let mut d = b"[Infinityy]".to_vec();
+        assert!(to_owned_value_with_options(&mut d, options).is_err());
+        let mut d = b"[-Inf]".to_vec();
+        assert!(to_owned_value_with_options(&mut d, options).is_err());
+    }
+
+    #[test]
+    fn relaxed_syntax() {
+        use crate::{ErrorType, OwnedValue, ParseOptions, StaticNode};
+        let parse =
+            |options: ParseOptions, s: &str| options.to_owned_value(&mut s.as_bytes().to_vec());
+        let all = ParseOptions::lenient();
+        let v = parse(
+            all,
+            "[007, -007, 00, -0, 00.50, +1, +0.5e1, .5, -.5, +.5, 1., -1.e2]",
+        )
+        .expect("failed to parse");
+        assert_eq!(
+            v,
+            json!([7, -7, 0, 0, 0.5, 1, 5.0, 0.5, -0.5, 0.5, 1.0, -100.0])
+        );
+        assert_eq!(v[0], OwnedValue::Static(StaticNode::U64(7)));
+        assert_eq!(v[10], OwnedValue::Static(StaticNode::F64(1.0)));
+        // in objects, at the root and at the end of the input
+        assert_eq!(parse(all, r#"{"a": +01}"#).ok(), Some(json!({"a": 1})));
+        assert_eq!(parse(all, ".25").ok(), Some(json!(0.25)));
+        assert_eq!(parse(all, "2.").ok(), Some(json!(2.0)));
+        for s in &[
+            "+", ".", "-.", "+-1", "-+1", "++1", "..5", "1..", ".e5", "+.", "1.5.", "01x",
+        ] {
+            assert!(parse(all, s).is_err(), "{}", s);
+        }
+        // only what is turned on is relaxed, and the errors are those of
+        // the strict syntax
+        let zeros = ParseOptions::default().allow_leading_zeros(true);
+        assert_eq!(parse(zeros, "[010]").ok(), Some(json!([10])));
+        let e = parse(zeros, "[+1]").expect_err("parsed");
+        assert_eq!((e.index(), e.error()), (1, &ErrorType::InvalidNumber));
+        let e = parse(zeros, "[1.]").expect_err("parsed");
+        assert_eq!((e.index(), e.error()), (3, &ErrorType::InvalidNumber));
+        let plus = ParseOptions::default().allow_leading_plus(true);
+        assert_eq!(parse(plus, "+1").ok(), Some(json!(1)));
+        let e = parse(plus, "[+01]").expect_err("parsed");
+        assert_eq!(e.error(), &ErrorType::InvalidNumber);
+        assert!(parse(plus, "+.5").is_err());
+        let fraction = ParseOptions::default().allow_bare_fraction(true);
+        assert_eq!(parse(fraction, "-.5").ok(), Some(json!(-0.5)));
+        assert!(parse(fraction, "5.").is_err());
+        let dot = ParseOptions::default().allow_trailing_dot(true);
+        assert_eq!(parse(dot, "[5., 5.e1]").ok(), Some(json!([5.0, 50.0])));
+        assert!(parse(dot, ".5").is_err());
+        for s in &["010", "+1", ".5", "1."] {
+            assert!(parse(ParseOptions::default(), s).is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn integer_overflow() {
+        use crate::value::owned::to_value_recovering_with_options;
+        use crate::{ErrorType, IntegerOverflow, ParseOptions};
+        // too large for `u128` as well
+        let big = "10000000000000000000000000000000000000000";
+        let d = alloc::format!(r#"[{big}, -{big}, {{"a": {big}}}, 1, "s"]"#);
+        let parse = |options: ParseOptions| options.to_owned_value(&mut d.as_bytes().to_vec());
+        let e = parse(ParseOptions::default()).expect_err("parsed");
+        assert_eq!(e.error(), &ErrorType::Overflow);
+        let floats = ParseOptions::default().integer_overflow(IntegerOverflow::Float);
+        let v = parse(floats).expect("failed to parse");
+        assert_eq!(v, json!([1e40, -1e40, {"a": 1e40}, 1, "s"]));
+        let strings = ParseOptions::default().integer_overflow(IntegerOverflow::String);
+        let minus = alloc::format!("-{big}");
+        let v = parse(strings).expect("failed to parse");
+        assert_eq!(v, json!([big, minus, {"a": big}, 1, "s"]));
+        let mut borrowed = d.as_bytes().to_vec();
+        let v = strings
+            .to_borrowed_value(&mut borrowed)
+            .expect("failed to parse");
+        assert_eq!(v, json!([big, minus, {"a": big}, 1, "s"]));
+        assert_eq!(
+            strings.to_owned_value(&mut big.as_bytes().to_vec()).ok(),
+            Some(json!(big))
+        );
+        // floats are floats and other errors stay errors
+        let mut float = alloc::format!("{big}.5").into_bytes();
+        assert_eq!(strings.to_owned_value(&mut float).ok(), Some(json!(1e40)));
+        let huge = "9".repeat(400);
+        assert!(floats.to_owned_value(&mut huge.into_bytes()).is_err());
+        for s in &["1e400", "[1x]"] {
+            assert!(floats.to_owned_value(&mut s.as_bytes().to_vec()).is_err());
+            assert!(strings.to_owned_value(&mut s.as_bytes().to_vec()).is_err());
+        }
+        let mut d = alloc::format!("[{big}x]").into_bytes();
+        assert!(strings.to_owned_value(&mut d).is_err());
+        // with the relaxed syntax
+        let relaxed = floats.allow_leading_plus(true);
+        let mut d = alloc::format!("+{big}").into_bytes();
+        assert_eq!(relaxed.to_owned_value(&mut d).ok(), Some(json!(1e40)));
+        let mut d = alloc::format!("[{big}, 1,]").into_bytes();
+        let (v, errors) = to_value_recovering_with_options(&mut d, strings).expect("failed");
+        assert_eq!(v, json!([big, 1]));
+        assert_eq!(errors.len(), 1);
+    }
+
     #[test]
     fn bad_dot() {
         let mut i = String::from("1.");
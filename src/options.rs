@@ -0,0 +1,810 @@
+use crate::escape::repair_escapes;
+use crate::{BorrowedValue, Node, OwnedValue, Result};
+#[cfg(feature = "unicode")]
+use alloc::borrow::Cow;
+use alloc::{string::String, vec::Vec};
+
+/// Options that change how the parser treats its input. The defaults
+/// follow RFC 8259 strictly, every relaxation has to be opted into.
+///
+/// ```rust
+/// use simd_json::{ParseOptions, prelude::*};
+/// let mut d = br#"[NaN, -Infinity]"#.to_vec();
+/// let options = ParseOptions::default().allow_non_finite(true);
+/// let v = simd_json::to_owned_value_with_options(&mut d, options).unwrap();
+/// assert!(v[0].as_f64().unwrap().is_nan());
+/// assert_eq!(v[1], std::f64::NEG_INFINITY);
+/// ```
+///
+/// The options are cheap to copy and can be kept to parse any number of
+/// documents with them, see `ParseOptions::to_owned_value` and its
+/// siblings, instead of passing them to the `_with_options` functions.
+///
+/// Instead of setting every option there are three presets to start
+/// from:
+///
+/// | preset      | non finite floats | relaxed numbers | too large integers | invalid escapes | invalid UTF-8 | BOM      | depth |
+/// |-------------|-------------------|-----------------|--------------------|-----------------|---------------|----------|-------|
+/// | `strict()`  | rejected          | rejected        | rejected           | rejected        | rejected      | rejected | 128   |
+/// | `default()` | rejected          | rejected        | rejected           | rejected        | rejected      | rejected | any   |
+/// | `lenient()` | accepted          | accepted        | floats             | replaced        | replaced      | skipped  | any   |
+///
+/// Comments and trailing commas are rejected by all of them, the parser
+/// has no way to accept them. Duplicate keys are accepted by all of them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParseOptions {
+    pub(crate) allow_non_finite: bool,
+    pub(crate) number_syntax: NumberSyntax,
+    pub(crate) integer_overflow: IntegerOverflow,
+    pub(crate) invalid_escapes: InvalidEscapes,
+    pub(crate) lossy_utf8_fallback: bool,
+    pub(crate) skip_bom: bool,
+    pub(crate) max_depth: usize,
+    #[cfg(feature = "unicode")]
+    pub(crate) normalization: Normalization,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            allow_non_finite: false,
+            number_syntax: NumberSyntax::STRICT,
+            integer_overflow: IntegerOverflow::Error,
+            invalid_escapes: InvalidEscapes::Error,
+            lossy_utf8_fallback: false,
+            skip_bom: false,
+            max_depth: usize::MAX,
+            #[cfg(feature = "unicode")]
+            normalization: Normalization::Off,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// The depth `ParseOptions::strict` limits documents to
+    pub const STRICT_MAX_DEPTH: usize = 128;
+
+    /// RFC 8259 like the defaults and a limit of
+    /// `ParseOptions::STRICT_MAX_DEPTH` levels of nesting, for input that
+    /// isn't trusted. The limit is the recursion limit of `serde_json`,
+    /// so documents it reads are read with these options as well.
+    ///
+    /// ```rust
+    /// use simd_json::{ErrorType, ParseOptions};
+    /// let mut d = format!("{}{}", "[".repeat(200), "]".repeat(200)).into_bytes();
+    /// assert!(ParseOptions::default().to_tape(&mut d.clone()).is_ok());
+    /// let err = ParseOptions::strict().to_tape(&mut d).unwrap_err();
+    /// assert_eq!(err.error(), &ErrorType::TooDeep);
+    /// ```
+    #[must_use]
+    pub fn strict() -> Self {
+        Self::default().max_depth(Self::STRICT_MAX_DEPTH)
+    }
+
+    /// Everything the parser can relax relaxed: non finite floats and
+    /// numbers with the relaxed syntax are accepted, integers that are
+    /// too large are read as floats, invalid escapes and invalid UTF-8
+    /// are replaced and a byte order mark is skipped, for reading
+    /// whatever other tools wrote. Options added
+    /// later that relax the syntax will be on here as well.
+    ///
+    /// ```rust
+    /// use simd_json::ParseOptions;
+    /// let mut d = b"\xef\xbb\xbf[NaN, +1, \"caf\xe9\"]".to_vec();
+    /// assert!(ParseOptions::default().to_owned_value(&mut d.clone()).is_err());
+    /// let v = ParseOptions::lenient().to_owned_value(&mut d).unwrap();
+    /// assert_eq!(v[2], "caf\u{fffd}");
+    /// ```
+    #[must_use]
+    pub fn lenient() -> Self {
+        Self::default()
+            .allow_non_finite(true)
+            .allow_leading_zeros(true)
+            .allow_leading_plus(true)
+            .allow_bare_fraction(true)
+            .allow_trailing_dot(true)
+            .integer_overflow(IntegerOverflow::Float)
+            .invalid_escapes(InvalidEscapes::Replace)
+            .lossy_utf8_fallback(true)
+            .skip_bom(true)
+    }
+
+    /// Accept the `NaN`, `Infinity` and `-Infinity` literals and parse
+    /// them into their `f64` counterparts.
+    #[must_use]
+    pub fn allow_non_finite(mut self, allow: bool) -> Self {
+        self.allow_non_finite = allow;
+        self
+    }
+
+    /// Accept integer parts with leading zeros, like `007` or `-00.5`,
+    /// they are read as if the zeros weren't there. This never makes a
+    /// number octal.
+    ///
+    /// ```rust
+    /// use simd_json::ParseOptions;
+    /// let options = ParseOptions::default().allow_leading_zeros(true);
+    /// let v = options.to_owned_value(&mut br#"[007, -00.5, 00]"#.to_vec()).unwrap();
+    /// assert_eq!(v, simd_json::json!([7, -0.5, 0]));
+    /// ```
+    #[must_use]
+    pub fn allow_leading_zeros(mut self, allow: bool) -> Self {
+        self.number_syntax.set(NumberSyntax::LEADING_ZEROS, allow);
+        self
+    }
+
+    /// Accept a `+` in front of numbers, like `+1` or `+2.5e3`.
+    #[must_use]
+    pub fn allow_leading_plus(mut self, allow: bool) -> Self {
+        self.number_syntax.set(NumberSyntax::LEADING_PLUS, allow);
+        self
+    }
+
+    /// Accept numbers without an integer part, like `.5` or `-.5`, they
+    /// are read as `0.5` and `-0.5`.
+    #[must_use]
+    pub fn allow_bare_fraction(mut self, allow: bool) -> Self {
+        self.number_syntax.set(NumberSyntax::BARE_FRACTION, allow);
+        self
+    }
+
+    /// Accept a `.` without digits after it, like `1.` or `1.e3`, the
+    /// number is still a float.
+    ///
+    /// ```rust
+    /// use simd_json::{ParseOptions, prelude::*};
+    /// let options = ParseOptions::default()
+    ///     .allow_trailing_dot(true)
+    ///     .allow_bare_fraction(true)
+    ///     .allow_leading_plus(true);
+    /// let v = options.to_owned_value(&mut br#"[1., +.5]"#.to_vec()).unwrap();
+    /// assert_eq!(v[0].as_f64(), Some(1.0));
+    /// assert_eq!(v[1].as_f64(), Some(0.5));
+    /// ```
+    #[must_use]
+    pub fn allow_trailing_dot(mut self, allow: bool) -> Self {
+        self.number_syntax.set(NumberSyntax::TRAILING_DOT, allow);
+        self
+    }
+
+    /// Sets what happens to integers that don't fit the integer types,
+    /// `u64` and `i64` or with the `128bit` feature `u128` and `i128`.
+    /// By default they are an error.
+    #[must_use]
+    pub fn integer_overflow(mut self, integer_overflow: IntegerOverflow) -> Self {
+        self.integer_overflow = integer_overflow;
+        self
+    }
+
+    /// Sets what happens to escapes in strings the parser doesn't know,
+    /// like `\q`, and to `\u` escapes of surrogates that aren't part of
+    /// a pair, like `\ud800` on its own. By default they are an error.
+    ///
+    /// This is only honoured by the DOM entry points, and for those the
+    /// input is searched for such escapes first and a rewritten copy of it
+    /// is parsed if there are any.
+    #[must_use]
+    pub fn invalid_escapes(mut self, invalid_escapes: InvalidEscapes) -> Self {
+        self.invalid_escapes = invalid_escapes;
+        self
+    }
+
+    /// Replace invalid UTF-8 sequences with U+FFFD instead of failing.
+    ///
+    /// This is a slow fallback rather than part of the SIMD UTF-8 check:
+    /// stage 1 validates the input as usual and only when that fails a
+    /// repaired copy of the whole input is made and parsed again from the
+    /// start. Valid input costs nothing extra, invalid input a copy and a
+    /// second parse. Since the repair can grow the input this is only
+    /// honoured by the DOM entry points (`to_owned_value_with_options`,
+    /// `to_borrowed_value_with_options`, where the latter returns owned
+    /// strings for repaired input, and `to_arena_value_with_options`,
+    /// which copies the repaired input into the arena) and by serde's
+    /// `from_slice_with_options`, which reads the repaired copy from an
+    /// `OwnedValue`. Tapes still report `ErrorType::InvalidUTF8`.
+    ///
+    /// ```rust
+    /// use simd_json::{ParseOptions, prelude::*};
+    /// let mut d = b"[\"caf\xe9\"]".to_vec();
+    /// assert!(simd_json::to_owned_value(&mut d.clone()).is_err());
+    /// let options = ParseOptions::default().lossy_utf8_fallback(true);
+    /// let v = simd_json::to_owned_value_with_options(&mut d.clone(), options).unwrap();
+    /// assert_eq!(v[0], "caf\u{fffd}");
+    /// let v: Vec<String> = options.from_slice(&mut d).unwrap();
+    /// assert_eq!(v, ["caf\u{fffd}"]);
+    /// ```
+    #[must_use]
+    pub fn lossy_utf8_fallback(mut self, lossy: bool) -> Self {
+        self.lossy_utf8_fallback = lossy;
+        self
+    }
+
+    /// Skip a leading UTF-8 byte order mark (`EF BB BF`) instead of
+    /// failing with `ErrorType::ByteOrderMark`. Error offsets are
+    /// relative to the input after the mark.
+    ///
+    /// ```rust
+    /// use simd_json::ParseOptions;
+    /// let mut d = b"\xef\xbb\xbf[1]".to_vec();
+    /// assert!(simd_json::to_owned_value(&mut d.clone()).is_err());
+    /// let options = ParseOptions::default().skip_bom(true);
+    /// let v = simd_json::to_owned_value_with_options(&mut d, options).unwrap();
+    /// assert_eq!(v, simd_json::json!([1]));
+    /// ```
+    #[must_use]
+    pub fn skip_bom(mut self, skip: bool) -> Self {
+        self.skip_bom = skip;
+        self
+    }
+
+    /// Fail with `ErrorType::TooDeep` at the first array or object that
+    /// is nested more than `depth` levels deep, `0` only allows documents
+    /// that are a single string, number or literal. There is no limit by
+    /// default.
+    ///
+    /// ```rust
+    /// use simd_json::{ErrorType, ParseOptions};
+    /// let options = ParseOptions::default().max_depth(2);
+    /// assert!(options.to_owned_value(&mut br#"[{"a": 1}]"#.to_vec()).is_ok());
+    /// let err = options.to_owned_value(&mut br#"[{"a": []}]"#.to_vec()).unwrap_err();
+    /// assert_eq!((err.index(), err.error()), (7, &ErrorType::TooDeep));
+    /// ```
+    #[must_use]
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Sets which strings are normalized to unicode NFC, nothing is by
+    /// default. Strings that are in NFC already, which is almost all of
+    /// them, are only checked and not copied.
+    ///
+    /// This is only honoured by the DOM entry points, tapes and serde
+    /// still hand out the strings as they are written.
+    ///
+    /// ```rust
+    /// use simd_json::{Normalization, ParseOptions, prelude::*};
+    /// let d = "{\"cafe\u{301}\": \"e\u{301}\"}";
+    /// let options = ParseOptions::default().normalization(Normalization::Keys);
+    /// let v = options.to_owned_value(&mut d.as_bytes().to_vec()).unwrap();
+    /// assert_eq!(v["caf\u{e9}"], "e\u{301}");
+    /// ```
+    #[cfg(feature = "unicode")]
+    #[must_use]
+    pub fn normalization(mut self, normalization: Normalization) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// Parses `input` into an `OwnedValue` with these options, like
+    /// `to_owned_value_with_options`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `input` is invalid JSON.
+    pub fn to_owned_value(self, input: &mut [u8]) -> Result<OwnedValue> {
+        crate::to_owned_value_with_options(input, self)
+    }
+
+    /// Parses `input` into a `BorrowedValue` with these options, like
+    /// `to_borrowed_value_with_options`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `input` is invalid JSON.
+    pub fn to_borrowed_value(self, input: &mut [u8]) -> Result<BorrowedValue<'_>> {
+        crate::to_borrowed_value_with_options(input, self)
+    }
+
+    /// Parses `input` into a tape with these options, like
+    /// `to_tape_with_options`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `input` is invalid JSON.
+    pub fn to_tape(self, input: &mut [u8]) -> Result<Vec<Node<'_>>> {
+        crate::to_tape_with_options(input, self)
+    }
+
+    /// Deserializes `input` with serde and these options, like
+    /// `serde::from_slice_with_options`.
+    ///
+    /// ```rust
+    /// let options = simd_json::ParseOptions::default().allow_non_finite(true);
+    /// let v: Vec<f64> = options.from_slice(&mut b"[1, Infinity]".to_vec()).unwrap();
+    /// assert_eq!(v, [1.0, f64::INFINITY]);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `input` is invalid JSON or doesn't fit `T`.
+    #[cfg(feature = "serde_impl")]
+    pub fn from_slice<'de, T>(self, input: &'de mut [u8]) -> Result<T>
+    where
+        T: serde_ext::Deserialize<'de>,
+    {
+        crate::serde::from_slice_with_options(input, self)
+    }
+
+    /// Whether any of the number syntax is relaxed
+    pub(crate) fn relaxes_numbers(self) -> bool {
+        self.number_syntax != NumberSyntax::STRICT
+    }
+
+    /// Returns a repaired copy of `input` if invalid escapes are rewritten
+    /// and `input` has any.
+    pub(crate) fn repair(self, input: &[u8]) -> Option<Vec<u8>> {
+        if self.invalid_escapes == InvalidEscapes::Error {
+            return None;
+        }
+        repair_escapes(input, self.invalid_escapes)
+    }
+
+    /// Returns a copy of `input` with its invalid UTF-8 sequences replaced
+    /// if lossy UTF-8 mode is on, for the input a parse hands back when it
+    /// fails for not being UTF-8.
+    pub(crate) fn repair_utf8(self, input: Option<&mut [u8]>) -> Option<Vec<u8>> {
+        input
+            .filter(|_| self.lossy_utf8_fallback)
+            .map(|input| String::from_utf8_lossy(input).into_owned().into_bytes())
+    }
+}
+
+/// The relaxations of the number syntax that are allowed, as set with
+/// `ParseOptions::allow_leading_zeros` and its siblings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct NumberSyntax(u8);
+
+impl NumberSyntax {
+    pub(crate) const STRICT: Self = Self(0);
+    pub(crate) const LEADING_ZEROS: Self = Self(1);
+    pub(crate) const LEADING_PLUS: Self = Self(1 << 1);
+    pub(crate) const BARE_FRACTION: Self = Self(1 << 2);
+    pub(crate) const TRAILING_DOT: Self = Self(1 << 3);
+
+    /// Whether the relaxation `flag` is allowed
+    pub(crate) fn allows(self, flag: Self) -> bool {
+        self.0 & flag.0 != 0
+    }
+
+    fn set(&mut self, flag: Self, allow: bool) {
+        if allow {
+            self.0 |= flag.0;
+        } else {
+            self.0 &= !flag.0;
+        }
+    }
+}
+
+/// What happens to integers that don't fit the integer types, as set
+/// with `ParseOptions::integer_overflow`.
+///
+/// ```rust
+/// use simd_json::{IntegerOverflow, ParseOptions, prelude::*};
+/// let mut d = br#"{"id": 1234567890123456789012345678901234567890}"#.to_vec();
+/// assert!(simd_json::to_owned_value(&mut d.clone()).is_err());
+/// let options = ParseOptions::default().integer_overflow(IntegerOverflow::Float);
+/// let v = options.to_owned_value(&mut d.clone()).unwrap();
+/// assert_eq!(v["id"].as_f64(), Some(1.2345678901234568e39));
+/// let options = ParseOptions::default().integer_overflow(IntegerOverflow::String);
+/// let v = options.to_owned_value(&mut d).unwrap();
+/// assert_eq!(v["id"], "1234567890123456789012345678901234567890");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum IntegerOverflow {
+    /// Fail with `ErrorType::Overflow`
+    #[default]
+    Error,
+    /// Read them as the closest `f64`, this loses precision and is
+    /// only right for numbers that are measurements rather than ids
+    Float,
+    /// Read them as a string of their digits, and a leading `-`, so
+    /// they can be handed to a big integer type without losing any of
+    /// them. The value no longer tells them apart from strings.
+    String,
+}
+
+/// What happens to invalid escapes in strings, as set with
+/// `ParseOptions::invalid_escapes`. Parsers in other languages disagree
+/// here, JavaScript keeps lone surrogates while Python's `json` can be
+/// asked to, and others replace them or fail.
+///
+/// ```rust
+/// use simd_json::{InvalidEscapes, ParseOptions};
+/// let d = br#"["\q", "\ud800!"]"#;
+/// assert!(simd_json::to_owned_value(&mut d.to_vec()).is_err());
+/// let options = ParseOptions::default().invalid_escapes(InvalidEscapes::Replace);
+/// let v = options.to_owned_value(&mut d.to_vec()).unwrap();
+/// assert_eq!(v, simd_json::json!(["\u{fffd}", "\u{fffd}!"]));
+/// let options = ParseOptions::default().invalid_escapes(InvalidEscapes::Keep);
+/// let v = options.to_owned_value(&mut d.to_vec()).unwrap();
+/// assert_eq!(v, simd_json::json!(["\\q", "\\ud800!"]));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum InvalidEscapes {
+    /// Fail with `ErrorType::InvalidEscape` or
+    /// `ErrorType::InvlaidUnicodeCodepoint`
+    #[default]
+    Error,
+    /// Replace the escape with U+FFFD, the escaped character of an
+    /// unknown escape is replaced along with the backslash
+    Replace,
+    /// Keep the escape as it is written, backslash included, since a
+    /// lone surrogate can't be part of a Rust string
+    Keep,
+}
+
+/// Which strings are normalized to unicode NFC, as set with
+/// `ParseOptions::normalization`.
+#[cfg(feature = "unicode")]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Normalization {
+    /// Keep all strings as they are written
+    #[default]
+    Off,
+    /// Normalize the keys of objects
+    Keys,
+    /// Normalize strings that are values
+    Values,
+    /// Normalize keys and values
+    All,
+}
+
+#[cfg(feature = "unicode")]
+impl Normalization {
+    /// `s` in NFC if strings in the position `key` tells are normalized
+    pub(crate) fn apply(self, s: &str, key: bool) -> Cow<'_, str> {
+        use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+        let on = match self {
+            Self::Off => false,
+            Self::Keys => key,
+            Self::Values => !key,
+            Self::All => true,
+        };
+        if !on || is_nfc_quick(s.chars()) == IsNormalized::Yes {
+            Cow::Borrowed(s)
+        } else {
+            Cow::Owned(s.nfc().collect())
+        }
+    }
+}
+
+/// How floats that JSON can not represent (`NaN`, `Infinity` and
+/// `-Infinity`) are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum NonFiniteFloats {
+    /// Write them as `null`, this keeps the output valid JSON
+    #[default]
+    Null,
+    /// Write them as the `NaN`, `Infinity` and `-Infinity` literals,
+    /// this can be read back with `ParseOptions::allow_non_finite`
+    Literal,
+}
+
+/// How finite floats are written.
+///
+/// ```rust
+/// use simd_json::{EncodeOptions, FloatFormat, OwnedValue};
+/// let v = OwnedValue::from(vec![1.0 / 3.0, 1500.0]);
+/// assert_eq!(v.encode_with(EncodeOptions::default()), "[0.3333333333333333,1500.0]");
+/// let fixed = EncodeOptions::default().float_format(FloatFormat::Fixed(2));
+/// assert_eq!(v.encode_with(fixed), "[0.33,1500.00]");
+/// let scientific = EncodeOptions::default().float_format(FloatFormat::Scientific(1));
+/// assert_eq!(v.encode_with(scientific), "[3.3e-1,1.5e3]");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum FloatFormat {
+    /// The shortest text that reads back as the same float, as written
+    /// by `ryu`
+    #[default]
+    Shortest,
+    /// Always this many digits after the decimal point, without an
+    /// exponent, `0` writes no decimal point at all
+    Fixed(u8),
+    /// One digit before the decimal point, this many after it and an
+    /// exponent, like `1.25e-3`
+    Scientific(u8),
+}
+
+/// How `-0.0` is written.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum NegativeZero {
+    /// Keep the sign, `-0.0` reads back as `-0.0`
+    #[default]
+    Keep,
+    /// Write it like `0.0`, for output that is compared as text and
+    /// shouldn't differ by the sign of zero. Negative numbers that are
+    /// rounded to zero by `FloatFormat::Fixed` or `Scientific` keep
+    /// their sign.
+    Positive,
+}
+
+/// How a character in a string is written, as decided by the hook set
+/// with `EncodeOptions::escape_with`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Escape {
+    /// Write it as if there was no hook
+    Default,
+    /// Write it as it is. Quotes, backslashes and control characters
+    /// written like this leave the output invalid JSON.
+    Keep,
+    /// Write it as `\uXXXX`, or two of them for a surrogate pair
+    Unicode,
+    /// Write this text instead, the hook has to make sure it is a valid
+    /// escape
+    Text(&'static str),
+}
+
+/// The hook of `EncodeOptions::escape_with`, hooks are the same if they
+/// are the same function
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct EscapeHook(pub(crate) fn(char) -> Escape);
+
+impl PartialEq for EscapeHook {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 as usize == other.0 as usize
+    }
+}
+
+/// Options that change how values are serialized.
+///
+/// ```rust
+/// use simd_json::{EncodeOptions, NonFiniteFloats, OwnedValue};
+/// let v = OwnedValue::from(vec![std::f64::NAN, 1.5]);
+/// assert_eq!(v.encode_with(EncodeOptions::default()), "[null,1.5]");
+/// let options = EncodeOptions::default().non_finite(NonFiniteFloats::Literal);
+/// assert_eq!(v.encode_with(options), "[NaN,1.5]");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EncodeOptions {
+    pub(crate) non_finite: NonFiniteFloats,
+    pub(crate) float_format: FloatFormat,
+    pub(crate) negative_zero: NegativeZero,
+    pub(crate) ascii_only: bool,
+    pub(crate) html_safe: bool,
+    pub(crate) escape: Option<EscapeHook>,
+    pub(crate) indent: Option<u16>,
+    #[cfg(feature = "color")]
+    pub(crate) colors: Option<Colors>,
+}
+
+impl EncodeOptions {
+    /// Sets how `NaN` and infinite floats are written
+    #[must_use]
+    pub fn non_finite(mut self, non_finite: NonFiniteFloats) -> Self {
+        self.non_finite = non_finite;
+        self
+    }
+
+    /// Sets how finite floats are written
+    #[must_use]
+    pub fn float_format(mut self, float_format: FloatFormat) -> Self {
+        self.float_format = float_format;
+        self
+    }
+
+    /// Sets how `-0.0` is written
+    ///
+    /// ```rust
+    /// use simd_json::{EncodeOptions, NegativeZero, OwnedValue};
+    /// let v = OwnedValue::from(vec![-0.0, -1.0]);
+    /// assert_eq!(v.encode_with(EncodeOptions::default()), "[-0.0,-1.0]");
+    /// let options = EncodeOptions::default().negative_zero(NegativeZero::Positive);
+    /// assert_eq!(v.encode_with(options), "[0.0,-1.0]");
+    /// ```
+    #[must_use]
+    pub fn negative_zero(mut self, negative_zero: NegativeZero) -> Self {
+        self.negative_zero = negative_zero;
+        self
+    }
+
+    /// Escape every character outside of ASCII in strings and keys as
+    /// `\uXXXX`, using a surrogate pair for those outside of the basic
+    /// multilingual plane, so the output is plain ASCII.
+    ///
+    /// ```rust
+    /// use simd_json::{json, EncodeOptions};
+    /// let v = json!({"café": "😀"});
+    /// let options = EncodeOptions::default().ascii_only(true);
+    /// assert_eq!(v.encode_with(options), r#"{"caf\u00e9":"\ud83d\ude00"}"#);
+    /// ```
+    #[must_use]
+    pub fn ascii_only(mut self, ascii_only: bool) -> Self {
+        self.ascii_only = ascii_only;
+        self
+    }
+
+    /// Escape `<`, `>`, `&`, U+2028 and U+2029 in strings and keys as
+    /// `\uXXXX` as well, so the output can be put into a `<script>` tag
+    /// or an HTML template without ending the tag or being read as
+    /// markup, and is valid JavaScript.
+    ///
+    /// ```rust
+    /// use simd_json::{json, EncodeOptions};
+    /// let v = json!(["</script>", "a & b"]);
+    /// let options = EncodeOptions::default().html_safe(true);
+    /// assert_eq!(
+    ///     v.encode_with(options),
+    ///     r#"["\u003c/script\u003e","a \u0026 b"]"#
+    /// );
+    /// ```
+    #[must_use]
+    pub fn html_safe(mut self, html_safe: bool) -> Self {
+        self.html_safe = html_safe;
+        self
+    }
+
+    /// Asks `hook` how to write every character of strings and keys, for
+    /// escapes that the other options don't cover. Characters it returns
+    /// `Escape::Default` for are written as without a hook.
+    ///
+    /// ```rust
+    /// use simd_json::{json, EncodeOptions, Escape};
+    /// fn escape(c: char) -> Escape {
+    ///     match c {
+    ///         '/' => Escape::Text("\\/"),
+    ///         'é' => Escape::Unicode,
+    ///         _ => Escape::Default,
+    ///     }
+    /// }
+    /// let v = json!({"path": "/café\n"});
+    /// let options = EncodeOptions::default().escape_with(escape);
+    /// assert_eq!(v.encode_with(options), r#"{"path":"\/caf\u00e9\n"}"#);
+    /// ```
+    #[must_use]
+    pub fn escape_with(mut self, hook: fn(char) -> Escape) -> Self {
+        self.escape = Some(EscapeHook(hook));
+        self
+    }
+
+    /// Pretty print the output, indenting every level by `spaces`
+    #[must_use]
+    pub fn pretty(mut self, spaces: u16) -> Self {
+        self.indent = Some(spaces);
+        self
+    }
+
+    /// Highlights keys, strings, numbers, booleans and `null` with ANSI
+    /// escape codes for printing to a terminal. The output is no longer
+    /// JSON, only use this for people to read.
+    ///
+    /// ```rust
+    /// use simd_json::{json, Colors, EncodeOptions};
+    /// let v = json!({"a": [1, "x", null]});
+    /// let options = EncodeOptions::default().pretty(2).colors(Colors::default());
+    /// assert_eq!(
+    ///     v.encode_with(options),
+    ///     "{\n  \u{1b}[1;34m\"a\"\u{1b}[0m: [\n    \u{1b}[36m1\u{1b}[0m,\n    \
+    ///      \u{1b}[32m\"x\"\u{1b}[0m,\n    \u{1b}[1;30mnull\u{1b}[0m\n  ]\n}"
+    /// );
+    /// ```
+    #[cfg(feature = "color")]
+    #[must_use]
+    pub fn colors(mut self, colors: Colors) -> Self {
+        self.colors = Some(colors);
+        self
+    }
+}
+
+/// The colors `EncodeOptions::colors` uses, each given as the parameters
+/// of an ANSI SGR escape code like `"1;34"` for bold blue. The defaults
+/// are close to those of `jq`: bold blue keys, green strings, cyan
+/// numbers, yellow booleans and dark gray `null`.
+///
+/// ```rust
+/// use simd_json::{json, Colors, EncodeOptions};
+/// let colors = Colors::default().key("35").string("0");
+/// let s = json!({"a": true}).encode_with(EncodeOptions::default().colors(colors));
+/// assert_eq!(s, "{\u{1b}[35m\"a\"\u{1b}[0m:\u{1b}[33mtrue\u{1b}[0m}");
+/// ```
+#[cfg(feature = "color")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Colors {
+    pub(crate) key: &'static str,
+    pub(crate) string: &'static str,
+    pub(crate) number: &'static str,
+    pub(crate) boolean: &'static str,
+    pub(crate) null: &'static str,
+}
+
+#[cfg(feature = "color")]
+impl Default for Colors {
+    fn default() -> Self {
+        Self {
+            key: "1;34",
+            string: "32",
+            number: "36",
+            boolean: "33",
+            null: "1;30",
+        }
+    }
+}
+
+#[cfg(feature = "color")]
+impl Colors {
+    /// Sets the color of object keys
+    #[must_use]
+    pub fn key(mut self, sgr: &'static str) -> Self {
+        self.key = sgr;
+        self
+    }
+
+    /// Sets the color of strings
+    #[must_use]
+    pub fn string(mut self, sgr: &'static str) -> Self {
+        self.string = sgr;
+        self
+    }
+
+    /// Sets the color of numbers
+    #[must_use]
+    pub fn number(mut self, sgr: &'static str) -> Self {
+        self.number = sgr;
+        self
+    }
+
+    /// Sets the color of `true` and `false`
+    #[must_use]
+    pub fn boolean(mut self, sgr: &'static str) -> Self {
+        self.boolean = sgr;
+        self
+    }
+
+    /// Sets the color of `null`
+    #[must_use]
+    pub fn null(mut self, sgr: &'static str) -> Self {
+        self.null = sgr;
+        self
+    }
+}
+
+/// Options that change how `to_csv` writes a document.
+///
+/// ```rust
+/// use simd_json::{CsvOptions, json};
+/// let v = json!([{"a": {"b": 1}, "c": "x"}]);
+/// let mut out = Vec::new();
+/// let options = CsvOptions::default().delimiter(b';').key_separator('_');
+/// simd_json::to_csv(&v, &mut out, options).unwrap();
+/// assert_eq!(out, b"a_b;c\n1;x\n");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CsvOptions {
+    pub(crate) delimiter: u8,
+    pub(crate) key_separator: char,
+    pub(crate) header: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            key_separator: '.',
+            header: true,
+        }
+    }
+}
+
+impl CsvOptions {
+    /// Sets the byte between fields, `,` by default
+    #[must_use]
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Sets what joins the keys of nested objects into a column name,
+    /// `.` by default
+    #[must_use]
+    pub fn key_separator(mut self, separator: char) -> Self {
+        self.key_separator = separator;
+        self
+    }
+
+    /// Write a header line with the column names first, on by default
+    #[must_use]
+    pub fn header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+}
@@ -0,0 +1,233 @@
+use crate::buffers::Buffers;
+use crate::value::owned::to_value_with_buffers;
+use crate::{find_structurals, to_owned_value_from_slice, OwnedValue, Result};
+use alloc::vec::Vec;
+use core::ops::Range;
+use rayon::prelude::*;
+
+/// Parses newline delimited JSON, one document per line, on the rayon
+/// thread pool. Lines that are empty or only hold whitespace are
+/// skipped, the results are in input order. Like `to_owned_value` this
+/// rewrites the input to de-escape strings.
+///
+/// Each worker reuses its scratch buffers for all the lines it parses so
+/// the per line cost is just the tape and the value itself.
+///
+/// ```rust
+/// use simd_json::prelude::*;
+/// let mut d = b"{\"id\": 1}\n\n[2, 3]\r\n{\"id\": }\n".to_vec();
+/// let values = simd_json::parse_lines_parallel(&mut d);
+/// assert_eq!(values.len(), 3);
+/// assert_eq!(values[0].as_ref().unwrap()["id"], 1);
+/// assert_eq!(values[1].as_ref().unwrap()[1], 3);
+/// assert!(values[2].is_err());
+/// ```
+#[must_use]
+pub fn parse_lines_parallel(input: &mut [u8]) -> Vec<Result<OwnedValue>> {
+    let lines: Vec<&mut [u8]> = input
+        .split_mut(|&c| c == b'\n')
+        .filter(|line| {
+            !line
+                .iter()
+                .all(|c| matches!(c, b' ' | b'\t' | b'\n' | b'\r'))
+        })
+        .collect();
+    lines
+        .into_par_iter()
+        .map_init(Buffers::default, |buffers, line| {
+            to_value_with_buffers(line, buffers)
+        })
+        .collect()
+}
+
+/// Parses a document that is one large array, like an export of records,
+/// parsing its elements in parallel on the rayon thread pool.
+///
+/// Stage 1 runs over the whole input to find where the elements of the
+/// top level array start and end, the elements are then parsed on their
+/// own. Unlike `to_owned_value` the input is left untouched since every
+/// element is parsed from a copy in a per worker buffer. Input that isn't
+/// an array is parsed as usual, and so is input that turns out to be
+/// invalid so the errors are exactly the ones `to_owned_value` reports.
+///
+/// ```rust
+/// use simd_json::prelude::*;
+/// let d = br#"[{"id": 1}, {"id": 2, "tags": ["a", "b"]}, 3]"#;
+/// let v = simd_json::parse_array_parallel(d).unwrap();
+/// assert_eq!(v[1]["tags"][1], "b");
+/// assert_eq!(v[2], 3);
+/// ```
+///
+/// # Errors
+///
+/// Will return `Err` if `input` is invalid JSON.
+pub fn parse_array_parallel(input: &[u8]) -> Result<OwnedValue> {
+    if let Some(ranges) = element_ranges(input) {
+        let values: Result<Vec<OwnedValue>> = ranges
+            .into_par_iter()
+            .map_init(
+                || (Buffers::default(), Vec::new()),
+                |(buffers, element), range| {
+                    element.clear();
+                    element.extend_from_slice(&input[range]);
+                    to_value_with_buffers(element, buffers)
+                },
+            )
+            .collect();
+        if let Ok(values) = values {
+            return Ok(OwnedValue::Array(values));
+        }
+    }
+    to_owned_value_from_slice(input)
+}
+
+/// Finds the byte ranges of the elements of the top level array, `None`
+/// if `input` isn't an array or the brackets don't line up
+fn element_ranges(input: &[u8]) -> Option<Vec<Range<usize>>> {
+    let index = find_structurals(input).ok()?;
+    let mut structurals = index.iter();
+    let open = structurals.next()?;
+    if input[open] != b'[' {
+        return None;
+    }
+    let mut ranges = Vec::new();
+    // depth inside the current element
+    let mut depth = 0_usize;
+    let mut start = open + 1;
+    while let Some(idx) = structurals.next() {
+        match input[idx] {
+            b'[' | b'{' => depth += 1,
+            b']' | b'}' if depth > 0 => depth -= 1,
+            b',' if depth == 0 => {
+                ranges.push(start..idx);
+                start = idx + 1;
+            }
+            b']' => {
+                // `[]` has no elements, everything else has one more
+                // after the last comma
+                let empty = input[start..idx]
+                    .iter()
+                    .all(|c| matches!(c, b' ' | b'\t' | b'\n' | b'\r'));
+                if !ranges.is_empty() || !empty {
+                    ranges.push(start..idx);
+                }
+                return if structurals.next().is_none() {
+                    Some(ranges)
+                } else {
+                    None
+                };
+            }
+            b'}' => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::to_owned_value;
+
+    #[test]
+    fn lines_match_sequential() {
+        let lines: Vec<String> = (0..1000)
+            .map(|i| {
+                if i % 97 == 0 {
+                    format!("{{\"id\": {}, \"broken\": }}", i)
+                } else {
+                    format!(
+                        "{{\"id\": {}, \"name\": \"n\\u00e9{}\", \"tags\": [{}]}}",
+                        i, i, i
+                    )
+                }
+            })
+            .collect();
+        let mut input = lines.join("\n").into_bytes();
+        let values = parse_lines_parallel(&mut input);
+        assert_eq!(values.len(), lines.len());
+        for (value, line) in values.into_iter().zip(lines) {
+            let expected = to_owned_value(&mut line.into_bytes());
+            assert_eq!(value.is_ok(), expected.is_ok());
+            if let (Ok(value), Ok(expected)) = (value, expected) {
+                assert_eq!(value, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn lines_blank() {
+        assert!(parse_lines_parallel(&mut Vec::new()).is_empty());
+        assert!(parse_lines_parallel(&mut b"\n \r\n\t\n".to_vec()).is_empty());
+        let values = parse_lines_parallel(&mut b"\r\n1\n\n2".to_vec());
+        let values: Vec<_> = values.into_iter().map(Result::unwrap).collect();
+        assert_eq!(values, vec![OwnedValue::from(1), OwnedValue::from(2)]);
+    }
+
+    fn assert_matches_sequential(input: &[u8]) {
+        assert_eq!(
+            parse_array_parallel(input),
+            to_owned_value(&mut input.to_vec()),
+            "{}",
+            String::from_utf8_lossy(input)
+        );
+    }
+
+    #[test]
+    fn array_matches_sequential() {
+        let inputs: [&[u8]; 20] = [
+            b"[]",
+            b" [ ] ",
+            b"[1]",
+            br#"[1, "a,b", [2, [3]], {"c": [4, {}], "d": "]"}, null]"#,
+            br#"["\"],[", "\u00e9"]"#,
+            b"42",
+            br#"{"a": [1, 2]}"#,
+            b"[1, 2",
+            b"[1, 2]]",
+            b"[1, 2] 3",
+            b"[1, 2}",
+            b"[1,, 2]",
+            b"[1, 2,]",
+            b"[,]",
+            b"[1 2]",
+            b"[{\"a\" 1}]",
+            b"[tru]",
+            b"[\"\xff\"]",
+            b"\xef\xbb\xbf[1]",
+            b"",
+        ];
+        for input in &inputs {
+            assert_matches_sequential(input);
+        }
+    }
+
+    #[test]
+    fn array_of_records() {
+        let records: Vec<String> = (0..10_000)
+            .map(|i| format!("{{\"id\": {}, \"v\": [{}.5, \"x\\n{}\"]}}", i, i, i))
+            .collect();
+        let input = format!("[{}]", records.join(",\n"));
+        assert_matches_sequential(input.as_bytes());
+        let v = parse_array_parallel(input.as_bytes()).expect("failed to parse");
+        assert_eq!(v[9999]["v"][1], "x\n9999");
+        assert_eq!(
+            element_ranges(input.as_bytes()).map(|r| r.len()),
+            Some(10_000)
+        );
+    }
+
+    #[test]
+    fn array_element_ranges() {
+        assert_eq!(element_ranges(b" [ ] "), Some(vec![]));
+        assert_eq!(element_ranges(b"[ ]"), Some(vec![]));
+        assert_eq!(
+            element_ranges(br#"[1, {"a": [2, 3]}, "x,]"]"#),
+            Some(vec![1..2, 3..17, 18..24])
+        );
+        assert_eq!(element_ranges(b"{}"), None);
+        assert_eq!(element_ranges(b"[1}"), None);
+        assert_eq!(element_ranges(b"[1] 2"), None);
+        assert_eq!(element_ranges(b"[[1]"), None);
+    }
+}
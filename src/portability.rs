@@ -1,7 +1,7 @@
 #[cfg(target_arch = "x86")]
-use std::arch::x86::*;
+use core::arch::x86::*;
 #[cfg(target_arch = "x86_64")]
-use std::arch::x86_64::*;
+use core::arch::x86_64::*;
 
 #[cfg_attr(not(feature = "no-inline"), inline)]
 #[cfg(target_arch = "x86_64")]
@@ -0,0 +1,9 @@
+#[cfg(not(any(
+    target_feature = "sse4.2",
+    target_feature = "avx2",
+    target_feature = "neon",
+    target_feature = "simd128"
+)))]
+pub mod deser;
+pub mod stage1;
+pub mod utf8check;
@@ -0,0 +1,178 @@
+#![allow(dead_code)]
+use crate::utf8check::Utf8Check;
+use alloc::vec::Vec;
+
+use crate::{ProcessedUtfBytes, Stage1Parse, Utf8CheckingState};
+use core::simd::prelude::*;
+
+use core::mem;
+
+pub const SIMDJSON_PADDING: usize = mem::size_of::<u8x32>();
+pub const SIMDINPUT_LENGTH: usize = 64;
+
+// the 64 byte chunk is a single vector, it's up to the compiler to split it
+// into whatever the target has
+#[derive(Debug)]
+pub(crate) struct SimdInput {
+    v: u8x64,
+}
+
+// the last byte of an ASCII chunk may not carry a continuation that is
+// more then one byte long
+const ASCII_MAX_CARRIES: i8x64 = {
+    let mut carries = [9; 64];
+    carries[63] = 1;
+    i8x64::from_array(carries)
+};
+
+impl Stage1Parse<i8x64> for SimdInput {
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn new(ptr: &[u8]) -> Self {
+        Self {
+            v: u8x64::from_slice(&ptr[..SIMDINPUT_LENGTH]),
+        }
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn new_utf8_checking_state() -> Utf8CheckingState<i8x64> {
+        Utf8CheckingState {
+            has_error: Self::zero(),
+            previous: ProcessedUtfBytes::default(),
+        }
+    }
+
+    // there is no portable carry-less multiply so we compute the prefix
+    // xor by hand
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn compute_quote_mask(quote_bits: u64) -> u64 {
+        let mut quote_mask: u64 = quote_bits ^ (quote_bits << 1);
+        quote_mask ^= quote_mask << 2;
+        quote_mask ^= quote_mask << 4;
+        quote_mask ^= quote_mask << 8;
+        quote_mask ^= quote_mask << 16;
+        quote_mask ^= quote_mask << 32;
+        quote_mask
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn check_utf8(&self, state: &mut Utf8CheckingState<i8x64>) {
+        let bytes: i8x64 = self.v.cast();
+        if bytes.simd_lt(Self::zero()).any() {
+            // it is not ascii so we have to do heavy work
+            state.previous =
+                ProcessedUtfBytes::check_utf8_bytes(bytes, &state.previous, &mut state.has_error);
+        } else {
+            // it is ascii, we just check continuation
+            state.has_error |= state
+                .previous
+                .carried_continuations
+                .simd_gt(ASCII_MAX_CARRIES)
+                .to_simd();
+        }
+    }
+
+    /// a straightforward comparison of a mask against input
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn cmp_mask_against_input(&self, m: u8) -> u64 {
+        self.v.simd_eq(u8x64::splat(m)).to_bitmask()
+    }
+
+    // find all values less than or equal than the content of maxval (using unsigned arithmetic)
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn unsigned_lteq_against_input(&self, maxval: i8x64) -> u64 {
+        self.v.simd_le(maxval.cast()).to_bitmask()
+    }
+
+    // a table lookup is only cheap on some targets, comparing against every
+    // character is cheap on all of them
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn find_whitespace_and_structurals(&self, whitespace: &mut u64, structurals: &mut u64) {
+        let eq = |c: u8| self.v.simd_eq(u8x64::splat(c));
+
+        *structurals =
+            (eq(b'{') | eq(b'}') | eq(b'[') | eq(b']') | eq(b':') | eq(b',')).to_bitmask();
+        *whitespace = (eq(b' ') | eq(b'\n') | eq(b'\t') | eq(b'\r')).to_bitmask();
+    }
+
+    // flatten out values in 'bits' assuming that they are are to have values of idx
+    // plus their position in the bitvector
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn flatten_bits(base: &mut Vec<u32>, idx: u32, mut bits: u64) {
+        let idx_minus_64 = idx.wrapping_sub(64);
+        base.reserve(64);
+        while bits != 0 {
+            base.push(idx_minus_64.wrapping_add(bits.trailing_zeros()));
+            bits &= bits.wrapping_sub(1);
+        }
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn check_utf8_errors(state: &Utf8CheckingState<i8x64>) -> bool {
+        state.has_error.simd_ne(Self::zero()).any()
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn fill_s8(n: i8) -> i8x64 {
+        i8x64::splat(n)
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn zero() -> i8x64 {
+        i8x64::splat(0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SimdInput;
+    use crate::Deserializer;
+    use proptest::prelude::*;
+
+    // the hand written backend has to find the same structurals as the
+    // portable one
+    fn assert_backends_agree(input: &[u8]) {
+        let mut expected = Vec::new();
+        let mut native = Vec::new();
+        let expected = unsafe {
+            Deserializer::find_structural_bits_simd::<SimdInput, _>(input, &mut expected)
+        }
+        .map(|()| expected);
+        let native = unsafe {
+            Deserializer::find_structural_bits_simd::<crate::SimdInput, _>(input, &mut native)
+        }
+        .map(|()| native);
+        assert_eq!(native, expected);
+    }
+
+    #[test]
+    fn backends_agree() {
+        assert_backends_agree(br#"{"a": [1, true, null, "b\"c"], "d": {"e": -1.5e3}}"#);
+        assert_backends_agree("[\"ü\", \"€\", \"😀\"]".repeat(20).as_bytes());
+        assert_backends_agree(
+            b"[1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23,24,25]",
+        );
+        assert_backends_agree(&[b','; 200]);
+        assert_backends_agree(b"   ");
+        assert_backends_agree(b"[\"unterminated");
+        assert_backends_agree(b"[\"a\nb\"]");
+        assert_backends_agree(b"[\"\xff\xfe\"]");
+        assert_backends_agree(b"[\"\xed\xa0\x80\"]");
+        assert_backends_agree(b"[\"\xf4\x90\x80\x80\"]");
+        assert_backends_agree(b"[\"\xc0\xaf\"]");
+        assert_backends_agree(b"[\"\xe0\x80\xaf\"]");
+        // a sequence cut off at the end of a chunk that is followed by ASCII
+        assert_backends_agree(&[&b"[\""[..], &[b'a'; 60], b"\xe2\x82\"]"].concat());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_backends_agree(d in "[\\[\\]{},: 1tn\\\\\"\t\nü😀]{0,300}") {
+            assert_backends_agree(d.as_bytes());
+        }
+
+        #[test]
+        fn prop_backends_agree_junk(d in prop::collection::vec(any::<u8>(), 0..300)) {
+            assert_backends_agree(&d);
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use core::simd::prelude::*;
+
+use crate::utf8check::{ProcessedUtfBytes, Utf8Check};
+
+// the tables are indexed by the high nibble so only the first 16 lanes of
+// the lookup are ever used, the comparisons are signed
+const INITIAL_MINS: [u8; 16] = [
+    0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, // 0xxx => false
+    0x80, 0x80, 0x80, 0x80, // 10xx => false
+    0xC2, 0x80, // 110x
+    0xE1, // 1110
+    0xF1, // 1111
+];
+
+const SECOND_MINS: [u8; 16] = [
+    0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, // 0xxx => false
+    0x80, 0x80, 0x80, 0x80, // 10xx => false
+    0x7F, 0x7F, // 110x => true
+    0xA0, // 1110
+    0x90, // 1111
+];
+
+const CONTINUATION_LENGTHS: [u8; 16] = [
+    1, 1, 1, 1, 1, 1, 1, 1, // 0xxx (ASCII)
+    0, 0, 0, 0, // 10xx (continuation)
+    2, 2, // 110x
+    3, // 1110
+    4, // 1111, next should be 0 (not checked here)
+];
+
+// indexes to move the last `n` lanes of the first vector in front of the
+// second one
+const fn shift_in(n: usize) -> [usize; 64] {
+    let mut idx = [0; 64];
+    let mut i = 0;
+    while i < 64 {
+        idx[i] = 64 - n + i;
+        i += 1;
+    }
+    idx
+}
+
+const SHIFT_IN_1: [usize; 64] = shift_in(1);
+const SHIFT_IN_2: [usize; 64] = shift_in(2);
+
+#[cfg_attr(not(feature = "no-inline"), inline(always))]
+fn lookup(table: [u8; 16], nibbles: i8x64) -> i8x64 {
+    let mut lanes = [0; 64];
+    lanes[..16].copy_from_slice(&table);
+    u8x64::from_array(lanes).swizzle_dyn(nibbles.cast()).cast()
+}
+
+impl Default for ProcessedUtfBytes<i8x64> {
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn default() -> Self {
+        Self {
+            rawbytes: i8x64::splat(0),
+            high_nibbles: i8x64::splat(0),
+            carried_continuations: i8x64::splat(0),
+        }
+    }
+}
+
+impl Utf8Check<i8x64> for ProcessedUtfBytes<i8x64> {
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn new_processed_utf_bytes() -> Self {
+        Self::default()
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn push_last_byte_of_a_to_b(a: i8x64, b: i8x64) -> i8x64 {
+        simd_swizzle!(a, b, SHIFT_IN_1)
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn push_last_2bytes_of_a_to_b(a: i8x64, b: i8x64) -> i8x64 {
+        simd_swizzle!(a, b, SHIFT_IN_2)
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn check_smaller_than_0xf4(current_bytes: i8x64, has_error: &mut i8x64) {
+        // unsigned, saturates to 0 below max
+        *has_error |= current_bytes
+            .cast::<u8>()
+            .saturating_sub(u8x64::splat(0xF4))
+            .cast();
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn continuation_lengths(high_nibbles: i8x64) -> i8x64 {
+        lookup(CONTINUATION_LENGTHS, high_nibbles)
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn carry_continuations(initial_lengths: i8x64, previous_carries: i8x64) -> i8x64 {
+        let right1: i8x64 = Self::push_last_byte_of_a_to_b(previous_carries, initial_lengths)
+            .cast::<u8>()
+            .saturating_sub(u8x64::splat(1))
+            .cast();
+        let sum: i8x64 = initial_lengths + right1;
+        let right2: i8x64 = Self::push_last_2bytes_of_a_to_b(previous_carries, sum)
+            .cast::<u8>()
+            .saturating_sub(u8x64::splat(2))
+            .cast();
+        sum + right2
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn check_continuations(initial_lengths: i8x64, carries: i8x64, has_error: &mut i8x64) {
+        // overlap || underlap
+        // carry > length && length > 0 || !(carry > length) && !(length > 0)
+        // (carries > length) == (lengths > 0)
+        let overunder =
+            !(carries.simd_gt(initial_lengths) ^ initial_lengths.simd_gt(i8x64::splat(0)));
+
+        *has_error |= overunder.to_simd();
+    }
+
+    // when 0xED is found, next byte must be no larger than 0x9F
+    // when 0xF4 is found, next byte must be no larger than 0x8F
+    // next byte must be continuation, ie sign bit is set, so signed < is ok
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn check_first_continuation_max(
+        current_bytes: i8x64,
+        off1_current_bytes: i8x64,
+        has_error: &mut i8x64,
+    ) {
+        let off1_current_bytes: u8x64 = off1_current_bytes.cast();
+        let mask_ed = off1_current_bytes.simd_eq(u8x64::splat(0xED));
+        let mask_f4 = off1_current_bytes.simd_eq(u8x64::splat(0xF4));
+
+        let badfollow_ed = current_bytes.simd_gt(u8x64::splat(0x9F).cast()) & mask_ed;
+        let badfollow_f4 = current_bytes.simd_gt(u8x64::splat(0x8F).cast()) & mask_f4;
+
+        *has_error |= (badfollow_ed | badfollow_f4).to_simd();
+    }
+
+    // map off1_hibits => error condition
+    // hibits     off1    cur
+    // C       => < C2 && true
+    // E       => < E1 && < A0
+    // F       => < F1 && < 90
+    // else      false && false
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn check_overlong(
+        current_bytes: i8x64,
+        off1_current_bytes: i8x64,
+        hibits: i8x64,
+        previous_hibits: i8x64,
+        has_error: &mut i8x64,
+    ) {
+        let off1_hibits: i8x64 = Self::push_last_byte_of_a_to_b(previous_hibits, hibits);
+        let initial_mins: i8x64 = lookup(INITIAL_MINS, off1_hibits);
+
+        let initial_under = initial_mins.simd_gt(off1_current_bytes);
+
+        let second_mins: i8x64 = lookup(SECOND_MINS, off1_hibits);
+        let second_under = second_mins.simd_gt(current_bytes);
+        *has_error |= (initial_under & second_under).to_simd();
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn count_nibbles(bytes: i8x64, answer: &mut Self) {
+        answer.rawbytes = bytes;
+        answer.high_nibbles = (bytes.cast::<u8>() >> 4).cast();
+    }
+}
@@ -2,3 +2,5 @@
 pub use value_trait::{
     AccessError, Array, Builder, Mutable, Object, Value as ValueTrait, ValueType, Writable,
 };
+
+pub use crate::value::TryGet;
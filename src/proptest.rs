@@ -0,0 +1,174 @@
+//! [proptest](https://docs.rs/proptest) strategies that generate values,
+//! so code that consumes them can be property tested against documents
+//! of realistic shape.
+//!
+//! ```rust
+//! use proptest::prelude::*;
+//! use simd_json::prelude::*;
+//! use simd_json::proptest::{owned_value, ValueConfig};
+//!
+//! proptest! {
+//!     fn round_trip(v in owned_value(ValueConfig::default())) {
+//!         let mut d = v.encode().into_bytes();
+//!         prop_assert_eq!(simd_json::to_owned_value(&mut d).unwrap(), v);
+//!     }
+//! }
+//! # round_trip();
+//! ```
+
+use crate::{BorrowedValue, OwnedValue, StaticNode};
+use ::proptest::prelude::*;
+use ::proptest::strategy::Union;
+use alloc::vec;
+
+/// What values the strategies generate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ValueConfig {
+    pub(crate) depth: u32,
+    pub(crate) size: u32,
+    pub(crate) items: u32,
+    pub(crate) floats: bool,
+    pub(crate) non_finite: bool,
+}
+
+impl Default for ValueConfig {
+    fn default() -> Self {
+        Self {
+            depth: 8,
+            size: 256,
+            items: 10,
+            floats: true,
+            non_finite: false,
+        }
+    }
+}
+
+impl ValueConfig {
+    /// Sets how many levels arrays and objects nest at most, 8 by default
+    #[must_use]
+    pub fn depth(mut self, depth: u32) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Sets the number of values a document aims to have at most, 256 by
+    /// default
+    #[must_use]
+    pub fn size(mut self, size: u32) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Sets the number of elements an array or object has at most, 10 by
+    /// default
+    #[must_use]
+    pub fn items(mut self, items: u32) -> Self {
+        self.items = items;
+        self
+    }
+
+    /// Generate floats, on by default. Without them all numbers are
+    /// integers, which keeps comparisons exact.
+    #[must_use]
+    pub fn floats(mut self, floats: bool) -> Self {
+        self.floats = floats;
+        self
+    }
+
+    /// Generate `NaN` and the infinities as well, off by default since
+    /// JSON can't represent them
+    #[must_use]
+    pub fn non_finite(mut self, non_finite: bool) -> Self {
+        self.non_finite = non_finite;
+        self
+    }
+}
+
+/// A strategy for owned values, with strings, keys and numbers of any
+/// kind and arrays and objects nested as `config` allows
+pub fn owned_value(config: ValueConfig) -> BoxedStrategy<OwnedValue> {
+    let mut leaves = vec![
+        Just(StaticNode::Null).boxed(),
+        any::<bool>().prop_map(StaticNode::Bool).boxed(),
+        any::<i64>().prop_map(StaticNode::I64).boxed(),
+        any::<u64>().prop_map(StaticNode::U64).boxed(),
+    ];
+    if config.floats {
+        leaves.push(any::<f64>().prop_map(StaticNode::F64).boxed());
+    }
+    if config.non_finite {
+        leaves.push(
+            prop_oneof![Just(f64::NAN), Just(f64::INFINITY), Just(f64::NEG_INFINITY)]
+                .prop_map(StaticNode::F64)
+                .boxed(),
+        );
+    }
+    let leaf = prop_oneof![
+        Union::new(leaves).prop_map(OwnedValue::Static),
+        ".*".prop_map(OwnedValue::from),
+    ];
+    let items = config.items as usize;
+    leaf.prop_recursive(config.depth, config.size, config.items, move |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..=items).prop_map(OwnedValue::Array),
+            prop::collection::hash_map(".*", inner, 0..=items)
+                .prop_map(|m| m.into_iter().collect()),
+        ]
+    })
+    .boxed()
+}
+
+/// A strategy for borrowed values, generates the same values as
+/// `owned_value`
+pub fn borrowed_value(config: ValueConfig) -> BoxedStrategy<BorrowedValue<'static>> {
+    owned_value(config).prop_map(BorrowedValue::from).boxed()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+    use ::proptest::test_runner::TestRunner;
+
+    fn depth(v: &OwnedValue) -> u32 {
+        match v {
+            OwnedValue::Array(a) => 1 + a.iter().map(depth).max().unwrap_or(0),
+            OwnedValue::Object(o) => 1 + o.values().map(depth).max().unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    fn no_floats(v: &OwnedValue) -> bool {
+        match v {
+            OwnedValue::Static(StaticNode::F64(_)) => false,
+            OwnedValue::Array(a) => a.iter().all(no_floats),
+            OwnedValue::Object(o) => o.values().all(no_floats),
+            _ => true,
+        }
+    }
+
+    #[test]
+    fn config() {
+        let mut runner = TestRunner::default();
+        let config = ValueConfig::default().depth(2).items(3).floats(false);
+        runner
+            .run(&owned_value(config), |v| {
+                prop_assert!(depth(&v) <= 2);
+                prop_assert!(no_floats(&v));
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn non_finite() {
+        let mut runner = TestRunner::default();
+        let config = ValueConfig::default().depth(0).non_finite(true);
+        let mut nan = false;
+        for _ in 0..4096 {
+            let v = owned_value(config).new_tree(&mut runner).unwrap().current();
+            nan |= matches!(v.as_f64(), Some(f) if f.is_nan());
+        }
+        assert!(nan);
+    }
+}
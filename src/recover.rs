@@ -0,0 +1,439 @@
+//! Best-effort parsing that keeps going after an error.
+//!
+//! This walks the structural indexes from stage 1 with a small parser of
+//! its own instead of the stage 2 state machine. Whenever something
+//! doesn't fit the grammar the error is recorded, a `null` is put in place
+//! of the broken value and parsing resumes at the next structural
+//! character. It is a lot slower than the regular parser but only meant
+//! for tools that want to report every problem in one pass.
+use crate::numberparse::{is_non_finite_start, overflowed_integer_len};
+use crate::prelude::*;
+use crate::stage2::{is_valid_false_atom, is_valid_null_atom, is_valid_true_atom};
+use crate::value::owned::{Object, Value};
+use crate::{Deserializer, Error, ErrorType, ParseOptions, Result, SIMDJSON_PADDING, UTF8_BOM};
+use alloc::string::{String, ToString};
+use alloc::{vec, vec::Vec};
+
+pub(crate) fn recover(input: &mut [u8], options: ParseOptions) -> Result<(Value, Vec<Error>)> {
+    let mut errors = Vec::new();
+    let input = if input.starts_with(UTF8_BOM) {
+        if !options.skip_bom {
+            errors.push(Error::new(0, '\u{feff}', ErrorType::ByteOrderMark).with_context(input));
+        }
+        input.split_at_mut(UTF8_BOM.len()).1
+    } else {
+        input
+    };
+
+    let len = input.len();
+    // stage 1 and the atom / string parsers read past the end so we work
+    // on a zero padded copy
+    let mut input2 = vec![0; len + SIMDJSON_PADDING];
+    input2[..len].copy_from_slice(input);
+    let mut structural_indexes = Vec::new();
+    Deserializer::find_structural_bits(&input2[..len], &mut structural_indexes)
+        .map_err(Error::generic)?;
+    #[cfg(not(feature = "safe"))]
+    let mut buffer = vec![0; len + SIMDJSON_PADDING];
+
+    let mut r = Recovery {
+        input,
+        len,
+        input2: &input2,
+        #[cfg(not(feature = "safe"))]
+        buffer: &mut buffer,
+        #[cfg(feature = "safe")]
+        offset: 0,
+        structural_indexes: &structural_indexes,
+        // skip the root element
+        i: 1,
+        options,
+        errors,
+    };
+    let value = r.parse_value();
+    if let Some((idx, c)) = r.peek() {
+        r.error(idx, c, ErrorType::TrailingCharacters);
+    }
+    let Recovery { errors, .. } = r;
+    Ok((value, errors))
+}
+
+struct Recovery<'r> {
+    input: &'r mut [u8],
+    len: usize,
+    input2: &'r [u8],
+    #[cfg(not(feature = "safe"))]
+    buffer: &'r mut [u8],
+    // where `input` starts, the safe string parser splits strings off it
+    #[cfg(feature = "safe")]
+    offset: usize,
+    structural_indexes: &'r [u32],
+    i: usize,
+    options: ParseOptions,
+    errors: Vec<Error>,
+}
+
+impl Recovery<'_> {
+    fn peek(&self) -> Option<(usize, u8)> {
+        self.structural_indexes.get(self.i).map(|&idx| {
+            let idx = idx as usize;
+            (idx, self.input2[idx])
+        })
+    }
+
+    fn error(&mut self, idx: usize, c: u8, error: ErrorType) {
+        let e = Error::new(idx, c as char, error).with_context(&self.input2[..self.len]);
+        self.errors.push(e);
+    }
+
+    fn eof(&mut self) {
+        self.error(self.len, 0, ErrorType::Incomplete { needed: None });
+    }
+
+    /// Parses the next value. Arrays and objects that are still open
+    /// are kept on an explicit stack instead of recursing, so deeply
+    /// nested documents can't overflow the thread stack.
+    fn parse_value(&mut self) -> Value {
+        let mut stack: Vec<Frame> = Vec::new();
+        loop {
+            let mut value = match self.peek() {
+                None => {
+                    self.eof();
+                    Value::null()
+                }
+                Some((_, b'[')) => {
+                    // eat the `[`
+                    self.i += 1;
+                    if self.array_element() {
+                        stack.push(Frame::Array(Vec::new()));
+                        continue;
+                    }
+                    Value::Array(Vec::new())
+                }
+                Some((_, b'{')) => {
+                    // eat the `{`
+                    self.i += 1;
+                    stack.push(Frame::Object(Object::new(), None));
+                    if self.object_entry(&mut stack) {
+                        continue;
+                    }
+                    stack.pop();
+                    Value::from(Object::new())
+                }
+                Some((idx, c)) => self.parse_value_at(idx, c),
+            };
+            // Hand the value to its parent, every parent that is closed
+            // after it becomes the value for its own parent in turn.
+            loop {
+                let open = match stack.last_mut() {
+                    None => return value,
+                    Some(Frame::Array(res)) => {
+                        res.push(value);
+                        self.array_separator() && self.array_element()
+                    }
+                    Some(Frame::Object(res, key)) => {
+                        if let Some(key) = key.take() {
+                            res.insert(key, value);
+                        }
+                        self.object_separator() && self.object_entry(&mut stack)
+                    }
+                    Some(Frame::Skipped) => {
+                        stack.pop();
+                        self.object_entry(&mut stack)
+                    }
+                };
+                if open {
+                    break;
+                }
+                value = match stack.pop() {
+                    Some(Frame::Array(res)) => Value::Array(res),
+                    Some(Frame::Object(res, _)) => Value::from(res),
+                    Some(Frame::Skipped) | None => unreachable!(),
+                };
+            }
+        }
+    }
+
+    fn parse_value_at(&mut self, idx: usize, c: u8) -> Value {
+        let input2 = self.input2;
+        let atom = &input2[idx..];
+        let res = match c {
+            // leave these for the enclosing array or object to handle
+            b',' | b']' | b'}' => {
+                self.error(idx, c, ErrorType::UnexpectedCharacter);
+                return Value::null();
+            }
+            b'"' => self.parse_str(idx).map(|s| Value::from(s.to_string())),
+            b't' if is_valid_true_atom(atom) => Ok(Value::from(true)),
+            b'f' if is_valid_false_atom(atom) => Ok(Value::from(false)),
+            b'n' if is_valid_null_atom(atom) => Ok(Value::null()),
+            b't' | b'f' => Err(Error::new(idx, c as char, ErrorType::ExpectedBoolean)),
+            b'n' => Err(Error::new(idx, c as char, ErrorType::ExpectedNull)),
+            b'-' | b'I' | b'N' if self.options.allow_non_finite && is_non_finite_start(atom) => {
+                Deserializer::parse_non_finite(idx, atom).map(Value::Static)
+            }
+            b'-' => self.parse_number(idx, atom, true),
+            b'0'..=b'9' => self.parse_number(idx, atom, false),
+            b'+' | b'.' if self.options.relaxes_numbers() => self.parse_number(idx, atom, false),
+            _ => Err(Error::new(idx, c as char, ErrorType::UnexpectedCharacter)),
+        };
+        self.i += 1;
+        res.unwrap_or_else(|e| {
+            self.errors.push(e.with_context(&self.input2[..self.len]));
+            Value::null()
+        })
+    }
+
+    fn parse_number(&self, idx: usize, atom: &[u8], negative: bool) -> Result<Value> {
+        Deserializer::parse_number_with(idx, atom, negative, self.options)
+            .map(Value::Static)
+            .or_else(|e| match overflowed_integer_len(&e, atom, self.options) {
+                Some(len) => Ok(Value::from(
+                    String::from_utf8_lossy(&atom[..len]).into_owned(),
+                )),
+                None => Err(e),
+            })
+    }
+
+    #[cfg(not(feature = "safe"))]
+    fn parse_str(&mut self, idx: usize) -> Result<&str> {
+        Deserializer::parse_str_(self.input, self.input2, self.buffer, idx)
+    }
+
+    #[cfg(feature = "safe")]
+    fn parse_str(&mut self, idx: usize) -> Result<&str> {
+        Deserializer::parse_str_(&mut self.input, &mut self.offset, self.input2, idx)
+    }
+
+    /// Checks if an element comes next in an array, `false` if the
+    /// array is closed instead
+    fn array_element(&mut self) -> bool {
+        match self.peek() {
+            None => {
+                self.eof();
+                false
+            }
+            Some((_, b']')) => {
+                self.i += 1;
+                false
+            }
+            // mismatched bracket, let the enclosing object close
+            Some((idx, c @ b'}')) => {
+                self.error(idx, c, ErrorType::ExpectedArrayContent);
+                false
+            }
+            Some(_) => true,
+        }
+    }
+
+    /// Reads what follows an element of an array, `false` if it closes
+    /// the array
+    fn array_separator(&mut self) -> bool {
+        match self.peek() {
+            None => {
+                self.eof();
+                false
+            }
+            Some((_, b',')) => {
+                self.i += 1;
+                if let Some((idx, c @ b']')) = self.peek() {
+                    // trailing comma
+                    self.error(idx, c, ErrorType::ExpectedArrayContent);
+                    self.i += 1;
+                    return false;
+                }
+                true
+            }
+            Some((_, b']')) => {
+                self.i += 1;
+                false
+            }
+            Some((idx, c @ b'}')) => {
+                self.error(idx, c, ErrorType::ExpectedArrayContent);
+                false
+            }
+            Some((idx, c)) => {
+                self.error(idx, c, ErrorType::ExpectedArrayComma);
+                true
+            }
+        }
+    }
+
+    /// Reads the key and the colon of the next entry of the object on top
+    /// of `stack`, `false` if the object is closed instead. Whatever is
+    /// in place of a key is read as a value and dropped.
+    fn object_entry(&mut self, stack: &mut Vec<Frame>) -> bool {
+        loop {
+            match self.peek() {
+                None => {
+                    self.eof();
+                    return false;
+                }
+                Some((_, b'}')) => {
+                    self.i += 1;
+                    return false;
+                }
+                // mismatched bracket, let the enclosing array close
+                Some((idx, c @ b']')) => {
+                    self.error(idx, c, ErrorType::ExpectedObjectContent);
+                    return false;
+                }
+                Some((idx, c @ b'"')) => {
+                    let key = match self.parse_value_at(idx, c) {
+                        Value::String(key) => Some(key),
+                        _ => None,
+                    };
+                    match self.peek() {
+                        Some((_, b':')) => self.i += 1,
+                        Some((idx, c)) => self.error(idx, c, ErrorType::ExpectedObjectColon),
+                        None => {}
+                    }
+                    if let Some(Frame::Object(_, k)) = stack.last_mut() {
+                        *k = key;
+                    }
+                    return true;
+                }
+                Some((idx, b',')) => {
+                    self.error(idx, b',', ErrorType::ExpectedObjectKey);
+                    self.i += 1;
+                }
+                Some((idx, c)) => {
+                    self.error(idx, c, ErrorType::ExpectedObjectKey);
+                    // skip whatever is in place of the key
+                    stack.push(Frame::Skipped);
+                    return true;
+                }
+            }
+        }
+    }
+
+    /// Reads what follows the value of an entry of an object, `false` if
+    /// it closes the object
+    fn object_separator(&mut self) -> bool {
+        match self.peek() {
+            None => {
+                self.eof();
+                false
+            }
+            Some((_, b',')) => {
+                self.i += 1;
+                if let Some((idx, c @ b'}')) = self.peek() {
+                    // trailing comma
+                    self.error(idx, c, ErrorType::ExpectedObjectKey);
+                    self.i += 1;
+                    return false;
+                }
+                true
+            }
+            Some((_, b'}')) => {
+                self.i += 1;
+                false
+            }
+            Some((idx, c @ b']')) => {
+                self.error(idx, c, ErrorType::ExpectedObjectContent);
+                false
+            }
+            Some((idx, c)) => {
+                self.error(idx, c, ErrorType::ExpectedMapComma);
+                true
+            }
+        }
+    }
+}
+
+/// An array or object that is still being read by `parse_value`
+enum Frame {
+    /// The elements so far
+    Array(Vec<Value>),
+    /// The entries so far and the key of the value being read, `None`
+    /// if that value is dropped
+    Object(Object, Option<String>),
+    /// A value in place of a key of the object below, it is dropped
+    Skipped,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn recover_str(s: &str) -> (Value, Vec<Error>) {
+        let mut d = s.as_bytes().to_vec();
+        recover(&mut d, ParseOptions::default()).expect("stage 1 failed")
+    }
+
+    fn types(errors: &[Error]) -> Vec<&ErrorType> {
+        errors.iter().map(Error::error).collect()
+    }
+
+    #[test]
+    fn valid() {
+        let (v, errors) = recover_str(r#"{"a": [1, true, null, "x\n"], "b": {}, "c": -1.5}"#);
+        assert!(errors.is_empty());
+        assert_eq!(v["a"][3], "x\n");
+        assert_eq!(v["c"], -1.5);
+    }
+
+    #[test]
+    fn multiple_errors() {
+        let (v, errors) = recover_str(r#"{"a": tru, "b" 2, "c": [1 2,], "d": 4,}"#);
+        assert_eq!(
+            types(&errors),
+            vec![
+                &ErrorType::ExpectedBoolean,
+                &ErrorType::ExpectedObjectColon,
+                &ErrorType::ExpectedArrayComma,
+                &ErrorType::ExpectedArrayContent,
+                &ErrorType::ExpectedObjectKey,
+            ]
+        );
+        assert_eq!(errors[1].line(), Some(1));
+        assert_eq!(errors[1].column(), Some(16));
+        assert_eq!(v["a"], ());
+        assert_eq!(v["b"], 2);
+        assert_eq!(v["c"], Value::from(vec![1, 2]));
+        assert_eq!(v["d"], 4);
+    }
+
+    #[test]
+    fn unclosed() {
+        let (v, errors) = recover_str(r#"[{"a": 1], [2"#);
+        assert_eq!(
+            types(&errors),
+            vec![
+                &ErrorType::ExpectedObjectContent,
+                &ErrorType::TrailingCharacters
+            ]
+        );
+        assert_eq!(v[0]["a"], 1);
+
+        let (v, errors) = recover_str(r#"{"a": [1, {"b": 2"#);
+        let incomplete = ErrorType::Incomplete { needed: None };
+        assert_eq!(types(&errors), vec![&incomplete; 3]);
+        assert_eq!(v["a"][1]["b"], 2);
+    }
+
+    #[test]
+    fn trailing() {
+        let (v, errors) = recover_str("1 2");
+        assert_eq!(types(&errors), vec![&ErrorType::TrailingCharacters]);
+        assert_eq!(v, 1);
+    }
+
+    #[test]
+    fn deep_nesting() {
+        use crate::value::deep::{nested, DEPTH};
+        let (v, errors) = recover_str(&nested(DEPTH, "nul"));
+        assert_eq!(types(&errors), vec![&ErrorType::ExpectedNull]);
+        assert!(v[0]["a"][0]["a"][0].is_object());
+        v.dismantle();
+    }
+
+    proptest! {
+        #[test]
+        fn prop_terminates(d in "[\\[\\]{},: 1an\"]{0,40}") {
+            let mut d = d.into_bytes();
+            let _ = recover(&mut d, ParseOptions::default());
+        }
+    }
+}
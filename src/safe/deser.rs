@@ -0,0 +1,95 @@
+use crate::stringparse::ESCAPE_MAP;
+use crate::{Deserializer, ErrorType, Result};
+
+use core::mem;
+
+impl<'de> Deserializer<'de> {
+    /// Parses the string whose opening quote is at `idx` of `data` and
+    /// de-escapes it in place.
+    ///
+    /// `input` is the part of the input that starts at `offset` and hasn't
+    /// been handed out yet, the bytes up to the closing quote are split off
+    /// of it and borrowed by the returned string. So strings have to be
+    /// parsed in the order they appear in.
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    #[allow(clippy::mut_mut)]
+    pub(crate) fn parse_str_(
+        input: &mut &'de mut [u8],
+        offset: &mut usize,
+        data: &[u8],
+        idx: usize,
+    ) -> Result<&'de str> {
+        use ErrorType::{InternalError, InvalidEscape, InvalidUTF8, InvlaidUnicodeCodepoint};
+        // Add 1 to skip the initial "
+        let start = idx + 1;
+        let dst: &mut [u8] = input;
+        let mut src_i = start;
+        // escapes are always longer than what they stand for so writing
+        // never overtakes reading
+        let mut dst_i = start - *offset;
+        loop {
+            match data.get(src_i) {
+                Some(b'"') => break,
+                Some(b'\\') => {
+                    let escape_char = data.get(src_i + 1).copied().unwrap_or(0);
+                    if escape_char == b'u' {
+                        let (c, src_len) = Self::parse_unicode_escape(&data[src_i..])
+                            .ok_or_else(|| Self::raw_error(src_i, 'u', InvlaidUnicodeCodepoint))?;
+                        dst_i += c.encode_utf8(&mut dst[dst_i..]).len();
+                        src_i += src_len;
+                    } else {
+                        let escape_result = ESCAPE_MAP[escape_char as usize];
+                        if escape_result == 0 {
+                            return Err(Self::raw_error(src_i, escape_char as char, InvalidEscape));
+                        }
+                        dst[dst_i] = escape_result;
+                        src_i += 2;
+                        dst_i += 1;
+                    }
+                }
+                Some(c) => {
+                    dst[dst_i] = *c;
+                    src_i += 1;
+                    dst_i += 1;
+                }
+                // stage 1 makes sure every string is closed
+                None => return Err(Self::raw_error(src_i, '"', InternalError)),
+            }
+        }
+
+        let (head, tail) = mem::take(input).split_at_mut(src_i - *offset);
+        let head: &'de [u8] = head;
+        let string = &head[start - *offset..dst_i];
+        *input = tail;
+        *offset = src_i;
+        core::str::from_utf8(string).map_err(|_| Self::raw_error(idx, '"', InvalidUTF8))
+    }
+
+    /// Decodes the `\uXXXX` escape, or surrogate pair of them, at the start
+    /// of `src` and returns the character along with the number of bytes
+    /// it took up.
+    fn parse_unicode_escape(src: &[u8]) -> Option<(char, usize)> {
+        let code_point = hex_to_u32(src.get(2..)?)?;
+        if (0xd800..0xdc00).contains(&code_point) {
+            // a high surrogate has to be followed by a low one
+            if src.get(6..8)? != b"\\u" {
+                return None;
+            }
+            let low = hex_to_u32(src.get(8..)?)?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return None;
+            }
+            let code_point = (((code_point - 0xd800) << 10) | (low - 0xdc00)) + 0x1_0000;
+            core::char::from_u32(code_point).map(|c| (c, 12))
+        } else {
+            core::char::from_u32(code_point).map(|c| (c, 6))
+        }
+    }
+}
+
+/// Converts the 4 hex digits at the start of `src`
+fn hex_to_u32(src: &[u8]) -> Option<u32> {
+    src.get(..4)?.iter().try_fold(0, |acc, c| {
+        char::from(*c).to_digit(16).map(|d| acc << 4 | d)
+    })
+}
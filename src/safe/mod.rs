@@ -0,0 +1,2 @@
+pub mod deser;
+pub mod stage1;
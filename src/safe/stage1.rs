@@ -0,0 +1,115 @@
+use crate::{Deserializer, ErrorType};
+use alloc::vec::Vec;
+
+/// The safe backend never reads past the end of the input, this is only
+/// used to size the string buffer.
+pub const SIMDJSON_PADDING: usize = 32;
+
+impl Deserializer<'_> {
+    /// Validates that `input` is UTF-8
+    pub(crate) fn is_valid_utf8(input: &[u8]) -> bool {
+        core::str::from_utf8(input).is_ok()
+    }
+
+    /// Scalar version of stage 1, this walks the input one byte at a time
+    /// but finds the same structural indexes as the SIMD implementations:
+    /// structural characters and opening quotes outside of strings as well
+    /// as the first character of every atom or number.
+    #[allow(clippy::cast_possible_truncation)]
+    pub(crate) fn find_structural_bits(
+        input: &[u8],
+        structural_indexes: &mut Vec<u32>,
+    ) -> core::result::Result<(), ErrorType> {
+        let len = input.len();
+        // 6 is a heuristic number to estimate it turns out a rate of 1/6 structural caracters lears
+        // almost never to relocations.
+        structural_indexes.clear();
+        structural_indexes.reserve(len / 6);
+        structural_indexes.push(0); // push extra root element
+
+        // is the current character escaped by an odd number of backslashes
+        let mut escaped = false;
+        let mut inside_quote = false;
+        // does the previous character allow a pseudo-structural character
+        // to follow it, the start of the input counts as whitespace
+        let mut prev_pseudo_pred = true;
+        // unescaped characters within strings (ASCII code points < 0x20)
+        let mut unescaped = false;
+
+        for (idx, &c) in input.iter().enumerate() {
+            let quote = c == b'"' && !escaped;
+            escaped = c == b'\\' && !escaped;
+            if quote {
+                inside_quote = !inside_quote;
+            }
+            // the opening quote is part of the string, the closing one isn't
+            let in_string = inside_quote;
+            let whitespace = matches!(c, b' ' | b'\t' | b'\n' | b'\r');
+            let structural =
+                quote || (!in_string && matches!(c, b'{' | b'}' | b'[' | b']' | b':' | b','));
+            unescaped |= in_string && c <= 0x1F;
+            let pseudo_structural = prev_pseudo_pred && !whitespace && !in_string;
+            prev_pseudo_pred = structural || whitespace;
+            // closing quotes are only needed to find pseudo-structural characters
+            let closing_quote = quote && !in_string;
+            if (structural || pseudo_structural) && !closing_quote {
+                structural_indexes.push(idx as u32);
+            }
+        }
+
+        if inside_quote {
+            return Err(ErrorType::Incomplete { needed: None });
+        }
+
+        // a valid JSON file cannot have zero structural indexes - we should have
+        // found something (note that we compare to 1 as we always add the root!)
+        if structural_indexes.len() == 1 {
+            return Err(ErrorType::EOF);
+        }
+
+        if unescaped {
+            return Err(ErrorType::Syntax);
+        }
+
+        if Self::is_valid_utf8(input) {
+            Ok(())
+        } else {
+            Err(ErrorType::InvalidUTF8)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn find_structural_bits(input: &[u8]) -> Result<Vec<u32>, ErrorType> {
+        let mut structural_indexes = Vec::new();
+        Deserializer::find_structural_bits(input, &mut structural_indexes)
+            .map(|()| structural_indexes)
+    }
+
+    fn structurals(input: &str) -> Vec<u32> {
+        find_structural_bits(input.as_bytes()).expect("stage 1 failed")
+    }
+
+    #[test]
+    fn structural_indexes() {
+        assert_eq!(
+            structurals(r#"{"a": [1, true]}"#),
+            vec![0, 0, 1, 4, 6, 7, 8, 10, 14, 15]
+        );
+        assert_eq!(structurals(r#"  "x\" y\\" null"#), vec![0, 2, 12]);
+        assert_eq!(structurals(r#""{,}"x"#), vec![0, 0, 5]);
+        assert_eq!(structurals("-1.5e3"), vec![0, 0]);
+    }
+
+    #[test]
+    fn errors() {
+        let err = |s: &[u8]| find_structural_bits(s).expect_err("stage 1 passed");
+        assert_eq!(err(b"  "), ErrorType::EOF);
+        assert_eq!(err(b"[\"a"), ErrorType::Incomplete { needed: None });
+        assert_eq!(err(b"[\"a\nb\"]"), ErrorType::Syntax);
+        assert_eq!(err(b"[\"\xff\"]"), ErrorType::InvalidUTF8);
+    }
+}
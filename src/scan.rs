@@ -0,0 +1,207 @@
+use crate::value::steps;
+use crate::{find_structurals, unescape_str};
+use core::ops::Range;
+
+/// Finds the value at `pointer` in `input` and returns its byte range,
+/// `None` if there is no value there. The value is located with the
+/// structural index from stage 1 alone, without building a value or
+/// de-escaping strings, for callers that only need one field of large
+/// documents, like routing on `"type"`.
+///
+/// `pointer` is a list of keys separated by `/` like a JSON pointer, the
+/// leading `/` is optional and `~1` and `~0` escape `/` and `~`. Steps
+/// into arrays are indexes. If an object has a key more than once the
+/// first one is used. The range covers the raw value, quotes and escapes
+/// of strings included, and the empty pointer finds the whole document.
+///
+/// ```rust
+/// use simd_json::scan::find_field;
+/// let d = br#"{"type": "order", "items": [{"sku": "a-1", "n": 2}]}"#;
+/// assert_eq!(find_field(d, "/type").map(|r| &d[r]), Some(&br#""order""#[..]));
+/// let item = find_field(d, "/items/0").unwrap();
+/// assert_eq!(&d[item], br#"{"sku": "a-1", "n": 2}"#);
+/// assert_eq!(find_field(d, "/items/0/n"), Some(48..49));
+/// assert_eq!(find_field(d, "/items/1"), None);
+/// ```
+///
+/// Only the keys on the way to the value have to be read, the document
+/// isn't validated beyond what stage 1 checks. For invalid JSON the
+/// result can be `None` or a range that doesn't hold a valid value.
+#[must_use]
+pub fn find_field(input: &[u8], pointer: &str) -> Option<Range<usize>> {
+    let index = find_structurals(input).ok()?;
+    let mut scanner = Scanner {
+        input,
+        indexes: index.as_slice(),
+        pos: 0,
+    };
+    for step in steps(pointer) {
+        scanner.descend(&step)?;
+    }
+    scanner.range()
+}
+
+/// Walks the structural index, `pos` is the index of the first
+/// structural character of the current value
+struct Scanner<'i> {
+    input: &'i [u8],
+    indexes: &'i [u32],
+    pos: usize,
+}
+
+impl Scanner<'_> {
+    /// The position and the character of the structural character at
+    /// `pos`
+    fn at(&self, pos: usize) -> Option<(usize, u8)> {
+        let idx = *self.indexes.get(pos)? as usize;
+        Some((idx, *self.input.get(idx)?))
+    }
+
+    /// Moves to the value of `step` in the current object or array
+    fn descend(&mut self, step: &str) -> Option<()> {
+        match self.at(self.pos)?.1 {
+            b'{' => {
+                self.pos += 1;
+                loop {
+                    let (start, c) = self.at(self.pos)?;
+                    if c != b'"' {
+                        return None;
+                    }
+                    let (colon, c) = self.at(self.pos + 1)?;
+                    if c != b':' {
+                        return None;
+                    }
+                    // the closing quote is the last thing before the `:`
+                    let key = trim_end(self.input.get(start + 1..colon)?).strip_suffix(b"\"")?;
+                    self.pos += 2;
+                    if key_eq(key, step) {
+                        return Some(());
+                    }
+                    self.pos = self.skip(self.pos)?;
+                    if self.at(self.pos)?.1 != b',' {
+                        return None;
+                    }
+                    self.pos += 1;
+                }
+            }
+            b'[' => {
+                if step.is_empty() || !step.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                let n: usize = step.parse().ok()?;
+                self.pos += 1;
+                for _ in 0..n {
+                    self.pos = self.skip(self.pos)?;
+                    if self.at(self.pos)?.1 != b',' {
+                        return None;
+                    }
+                    self.pos += 1;
+                }
+                Some(())
+            }
+            _ => None,
+        }
+    }
+
+    /// The index of the structural character after the value at `pos`
+    fn skip(&self, mut pos: usize) -> Option<usize> {
+        let mut depth = 0_usize;
+        loop {
+            let c = self.at(pos)?.1;
+            pos += 1;
+            match c {
+                b'{' | b'[' => depth += 1,
+                b'}' | b']' => depth = depth.checked_sub(1)?,
+                b',' | b':' if depth == 0 => return None,
+                _ => {}
+            }
+            if depth == 0 {
+                return Some(pos);
+            }
+        }
+    }
+
+    /// The byte range of the current value
+    fn range(&self) -> Option<Range<usize>> {
+        let (start, c) = self.at(self.pos)?;
+        let next = self.skip(self.pos)?;
+        let end = match c {
+            // the closing bracket is the last structural character
+            b'{' | b'[' => self.at(next - 1)?.0 + 1,
+            // strings, numbers and atoms run up to the next structural
+            // character, or the end, with whitespace in between
+            _ => {
+                let end = self.at(next).map_or(self.input.len(), |(idx, _)| idx);
+                start + trim_end(self.input.get(start..end)?).len()
+            }
+        };
+        Some(start..end)
+    }
+}
+
+fn trim_end(s: &[u8]) -> &[u8] {
+    let len = s
+        .iter()
+        .rposition(|c| !matches!(c, b' ' | b'\t' | b'\n' | b'\r'))
+        .map_or(0, |idx| idx + 1);
+    &s[..len]
+}
+
+/// Whether the raw `key` is `step`, only keys with escapes are
+/// de-escaped
+fn key_eq(key: &[u8], step: &str) -> bool {
+    if key.contains(&b'\\') {
+        unescape_str(key).ok().as_deref() == Some(step)
+    } else {
+        key == step.as_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn field<'i>(input: &'i [u8], pointer: &str) -> Option<&'i [u8]> {
+        find_field(input, pointer).map(|r| &input[r])
+    }
+
+    #[test]
+    fn finds() {
+        let d = " {\"a\" : [1 , \"x\\\"y\" ,{\"b\":null}, [] ], \"c~/\": true ,\n\"\\u0064\": -2.5e3 } "
+            .as_bytes();
+        assert_eq!(field(d, ""), Some(&d[1..d.len() - 1]));
+        assert_eq!(field(d, "/"), field(d, ""));
+        assert_eq!(field(d, "/a/0"), Some(&b"1"[..]));
+        assert_eq!(field(d, "a/1"), Some(&br#""x\"y""#[..]));
+        assert_eq!(field(d, "/a/2"), Some(&br#"{"b":null}"#[..]));
+        assert_eq!(field(d, "/a/2/b"), Some(&b"null"[..]));
+        assert_eq!(field(d, "/a/3"), Some(&b"[]"[..]));
+        assert_eq!(field(d, "/c~0~1"), Some(&b"true"[..]));
+        assert_eq!(field(d, "/d"), Some(&b"-2.5e3"[..]));
+        assert_eq!(field(b"42", ""), Some(&b"42"[..]));
+        assert_eq!(field(b"[\"a\"]", "/0"), Some(&b"\"a\""[..]));
+        // the first of a duplicated key
+        assert_eq!(field(br#"{"k": 1, "k": 2}"#, "/k"), Some(&b"1"[..]));
+        // more than one block of 64 bytes
+        let long = alloc::format!("[{}{{\"x\": \"y\"}}]", "[0, {\"x\": 1}], ".repeat(50));
+        assert_eq!(field(long.as_bytes(), "/50/x"), Some(&b"\"y\""[..]));
+        assert_eq!(field(long.as_bytes(), "/49/1/x"), Some(&b"1"[..]));
+    }
+
+    #[test]
+    fn missing() {
+        let d = br#"{"a": [1, {"b": 2}], "c": "d", "e": {}}"#;
+        for pointer in &[
+            "/x", "/a/2", "/a/-", "/a/b", "/a/1/c", "/c/0", "/e/a", "/a/", "/a/+1",
+        ] {
+            assert_eq!(find_field(d, pointer), None, "{}", pointer);
+        }
+        assert_eq!(find_field(b"[]", "/0"), None);
+        assert_eq!(find_field(b"[1,]", "/1"), None);
+        assert_eq!(find_field(b"", ""), None);
+        assert_eq!(find_field(b"[\"a", "/0"), None);
+        assert_eq!(find_field(b"{\"a\" 1}", "/a"), None);
+        assert_eq!(find_field(b"{\"a\": 1", "/a"), Some(6..7));
+        assert_eq!(find_field(b"}", ""), None);
+    }
+}
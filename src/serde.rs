@@ -11,14 +11,16 @@ mod se;
 mod value;
 pub use self::se::*;
 pub use self::value::*;
-use crate::{stry, Deserializer, Error, ErrorType, Result};
+use crate::{stry, Deserializer, Error, ErrorType, ParseOptions, Result};
 use crate::{BorrowedValue, OwnedValue};
 use crate::{Node, StaticNode};
 use serde::de::DeserializeOwned;
 use serde_ext::Deserialize;
 use std::convert::{TryFrom, TryInto};
 use std::fmt;
+use std::fs;
 use std::io;
+use std::path::Path;
 use value_trait::Value;
 type ConvertResult<T> = std::result::Result<T, SerdeConversionError>;
 
@@ -62,14 +64,47 @@ where
     let mut deserializer = stry!(Deserializer::from_slice(s));
     T::deserialize(&mut deserializer)
 }
+/// parses a byte slice using a serde deserializer and the given
+/// parse `options`.
+/// note that the slice will be rewritten in the process.
+///
+/// # Errors
+///
+/// Will return `Err` if `s` is invalid JSON. With the lossy UTF-8
+/// fallback, input that isn't UTF-8 is read from an `OwnedValue` of the
+/// repaired copy since `T` can't borrow from that, so types that borrow
+/// strings from the input fail on it.
+#[cfg_attr(not(feature = "no-inline"), inline(always))]
+pub fn from_slice_with_options<'a, T>(s: &'a mut [u8], options: ParseOptions) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    let mut deserializer = match Deserializer::from_slice_or_input(s, options) {
+        Ok(deserializer) => deserializer,
+        Err((e, input)) => {
+            return match options.repair_utf8(input) {
+                Some(mut repaired) => T::deserialize(stry!(crate::to_owned_value_with_options(
+                    &mut repaired,
+                    options
+                ))),
+                None => Err(e),
+            };
+        }
+    };
+    T::deserialize(&mut deserializer)
+}
 /// parses a str  using a serde deserializer.
 /// note that the slice will be rewritten in the process and
 /// might not remain a valid utf8 string in its entirety.
 ///
+/// This needs `str::as_bytes_mut` and so is the one function that uses
+/// `unsafe` even with the `safe` feature, use `from_slice` instead.
+///
 /// # Errors
 ///
 /// Will return `Err` if `s` is invalid JSON.
 #[cfg_attr(not(feature = "no-inline"), inline(always))]
+#[allow(unsafe_code)]
 pub fn from_str<'a, T>(s: &'a mut str) -> Result<T>
 where
     T: Deserialize<'a>,
@@ -99,7 +134,21 @@ where
     T::deserialize(&mut deserializer)
 }
 
-impl std::error::Error for Error {}
+/// Reads the file at `path` and deserializes it with serde.
+///
+/// # Errors
+///
+/// Will return `Err` if the file can't be read or if its content is
+/// invalid JSON.
+pub fn from_file<T, P>(path: P) -> Result<T>
+where
+    T: DeserializeOwned,
+    P: AsRef<Path>,
+{
+    let mut data = stry!(fs::read(path).map_err(|e| Error::generic(ErrorType::IO(e))));
+    let mut deserializer = stry!(Deserializer::from_slice(&mut data));
+    T::deserialize(&mut deserializer)
+}
 
 impl serde::de::Error for Error {
     fn custom<T: fmt::Display>(msg: T) -> Self {
@@ -314,6 +363,8 @@ impl TryInto<serde_json::Value> for OwnedValue {
                 }
             }
             Self::String(b) => Value::String(b),
+            #[cfg(feature = "bytes")]
+            Self::Bytes(b) => Value::String(crate::value::to_base64(&b)),
             Self::Array(a) => Value::Array(
                 a.into_iter()
                     .map(|v| v.try_into())
@@ -385,6 +436,8 @@ impl<'value> TryInto<serde_json::Value> for BorrowedValue<'value> {
                 }
             }
             BorrowedValue::String(b) => Value::String(b.to_string()),
+            #[cfg(feature = "bytes")]
+            BorrowedValue::Bytes(b) => Value::String(crate::value::to_base64(&b)),
             BorrowedValue::Array(a) => Value::Array(
                 a.into_iter()
                     .map(|v| v.try_into())
@@ -399,12 +452,103 @@ impl<'value> TryInto<serde_json::Value> for BorrowedValue<'value> {
     }
 }
 
+/// Compares a DOM value with a `serde_json::Value` without converting
+/// either of them. Numbers compare like they do between the DOMs, by
+/// value for integers while a float never equals an integer.
+fn eq_serde_json<V>(value: &V, other: &serde_json::Value) -> bool
+where
+    V: Value,
+    V::Key: std::borrow::Borrow<str>,
+{
+    use serde_json::Value as SerdeValue;
+    use value_trait::{Array, Object};
+    match other {
+        SerdeValue::Null => value.is_null(),
+        SerdeValue::Bool(b) => value.as_bool() == Some(*b),
+        SerdeValue::Number(n) => {
+            if let Some(n) = n.as_i64() {
+                value.as_i64() == Some(n)
+            } else if let Some(n) = n.as_u64() {
+                value.as_u64() == Some(n)
+            } else {
+                n.as_f64().is_some() && value.as_f64() == n.as_f64()
+            }
+        }
+        SerdeValue::String(s) => value.as_str() == Some(s.as_str()),
+        SerdeValue::Array(a) => match value.as_array() {
+            Some(v) => v.len() == a.len() && v.iter().zip(a).all(|(v, a)| eq_serde_json(v, a)),
+            None => false,
+        },
+        SerdeValue::Object(o) => match value.as_object() {
+            Some(v) => {
+                v.len() == o.len()
+                    && o.iter().all(|(k, o)| match v.get(k.as_str()) {
+                        Some(v) => eq_serde_json(v, o),
+                        None => false,
+                    })
+            }
+            None => false,
+        },
+    }
+}
+
+impl PartialEq<serde_json::Value> for OwnedValue {
+    fn eq(&self, other: &serde_json::Value) -> bool {
+        eq_serde_json(self, other)
+    }
+}
+
+impl PartialEq<OwnedValue> for serde_json::Value {
+    fn eq(&self, other: &OwnedValue) -> bool {
+        eq_serde_json(other, self)
+    }
+}
+
+impl PartialEq<serde_json::Value> for BorrowedValue<'_> {
+    fn eq(&self, other: &serde_json::Value) -> bool {
+        eq_serde_json(self, other)
+    }
+}
+
+impl<'value> PartialEq<BorrowedValue<'value>> for serde_json::Value {
+    fn eq(&self, other: &BorrowedValue<'value>) -> bool {
+        eq_serde_json(other, self)
+    }
+}
+
 #[cfg(test)]
 mod test {
     #![allow(clippy::result_unwrap_used)]
     use crate::{json, BorrowedValue, OwnedValue};
     use serde_json::{json as sjson, Value as SerdeValue};
     use std::convert::TryInto;
+    #[test]
+    fn files() {
+        let dir = std::env::temp_dir().join("simd-json-serde-files");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir(&dir).unwrap();
+        let path = dir.join("config.json");
+        std::fs::write(&path, "old").unwrap();
+        let v = vec![json!({"a": [1, 2]})];
+        crate::to_file(&v, &path, true).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            crate::to_string_pretty(&v).unwrap()
+        );
+        crate::to_file(&v, &path, false).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), r#"[{"a":[1,2]}]"#);
+        let back: Vec<OwnedValue> = crate::from_file(&path).unwrap();
+        assert_eq!(back, v);
+        // only the file is left, without the temporary one
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        assert!(crate::to_file(&v, dir.join("missing/x.json"), false).is_err());
+        assert!(crate::to_file(&v, "", false).is_err());
+        std::fs::write(&path, "[1,").unwrap();
+        assert!(crate::from_file::<Vec<u8>, _>(&path).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(crate::from_file::<Vec<u8>, _>(&path).is_err());
+    }
+
     #[test]
     fn convert_owned_value() {
         let v: OwnedValue = json!({
@@ -468,6 +612,41 @@ mod test {
         assert_eq!(v, v_c);
     }
 
+    #[test]
+    fn compare_serde_json_value() {
+        let mut d = br#"{"int": 42, "neg": -23, "big": 18446744073709551615, "float": 7.5,
+            "string": "s", "bool": true, "null": null, "nested": {"a": [1, {"b": []}]}}"#
+            .to_vec();
+        let o = crate::to_owned_value(&mut d.clone()).unwrap();
+        let b = crate::to_borrowed_value(&mut d).unwrap();
+        let s: SerdeValue = sjson!({
+            "int": 42, "neg": -23, "big": 18_446_744_073_709_551_615_u64, "float": 7.5,
+            "string": "s", "bool": true, "null": null, "nested": {"a": [1, {"b": []}]}
+        });
+        assert_eq!(o, s);
+        assert_eq!(s, o);
+        assert_eq!(b, s);
+        assert_eq!(s, b);
+
+        let differs = [
+            sjson!({}),
+            sjson!([]),
+            sjson!({"int": 42}),
+            sjson!({"int": 42.0}),
+            sjson!({"a": null}),
+        ];
+        for s in &differs {
+            assert_ne!(&o, s);
+            assert_ne!(s, &b);
+        }
+        assert_eq!(OwnedValue::from(1), sjson!(1));
+        assert_ne!(OwnedValue::from(1.0), sjson!(1));
+        assert_ne!(OwnedValue::from(-1), sjson!(18_446_744_073_709_551_615_u64));
+        assert_eq!(OwnedValue::from(vec![1, 2]), sjson!([1, 2]));
+        assert_ne!(OwnedValue::from(vec![1, 2]), sjson!([1, 2, 3]));
+        assert_ne!(OwnedValue::from("1"), sjson!(1));
+    }
+
     #[test]
     fn option_field_absent() {
         #[derive(serde::Deserialize, Debug)]
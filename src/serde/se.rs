@@ -1,8 +1,11 @@
 mod pp;
+use crate::value::encoder::into_string;
 use crate::{serde_ext, str, Error, ErrorType};
 pub use pp::*;
 use serde_ext::ser;
-use std::io::Write;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
 use std::result::Result;
 use value_trait::generator::BaseGenerator;
 
@@ -34,7 +37,7 @@ pub fn to_string<T>(to: &T) -> crate::Result<String>
 where
     T: ser::Serialize + ?Sized,
 {
-    to_vec(to).map(|v| unsafe { String::from_utf8_unchecked(v) })
+    to_vec(to).map(into_string)
 }
 
 /// Write a value to a string
@@ -49,6 +52,58 @@ where
     let mut s = Serializer(writer);
     to.serialize(&mut s)
 }
+
+/// Serializes `to` into the file at `path`, pretty printed if `pretty` is
+/// set. The output goes to a temporary file next to `path` first, which
+/// replaces `path` once it is complete and synced, so readers of `path`
+/// see either the old or the new content and never a partial file.
+///
+/// ```rust
+/// let path = std::env::temp_dir().join("simd-json-to-file-doc.json");
+/// simd_json::to_file(&vec![1, 2, 3], &path, false).unwrap();
+/// let v: Vec<u8> = simd_json::from_file(&path).unwrap();
+/// assert_eq!(v, [1, 2, 3]);
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+///
+/// # Errors
+/// when the data can not be serialized or the file can not be written
+pub fn to_file<T, P>(to: &T, path: P, pretty: bool) -> crate::Result<()>
+where
+    T: ser::Serialize + ?Sized,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let Some(name) = path.file_name() else {
+        let err = io::Error::from(io::ErrorKind::InvalidInput);
+        return Err(Error::generic(ErrorType::IO(err)));
+    };
+    let tmp = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        name.to_string_lossy(),
+        std::process::id()
+    ));
+    let res = write_file(to, &tmp, pretty).and_then(|()| iomap!(fs::rename(&tmp, path)));
+    if res.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    res
+}
+
+fn write_file<T>(to: &T, path: &Path, pretty: bool) -> crate::Result<()>
+where
+    T: ser::Serialize + ?Sized,
+{
+    let mut writer = io::BufWriter::new(iomap!(File::create(path))?);
+    if pretty {
+        to_writer_pretty(&mut writer, to)?;
+    } else {
+        to_writer(&mut writer, to)?;
+    }
+    let file = iomap!(writer.into_inner().map_err(io::IntoInnerError::into_error))?;
+    iomap!(file.sync_all())
+}
+
 struct Serializer<W: Write>(W);
 
 impl<'w, W> BaseGenerator for Serializer<W>
@@ -64,6 +119,16 @@ where
     fn write_min(&mut self, _slice: &[u8], min: u8) -> std::io::Result<()> {
         self.0.write_all(&[min])
     }
+    #[inline]
+    fn write_float(&mut self, num: f64) -> std::io::Result<()> {
+        if num.is_finite() {
+            let mut buffer = ryu::Buffer::new();
+            self.write(buffer.format_finite(num).as_bytes())
+        } else {
+            // JSON has no representation for NaN or infinity
+            self.write(b"null")
+        }
+    }
 }
 struct SerializeSeq<'s, W: Write + 's> {
     s: &'s mut Serializer<W>,
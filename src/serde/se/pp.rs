@@ -1,3 +1,4 @@
+use crate::value::encoder::into_string;
 use crate::{serde_ext, str, stry, Error, ErrorType};
 use serde_ext::ser;
 use std::io::Write;
@@ -32,7 +33,7 @@ pub fn to_string_pretty<T>(to: &T) -> crate::Result<String>
 where
     T: ser::Serialize + ?Sized,
 {
-    to_vec_pretty(to).map(|v| unsafe { String::from_utf8_unchecked(v) })
+    to_vec_pretty(to).map(into_string)
 }
 
 /// Write a value to a string
@@ -71,6 +72,16 @@ where
         self.writer.write_all(&[min])
     }
     #[inline]
+    fn write_float(&mut self, num: f64) -> std::io::Result<()> {
+        if num.is_finite() {
+            let mut buffer = ryu::Buffer::new();
+            self.write(buffer.format_finite(num).as_bytes())
+        } else {
+            // JSON has no representation for NaN or infinity
+            self.write(b"null")
+        }
+    }
+    #[inline]
     fn new_line(&mut self) -> std::io::Result<()> {
         self.write_char(b'\n').and_then(|_| match self.dent {
             0 => Ok(()),
@@ -42,6 +42,11 @@ impl<'de> de::Deserializer<'de> for Value<'de> {
                 Cow::Owned(s) => visitor.visit_string(s),
             },
 
+            #[cfg(feature = "bytes")]
+            Value::Bytes(b) => match b {
+                std::borrow::Cow::Borrowed(b) => visitor.visit_borrowed_bytes(b),
+                std::borrow::Cow::Owned(b) => visitor.visit_byte_buf(b),
+            },
             Value::Array(a) => visitor.visit_seq(Array(a.iter())),
             Value::Object(o) => visitor.visit_map(ObjectAccess {
                 i: o.iter(),
@@ -310,6 +315,33 @@ impl<'de> Visitor<'de> for ValueVisitor {
 
     /****************** byte stuff ******************/
 
+    #[cfg(feature = "bytes")]
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn visit_borrowed_bytes<E>(self, value: &'de [u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Bytes(value.into()))
+    }
+
+    #[cfg(feature = "bytes")]
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn visit_bytes<E>(self, value: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Bytes(value.to_vec().into()))
+    }
+
+    #[cfg(feature = "bytes")]
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn visit_byte_buf<E>(self, value: Vec<u8>) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Bytes(value.into()))
+    }
+
     /*
     #[cfg_attr(not(feature = "no-inline"), inline)]
     fn visit_borrowed_bytes<E>(self, value: &'de [u8]) -> Result<Self::Value, E>
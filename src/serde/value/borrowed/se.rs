@@ -20,6 +20,16 @@ impl<'a> Serialize for Value<'a> {
             #[cfg(feature = "128bit")]
             Value::Static(StaticNode::I128(i)) => serializer.serialize_i128(*i),
             Value::String(s) => serializer.serialize_str(&s),
+            // binary formats take the bytes as they are, JSON and other
+            // text formats get them as base64 like `encode` writes them
+            #[cfg(feature = "bytes")]
+            Value::Bytes(b) => {
+                if serializer.is_human_readable() {
+                    serializer.serialize_str(&crate::value::to_base64(b))
+                } else {
+                    serializer.serialize_bytes(b)
+                }
+            }
             Value::Array(v) => {
                 let mut seq = serializer.serialize_seq(Some(v.len()))?;
                 for e in v {
@@ -30,6 +30,8 @@ impl<'de> de::Deserializer<'de> for Value {
             Self::Static(StaticNode::U128(n)) => visitor.visit_u128(n),
             Self::Static(StaticNode::F64(n)) => visitor.visit_f64(n),
             Self::String(s) => visitor.visit_string(s),
+            #[cfg(feature = "bytes")]
+            Self::Bytes(b) => visitor.visit_byte_buf(b),
             Self::Array(a) => visit_array(a, visitor),
             Self::Object(o) => visit_object(o, visitor),
         }
@@ -616,6 +618,24 @@ impl<'de> Visitor<'de> for ValueVisitor {
 
     /****************** byte stuff ******************/
 
+    #[cfg(feature = "bytes")]
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn visit_bytes<E>(self, value: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Bytes(value.to_vec()))
+    }
+
+    #[cfg(feature = "bytes")]
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn visit_byte_buf<E>(self, value: Vec<u8>) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Bytes(value))
+    }
+
     /*
     #[cfg_attr(not(feature = "no-inline"), inline)]
     fn visit_borrowed_bytes<E>(self, value: &'de [u8]) -> Result<Self::Value, E>
@@ -702,4 +722,23 @@ mod test {
             .and_then(super::super::from_value);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn value_as_field() {
+        // the impls only go through the serde data model, so any format
+        // can carry a value as part of a larger struct
+        #[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+        pub struct Event {
+            pub id: u64,
+            pub payload: crate::OwnedValue,
+        }
+        let mut raw_json = br#"{"id":1,"payload":{"a":[1,-2,3.5,"b",null,true],"c":{}}}"#.to_vec();
+        let payload = crate::to_owned_value(&mut raw_json.clone()).unwrap()["payload"].clone();
+        let event: Event = serde_json::from_slice(&raw_json).unwrap();
+        assert_eq!(event.payload, payload);
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(serde_json::from_str::<Event>(&json).unwrap(), event);
+        let simd: Event = crate::serde::from_slice(&mut raw_json).unwrap();
+        assert_eq!(simd, event);
+    }
 }
@@ -22,6 +22,16 @@ impl Serialize for Value {
             #[cfg(feature = "128bit")]
             Self::Static(StaticNode::I128(i)) => serializer.serialize_i128(*i),
             Self::String(s) => serializer.serialize_str(&s),
+            // binary formats take the bytes as they are, JSON and other
+            // text formats get them as base64 like `encode` writes them
+            #[cfg(feature = "bytes")]
+            Self::Bytes(b) => {
+                if serializer.is_human_readable() {
+                    serializer.serialize_str(&crate::value::to_base64(b))
+                } else {
+                    serializer.serialize_bytes(b)
+                }
+            }
             Self::Array(v) => {
                 let mut seq = serializer.serialize_seq(Some(v.len()))?;
                 for e in v {
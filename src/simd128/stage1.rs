@@ -0,0 +1,238 @@
+#![allow(dead_code)]
+use crate::utf8check::Utf8Check;
+use alloc::vec::Vec;
+
+use crate::{ProcessedUtfBytes, Stage1Parse, Utf8CheckingState};
+use core::arch::wasm32::{
+    i8x16, i8x16_gt, i8x16_ne, i8x16_splat, u32x4, u32x4_add, u32x4_splat, u8x16, u8x16_bitmask,
+    u8x16_eq, u8x16_le, u8x16_shr, u8x16_splat, u8x16_swizzle, v128, v128_and, v128_any_true,
+    v128_load, v128_or, v128_store,
+};
+
+use core::mem;
+
+pub const SIMDJSON_PADDING: usize = mem::size_of::<v128>() * 2;
+pub const SIMDINPUT_LENGTH: usize = 64;
+
+#[derive(Debug)]
+pub(crate) struct SimdInput {
+    v0: v128,
+    v1: v128,
+    v2: v128,
+    v3: v128,
+}
+
+/// combines the high bits of four comparison results into one mask
+#[cfg_attr(not(feature = "no-inline"), inline(always))]
+fn movemask_bulk(p0: v128, p1: v128, p2: v128, p3: v128) -> u64 {
+    u64::from(u8x16_bitmask(p0))
+        | (u64::from(u8x16_bitmask(p1)) << 16)
+        | (u64::from(u8x16_bitmask(p2)) << 32)
+        | (u64::from(u8x16_bitmask(p3)) << 48)
+}
+
+impl Stage1Parse<v128> for SimdInput {
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    #[allow(clippy::cast_ptr_alignment)]
+    fn new(ptr: &[u8]) -> Self {
+        unsafe {
+            Self {
+                v0: v128_load(ptr.as_ptr() as *const v128),
+                v1: v128_load(ptr.as_ptr().add(16) as *const v128),
+                v2: v128_load(ptr.as_ptr().add(32) as *const v128),
+                v3: v128_load(ptr.as_ptr().add(48) as *const v128),
+            }
+        }
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn new_utf8_checking_state() -> Utf8CheckingState<v128> {
+        Utf8CheckingState {
+            has_error: Self::zero(),
+            previous: ProcessedUtfBytes::default(),
+        }
+    }
+
+    // there is no carry-less multiply in simd128 so we compute the prefix
+    // xor by hand
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn compute_quote_mask(quote_bits: u64) -> u64 {
+        let mut quote_mask: u64 = quote_bits ^ (quote_bits << 1);
+        quote_mask ^= quote_mask << 2;
+        quote_mask ^= quote_mask << 4;
+        quote_mask ^= quote_mask << 8;
+        quote_mask ^= quote_mask << 16;
+        quote_mask ^= quote_mask << 32;
+        quote_mask
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn check_utf8(&self, state: &mut Utf8CheckingState<v128>) {
+        if u8x16_bitmask(v128_or(self.v0, self.v1)) == 0 {
+            // it is ascii, we just check continuation
+            state.has_error = v128_or(
+                i8x16_gt(
+                    state.previous.carried_continuations,
+                    i8x16(9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 1),
+                ),
+                state.has_error,
+            );
+        } else {
+            // it is not ascii so we have to do heavy work
+            state.previous =
+                ProcessedUtfBytes::check_utf8_bytes(self.v0, &state.previous, &mut state.has_error);
+            state.previous =
+                ProcessedUtfBytes::check_utf8_bytes(self.v1, &state.previous, &mut state.has_error);
+        }
+
+        if u8x16_bitmask(v128_or(self.v2, self.v3)) == 0 {
+            // it is ascii, we just check continuation
+            state.has_error = v128_or(
+                i8x16_gt(
+                    state.previous.carried_continuations,
+                    i8x16(9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 1),
+                ),
+                state.has_error,
+            );
+        } else {
+            // it is not ascii so we have to do heavy work
+            state.previous =
+                ProcessedUtfBytes::check_utf8_bytes(self.v2, &state.previous, &mut state.has_error);
+            state.previous =
+                ProcessedUtfBytes::check_utf8_bytes(self.v3, &state.previous, &mut state.has_error);
+        }
+    }
+
+    /// a straightforward comparison of a mask against input
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn cmp_mask_against_input(&self, m: u8) -> u64 {
+        let mask: v128 = u8x16_splat(m);
+        movemask_bulk(
+            u8x16_eq(self.v0, mask),
+            u8x16_eq(self.v1, mask),
+            u8x16_eq(self.v2, mask),
+            u8x16_eq(self.v3, mask),
+        )
+    }
+
+    // find all values less than or equal than the content of maxval (using unsigned arithmetic)
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn unsigned_lteq_against_input(&self, maxval: v128) -> u64 {
+        movemask_bulk(
+            u8x16_le(self.v0, maxval),
+            u8x16_le(self.v1, maxval),
+            u8x16_le(self.v2, maxval),
+            u8x16_le(self.v3, maxval),
+        )
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn find_whitespace_and_structurals(&self, whitespace: &mut u64, structurals: &mut u64) {
+        // do a 'shufti' to detect structural JSON characters
+        // they are
+        // * `{` 0x7b
+        // * `}` 0x7d
+        // * `:` 0x3a
+        // * `[` 0x5b
+        // * `]` 0x5d
+        // * `,` 0x2c
+        // these go into the first 3 buckets of the comparison (1/2/4)
+
+        // we are also interested in the four whitespace characters:
+        // * space 0x20
+        // * linefeed 0x0a
+        // * horizontal tab 0x09
+        // * carriage return 0x0d
+        // these go into the next 2 buckets of the comparison (8/16)
+
+        let low_nibble_mask: v128 = u8x16(16, 0, 0, 0, 0, 0, 0, 0, 0, 8, 12, 1, 2, 9, 0, 0);
+        // unlike `pshufb` the swizzle doesn't zero bytes with the high bit set
+        // so the upper half of the table has to be empty to ignore them
+        let high_nibble_mask: v128 = u8x16(8, 0, 18, 4, 0, 1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0);
+
+        let structural_shufti_mask: v128 = u8x16_splat(0x7);
+        let whitespace_shufti_mask: v128 = u8x16_splat(0x18);
+        let low_nib_and_mask: v128 = u8x16_splat(0xf);
+        let zero: v128 = Self::zero();
+
+        let classify = |v: v128| -> v128 {
+            v128_and(
+                u8x16_swizzle(low_nibble_mask, v128_and(v, low_nib_and_mask)),
+                u8x16_swizzle(high_nibble_mask, u8x16_shr(v, 4)),
+            )
+        };
+        let v_0: v128 = classify(self.v0);
+        let v_1: v128 = classify(self.v1);
+        let v_2: v128 = classify(self.v2);
+        let v_3: v128 = classify(self.v3);
+
+        *structurals = movemask_bulk(
+            i8x16_ne(v128_and(v_0, structural_shufti_mask), zero),
+            i8x16_ne(v128_and(v_1, structural_shufti_mask), zero),
+            i8x16_ne(v128_and(v_2, structural_shufti_mask), zero),
+            i8x16_ne(v128_and(v_3, structural_shufti_mask), zero),
+        );
+
+        *whitespace = movemask_bulk(
+            i8x16_ne(v128_and(v_0, whitespace_shufti_mask), zero),
+            i8x16_ne(v128_and(v_1, whitespace_shufti_mask), zero),
+            i8x16_ne(v128_and(v_2, whitespace_shufti_mask), zero),
+            i8x16_ne(v128_and(v_3, whitespace_shufti_mask), zero),
+        );
+    }
+
+    // flatten out values in 'bits' assuming that they are are to have values of idx
+    // plus their position in the bitvector, and store these indexes at
+    // base_ptr[base] incrementing base as we go
+    // will potentially store extra values beyond end of valid bits, so base_ptr
+    // needs to be large enough to handle this
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    #[allow(clippy::cast_ptr_alignment)]
+    fn flatten_bits(base: &mut Vec<u32>, idx: u32, mut bits: u64) {
+        let cnt: usize = bits.count_ones() as usize;
+        let mut l = base.len();
+        let idx_64_v: v128 = u32x4_splat(idx.wrapping_sub(64));
+
+        // We're doing some trickery here.
+        // We reserve 64 extra entries, because we've at most 64 bit to set
+        // then we trunctate the base to the next base (that we calcuate above)
+        // We later indiscriminatory writre over the len we set but that's OK
+        // since we ensure we reserve the needed space
+        base.reserve(64);
+        unsafe {
+            base.set_len(l + cnt);
+        }
+
+        while bits != 0 {
+            let v0 = bits.trailing_zeros();
+            bits &= bits.wrapping_sub(1);
+            let v1 = bits.trailing_zeros();
+            bits &= bits.wrapping_sub(1);
+            let v2 = bits.trailing_zeros();
+            bits &= bits.wrapping_sub(1);
+            let v3 = bits.trailing_zeros();
+            bits &= bits.wrapping_sub(1);
+
+            let v: v128 = u32x4_add(idx_64_v, u32x4(v0, v1, v2, v3));
+            unsafe {
+                v128_store(base.as_mut_ptr().add(l) as *mut v128, v);
+            }
+            l += 4;
+        }
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn check_utf8_errors(state: &Utf8CheckingState<v128>) -> bool {
+        v128_any_true(state.has_error)
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn fill_s8(n: i8) -> v128 {
+        i8x16_splat(n)
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn zero() -> v128 {
+        i8x16_splat(0)
+    }
+}
@@ -0,0 +1,155 @@
+use core::arch::wasm32::{
+    i8x16, i8x16_add, i8x16_eq, i8x16_gt, i8x16_shuffle, i8x16_splat, u8x16, u8x16_shr,
+    u8x16_splat, u8x16_sub_sat, u8x16_swizzle, v128, v128_and, v128_or,
+};
+
+use crate::utf8check::{ProcessedUtfBytes, Utf8Check};
+
+// the tables are compared as signed bytes, `v128` doesn't care how they
+// are built so we can spell them as unsigned ones
+macro_rules! initial_mins {
+    () => {
+        u8x16(
+            0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, // 0xxx => false
+            0x80, 0x80, 0x80, 0x80, // 10xx => false
+            0xC2, 0x80, // 110x
+            0xE1, // 1110
+            0xF1, // 1111
+        )
+    };
+}
+
+macro_rules! second_mins {
+    () => {
+        u8x16(
+            0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, // 0xxx => false
+            0x80, 0x80, 0x80, 0x80, // 10xx => false
+            0x7F, 0x7F, // 110x => true
+            0xA0, // 1110
+            0x90, // 1111
+        )
+    };
+}
+
+impl Default for ProcessedUtfBytes<v128> {
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn default() -> Self {
+        Self {
+            rawbytes: i8x16_splat(0),
+            high_nibbles: i8x16_splat(0),
+            carried_continuations: i8x16_splat(0),
+        }
+    }
+}
+
+impl Utf8Check<v128> for ProcessedUtfBytes<v128> {
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn new_processed_utf_bytes() -> Self {
+        Self::default()
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn push_last_byte_of_a_to_b(a: v128, b: v128) -> v128 {
+        i8x16_shuffle::<15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30>(a, b)
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn push_last_2bytes_of_a_to_b(a: v128, b: v128) -> v128 {
+        i8x16_shuffle::<14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29>(a, b)
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn check_smaller_than_0xf4(current_bytes: v128, has_error: &mut v128) {
+        // unsigned, saturates to 0 below max
+        *has_error = v128_or(*has_error, u8x16_sub_sat(current_bytes, u8x16_splat(0xF4)));
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn continuation_lengths(high_nibbles: v128) -> v128 {
+        u8x16_swizzle(
+            i8x16(
+                1, 1, 1, 1, 1, 1, 1, 1, // 0xxx (ASCII)
+                0, 0, 0, 0, // 10xx (continuation)
+                2, 2, // 110x
+                3, // 1110
+                4, // 1111, next should be 0 (not checked here)
+            ),
+            high_nibbles,
+        )
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn carry_continuations(initial_lengths: v128, previous_carries: v128) -> v128 {
+        let right1: v128 = u8x16_sub_sat(
+            Self::push_last_byte_of_a_to_b(previous_carries, initial_lengths),
+            u8x16_splat(1),
+        );
+        let sum: v128 = i8x16_add(initial_lengths, right1);
+        let right2: v128 = u8x16_sub_sat(
+            Self::push_last_2bytes_of_a_to_b(previous_carries, sum),
+            u8x16_splat(2),
+        );
+        i8x16_add(sum, right2)
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn check_continuations(initial_lengths: v128, carries: v128, has_error: &mut v128) {
+        // overlap || underlap
+        // carry > length && length > 0 || !(carry > length) && !(length > 0)
+        // (carries > length) == (lengths > 0)
+        let overunder: v128 = i8x16_eq(
+            i8x16_gt(carries, initial_lengths),
+            i8x16_gt(initial_lengths, i8x16_splat(0)),
+        );
+
+        *has_error = v128_or(*has_error, overunder);
+    }
+
+    // when 0xED is found, next byte must be no larger than 0x9F
+    // when 0xF4 is found, next byte must be no larger than 0x8F
+    // next byte must be continuation, ie sign bit is set, so signed < is ok
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn check_first_continuation_max(
+        current_bytes: v128,
+        off1_current_bytes: v128,
+        has_error: &mut v128,
+    ) {
+        let mask_ed: v128 = i8x16_eq(off1_current_bytes, u8x16_splat(0xED));
+        let mask_f4: v128 = i8x16_eq(off1_current_bytes, u8x16_splat(0xF4));
+
+        let badfollow_ed: v128 = v128_and(i8x16_gt(current_bytes, u8x16_splat(0x9F)), mask_ed);
+        let badfollow_f4: v128 = v128_and(i8x16_gt(current_bytes, u8x16_splat(0x8F)), mask_f4);
+
+        *has_error = v128_or(*has_error, v128_or(badfollow_ed, badfollow_f4));
+    }
+
+    // map off1_hibits => error condition
+    // hibits     off1    cur
+    // C       => < C2 && true
+    // E       => < E1 && < A0
+    // F       => < F1 && < 90
+    // else      false && false
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn check_overlong(
+        current_bytes: v128,
+        off1_current_bytes: v128,
+        hibits: v128,
+        previous_hibits: v128,
+        has_error: &mut v128,
+    ) {
+        let off1_hibits: v128 = Self::push_last_byte_of_a_to_b(previous_hibits, hibits);
+        let initial_mins: v128 = u8x16_swizzle(initial_mins!(), off1_hibits);
+
+        let initial_under: v128 = i8x16_gt(initial_mins, off1_current_bytes);
+
+        let second_mins: v128 = u8x16_swizzle(second_mins!(), off1_hibits);
+        let second_under: v128 = i8x16_gt(second_mins, current_bytes);
+        *has_error = v128_or(*has_error, v128_and(initial_under, second_under));
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline)]
+    fn count_nibbles(bytes: v128, answer: &mut Self) {
+        answer.rawbytes = bytes;
+        answer.high_nibbles = u8x16_shr(bytes, 4);
+    }
+}
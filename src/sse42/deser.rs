@@ -1,13 +1,13 @@
 #[cfg(target_arch = "x86")]
-use std::arch::x86::{
+use core::arch::x86::{
     __m128i, _mm_cmpeq_epi8, _mm_loadu_si128, _mm_movemask_epi8, _mm_set1_epi8, _mm_storeu_si128,
 };
 #[cfg(target_arch = "x86_64")]
-use std::arch::x86_64::{
+use core::arch::x86_64::{
     __m128i, _mm_cmpeq_epi8, _mm_loadu_si128, _mm_movemask_epi8, _mm_set1_epi8, _mm_storeu_si128,
 };
 
-use std::mem;
+use core::mem;
 
 pub use crate::error::{Error, ErrorType};
 use crate::stringparse::{handle_unicode_codepoint, ESCAPE_MAP};
@@ -31,7 +31,7 @@ impl<'de> Deserializer<'de> {
         mut idx: usize,
     ) -> Result<&'de str> {
         use ErrorType::{InvalidEscape, InvlaidUnicodeCodepoint};
-        let input: &mut [u8] = unsafe { std::mem::transmute(input) };
+        let input: &mut [u8] = unsafe { core::mem::transmute(input) };
         // Add 1 to skip the initial "
         idx += 1;
 
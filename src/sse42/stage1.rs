@@ -1,26 +1,27 @@
 #![allow(dead_code)]
 use crate::utf8check::Utf8Check;
+use alloc::vec::Vec;
 
 use crate::{
     static_cast_i32, static_cast_i64, static_cast_i8, static_cast_u32, ProcessedUtfBytes,
     Stage1Parse, Utf8CheckingState,
 };
 #[cfg(target_arch = "x86")]
-use std::arch::x86::{
+use core::arch::x86::{
     __m128i, _mm_add_epi32, _mm_and_si128, _mm_clmulepi64_si128, _mm_cmpeq_epi8, _mm_cmpgt_epi8,
     _mm_cvtsi128_si64, _mm_loadu_si128, _mm_max_epu8, _mm_movemask_epi8, _mm_or_si128,
     _mm_set1_epi8, _mm_set_epi32, _mm_set_epi64x, _mm_setr_epi8, _mm_setzero_si128,
     _mm_shuffle_epi8, _mm_srli_epi32, _mm_storeu_si128, _mm_testz_si128,
 };
 #[cfg(target_arch = "x86_64")]
-use std::arch::x86_64::{
+use core::arch::x86_64::{
     __m128i, _mm_add_epi32, _mm_and_si128, _mm_clmulepi64_si128, _mm_cmpeq_epi8, _mm_cmpgt_epi8,
     _mm_cvtsi128_si64, _mm_loadu_si128, _mm_max_epu8, _mm_movemask_epi8, _mm_or_si128,
     _mm_set1_epi8, _mm_set_epi32, _mm_set_epi64x, _mm_setr_epi8, _mm_setzero_si128,
     _mm_shuffle_epi8, _mm_srli_epi32, _mm_storeu_si128, _mm_testz_si128,
 };
 
-use std::mem;
+use core::mem;
 
 macro_rules! low_nibble_mask {
     () => {
@@ -45,10 +46,10 @@ pub(crate) struct SimdInput {
     v3: __m128i,
 }
 
-impl SimdInput {
+impl Stage1Parse<__m128i> for SimdInput {
     #[cfg_attr(not(feature = "no-inline"), inline)]
     #[allow(clippy::cast_ptr_alignment)]
-    pub(crate) fn new(ptr: &[u8]) -> Self {
+    fn new(ptr: &[u8]) -> Self {
         unsafe {
             Self {
                 v0: _mm_loadu_si128(ptr.as_ptr() as *const __m128i),
@@ -58,9 +59,7 @@ impl SimdInput {
             }
         }
     }
-}
 
-impl Stage1Parse<__m128i> for SimdInput {
     #[cfg_attr(not(feature = "no-inline"), inline(always))]
     fn new_utf8_checking_state() -> Utf8CheckingState<__m128i> {
         Utf8CheckingState {
@@ -1,9 +1,12 @@
 #![allow(dead_code)]
 use crate::charutils::is_not_structural_or_whitespace;
+use crate::numberparse::is_non_finite_start;
 use crate::value::tape::Node;
-use crate::{Deserializer, Error, ErrorType, Result};
+use crate::{Deserializer, Error, ErrorType, ParseOptions, Result};
+use alloc::vec::Vec;
 use value_trait::StaticNode;
 
+#[cfg(not(feature = "safe"))]
 #[cfg_attr(not(feature = "no-inline"), inline(always))]
 #[allow(clippy::cast_ptr_alignment)]
 pub fn is_valid_true_atom(loc: &[u8]) -> bool {
@@ -53,6 +56,30 @@ macro_rules! get_mut {
     }};
 }
 
+#[cfg(feature = "safe")]
+#[cfg_attr(not(feature = "no-inline"), inline(always))]
+fn is_valid_atom(loc: &[u8], atom: &[u8]) -> bool {
+    // the end of the input counts as whitespace just like the padding does
+    loc.starts_with(atom)
+        && is_not_structural_or_whitespace(loc.get(atom.len()).copied().unwrap_or(0)) == 0
+}
+
+#[cfg(feature = "safe")]
+pub fn is_valid_true_atom(loc: &[u8]) -> bool {
+    is_valid_atom(loc, b"true")
+}
+
+#[cfg(feature = "safe")]
+pub fn is_valid_false_atom(loc: &[u8]) -> bool {
+    is_valid_atom(loc, b"false")
+}
+
+#[cfg(feature = "safe")]
+pub fn is_valid_null_atom(loc: &[u8]) -> bool {
+    is_valid_atom(loc, b"null")
+}
+
+#[cfg(not(feature = "safe"))]
 #[cfg_attr(not(feature = "no-inline"), inline(always))]
 #[allow(clippy::cast_ptr_alignment, unused_unsafe)]
 pub fn is_valid_false_atom(loc: &[u8]) -> bool {
@@ -78,6 +105,7 @@ pub fn is_valid_false_atom(loc: &[u8]) -> bool {
     error == 0
 }
 
+#[cfg(not(feature = "safe"))]
 #[cfg_attr(not(feature = "no-inline"), inline(always))]
 #[allow(clippy::cast_ptr_alignment, unused_unsafe)]
 pub fn is_valid_null_atom(loc: &[u8]) -> bool {
@@ -96,13 +124,30 @@ pub fn is_valid_null_atom(loc: &[u8]) -> bool {
     error == 0
 }
 
+impl Deserializer<'_> {
+    /// Turns a failed `atom` check at `idx` into `ErrorType::Incomplete`
+    /// if the input is cut off in the middle of the atom.
+    #[cold]
+    #[inline(never)]
+    fn atom_error(input: &[u8], idx: usize, atom: &[u8], error: ErrorType) -> ErrorType {
+        let rest = &input[idx..];
+        if rest.len() < atom.len() && atom.starts_with(rest) {
+            ErrorType::Incomplete {
+                needed: Some(atom.len() - rest.len()),
+            }
+        } else {
+            error
+        }
+    }
+}
+
 #[derive(Debug)]
 enum State {
     ObjectKey,
     ScopeEnd,
     MainArraySwitch,
 }
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 enum StackState {
     Start,
     Object,
@@ -116,15 +161,26 @@ impl<'de> Deserializer<'de> {
         input2: &[u8],
         buffer: &mut [u8],
         structural_indexes: &[u32],
+        options: ParseOptions,
     ) -> Result<Vec<Node<'de>>> {
         // While a valid json can have at max len/2 (`[[[]]]`)elements that are relevant
         // a invalid json might exceed this `[[[[[[` and we need to pretect against that.
         let mut res: Vec<Node<'de>> = Vec::with_capacity(structural_indexes.len());
+        #[cfg(not(feature = "safe"))]
         let mut stack = Vec::with_capacity(structural_indexes.len());
+        #[cfg(not(feature = "safe"))]
         unsafe {
             stack.set_len(structural_indexes.len());
             res.set_len(structural_indexes.len());
         }
+        #[cfg(feature = "safe")]
+        let mut stack = alloc::vec![(StackState::Start, 0, 0); structural_indexes.len()];
+
+        // The safe string parser de-escapes strings in place and splits
+        // them off the front of the input as it goes, so it keeps track
+        // of where the rest of the input starts instead of using `buffer`.
+        #[cfg(feature = "safe")]
+        let (mut input, mut offset, _) = (input, 0, buffer);
 
         let mut depth: usize = 0;
         let mut last_start = 1;
@@ -144,34 +200,57 @@ impl<'de> Deserializer<'de> {
         macro_rules! s2try {
             ($e:expr) => {
                 match $e {
-                    ::std::result::Result::Ok(val) => val,
-                    ::std::result::Result::Err(err) => {
+                    ::core::result::Result::Ok(val) => val,
+                    ::core::result::Result::Err(err) => {
                         // We need to ensure that rust doens't
                         // try to free strings that we never
                         // allocated
-                        unsafe {
-                            res.set_len(r_i);
-                        };
-                        return ::std::result::Result::Err(err);
+                        set_len!();
+                        return ::core::result::Result::Err(err);
                     }
                 }
             };
         }
 
+        #[cfg(not(feature = "safe"))]
+        macro_rules! set_len {
+            () => {
+                unsafe {
+                    res.set_len(r_i);
+                }
+            };
+        }
+
+        // `res` is only ever pushed to, so it already has the right length
+        #[cfg(feature = "safe")]
+        macro_rules! set_len {
+            () => {
+                debug_assert_eq!(res.len(), r_i);
+            };
+        }
+
+        #[cfg(not(feature = "safe"))]
         macro_rules! insert_res {
             ($t:expr) => {
                 unsafe {
-                    std::ptr::write(get_mut!(res, r_i), $t);
+                    core::ptr::write(get_mut!(res, r_i), $t);
                     r_i += 1;
                 }
             };
         }
+
+        #[cfg(feature = "safe")]
+        macro_rules! insert_res {
+            ($t:expr) => {
+                res.push($t);
+                r_i += 1;
+            };
+        }
+
         macro_rules! success {
             () => {
-                unsafe {
-                    res.set_len(r_i);
-                    return Ok(res);
-                }
+                set_len!();
+                return Ok(res);
             };
         }
         macro_rules! update_char {
@@ -181,7 +260,7 @@ impl<'de> Deserializer<'de> {
                     i += 1;
                     c = *get!(input2, idx);
                 } else {
-                    fail!(ErrorType::Syntax);
+                    fail!(ErrorType::Incomplete { needed: None });
                 }
             };
         }
@@ -195,6 +274,7 @@ impl<'de> Deserializer<'de> {
 
         insert_res!(Node::Static(StaticNode::Null));
 
+        #[cfg(not(feature = "safe"))]
         macro_rules! insert_str {
             () => {
                 insert_res!(Node::String(s2try!(Self::parse_str_(
@@ -203,6 +283,45 @@ impl<'de> Deserializer<'de> {
             };
         }
 
+        #[cfg(feature = "safe")]
+        macro_rules! insert_str {
+            () => {
+                insert_res!(Node::String(s2try!(Self::parse_str_(
+                    &mut input,
+                    &mut offset,
+                    input2,
+                    idx
+                ))));
+            };
+        }
+
+        #[cfg(not(feature = "safe"))]
+        macro_rules! insert_number {
+            ($negative:expr) => {
+                insert_res!(s2try!(Self::parse_number_node(
+                    input,
+                    idx,
+                    get!(input2, idx..),
+                    $negative,
+                    options
+                )));
+            };
+        }
+
+        #[cfg(feature = "safe")]
+        macro_rules! insert_number {
+            ($negative:expr) => {
+                insert_res!(s2try!(Self::parse_number_node(
+                    &mut input,
+                    &mut offset,
+                    idx,
+                    get!(input2, idx..),
+                    $negative,
+                    options
+                )));
+            };
+        }
+
         // The continue cases are the most frequently called onces it's
         // worth pulling them out into a macro (aka inlining them)
         // Since we don't have a 'gogo' in rust.
@@ -284,18 +403,14 @@ impl<'de> Deserializer<'de> {
                 // We need to ensure that rust doens't
                 // try to free strings that we never
                 // allocated
-                unsafe {
-                    res.set_len(r_i);
-                };
+                set_len!();
                 return Err(Error::new(idx, c as char, ErrorType::InternalError));
             };
             ($t:expr) => {
                 // We need to ensure that rust doens't
                 // try to free strings that we never
                 // allocated
-                unsafe {
-                    res.set_len(r_i);
-                };
+                set_len!();
                 return Err(Error::new(idx, c as char, $t));
             };
         }
@@ -310,6 +425,9 @@ impl<'de> Deserializer<'de> {
                 insert_res!(Node::Object(0, 0));
 
                 depth += 1;
+                if depth > options.max_depth {
+                    fail!(ErrorType::TooDeep);
+                }
                 cnt = 1;
 
                 update_char!();
@@ -334,6 +452,9 @@ impl<'de> Deserializer<'de> {
                 insert_res!(Node::Array(0, 0));
 
                 depth += 1;
+                if depth > options.max_depth {
+                    fail!(ErrorType::TooDeep);
+                }
                 cnt = 1;
 
                 update_char!();
@@ -345,11 +466,14 @@ impl<'de> Deserializer<'de> {
                 }
             }
             b't' => {
-                unsafe {
-                    if !is_valid_true_atom(get!(input2, idx..)) {
-                        fail!(ErrorType::ExpectedNull); // TODO: better error
-                    }
-                };
+                if !is_valid_true_atom(get!(input2, idx..)) {
+                    fail!(Self::atom_error(
+                        input2,
+                        idx,
+                        b"true",
+                        ErrorType::ExpectedNull
+                    )); // TODO: better error
+                }
                 insert_res!(Node::Static(StaticNode::Bool(true)));
                 if i == structural_indexes.len() {
                     success!();
@@ -358,11 +482,14 @@ impl<'de> Deserializer<'de> {
                 }
             }
             b'f' => {
-                unsafe {
-                    if !is_valid_false_atom(get!(input2, idx..)) {
-                        fail!(ErrorType::ExpectedNull); // TODO: better error
-                    }
-                };
+                if !is_valid_false_atom(get!(input2, idx..)) {
+                    fail!(Self::atom_error(
+                        input2,
+                        idx,
+                        b"false",
+                        ErrorType::ExpectedNull
+                    )); // TODO: better error
+                }
                 insert_res!(Node::Static(StaticNode::Bool(false)));
                 if i == structural_indexes.len() {
                     success!();
@@ -371,11 +498,14 @@ impl<'de> Deserializer<'de> {
                 }
             }
             b'n' => {
-                unsafe {
-                    if !is_valid_null_atom(get!(input2, idx..)) {
-                        fail!(ErrorType::ExpectedNull); // TODO: better error
-                    }
-                };
+                if !is_valid_null_atom(get!(input2, idx..)) {
+                    fail!(Self::atom_error(
+                        input2,
+                        idx,
+                        b"null",
+                        ErrorType::ExpectedNull
+                    )); // TODO: better error
+                }
                 insert_res!(Node::Static(StaticNode::Null));
                 if i == structural_indexes.len() {
                     success!();
@@ -391,11 +521,12 @@ impl<'de> Deserializer<'de> {
                     fail!(ErrorType::TrailingCharacters);
                 }
             }
-            b'-' => {
-                insert_res!(Node::Static(s2try!(Self::parse_number_int(
+            b'-' | b'I' | b'N'
+                if options.allow_non_finite && is_non_finite_start(get!(input2, idx..)) =>
+            {
+                insert_res!(Node::Static(s2try!(Self::parse_non_finite(
                     idx,
-                    get!(input2, idx..),
-                    true
+                    get!(input2, idx..)
                 ))));
 
                 if i == structural_indexes.len() {
@@ -404,12 +535,26 @@ impl<'de> Deserializer<'de> {
                     fail!(ErrorType::TrailingCharacters);
                 }
             }
+            b'-' => {
+                insert_number!(true);
+
+                if i == structural_indexes.len() {
+                    success!();
+                } else {
+                    fail!(ErrorType::TrailingCharacters);
+                }
+            }
             b'0'..=b'9' => {
-                insert_res!(Node::Static(s2try!(Self::parse_number_int(
-                    idx,
-                    get!(input2, idx..),
-                    false
-                ))));
+                insert_number!(false);
+
+                if i == structural_indexes.len() {
+                    success!();
+                } else {
+                    fail!(ErrorType::TrailingCharacters);
+                }
+            }
+            b'+' | b'.' if options.relaxes_numbers() => {
+                insert_number!(false);
 
                 if i == structural_indexes.len() {
                     success!();
@@ -440,59 +585,80 @@ impl<'de> Deserializer<'de> {
                         b't' => {
                             insert_res!(Node::Static(StaticNode::Bool(true)));
                             if !is_valid_true_atom(get!(input2, idx..)) {
-                                fail!(ErrorType::ExpectedBoolean); // TODO: better error
+                                fail!(Self::atom_error(
+                                    input2,
+                                    idx,
+                                    b"true",
+                                    ErrorType::ExpectedBoolean
+                                )); // TODO: better error
                             }
                             object_continue!();
                         }
                         b'f' => {
                             insert_res!(Node::Static(StaticNode::Bool(false)));
                             if !is_valid_false_atom(get!(input2, idx..)) {
-                                fail!(ErrorType::ExpectedBoolean); // TODO: better error
+                                fail!(Self::atom_error(
+                                    input2,
+                                    idx,
+                                    b"false",
+                                    ErrorType::ExpectedBoolean
+                                )); // TODO: better error
                             }
                             object_continue!();
                         }
                         b'n' => {
                             insert_res!(Node::Static(StaticNode::Null));
                             if !is_valid_null_atom(get!(input2, idx..)) {
-                                fail!(ErrorType::ExpectedNull); // TODO: better error
+                                fail!(Self::atom_error(
+                                    input2,
+                                    idx,
+                                    b"null",
+                                    ErrorType::ExpectedNull
+                                )); // TODO: better error
                             }
                             object_continue!();
                         }
-                        b'-' => {
-                            insert_res!(Node::Static(s2try!(Self::parse_number_int(
+                        b'-' | b'I' | b'N'
+                            if options.allow_non_finite
+                                && is_non_finite_start(get!(input2, idx..)) =>
+                        {
+                            insert_res!(Node::Static(s2try!(Self::parse_non_finite(
                                 idx,
-                                get!(input2, idx..),
-                                true
+                                get!(input2, idx..)
                             ))));
                             object_continue!();
                         }
+                        b'-' => {
+                            insert_number!(true);
+                            object_continue!();
+                        }
                         b'0'..=b'9' => {
-                            insert_res!(Node::Static(s2try!(Self::parse_number_int(
-                                idx,
-                                get!(input2, idx..),
-                                false
-                            ))));
+                            insert_number!(false);
+                            object_continue!();
+                        }
+                        b'+' | b'.' if options.relaxes_numbers() => {
+                            insert_number!(false);
                             object_continue!();
                         }
                         b'{' => {
-                            unsafe {
-                                *stack.get_unchecked_mut(depth) =
-                                    (StackState::Object, last_start, cnt);
-                            }
+                            *get_mut!(stack, depth) = (StackState::Object, last_start, cnt);
                             last_start = r_i;
                             insert_res!(Node::Object(0, 0));
                             depth += 1;
+                            if depth > options.max_depth {
+                                fail!(ErrorType::TooDeep);
+                            }
                             cnt = 1;
                             object_begin!();
                         }
                         b'[' => {
-                            unsafe {
-                                *stack.get_unchecked_mut(depth) =
-                                    (StackState::Object, last_start, cnt);
-                            }
+                            *get_mut!(stack, depth) = (StackState::Object, last_start, cnt);
                             last_start = r_i;
                             insert_res!(Node::Array(0, 0));
                             depth += 1;
+                            if depth > options.max_depth {
+                                fail!(ErrorType::TooDeep);
+                            }
                             cnt = 1;
                             array_begin!();
                         }
@@ -507,18 +673,16 @@ impl<'de> Deserializer<'de> {
                         fail!(ErrorType::Syntax);
                     }
                     depth -= 1;
-                    unsafe {
-                        match res.get_unchecked_mut(last_start) {
-                            Node::Array(ref mut len, ref mut end)
-                            | Node::Object(ref mut len, ref mut end) => {
-                                *len = cnt;
-                                *end = r_i;
-                            }
-                            _ => unreachable!(),
-                        };
+                    match get_mut!(res, last_start) {
+                        Node::Array(ref mut len, ref mut end)
+                        | Node::Object(ref mut len, ref mut end) => {
+                            *len = cnt;
+                            *end = r_i;
+                        }
+                        _ => unreachable!(),
                     }
 
-                    let (a_state, a_last_start, a_cnt) = unsafe { stack.get_unchecked(depth) };
+                    let (a_state, a_last_start, a_cnt) = get!(stack, depth);
                     //                    let (a_state, a_last_start, a_cnt) = unsafe {  };
                     //s2try!(stack.pop().ok_or_else(|| Error::generic(ErrorType::Syntax)));
 
@@ -550,59 +714,80 @@ impl<'de> Deserializer<'de> {
                         b't' => {
                             insert_res!(Node::Static(StaticNode::Bool(true)));
                             if !is_valid_true_atom(get!(input2, idx..)) {
-                                fail!(ErrorType::ExpectedBoolean); // TODO: better error
+                                fail!(Self::atom_error(
+                                    input2,
+                                    idx,
+                                    b"true",
+                                    ErrorType::ExpectedBoolean
+                                )); // TODO: better error
                             }
                             array_continue!();
                         }
                         b'f' => {
                             insert_res!(Node::Static(StaticNode::Bool(false)));
                             if !is_valid_false_atom(get!(input2, idx..)) {
-                                fail!(ErrorType::ExpectedBoolean); // TODO: better error
+                                fail!(Self::atom_error(
+                                    input2,
+                                    idx,
+                                    b"false",
+                                    ErrorType::ExpectedBoolean
+                                )); // TODO: better error
                             }
                             array_continue!();
                         }
                         b'n' => {
                             insert_res!(Node::Static(StaticNode::Null));
                             if !is_valid_null_atom(get!(input2, idx..)) {
-                                fail!(ErrorType::ExpectedNull); // TODO: better error
+                                fail!(Self::atom_error(
+                                    input2,
+                                    idx,
+                                    b"null",
+                                    ErrorType::ExpectedNull
+                                )); // TODO: better error
                             }
                             array_continue!();
                         }
-                        b'-' => {
-                            insert_res!(Node::Static(s2try!(Self::parse_number_int(
+                        b'-' | b'I' | b'N'
+                            if options.allow_non_finite
+                                && is_non_finite_start(get!(input2, idx..)) =>
+                        {
+                            insert_res!(Node::Static(s2try!(Self::parse_non_finite(
                                 idx,
-                                get!(input2, idx..),
-                                true
+                                get!(input2, idx..)
                             ))));
                             array_continue!();
                         }
+                        b'-' => {
+                            insert_number!(true);
+                            array_continue!();
+                        }
                         b'0'..=b'9' => {
-                            insert_res!(Node::Static(s2try!(Self::parse_number_int(
-                                idx,
-                                get!(input2, idx..),
-                                false
-                            ))));
+                            insert_number!(false);
+                            array_continue!();
+                        }
+                        b'+' | b'.' if options.relaxes_numbers() => {
+                            insert_number!(false);
                             array_continue!();
                         }
                         b'{' => {
-                            unsafe {
-                                *stack.get_unchecked_mut(depth) =
-                                    (StackState::Array, last_start, cnt);
-                            }
+                            *get_mut!(stack, depth) = (StackState::Array, last_start, cnt);
                             last_start = r_i;
                             insert_res!(Node::Object(0, 0));
                             depth += 1;
+                            if depth > options.max_depth {
+                                fail!(ErrorType::TooDeep);
+                            }
                             cnt = 1;
                             object_begin!();
                         }
                         b'[' => {
-                            unsafe {
-                                *stack.get_unchecked_mut(depth) =
-                                    (StackState::Array, last_start, cnt);
-                            }
+                            *get_mut!(stack, depth) = (StackState::Array, last_start, cnt);
                             last_start = r_i;
                             insert_res!(Node::Array(0, 0));
                             depth += 1;
+                            if depth > options.max_depth {
+                                fail!(ErrorType::TooDeep);
+                            }
                             cnt = 1;
                             array_begin!();
                         }
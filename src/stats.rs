@@ -0,0 +1,158 @@
+use crate::Node;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+/// What a parse found and how long its stages took, returned by
+/// `to_owned_value_with_stats` and `to_borrowed_value_with_stats` next
+/// to the value.
+///
+/// ```rust
+/// let mut d = br#"{"name": "a\"b", "tags": ["x", "y"], "n": 1}"#.to_vec();
+/// let (v, stats) = simd_json::to_owned_value_with_stats(&mut d).unwrap();
+/// assert_eq!(stats.input_len, 44);
+/// assert_eq!(stats.string_bytes, 14);
+/// // the object, the array and the six strings and keys
+/// assert_eq!(stats.allocations, 8);
+/// println!("stage 1 took {:?}, stage 2 {:?}", stats.stage1, stats.stage2);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ParseStats {
+    /// The length of the input in bytes
+    pub input_len: usize,
+    /// The number of structural characters stage 1 found
+    pub structurals: usize,
+    /// The number of nodes on the tape stage 2 built
+    pub tape_len: usize,
+    /// The bytes of all strings and keys, after de-escaping them
+    pub string_bytes: usize,
+    /// The heap allocations building the value took: one for every
+    /// array and object that isn't empty, and for an `OwnedValue` one
+    /// for every string or key that isn't empty as well
+    pub allocations: usize,
+    /// The time stage 1 took to validate the input and find the
+    /// structural characters
+    #[cfg(feature = "std")]
+    pub stage1: Duration,
+    /// The time stage 2 took to build the tape
+    #[cfg(feature = "std")]
+    pub stage2: Duration,
+    /// The time building the value from the tape took
+    #[cfg(feature = "std")]
+    pub build: Duration,
+}
+
+impl ParseStats {
+    pub(crate) fn stage1_done(&mut self, watch: &mut Stopwatch, structurals: usize) {
+        self.structurals = structurals;
+        #[cfg(feature = "std")]
+        {
+            self.stage1 = watch.lap();
+        }
+        #[cfg(not(feature = "std"))]
+        let _ = watch;
+    }
+
+    pub(crate) fn stage2_done(&mut self, watch: &mut Stopwatch, tape: &[Node]) {
+        #[cfg(feature = "std")]
+        {
+            self.stage2 = watch.lap();
+        }
+        #[cfg(not(feature = "std"))]
+        let _ = watch;
+        self.tape_len = tape.len();
+        self.string_bytes = tape
+            .iter()
+            .map(|node| match node {
+                Node::String(s) => s.len(),
+                _ => 0,
+            })
+            .sum();
+    }
+
+    /// Counts the allocations of a value built from `tape`, strings
+    /// only count if `owned_strings` is set
+    pub(crate) fn count_allocations(&mut self, tape: &[Node], owned_strings: bool) {
+        self.allocations = tape
+            .iter()
+            .filter(|node| match node {
+                Node::Array(len, _) | Node::Object(len, _) => *len > 0,
+                Node::String(s) => owned_strings && !s.is_empty(),
+                Node::Static(_) => false,
+            })
+            .count();
+    }
+
+    pub(crate) fn build_done(&mut self, watch: &mut Stopwatch) {
+        #[cfg(feature = "std")]
+        {
+            self.build = watch.lap();
+        }
+        #[cfg(not(feature = "std"))]
+        let _ = watch;
+    }
+}
+
+/// Times the stages of a parse when it is asked for stats, there is no
+/// clock without `std`
+pub(crate) struct Stopwatch {
+    #[cfg(feature = "std")]
+    last: Option<Instant>,
+}
+
+impl Stopwatch {
+    #[cfg(feature = "std")]
+    pub(crate) fn start(running: bool) -> Self {
+        Self {
+            last: if running { Some(Instant::now()) } else { None },
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub(crate) fn start(_running: bool) -> Self {
+        Self {}
+    }
+
+    /// The time since the start or the last lap
+    #[cfg(feature = "std")]
+    fn lap(&mut self) -> Duration {
+        match self.last {
+            Some(last) => {
+                let now = Instant::now();
+                self.last = Some(now);
+                now - last
+            }
+            None => Duration::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{to_borrowed_value_with_stats, to_owned_value_with_stats};
+
+    #[test]
+    fn counts() {
+        let input = br#"{"a": ["x\ty", "", {}], "bb": [], "c": {"d": null}}"#;
+        let mut d = input.to_vec();
+        let (owned, stats) = to_owned_value_with_stats(&mut d).expect("failed to parse");
+        assert_eq!(
+            owned,
+            json!({"a": ["x\ty", "", {}], "bb": [], "c": {"d": null}})
+        );
+        assert_eq!(stats.input_len, input.len());
+        assert_eq!(stats.tape_len, 13);
+        // a, x\ty, bb, c and d
+        assert_eq!(stats.string_bytes, 8);
+        // the outer object, a, c and the four keys and x\ty
+        assert_eq!(stats.allocations, 8);
+        let mut d = input.to_vec();
+        let (borrowed, borrowed_stats) =
+            to_borrowed_value_with_stats(&mut d).expect("failed to parse");
+        assert_eq!(borrowed, owned);
+        assert_eq!(borrowed_stats.structurals, stats.structurals);
+        assert_eq!(borrowed_stats.string_bytes, stats.string_bytes);
+        assert_eq!(borrowed_stats.allocations, 3);
+        let mut d = b"[1,".to_vec();
+        assert!(to_owned_value_with_stats(&mut d).is_err());
+    }
+}
@@ -1,4 +1,6 @@
+#[cfg(not(feature = "safe"))]
 use crate::charutils::{codepoint_to_utf8, hex_to_u32_nocheck};
+#[cfg(not(feature = "safe"))]
 use crate::error::ErrorType;
 
 /// begin copypasta
@@ -25,6 +27,7 @@ pub(crate) const ESCAPE_MAP: [u8; 256] = [
 /// dest will advance a variable amount (return via pointer)
 /// return true if the unicode codepoint was valid
 /// We work in little-endian then swap at write time
+#[cfg(not(feature = "safe"))]
 #[cfg_attr(not(feature = "no-inline"), inline(always))]
 pub(crate) fn handle_unicode_codepoint(
     mut src_ptr: &[u8],
@@ -67,6 +70,9 @@ pub(crate) fn handle_unicode_codepoint(
         };
         code_point = ((c1 << 10) | c2) + 0x10000;
         src_offset += 6;
+    } else if (0xdc00..0xe000).contains(&code_point) {
+        // a low surrogate without a high one in front of it
+        return Ok((0, src_offset));
     }
     let offset: usize = codepoint_to_utf8(code_point, dst_ptr);
     Ok((offset, src_offset))
@@ -0,0 +1,164 @@
+use crate::{Deserializer, Error, Result};
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+use core::slice;
+
+/// The positions of the structural characters of a JSON document, found
+/// by `find_structurals`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructuralIndex {
+    indexes: Vec<u32>,
+}
+
+impl StructuralIndex {
+    /// Iterates over the positions in the order they are in the input
+    #[must_use]
+    pub fn iter(&self) -> StructuralIter<'_> {
+        StructuralIter {
+            inner: self.indexes.iter(),
+        }
+    }
+
+    /// The positions as they are stored
+    #[must_use]
+    pub fn as_slice(&self) -> &[u32] {
+        &self.indexes
+    }
+
+    /// The number of structural characters
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.indexes.len()
+    }
+
+    /// Whether there are no structural characters, `find_structurals`
+    /// never returns an empty index
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.indexes.is_empty()
+    }
+}
+
+impl<'a> IntoIterator for &'a StructuralIndex {
+    type Item = usize;
+    type IntoIter = StructuralIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the positions of a `StructuralIndex`, created by
+/// `StructuralIndex::iter`
+#[derive(Debug, Clone)]
+pub struct StructuralIter<'a> {
+    inner: slice::Iter<'a, u32>,
+}
+
+impl Iterator for StructuralIter<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        self.inner.next().map(|&i| i as usize)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for StructuralIter<'_> {
+    fn next_back(&mut self) -> Option<usize> {
+        self.inner.next_back().map(|&i| i as usize)
+    }
+}
+
+impl ExactSizeIterator for StructuralIter<'_> {}
+
+impl FusedIterator for StructuralIter<'_> {}
+
+/// Runs stage 1 of the parser over `input` and returns the positions of
+/// its structural characters, for scanners that find their way around a
+/// document without parsing it, like splitting a large array or finding
+/// where records start.
+///
+/// The structural characters are `{`, `}`, `[`, `]`, `:` and `,` outside
+/// of strings, the opening quote of every string and the first character
+/// of every number, `true`, `false` and `null`. Stage 1 only tells them
+/// apart from the rest of the input, so the document can still turn out
+/// not to be valid JSON when it is parsed.
+///
+/// ```rust
+/// let d = br#"[{"id": 1}, [2, 3], "a,b"]"#;
+/// let index = simd_json::find_structurals(d).unwrap();
+/// let found: Vec<char> = index.iter().map(|i| char::from(d[i])).collect();
+/// let expected: Vec<char> = r#"[{":1},[2,3],"]"#.chars().collect();
+/// assert_eq!(found, expected);
+///
+/// // the commas between the elements of the top level array
+/// let mut depth = 0;
+/// let commas: Vec<usize> = index
+///     .iter()
+///     .filter(|&i| {
+///         match d[i] {
+///             b'[' | b'{' => depth += 1,
+///             b']' | b'}' => depth -= 1,
+///             _ => {}
+///         }
+///         d[i] == b',' && depth == 1
+///     })
+///     .collect();
+/// assert_eq!(commas, [10, 18]);
+/// ```
+///
+/// # Errors
+///
+/// Will return `Err` if `input` has no structural characters, ends inside
+/// a string, has an unescaped control character in a string or isn't
+/// UTF-8.
+pub fn find_structurals(input: &[u8]) -> Result<StructuralIndex> {
+    let mut indexes = Vec::new();
+    Deserializer::find_structural_bits(input, &mut indexes).map_err(Error::generic)?;
+    // drop the root element stage 1 puts in front
+    indexes.remove(0);
+    Ok(StructuralIndex { indexes })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ErrorType;
+
+    #[test]
+    fn positions() {
+        let d = br#" {"a\"": [1, -2.5e3, true], "b": null} "#;
+        let index = find_structurals(d).expect("stage 1 failed");
+        assert_eq!(
+            index.as_slice(),
+            [1, 2, 7, 9, 10, 11, 13, 19, 21, 25, 26, 28, 31, 33, 37]
+        );
+        assert_eq!(index.len(), 15);
+        assert!(!index.is_empty());
+        let positions: Vec<usize> = index.iter().collect();
+        assert_eq!(positions.len(), index.iter().len());
+        assert_eq!(index.iter().next_back(), Some(37));
+        assert_eq!((&index).into_iter().next(), Some(1));
+        // more than one block of 64 bytes
+        let long = alloc::format!("[{}\"x\"]", "0, ".repeat(100));
+        let index = find_structurals(long.as_bytes()).expect("stage 1 failed");
+        assert_eq!(index.len(), 203);
+        assert_eq!(index.iter().next_back(), Some(long.len() - 1));
+    }
+
+    #[test]
+    fn errors() {
+        let err = |d: &[u8]| find_structurals(d).expect_err("stage 1 passed");
+        assert_eq!(err(b"  ").error(), &ErrorType::EOF);
+        assert_eq!(
+            err(b"[\"a").error(),
+            &ErrorType::Incomplete { needed: None }
+        );
+        assert_eq!(err(b"[\"a\nb\"]").error(), &ErrorType::Syntax);
+        assert_eq!(err(b"[\"\xff\"]").error(), &ErrorType::InvalidUTF8);
+    }
+}
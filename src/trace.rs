@@ -0,0 +1,142 @@
+//! Parse instrumentation, it only does something with the `tracing`
+//! feature and compiles to nothing without it.
+
+use crate::Error;
+#[cfg(feature = "tracing")]
+use std::time::{Duration, Instant};
+#[cfg(feature = "tracing")]
+use tracing_crate::{debug, debug_span, span::EnteredSpan};
+
+/// The span of one parse, with the time each stage took
+#[cfg(feature = "tracing")]
+pub(crate) struct ParseTrace {
+    _span: EnteredSpan,
+    start: Instant,
+    stage1: Option<Duration>,
+    structurals: usize,
+}
+
+#[cfg(feature = "tracing")]
+impl ParseTrace {
+    /// Enters the span of a parse of `len` bytes
+    pub(crate) fn start(len: usize) -> Self {
+        Self {
+            _span: debug_span!(target: "simd_json", "parse", len).entered(),
+            start: Instant::now(),
+            stage1: None,
+            structurals: 0,
+        }
+    }
+
+    /// Stage 1 found `structurals` structural characters
+    pub(crate) fn stage1_done(&mut self, structurals: usize) {
+        self.stage1 = Some(self.start.elapsed());
+        self.structurals = structurals;
+    }
+
+    /// The parse failed with `error`
+    pub(crate) fn failed(self, error: &Error) {
+        debug!(
+            target: "simd_json",
+            index = error.index(),
+            error = ?error.error(),
+            stage1 = ?self.stage1,
+            total = ?self.start.elapsed(),
+            "invalid JSON"
+        );
+    }
+
+    /// The parse succeeded with a tape of `nodes` nodes
+    pub(crate) fn done(self, nodes: usize) {
+        let total = self.start.elapsed();
+        let stage1 = self.stage1.unwrap_or_default();
+        debug!(
+            target: "simd_json",
+            structurals = self.structurals,
+            nodes,
+            stage1 = ?stage1,
+            stage2 = ?total.saturating_sub(stage1),
+            total = ?total,
+            "parsed"
+        );
+    }
+}
+
+/// Nothing to record without the `tracing` feature
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct ParseTrace;
+
+#[cfg(not(feature = "tracing"))]
+#[allow(clippy::unused_self)]
+impl ParseTrace {
+    #[inline(always)]
+    pub(crate) fn start(_len: usize) -> Self {
+        Self
+    }
+
+    #[inline(always)]
+    pub(crate) fn stage1_done(&mut self, _structurals: usize) {}
+
+    #[inline(always)]
+    pub(crate) fn failed(self, _error: &Error) {}
+
+    #[inline(always)]
+    pub(crate) fn done(self, _nodes: usize) {}
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod test {
+    use std::fmt::{self, Write};
+    use std::sync::{Arc, Mutex};
+    use tracing_crate::field::{Field, Visit};
+    use tracing_crate::span::{Attributes, Id, Record};
+    use tracing_crate::{Event, Metadata, Subscriber};
+
+    /// Records spans and events as text
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    struct Fields(String);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            let _ = write!(self.0, " {}={:?}", field.name(), value);
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+            metadata.target() == "simd_json"
+        }
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut fields = Fields(format!("span {}", span.metadata().name()));
+            span.record(&mut fields);
+            self.0.lock().expect("poisoned").push(fields.0);
+            Id::from_u64(1)
+        }
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = Fields(String::from("event"));
+            event.record(&mut fields);
+            self.0.lock().expect("poisoned").push(fields.0);
+        }
+        fn enter(&self, _span: &Id) {}
+        fn exit(&self, _span: &Id) {}
+    }
+
+    #[test]
+    fn events() {
+        let recorder = Recorder::default();
+        tracing_crate::subscriber::with_default(recorder.clone(), || {
+            assert!(crate::to_owned_value(&mut br#"{"a": [1, 2]}"#.to_vec()).is_ok());
+            assert!(crate::to_owned_value(&mut b"[1,]".to_vec()).is_err());
+        });
+        let events = recorder.0.lock().expect("poisoned");
+        assert_eq!(events.len(), 4, "{:?}", *events);
+        assert_eq!(events[0], "span parse len=13");
+        assert!(events[1].starts_with("event message=parsed structurals=10 nodes=6 stage1="));
+        assert_eq!(events[2], "span parse len=4");
+        assert!(events[3].starts_with("event message=invalid JSON index=3 error="));
+    }
+}
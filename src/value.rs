@@ -1,67 +1,222 @@
-/// This module holds the two dom implementations we use. We distingush between
-/// owned and borrowed. The difference being is that the borrowed value will
-/// use `&str` as its string type, refferencing the input, while owned will
-/// allocate a new String for each value.
-///
-/// Note that since json strings allow for for escape sequences the borrowed
-/// value does not impement zero copy parsing, it does however not allocate
-/// new memory for strings.
-///
-/// This differs notably from serds zero copy implementation as, unlike serde,
-/// we do not require prior knowledge sbout string comtent to to take advantage
-/// of it.
-///
-/// ## Usage
-/// The value trait is meant to simplify interacting with DOM values, for both
-/// creation as well as mutation and inspection.
-///
-/// Objects can be treated as hashmap's for the most part
-/// ```rust
-/// use simd_json::{OwnedValue as Value, prelude::*};
-/// let mut v = Value::object();
-/// v.insert("key", 42);
-/// assert_eq!(v.get("key").unwrap(), &42);
-/// assert_eq!(v["key"], &42);
-/// assert_eq!(v.remove("key").unwrap().unwrap(), 42);
-/// assert_eq!(v.get("key"), None);
-/// ```
-///
-/// Arrays can be treated as vectors for the most part
-///
-/// ```rust
-/// use simd_json::{OwnedValue as Value, prelude::*};
-/// let mut v = Value::array();
-/// v.push("zero");
-/// v.push(1);
-/// assert_eq!(v[0], &"zero");
-/// assert_eq!(v.get_idx(1).unwrap(), &1);
-/// assert_eq!(v.pop().unwrap().unwrap(), 1);
-/// assert_eq!(v.pop().unwrap().unwrap(), "zero");
-/// assert_eq!(v.pop().unwrap(), None);
-/// ```
-///
-/// Nested changes are also possible:
-/// ```rust
-/// use simd_json::{OwnedValue as Value, prelude::*};
-/// let mut o = Value::object();
-/// o.insert("key", Value::array());
-/// o["key"].push(Value::object());
-/// o["key"][0].insert("other", "value");
-/// assert_eq!(o.encode(), r#"{"key":[{"other":"value"}]}"#);
-/// ```
+//! This module holds the two dom implementations we use. We distingush between
+//! owned and borrowed. The difference being is that the borrowed value will
+//! use `&str` as its string type, refferencing the input, while owned will
+//! allocate a new String for each value.
+//!
+//! Note that since json strings allow for for escape sequences the borrowed
+//! value does not impement zero copy parsing, it does however not allocate
+//! new memory for strings.
+//!
+//! This differs notably from serds zero copy implementation as, unlike serde,
+//! we do not require prior knowledge sbout string comtent to to take advantage
+//! of it.
+//!
+//! ## Usage
+//! The value trait is meant to simplify interacting with DOM values, for both
+//! creation as well as mutation and inspection.
+//!
+//! Objects can be treated as hashmap's for the most part
+//! ```rust
+//! use simd_json::{OwnedValue as Value, prelude::*};
+//! let mut v = Value::object();
+//! v.insert("key", 42);
+//! assert_eq!(v.get("key").unwrap(), &42);
+//! assert_eq!(v["key"], &42);
+//! assert_eq!(v.remove("key").unwrap().unwrap(), 42);
+//! assert_eq!(v.get("key"), None);
+//! ```
+//!
+//! Arrays can be treated as vectors for the most part
+//!
+//! ```rust
+//! use simd_json::{OwnedValue as Value, prelude::*};
+//! let mut v = Value::array();
+//! v.push("zero");
+//! v.push(1);
+//! assert_eq!(v[0], &"zero");
+//! assert_eq!(v.get_idx(1).unwrap(), &1);
+//! assert_eq!(v.pop().unwrap().unwrap(), 1);
+//! assert_eq!(v.pop().unwrap().unwrap(), "zero");
+//! assert_eq!(v.pop().unwrap(), None);
+//! ```
+//!
+//! Nested changes are also possible:
+//! ```rust
+//! use simd_json::{OwnedValue as Value, prelude::*};
+//! let mut o = Value::object();
+//! o.insert("key", Value::array());
+//! o["key"].push(Value::object());
+//! o["key"][0].insert("other", "value");
+//! assert_eq!(o.encode(), r#"{"key":[{"other":"value"}]}"#);
+//! ```
 
+/// Comparing values with a tolerance for numbers
+mod approx;
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+/// Values with their arrays and objects allocated from a bump arena,
+/// dropping them is free
+#[cfg(feature = "bumpalo")]
+pub mod arena;
+/// Decoding base64 and hex strings
+#[cfg(feature = "base64")]
+mod binary;
 /// Borrowed values, using Cow's for strings using in situ parsing strategies wherever possible
 pub mod borrowed;
+/// Building values from tapes and taking them apart without recursion
+mod build;
+/// Chained construction of objects and arrays
+mod builder;
+/// The deeply nested documents the tests of the doms share
+#[cfg(test)]
+pub(crate) mod deep;
+/// Structural differences between values, for tests
+#[cfg(feature = "std")]
+mod diff;
+#[cfg(feature = "digest")]
+mod digest;
+/// Documents that own the buffer their value points into
+mod doc;
+/// Walking values to write them out without recursion
+pub(crate) mod emit;
+/// Writing values as JSON text
+#[cfg(feature = "std")]
+pub(crate) mod encoder;
+/// Flattening values into objects with a key per leaf and back
+mod flatten;
+/// Getting the values of several keys of an object at once
+mod get_many;
+/// Comparing and hashing values exactly, to use them as keys of maps
+mod hash;
+/// Inferring schemas from sample values
+#[cfg(feature = "schema")]
+mod infer;
+/// Validating values and tapes against JSON Type Definitions
+#[cfg(feature = "schema")]
+pub mod jtd;
+/// Coercing strings and numbers into the type asked for
+mod lenient;
+/// Pluggable maps for the objects of values
+pub mod map;
+/// Deep merging of values
+mod merge;
+/// Values whose objects keep every value of repeated keys
+pub mod multi;
+/// Giving numbers one representation per value
+mod normalize;
 /// Owned, lifetimeless version of the value for times when lifetimes are to be avoided
 pub mod owned;
+/// Inserting and removing values at a path
+mod path;
+/// Matching values against patterns of their shape
+mod pattern;
+/// Extracting one field from every element of an array, for both the DOMs
+/// and the tape, and parsing only the parts of a document paths select
+mod project;
+/// Removing `null`s and empty values
+mod prune;
+/// Masking sensitive fields
+mod redact;
+/// Validating values and tapes against JSON Schemas
+#[cfg(feature = "schema")]
+pub mod schema;
+/// Immutable values behind `Arc`s that are cloned in O(1)
+pub mod shared;
+/// Estimating the heap memory of values
+mod size;
+/// Sorting the keys of objects
+mod sort;
+/// Values that remember the byte range of the input they were parsed from, for
+/// linters, config validators and error messages pointing into the user's file
+pub mod spanned;
+/// Statistics about the shape of values
+mod stats;
 /// Tape implementation
 pub mod tape;
-pub use self::borrowed::{to_value as to_borrowed_value, Value as BorrowedValue};
-pub use self::owned::{to_value as to_owned_value, Value as OwnedValue};
+/// Conversions of values into Rust types
+mod try_from;
+/// Getters for typed fields with errors that say what is wrong
+mod try_get;
+/// Depth first traversal of values with their JSON pointers
+mod walk;
+pub use self::approx::Tolerance;
+#[cfg(feature = "bumpalo")]
+pub use self::arena::{
+    to_value as to_arena_value, to_value_with_options as to_arena_value_with_options, ArenaValue,
+};
+#[cfg(feature = "bytes")]
+pub(crate) use self::binary::to_base64;
+pub use self::borrowed::{
+    to_value as to_borrowed_value, to_value_with_map as to_borrowed_value_with_map,
+    to_value_with_options as to_borrowed_value_with_options,
+    to_value_with_stats as to_borrowed_value_with_stats, GenericValue as GenericBorrowedValue,
+    Value as BorrowedValue,
+};
+pub use self::builder::{ArrayBuilder, ObjectBuilder};
+#[cfg(feature = "std")]
+pub use self::diff::{assert_json_eq_message, diff, Difference};
+#[cfg(feature = "bytes-buffer")]
+pub use self::doc::BytesDoc;
+pub use self::doc::OwnedDoc;
+pub use self::flatten::{FlattenStyle, UnflattenError};
+pub use self::hash::ExactValue;
+#[cfg(feature = "schema")]
+pub use self::infer::{infer_schema, InferredSchema};
+#[cfg(feature = "schema")]
+pub use self::jtd::{JtdError, JtdSchema};
+#[cfg(feature = "ahash")]
+pub use self::map::AHashMaps;
+#[cfg(feature = "fxhash")]
+pub use self::map::FxHashMaps;
+#[cfg(feature = "std")]
+pub use self::map::SipHashMaps;
+pub use self::map::{
+    DefaultMaps, HashedMap, HashedMaps, MapBackend, ObjectMap, SortedMap, SortedMaps,
+};
+pub use self::merge::{ArrayMerge, MergeStrategy, NullMerge};
+pub use self::multi::{
+    to_value as to_multi_value, to_value_with_options as to_multi_value_with_options, MultiValue,
+};
+#[cfg(feature = "std")]
+pub use self::owned::to_value_from_file as to_owned_value_from_file;
+pub use self::owned::{
+    to_value as to_owned_value, to_value_from_slice as to_owned_value_from_slice,
+    to_value_from_str as to_owned_value_from_str, to_value_recovering as to_owned_value_recovering,
+    to_value_recovering_with_options as to_owned_value_recovering_with_options,
+    to_value_with_map as to_owned_value_with_map,
+    to_value_with_options as to_owned_value_with_options,
+    to_value_with_stats as to_owned_value_with_stats, GenericValue as GenericOwnedValue,
+    Value as OwnedValue,
+};
+pub(crate) use self::path::steps;
+pub use self::path::{MissingParents, PathError};
+pub use self::pattern::Captures;
+pub use self::project::{
+    project, project_column, project_tape, project_tape_column, to_borrowed_value_projected,
+    to_owned_value_projected,
+};
+pub use self::prune::PruneOptions;
+pub use self::redact::Replacement;
+#[cfg(feature = "schema")]
+pub use self::schema::{Schema, SchemaError, ValidationError};
+pub use self::shared::{
+    to_value as to_shared_value, to_value_with_pool as to_shared_value_with_pool, SharedValue,
+    StringPool,
+};
+pub use self::spanned::{
+    to_value as to_spanned_value, to_value_with_options as to_spanned_value_with_options, Span,
+    SpannedEntry, SpannedValue,
+};
+pub use self::stats::ValueStats;
+pub use self::try_from::TryFromValueError;
+pub use self::try_get::{TryGet, TryGetError};
+pub(crate) use self::walk::Dom;
+pub use self::walk::Walk;
 use crate::{Deserializer, Result};
+use alloc::vec::Vec;
+use core::hash::Hash;
+use core::marker::PhantomData;
 use halfbrown::HashMap;
-use std::hash::Hash;
-use std::marker::PhantomData;
 use tape::Node;
 pub use value_trait::*;
 
@@ -79,28 +234,96 @@ where
     Key: Hash + Eq + From<&'de str>,
 {
     match Deserializer::from_slice(s) {
-        Ok(de) => Ok(ValueDeserializer::from_deserializer(de).parse()),
+        Ok(de) => Ok(ValueDeserializer::from_deserializer(de, KeyFrom).parse()),
         Err(e) => Err(e),
     }
 }
 
-struct ValueDeserializer<'de, Value, Key>
+/// Parses a slice of bytes into a Value dom like `deserialize` but
+/// interns object keys: each distinct key is converted into a `Key`
+/// once and every further occurrence gets a clone of it.
+///
+/// With a key type whose clones share one allocation, like `Rc<str>` or
+/// `Arc<str>`, documents made of many records with the same handful of
+/// keys store each key only once instead of once per record.
+///
+/// ```rust
+/// use simd_json::{BorrowedValue, prelude::*};
+/// use std::borrow::Cow;
+/// let mut d = br#"[{"id": 1}, {"id": 2}]"#.to_vec();
+/// let v: BorrowedValue = simd_json::deserialize_interned::<_, Cow<str>>(&mut d).unwrap();
+/// assert_eq!(v[1]["id"], 2);
+/// ```
+///
+/// # Errors
+///
+/// Will return `Err` if `s` is invalid JSON.
+pub fn deserialize_interned<'de, Value, Key>(s: &'de mut [u8]) -> Result<Value>
 where
     Value: Builder<'de> + From<Vec<Value>> + From<HashMap<Key, Value>> + 'de,
-    Key: Hash + Eq + From<&'de str>,
+    Key: Hash + Eq + Clone + From<&'de str>,
+{
+    match Deserializer::from_slice(s) {
+        Ok(de) => Ok(ValueDeserializer::from_deserializer(de, HashMap::new()).parse()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Turns the keys of objects into the key type of the dom
+trait KeySource<'de, Key> {
+    fn key(&mut self, key: &'de str) -> Key;
+}
+
+/// Converts every key on its own
+struct KeyFrom;
+
+impl<'de, Key> KeySource<'de, Key> for KeyFrom
+where
+    Key: From<&'de str>,
+{
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn key(&mut self, key: &'de str) -> Key {
+        key.into()
+    }
+}
+
+/// Converts every distinct key once and hands out clones of it after that
+impl<'de, Key> KeySource<'de, Key> for HashMap<&'de str, Key>
+where
+    Key: Clone + From<&'de str>,
+{
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn key(&mut self, key: &'de str) -> Key {
+        if let Some(interned) = self.get(key) {
+            return interned.clone();
+        }
+        let interned = Key::from(key);
+        self.insert_nocheck(key, interned.clone());
+        interned
+    }
+}
+
+struct ValueDeserializer<'de, Value, Key, Keys>
+where
+    Value: Builder<'de> + From<Vec<Value>> + From<HashMap<Key, Value>> + 'de,
+    Key: Hash + Eq,
+    Keys: KeySource<'de, Key>,
 {
     de: Deserializer<'de>,
+    keys: Keys,
     _marker: PhantomData<(Value, Key)>,
 }
 
-impl<'de, Value, Key> ValueDeserializer<'de, Value, Key>
+impl<'de, Value, Key, Keys> ValueDeserializer<'de, Value, Key, Keys>
 where
     Value: Builder<'de> + From<&'de str> + From<Vec<Value>> + From<HashMap<Key, Value>> + 'de,
-    Key: Hash + Eq + From<&'de str>,
+    Key: Hash + Eq,
+    Keys: KeySource<'de, Key>,
 {
-    pub fn from_deserializer(de: Deserializer<'de>) -> Self {
+    pub fn from_deserializer(de: Deserializer<'de>, keys: Keys) -> Self {
         Self {
             de,
+            keys,
             _marker: PhantomData::default(),
         }
     }
@@ -115,6 +338,7 @@ where
         }
     }
 
+    #[cfg(not(feature = "safe"))]
     #[cfg_attr(not(feature = "no-inline"), inline(always))]
     fn parse_array(&mut self, len: usize) -> Value {
         // Rust doens't optimize the normal loop away here
@@ -124,12 +348,22 @@ where
         unsafe {
             res.set_len(len);
             for i in 0..len {
-                std::ptr::write(res.get_unchecked_mut(i), self.parse());
+                core::ptr::write(res.get_unchecked_mut(i), self.parse());
             }
         }
         Value::from(res)
     }
 
+    #[cfg(feature = "safe")]
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn parse_array(&mut self, len: usize) -> Value {
+        let mut res = Vec::with_capacity(len);
+        for _ in 0..len {
+            res.push(self.parse());
+        }
+        Value::from(res)
+    }
+
     #[cfg_attr(not(feature = "no-inline"), inline(always))]
     fn parse_map(&mut self, len: usize) -> Value {
         let mut res: HashMap<Key, Value> = HashMap::with_capacity(len);
@@ -138,7 +372,8 @@ where
         // element so we eat this
         for _ in 0..len {
             if let Node::String(key) = self.de.next_() {
-                res.insert_nocheck(key.into(), self.parse());
+                let key = self.keys.key(key);
+                res.insert_nocheck(key, self.parse());
             } else {
                 unreachable!()
             }
@@ -146,3 +381,34 @@ where
         Value::from(res)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cow::Cow;
+    use alloc::rc::Rc;
+
+    #[test]
+    fn interned_keys_share_allocations() {
+        let mut keys: HashMap<&str, Rc<str>> = HashMap::new();
+        let a = keys.key("id");
+        let b = keys.key("name");
+        let c = keys.key("id");
+        assert!(Rc::ptr_eq(&a, &c));
+        assert!(!Rc::ptr_eq(&a, &b));
+        assert_eq!(keys.len(), 2);
+    }
+
+    #[test]
+    fn deserialize_interned_matches_dom() {
+        let src = br#"[{"id":1,"tags":{"id":"x"}},{"id":2,"tags":{}},{"a\"b":3}]"#;
+        let mut d1 = src.to_vec();
+        let mut d2 = src.to_vec();
+        let mut d3 = src.to_vec();
+        let v: BorrowedValue = deserialize_interned::<_, Cow<str>>(&mut d1).unwrap();
+        assert_eq!(v, to_borrowed_value(&mut d2).unwrap());
+        let v: OwnedValue = deserialize_interned::<_, alloc::string::String>(&mut d3).unwrap();
+        assert_eq!(v[2]["a\"b"], 3);
+        assert_eq!(v[0]["tags"]["id"], "x");
+    }
+}
@@ -0,0 +1,280 @@
+use crate::prelude::*;
+use crate::value::Dom;
+use crate::{BorrowedValue, OwnedValue};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::convert::TryFrom;
+
+/// How far apart two numbers may be for `approx_eq` to take them as the
+/// same. Numbers are the same if they are within any one of the limits,
+/// the default has none so only equal numbers are the same.
+///
+/// ```rust
+/// use simd_json::Tolerance;
+/// let tolerance = Tolerance::default().absolute(1e-9).relative(1e-6).ulps(4);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Tolerance {
+    absolute: f64,
+    relative: f64,
+    ulps: u64,
+}
+
+impl Tolerance {
+    /// Numbers at most `epsilon` apart are the same, this suits values
+    /// close to zero
+    #[must_use]
+    pub fn absolute(mut self, epsilon: f64) -> Self {
+        self.absolute = epsilon;
+        self
+    }
+
+    /// Numbers whose difference is at most `fraction` of the larger one
+    /// are the same, `1e-6` allows them to differ in the millionths
+    #[must_use]
+    pub fn relative(mut self, fraction: f64) -> Self {
+        self.relative = fraction;
+        self
+    }
+
+    /// Numbers with at most `ulps` floats between them are the same, this
+    /// allows for rounding in the last digits at any magnitude
+    #[must_use]
+    pub fn ulps(mut self, ulps: u64) -> Self {
+        self.ulps = ulps;
+        self
+    }
+
+    #[allow(clippy::float_cmp)]
+    fn allows(self, a: f64, b: f64) -> bool {
+        if a == b || (a.is_nan() && b.is_nan()) {
+            return true;
+        }
+        // infinities are only the same as themselves
+        if !a.is_finite() || !b.is_finite() {
+            return false;
+        }
+        let difference = (a - b).abs();
+        difference <= self.absolute
+            || difference <= self.relative * a.abs().max(b.abs())
+            || ulps_between(a, b) <= self.ulps
+    }
+
+    /// Integers are compared by their exact difference, a float might not
+    /// hold them
+    #[allow(clippy::cast_precision_loss)]
+    fn allows_integers(self, a: i128, b: i128) -> bool {
+        let difference = a.abs_diff(b) as f64;
+        a == b
+            || difference <= self.absolute
+            || difference <= self.relative * a.unsigned_abs().max(b.unsigned_abs()) as f64
+    }
+}
+
+/// Number of floats from `a` to `b`, `u64::MAX` if either isn't a number
+#[allow(clippy::cast_possible_wrap)]
+fn ulps_between(a: f64, b: f64) -> u64 {
+    if a.is_nan() || b.is_nan() {
+        return u64::MAX;
+    }
+    // maps the floats to integers in the same order, with both zeros at 0
+    let ordered = |f: f64| {
+        let bits = f.to_bits() as i64;
+        if bits < 0 {
+            i64::MIN.wrapping_sub(bits)
+        } else {
+            bits
+        }
+    };
+    u64::try_from((i128::from(ordered(a)) - i128::from(ordered(b))).unsigned_abs())
+        .unwrap_or(u64::MAX)
+}
+
+impl OwnedValue {
+    /// Compares the value with `other` like `==` but takes numbers as
+    /// the same when `tolerance` allows it, see
+    /// `BorrowedValue::approx_eq`.
+    ///
+    /// ```rust
+    /// use simd_json::{json, Tolerance};
+    /// let v = json!({"mean": 0.30000000000000004, "n": 10});
+    /// let tolerance = Tolerance::default().ulps(1);
+    /// assert!(v.approx_eq(&json!({"n": 10.0, "mean": 0.3}), tolerance));
+    /// ```
+    #[must_use]
+    pub fn approx_eq<V>(&self, other: &V, tolerance: Tolerance) -> bool
+    where
+        V: Dom,
+        V::Key: Borrow<str>,
+    {
+        approx_eq(self, other, tolerance)
+    }
+}
+
+impl BorrowedValue<'_> {
+    /// Compares the value with `other`, which can be an owned or a
+    /// borrowed value, like `==` but takes numbers as the same when
+    /// `tolerance` allows it. Integers and floats are compared by their
+    /// value, so `1` and `1.0` are always the same, and `NaN`s are the
+    /// same as each other. Objects are compared regardless of the order
+    /// of their keys, like with `==`.
+    ///
+    /// ```rust
+    /// use simd_json::{json, Tolerance};
+    /// let mut d = br#"{"scores": [0.1, 2.5e10], "ok": true}"#.to_vec();
+    /// let v = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// let fixture = json!({"ok": true, "scores": [0.1000001, 2.5000001e10]});
+    /// assert!(!v.approx_eq(&fixture, Tolerance::default()));
+    /// assert!(v.approx_eq(&fixture, Tolerance::default().relative(1e-6)));
+    /// assert!(!v.approx_eq(&fixture, Tolerance::default().absolute(1e-6)));
+    /// ```
+    #[must_use]
+    pub fn approx_eq<V>(&self, other: &V, tolerance: Tolerance) -> bool
+    where
+        V: Dom,
+        V::Key: Borrow<str>,
+    {
+        approx_eq(self, other, tolerance)
+    }
+}
+
+pub(super) fn approx_eq<A, B>(a: &A, b: &B, tolerance: Tolerance) -> bool
+where
+    A: Dom,
+    A::Key: Borrow<str>,
+    B: Dom,
+    B::Key: Borrow<str>,
+{
+    // pairs still to compare, kept on a stack so deep nesting can't
+    // overflow the call stack
+    let mut stack: Vec<(&A, &B)> = alloc::vec![(a, b)];
+    while let Some((a, b)) = stack.pop() {
+        if let (Some(a), Some(b)) = (a.as_object(), b.as_object()) {
+            if a.len() != b.len() {
+                return false;
+            }
+            for (key, a) in a.iter() {
+                match b.get(key.borrow()) {
+                    Some(b) => stack.push((a, b)),
+                    None => return false,
+                }
+            }
+        } else if let (Some(a), Some(b)) = (a.as_array(), b.as_array()) {
+            if a.len() != b.len() {
+                return false;
+            }
+            stack.extend(a.iter().zip(b.iter()));
+        } else if !leaf_eq(a, b, tolerance) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Compares values that aren't both arrays or both objects
+pub(super) fn leaf_eq<A: Dom, B: Dom>(a: &A, b: &B, tolerance: Tolerance) -> bool {
+    #[cfg(feature = "bytes")]
+    {
+        if a.bytes().is_some() || b.bytes().is_some() {
+            return a.bytes() == b.bytes();
+        }
+    }
+    if a.is_number() && b.is_number() {
+        if let (Some(a), Some(b)) = (integer(a), integer(b)) {
+            return tolerance.allows_integers(a, b);
+        }
+        return match (a.cast_f64(), b.cast_f64()) {
+            (Some(a), Some(b)) => tolerance.allows(a, b),
+            _ => false,
+        };
+    }
+    match (a.value_type(), b.value_type()) {
+        (ValueType::Null, ValueType::Null) => true,
+        (ValueType::Bool, ValueType::Bool) => a.as_bool() == b.as_bool(),
+        (ValueType::String, ValueType::String) => a.as_str() == b.as_str(),
+        _ => false,
+    }
+}
+
+fn integer<V: Dom>(value: &V) -> Option<i128> {
+    if value.is_float() {
+        return None;
+    }
+    #[cfg(feature = "128bit")]
+    {
+        if let Some(i) = value.as_i128() {
+            return Some(i);
+        }
+    }
+    value
+        .as_i64()
+        .map(i128::from)
+        .or_else(|| value.as_u64().map(i128::from))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn numbers() {
+        let exact = Tolerance::default();
+        assert!(exact.allows(1.0, 1.0));
+        assert!(exact.allows(f64::NAN, f64::NAN));
+        assert!(exact.allows(0.0, -0.0));
+        assert!(!exact.allows(1.0, 1.0 + f64::EPSILON));
+        assert!(Tolerance::default().ulps(1).allows(1.0, 1.0 + f64::EPSILON));
+        assert!(!Tolerance::default()
+            .ulps(1)
+            .allows(1.0, 1.0 + 2.0 * f64::EPSILON));
+        assert!(!Tolerance::default()
+            .ulps(2)
+            .allows(-f64::MIN_POSITIVE, f64::MIN_POSITIVE));
+        assert!(Tolerance::default().ulps(2).allows(-5e-324, 5e-324));
+        assert!(!Tolerance::default()
+            .ulps(u64::MAX - 1)
+            .allows(f64::NAN, 1.0));
+        assert!(Tolerance::default().absolute(0.5).allows(1.0, 1.5));
+        assert!(!Tolerance::default().absolute(0.5).allows(1.0, 1.6));
+        assert!(Tolerance::default().relative(0.01).allows(100.0, 101.0));
+        assert!(!Tolerance::default().relative(0.01).allows(1.0, 1.02));
+        assert!(!Tolerance::default()
+            .relative(1.0)
+            .allows(f64::INFINITY, 1.0));
+    }
+
+    #[test]
+    fn values() {
+        let close = Tolerance::default().absolute(0.01);
+        let v = json!({"a": [1, 2.0, {"b": null}], "c": "x", "d": true});
+        assert!(v.approx_eq(&v, Tolerance::default()));
+        assert!(v.approx_eq(
+            &json!({"d": true, "c": "x", "a": [1.0, 2.001, {"b": null}]}),
+            close
+        ));
+        assert!(!v.approx_eq(
+            &json!({"d": true, "c": "x", "a": [1.0, 2.1, {"b": null}]}),
+            close
+        ));
+        assert!(!v.approx_eq(&json!({"d": true, "c": "x", "a": [1, 2.0]}), close));
+        assert!(!v.approx_eq(
+            &json!({"d": true, "c": "x", "e": [1, 2.0, {"b": null}]}),
+            close
+        ));
+        assert!(!v.approx_eq(
+            &json!({"d": true, "c": "y", "a": [1, 2.0, {"b": null}]}),
+            close
+        ));
+        assert!(!json!(1).approx_eq(&json!("1"), close));
+        assert!(!json!(null).approx_eq(&json!(false), close));
+        assert!(!json!([]).approx_eq(&json!({}), close));
+        assert!(json!(i64::MAX).approx_eq(&OwnedValue::from(i64::MAX as u64), close));
+        assert!(!json!(i64::MAX).approx_eq(&json!(i64::MAX - 1), Tolerance::default()));
+        assert!(json!(i64::MAX).approx_eq(&json!(i64::MAX - 1), Tolerance::default().absolute(1.0)));
+        assert!(json!(1).approx_eq(&json!(1.0), Tolerance::default()));
+        assert!(json!(100).approx_eq(&json!(101), Tolerance::default().relative(0.01)));
+        let b = BorrowedValue::from(v.clone());
+        assert!(b.approx_eq(&v, Tolerance::default()));
+        assert!(v.approx_eq(&b, Tolerance::default()));
+    }
+}
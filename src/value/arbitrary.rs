@@ -0,0 +1,115 @@
+use crate::{BorrowedValue, OwnedValue, StaticNode};
+use ::arbitrary::{Arbitrary, Result, Unstructured};
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// How deep arrays and objects nest at most, deeper values are scalars so
+/// the generator can't overflow the stack
+const MAX_DEPTH: usize = 32;
+
+/// Generates values of the shape the parser produces: non negative
+/// integers are `U64` and negative ones `I64`, floats are finite.
+impl<'a> Arbitrary<'a> for OwnedValue {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        arbitrary_value(u, MAX_DEPTH)
+    }
+
+    fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+        (1, None)
+    }
+}
+
+/// Generates the same values as the implementation for `OwnedValue`
+impl<'a> Arbitrary<'a> for BorrowedValue<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        OwnedValue::arbitrary(u).map(Self::from)
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        OwnedValue::size_hint(depth)
+    }
+}
+
+fn arbitrary_value(u: &mut Unstructured, depth: usize) -> Result<OwnedValue> {
+    let kinds = if depth == 0 { 5 } else { 7 };
+    Ok(match u.choose_index(kinds)? {
+        0 => OwnedValue::Static(StaticNode::Null),
+        1 => OwnedValue::Static(StaticNode::Bool(u.arbitrary()?)),
+        2 => {
+            let i: i64 = u.arbitrary()?;
+            #[allow(clippy::cast_sign_loss)]
+            OwnedValue::Static(if i < 0 {
+                StaticNode::I64(i)
+            } else {
+                StaticNode::U64(i as u64)
+            })
+        }
+        3 => {
+            let f: f64 = u.arbitrary()?;
+            OwnedValue::Static(StaticNode::F64(if f.is_finite() { f } else { 0.0 }))
+        }
+        4 => OwnedValue::String(u.arbitrary()?),
+        5 => {
+            let len = u.arbitrary_len::<OwnedValue>()?;
+            let mut array = Vec::with_capacity(len);
+            for _ in 0..len {
+                array.push(arbitrary_value(u, depth - 1)?);
+            }
+            OwnedValue::Array(array)
+        }
+        _ => {
+            let len = u.arbitrary_len::<(String, OwnedValue)>()?;
+            let mut object = crate::value::owned::Object::with_capacity(len);
+            for _ in 0..len {
+                let key: String = u.arbitrary()?;
+                object.insert(key, arbitrary_value(u, depth - 1)?);
+            }
+            OwnedValue::Object(Box::new(object))
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn round_trip() {
+        // a cheap deterministic stream of bytes to generate values from
+        let mut x = 0x2545_f491_4f6c_dd1d_u64;
+        let data: Vec<u8> = (0..1 << 16)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                x.to_le_bytes()[0]
+            })
+            .collect();
+        let mut u = Unstructured::new(&data);
+        let mut nested = false;
+        while !u.is_empty() {
+            let v = OwnedValue::arbitrary(&mut u).unwrap();
+            if let Some(a) = v.as_array() {
+                nested |= a.iter().any(|v| v.is_array() || v.is_object());
+            }
+            let mut d = v.encode().into_bytes();
+            assert_eq!(crate::to_owned_value(&mut d).unwrap(), v);
+        }
+        assert!(nested);
+    }
+
+    #[test]
+    fn depth_is_limited() {
+        let data = [255_u8; 4096];
+        let v = OwnedValue::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        let mut d = v.encode().into_bytes();
+        assert_eq!(crate::to_owned_value(&mut d).unwrap(), v);
+        let v = BorrowedValue::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        assert_eq!(
+            v,
+            OwnedValue::arbitrary(&mut Unstructured::new(&data)).unwrap()
+        );
+    }
+}
@@ -0,0 +1,392 @@
+use super::borrowed::{Object, Value};
+use super::build::{build, Build};
+#[cfg(feature = "unicode")]
+use crate::Normalization;
+use crate::{Deserializer, Node, ParseOptions, Result, StaticNode};
+use bumpalo::collections::Vec as BumpVec;
+use bumpalo::Bump;
+use core::convert::TryFrom;
+
+/// Parses a slice of bytes into an arena dom. Strings are de-escaped in
+/// place and referenced from the slice, arrays and objects are allocated
+/// from `bump`.
+///
+/// # Errors
+///
+/// Will return `Err` if `s` is invalid JSON.
+pub fn to_value<'a>(s: &'a mut [u8], bump: &'a Bump) -> Result<ArenaValue<'a>> {
+    to_value_with_options(s, bump, ParseOptions::default())
+}
+
+/// Parses a slice of bytes into an arena dom using the given parse
+/// `options`, see `to_value` for details. With lossy UTF-8 mode, or when
+/// invalid escapes are rewritten, the repaired input is allocated from
+/// `bump` as well.
+///
+/// # Errors
+///
+/// Will return `Err` if `s` is invalid JSON.
+pub fn to_value_with_options<'a>(
+    s: &'a mut [u8],
+    bump: &'a Bump,
+    options: ParseOptions,
+) -> Result<ArenaValue<'a>> {
+    if let Some(repaired) = options.repair(s) {
+        return to_value_with_options(bump.alloc_slice_copy(&repaired), bump, options);
+    }
+    match Deserializer::from_slice_or_input(s, options) {
+        Ok(de) => Ok(ArenaDeserializer {
+            de,
+            bump,
+            #[cfg(feature = "unicode")]
+            normalization: options.normalization,
+        }
+        .parse()),
+        Err((e, input)) => match options.repair_utf8(input) {
+            Some(repaired) => {
+                to_value_with_options(bump.alloc_slice_copy(&repaired), bump, options)
+            }
+            None => Err(e),
+        },
+    }
+}
+
+/// A JSON value whose arrays and objects live in a bump arena
+///
+/// Nothing in the value needs dropping, freeing a document is resetting
+/// or dropping its arena.
+///
+/// Arrays that hold nothing but integers fitting an `i64` are packed into
+/// `I64Array` instead of `Array`, arrays of other numbers into `F64Array`.
+/// Those are floats, or floats mixed with integers a float holds exactly,
+/// which come back as floats. That takes a third of the memory and keeps
+/// the numbers next to each other, since the parser always packs them two
+/// parsed documents still compare equal.
+///
+/// ```rust
+/// use simd_json::{to_arena_value, ArenaValue};
+/// use bumpalo::Bump;
+/// let bump = Bump::new();
+/// let mut d = br#"{"name": "simd", "tags": [1, 2], "load": [0.5, 1.5]}"#.to_vec();
+/// let v = to_arena_value(&mut d, &bump).unwrap();
+/// assert_eq!(v.get("name"), Some(ArenaValue::String("simd")));
+/// assert_eq!(v.get("tags").unwrap().get_idx(1), Some(ArenaValue::from(2)));
+/// assert_eq!(v.get("load"), Some(ArenaValue::F64Array(&[0.5, 1.5])));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArenaValue<'a> {
+    /// Static values
+    Static(StaticNode),
+    /// string type
+    String(&'a str),
+    /// array type
+    Array(&'a [ArenaValue<'a>]),
+    /// array made up of only integers
+    I64Array(&'a [i64]),
+    /// array made up of only numbers that aren't all integers
+    F64Array(&'a [f64]),
+    /// object type, entries are kept in input order
+    Object(&'a [(&'a str, ArenaValue<'a>)]),
+}
+
+impl<'a> ArenaValue<'a> {
+    /// Gets the value of `key` if this is an object, for duplicate keys
+    /// the first one wins just like in the other doms
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<ArenaValue<'a>> {
+        if let Self::Object(o) = self {
+            o.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+        } else {
+            None
+        }
+    }
+
+    /// Gets the element at `idx` if this is an array, elements of packed
+    /// arrays are returned as `Static` values
+    #[must_use]
+    pub fn get_idx(&self, idx: usize) -> Option<ArenaValue<'a>> {
+        match self {
+            Self::Array(a) => a.get(idx).copied(),
+            Self::I64Array(a) => a.get(idx).map(|&i| Self::from(i)),
+            Self::F64Array(a) => a.get(idx).map(|&f| Self::from(f)),
+            _ => None,
+        }
+    }
+
+    /// Copies this into a borrowed value that doesn't depend on the arena
+    #[must_use]
+    pub fn to_value(&self) -> Value<'a> {
+        match *self {
+            Self::Static(s) => Value::Static(s),
+            Self::String(s) => Value::from(s),
+            Self::Array(a) => Value::Array(a.iter().map(Self::to_value).collect()),
+            Self::I64Array(a) => Value::Array(a.iter().map(|&i| Value::from(i)).collect()),
+            Self::F64Array(a) => Value::Array(a.iter().map(|&f| Value::from(f)).collect()),
+            Self::Object(o) => {
+                let mut res = Object::with_capacity(o.len());
+                for (k, v) in o {
+                    // keeps duplicate keys like `to_borrowed_value` does
+                    res.insert_nocheck((*k).into(), v.to_value());
+                }
+                Value::from(res)
+            }
+        }
+    }
+}
+
+impl<T> From<T> for ArenaValue<'_>
+where
+    StaticNode: From<T>,
+{
+    fn from(v: T) -> Self {
+        Self::Static(StaticNode::from(v))
+    }
+}
+
+struct ArenaDeserializer<'de> {
+    de: Deserializer<'de>,
+    bump: &'de Bump,
+    #[cfg(feature = "unicode")]
+    normalization: Normalization,
+}
+
+impl<'de> ArenaDeserializer<'de> {
+    // The containers are allocated with their final length up front so
+    // they never move inside the arena.
+    fn parse(&mut self) -> ArenaValue<'de> {
+        build(self)
+    }
+
+    /// `s` as a string of the value, `key` tells if it is a key.
+    /// Normalized strings are allocated from the arena.
+    #[cfg(feature = "unicode")]
+    fn arena_str(&self, s: &'de str, key: bool) -> &'de str {
+        match self.normalization.apply(s, key) {
+            alloc::borrow::Cow::Borrowed(s) => s,
+            alloc::borrow::Cow::Owned(s) => self.bump.alloc_str(&s),
+        }
+    }
+
+    /// `s` as a string of the value, `key` tells if it is a key
+    #[cfg(not(feature = "unicode"))]
+    #[allow(clippy::unused_self)]
+    fn arena_str(&self, s: &'de str, _key: bool) -> &'de str {
+        s
+    }
+
+    /// Packs the `len` elements of the array we just entered if they are
+    /// all integers, or all numbers a float holds exactly. Nested arrays
+    /// and objects take more than one node on the tape, so if the next
+    /// `len` nodes are numbers they are exactly the elements.
+    fn packed(&mut self, len: usize) -> Option<ArenaValue<'de>> {
+        fn as_i64(node: &Node) -> Option<i64> {
+            match node {
+                Node::Static(StaticNode::I64(i)) => Some(*i),
+                Node::Static(StaticNode::U64(u)) => i64::try_from(*u).ok(),
+                _ => None,
+            }
+        }
+        // integers up to 2^53 are exactly the float they are cast to
+        #[allow(clippy::cast_precision_loss)]
+        fn as_f64(node: &Node) -> Option<f64> {
+            const EXACT: u64 = 1 << f64::MANTISSA_DIGITS;
+            match node {
+                Node::Static(StaticNode::F64(f)) => Some(*f),
+                Node::Static(StaticNode::I64(i)) if i.unsigned_abs() <= EXACT => Some(*i as f64),
+                Node::Static(StaticNode::U64(u)) if *u <= EXACT => Some(*u as f64),
+                _ => None,
+            }
+        }
+        let elements = self.de.peek_n(len);
+        let packed = if elements.iter().all(|n| as_i64(n).is_some()) {
+            let packed = self
+                .bump
+                .alloc_slice_fill_iter(elements.iter().map(|n| as_i64(n).unwrap_or_default()));
+            ArenaValue::I64Array(packed)
+        } else if elements.iter().all(|n| as_f64(n).is_some()) {
+            let packed = self
+                .bump
+                .alloc_slice_fill_iter(elements.iter().map(|n| as_f64(n).unwrap_or_default()));
+            ArenaValue::F64Array(packed)
+        } else {
+            return None;
+        };
+        self.de.skip_n(len);
+        Some(packed)
+    }
+}
+
+impl<'de> Build<'de> for ArenaDeserializer<'de> {
+    type Value = ArenaValue<'de>;
+    type Key = &'de str;
+    type Array = BumpVec<'de, ArenaValue<'de>>;
+    type Object = BumpVec<'de, (&'de str, ArenaValue<'de>)>;
+
+    fn next_node(&mut self) -> Node<'de> {
+        self.de.next_()
+    }
+
+    fn static_node(&mut self, s: StaticNode) -> Self::Value {
+        ArenaValue::Static(s)
+    }
+
+    fn string(&mut self, s: &'de str) -> Self::Value {
+        ArenaValue::String(self.arena_str(s, false))
+    }
+
+    fn key(&mut self, s: &'de str) -> Self::Key {
+        self.arena_str(s, true)
+    }
+
+    fn array(&mut self, len: usize) -> Self::Array {
+        BumpVec::with_capacity_in(len, self.bump)
+    }
+
+    fn push(&mut self, array: &mut Self::Array, value: Self::Value) {
+        array.push(value);
+    }
+
+    fn finish_array(&mut self, array: Self::Array) -> Self::Value {
+        ArenaValue::Array(array.into_bump_slice())
+    }
+
+    fn object(&mut self, len: usize) -> Self::Object {
+        BumpVec::with_capacity_in(len, self.bump)
+    }
+
+    fn insert(&mut self, object: &mut Self::Object, key: Self::Key, value: Self::Value) {
+        object.push((key, value));
+    }
+
+    fn finish_object(&mut self, object: Self::Object) -> Self::Value {
+        ArenaValue::Object(object.into_bump_slice())
+    }
+
+    fn whole_array(&mut self, len: usize) -> Option<Self::Value> {
+        self.packed(len)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+    use crate::to_borrowed_value;
+
+    #[test]
+    fn matches_borrowed() {
+        let inputs: [&[u8]; 6] = [
+            b"null",
+            br#""a\nb""#,
+            b"[]",
+            b"{}",
+            br#"[1, -2.5, true, "x", [[]], {"a": {"b": [null]}}]"#,
+            br#"{"k": 1, "l": [{"k": 2}, {}], "m": "\u00e9"}"#,
+        ];
+        let bump = Bump::new();
+        for input in &inputs {
+            let mut d1 = input.to_vec();
+            let mut d2 = input.to_vec();
+            let v = to_value(&mut d1, &bump).expect("failed to parse");
+            let expected = to_borrowed_value(&mut d2).expect("failed to parse");
+            assert_eq!(v.to_value(), expected);
+        }
+    }
+
+    #[test]
+    fn lookups() {
+        let bump = Bump::new();
+        let mut d = br#"{"a": [1, 2], "b": "x"}"#.to_vec();
+        let v = to_value(&mut d, &bump).expect("failed to parse");
+        assert_eq!(v.get("a").and_then(|a| a.get_idx(1)), Some(2.into()));
+        assert_eq!(v.get("b"), Some(ArenaValue::String("x")));
+        assert_eq!(v.get("c"), None);
+        assert_eq!(v.get_idx(0), None);
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let bump = Bump::new();
+        let mut d = br#"{"a": [1, 2], "b": "x", "a": [3]}"#.to_vec();
+        let mut d2 = d.clone();
+        let v = to_value(&mut d, &bump).expect("failed to parse");
+        let expected = to_borrowed_value(&mut d2).expect("failed to parse");
+        assert_eq!(v.get("a").and_then(|a| a.get_idx(0)), Some(1.into()));
+        assert_eq!(v.get("a").map(|a| a.to_value()), expected.get("a").cloned());
+        assert_eq!(v.to_value().encode(), expected.encode());
+        if let ArenaValue::Object(o) = v {
+            assert_eq!(o.len(), 3);
+        } else {
+            panic!("expected an object");
+        }
+    }
+
+    #[test]
+    fn packed_arrays() {
+        let bump = Bump::new();
+        let mut d = br#"[[1, -2, 3], [0.5, -1e3], [1, 2.5], [1, [2]], [[1], 2],
+            [18446744073709551615], [1, "a"], [true], [{"a": [4, 5]}], [7],
+            [9007199254740993, 0.5]]"#
+            .to_vec();
+        // the integers of mixed arrays come back as floats
+        let mut d2 = String::from_utf8(d.clone())
+            .expect("UTF-8")
+            .replace("[1, 2.5]", "[1.0, 2.5]")
+            .into_bytes();
+        let v = to_value(&mut d, &bump).expect("failed to parse");
+        let expected = to_borrowed_value(&mut d2).expect("failed to parse");
+        assert_eq!(v.to_value(), expected);
+        let element = |idx| v.get_idx(idx).expect("missing element");
+        assert_eq!(element(0), ArenaValue::I64Array(&[1, -2, 3]));
+        assert_eq!(element(1), ArenaValue::F64Array(&[0.5, -1000.0]));
+        assert_eq!(element(2), ArenaValue::F64Array(&[1.0, 2.5]));
+        assert!(matches!(element(3), ArenaValue::Array(_)));
+        assert!(matches!(element(4), ArenaValue::Array(_)));
+        // too large for an i64
+        assert!(matches!(element(5), ArenaValue::Array(_)));
+        assert!(matches!(element(6), ArenaValue::Array(_)));
+        assert!(matches!(element(7), ArenaValue::Array(_)));
+        let nested = element(8).get_idx(0).and_then(|o| o.get("a"));
+        assert_eq!(nested, Some(ArenaValue::I64Array(&[4, 5])));
+        assert_eq!(element(9).get_idx(0), Some(7.into()));
+        // past 2^53, a float would change the integer
+        assert!(matches!(element(10), ArenaValue::Array(_)));
+        assert_eq!(element(1).get_idx(1), Some((-1000.0).into()));
+        assert_eq!(element(1).get_idx(2), None);
+        assert!(matches!(v, ArenaValue::Array(_)));
+    }
+
+    #[test]
+    fn deep_nesting() {
+        use crate::value::deep::{innermost, nested, DEPTH};
+        let mut d = nested(DEPTH, "null").into_bytes();
+        let bump = Bump::new();
+        // nothing to drop, the arena frees the whole document at once
+        let v = to_value(&mut d, &bump).expect("failed to parse");
+        let (_, depth) = innermost(v, |v| v.get_idx(0).or_else(|| v.get("a")));
+        assert_eq!(depth, DEPTH * 2 + 1);
+    }
+
+    #[test]
+    fn lossy_input_lives_in_the_arena() {
+        let bump = Bump::new();
+        let mut d = b"[\"caf\xe9\"]".to_vec();
+        let options = ParseOptions::default().lossy_utf8_fallback(true);
+        let v = to_value_with_options(&mut d, &bump, options).expect("failed to parse");
+        assert_eq!(v.get_idx(0), Some(ArenaValue::String("caf\u{fffd}")));
+        assert_eq!(d, b"[\"caf\xe9\"]");
+    }
+
+    #[cfg(feature = "unicode")]
+    #[test]
+    fn normalized_strings_live_in_the_arena() {
+        use crate::Normalization;
+        let bump = Bump::new();
+        let mut d = "{\"e\u{301}\": [\"e\u{301}\", \"x\"]}".as_bytes().to_vec();
+        let options = ParseOptions::default().normalization(Normalization::All);
+        let v = to_value_with_options(&mut d, &bump, options).expect("failed to parse");
+        let a = v.get("\u{e9}").expect("no such key");
+        assert_eq!(a.get_idx(0), Some(ArenaValue::String("\u{e9}")));
+        assert_eq!(a.get_idx(1), Some(ArenaValue::String("x")));
+    }
+}
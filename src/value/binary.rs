@@ -0,0 +1,208 @@
+use crate::prelude::*;
+use crate::{BorrowedValue, OwnedValue};
+use alloc::vec::Vec;
+use base64::alphabet;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig};
+use base64::engine::DecodePaddingMode;
+use base64::Engine;
+
+const CONFIG: GeneralPurposeConfig =
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent);
+/// `+` and `/`, with or without padding
+const STANDARD: GeneralPurpose = GeneralPurpose::new(&alphabet::STANDARD, CONFIG);
+/// `-` and `_`, with or without padding
+const URL_SAFE: GeneralPurpose = GeneralPurpose::new(&alphabet::URL_SAFE, CONFIG);
+
+impl OwnedValue {
+    /// Decodes a base64 string, see `BorrowedValue::as_base64`.
+    ///
+    /// ```rust
+    /// use simd_json::json;
+    /// assert_eq!(json!("aGk=").as_base64(), Some(b"hi".to_vec()));
+    /// assert_eq!(json!("hi!").as_base64(), None);
+    /// ```
+    #[must_use]
+    pub fn as_base64(&self) -> Option<Vec<u8>> {
+        base64(self)
+    }
+
+    /// Decodes a hex string, see `BorrowedValue::as_hex`.
+    ///
+    /// ```rust
+    /// use simd_json::json;
+    /// assert_eq!(json!("00fF").as_hex(), Some(vec![0, 255]));
+    /// ```
+    #[must_use]
+    pub fn as_hex(&self) -> Option<Vec<u8>> {
+        hex(self)
+    }
+
+    /// Gets the data of a `Bytes` value, see `BorrowedValue::as_bytes`.
+    ///
+    /// ```rust
+    /// use simd_json::OwnedValue;
+    /// assert_eq!(OwnedValue::Bytes(vec![1, 2]).as_bytes(), Some(&[1, 2][..]));
+    /// ```
+    #[cfg(feature = "bytes")]
+    #[must_use]
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+}
+
+impl BorrowedValue<'_> {
+    /// Decodes a base64 string into bytes, `None` if the value isn't a
+    /// string or isn't valid base64. Both the standard alphabet with `+`
+    /// and `/` and the URL safe one with `-` and `_` are accepted, with
+    /// or without `=` padding, but not mixed in one string. Whitespace
+    /// isn't skipped.
+    ///
+    /// ```rust
+    /// let mut d = br#"["3q2+7w==", "3q2-7w", "3q2 7w=="]"#.to_vec();
+    /// let v = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// assert_eq!(v[0].as_base64(), Some(vec![0xde, 0xad, 0xbe, 0xef]));
+    /// assert_eq!(v[1].as_base64(), Some(vec![0xde, 0xad, 0xbe, 0xef]));
+    /// assert_eq!(v[2].as_base64(), None);
+    /// ```
+    #[must_use]
+    pub fn as_base64(&self) -> Option<Vec<u8>> {
+        base64(self)
+    }
+
+    /// Decodes a hex string into bytes, `None` if the value isn't a
+    /// string or isn't an even number of hex digits. Upper and lower case
+    /// digits are accepted, a `0x` prefix isn't.
+    ///
+    /// ```rust
+    /// let mut d = br#"["CAFE", "caf", "0xcafe"]"#.to_vec();
+    /// let v = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// assert_eq!(v[0].as_hex(), Some(vec![0xca, 0xfe]));
+    /// assert_eq!(v[1].as_hex(), None);
+    /// assert_eq!(v[2].as_hex(), None);
+    /// ```
+    #[must_use]
+    pub fn as_hex(&self) -> Option<Vec<u8>> {
+        hex(self)
+    }
+
+    /// Gets the data of a `Bytes` value, `None` for everything else.
+    /// Strings aren't decoded, use `as_base64` for them. A `Bytes` value
+    /// is written as a base64 string in JSON, so it comes back as a
+    /// string when the JSON is parsed again.
+    ///
+    /// ```rust
+    /// use simd_json::prelude::*;
+    /// use simd_json::BorrowedValue;
+    /// let v = BorrowedValue::Bytes(b"hi"[..].into());
+    /// assert_eq!(v.as_bytes(), Some(&b"hi"[..]));
+    /// assert_eq!(v.encode(), r#""aGk=""#);
+    /// let mut json = v.encode().into_bytes();
+    /// let parsed = simd_json::to_borrowed_value(&mut json).unwrap();
+    /// assert_eq!(parsed.as_bytes(), None);
+    /// assert_eq!(parsed.as_base64(), Some(b"hi".to_vec()));
+    /// ```
+    #[cfg(feature = "bytes")]
+    #[must_use]
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+}
+
+/// Encodes `Bytes` values for JSON, with the standard alphabet and
+/// padding
+#[cfg(feature = "bytes")]
+pub(crate) fn to_base64(bytes: &[u8]) -> alloc::string::String {
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+fn base64<V: ValueTrait>(value: &V) -> Option<Vec<u8>> {
+    let s = value.as_str()?;
+    STANDARD.decode(s).or_else(|_| URL_SAFE.decode(s)).ok()
+}
+
+fn hex<V: ValueTrait>(value: &V) -> Option<Vec<u8>> {
+    let s = value.as_str()?.as_bytes();
+    if s.len() % 2 != 0 {
+        return None;
+    }
+    let digit = |c: u8| match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    };
+    s.chunks_exact(2)
+        .map(|pair| Some(digit(pair[0])? << 4 | digit(pair[1])?))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decoding() {
+        let v = json!({"empty": "", "b64": "+/+/", "url": "-_-_", "mixed": "+_", "n": 1});
+        let b = BorrowedValue::from(v.clone());
+        assert_eq!(v["empty"].as_base64(), Some(vec![]));
+        assert_eq!(v["empty"].as_hex(), Some(vec![]));
+        assert_eq!(b["b64"].as_base64(), Some(vec![0xfb, 0xff, 0xbf]));
+        assert_eq!(b["url"].as_base64(), Some(vec![0xfb, 0xff, 0xbf]));
+        assert_eq!(v["mixed"].as_base64(), None);
+        assert_eq!(v["n"].as_base64(), None);
+        assert_eq!(b["n"].as_hex(), None);
+        assert_eq!(json!("0g").as_hex(), None);
+        assert_eq!(json!("a=").as_base64(), None);
+        assert_eq!(
+            json!("0123456789abcdef").as_hex(),
+            Some(vec![1, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef])
+        );
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn bytes() {
+        use serde::de::value::{BorrowedBytesDeserializer, BytesDeserializer, Error};
+        use serde::Deserialize;
+        use std::borrow::Cow;
+        use std::collections::hash_map::DefaultHasher;
+        use std::convert::TryInto;
+        use std::hash::{Hash, Hasher};
+
+        let hash = |v: &OwnedValue| {
+            let mut h = DefaultHasher::new();
+            crate::ExactValue(v.clone()).hash(&mut h);
+            h.finish()
+        };
+        let mut o = crate::owned::Object::new();
+        o.insert("b".to_string(), OwnedValue::Bytes(vec![0, 255]));
+        let owned = OwnedValue::from(o);
+        let borrowed = BorrowedValue::from(owned.clone());
+        assert_eq!(owned.encode(), r#"{"b":"AP8="}"#);
+        assert_eq!(borrowed.encode_pp(), "{\n  \"b\": \"AP8=\"\n}");
+        assert_eq!(owned["b"].to_string(), "AP8=");
+        assert_eq!(owned["b"].value_type(), ValueType::Custom("bytes"));
+        assert_eq!(owned, borrowed);
+        assert_eq!(OwnedValue::from(borrowed.clone_static()), owned);
+        assert_ne!(owned["b"], OwnedValue::from("AP8="));
+        assert_ne!(hash(&owned["b"]), hash(&OwnedValue::from("AP8=")));
+        assert_eq!(borrowed["b"].as_bytes(), Some(&[0, 255][..]));
+        assert_eq!(borrowed["b"].as_str(), None);
+
+        assert_eq!(serde_json::to_string(&borrowed).unwrap(), r#"{"b":"AP8="}"#);
+        let json: serde_json::Value = owned.try_into().unwrap();
+        assert_eq!(json, serde_json::json!({"b": "AP8="}));
+        let v: OwnedValue =
+            Deserialize::deserialize(BytesDeserializer::<Error>::new(&[1, 2])).unwrap();
+        assert_eq!(v.as_bytes(), Some(&[1, 2][..]));
+        let v: BorrowedValue =
+            Deserialize::deserialize(BorrowedBytesDeserializer::<Error>::new(&[3])).unwrap();
+        assert_eq!(v, BorrowedValue::Bytes(Cow::Borrowed(&[3])));
+    }
+}
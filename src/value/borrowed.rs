@@ -22,18 +22,37 @@
 /// ```
 mod cmp;
 mod from;
+#[cfg(feature = "std")]
 mod serialize;
 
+#[cfg(feature = "std")]
+use crate::buffers::Buffers;
 use crate::cow::Cow;
 use crate::prelude::*;
-use crate::{Deserializer, Node, Result, StaticNode};
+use crate::stats::Stopwatch;
+use crate::value::build::{build, dismantle, Build, Nested};
+use crate::value::emit::{Emit, Part};
+use crate::value::map::{DebugMap, DefaultMaps, MapBackend, ObjectMap};
+#[cfg(feature = "unicode")]
+use crate::Normalization;
+use crate::{
+    ArrayBuilder, Deserializer, Node, ObjectBuilder, ParseOptions, ParseStats, Result, StaticNode,
+};
+use alloc::boxed::Box;
+use alloc::string::ToString;
+use alloc::vec::{self, Vec};
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::{Index, IndexMut, RangeBounds};
 use halfbrown::HashMap;
-use std::fmt;
-use std::ops::{Index, IndexMut};
 
 /// Representation of a JSON object
 pub type Object<'v> = HashMap<Cow<'v, str>, Value<'v>>;
 
+/// An entry of an `Object`, see `Value::entry`
+pub type Entry<'a, 'v> =
+    halfbrown::Entry<'a, Cow<'v, str>, Value<'v>, halfbrown::DefaultHashBuilder>;
+
 /// Parses a slice of butes into a Value dom. This function will
 /// rewrite the slice to de-escape strings.
 /// As we reference parts of the input slice the resulting dom
@@ -49,6 +68,71 @@ pub fn to_value<'v>(s: &'v mut [u8]) -> Result<Value<'v>> {
     }
 }
 
+/// Parses a slice of bytes into a Value dom using the given parse
+/// `options`, see `to_value` for details.
+///
+/// # Errors
+///
+/// Will return `Err` if `s` is invalid JSON.
+pub fn to_value_with_options<'v>(s: &'v mut [u8], options: ParseOptions) -> Result<Value<'v>> {
+    if let Some(mut repaired) = options.repair(s) {
+        // The repaired input doesn't outlive this call so we can't
+        // borrow from it
+        return to_value_with_options(&mut repaired, options).map(Value::into_static);
+    }
+    match Deserializer::from_slice_or_input(s, options) {
+        Ok(de) => Ok(BorrowDeserializer::from_deserializer(de)
+            .with_options(options)
+            .parse()),
+        Err((e, input)) => match options.repair_utf8(input) {
+            Some(mut repaired) => {
+                to_value_with_options(&mut repaired, options).map(Value::into_static)
+            }
+            None => Err(e),
+        },
+    }
+}
+
+/// Parses a slice of bytes into a value dom like `to_value` with the
+/// objects stored in the maps `M` picks, see `MapBackend`.
+///
+/// # Errors
+///
+/// Will return `Err` if `s` is invalid JSON.
+pub fn to_value_with_map<'v, M: MapBackend>(s: &'v mut [u8]) -> Result<GenericValue<'v, M>> {
+    match Deserializer::from_slice(s) {
+        Ok(de) => Ok(BorrowDeserializer::from_deserializer(de).parse()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Parses a slice of bytes into a Value dom like `to_value` and returns
+/// what the parse found and how long its stages took next to it, see
+/// `ParseStats`.
+///
+/// # Errors
+///
+/// Will return `Err` if `s` is invalid JSON.
+pub fn to_value_with_stats<'v>(s: &'v mut [u8]) -> Result<(Value<'v>, ParseStats)> {
+    let mut stats = ParseStats::default();
+    let de = Deserializer::from_slice_with_stats(s, ParseOptions::default(), &mut stats)?;
+    stats.count_allocations(&de.tape, false);
+    let mut watch = Stopwatch::start(true);
+    let value = BorrowDeserializer::from_deserializer(de).parse();
+    stats.build_done(&mut watch);
+    Ok((value, stats))
+}
+
+/// Like `to_value` but uses `buffers` as scratch space
+#[cfg(feature = "std")]
+pub(crate) fn to_value_with_buffers<'v>(
+    s: &'v mut [u8],
+    buffers: &mut Buffers,
+) -> Result<Value<'v>> {
+    let de = Deserializer::from_slice_with_buffers(s, ParseOptions::default(), None, buffers)?;
+    Ok(BorrowDeserializer::from_deserializer(de).parse())
+}
+
 /// Borrowed JSON-DOM Value, consider using the `ValueTrait`
 /// to access its content
 #[derive(Debug, Clone)]
@@ -61,6 +145,30 @@ pub enum Value<'v> {
     Array(Vec<Value<'v>>),
     /// object type
     Object(Box<Object<'v>>),
+    /// binary data, from or for formats other than JSON, written as a
+    /// base64 string in JSON
+    #[cfg(feature = "bytes")]
+    Bytes(alloc::borrow::Cow<'v, [u8]>),
+}
+
+/// A borrowed value with its objects stored in the maps `M` picks, see
+/// `MapBackend`. Unlike `Value` it is invariant over its lifetime, the
+/// maps are picked through `M` so the compiler can't tell how they use
+/// it: a `GenericValue<'static, M>` can't be used where a shorter
+/// lifetime is expected.
+pub enum GenericValue<'v, M: MapBackend = DefaultMaps> {
+    /// Static values
+    Static(StaticNode),
+    /// string type
+    String(Cow<'v, str>),
+    /// array type
+    Array(Vec<GenericValue<'v, M>>),
+    /// object type
+    Object(Box<M::Map<Cow<'v, str>, GenericValue<'v, M>>>),
+    /// binary data, from or for formats other than JSON, written as a
+    /// base64 string in JSON
+    #[cfg(feature = "bytes")]
+    Bytes(alloc::borrow::Cow<'v, [u8]>),
 }
 
 impl<'v> Value<'v> {
@@ -70,18 +178,16 @@ impl<'v> Value<'v> {
     #[inline]
     #[must_use]
     pub fn into_static(self) -> Value<'static> {
-        unsafe {
-            use std::mem::transmute;
-            let r = match self {
-                Self::String(s) => Self::String(s.into_owned().into()),
-                Self::Array(arr) => arr.into_iter().map(Value::into_static).collect(),
-                Self::Object(obj) => obj
-                    .into_iter()
-                    .map(|(k, v)| (Cow::from(k.into_owned()), v.into_static()))
-                    .collect(),
-                _ => self,
-            };
-            transmute(r)
+        match self {
+            Self::String(s) => Value::String(s.into_owned().into()),
+            Self::Array(arr) => arr.into_iter().map(Value::into_static).collect(),
+            Self::Object(obj) => obj
+                .into_iter()
+                .map(|(k, v)| (Cow::from(k.into_owned()), v.into_static()))
+                .collect(),
+            Self::Static(s) => Value::Static(s),
+            #[cfg(feature = "bytes")]
+            Self::Bytes(b) => Value::Bytes(b.into_owned().into()),
         }
     }
 
@@ -90,23 +196,250 @@ impl<'v> Value<'v> {
     #[inline]
     #[must_use]
     pub fn clone_static(&self) -> Value<'static> {
-        unsafe {
-            use std::mem::transmute;
-            let r = match self {
-                Self::String(s) => Self::String(Cow::from(s.to_string())),
-                Self::Array(arr) => arr.iter().map(Value::clone_static).collect(),
-                Self::Object(obj) => obj
-                    .iter()
-                    .map(|(k, v)| (Cow::from(k.to_string()), v.clone_static()))
-                    .collect(),
-                Self::Static(s) => Self::Static(*s),
-            };
-            transmute(r)
+        match self {
+            Self::String(s) => Value::String(Cow::from(s.to_string())),
+            Self::Array(arr) => arr.iter().map(Value::clone_static).collect(),
+            Self::Object(obj) => obj
+                .iter()
+                .map(|(k, v)| (Cow::from(k.to_string()), v.clone_static()))
+                .collect(),
+            Self::Static(s) => Value::Static(*s),
+            #[cfg(feature = "bytes")]
+            Self::Bytes(b) => Value::Bytes(b.to_vec().into()),
+        }
+    }
+
+    /// Starts building an object, see `ObjectBuilder`
+    #[inline]
+    pub fn build_object() -> ObjectBuilder<Self> {
+        ObjectBuilder::new()
+    }
+
+    /// Starts building an array, see `ArrayBuilder`
+    #[inline]
+    pub fn build_array() -> ArrayBuilder<Self> {
+        ArrayBuilder::new()
+    }
+
+    /// Moves the value out, leaving `null` in its place, like
+    /// `mem::take`. Combined with `get_mut` this moves a nested value out
+    /// of a document without cloning it.
+    ///
+    /// ```rust
+    /// use simd_json::prelude::*;
+    /// let mut d = br#"{"a": [1, 2]}"#.to_vec();
+    /// let mut v = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// let a = v.get_mut("a").unwrap().take();
+    /// assert_eq!(a, simd_json::json!([1, 2]));
+    /// assert!(v["a"].is_null());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn take(&mut self) -> Self {
+        core::mem::take(self)
+    }
+
+    /// Puts `value` in place of the value and returns the old one, like
+    /// `mem::replace`
+    #[inline]
+    #[must_use]
+    pub fn replace(&mut self, value: Self) -> Self {
+        core::mem::replace(self, value)
+    }
+
+    /// Gets the field `key` and panics if there is none, unlike indexing
+    /// with `v[key]` which gives `null`.
+    ///
+    /// ```rust
+    /// use simd_json::prelude::*;
+    /// let mut d = br#"{"a": [1]}"#.to_vec();
+    /// let v = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// assert_eq!(v.expect_key("a").expect_idx(0), &1);
+    /// assert!(v["a"][1].is_null());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// if the value isn't an object or has no field `key`
+    #[inline]
+    #[must_use]
+    pub fn expect_key(&self, key: &str) -> &Self {
+        match self.get(key) {
+            Some(v) => v,
+            None => panic!("no key {:?} in {:?} value", key, self.value_type()),
+        }
+    }
+
+    /// Gets the element at `idx` and panics if there is none, unlike
+    /// indexing with `v[idx]` which gives `null`.
+    ///
+    /// # Panics
+    ///
+    /// if the value isn't an array or `idx` is out of bounds
+    #[inline]
+    #[must_use]
+    pub fn expect_idx(&self, idx: usize) -> &Self {
+        match self.get_idx(idx) {
+            Some(v) => v,
+            None => panic!("no index {} in {:?} value", idx, self.value_type()),
+        }
+    }
+
+    /// Gets the entry of `key` if the value is an object, so a field can
+    /// be inserted or updated with a single lookup.
+    ///
+    /// ```rust
+    /// use simd_json::prelude::*;
+    /// let mut d = br#"{"a": 1}"#.to_vec();
+    /// let mut v = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// for key in &["a", "b", "a"] {
+    ///     let count = v.entry(*key).unwrap().or_insert_with(|| 0.into());
+    ///     *count = (count.as_u64().unwrap() + 1).into();
+    /// }
+    /// assert_eq!(v, simd_json::json!({"a": 3, "b": 1}));
+    /// ```
+    #[inline]
+    pub fn entry<K: Into<Cow<'v, str>>>(&mut self, key: K) -> Option<Entry<'_, 'v>> {
+        self.as_object_mut().map(|o| o.entry(key.into()))
+    }
+
+    /// Keeps only the elements for which `f` returns `true`, in place and
+    /// in order, like `Vec::retain`.
+    ///
+    /// ```rust
+    /// use simd_json::prelude::*;
+    /// let mut d = br#"{"a": [1, 2, 3, 4], "b": 2}"#.to_vec();
+    /// let mut v = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// v["a"].retain_items(|x| x.as_u64().unwrap_or(1) % 2 == 0).unwrap();
+    /// assert_eq!(v["a"], simd_json::json!([2, 4]));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `self` is not an array.
+    #[inline]
+    pub fn retain_items<F: FnMut(&Self) -> bool>(
+        &mut self,
+        f: F,
+    ) -> core::result::Result<(), AccessError> {
+        match self {
+            Self::Array(a) => {
+                a.retain(f);
+                Ok(())
+            }
+            _ => Err(AccessError::NotAnArray),
+        }
+    }
+
+    /// Keeps only the entries for which `f` returns `true`, in place, like
+    /// `HashMap::retain`. `f` can change the values it keeps.
+    ///
+    /// ```rust
+    /// use simd_json::prelude::*;
+    /// let mut d = br#"{"a": [1, 2, 3, 4], "b": 2}"#.to_vec();
+    /// let mut v = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// v.retain_entries(|k, _| k != "a").unwrap();
+    /// assert_eq!(v, simd_json::json!({"b": 2}));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `self` is not an object.
+    #[inline]
+    pub fn retain_entries<F: FnMut(&Cow<'v, str>, &mut Self) -> bool>(
+        &mut self,
+        f: F,
+    ) -> core::result::Result<(), AccessError> {
+        match self {
+            Self::Object(o) => {
+                o.retain(f);
+                Ok(())
+            }
+            _ => Err(AccessError::NotAnObject),
+        }
+    }
+
+    /// Removes the elements in `range` and returns them, like
+    /// `Vec::drain`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `self` is not an array.
+    ///
+    /// # Panics
+    ///
+    /// If `range` is out of bounds, like `Vec::drain`.
+    #[inline]
+    pub fn drain_items<R: RangeBounds<usize>>(
+        &mut self,
+        range: R,
+    ) -> core::result::Result<vec::Drain<'_, Self>, AccessError> {
+        match self {
+            Self::Array(a) => Ok(a.drain(range)),
+            _ => Err(AccessError::NotAnArray),
+        }
+    }
+
+    /// Removes all entries and returns them, the object stays allocated.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `self` is not an object.
+    #[inline]
+    pub fn drain_entries(
+        &mut self,
+    ) -> core::result::Result<halfbrown::Drain<'_, Cow<'v, str>, Self>, AccessError> {
+        match self {
+            Self::Object(o) => Ok(o.drain()),
+            _ => Err(AccessError::NotAnObject),
+        }
+    }
+}
+
+/// The impls `Value` and `GenericValue` share. `$lt` is the lifetime of
+/// the value, `$generics` the parameters of the impls next to it and
+/// `$array` and `$object` what arrays and objects are stored in.
+macro_rules! value_impls {
+    ($lt:lifetime, [$($generics:tt)*], $value:ty, $array:ty, $object:ty) => {
+impl<$lt, $($generics)*> $value {
+    /// Drops the value without recursing into it, see
+    /// `OwnedValue::dismantle`.
+    pub fn dismantle(self) {
+        dismantle(self);
+    }
+}
+
+impl<$lt, $($generics)*> Nested for $value {
+    fn take_children(&mut self, out: &mut Vec<Self>) {
+        match self {
+            Self::Array(a) => out.extend(core::mem::take(a)),
+            Self::Object(o) => out.extend(core::mem::take(&mut **o).into_iter().map(|(_, v)| v)),
+            _ => (),
+        }
+    }
+}
+
+impl<$lt, $($generics)*> Emit for $value {
+    type Key = Cow<$lt, str>;
+    type Entries<'a>
+        = <$object as ObjectMap<Self::Key, Self>>::Iter<'a>
+    where
+        Self: 'a;
+
+    #[inline(always)]
+    fn part(&self) -> Part<'_, Self> {
+        match self {
+            Self::Static(s) => Part::Static(*s),
+            Self::String(s) => Part::String(s),
+            #[cfg(feature = "bytes")]
+            Self::Bytes(b) => Part::Bytes(b),
+            Self::Array(a) => Part::Array(a),
+            Self::Object(o) => Part::Object(value_trait::Object::len(&**o), o.entries()),
         }
     }
 }
 
-impl<'v> Builder<'v> for Value<'v> {
+impl<$lt, $($generics)*> Builder<$lt> for $value {
     #[inline]
     #[must_use]
     fn null() -> Self {
@@ -120,14 +453,14 @@ impl<'v> Builder<'v> for Value<'v> {
     #[inline]
     #[must_use]
     fn object_with_capacity(capacity: usize) -> Self {
-        Self::Object(Box::new(Object::with_capacity(capacity)))
+        Self::Object(Box::new(ObjectMap::with_capacity(capacity)))
     }
 }
 
-impl<'v> Mutable for Value<'v> {
+impl<$lt, $($generics)*> Mutable for $value {
     #[inline]
     #[must_use]
-    fn as_array_mut(&mut self) -> Option<&mut Vec<Value<'v>>> {
+    fn as_array_mut(&mut self) -> Option<&mut Vec<Self>> {
         match self {
             Self::Array(a) => Some(a),
             _ => None,
@@ -135,7 +468,7 @@ impl<'v> Mutable for Value<'v> {
     }
     #[inline]
     #[must_use]
-    fn as_object_mut(&mut self) -> Option<&mut HashMap<<Self as ValueTrait>::Key, Self>> {
+    fn as_object_mut(&mut self) -> Option<&mut <Self as ValueTrait>::Object> {
         match self {
             Self::Object(m) => Some(m),
             _ => None,
@@ -143,10 +476,10 @@ impl<'v> Mutable for Value<'v> {
     }
 }
 
-impl<'v> ValueTrait for Value<'v> {
-    type Key = Cow<'v, str>;
-    type Array = Vec<Self>;
-    type Object = HashMap<Self::Key, Self>;
+impl<$lt, $($generics)*> ValueTrait for $value {
+    type Key = Cow<$lt, str>;
+    type Array = $array;
+    type Object = $object;
 
     #[inline]
     #[must_use]
@@ -156,6 +489,8 @@ impl<'v> ValueTrait for Value<'v> {
             Self::String(_) => ValueType::String,
             Self::Array(_) => ValueType::Array,
             Self::Object(_) => ValueType::Object,
+            #[cfg(feature = "bytes")]
+            Self::Bytes(_) => ValueType::Custom("bytes"),
         }
     }
 
@@ -238,7 +573,7 @@ impl<'v> ValueTrait for Value<'v> {
     #[inline]
     #[must_use]
     fn as_str(&self) -> Option<&str> {
-        use std::borrow::Borrow;
+        use core::borrow::Borrow;
         match self {
             Self::String(s) => Some(s.borrow()),
             _ => None,
@@ -247,7 +582,7 @@ impl<'v> ValueTrait for Value<'v> {
 
     #[inline]
     #[must_use]
-    fn as_array(&self) -> Option<&Vec<Value<'v>>> {
+    fn as_array(&self) -> Option<&Vec<Self>> {
         match self {
             Self::Array(a) => Some(a),
             _ => None,
@@ -256,7 +591,7 @@ impl<'v> ValueTrait for Value<'v> {
 
     #[inline]
     #[must_use]
-    fn as_object(&self) -> Option<&HashMap<Self::Key, Self>> {
+    fn as_object(&self) -> Option<&<Self as ValueTrait>::Object> {
         match self {
             Self::Object(m) => Some(m),
             _ => None,
@@ -265,36 +600,49 @@ impl<'v> ValueTrait for Value<'v> {
 }
 
 #[cfg_attr(tarpaulin, skip)]
-impl<'v> fmt::Display for Value<'v> {
+impl<$lt, $($generics)*> fmt::Display for $value {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Self::Static(s) => write!(f, "{}", s),
             Self::String(s) => write!(f, "{}", s),
             Self::Array(a) => write!(f, "{:?}", a),
-            Self::Object(o) => write!(f, "{:?}", o),
+            Self::Object(o) => write!(f, "{:?}", DebugMap(&**o)),
+            #[cfg(feature = "bytes")]
+            Self::Bytes(b) => write!(f, "{}", crate::value::to_base64(b)),
         }
     }
 }
 
-impl<'v> Index<&str> for Value<'v> {
-    type Output = Value<'v>;
+impl<$lt, $($generics)*> Index<&str> for $value {
+    type Output = Self;
+    /// Gets the field `index`, or `null` if the value isn't an object or
+    /// has no such field so `v["a"]["b"]` doesn't panic. Use `expect_key`
+    /// to panic instead.
     #[inline]
     #[must_use]
     fn index(&self, index: &str) -> &Self::Output {
-        self.get(index).unwrap()
+        // missing fields and elements give a promoted static `null`
+        self.get(index)
+            .unwrap_or(&Self::Static(StaticNode::Null))
     }
 }
 
-impl<'v> Index<usize> for Value<'v> {
-    type Output = Value<'v>;
+impl<$lt, $($generics)*> Index<usize> for $value {
+    type Output = Self;
+    /// Gets the element at `index`, or `null` if the value isn't an array
+    /// or is too short. Use `expect_idx` to panic instead.
     #[inline]
     #[must_use]
     fn index(&self, index: usize) -> &Self::Output {
-        self.get_idx(index).unwrap()
+        self.get_idx(index)
+            .unwrap_or(&Self::Static(StaticNode::Null))
     }
 }
 
-impl<'v> IndexMut<&str> for Value<'v> {
+impl<$lt, $($generics)*> IndexMut<&str> for $value {
+    /// Gets the field `index` mutably, there is no `null` to hand out
+    /// here so this panics if the value isn't an object or has no such
+    /// field
     #[inline]
     #[must_use]
     fn index_mut(&mut self, index: &str) -> &mut Self::Output {
@@ -302,7 +650,9 @@ impl<'v> IndexMut<&str> for Value<'v> {
     }
 }
 
-impl<'v> IndexMut<usize> for Value<'v> {
+impl<$lt, $($generics)*> IndexMut<usize> for $value {
+    /// Gets the element at `index` mutably, panics if the value isn't an
+    /// array or is too short
     #[inline]
     #[must_use]
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
@@ -310,7 +660,7 @@ impl<'v> IndexMut<usize> for Value<'v> {
     }
 }
 
-impl<'v> Default for Value<'v> {
+impl<$lt, $($generics)*> Default for $value {
     #[inline]
     #[must_use]
     fn default() -> Self {
@@ -318,52 +668,155 @@ impl<'v> Default for Value<'v> {
     }
 }
 
-struct BorrowDeserializer<'de>(Deserializer<'de>);
 
-impl<'de> BorrowDeserializer<'de> {
-    pub fn from_deserializer(de: Deserializer<'de>) -> Self {
-        Self(de)
+impl<$lt, $($generics)*> Build<$lt> for BorrowDeserializer<$lt, $value> {
+    type Value = $value;
+    type Key = Cow<$lt, str>;
+    type Array = $array;
+    type Object = $object;
+
+    #[inline]
+    fn next_node(&mut self) -> Node<$lt> {
+        self.de.next_()
     }
 
-    #[cfg_attr(not(feature = "no-inline"), inline(always))]
-    pub fn parse(&mut self) -> Value<'de> {
-        match self.0.next_() {
-            Node::Static(s) => Value::Static(s),
-            Node::String(s) => Value::from(s),
-            Node::Array(len, _) => self.parse_array(len),
-            Node::Object(len, _) => self.parse_map(len),
+    #[inline]
+    fn static_node(&mut self, s: StaticNode) -> Self::Value {
+        <$value>::Static(s)
+    }
+
+    #[inline]
+    fn string(&mut self, s: &$lt str) -> Self::Value {
+        <$value>::String(self.cow(s, false))
+    }
+
+    #[inline]
+    fn key(&mut self, s: &$lt str) -> Self::Key {
+        self.cow(s, true)
+    }
+
+    #[inline]
+    fn array(&mut self, len: usize) -> Self::Array {
+        Vec::with_capacity(len)
+    }
+
+    #[inline]
+    fn push(&mut self, array: &mut Self::Array, value: Self::Value) {
+        array.push(value);
+    }
+
+    #[inline]
+    fn finish_array(&mut self, array: Self::Array) -> Self::Value {
+        <$value>::Array(array)
+    }
+
+    #[inline]
+    fn object(&mut self, len: usize) -> Self::Object {
+        ObjectMap::with_capacity(len)
+    }
+
+    #[inline]
+    fn insert(&mut self, object: &mut Self::Object, key: Self::Key, value: Self::Value) {
+        object.insert_parsed(key, value);
+    }
+
+    #[inline]
+    fn finish_object(&mut self, object: Self::Object) -> Self::Value {
+        <$value>::Object(Box::new(object))
+    }
+}
+    };
+}
+
+value_impls!('v, [], Value<'v>, Vec<Value<'v>>, Object<'v>);
+value_impls!(
+    'v,
+    [M: MapBackend,],
+    GenericValue<'v, M>,
+    Vec<GenericValue<'v, M>>,
+    M::Map<Cow<'v, str>, GenericValue<'v, M>>
+);
+
+impl<M: MapBackend> fmt::Debug for GenericValue<'_, M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Static(s) => f.debug_tuple("Static").field(s).finish(),
+            Self::String(s) => f.debug_tuple("String").field(s).finish(),
+            Self::Array(a) => f.debug_tuple("Array").field(a).finish(),
+            Self::Object(o) => f.debug_tuple("Object").field(&DebugMap(&**o)).finish(),
+            #[cfg(feature = "bytes")]
+            Self::Bytes(b) => f.debug_tuple("Bytes").field(b).finish(),
         }
     }
+}
 
-    #[cfg_attr(not(feature = "no-inline"), inline(always))]
-    fn parse_array(&mut self, len: usize) -> Value<'de> {
-        // Rust doens't optimize the normal loop away here
-        // so we write our own avoiding the lenght
-        // checks during push
-        let mut res = Vec::with_capacity(len);
-        unsafe {
-            res.set_len(len);
-            for i in 0..len {
-                std::ptr::write(res.get_unchecked_mut(i), self.parse());
-            }
+impl<M: MapBackend> Clone for GenericValue<'_, M> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Static(s) => Self::Static(*s),
+            Self::String(s) => Self::String(s.clone()),
+            Self::Array(a) => Self::Array(a.clone()),
+            Self::Object(o) => Self::Object(Box::new(o.clone_map())),
+            #[cfg(feature = "bytes")]
+            Self::Bytes(b) => Self::Bytes(b.clone()),
         }
-        Value::Array(res)
     }
+}
 
-    #[cfg_attr(not(feature = "no-inline"), inline(always))]
-    fn parse_map(&mut self, len: usize) -> Value<'de> {
-        let mut res = Object::with_capacity(len);
-
-        // Since we checked if it's empty we know that we at least have one
-        // element so we eat this
-        for _ in 0..len {
-            if let Node::String(key) = self.0.next_() {
-                res.insert_nocheck(key.into(), self.parse());
-            } else {
-                unreachable!()
-            }
+/// Builds values of type `V`, either `Value` or a `GenericValue`
+struct BorrowDeserializer<'de, V> {
+    de: Deserializer<'de>,
+    #[cfg(feature = "unicode")]
+    normalization: Normalization,
+    value: PhantomData<V>,
+}
+
+impl<'de, V> BorrowDeserializer<'de, V>
+where
+    Self: Build<'de, Value = V>,
+{
+    pub fn from_deserializer(de: Deserializer<'de>) -> Self {
+        Self {
+            de,
+            #[cfg(feature = "unicode")]
+            normalization: Normalization::Off,
+            value: PhantomData,
         }
-        Value::from(res)
+    }
+
+    /// Builds the value the way `options` ask for
+    #[cfg(feature = "unicode")]
+    fn with_options(mut self, options: ParseOptions) -> Self {
+        self.normalization = options.normalization;
+        self
+    }
+
+    /// Builds the value the way `options` ask for
+    #[cfg(not(feature = "unicode"))]
+    fn with_options(self, _options: ParseOptions) -> Self {
+        self
+    }
+
+    /// `s` as a string of the value, `key` tells if it is a key. Only
+    /// strings that are normalized stop borrowing from the input.
+    #[cfg(feature = "unicode")]
+    fn cow(&self, s: &'de str, key: bool) -> Cow<'de, str> {
+        match self.normalization.apply(s, key) {
+            alloc::borrow::Cow::Borrowed(s) => Cow::from(s),
+            alloc::borrow::Cow::Owned(s) => Cow::from(s),
+        }
+    }
+
+    /// `s` as a string of the value, `key` tells if it is a key
+    #[cfg(not(feature = "unicode"))]
+    #[allow(clippy::unused_self)]
+    fn cow(&self, s: &'de str, _key: bool) -> Cow<'de, str> {
+        Cow::from(s)
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    pub fn parse(&mut self) -> V {
+        build(self)
     }
 }
 
@@ -372,6 +825,29 @@ mod test {
     #![allow(clippy::cognitive_complexity)]
     use super::*;
 
+    #[test]
+    fn deep_nesting() {
+        use crate::value::deep::{innermost, nested, DEPTH};
+        let mut d = nested(DEPTH, "null").into_bytes();
+        let v = to_value(&mut d).expect("failed to parse");
+        let (null, depth) = innermost(&v, |v| v.get_idx(0).or_else(|| v.get("a")));
+        assert!(null.is_null());
+        assert_eq!(depth, DEPTH * 2 + 1);
+        v.dismantle();
+    }
+
+    #[test]
+    fn covariant() {
+        fn shorten<'a>(v: Value<'static>) -> Value<'a> {
+            v
+        }
+        fn shorten_ref<'a>(v: &'a Value<'static>) -> &'a Value<'a> {
+            v
+        }
+        let v = Value::from("static");
+        assert_eq!(shorten_ref(&v), &shorten(v.clone()));
+    }
+
     #[test]
     fn object_access() {
         let mut v = Value::null();
@@ -677,13 +1153,13 @@ mod test {
 
     #[test]
     fn conversions_f64() {
-        let v = Value::from(std::f64::MAX);
+        let v = Value::from(f64::MAX);
         assert!(!v.is_i64());
         assert!(!v.is_u64());
         assert!(v.is_f64());
         assert!(!v.is_f32());
         assert!(v.is_f64_castable());
-        let v = Value::from(std::f64::MIN);
+        let v = Value::from(f64::MIN);
         assert!(!v.is_i64());
         assert!(!v.is_u64());
         assert!(v.is_f64());
@@ -695,13 +1171,13 @@ mod test {
 
     #[test]
     fn conversions_f32() {
-        let v = Value::from(std::f32::MAX);
+        let v = Value::from(f32::MAX);
         assert!(!v.is_i64());
         assert!(!v.is_u64());
         assert!(v.is_f64());
         assert!(v.is_f32());
         assert!(v.is_f64_castable());
-        let v = Value::from(std::f32::MIN);
+        let v = Value::from(f32::MIN);
         assert!(!v.is_i64());
         assert!(!v.is_u64());
         assert!(v.is_f64());
@@ -803,38 +1279,109 @@ mod test {
         assert_eq!(Value::default(), Value::null())
     }
 
+    #[test]
+    fn take_replace() {
+        let mut v = Value::from(vec![Value::from("a"), Value::from(1)]);
+        let a = v.get_idx_mut(0).unwrap().take();
+        assert_eq!(a, "a");
+        assert_eq!(v, Value::from(vec![Value::null(), Value::from(1)]));
+        let one = v.get_idx_mut(1).unwrap().replace(Value::from(2));
+        assert_eq!(one, 1);
+        assert_eq!(v, Value::from(vec![Value::null(), Value::from(2)]));
+    }
+
+    #[test]
+    fn index_missing() {
+        let v = Value::from(vec![Value::from(1)]);
+        assert_eq!(v[0], 1);
+        assert!(v[1].is_null());
+        assert!(v["a"]["b"][2].is_null());
+        assert_eq!(v.expect_idx(0), &1);
+    }
+
+    #[test]
+    #[should_panic(expected = "no key \"a\" in Array value")]
+    fn expect_key_missing() {
+        let _ = Value::array().expect_key("a");
+    }
+
+    #[test]
+    fn entry() {
+        let mut v = Value::object();
+        match v.entry("a") {
+            Some(halfbrown::Entry::Vacant(e)) => {
+                e.insert(Value::from(1));
+            }
+            _ => panic!("not vacant"),
+        }
+        match v.entry("a") {
+            Some(halfbrown::Entry::Occupied(e)) => assert_eq!(e.get(), &1),
+            _ => panic!("not occupied"),
+        }
+        *v.entry("b").unwrap().or_insert_with(Value::array) = Value::from(2);
+        assert_eq!(v["a"], 1);
+        assert_eq!(v["b"], 2);
+        assert!(Value::null().entry("a").is_none());
+    }
+
+    #[test]
+    fn retain_drain() {
+        let mut v = Value::from(vec![Value::from(1), Value::from(2), Value::from(3)]);
+        v.retain_items(|x| x != &2).unwrap();
+        assert_eq!(v, Value::from(vec![Value::from(1), Value::from(3)]));
+        let drained: Vec<_> = v.drain_items(..1).unwrap().collect();
+        assert_eq!(drained, vec![Value::from(1)]);
+        assert_eq!(v, Value::from(vec![Value::from(3)]));
+        assert_eq!(v.retain_entries(|_, _| true), Err(AccessError::NotAnObject));
+        assert!(v.drain_entries().is_err());
+
+        let mut v = Value::object();
+        v.insert("a", 1).unwrap();
+        v.insert("b", 2).unwrap();
+        v.retain_entries(|_, v| {
+            *v = Value::from(v.as_u64().unwrap() * 10);
+            v != &20
+        })
+        .unwrap();
+        assert_eq!(v["a"], 10);
+        assert!(v.get("b").is_none());
+        let drained: Vec<_> = v.drain_entries().unwrap().collect();
+        assert_eq!(drained.len(), 1);
+        assert_eq!(v, Value::object());
+        assert_eq!(v.retain_items(|_| true), Err(AccessError::NotAnArray));
+        assert!(v.drain_items(..).is_err());
+    }
+
+    #[test]
+    fn collect_extend() {
+        let mut v: Value = (1..3).collect();
+        v.extend(vec!["a"]);
+        assert_eq!(
+            v,
+            Value::from(vec![Value::from(1), Value::from(2), Value::from("a")])
+        );
+        let mut v: Value = vec![("a", 1), ("b", 2)].into_iter().collect();
+        v.extend(vec![("b", 3), ("c", 4)]);
+        assert_eq!(v.as_object().map(Object::len), Some(3));
+        assert_eq!(v["b"], 3);
+        assert_eq!(v["c"], 4);
+    }
+
+    #[test]
+    fn extend_other_values() {
+        let mut v = Value::object();
+        v.extend(vec![1]);
+        assert_eq!(v, Value::from(vec![1]));
+        let mut v = Value::from("a");
+        v.extend(vec![("b", 2)]);
+        assert_eq!(v, vec![("b", 2)].into_iter().collect::<Value>());
+        v.extend(Vec::<Value>::new());
+        assert_eq!(v, Value::array());
+    }
+
     use proptest::prelude::*;
     fn arb_value() -> BoxedStrategy<Value<'static>> {
-        let leaf = prop_oneof![
-            Just(Value::Static(StaticNode::Null)),
-            any::<bool>()
-                .prop_map(StaticNode::Bool)
-                .prop_map(Value::Static),
-            any::<i64>()
-                .prop_map(StaticNode::I64)
-                .prop_map(Value::Static),
-            any::<u64>()
-                .prop_map(StaticNode::U64)
-                .prop_map(Value::Static),
-            any::<f64>()
-                .prop_map(StaticNode::F64)
-                .prop_map(Value::Static),
-            ".*".prop_map(Value::from),
-        ];
-        leaf.prop_recursive(
-            8,   // 8 levels deep
-            256, // Shoot for maximum size of 256 nodes
-            10,  // We put up to 10 items per collection
-            |inner| {
-                prop_oneof![
-                    // Take the inner strategy and make the two recursive cases.
-                    prop::collection::vec(inner.clone(), 0..10).prop_map(Value::Array),
-                    prop::collection::hash_map(".*".prop_map(Cow::from), inner, 0..10)
-                        .prop_map(|m| m.into_iter().collect()),
-                ]
-            },
-        )
-        .boxed()
+        crate::proptest::borrowed_value(crate::proptest::ValueConfig::default())
     }
 
     proptest! {
@@ -858,6 +1405,7 @@ mod test {
             let static_borrowed = borrowed.clone_static();
             assert_eq!(borrowed, static_borrowed);
         }
+        #[cfg(feature = "std")]
         #[test]
         fn prop_serialize_deserialize(borrowed in arb_value()) {
             let mut string = borrowed.encode();
@@ -1,15 +1,19 @@
-use super::Value;
+use super::{GenericValue, Value};
 use crate::prelude::*;
-use crate::OwnedValue;
+use crate::value::map::{self, MapBackend};
+use crate::{GenericOwnedValue, OwnedValue};
+use alloc::string::String;
 
 #[allow(clippy::cast_sign_loss, clippy::default_trait_access)]
-impl<'a> PartialEq for Value<'a> {
+impl PartialEq for Value<'_> {
     #[inline]
     #[must_use]
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Self::Static(s1), Self::Static(s2)) => s1 == s2,
             (Self::String(v1), Self::String(v2)) => v1.eq(v2),
+            #[cfg(feature = "bytes")]
+            (Self::Bytes(v1), Self::Bytes(v2)) => v1.eq(v2),
             (Self::Array(v1), Self::Array(v2)) => v1.eq(v2),
             (Self::Object(v1), Self::Object(v2)) => v1.eq(v2),
             _ => false,
@@ -17,18 +21,26 @@ impl<'a> PartialEq for Value<'a> {
     }
 }
 
-impl<'v, T> PartialEq<&T> for Value<'v>
-where
-    Value<'v>: PartialEq<T>,
-{
+#[allow(clippy::cast_sign_loss, clippy::default_trait_access)]
+/// Values of different lifetimes can be compared, the lifetime can't be
+/// shortened to match as values are invariant over it
+impl<'b, M: MapBackend> PartialEq<GenericValue<'b, M>> for GenericValue<'_, M> {
     #[inline]
     #[must_use]
-    fn eq(&self, other: &&T) -> bool {
-        self == *other
+    fn eq(&self, other: &GenericValue<'b, M>) -> bool {
+        match (self, other) {
+            (Self::Static(s1), GenericValue::Static(s2)) => s1 == s2,
+            (Self::String(v1), GenericValue::String(v2)) => v1.eq(v2),
+            #[cfg(feature = "bytes")]
+            (Self::Bytes(v1), GenericValue::Bytes(v2)) => v1.eq(v2),
+            (Self::Array(v1), GenericValue::Array(v2)) => v1.eq(v2),
+            (Self::Object(v1), GenericValue::Object(v2)) => map::eq(&**v1, &**v2),
+            _ => false,
+        }
     }
 }
 
-impl<'a> PartialEq<OwnedValue> for Value<'a> {
+impl PartialEq<OwnedValue> for Value<'_> {
     #[inline]
     #[must_use]
     fn eq(&self, other: &OwnedValue) -> bool {
@@ -37,145 +49,173 @@ impl<'a> PartialEq<OwnedValue> for Value<'a> {
     }
 }
 
-impl<'v> PartialEq<()> for Value<'v> {
+impl<M: MapBackend> PartialEq<GenericOwnedValue<M>> for GenericValue<'_, M> {
     #[inline]
     #[must_use]
-    fn eq(&self, _other: &()) -> bool {
-        self.is_null()
+    fn eq(&self, other: &GenericOwnedValue<M>) -> bool {
+        // We only need to implement this once
+        other.eq(self)
     }
 }
 
-impl<'v> PartialEq<bool> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn eq(&self, other: &bool) -> bool {
-        self.as_bool().map(|t| t.eq(other)).unwrap_or_default()
-    }
-}
+/// The comparisons with other types `Value` and `GenericValue` share
+macro_rules! cmp_impls {
+    ($lt:lifetime, [$($generics:tt)*], $value:ty) => {
+        impl<$lt, $($generics)* T> PartialEq<&T> for $value
+        where
+            $value: PartialEq<T>,
+        {
+            #[inline]
+            #[must_use]
+            fn eq(&self, other: &&T) -> bool {
+                self == *other
+            }
+        }
 
-impl<'v> PartialEq<str> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn eq(&self, other: &str) -> bool {
-        self.as_str().map(|t| t.eq(other)).unwrap_or_default()
-    }
-}
+        impl<$lt, $($generics)*> PartialEq<()> for $value {
+            #[inline]
+            #[must_use]
+            fn eq(&self, _other: &()) -> bool {
+                self.is_null()
+            }
+        }
 
-impl<'v> PartialEq<&str> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn eq(&self, other: &&str) -> bool {
-        self == *other
-    }
-}
+        impl<$lt, $($generics)*> PartialEq<bool> for $value {
+            #[inline]
+            #[must_use]
+            fn eq(&self, other: &bool) -> bool {
+                self.as_bool().map(|t| t.eq(other)).unwrap_or_default()
+            }
+        }
 
-impl<'v> PartialEq<String> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn eq(&self, other: &String) -> bool {
-        self.as_str().map(|t| t.eq(other)).unwrap_or_default()
-    }
-}
+        impl<$lt, $($generics)*> PartialEq<str> for $value {
+            #[inline]
+            #[must_use]
+            fn eq(&self, other: &str) -> bool {
+                self.as_str().map(|t| t.eq(other)).unwrap_or_default()
+            }
+        }
 
-impl<'v> PartialEq<i8> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn eq(&self, other: &i8) -> bool {
-        self.as_i8().map(|t| t.eq(other)).unwrap_or_default()
-    }
-}
+        impl<$lt, $($generics)*> PartialEq<&str> for $value {
+            #[inline]
+            #[must_use]
+            fn eq(&self, other: &&str) -> bool {
+                self == *other
+            }
+        }
 
-impl<'v> PartialEq<i16> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn eq(&self, other: &i16) -> bool {
-        self.as_i16().map(|t| t.eq(other)).unwrap_or_default()
-    }
-}
+        impl<$lt, $($generics)*> PartialEq<String> for $value {
+            #[inline]
+            #[must_use]
+            fn eq(&self, other: &String) -> bool {
+                self.as_str().map(|t| t.eq(other)).unwrap_or_default()
+            }
+        }
 
-impl<'v> PartialEq<i32> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn eq(&self, other: &i32) -> bool {
-        self.as_i32().map(|t| t.eq(other)).unwrap_or_default()
-    }
-}
+        impl<$lt, $($generics)*> PartialEq<i8> for $value {
+            #[inline]
+            #[must_use]
+            fn eq(&self, other: &i8) -> bool {
+                self.as_i8().map(|t| t.eq(other)).unwrap_or_default()
+            }
+        }
 
-impl<'v> PartialEq<i64> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn eq(&self, other: &i64) -> bool {
-        self.as_i64().map(|t| t.eq(other)).unwrap_or_default()
-    }
-}
+        impl<$lt, $($generics)*> PartialEq<i16> for $value {
+            #[inline]
+            #[must_use]
+            fn eq(&self, other: &i16) -> bool {
+                self.as_i16().map(|t| t.eq(other)).unwrap_or_default()
+            }
+        }
 
-impl<'v> PartialEq<i128> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn eq(&self, other: &i128) -> bool {
-        self.as_i128().map(|t| t.eq(other)).unwrap_or_default()
-    }
-}
+        impl<$lt, $($generics)*> PartialEq<i32> for $value {
+            #[inline]
+            #[must_use]
+            fn eq(&self, other: &i32) -> bool {
+                self.as_i32().map(|t| t.eq(other)).unwrap_or_default()
+            }
+        }
 
-impl<'v> PartialEq<u8> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn eq(&self, other: &u8) -> bool {
-        self.as_u8().map(|t| t.eq(other)).unwrap_or_default()
-    }
-}
+        impl<$lt, $($generics)*> PartialEq<i64> for $value {
+            #[inline]
+            #[must_use]
+            fn eq(&self, other: &i64) -> bool {
+                self.as_i64().map(|t| t.eq(other)).unwrap_or_default()
+            }
+        }
 
-impl<'v> PartialEq<u16> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn eq(&self, other: &u16) -> bool {
-        self.as_u16().map(|t| t.eq(other)).unwrap_or_default()
-    }
-}
+        impl<$lt, $($generics)*> PartialEq<i128> for $value {
+            #[inline]
+            #[must_use]
+            fn eq(&self, other: &i128) -> bool {
+                self.as_i128().map(|t| t.eq(other)).unwrap_or_default()
+            }
+        }
 
-impl<'v> PartialEq<u32> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn eq(&self, other: &u32) -> bool {
-        self.as_u32().map(|t| t.eq(other)).unwrap_or_default()
-    }
-}
+        impl<$lt, $($generics)*> PartialEq<u8> for $value {
+            #[inline]
+            #[must_use]
+            fn eq(&self, other: &u8) -> bool {
+                self.as_u8().map(|t| t.eq(other)).unwrap_or_default()
+            }
+        }
 
-impl<'v> PartialEq<u64> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn eq(&self, other: &u64) -> bool {
-        self.as_u64().map(|t| t.eq(other)).unwrap_or_default()
-    }
-}
+        impl<$lt, $($generics)*> PartialEq<u16> for $value {
+            #[inline]
+            #[must_use]
+            fn eq(&self, other: &u16) -> bool {
+                self.as_u16().map(|t| t.eq(other)).unwrap_or_default()
+            }
+        }
 
-impl<'v> PartialEq<usize> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn eq(&self, other: &usize) -> bool {
-        self.as_usize().map(|t| t.eq(other)).unwrap_or_default()
-    }
-}
+        impl<$lt, $($generics)*> PartialEq<u32> for $value {
+            #[inline]
+            #[must_use]
+            fn eq(&self, other: &u32) -> bool {
+                self.as_u32().map(|t| t.eq(other)).unwrap_or_default()
+            }
+        }
 
-impl<'v> PartialEq<u128> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn eq(&self, other: &u128) -> bool {
-        self.as_u128().map(|t| t.eq(other)).unwrap_or_default()
-    }
-}
+        impl<$lt, $($generics)*> PartialEq<u64> for $value {
+            #[inline]
+            #[must_use]
+            fn eq(&self, other: &u64) -> bool {
+                self.as_u64().map(|t| t.eq(other)).unwrap_or_default()
+            }
+        }
 
-impl<'v> PartialEq<f32> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn eq(&self, other: &f32) -> bool {
-        self.as_f32().map(|t| t.eq(other)).unwrap_or_default()
-    }
-}
-impl<'v> PartialEq<f64> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn eq(&self, other: &f64) -> bool {
-        self.as_f64().map(|t| t.eq(other)).unwrap_or_default()
-    }
+        impl<$lt, $($generics)*> PartialEq<usize> for $value {
+            #[inline]
+            #[must_use]
+            fn eq(&self, other: &usize) -> bool {
+                self.as_usize().map(|t| t.eq(other)).unwrap_or_default()
+            }
+        }
+
+        impl<$lt, $($generics)*> PartialEq<u128> for $value {
+            #[inline]
+            #[must_use]
+            fn eq(&self, other: &u128) -> bool {
+                self.as_u128().map(|t| t.eq(other)).unwrap_or_default()
+            }
+        }
+
+        impl<$lt, $($generics)*> PartialEq<f32> for $value {
+            #[inline]
+            #[must_use]
+            fn eq(&self, other: &f32) -> bool {
+                self.as_f32().map(|t| t.eq(other)).unwrap_or_default()
+            }
+        }
+        impl<$lt, $($generics)*> PartialEq<f64> for $value {
+            #[inline]
+            #[must_use]
+            fn eq(&self, other: &f64) -> bool {
+                self.as_f64().map(|t| t.eq(other)).unwrap_or_default()
+            }
+        }
+    };
 }
+
+cmp_impls!('v, [], Value<'v>);
+cmp_impls!('v, [M: MapBackend,], GenericValue<'v, M>);
@@ -1,8 +1,11 @@
-use super::{Object, Value};
+use super::{GenericValue, Object, Value};
 use crate::cow::Cow;
+use crate::value::map::{MapBackend, ObjectMap};
 use crate::OwnedValue;
 use crate::StaticNode;
-use std::iter::FromIterator;
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::iter::FromIterator;
+use value_trait::Object as _;
 
 impl<'a> From<OwnedValue> for Value<'a> {
     #[inline]
@@ -13,232 +16,281 @@ impl<'a> From<OwnedValue> for Value<'a> {
             OwnedValue::String(s) => Value::from(s),
             OwnedValue::Array(a) => a.into_iter().collect(),
             OwnedValue::Object(m) => m.into_iter().collect(),
+            #[cfg(feature = "bytes")]
+            OwnedValue::Bytes(b) => Value::Bytes(b.into()),
         }
     }
 }
 
-impl<'v> From<StaticNode> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn from(s: StaticNode) -> Self {
-        Self::Static(s)
-    }
-}
+/// The conversions `Value` and `GenericValue` share
+macro_rules! from_impls {
+    ($lt:lifetime, [$($generics:tt)*], $value:ty, $object:ty) => {
+        impl<$lt, $($generics)*> From<StaticNode> for $value {
+            #[inline]
+            #[must_use]
+            fn from(s: StaticNode) -> Self {
+                Self::Static(s)
+            }
+        }
 
-impl<'v, T> From<Option<T>> for Value<'v>
-where
-    Value<'v>: From<T>,
-{
-    #[inline]
-    #[must_use]
-    fn from(s: Option<T>) -> Self {
-        if let Some(v) = s {
-            Value::from(v)
-        } else {
-            Value::Static(StaticNode::Null)
+        impl<$lt, $($generics)* T> From<Option<T>> for $value
+        where
+            $value: From<T>,
+        {
+            #[inline]
+            #[must_use]
+            fn from(s: Option<T>) -> Self {
+                if let Some(v) = s {
+                    Self::from(v)
+                } else {
+                    Self::Static(StaticNode::Null)
+                }
+            }
+        }
+        /********* str_ **********/
+        impl<$lt, $($generics)*> From<&$lt str> for $value {
+            #[inline]
+            #[must_use]
+            fn from(s: &$lt str) -> Self {
+                Self::String(Cow::from(s))
+            }
         }
-    }
-}
-/********* str_ **********/
-impl<'v> From<&'v str> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn from(s: &'v str) -> Self {
-        Value::String(Cow::from(s))
-    }
-}
 
-#[cfg(feature = "beef")]
-impl<'v> From<std::borrow::Cow<'v, str>> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn from(c: std::borrow::Cow<'v, str>) -> Self {
-        Value::String(c.into())
-    }
-}
+        #[cfg(feature = "beef")]
+        impl<$lt, $($generics)*> From<alloc::borrow::Cow<$lt, str>> for $value {
+            #[inline]
+            #[must_use]
+            fn from(c: alloc::borrow::Cow<$lt, str>) -> Self {
+                Self::String(c.into())
+            }
+        }
 
-#[cfg(not(feature = "beef"))]
-impl<'v> From<std::borrow::Cow<'v, str>> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn from(c: std::borrow::Cow<'v, str>) -> Self {
-        Value::String(c)
-    }
-}
+        #[cfg(not(feature = "beef"))]
+        impl<$lt, $($generics)*> From<alloc::borrow::Cow<$lt, str>> for $value {
+            #[inline]
+            #[must_use]
+            fn from(c: alloc::borrow::Cow<$lt, str>) -> Self {
+                Self::String(c)
+            }
+        }
 
-#[cfg(feature = "beef")]
-impl<'v> From<beef::lean::Cow<'v, str>> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn from(c: beef::lean::Cow<'v, str>) -> Self {
-        Self::String(c.into())
-    }
-}
+        #[cfg(feature = "beef")]
+        impl<$lt, $($generics)*> From<beef::lean::Cow<$lt, str>> for $value {
+            #[inline]
+            #[must_use]
+            fn from(c: beef::lean::Cow<$lt, str>) -> Self {
+                Self::String(c.into())
+            }
+        }
 
-impl<'v> From<String> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn from(s: String) -> Self {
-        Value::String(s.into())
-    }
-}
+        impl<$lt, $($generics)*> From<String> for $value {
+            #[inline]
+            #[must_use]
+            fn from(s: String) -> Self {
+                Self::String(s.into())
+            }
+        }
 
-/********* atoms **********/
-impl<'v> From<bool> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn from(b: bool) -> Self {
-        Value::Static(StaticNode::Bool(b))
-    }
-}
-impl<'v> From<()> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn from(_b: ()) -> Self {
-        Value::Static(StaticNode::Null)
-    }
-}
+        /********* atoms **********/
+        impl<$lt, $($generics)*> From<bool> for $value {
+            #[inline]
+            #[must_use]
+            fn from(b: bool) -> Self {
+                Self::Static(StaticNode::Bool(b))
+            }
+        }
+        impl<$lt, $($generics)*> From<()> for $value {
+            #[inline]
+            #[must_use]
+            fn from(_b: ()) -> Self {
+                Self::Static(StaticNode::Null)
+            }
+        }
 
-/********* i_ **********/
-impl<'v> From<i8> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn from(i: i8) -> Self {
-        Value::Static(StaticNode::I64(i64::from(i)))
-    }
-}
+        /********* i_ **********/
+        impl<$lt, $($generics)*> From<i8> for $value {
+            #[inline]
+            #[must_use]
+            fn from(i: i8) -> Self {
+                Self::Static(StaticNode::I64(i64::from(i)))
+            }
+        }
 
-impl<'v> From<i16> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn from(i: i16) -> Self {
-        Value::Static(StaticNode::I64(i64::from(i)))
-    }
-}
+        impl<$lt, $($generics)*> From<i16> for $value {
+            #[inline]
+            #[must_use]
+            fn from(i: i16) -> Self {
+                Self::Static(StaticNode::I64(i64::from(i)))
+            }
+        }
 
-impl<'v> From<i32> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn from(i: i32) -> Self {
-        Value::Static(StaticNode::I64(i64::from(i)))
-    }
-}
+        impl<$lt, $($generics)*> From<i32> for $value {
+            #[inline]
+            #[must_use]
+            fn from(i: i32) -> Self {
+                Self::Static(StaticNode::I64(i64::from(i)))
+            }
+        }
 
-impl<'v> From<i64> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn from(i: i64) -> Self {
-        Value::Static(StaticNode::I64(i))
-    }
-}
+        impl<$lt, $($generics)*> From<i64> for $value {
+            #[inline]
+            #[must_use]
+            fn from(i: i64) -> Self {
+                Self::Static(StaticNode::I64(i))
+            }
+        }
 
-#[cfg(feature = "128bit")]
-impl<'v> From<i128> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn from(i: i128) -> Self {
-        Value::Static(StaticNode::I128(i))
-    }
-}
+        #[cfg(feature = "128bit")]
+        impl<$lt, $($generics)*> From<i128> for $value {
+            #[inline]
+            #[must_use]
+            fn from(i: i128) -> Self {
+                Self::Static(StaticNode::I128(i))
+            }
+        }
 
-/********* u_ **********/
-impl<'v> From<u8> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn from(i: u8) -> Self {
-        Self::Static(StaticNode::U64(u64::from(i)))
-    }
-}
+        /********* u_ **********/
+        impl<$lt, $($generics)*> From<u8> for $value {
+            #[inline]
+            #[must_use]
+            fn from(i: u8) -> Self {
+                Self::Static(StaticNode::U64(u64::from(i)))
+            }
+        }
 
-impl<'v> From<u16> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn from(i: u16) -> Self {
-        Self::Static(StaticNode::U64(u64::from(i)))
-    }
-}
+        impl<$lt, $($generics)*> From<u16> for $value {
+            #[inline]
+            #[must_use]
+            fn from(i: u16) -> Self {
+                Self::Static(StaticNode::U64(u64::from(i)))
+            }
+        }
 
-impl<'v> From<u32> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn from(i: u32) -> Self {
-        Self::Static(StaticNode::U64(u64::from(i)))
-    }
-}
+        impl<$lt, $($generics)*> From<u32> for $value {
+            #[inline]
+            #[must_use]
+            fn from(i: u32) -> Self {
+                Self::Static(StaticNode::U64(u64::from(i)))
+            }
+        }
 
-impl<'v> From<u64> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn from(i: u64) -> Self {
-        Value::Static(StaticNode::U64(i))
-    }
-}
+        impl<$lt, $($generics)*> From<u64> for $value {
+            #[inline]
+            #[must_use]
+            fn from(i: u64) -> Self {
+                Self::Static(StaticNode::U64(i))
+            }
+        }
 
-#[cfg(feature = "128bit")]
-impl<'v> From<u128> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn from(i: u128) -> Self {
-        Value::Static(StaticNode::U128(i))
-    }
-}
+        #[cfg(feature = "128bit")]
+        impl<$lt, $($generics)*> From<u128> for $value {
+            #[inline]
+            #[must_use]
+            fn from(i: u128) -> Self {
+                Self::Static(StaticNode::U128(i))
+            }
+        }
 
-impl<'v> From<usize> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn from(i: usize) -> Self {
-        Self::Static(StaticNode::U64(i as u64))
-    }
-}
+        impl<$lt, $($generics)*> From<usize> for $value {
+            #[inline]
+            #[must_use]
+            fn from(i: usize) -> Self {
+                Self::Static(StaticNode::U64(i as u64))
+            }
+        }
 
-/********* f_ **********/
-impl<'v> From<f32> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn from(f: f32) -> Self {
-        Value::Static(StaticNode::F64(f64::from(f)))
-    }
-}
+        /********* f_ **********/
+        impl<$lt, $($generics)*> From<f32> for $value {
+            #[inline]
+            #[must_use]
+            fn from(f: f32) -> Self {
+                Self::Static(StaticNode::F64(f64::from(f)))
+            }
+        }
 
-impl<'v> From<f64> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn from(f: f64) -> Self {
-        Value::Static(StaticNode::F64(f))
-    }
-}
+        impl<$lt, $($generics)*> From<f64> for $value {
+            #[inline]
+            #[must_use]
+            fn from(f: f64) -> Self {
+                Self::Static(StaticNode::F64(f))
+            }
+        }
 
-impl<'v, S> From<Vec<S>> for Value<'v>
-where
-    Value<'v>: From<S>,
-{
-    #[inline]
-    #[must_use]
-    fn from(v: Vec<S>) -> Self {
-        v.into_iter().collect()
-    }
-}
+        impl<$lt, $($generics)* S> From<Vec<S>> for $value
+        where
+            $value: From<S>,
+        {
+            #[inline]
+            #[must_use]
+            fn from(v: Vec<S>) -> Self {
+                v.into_iter().collect()
+            }
+        }
 
-impl<'v, V: Into<Value<'v>>> FromIterator<V> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn from_iter<I: IntoIterator<Item = V>>(iter: I) -> Self {
-        Value::Array(iter.into_iter().map(Into::into).collect())
-    }
-}
+        impl<$lt, $($generics)* V: Into<$value>> FromIterator<V> for $value {
+            #[inline]
+            #[must_use]
+            fn from_iter<I: IntoIterator<Item = V>>(iter: I) -> Self {
+                Self::Array(iter.into_iter().map(Into::into).collect())
+            }
+        }
 
-impl<'v, K: Into<Cow<'v, str>>, V: Into<Value<'v>>> FromIterator<(K, V)> for Value<'v> {
-    #[inline]
-    #[must_use]
-    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
-        Value::Object(Box::new(
-            iter.into_iter()
-                .map(|(k, v)| (Into::into(k), Into::into(v)))
-                .collect(),
-        ))
-    }
+        impl<$lt, $($generics)* K: Into<Cow<$lt, str>>, V: Into<$value>> FromIterator<(K, V)>
+            for $value
+        {
+            #[inline]
+            #[must_use]
+            fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+                let iter = iter.into_iter();
+                let mut res: $object = ObjectMap::with_capacity(iter.size_hint().0);
+                for (k, v) in iter {
+                    let _ = res.insert(k.into(), v.into());
+                }
+                Self::Object(Box::new(res))
+            }
+        }
+
+        /// Pushes the values onto an array. A value that isn't an array is
+        /// replaced by the array `collect` makes of them.
+        impl<$lt, $($generics)* V: Into<$value>> Extend<V> for $value {
+            #[inline]
+            fn extend<I: IntoIterator<Item = V>>(&mut self, iter: I) {
+                match self {
+                    Self::Array(a) => a.extend(iter.into_iter().map(Into::into)),
+                    _ => *self = iter.into_iter().collect(),
+                }
+            }
+        }
+
+        /// Inserts the entries into an object, replacing existing keys. A value
+        /// that isn't an object is replaced by the object `collect` makes of
+        /// them.
+        impl<$lt, $($generics)* K: Into<Cow<$lt, str>>, V: Into<$value>> Extend<(K, V)>
+            for $value
+        {
+            #[inline]
+            fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+                match self {
+                    Self::Object(o) => {
+                        for (k, v) in iter {
+                            let _ = o.insert(k.into(), v.into());
+                        }
+                    }
+                    _ => *self = <Self as FromIterator<(K, V)>>::from_iter(iter),
+                }
+            }
+        }
+    };
 }
 
+from_impls!('v, [], Value<'v>, Object<'v>);
+from_impls!(
+    'v,
+    [M: MapBackend,],
+    GenericValue<'v, M>,
+    M::Map<Cow<'v, str>, GenericValue<'v, M>>
+);
+
 impl<'v> From<Object<'v>> for Value<'v> {
     #[inline]
     #[must_use]
@@ -4,218 +4,138 @@
 //
 // https://github.com/maciejhirsz/json-rust/blob/master/src/codegen.rs
 
-use super::{Object, Value};
+use super::{GenericValue, Value};
 use crate::prelude::*;
-use crate::stry;
-use crate::StaticNode;
+use crate::value::encoder::{append_to, into_string, write_json, FmtWriter, OptionsGenerator};
+use crate::value::map::MapBackend;
+use crate::EncodeOptions;
+use core::fmt;
 use std::io;
 use std::io::Write;
 use value_trait::generator::{
-    BaseGenerator, DumpGenerator, PrettyGenerator, PrettyWriterGenerator, WriterGenerator,
+    DumpGenerator, PrettyGenerator, PrettyWriterGenerator, WriterGenerator,
 };
 
 //use util::print_dec;
 
-impl<'value> Writable for Value<'value> {
-    #[inline]
-    fn encode(&self) -> String {
-        let mut g = DumpGenerator::new();
-        let _ = g.write_json(&self);
-        g.consume()
-    }
-
-    #[inline]
-    fn encode_pp(&self) -> String {
-        let mut g = PrettyGenerator::new(2);
-        let _ = g.write_json(&self);
-        g.consume()
-    }
-
-    #[inline]
-    fn write<'writer, W>(&self, w: &mut W) -> io::Result<()>
-    where
-        W: 'writer + Write,
-    {
-        let mut g = WriterGenerator::new(w);
-        g.write_json(self)
-    }
-
-    #[inline]
-    fn write_pp<'writer, W>(&self, w: &mut W) -> io::Result<()>
-    where
-        W: 'writer + Write,
-    {
-        let mut g = PrettyWriterGenerator::new(w, 2);
-        g.write_json(self)
-    }
-}
-
-trait Generator: BaseGenerator {
-    type T: Write;
-
-    #[inline(always)]
-    fn write_object(&mut self, object: &Object) -> io::Result<()> {
-        if object.is_empty() {
-            self.write(b"{}")
-        } else {
-            let mut iter = object.iter();
-            self.write(b"{")
-                .and_then(|_| {
-                    // We know this exists since it's not empty
-                    let (key, value) = iter.next().unwrap();
-                    self.indent();
-                    self.new_line()
-                        .and_then(|_| self.write_simple_string(key))
-                        .and_then(|_| self.write_min(b": ", b':'))
-                        .and_then(|_| self.write_json(value))
-                })
-                .and_then(|_| {
-                    for (key, value) in iter {
-                        stry!(self
-                            .write(b",")
-                            .and_then(|_| self.new_line())
-                            .and_then(|_| self.write_simple_string(key))
-                            .and_then(|_| self.write_min(b": ", b':'))
-                            .and_then(|_| self.write_json(value)));
-                    }
-                    self.dedent();
-                    self.new_line().and_then(|_| self.write(b"}"))
-                })
-        }
-    }
+/// `Value` and `GenericValue` are written with the same generators
+macro_rules! writable_impls {
+    ([$($generics:tt)*], $value:ty) => {
+        impl<$($generics)*> Writable for $value {
+            #[inline]
+            fn encode(&self) -> String {
+                let mut g = DumpGenerator::<Value>::new();
+                let _ = write_json(&mut g, self);
+                g.consume()
+            }
 
-    #[inline(always)]
-    fn write_json(&mut self, json: &Value) -> io::Result<()> {
-        match *json {
-            Value::Static(StaticNode::Null) => self.write(b"null"),
-            Value::Static(StaticNode::I64(number)) => self.write_int(number),
-            #[cfg(feature = "128bit")]
-            Value::Static(StaticNode::I128(number)) => self.write_int(number),
-            Value::Static(StaticNode::U64(number)) => self.write_int(number),
-            #[cfg(feature = "128bit")]
-            Value::Static(StaticNode::U128(number)) => self.write_int(number),
-            Value::Static(StaticNode::F64(number)) => self.write_float(number),
-            Value::Static(StaticNode::Bool(true)) => self.write(b"true"),
-            Value::Static(StaticNode::Bool(false)) => self.write(b"false"),
-            Value::String(ref string) => self.write_string(string),
-            Value::Array(ref array) => {
-                if array.is_empty() {
-                    self.write(b"[]")
-                } else {
-                    let mut iter = <[Value]>::iter(array);
-                    // We know we have one item
+            #[inline]
+            fn encode_pp(&self) -> String {
+                let mut g = PrettyGenerator::<Value>::new(2);
+                let _ = write_json(&mut g, self);
+                g.consume()
+            }
 
-                    let item = iter.next().unwrap();
-                    self.write(b"[")
-                        .and_then(|_| {
-                            self.indent();
-                            self.new_line().and_then(|_| self.write_json(item))
-                        })
-                        .and_then(|_| {
-                            for item in iter {
-                                stry!(self
-                                    .write(b",")
-                                    .and_then(|_| self.new_line())
-                                    .and_then(|_| self.write_json(item)));
-                            }
+            #[inline]
+            fn write<'writer, W>(&self, w: &mut W) -> io::Result<()>
+            where
+                W: 'writer + Write,
+            {
+                let mut g = WriterGenerator::<_, Value>::new(w);
+                write_json(&mut g, self)
+            }
 
-                            self.dedent();
-                            self.new_line().and_then(|_| self.write(b"]"))
-                        })
-                }
+            #[inline]
+            fn write_pp<'writer, W>(&self, w: &mut W) -> io::Result<()>
+            where
+                W: 'writer + Write,
+            {
+                let mut g = PrettyWriterGenerator::<_, Value>::new(w, 2);
+                write_json(&mut g, self)
             }
-            Value::Object(ref object) => self.write_object(object),
         }
-    }
+    };
 }
 
-trait FastGenerator: BaseGenerator {
-    type T: Write;
+writable_impls!([], Value<'_>);
+writable_impls!([M: MapBackend], GenericValue<'_, M>);
 
-    #[inline(always)]
-    fn write_object(&mut self, object: &Object) -> io::Result<()> {
-        if object.is_empty() {
-            self.write(b"{}")
-        } else {
-            let mut iter = object.iter();
-            self.write(b"{")
-                .and_then(|_| {
-                    // We know this exists since it's not empty
-                    let (key, value) = iter.next().unwrap();
-                    self.write_simple_string(key)
-                        .and_then(|_| self.write(b":"))
-                        .and_then(|_| self.write_json(value))
-                })
-                .and_then(|_| {
-                    for (key, value) in iter {
-                        stry!(self
-                            .write(b",")
-                            .and_then(|_| self.write_simple_string(key))
-                            .and_then(|_| self.write(b":"))
-                            .and_then(|_| self.write_json(value)));
-                    }
-                    self.write(b"}")
-                })
-        }
+impl<'value> Value<'value> {
+    /// Encodes the value into a string honouring the given `options`
+    #[must_use]
+    pub fn encode_with(&self, options: EncodeOptions) -> String {
+        let mut g = OptionsGenerator::new(Vec::with_capacity(1024), options);
+        let _ = write_json(&mut g, self);
+        into_string(g.consume())
     }
 
-    #[inline(always)]
-    fn write_json(&mut self, json: &Value) -> io::Result<()> {
-        match *json {
-            Value::Static(StaticNode::Null) => self.write(b"null"),
-            Value::Static(StaticNode::I64(number)) => self.write_int(number),
-            #[cfg(feature = "128bit")]
-            Value::Static(StaticNode::I128(number)) => self.write_int(number),
-            Value::Static(StaticNode::U64(number)) => self.write_int(number),
-            #[cfg(feature = "128bit")]
-            Value::Static(StaticNode::U128(number)) => self.write_int(number),
-            Value::Static(StaticNode::F64(number)) => self.write_float(number),
-            Value::Static(StaticNode::Bool(true)) => self.write(b"true"),
-            Value::Static(StaticNode::Bool(false)) => self.write(b"false"),
-            Value::String(ref string) => self.write_string(string),
-            Value::Array(ref array) => {
-                if array.is_empty() {
-                    self.write(b"[]")
-                } else {
-                    let mut iter = <[Value]>::iter(array);
-                    // We know we have one item
-                    let item = iter.next().unwrap();
-
-                    self.write(b"[")
-                        .and_then(|_| self.write_json(item))
-                        .and_then(|_| {
-                            for item in iter {
-                                stry!(self.write(b",").and_then(|_| self.write_json(item)))
-                            }
-                            self.write(b"]")
-                        })
-                }
-            }
-            Value::Object(ref object) => self.write_object(object),
-        }
+    /// Appends the encoded value to `out`, like `encode` but reusing the
+    /// allocation of `out` for producers that write many values. `write`
+    /// does the same for a `Vec<u8>`.
+    ///
+    /// ```rust
+    /// use simd_json::{json, BorrowedValue};
+    /// let mut out = String::with_capacity(64);
+    /// for i in 0..2 {
+    ///     BorrowedValue::from(json!({"n": i})).encode_into(&mut out);
+    ///     out.push('\n');
+    /// }
+    /// assert_eq!(out, "{\"n\":0}\n{\"n\":1}\n");
+    /// ```
+    pub fn encode_into(&self, out: &mut String) {
+        append_to(out, |bytes| {
+            let _ = self.write(bytes);
+        });
     }
-}
-
-impl<'value> FastGenerator for DumpGenerator<Value<'value>> {
-    type T = Vec<u8>;
-}
 
-impl<'value> Generator for PrettyGenerator<Value<'value>> {
-    type T = Vec<u8>;
-}
+    /// Appends the value encoded with `options` to `out`, like
+    /// `encode_with` but reusing the allocation of `out`
+    pub fn encode_with_into(&self, out: &mut String, options: EncodeOptions) {
+        append_to(out, |bytes| {
+            let _ = self.write_with(bytes, options);
+        });
+    }
 
-impl<'w, 'value, W> FastGenerator for WriterGenerator<'w, W, Value<'value>>
-where
-    W: Write,
-{
-    type T = W;
-}
+    /// Writes the value encoded with `options` to a `fmt::Write`, like a
+    /// `fmt::Formatter`, without encoding it into a string first
+    ///
+    /// ```rust
+    /// use simd_json::{json, EncodeOptions, BorrowedValue};
+    /// use std::fmt;
+    /// struct Event(BorrowedValue<'static>);
+    /// impl fmt::Display for Event {
+    ///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    ///         f.write_str("event: ")?;
+    ///         self.0.encode_fmt(f, EncodeOptions::default())
+    ///     }
+    /// }
+    /// let event = Event(json!({"id": 1}).into());
+    /// assert_eq!(event.to_string(), "event: {\"id\":1}");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// if the write fails
+    pub fn encode_fmt<W>(&self, w: &mut W, options: EncodeOptions) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        self.write_with(&mut FmtWriter::new(w), options)
+            .map_err(|_| fmt::Error)
+    }
 
-impl<'w, 'value, W> Generator for PrettyWriterGenerator<'w, W, Value<'value>>
-where
-    W: Write,
-{
-    type T = W;
+    /// Writes the value to `w` honouring the given `options`
+    ///
+    /// # Errors
+    ///
+    /// if the write fails
+    pub fn write_with<W>(&self, w: &mut W, options: EncodeOptions) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let mut g = OptionsGenerator::new(w, options);
+        write_json(&mut g, self)
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,149 @@
+use crate::{Node, StaticNode};
+use alloc::vec::Vec;
+
+/// A dom that can be built from the nodes of a tape. The parsers of the
+/// doms implement this and leave walking the nodes to `build`.
+pub(crate) trait Build<'de> {
+    /// The values of the dom
+    type Value;
+    /// The keys of objects
+    type Key: Default;
+    /// An array that is still being filled
+    type Array;
+    /// An object that is still being filled
+    type Object;
+
+    /// The next node, `build` reads exactly the nodes of one value
+    fn next_node(&mut self) -> Node<'de>;
+
+    /// The value for a static node
+    fn static_node(&mut self, s: StaticNode) -> Self::Value;
+
+    /// The value for a string
+    fn string(&mut self, s: &'de str) -> Self::Value;
+
+    /// The key for a string
+    fn key(&mut self, s: &'de str) -> Self::Key;
+
+    /// An empty array with room for `len` elements
+    fn array(&mut self, len: usize) -> Self::Array;
+
+    /// Adds an element to `array`
+    fn push(&mut self, array: &mut Self::Array, value: Self::Value);
+
+    /// The value for a complete array
+    fn finish_array(&mut self, array: Self::Array) -> Self::Value;
+
+    /// An empty object with room for `len` entries
+    fn object(&mut self, len: usize) -> Self::Object;
+
+    /// Adds an entry to `object`
+    fn insert(&mut self, object: &mut Self::Object, key: Self::Key, value: Self::Value);
+
+    /// The value for a complete object
+    fn finish_object(&mut self, object: Self::Object) -> Self::Value;
+
+    /// The value for the non empty array of `len` elements whose nodes
+    /// come next, for doms that can build some arrays in one go. When
+    /// this returns a value it has to read the nodes of the elements.
+    fn whole_array(&mut self, _len: usize) -> Option<Self::Value> {
+        None
+    }
+}
+
+/// A container that is still being filled by `build`
+enum Frame<A, O, K> {
+    /// The array so far and the number of elements still missing
+    Array(A, usize),
+    /// The object so far, the number of entries still missing and the
+    /// key of the entry we are currently reading the value for
+    Object(O, usize, K),
+}
+
+/// Builds the value whose nodes `b` hands out. We keep the containers
+/// that are being filled on an explicit stack instead of recursing so
+/// deeply nested documents can't overflow the thread stack.
+#[cfg_attr(not(feature = "no-inline"), inline(always))]
+pub(crate) fn build<'de, B: Build<'de>>(b: &mut B) -> B::Value {
+    let mut stack: Vec<Frame<B::Array, B::Object, B::Key>> = Vec::new();
+    loop {
+        let mut value = match b.next_node() {
+            Node::Static(s) => b.static_node(s),
+            Node::String(s) => b.string(s),
+            Node::Array(0, _) => {
+                let res = b.array(0);
+                b.finish_array(res)
+            }
+            Node::Array(len, _) => {
+                if let Some(value) = b.whole_array(len) {
+                    value
+                } else {
+                    let res = b.array(len);
+                    stack.push(Frame::Array(res, len));
+                    continue;
+                }
+            }
+            Node::Object(0, _) => {
+                let res = b.object(0);
+                b.finish_object(res)
+            }
+            Node::Object(len, _) => {
+                let res = b.object(len);
+                let key = next_key(b);
+                stack.push(Frame::Object(res, len, key));
+                continue;
+            }
+        };
+        // Hand the value to its parent, every parent that is complete
+        // with it becomes the value for its own parent in turn.
+        loop {
+            match stack.last_mut() {
+                None => return value,
+                Some(Frame::Array(res, missing)) => {
+                    b.push(res, value);
+                    *missing -= 1;
+                    if *missing > 0 {
+                        break;
+                    }
+                }
+                Some(Frame::Object(res, missing, key)) => {
+                    b.insert(res, core::mem::take(key), value);
+                    *missing -= 1;
+                    if *missing > 0 {
+                        *key = next_key(b);
+                        break;
+                    }
+                }
+            }
+            value = match stack.pop() {
+                Some(Frame::Array(res, _)) => b.finish_array(res),
+                Some(Frame::Object(res, _, _)) => b.finish_object(res),
+                None => unreachable!(),
+            };
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "no-inline"), inline(always))]
+fn next_key<'de, B: Build<'de>>(b: &mut B) -> B::Key {
+    if let Node::String(key) = b.next_node() {
+        b.key(key)
+    } else {
+        unreachable!()
+    }
+}
+
+/// Values that can hold other values, see `dismantle`
+pub(crate) trait Nested: Sized {
+    /// Moves the values this one holds into `out`
+    fn take_children(&mut self, out: &mut Vec<Self>);
+}
+
+/// Drops `value` without recursing into it. The values it holds are
+/// moved onto a stack and dropped once they hold nothing themselves.
+pub(crate) fn dismantle<V: Nested>(value: V) {
+    let mut stack = alloc::vec![value];
+    while let Some(mut value) = stack.pop() {
+        value.take_children(&mut stack);
+    }
+}
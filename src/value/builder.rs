@@ -0,0 +1,238 @@
+use crate::prelude::*;
+use core::hash::Hash;
+
+/// Builds an object one entry at a time, start one with
+/// `OwnedValue::build_object` or `BorrowedValue::build_object`.
+///
+/// ```rust
+/// use simd_json::{json, OwnedValue};
+/// let name: Option<&str> = None;
+/// let admin = true;
+/// let v = OwnedValue::build_object()
+///     .insert("id", 1)
+///     .insert_opt("name", name)
+///     .insert_if(admin, "role", "admin")
+///     .insert_object("meta", |m| m.insert("version", 2))
+///     .insert_array("tags", |a| a.push("a").push("b"))
+///     .finish();
+/// assert_eq!(
+///     v,
+///     json!({"id": 1, "role": "admin", "meta": {"version": 2}, "tags": ["a", "b"]})
+/// );
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct ObjectBuilder<V> {
+    value: V,
+}
+
+impl<'v, V> ObjectBuilder<V>
+where
+    V: Builder<'v> + Mutable,
+    V::Key: Hash + Eq,
+{
+    /// Starts an empty object
+    pub fn new() -> Self {
+        Self { value: V::object() }
+    }
+
+    /// Inserts `value` under `key`, replacing an earlier value of `key`
+    pub fn insert<K, T>(mut self, key: K, value: T) -> Self
+    where
+        K: Into<V::Key>,
+        T: Into<V>,
+    {
+        if let Some(o) = self.value.as_object_mut() {
+            let _ = o.insert(key.into(), value.into());
+        }
+        self
+    }
+
+    /// Inserts `value` under `key` if it is `Some`, `None` leaves the key
+    /// out instead of writing `null`
+    pub fn insert_opt<K, T>(self, key: K, value: Option<T>) -> Self
+    where
+        K: Into<V::Key>,
+        T: Into<V>,
+    {
+        match value {
+            Some(value) => self.insert(key, value),
+            None => self,
+        }
+    }
+
+    /// Inserts `value` under `key` if `condition` holds
+    pub fn insert_if<K, T>(self, condition: bool, key: K, value: T) -> Self
+    where
+        K: Into<V::Key>,
+        T: Into<V>,
+    {
+        if condition {
+            self.insert(key, value)
+        } else {
+            self
+        }
+    }
+
+    /// Inserts the object `f` builds under `key`
+    pub fn insert_object<K, F>(self, key: K, f: F) -> Self
+    where
+        K: Into<V::Key>,
+        F: FnOnce(ObjectBuilder<V>) -> ObjectBuilder<V>,
+    {
+        let value = f(ObjectBuilder::new()).finish();
+        self.insert(key, value)
+    }
+
+    /// Inserts the array `f` builds under `key`
+    pub fn insert_array<K, F>(self, key: K, f: F) -> Self
+    where
+        K: Into<V::Key>,
+        F: FnOnce(ArrayBuilder<V>) -> ArrayBuilder<V>,
+    {
+        let value = f(ArrayBuilder::new()).finish();
+        self.insert(key, value)
+    }
+
+    /// Returns the object built so far
+    #[must_use]
+    pub fn finish(self) -> V {
+        self.value
+    }
+}
+
+impl<'v, V> Default for ObjectBuilder<V>
+where
+    V: Builder<'v> + Mutable,
+    V::Key: Hash + Eq,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Builds an array one element at a time, start one with
+/// `OwnedValue::build_array` or `BorrowedValue::build_array`.
+///
+/// ```rust
+/// use simd_json::{json, BorrowedValue};
+/// let v = BorrowedValue::build_array()
+///     .push(1)
+///     .push_opt(None::<u64>)
+///     .push_if(false, 2)
+///     .push_object(|o| o.insert("a", true))
+///     .push_array(|a| a.push("b"))
+///     .finish();
+/// assert_eq!(v, json!([1, {"a": true}, ["b"]]));
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct ArrayBuilder<V> {
+    value: V,
+}
+
+impl<'v, V> ArrayBuilder<V>
+where
+    V: Builder<'v> + Mutable,
+    V::Key: Hash + Eq,
+{
+    /// Starts an empty array
+    pub fn new() -> Self {
+        Self { value: V::array() }
+    }
+
+    /// Appends `value`
+    pub fn push<T: Into<V>>(mut self, value: T) -> Self {
+        if let Some(a) = self.value.as_array_mut() {
+            a.push(value.into());
+        }
+        self
+    }
+
+    /// Appends `value` if it is `Some`, `None` is left out instead of
+    /// writing `null`
+    pub fn push_opt<T: Into<V>>(self, value: Option<T>) -> Self {
+        match value {
+            Some(value) => self.push(value),
+            None => self,
+        }
+    }
+
+    /// Appends `value` if `condition` holds
+    pub fn push_if<T: Into<V>>(self, condition: bool, value: T) -> Self {
+        if condition {
+            self.push(value)
+        } else {
+            self
+        }
+    }
+
+    /// Appends the object `f` builds
+    pub fn push_object<F>(self, f: F) -> Self
+    where
+        F: FnOnce(ObjectBuilder<V>) -> ObjectBuilder<V>,
+    {
+        let value = f(ObjectBuilder::new()).finish();
+        self.push(value)
+    }
+
+    /// Appends the array `f` builds
+    pub fn push_array<F>(self, f: F) -> Self
+    where
+        F: FnOnce(ArrayBuilder<V>) -> ArrayBuilder<V>,
+    {
+        let value = f(ArrayBuilder::new()).finish();
+        self.push(value)
+    }
+
+    /// Returns the array built so far
+    #[must_use]
+    pub fn finish(self) -> V {
+        self.value
+    }
+}
+
+impl<'v, V> Default for ArrayBuilder<V>
+where
+    V: Builder<'v> + Mutable,
+    V::Key: Hash + Eq,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{BorrowedValue, OwnedValue};
+
+    #[test]
+    fn nested() {
+        let v = OwnedValue::build_object()
+            .insert("a", 1)
+            .insert("a", 2)
+            .insert_opt("b", Some("x"))
+            .insert_opt("c", None::<bool>)
+            .insert_if(false, "d", 3)
+            .insert_array("e", |a| {
+                a.push_object(|o| o.insert("f", ()))
+                    .push_array(|a| a)
+                    .push_opt(Some(4.5))
+            })
+            .finish();
+        assert_eq!(v, json!({"a": 2, "b": "x", "e": [{"f": null}, [], 4.5]}));
+        let b = BorrowedValue::build_object()
+            .insert("a", 2)
+            .insert("b", "x")
+            .insert_array("e", |a| {
+                a.push_object(|o| o.insert("f", ()))
+                    .push(BorrowedValue::build_array().finish())
+                    .push(4.5)
+            })
+            .finish();
+        assert_eq!(b, v);
+        assert_eq!(ArrayBuilder::<OwnedValue>::default().finish(), json!([]));
+        assert_eq!(ObjectBuilder::<OwnedValue>::default().finish(), json!({}));
+    }
+}
@@ -0,0 +1,27 @@
+use alloc::string::String;
+
+/// How deep the documents the tests parse are nested, deep enough that
+/// recursing once per level overflows the stack of a test thread
+pub(crate) const DEPTH: usize = 100_000;
+
+/// `inner` in `depth` levels of `[{"a": ...}]`, so it is `2 * depth`
+/// values deep
+pub(crate) fn nested(depth: usize, inner: &str) -> String {
+    let mut s = "[{\"a\":".repeat(depth);
+    s.push_str(inner);
+    s.push_str(&"}]".repeat(depth));
+    s
+}
+
+/// Follows the first element or the `a` field, as `child` gets them, down
+/// to the innermost value of a `nested` document. Returns it and how
+/// many values deep it is, counting the outermost one.
+pub(crate) fn innermost<V>(v: V, child: impl Fn(&V) -> Option<V>) -> (V, usize) {
+    let mut depth = 1;
+    let mut next = v;
+    while let Some(v) = child(&next) {
+        next = v;
+        depth += 1;
+    }
+    (next, depth)
+}
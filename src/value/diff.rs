@@ -0,0 +1,327 @@
+use super::flatten::{push_key, FlattenStyle};
+use crate::prelude::*;
+use crate::value::Dom;
+use crate::StaticNode;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt::{self, Write};
+
+/// Longest value shown in a `Difference` before it is cut off
+const MAX_SHOWN: usize = 80;
+
+/// Most differences `assert_json_eq!` lists before it only counts them
+const MAX_LISTED: usize = 20;
+
+/// One place where two values differ, found by `diff`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    path: String,
+    expected: Option<String>,
+    actual: Option<String>,
+}
+
+impl Difference {
+    /// The JSON pointer of the place the values differ at, empty for the
+    /// values themselves
+    #[must_use]
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// What the expected value has there as JSON, `None` if it has
+    /// nothing there
+    #[must_use]
+    pub fn expected(&self) -> Option<&str> {
+        self.expected.as_deref()
+    }
+
+    /// What the actual value has there as JSON, `None` if it has nothing
+    /// there
+    #[must_use]
+    pub fn actual(&self) -> Option<&str> {
+        self.actual.as_deref()
+    }
+}
+
+#[cfg_attr(tarpaulin, skip)]
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "(root): ")?;
+        } else {
+            write!(f, "{}: ", self.path)?;
+        }
+        match (&self.expected, &self.actual) {
+            (Some(e), Some(a)) => write!(f, "expected {}, got {}", shorten(e), shorten(a)),
+            (Some(e), None) => write!(f, "missing, expected {}", shorten(e)),
+            (None, Some(a)) => write!(f, "unexpected {}", shorten(a)),
+            (None, None) => write!(f, "no difference"),
+        }
+    }
+}
+
+fn shorten(json: &str) -> String {
+    match json.char_indices().nth(MAX_SHOWN) {
+        Some((end, _)) => alloc::format!("{}...", &json[..end]),
+        None => json.to_string(),
+    }
+}
+
+/// Compares two values structurally and lists every place they differ,
+/// as JSON pointers with what each value has there. Objects are compared
+/// key by key, with their keys in sorted order, arrays element by element.
+/// Owned and borrowed values can be compared with each other. This is
+/// what `assert_json_eq!` reports when values aren't equal.
+///
+/// ```rust
+/// use simd_json::json;
+/// let expected = json!({"id": 1, "tags": ["a", "b"], "name": "x"});
+/// let actual = json!({"id": 2, "tags": ["a"], "extra": null, "name": "x"});
+/// let diff: Vec<String> = simd_json::diff(&expected, &actual)
+///     .iter()
+///     .map(ToString::to_string)
+///     .collect();
+/// assert_eq!(
+///     diff,
+///     [
+///         "/extra: unexpected null",
+///         "/id: expected 1, got 2",
+///         "/tags/1: missing, expected \"b\"",
+///     ]
+/// );
+/// ```
+pub fn diff<E, A>(expected: &E, actual: &A) -> Vec<Difference>
+where
+    E: Dom + Writable,
+    E::Key: Borrow<str>,
+    A: Dom + Writable,
+    A::Key: Borrow<str>,
+{
+    let mut differences = Vec::new();
+    // pairs still to compare, the first one is popped first
+    let mut stack = alloc::vec![(String::new(), Some(expected), Some(actual))];
+    while let Some((path, expected, actual)) = stack.pop() {
+        let (expected, actual) = match (expected, actual) {
+            (Some(e), Some(a)) => (e, a),
+            (e, a) => {
+                differences.push(Difference {
+                    path,
+                    expected: e.map(Writable::encode),
+                    actual: a.map(Writable::encode),
+                });
+                continue;
+            }
+        };
+        let start = stack.len();
+        if let (Some(e), Some(a)) = (expected.as_object(), actual.as_object()) {
+            let mut keys: Vec<&str> = e
+                .keys()
+                .map(Borrow::borrow)
+                .chain(a.keys().map(Borrow::borrow))
+                .collect();
+            keys.sort_unstable();
+            keys.dedup();
+            for key in keys {
+                let mut child = path.clone();
+                push_key(&mut child, key, FlattenStyle::Pointer);
+                stack.push((child, e.get(key), a.get(key)));
+            }
+        } else if let (Some(e), Some(a)) = (expected.as_array(), actual.as_array()) {
+            for idx in 0..e.len().max(a.len()) {
+                let child = alloc::format!("{}/{}", path, idx);
+                stack.push((child, e.get(idx), a.get(idx)));
+            }
+        } else if !same_leaf(expected, actual) {
+            differences.push(Difference {
+                path,
+                expected: Some(expected.encode()),
+                actual: Some(actual.encode()),
+            });
+        }
+        stack[start..].reverse();
+    }
+    differences
+}
+
+/// Compares values that aren't both arrays or both objects
+fn same_leaf<E, A>(expected: &E, actual: &A) -> bool
+where
+    E: Dom,
+    A: Dom,
+{
+    #[cfg(feature = "bytes")]
+    {
+        if expected.bytes().is_some() || actual.bytes().is_some() {
+            return expected.bytes() == actual.bytes();
+        }
+    }
+    if let (Some(e), Some(a)) = (expected.as_str(), actual.as_str()) {
+        return e == a;
+    }
+    match (static_node(expected), static_node(actual)) {
+        (Some(e), Some(a)) => e == a,
+        _ => false,
+    }
+}
+
+fn static_node<V: Dom>(value: &V) -> Option<StaticNode> {
+    match value.value_type() {
+        ValueType::Null => Some(StaticNode::Null),
+        ValueType::Bool => value.as_bool().map(StaticNode::Bool),
+        ValueType::I64 => value.as_i64().map(StaticNode::I64),
+        ValueType::U64 => value.as_u64().map(StaticNode::U64),
+        #[cfg(feature = "128bit")]
+        ValueType::I128 => value.as_i128().map(StaticNode::I128),
+        #[cfg(feature = "128bit")]
+        ValueType::U128 => value.as_u128().map(StaticNode::U128),
+        ValueType::F64 => value.as_f64().map(StaticNode::F64),
+        ValueType::String | ValueType::Array | ValueType::Object => None,
+        #[cfg(feature = "bytes")]
+        ValueType::Custom(_) => None,
+    }
+}
+
+/// Builds the message `assert_json_eq!` panics with, `None` if the values
+/// are the same
+#[doc(hidden)]
+#[must_use]
+pub fn assert_json_eq_message<E, A>(expected: &E, actual: &A) -> Option<String>
+where
+    E: Dom + Writable,
+    E::Key: Borrow<str>,
+    A: Dom + Writable,
+    A::Key: Borrow<str>,
+{
+    let differences = diff(expected, actual);
+    if differences.is_empty() {
+        return None;
+    }
+    let mut message = alloc::format!(
+        "assertion failed: values differ in {} place{}",
+        differences.len(),
+        if differences.len() == 1 { "" } else { "s" }
+    );
+    for d in differences.iter().take(MAX_LISTED) {
+        let _ = write!(message, "\n    {}", d);
+    }
+    if differences.len() > MAX_LISTED {
+        let _ = write!(
+            message,
+            "\n    ... and {} more",
+            differences.len() - MAX_LISTED
+        );
+    }
+    Some(message)
+}
+
+/// Asserts that two values are equal, like `assert_eq!`, but when they
+/// aren't it panics with the places they differ at instead of printing
+/// both values in full. The first value is the actual one, the second the
+/// expected one; they can be owned or borrowed values, in any
+/// combination. An optional message is put in front of the differences.
+///
+/// ```rust,should_panic
+/// use simd_json::{assert_json_eq, json};
+/// let mut d = br#"{"user": {"name": "b", "roles": ["admin"]}}"#.to_vec();
+/// let actual = simd_json::to_borrowed_value(&mut d).unwrap();
+/// // panics with:
+/// // check user: assertion failed: values differ in 2 places
+/// //     /user/name: expected "a", got "b"
+/// //     /user/roles/0: expected "user", got "admin"
+/// assert_json_eq!(
+///     actual,
+///     json!({"user": {"name": "a", "roles": ["user"]}}),
+///     "check {}", "user"
+/// );
+/// ```
+#[macro_export]
+macro_rules! assert_json_eq {
+    ($actual:expr, $expected:expr $(,)?) => {
+        match (&$actual, &$expected) {
+            (actual, expected) => {
+                if let Some(message) = $crate::assert_json_eq_message(expected, actual) {
+                    ::core::panic!("{}", message);
+                }
+            }
+        }
+    };
+    ($actual:expr, $expected:expr, $($arg:tt)+) => {
+        match (&$actual, &$expected) {
+            (actual, expected) => {
+                if let Some(message) = $crate::assert_json_eq_message(expected, actual) {
+                    ::core::panic!("{}: {}", ::core::format_args!($($arg)+), message);
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{BorrowedValue, OwnedValue};
+
+    fn differences(expected: &OwnedValue, actual: &OwnedValue) -> Vec<String> {
+        let borrowed = BorrowedValue::from(actual.clone());
+        let found = diff(expected, &borrowed);
+        assert_eq!(found, diff(expected, actual));
+        found.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn differences_found() {
+        assert!(differences(
+            &json!({"a": [1, {"b": null}]}),
+            &json!({"a": [1, {"b": null}]})
+        )
+        .is_empty());
+        assert_eq!(
+            differences(&json!(1), &json!("1")),
+            ["(root): expected 1, got \"1\""]
+        );
+        assert_eq!(
+            differences(&json!({"a/b": {"c~": 1}}), &json!({"a/b": {"c~": 1.5}})),
+            ["/a~1b/c~0: expected 1, got 1.5"]
+        );
+        assert_eq!(
+            differences(&json!([1, [2, 3]]), &json!([0, [2], 4])),
+            [
+                "/0: expected 1, got 0",
+                "/1/1: missing, expected 3",
+                "/2: unexpected 4"
+            ]
+        );
+        assert_eq!(
+            differences(&json!({"a": []}), &json!({"a": {}})),
+            ["/a: expected [], got {}"]
+        );
+        // equal numbers of different types are the same
+        assert!(differences(&json!(1), &OwnedValue::from(1_u64)).is_empty());
+        let long = "x".repeat(100);
+        assert_eq!(
+            differences(&json!(null), &json!(long)),
+            [format!(
+                "(root): expected null, got \"{}...",
+                "x".repeat(79)
+            )]
+        );
+    }
+
+    #[test]
+    fn assertion() {
+        assert_json_eq!(json!({"a": 1, "b": [true]}), json!({"b": [true], "a": 1}));
+        let message = assert_json_eq_message(&json!((0..30).collect::<Vec<_>>()), &json!([]))
+            .unwrap_or_default();
+        assert!(message.starts_with(
+            "assertion failed: values differ in 30 places\n    /0: missing, expected 0"
+        ));
+        assert!(message.ends_with("\n    ... and 10 more"));
+        let panic = std::panic::catch_unwind(|| assert_json_eq!(json!(1), json!(2), "n={}", 1));
+        let panic = panic.expect_err("values differ");
+        assert_eq!(
+            panic.downcast_ref::<String>().map(String::as_str),
+            Some("n=1: assertion failed: values differ in 1 place\n    (root): expected 2, got 1")
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use crate::value::emit::{emit, Emit, Emitter};
+use crate::{BorrowedValue, OwnedValue, StaticNode};
+use core::convert::Infallible;
+use digest::{Digest, Output};
+
+// Tags that start every value in the canonical form
+const NULL: u8 = b'n';
+const TRUE: u8 = b't';
+const FALSE: u8 = b'f';
+const INT: u8 = b'i';
+#[cfg(feature = "128bit")]
+const UINT: u8 = b'u';
+const FLOAT: u8 = b'd';
+const STRING: u8 = b's';
+const ARRAY: u8 = b'a';
+const OBJECT: u8 = b'o';
+
+/// Largest integer a float can represent along with all smaller ones
+const MAX_EXACT_FLOAT: f64 = 9_007_199_254_740_992.0;
+
+impl OwnedValue {
+    /// Computes a digest of the value that is the same for all documents
+    /// with the same content, no matter the order of their keys or how
+    /// they were formatted. See `BorrowedValue::canonical_hash`.
+    ///
+    /// ```rust
+    /// use sha2::Sha256;
+    /// let a = simd_json::to_owned_value(&mut br#"{"a": 1, "b": [2.0]}"#.to_vec()).unwrap();
+    /// let b = simd_json::to_owned_value(&mut br#"{ "b" : [2], "a" : 1.0 }"#.to_vec()).unwrap();
+    /// assert_eq!(a.canonical_hash::<Sha256>(), b.canonical_hash::<Sha256>());
+    /// ```
+    #[must_use]
+    pub fn canonical_hash<D: Digest>(&self) -> Output<D> {
+        let mut digest = D::new();
+        update(self, &mut digest);
+        digest.finalize()
+    }
+}
+
+impl BorrowedValue<'_> {
+    /// Computes a digest of the value that is the same for all documents
+    /// with the same content, no matter the order of their keys or how
+    /// they were formatted. Borrowed and owned values with the same content
+    /// have the same digest.
+    ///
+    /// The digest is computed over a canonical binary form of the value:
+    /// object keys are sorted by their bytes, strings are length prefixed
+    /// and numbers are compared by value, so `1`, `1.0` and `1e0` are the
+    /// same number, as are `0` and `-0.0`. Floats that aren't integers
+    /// are taken as they are and all `NaN`s are the same. The form is
+    /// stable, digests can be stored and compared across versions.
+    ///
+    /// ```rust
+    /// use sha2::Sha256;
+    /// let mut d = br#"{"id": 1, "tags": ["a", "b"]}"#.to_vec();
+    /// let v = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// let mut d = br#"{"tags": ["b", "a"], "id": 1}"#.to_vec();
+    /// let other = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// assert_ne!(v.canonical_hash::<Sha256>(), other.canonical_hash::<Sha256>());
+    /// ```
+    #[must_use]
+    pub fn canonical_hash<D: Digest>(&self) -> Output<D> {
+        let mut digest = D::new();
+        update(self, &mut digest);
+        digest.finalize()
+    }
+}
+
+/// Feeds the canonical form of the values `emit` walks into a digest
+struct Canonical<'d, D>(&'d mut D);
+
+impl<D: Digest> Emitter for Canonical<'_, D> {
+    type Error = Infallible;
+
+    const SORTED: bool = true;
+
+    fn static_node(&mut self, s: StaticNode) -> Result<(), Infallible> {
+        update_static(s, self.0);
+        Ok(())
+    }
+
+    fn string(&mut self, s: &str) -> Result<(), Infallible> {
+        update_str(s, self.0);
+        Ok(())
+    }
+
+    // bytes are written as base64 strings, they hash like them too
+    #[cfg(feature = "bytes")]
+    fn bytes(&mut self, b: &[u8]) -> Result<(), Infallible> {
+        update_str(&crate::value::to_base64(b), self.0);
+        Ok(())
+    }
+
+    fn start_array(&mut self, len: usize) -> Result<usize, Infallible> {
+        self.0.update([ARRAY]);
+        update_len(len, self.0);
+        Ok(len)
+    }
+
+    fn start_object(&mut self, len: usize) -> Result<usize, Infallible> {
+        self.0.update([OBJECT]);
+        update_len(len, self.0);
+        Ok(len)
+    }
+
+    fn key(&mut self, key: &str, _first: bool) -> Result<(), Infallible> {
+        update_str(key, self.0);
+        Ok(())
+    }
+}
+
+/// Feeds the canonical form of `value` into `digest`
+fn update<V: Emit, D: Digest>(value: &V, digest: &mut D) {
+    let _ = emit(&mut Canonical(digest), value);
+}
+
+fn update_len<D: Digest>(len: usize, digest: &mut D) {
+    digest.update((len as u64).to_be_bytes());
+}
+
+fn update_str<D: Digest>(s: &str, digest: &mut D) {
+    digest.update([STRING]);
+    update_len(s.len(), digest);
+    digest.update(s.as_bytes());
+}
+
+fn update_int<D: Digest>(i: i128, digest: &mut D) {
+    digest.update([INT]);
+    digest.update(i.to_be_bytes());
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::float_cmp)]
+fn update_static<D: Digest>(s: StaticNode, digest: &mut D) {
+    match s {
+        StaticNode::Null => digest.update([NULL]),
+        StaticNode::Bool(true) => digest.update([TRUE]),
+        StaticNode::Bool(false) => digest.update([FALSE]),
+        StaticNode::I64(i) => update_int(i128::from(i), digest),
+        StaticNode::U64(u) => update_int(i128::from(u), digest),
+        #[cfg(feature = "128bit")]
+        StaticNode::I128(i) => update_int(i, digest),
+        #[cfg(feature = "128bit")]
+        StaticNode::U128(u) => {
+            if let Ok(i) = core::convert::TryFrom::try_from(u) {
+                update_int(i, digest);
+            } else {
+                digest.update([UINT]);
+                digest.update(u.to_be_bytes());
+            }
+        }
+        // integral floats that are exact are the same as the integer
+        StaticNode::F64(f) if f.trunc() == f && f.abs() <= MAX_EXACT_FLOAT => {
+            update_int(f as i128, digest);
+        }
+        StaticNode::F64(f) => {
+            let f = if f.is_nan() { f64::NAN } else { f };
+            digest.update([FLOAT]);
+            digest.update(f.to_bits().to_be_bytes());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{to_borrowed_value, to_owned_value};
+    use sha2::Sha256;
+
+    fn digest(s: &str) -> Vec<u8> {
+        let owned = to_owned_value(&mut s.as_bytes().to_vec())
+            .unwrap()
+            .canonical_hash::<Sha256>();
+        let mut d = s.as_bytes().to_vec();
+        let borrowed = to_borrowed_value(&mut d)
+            .unwrap()
+            .canonical_hash::<Sha256>();
+        assert_eq!(owned, borrowed, "{}", s);
+        owned.to_vec()
+    }
+
+    #[test]
+    fn same_content() {
+        let same = [
+            (
+                r#"{"a": 1, "b": {"c": [null, true]}}"#,
+                r#"{"b":{"c":[null,true]},"a":1}"#,
+            ),
+            ("1", "1.0"),
+            ("100", "1e2"),
+            ("0", "-0.0"),
+            ("-5", "-5.0"),
+            ("18446744073709551615", "18446744073709551615"),
+            ("0.5", "5e-1"),
+            (r#""é""#, r#""\u00e9""#),
+        ];
+        for (a, b) in &same {
+            assert_eq!(digest(a), digest(b), "{} {}", a, b);
+        }
+    }
+
+    #[test]
+    fn different_content() {
+        let values = [
+            "null",
+            "true",
+            "false",
+            "0",
+            "1",
+            "-1",
+            "0.5",
+            "\"\"",
+            "\"1\"",
+            "[]",
+            "{}",
+            "[null]",
+            "[[]]",
+            r#"["a", "b"]"#,
+            r#"["ab"]"#,
+            r#"{"a": "b"}"#,
+            r#"{"b": "a"}"#,
+            r#"{"a": {}}"#,
+            r#"{"a": []}"#,
+            "[1, 2]",
+            "[2, 1]",
+            "9007199254740993",
+            "9007199254740993.0",
+            "1e300",
+        ];
+        let mut digests: Vec<_> = values.iter().map(|v| digest(v)).collect();
+        digests.sort();
+        digests.dedup();
+        assert_eq!(digests.len(), values.len());
+    }
+
+    #[test]
+    fn deep_nesting() {
+        use crate::value::deep::{nested, DEPTH};
+        let mut d = nested(DEPTH, "null").into_bytes();
+        let v = to_borrowed_value(&mut d).unwrap();
+        let mut d = nested(1, "null").into_bytes();
+        let shallow = to_borrowed_value(&mut d).unwrap();
+        assert_ne!(
+            v.canonical_hash::<Sha256>(),
+            shallow.canonical_hash::<Sha256>()
+        );
+        v.dismantle();
+    }
+
+    #[test]
+    fn stable() {
+        use std::fmt::Write;
+        // the canonical form must not change between versions
+        let mut hex = String::new();
+        for b in digest(r#"{"b": [1, 2.5, "x"], "a": null}"#) {
+            write!(hex, "{:02x}", b).unwrap();
+        }
+        assert_eq!(
+            hex,
+            "8724a3e28eed88a4543d1c6949f0b79fc87a1cb470ad0e060bdf65be77b6538e"
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use crate::{to_borrowed_value, BorrowedValue, Result};
+use alloc::vec::Vec;
+#[cfg(feature = "bytes-buffer")]
+use bytes_crate::{Bytes, BytesMut};
+use core::fmt;
+
+/// A parsed document that owns the buffer it was parsed from, so its
+/// `BorrowedValue` can be kept and passed around without the lifetime
+/// of the input, while its strings still point into the buffer.
+///
+/// ```rust
+/// use simd_json::{prelude::*, OwnedDoc};
+/// fn load() -> OwnedDoc {
+///     let d = br#"{"name": "x", "tags": ["a", "b"]}"#.to_vec();
+///     OwnedDoc::parse(d).unwrap()
+/// }
+/// let doc = load();
+/// assert_eq!(doc.value()["tags"][1], "b");
+/// ```
+pub struct OwnedDoc {
+    // the value borrows from the heap allocation of `buffer`, it is
+    // declared first so it is dropped before the buffer
+    value: BorrowedValue<'static>,
+    #[cfg(not(feature = "safe"))]
+    _buffer: Vec<u8>,
+}
+
+impl OwnedDoc {
+    /// Parses `buffer` like `to_borrowed_value` and keeps it with the
+    /// value. With the `safe` feature the value is copied with
+    /// `into_static` instead and the buffer is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `buffer` is invalid JSON.
+    #[cfg(not(feature = "safe"))]
+    pub fn parse(mut buffer: Vec<u8>) -> Result<Self> {
+        let value = to_borrowed_value(&mut buffer)?;
+        // the strings point into the heap allocation, which doesn't move
+        // when the `Vec` is moved, and the buffer isn't touched again
+        // while the value is alive. `value` only hands out the value with
+        // the lifetime of the document, never `'static`.
+        let value =
+            unsafe { core::mem::transmute::<BorrowedValue<'_>, BorrowedValue<'static>>(value) };
+        Ok(Self {
+            value,
+            _buffer: buffer,
+        })
+    }
+
+    /// Parses `buffer` like `to_borrowed_value` and keeps it with the
+    /// value. With the `safe` feature the value is copied with
+    /// `into_static` instead and the buffer is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `buffer` is invalid JSON.
+    #[cfg(feature = "safe")]
+    pub fn parse(mut buffer: Vec<u8>) -> Result<Self> {
+        let value = to_borrowed_value(&mut buffer)?.into_static();
+        Ok(Self { value })
+    }
+
+    /// The parsed value, borrowing from the document
+    #[must_use]
+    pub fn value(&self) -> &BorrowedValue<'_> {
+        &self.value
+    }
+}
+
+impl fmt::Debug for OwnedDoc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("OwnedDoc").field(&self.value).finish()
+    }
+}
+
+/// A parsed document that owns the `Bytes` it was parsed from, like
+/// `OwnedDoc` does with a `Vec`. Clones share the buffer and only copy
+/// the value, and the strings of the value can be taken out as `Bytes`
+/// pointing into the buffer with `bytes_of`.
+///
+/// ```rust
+/// use bytes_crate::BytesMut;
+/// use simd_json::{prelude::*, BytesDoc};
+/// let doc = BytesDoc::parse(BytesMut::from(&br#"{"id": "abc"}"#[..])).unwrap();
+/// let id = doc.bytes_of(doc.value()["id"].as_str().unwrap()).unwrap();
+/// drop(doc);
+/// assert_eq!(id, "abc");
+/// ```
+#[cfg(feature = "bytes-buffer")]
+#[derive(Clone)]
+pub struct BytesDoc {
+    // the value borrows from `bytes`, it is declared first so it is
+    // dropped before the buffer
+    value: BorrowedValue<'static>,
+    bytes: Bytes,
+}
+
+#[cfg(feature = "bytes-buffer")]
+impl BytesDoc {
+    /// Parses `buffer` like `to_borrowed_value` and keeps it with the
+    /// value. With the `safe` feature the value is copied with
+    /// `into_static` instead and `bytes_of` doesn't find any strings.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `buffer` is invalid JSON.
+    #[cfg(not(feature = "safe"))]
+    pub fn parse(mut buffer: BytesMut) -> Result<Self> {
+        let value = to_borrowed_value(&mut buffer)?;
+        // like for `OwnedDoc`, as long as the data stays where it is
+        let value =
+            unsafe { core::mem::transmute::<BorrowedValue<'_>, BorrowedValue<'static>>(value) };
+        let data = buffer.as_ptr();
+        // `keep` shares the allocation, so the value stays valid until we
+        // know if `freeze` moved the data
+        let keep = buffer.split_off(buffer.len());
+        let bytes = buffer.freeze();
+        // `freeze` doesn't move the data since bytes 1.4, if it ever does
+        // the value gets its own copies of the strings instead
+        let value = if bytes.as_ptr() == data {
+            value
+        } else {
+            value.into_static()
+        };
+        drop(keep);
+        Ok(Self { value, bytes })
+    }
+
+    /// Parses `buffer` like `to_borrowed_value` and keeps it with the
+    /// value. With the `safe` feature the value is copied with
+    /// `into_static` instead and `bytes_of` doesn't find any strings.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `buffer` is invalid JSON.
+    #[cfg(feature = "safe")]
+    pub fn parse(mut buffer: BytesMut) -> Result<Self> {
+        let value = to_borrowed_value(&mut buffer)?.into_static();
+        Ok(Self {
+            value,
+            bytes: buffer.freeze(),
+        })
+    }
+
+    /// The parsed value, borrowing from the document
+    #[must_use]
+    pub fn value(&self) -> &BorrowedValue<'_> {
+        &self.value
+    }
+
+    /// `s` as `Bytes` sharing the buffer, for strings of the value that
+    /// should outlive the document. `None` if `s` isn't in the buffer.
+    #[must_use]
+    pub fn bytes_of(&self, s: &str) -> Option<Bytes> {
+        let start = (s.as_ptr() as usize).checked_sub(self.bytes.as_ptr() as usize)?;
+        let end = start + s.len();
+        if end <= self.bytes.len() {
+            Some(self.bytes.slice(start..end))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(feature = "bytes-buffer")]
+impl fmt::Debug for BytesDoc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("BytesDoc").field(&self.value).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(s: &str) -> OwnedDoc {
+        OwnedDoc::parse(s.as_bytes().to_vec()).expect("failed to parse")
+    }
+
+    #[test]
+    fn keeps_value() {
+        let docs: Vec<OwnedDoc> = (0..10)
+            .map(|i| {
+                parse(&alloc::format!(
+                    r#"{{"i": {0}, "s": "a\"{0}", "l": ["long string {0}"]}}"#,
+                    i
+                ))
+            })
+            .collect();
+        for (i, doc) in docs.iter().enumerate() {
+            assert_eq!(doc.value()["i"], i);
+            assert_eq!(doc.value()["s"], alloc::format!("a\"{}", i));
+            assert_eq!(doc.value()["l"][0], alloc::format!("long string {}", i));
+        }
+        let moved = docs.into_iter().nth(3).expect("no document");
+        assert_eq!(
+            *moved.value(),
+            json!({"i": 3, "s": "a\"3", "l": ["long string 3"]})
+        );
+        assert!(OwnedDoc::parse(b"[1,".to_vec()).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn send() {
+        let doc = parse(r#"["x"]"#);
+        let handle = std::thread::spawn(move || doc.value()[0] == "x");
+        assert!(handle.join().expect("thread panicked"));
+    }
+
+    #[cfg(feature = "bytes-buffer")]
+    #[test]
+    fn bytes_doc() {
+        use crate::prelude::*;
+        let buffer = BytesMut::from(&br#"{"a": ["x\"y", "z"], "b": 1}"#[..]);
+        let doc = BytesDoc::parse(buffer).expect("failed to parse");
+        let copy = doc.clone();
+        drop(doc);
+        let s = copy.value()["a"][0].as_str().expect("not a string");
+        assert_eq!(s, "x\"y");
+        #[cfg(not(feature = "safe"))]
+        {
+            let x = copy.bytes_of(s).expect("not in the buffer");
+            drop(copy);
+            assert_eq!(x, "x\"y");
+        }
+        #[cfg(feature = "safe")]
+        assert_eq!(copy.bytes_of(s), None);
+        let doc = BytesDoc::parse(BytesMut::from(&b"[1]"[..])).expect("failed to parse");
+        assert_eq!(doc.bytes_of("elsewhere"), None);
+        assert!(BytesDoc::parse(BytesMut::from(&b"{"[..])).is_err());
+    }
+}
@@ -0,0 +1,183 @@
+// without `std` there are no JSON writers, only the binary formats and
+// digests walk values
+#![cfg_attr(not(feature = "std"), allow(dead_code))]
+
+use crate::StaticNode;
+use alloc::vec::{self, Vec};
+use core::iter::Take;
+use core::ops::Deref;
+use core::slice;
+
+/// What the emitters look at of a value
+pub enum Part<'a, V: Emit> {
+    /// A static node
+    Static(StaticNode),
+    /// A string
+    String(&'a str),
+    /// The data of a `Bytes` value
+    #[cfg(feature = "bytes")]
+    Bytes(&'a [u8]),
+    /// The elements of an array
+    Array(&'a [V]),
+    /// The number of entries of an object and the entries
+    Object(usize, V::Entries<'a>),
+}
+
+/// A dom that can be written out by `emit`. The doms implement this and
+/// leave walking their values to `emit`, like they leave building them
+/// to `build`. It is public in a private module so public functions can
+/// take it as a bound without it becoming part of the API.
+pub trait Emit: Sized {
+    /// The keys of objects
+    type Key: Deref<Target = str>;
+    /// The iterator over the entries of an object
+    type Entries<'a>: Iterator<Item = (&'a Self::Key, &'a Self)>
+    where
+        Self: 'a;
+
+    /// What to write for the value
+    fn part(&self) -> Part<'_, Self>;
+}
+
+/// An output values can be written to, `emit` walks the value and hands
+/// the emitter one part after the other. The JSON generators and the
+/// binary formats implement this and only do the writing.
+pub(crate) trait Emitter {
+    /// The error writing can fail with
+    type Error;
+
+    /// Whether the entries of objects are emitted sorted by the bytes of
+    /// their keys rather than in the order of the object
+    const SORTED: bool = false;
+
+    /// Writes a static node
+    fn static_node(&mut self, s: StaticNode) -> Result<(), Self::Error>;
+
+    /// Writes a string
+    fn string(&mut self, s: &str) -> Result<(), Self::Error>;
+
+    /// Writes the data of a `Bytes` value
+    #[cfg(feature = "bytes")]
+    fn bytes(&mut self, b: &[u8]) -> Result<(), Self::Error>;
+
+    /// Starts an array of `len` elements, returns how many of them to
+    /// emit for formats that have to cut off long arrays
+    fn start_array(&mut self, len: usize) -> Result<usize, Self::Error>;
+
+    /// Comes before each element, `first` is set for the first one
+    fn element(&mut self, _first: bool) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Ends an array of `len` elements
+    fn end_array(&mut self, _len: usize) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Starts an object of `len` entries, returns how many of them to
+    /// emit like `start_array`
+    fn start_object(&mut self, len: usize) -> Result<usize, Self::Error>;
+
+    /// Writes the key of an entry before its value, `first` is set for
+    /// the first entry
+    fn key(&mut self, key: &str, first: bool) -> Result<(), Self::Error>;
+
+    /// Ends an object of `len` entries
+    fn end_object(&mut self, _len: usize) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// The entries of an object `emit` still has to write
+enum Entries<'a, V: Emit + 'a> {
+    InOrder(Take<V::Entries<'a>>),
+    Sorted(vec::IntoIter<(&'a V::Key, &'a V)>),
+}
+
+impl<'a, V: Emit + 'a> Iterator for Entries<'a, V> {
+    type Item = (&'a V::Key, &'a V);
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::InOrder(entries) => entries.next(),
+            Self::Sorted(entries) => entries.next(),
+        }
+    }
+}
+
+/// A container that is still being written, with its length and what
+/// is left of it
+enum Frame<'a, V: Emit + 'a> {
+    Array(usize, slice::Iter<'a, V>),
+    Object(usize, Entries<'a, V>),
+}
+
+/// Writes `value` to `e`. Containers that are still being written are
+/// kept on an explicit stack so deeply nested values can't overflow the
+/// thread stack.
+#[cfg_attr(not(feature = "no-inline"), inline(always))]
+pub(crate) fn emit<V: Emit, E: Emitter>(e: &mut E, value: &V) -> Result<(), E::Error> {
+    let mut stack: Vec<Frame<V>> = Vec::new();
+    let mut value = value;
+    loop {
+        match value.part() {
+            Part::Static(s) => stry!(e.static_node(s)),
+            Part::String(s) => stry!(e.string(s)),
+            #[cfg(feature = "bytes")]
+            Part::Bytes(b) => stry!(e.bytes(b)),
+            Part::Array(array) => {
+                let len = stry!(e.start_array(array.len()));
+                let mut elements = array[..len].iter();
+                if let Some(element) = elements.next() {
+                    stry!(e.element(true));
+                    stack.push(Frame::Array(len, elements));
+                    value = element;
+                    continue;
+                }
+                stry!(e.end_array(0));
+            }
+            Part::Object(len, entries) => {
+                let len = stry!(e.start_object(len));
+                let mut entries = if E::SORTED {
+                    let mut sorted: Vec<_> = entries.take(len).collect();
+                    sorted.sort_unstable_by(|(k1, _), (k2, _)| k1.as_bytes().cmp(k2.as_bytes()));
+                    Entries::Sorted(sorted.into_iter())
+                } else {
+                    Entries::InOrder(entries.take(len))
+                };
+                if let Some((key, entry)) = entries.next() {
+                    stry!(e.key(key, true));
+                    stack.push(Frame::Object(len, entries));
+                    value = entry;
+                    continue;
+                }
+                stry!(e.end_object(0));
+            }
+        }
+        // The value is written, move on to the next one in its parent
+        // and close every parent that has none left.
+        loop {
+            match stack.last_mut() {
+                None => return Ok(()),
+                Some(Frame::Array(len, elements)) => {
+                    if let Some(element) = elements.next() {
+                        stry!(e.element(false));
+                        value = element;
+                        break;
+                    }
+                    stry!(e.end_array(*len));
+                }
+                Some(Frame::Object(len, entries)) => {
+                    if let Some((key, entry)) = entries.next() {
+                        stry!(e.key(key, false));
+                        value = entry;
+                        break;
+                    }
+                    stry!(e.end_object(*len));
+                }
+            }
+            stack.pop();
+        }
+    }
+}
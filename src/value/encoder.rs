@@ -0,0 +1,474 @@
+use super::emit::{emit, Emit, Emitter};
+use crate::{EncodeOptions, Escape, FloatFormat, NegativeZero, NonFiniteFloats, StaticNode};
+use core::{fmt, str};
+use std::io;
+use std::io::Write;
+use value_trait::generator::{
+    BaseGenerator, DumpGenerator, PrettyGenerator, PrettyWriterGenerator, WriterGenerator,
+};
+use value_trait::Value;
+
+/// Turns generated output into a `String`, generators only ever write
+/// valid UTF-8.
+#[cfg(not(feature = "safe"))]
+pub(crate) fn into_string(output: Vec<u8>) -> String {
+    unsafe { String::from_utf8_unchecked(output) }
+}
+
+/// Turns generated output into a `String`, generators only ever write
+/// valid UTF-8.
+#[cfg(feature = "safe")]
+pub(crate) fn into_string(output: Vec<u8>) -> String {
+    String::from_utf8(output).expect("generators only write valid UTF-8")
+}
+
+/// Runs `f` with the bytes of `out` to append to them, without copying
+/// them. `f` must only write valid UTF-8.
+pub(crate) fn append_to<F>(out: &mut String, f: F)
+where
+    F: FnOnce(&mut Vec<u8>),
+{
+    let mut bytes = core::mem::take(out).into_bytes();
+    f(&mut bytes);
+    *out = into_string(bytes);
+}
+
+/// Hands what generators write on to a `fmt::Write`. Strings can be
+/// written in chunks that split a character, the start of a split
+/// character is kept until the rest of it comes in.
+pub(crate) struct FmtWriter<'w, W: fmt::Write> {
+    inner: &'w mut W,
+    pending: [u8; 4],
+    pending_len: usize,
+}
+
+impl<'w, W: fmt::Write> FmtWriter<'w, W> {
+    pub(crate) fn new(inner: &'w mut W) -> Self {
+        Self {
+            inner,
+            pending: [0; 4],
+            pending_len: 0,
+        }
+    }
+
+    fn write_str(&mut self, s: &str) -> io::Result<()> {
+        self.inner
+            .write_str(s)
+            .map_err(|_| io::Error::from(io::ErrorKind::Other))
+    }
+}
+
+impl<W: fmt::Write> Write for FmtWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        stry!(self.write_all(buf));
+        Ok(buf.len())
+    }
+
+    fn write_all(&mut self, mut buf: &[u8]) -> io::Result<()> {
+        while self.pending_len > 0 {
+            let Some((&byte, rest)) = buf.split_first() else {
+                return Ok(());
+            };
+            buf = rest;
+            self.pending[self.pending_len] = byte;
+            self.pending_len += 1;
+            let pending = self.pending;
+            match str::from_utf8(&pending[..self.pending_len]) {
+                Ok(s) => {
+                    self.pending_len = 0;
+                    stry!(self.write_str(s));
+                }
+                Err(e) if e.error_len().is_none() && self.pending_len < 4 => {}
+                Err(_) => return Err(io::Error::from(io::ErrorKind::InvalidData)),
+            }
+        }
+        match str::from_utf8(buf) {
+            Ok(s) => self.write_str(s),
+            Err(e) if e.error_len().is_none() => {
+                let (valid, rest) = buf.split_at(e.valid_up_to());
+                // `valid` is checked already
+                stry!(self.write_str(str::from_utf8(valid).unwrap_or_default()));
+                self.pending[..rest.len()].copy_from_slice(rest);
+                self.pending_len = rest.len();
+                Ok(())
+            }
+            Err(_) => Err(io::Error::from(io::ErrorKind::InvalidData)),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Generator that honours `EncodeOptions`, it is used for both the
+/// compact and the pretty output by only emitting whitespace when
+/// an indentation is configured.
+pub(crate) struct OptionsGenerator<W: Write> {
+    writer: W,
+    options: EncodeOptions,
+    dent: usize,
+}
+
+impl<W: Write> OptionsGenerator<W> {
+    pub(crate) fn new(writer: W, options: EncodeOptions) -> Self {
+        Self {
+            writer,
+            options,
+            dent: 0,
+        }
+    }
+
+    pub(crate) fn consume(self) -> W {
+        self.writer
+    }
+
+    /// Writes `null`, `true` or `false`
+    #[inline(always)]
+    pub(crate) fn write_literal(&mut self, literal: &[u8]) -> io::Result<()> {
+        let token = if literal == b"null" {
+            Token::Null
+        } else {
+            Token::Boolean
+        };
+        self.colored(token, |g| g.write(literal))
+    }
+
+    /// Whether strings need more escapes than JSON needs
+    #[inline(always)]
+    fn escapes_more(&self) -> bool {
+        self.options.ascii_only || self.options.html_safe || self.options.escape.is_some()
+    }
+
+    /// Writes the content of a string with the escapes JSON needs and
+    /// the ones the options ask for on top of them
+    #[inline(never)]
+    fn write_escaped_content(&mut self, string: &str) -> io::Result<()> {
+        let mut start = 0;
+        for (idx, c) in string.char_indices() {
+            let escape = match self
+                .options
+                .escape
+                .map_or(Escape::Default, |hook| (hook.0)(c))
+            {
+                Escape::Default => default_escape(c, &self.options),
+                escape => escape,
+            };
+            if matches!(escape, Escape::Default | Escape::Keep) {
+                continue;
+            }
+            stry!(self.writer.write_all(&string.as_bytes()[start..idx]));
+            if let Escape::Text(text) = escape {
+                stry!(self.writer.write_all(text.as_bytes()));
+            } else {
+                let mut units = [0; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    stry!(write!(self.writer, "\\u{:04x}", unit));
+                }
+            }
+            start = idx + c.len_utf8();
+        }
+        self.writer.write_all(&string.as_bytes()[start..])
+    }
+
+    /// Runs `f`, with its output in the color of `token` if the options
+    /// ask for colors
+    #[cfg(feature = "color")]
+    #[inline(always)]
+    fn colored<F>(&mut self, token: Token, f: F) -> io::Result<()>
+    where
+        F: FnOnce(&mut Self) -> io::Result<()>,
+    {
+        if let Some(colors) = self.options.colors {
+            let sgr = match token {
+                Token::Key => colors.key,
+                Token::String => colors.string,
+                Token::Number => colors.number,
+                Token::Boolean => colors.boolean,
+                Token::Null => colors.null,
+            };
+            stry!(write!(self.writer, "\x1b[{}m", sgr));
+            stry!(f(self));
+            self.writer.write_all(b"\x1b[0m")
+        } else {
+            f(self)
+        }
+    }
+
+    #[cfg(not(feature = "color"))]
+    #[inline(always)]
+    fn colored<F>(&mut self, _token: Token, f: F) -> io::Result<()>
+    where
+        F: FnOnce(&mut Self) -> io::Result<()>,
+    {
+        f(self)
+    }
+}
+
+/// What is being written, for picking its color
+#[derive(Clone, Copy)]
+enum Token {
+    Key,
+    String,
+    Number,
+    Boolean,
+    Null,
+}
+
+impl<W: Write> BaseGenerator for OptionsGenerator<W> {
+    type T = W;
+
+    #[inline(always)]
+    fn get_writer(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    #[inline(always)]
+    fn write_min(&mut self, slice: &[u8], min: u8) -> io::Result<()> {
+        if self.options.indent.is_some() {
+            self.writer.write_all(slice)
+        } else {
+            self.writer.write_all(&[min])
+        }
+    }
+
+    #[inline(always)]
+    fn new_line(&mut self) -> io::Result<()> {
+        if let Some(spaces) = self.options.indent {
+            stry!(self.write_char(b'\n'));
+            for _ in 0..(self.dent * usize::from(spaces)) {
+                stry!(self.write_char(b' '));
+            }
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn indent(&mut self) {
+        self.dent += 1;
+    }
+
+    #[inline(always)]
+    fn dedent(&mut self) {
+        self.dent -= 1;
+    }
+
+    #[inline(always)]
+    fn write_string(&mut self, string: &str) -> io::Result<()> {
+        self.colored(Token::String, |g| {
+            stry!(g.write_char(b'"'));
+            if g.escapes_more() {
+                stry!(g.write_escaped_content(string));
+            } else {
+                stry!(g.write_string_content(string));
+            }
+            g.write_char(b'"')
+        })
+    }
+
+    #[inline(always)]
+    fn write_simple_string(&mut self, string: &str) -> io::Result<()> {
+        // only used for keys by the value generators
+        self.colored(Token::Key, |g| {
+            stry!(g.write_char(b'"'));
+            if g.escapes_more() {
+                stry!(g.write_escaped_content(string));
+            } else {
+                stry!(g.write_simple_str_content(string));
+            }
+            g.write_char(b'"')
+        })
+    }
+
+    #[inline(always)]
+    fn write_float(&mut self, num: f64) -> io::Result<()> {
+        let policy = self.options.non_finite;
+        if num.is_finite() {
+            let num = if num == 0.0 && self.options.negative_zero == NegativeZero::Positive {
+                0.0
+            } else {
+                num
+            };
+            match self.options.float_format {
+                FloatFormat::Shortest => {
+                    let mut buffer = ryu::Buffer::new();
+                    self.colored(Token::Number, |g| {
+                        g.write(buffer.format_finite(num).as_bytes())
+                    })
+                }
+                FloatFormat::Fixed(digits) => self.colored(Token::Number, |g| {
+                    write!(g.writer, "{:.*}", usize::from(digits), num)
+                }),
+                // `{:e}` never writes a `+` in the exponent, so this is
+                // valid JSON as well
+                FloatFormat::Scientific(digits) => self.colored(Token::Number, |g| {
+                    write!(g.writer, "{:.*e}", usize::from(digits), num)
+                }),
+            }
+        } else if policy == NonFiniteFloats::Null {
+            self.colored(Token::Null, |g| write_non_finite(g, num, policy))
+        } else {
+            self.colored(Token::Number, |g| write_non_finite(g, num, policy))
+        }
+    }
+
+    #[cfg(feature = "color")]
+    #[inline(always)]
+    fn write_int<I: itoa::Integer>(&mut self, num: I) -> io::Result<()> {
+        self.colored(Token::Number, |g| {
+            itoa::write(&mut g.writer, num).map(|_| ())
+        })
+    }
+}
+
+/// How `c` is escaped when no hook decides it
+fn default_escape(c: char, options: &EncodeOptions) -> Escape {
+    match c {
+        '"' => Escape::Text("\\\""),
+        '\\' => Escape::Text("\\\\"),
+        '\n' => Escape::Text("\\n"),
+        '\r' => Escape::Text("\\r"),
+        '\t' => Escape::Text("\\t"),
+        '\u{8}' => Escape::Text("\\b"),
+        '\u{c}' => Escape::Text("\\f"),
+        '<' | '>' | '&' | '\u{2028}' | '\u{2029}' if options.html_safe => Escape::Unicode,
+        c if c < ' ' || (!c.is_ascii() && options.ascii_only) => Escape::Unicode,
+        _ => Escape::Keep,
+    }
+}
+
+/// Writes a float that JSON can not represent according to `policy`
+#[inline(never)]
+pub(crate) fn write_non_finite<G>(g: &mut G, num: f64, policy: NonFiniteFloats) -> io::Result<()>
+where
+    G: BaseGenerator + ?Sized,
+{
+    match policy {
+        NonFiniteFloats::Null => g.write(b"null"),
+        NonFiniteFloats::Literal if num.is_nan() => g.write(b"NaN"),
+        NonFiniteFloats::Literal if num.is_sign_negative() => g.write(b"-Infinity"),
+        NonFiniteFloats::Literal => g.write(b"Infinity"),
+    }
+}
+
+/// The generators values are written as JSON with
+pub(crate) trait JsonGenerator: BaseGenerator {
+    #[inline(always)]
+    fn write_f64(&mut self, num: f64) -> io::Result<()> {
+        if num.is_finite() {
+            self.write_float(num)
+        } else {
+            write_non_finite(self, num, NonFiniteFloats::Null)
+        }
+    }
+
+    /// Writes `null`, `true` or `false`
+    #[inline(always)]
+    fn write_literal(&mut self, literal: &[u8]) -> io::Result<()> {
+        self.write(literal)
+    }
+}
+
+impl<V: Value> JsonGenerator for DumpGenerator<V> {}
+
+impl<V: Value> JsonGenerator for PrettyGenerator<V> {}
+
+impl<W: Write, V: Value> JsonGenerator for WriterGenerator<'_, W, V> {}
+
+impl<W: Write, V: Value> JsonGenerator for PrettyWriterGenerator<'_, W, V> {}
+
+impl<W: Write> JsonGenerator for OptionsGenerator<W> {
+    #[inline(always)]
+    fn write_f64(&mut self, num: f64) -> io::Result<()> {
+        self.write_float(num)
+    }
+
+    #[inline(always)]
+    fn write_literal(&mut self, literal: &[u8]) -> io::Result<()> {
+        OptionsGenerator::write_literal(self, literal)
+    }
+}
+
+impl<G: JsonGenerator> Emitter for G {
+    type Error = io::Error;
+
+    #[inline(always)]
+    fn static_node(&mut self, s: StaticNode) -> io::Result<()> {
+        match s {
+            StaticNode::Null => self.write_literal(b"null"),
+            StaticNode::Bool(true) => self.write_literal(b"true"),
+            StaticNode::Bool(false) => self.write_literal(b"false"),
+            StaticNode::I64(number) => self.write_int(number),
+            StaticNode::U64(number) => self.write_int(number),
+            #[cfg(feature = "128bit")]
+            StaticNode::I128(number) => self.write_int(number),
+            #[cfg(feature = "128bit")]
+            StaticNode::U128(number) => self.write_int(number),
+            StaticNode::F64(number) => self.write_f64(number),
+        }
+    }
+
+    #[inline(always)]
+    fn string(&mut self, s: &str) -> io::Result<()> {
+        self.write_string(s)
+    }
+
+    #[cfg(feature = "bytes")]
+    #[inline(always)]
+    fn bytes(&mut self, b: &[u8]) -> io::Result<()> {
+        self.write_string(&crate::value::to_base64(b))
+    }
+
+    #[inline(always)]
+    fn start_array(&mut self, len: usize) -> io::Result<usize> {
+        stry!(self.write(b"["));
+        Ok(len)
+    }
+
+    #[inline(always)]
+    fn element(&mut self, first: bool) -> io::Result<()> {
+        if first {
+            self.indent();
+        } else {
+            stry!(self.write(b","));
+        }
+        self.new_line()
+    }
+
+    #[inline(always)]
+    fn end_array(&mut self, len: usize) -> io::Result<()> {
+        if len > 0 {
+            self.dedent();
+            stry!(self.new_line());
+        }
+        self.write(b"]")
+    }
+
+    #[inline(always)]
+    fn start_object(&mut self, len: usize) -> io::Result<usize> {
+        stry!(self.write(b"{"));
+        Ok(len)
+    }
+
+    #[inline(always)]
+    fn key(&mut self, key: &str, first: bool) -> io::Result<()> {
+        stry!(self.element(first));
+        stry!(self.write_simple_string(key));
+        self.write_min(b": ", b':')
+    }
+
+    #[inline(always)]
+    fn end_object(&mut self, len: usize) -> io::Result<()> {
+        if len > 0 {
+            self.dedent();
+            stry!(self.new_line());
+        }
+        self.write(b"}")
+    }
+}
+
+/// Writes `value` as JSON with `g`
+#[inline(always)]
+pub(crate) fn write_json<V: Emit, G: JsonGenerator>(g: &mut G, value: &V) -> io::Result<()> {
+    emit(g, value)
+}
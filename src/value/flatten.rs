@@ -0,0 +1,419 @@
+use super::path::{steps, PathError};
+use crate::prelude::*;
+use crate::{BorrowedValue, OwnedValue};
+use alloc::borrow::Cow;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::Hash;
+
+/// How the keys of a flattened value are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FlattenStyle {
+    /// Keys are joined by `.` and indexes are written as `[0]`, like
+    /// `a.b[0].c`. `.`, `[` and `\` in keys are escaped with a `\`.
+    #[default]
+    Dots,
+    /// Keys are JSON pointers, like `/a/b/0/c`. Steps that are numbers
+    /// become array indexes when unflattening.
+    Pointer,
+}
+
+/// Error for unflattening a value, holds the key that couldn't be
+/// placed and why
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnflattenError {
+    key: String,
+    error: PathError,
+}
+
+impl UnflattenError {
+    /// The flat key that couldn't be placed
+    #[must_use]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Why the key couldn't be placed, the step is counted in the
+    /// parsed key
+    #[must_use]
+    pub fn error(&self) -> PathError {
+        self.error
+    }
+}
+
+#[cfg_attr(tarpaulin, skip)]
+impl fmt::Display for UnflattenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} in key {:?}", self.error, self.key)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UnflattenError {}
+
+impl OwnedValue {
+    /// Flattens the value into an object with `a.b[0].c` keys, see
+    /// `BorrowedValue::flatten_with`.
+    ///
+    /// ```rust
+    /// use simd_json::json;
+    /// let v = json!({"cpu": {"load": [0.5, 0.7]}, "host": "a"});
+    /// let flat = v.flatten();
+    /// assert_eq!(flat, json!({"cpu.load[0]": 0.5, "cpu.load[1]": 0.7, "host": "a"}));
+    /// assert_eq!(flat.unflatten(), Ok(v));
+    /// ```
+    #[must_use]
+    pub fn flatten(&self) -> Self {
+        self.flatten_with(FlattenStyle::Dots)
+    }
+
+    /// Flattens the value into an object with a key per leaf, see
+    /// `BorrowedValue::flatten_with`.
+    #[must_use]
+    pub fn flatten_with(&self, style: FlattenStyle) -> Self {
+        flatten(self, style)
+    }
+
+    /// Turns an object with `a.b[0].c` keys back into a nested value,
+    /// see `BorrowedValue::unflatten_with`.
+    ///
+    /// # Errors
+    ///
+    /// if a key is malformed or conflicts with another key
+    pub fn unflatten(&self) -> Result<Self, UnflattenError> {
+        self.unflatten_with(FlattenStyle::Dots)
+    }
+
+    /// Turns a flattened object back into a nested value, see
+    /// `BorrowedValue::unflatten_with`.
+    ///
+    /// # Errors
+    ///
+    /// if a key is malformed or conflicts with another key
+    pub fn unflatten_with(&self, style: FlattenStyle) -> Result<Self, UnflattenError> {
+        unflatten(self, style)
+    }
+}
+
+impl BorrowedValue<'_> {
+    /// Flattens the value into an object with `a.b[0].c` keys, see
+    /// `flatten_with`.
+    #[must_use]
+    pub fn flatten(&self) -> Self {
+        self.flatten_with(FlattenStyle::Dots)
+    }
+
+    /// Flattens the value into an object with a key for every leaf,
+    /// written as `style` says. Leaves are everything but objects and
+    /// arrays, and empty objects and arrays so they survive a round trip.
+    /// A value that is a leaf itself ends up under the empty key.
+    ///
+    /// ```rust
+    /// use simd_json::{json, FlattenStyle};
+    /// let mut d = br#"{"a/b": {"c": [1, {}]}}"#.to_vec();
+    /// let v = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// let flat = v.flatten_with(FlattenStyle::Pointer);
+    /// assert_eq!(flat, json!({"/a~1b/c/0": 1, "/a~1b/c/1": {}}));
+    /// ```
+    #[must_use]
+    pub fn flatten_with(&self, style: FlattenStyle) -> Self {
+        flatten(self, style)
+    }
+
+    /// Turns an object with `a.b[0].c` keys back into a nested value,
+    /// see `unflatten_with`.
+    ///
+    /// # Errors
+    ///
+    /// if a key is malformed or conflicts with another key
+    pub fn unflatten(&self) -> Result<Self, UnflattenError> {
+        self.unflatten_with(FlattenStyle::Dots)
+    }
+
+    /// Turns an object with keys written as `style` says back into a
+    /// nested value. The keys can come in any order, array elements no
+    /// key mentions are `null`. Values that aren't objects are returned
+    /// as they are.
+    ///
+    /// ```rust
+    /// use simd_json::{json, FlattenStyle, PathError};
+    /// let mut d = br#"{"/a/1": true, "/a/0/b": 1}"#.to_vec();
+    /// let flat = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// let v = flat.unflatten_with(FlattenStyle::Pointer).unwrap();
+    /// assert_eq!(v, json!({"a": [{"b": 1}, true]}));
+    ///
+    /// let mut d = br#"{"a": 1, "a.b": 2}"#.to_vec();
+    /// let flat = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// let err = flat.unflatten().unwrap_err();
+    /// assert_eq!(err.key(), "a.b");
+    /// assert_eq!(err.error(), PathError::NotAContainer(1));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// if a key is malformed or conflicts with another key
+    pub fn unflatten_with(&self, style: FlattenStyle) -> Result<Self, UnflattenError> {
+        unflatten(self, style)
+    }
+}
+
+/// A step of a flat key
+enum Step<'k> {
+    Key(Cow<'k, str>),
+    Index(usize),
+}
+
+fn flatten<'v, V>(value: &V, style: FlattenStyle) -> V
+where
+    V: Builder<'v> + Mutable + Clone,
+    V::Key: From<String> + Borrow<str> + Hash + Eq,
+{
+    let mut flat = V::object();
+    if let Some(out) = flat.as_object_mut() {
+        leaves(value, &mut String::new(), style, out);
+    }
+    flat
+}
+
+fn leaves<V>(value: &V, path: &mut String, style: FlattenStyle, out: &mut V::Object)
+where
+    V: Mutable + Clone,
+    V::Key: From<String> + Borrow<str> + Hash + Eq,
+{
+    let len = path.len();
+    if let Some(object) = value.as_object().filter(|o| !o.is_empty()) {
+        for (key, value) in object.iter() {
+            push_key(path, key.borrow(), style);
+            leaves(value, path, style, out);
+            path.truncate(len);
+        }
+    } else if let Some(array) = value.as_array().filter(|a| !a.is_empty()) {
+        for (idx, value) in array.iter().enumerate() {
+            match style {
+                FlattenStyle::Dots => path.push('['),
+                FlattenStyle::Pointer => path.push('/'),
+            }
+            path.push_str(&idx.to_string());
+            if style == FlattenStyle::Dots {
+                path.push(']');
+            }
+            leaves(value, path, style, out);
+            path.truncate(len);
+        }
+    } else {
+        let _ = out.insert(V::Key::from(path.clone()), value.clone());
+    }
+}
+
+pub(super) fn push_key(path: &mut String, key: &str, style: FlattenStyle) {
+    match style {
+        FlattenStyle::Dots => {
+            if !path.is_empty() {
+                path.push('.');
+            }
+            for c in key.chars() {
+                if matches!(c, '.' | '[' | '\\') {
+                    path.push('\\');
+                }
+                path.push(c);
+            }
+        }
+        FlattenStyle::Pointer => {
+            path.push('/');
+            path.push_str(&key.replace('~', "~0").replace('/', "~1"));
+        }
+    }
+}
+
+/// Splits a key written with dots into its steps, on failure returns
+/// the position of the step that is malformed
+fn dot_steps(key: &str) -> Result<Vec<Step<'_>>, usize> {
+    let mut steps = Vec::new();
+    let mut name = String::new();
+    // false right after an index, when the next thing must be `.` or `[`
+    let mut in_name = true;
+    let mut chars = key.chars();
+    while let Some(c) = chars.next() {
+        if !in_name && c != '.' && c != '[' {
+            return Err(steps.len() - 1);
+        }
+        match c {
+            '\\' => name.push(chars.next().unwrap_or('\\')),
+            '.' if in_name => steps.push(Step::Key(Cow::Owned(core::mem::take(&mut name)))),
+            '.' => in_name = true,
+            '[' => {
+                if in_name && !(steps.is_empty() && name.is_empty()) {
+                    steps.push(Step::Key(Cow::Owned(core::mem::take(&mut name))));
+                }
+                let digits: String = chars.by_ref().take_while(|c| *c != ']').collect();
+                if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(steps.len());
+                }
+                steps.push(Step::Index(digits.parse().map_err(|_| steps.len())?));
+                in_name = false;
+            }
+            c => name.push(c),
+        }
+    }
+    if in_name && !key.is_empty() {
+        steps.push(Step::Key(Cow::Owned(name)));
+    }
+    Ok(steps)
+}
+
+fn pointer_steps(key: &str) -> Vec<Step<'_>> {
+    steps(key)
+        .into_iter()
+        .map(|step| match step.parse() {
+            Ok(idx) if step.bytes().all(|b| b.is_ascii_digit()) => Step::Index(idx),
+            _ => Step::Key(step),
+        })
+        .collect()
+}
+
+fn unflatten<'v, V>(flat: &V, style: FlattenStyle) -> Result<V, UnflattenError>
+where
+    V: Builder<'v> + Mutable + ValueTrait<Array = Vec<V>> + Clone,
+    V::Key: From<String> + Borrow<str> + Hash + Eq,
+{
+    let Some(object) = flat.as_object() else {
+        return Ok(flat.clone());
+    };
+    // no index of a flattened value is past the number of leaves, this
+    // keeps a key like `a[4000000000]` from allocating a huge array
+    let max_len = object.len();
+    let mut value = V::null();
+    for (key, leaf) in object.iter() {
+        let key: &str = key.borrow();
+        let steps = match style {
+            FlattenStyle::Dots => dot_steps(key).map_err(PathError::BadIndex),
+            FlattenStyle::Pointer => Ok(pointer_steps(key)),
+        };
+        steps
+            .and_then(|steps| place(&mut value, &steps, 0, leaf.clone(), max_len))
+            .map_err(|error| UnflattenError {
+                key: key.to_string(),
+                error,
+            })?;
+    }
+    Ok(value)
+}
+
+fn place<'v, V>(
+    target: &mut V,
+    steps: &[Step],
+    depth: usize,
+    value: V,
+    max_len: usize,
+) -> Result<(), PathError>
+where
+    V: Builder<'v> + Mutable + ValueTrait<Array = Vec<V>>,
+    V::Key: From<String> + Borrow<str> + Hash + Eq,
+{
+    let Some((step, rest)) = steps.split_first() else {
+        *target = value;
+        return Ok(());
+    };
+    if target.is_null() {
+        *target = match step {
+            Step::Key(_) => V::object(),
+            Step::Index(_) => V::array(),
+        };
+    }
+    let child = match step {
+        Step::Key(key) => {
+            let object = target
+                .as_object_mut()
+                .ok_or(PathError::NotAContainer(depth))?;
+            if object.get(key.as_ref()).is_none() {
+                let _ = object.insert(V::Key::from(key.to_string()), V::null());
+            }
+            object.get_mut(key.as_ref())
+        }
+        Step::Index(idx) => {
+            let array = target
+                .as_array_mut()
+                .ok_or(PathError::NotAContainer(depth))?;
+            if *idx >= max_len {
+                return Err(PathError::BadIndex(depth));
+            }
+            if array.len() <= *idx {
+                array.resize_with(idx + 1, V::null);
+            }
+            array.get_mut(*idx)
+        }
+    };
+    match child {
+        Some(child) => place(child, rest, depth + 1, value, max_len),
+        None => Err(PathError::Missing(depth)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let v = json!({
+            "a.b": {"c[0]": [1, [2, {"d\\": null}]]},
+            "e": [],
+            "f": {},
+            "g/~": [{}]
+        });
+        for style in &[FlattenStyle::Dots, FlattenStyle::Pointer] {
+            let flat = v.flatten_with(*style);
+            assert_eq!(flat.unflatten_with(*style), Ok(v.clone()));
+            let borrowed = BorrowedValue::from(v.clone()).flatten_with(*style);
+            assert_eq!(borrowed, flat);
+        }
+        assert_eq!(
+            v.flatten(),
+            json!({
+                "a\\.b.c\\[0][0]": 1,
+                "a\\.b.c\\[0][1][0]": 2,
+                "a\\.b.c\\[0][1][1].d\\\\": null,
+                "e": [],
+                "f": {},
+                "g/~[0]": {}
+            })
+        );
+        assert_eq!(v.flatten_with(FlattenStyle::Pointer)["/g~1~0/0"], json!({}));
+        assert_eq!(json!(1).flatten(), json!({"": 1}));
+        assert_eq!(json!({"": 1}).unflatten(), Ok(json!(1)));
+        assert_eq!(json!([[1]]).flatten(), json!({"[0][0]": 1}));
+        assert_eq!(json!({"[0][0]": 1}).unflatten(), Ok(json!([[1]])));
+        assert_eq!(json!(true).unflatten(), Ok(json!(true)));
+    }
+
+    #[test]
+    fn unflatten_errors() {
+        let error = |flat: OwnedValue| flat.unflatten().map_err(|e| e.error());
+        assert_eq!(
+            error(json!({"a[1]": 1, "b": 2})),
+            Ok(json!({"a": [null, 1], "b": 2}))
+        );
+        assert_eq!(
+            error(json!({"a[2]": 1, "b": 2})),
+            Err(PathError::BadIndex(1))
+        );
+        assert_eq!(error(json!({"a[x]": 1})), Err(PathError::BadIndex(1)));
+        assert_eq!(error(json!({"a[]": 1})), Err(PathError::BadIndex(1)));
+        assert_eq!(error(json!({"a[0]b": 1})), Err(PathError::BadIndex(1)));
+        assert_eq!(
+            error(json!({"a": [], "a.b": 1})),
+            Err(PathError::NotAContainer(1))
+        );
+        assert_eq!(
+            error(json!({"a.b": 1, "a[0]": 1})),
+            Err(PathError::NotAContainer(1))
+        );
+        let err = json!({"/a": 1, "/a/0": 2})
+            .unflatten_with(FlattenStyle::Pointer)
+            .unwrap_err();
+        assert_eq!(err.key(), "/a/0");
+        assert_eq!(err.error(), PathError::NotAContainer(1));
+    }
+}
@@ -0,0 +1,104 @@
+use crate::prelude::*;
+use crate::{BorrowedValue, OwnedValue};
+use core::borrow::Borrow;
+use core::hash::Hash;
+
+/// Objects up to this size are scanned once for all keys, larger ones
+/// get a lookup per key
+const SCAN_LEN: usize = 32;
+
+impl OwnedValue {
+    /// Gets the values of several keys of an object at once, see
+    /// `BorrowedValue::get_many`.
+    ///
+    /// ```rust
+    /// use simd_json::json;
+    /// let v = json!({"id": 1, "name": "a", "ts": 2});
+    /// let [id, ts] = v.get_many(["id", "ts"]).unwrap();
+    /// assert_eq!(id, &1);
+    /// assert_eq!(ts, &2);
+    /// assert!(v.get_many(["id", "missing"]).is_none());
+    /// ```
+    #[must_use]
+    pub fn get_many<const N: usize>(&self, keys: [&str; N]) -> Option<[&Self; N]> {
+        get_many(self, keys)
+    }
+}
+
+impl BorrowedValue<'_> {
+    /// Gets the values of several keys of an object at once, in the order
+    /// of `keys`. Returns `None` if the value isn't an object or any of
+    /// the keys is missing, so extracting a handful of fields needs one
+    /// check instead of one per field. Small objects are scanned only
+    /// once for all the keys.
+    ///
+    /// ```rust
+    /// use simd_json::prelude::*;
+    /// let mut d = br#"{"id": 1, "name": "a", "ts": 2}"#.to_vec();
+    /// let v = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// let [name, id] = v.get_many(["name", "id"]).unwrap();
+    /// assert_eq!(name.as_str(), Some("a"));
+    /// assert_eq!(id.as_u64(), Some(1));
+    /// ```
+    #[must_use]
+    pub fn get_many<const N: usize>(&self, keys: [&str; N]) -> Option<[&Self; N]> {
+        get_many(self, keys)
+    }
+}
+
+fn get_many<'v, V, const N: usize>(value: &'v V, keys: [&str; N]) -> Option<[&'v V; N]>
+where
+    V: ValueTrait,
+    V::Key: Borrow<str> + Hash + Eq,
+{
+    let object = value.as_object()?;
+    let mut found: [Option<&V>; N] = [None; N];
+    if object.len() <= SCAN_LEN {
+        for (key, value) in object.iter() {
+            let key: &str = key.borrow();
+            for (slot, wanted) in found.iter_mut().zip(keys.iter()) {
+                if slot.is_none() && key == *wanted {
+                    *slot = Some(value);
+                }
+            }
+        }
+    } else {
+        for (slot, key) in found.iter_mut().zip(keys.iter()) {
+            *slot = Some(object.get(*key)?);
+        }
+    }
+    if found.iter().any(Option::is_none) {
+        return None;
+    }
+    // every slot is filled, `value` is never used
+    Some(found.map(|v| v.unwrap_or(value)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn small_and_large() {
+        let v = json!({"a": 1, "b": 2, "c": 3});
+        assert_eq!(
+            v.get_many(["c", "a", "c"]),
+            Some([&v["c"], &v["a"], &v["c"]])
+        );
+        assert_eq!(v.get_many([]), Some([]));
+        assert_eq!(v.get_many(["a", "d"]), None);
+        assert_eq!(json!([1]).get_many(["a"]), None);
+
+        let large: OwnedValue = (0..100).map(|i| (i.to_string(), i)).collect();
+        assert_eq!(
+            large.get_many(["99", "0"]),
+            Some([&OwnedValue::from(99), &OwnedValue::from(0)])
+        );
+        assert_eq!(large.get_many(["99", "100"]), None);
+        let borrowed = BorrowedValue::from(large.clone());
+        let [a, b] = borrowed.get_many(["1", "2"]).unwrap();
+        assert_eq!(a, &large["1"]);
+        assert_eq!(b, &large["2"]);
+    }
+}
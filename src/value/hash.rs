@@ -0,0 +1,415 @@
+use super::Dom;
+use crate::prelude::*;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::hash::{Hash, Hasher};
+use value_trait::StaticNode;
+
+// Tags written before each kind of value so `[]` and `{}` or `null` and
+// `false` don't hash the same
+const NULL: u8 = 0;
+const BOOL: u8 = 1;
+const INT: u8 = 2;
+const FLOAT: u8 = 3;
+const STRING: u8 = 4;
+const ARRAY: u8 = 5;
+const OBJECT: u8 = 6;
+#[cfg(feature = "bytes")]
+const BYTES: u8 = 7;
+
+/// A value that can be the key of a `HashMap` or `HashSet`, for example to
+/// deduplicate documents.
+///
+/// `==` on values compares floats within a tolerance, which isn't
+/// transitive and so can't back `Eq` and `Hash`. `ExactValue` compares
+/// floats by their bits instead, only `-0.0` and `0.0` are the same.
+/// Integers are compared by value no matter if they were parsed as
+/// signed or unsigned, and objects regardless of the order of their keys.
+///
+/// ```rust
+/// use simd_json::{json, ExactValue};
+/// use std::collections::HashSet;
+/// let docs = vec![json!({"id": 1, "n": 0.5}), json!({"n": 0.5, "id": 1}), json!({"id": 2})];
+/// let unique: HashSet<ExactValue<_>> = docs.into_iter().map(ExactValue).collect();
+/// assert_eq!(unique.len(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExactValue<V>(pub V);
+
+impl<V> ExactValue<V> {
+    /// The wrapped value
+    #[must_use]
+    pub fn into_inner(self) -> V {
+        self.0
+    }
+}
+
+impl<V> PartialEq for ExactValue<V>
+where
+    V: Dom,
+    V::Key: Borrow<str>,
+{
+    fn eq(&self, other: &Self) -> bool {
+        exact_eq(&self.0, &other.0)
+    }
+}
+
+impl<V> Eq for ExactValue<V>
+where
+    V: Dom,
+    V::Key: Borrow<str>,
+{
+}
+
+impl<V> Hash for ExactValue<V>
+where
+    V: Dom,
+    V::Key: Borrow<str>,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_value(&self.0, state);
+    }
+}
+
+fn exact_eq<V>(a: &V, b: &V) -> bool
+where
+    V: Dom,
+    V::Key: Borrow<str>,
+{
+    // pairs still to compare, kept on a stack so deep nesting can't
+    // overflow the call stack
+    let mut stack: Vec<(&V, &V)> = alloc::vec![(a, b)];
+    while let Some((a, b)) = stack.pop() {
+        if let (Some(a), Some(b)) = (a.as_object(), b.as_object()) {
+            if a.len() != b.len() {
+                return false;
+            }
+            for (key, a) in a.iter() {
+                match b.get(key.borrow()) {
+                    Some(b) => stack.push((a, b)),
+                    None => return false,
+                }
+            }
+        } else if let (Some(a), Some(b)) = (a.as_array(), b.as_array()) {
+            if a.len() != b.len() {
+                return false;
+            }
+            stack.extend(a.iter().zip(b.iter()));
+        } else if let (Some(a), Some(b)) = (a.static_node(), b.static_node()) {
+            if !static_eq(a, b) {
+                return false;
+            }
+        } else if let (Some(a), Some(b)) = (a.as_str(), b.as_str()) {
+            if a != b {
+                return false;
+            }
+        } else {
+            #[cfg(feature = "bytes")]
+            {
+                if a.bytes().is_some() && a.bytes() == b.bytes() {
+                    continue;
+                }
+            }
+            return false;
+        }
+    }
+    true
+}
+
+/// Compares static nodes the way `hash_static` hashes them
+fn static_eq(a: &StaticNode, b: &StaticNode) -> bool {
+    match (a, b) {
+        (StaticNode::F64(a), StaticNode::F64(b)) => {
+            fold_zero(*a).to_bits() == fold_zero(*b).to_bits()
+        }
+        (StaticNode::F64(_), _) | (_, StaticNode::F64(_)) => false,
+        (StaticNode::Null, StaticNode::Null) => true,
+        (StaticNode::Bool(a), StaticNode::Bool(b)) => a == b,
+        _ => match (integer(a), integer(b)) {
+            (Some(a), Some(b)) => a == b,
+            #[cfg(feature = "128bit")]
+            (None, None) => a == b,
+            _ => false,
+        },
+    }
+}
+
+/// Integers as `i128`, `None` for other nodes and `u128`s that don't fit
+fn integer(node: &StaticNode) -> Option<i128> {
+    match node {
+        StaticNode::I64(i) => Some(i128::from(*i)),
+        StaticNode::U64(u) => Some(i128::from(*u)),
+        #[cfg(feature = "128bit")]
+        StaticNode::I128(i) => Some(*i),
+        #[cfg(feature = "128bit")]
+        StaticNode::U128(u) => core::convert::TryFrom::try_from(*u).ok(),
+        _ => None,
+    }
+}
+
+// `0.0 == -0.0`
+fn fold_zero(f: f64) -> f64 {
+    if f == 0.0 {
+        0.0
+    } else {
+        f
+    }
+}
+
+/// What's left to do for `hash_value`
+enum Todo<'v, V> {
+    Value(&'v V),
+    Key(&'v str),
+    /// Adds the hash of the entry to the sum of its object
+    EndEntry,
+    /// Writes the sum of the entries of the object
+    EndObject,
+}
+
+fn hash_value<V, H>(value: &V, state: &mut H)
+where
+    V: Dom,
+    V::Key: Borrow<str>,
+    H: Hasher,
+{
+    // kept on a stack like the pairs in `exact_eq`. Objects are hashed
+    // independent of the order of their entries, each entry is hashed on
+    // its own and the results are summed up, `objects` holds the sum and
+    // the hasher of the current entry for every object we are in.
+    let mut todo = alloc::vec![Todo::Value(value)];
+    let mut objects: Vec<(u64, EntryHasher)> = Vec::new();
+    while let Some(next) = todo.pop() {
+        match next {
+            Todo::Value(value) => {
+                let mut out = sink(&mut objects, state);
+                if let Some(s) = value.static_node() {
+                    hash_static(s, &mut out);
+                } else if let Some(s) = value.as_str() {
+                    out.write_u8(STRING);
+                    s.hash(&mut out);
+                } else if let Some(a) = value.as_array() {
+                    out.write_u8(ARRAY);
+                    out.write_usize(a.len());
+                    // the elements are popped in reverse
+                    let first = todo.len();
+                    todo.extend(a.iter().map(Todo::Value));
+                    todo[first..].reverse();
+                } else if let Some(o) = value.as_object() {
+                    out.write_u8(OBJECT);
+                    out.write_usize(o.len());
+                    objects.push((0, EntryHasher::default()));
+                    todo.push(Todo::EndObject);
+                    for (key, value) in o.iter() {
+                        todo.push(Todo::EndEntry);
+                        todo.push(Todo::Value(value));
+                        todo.push(Todo::Key(key.borrow()));
+                    }
+                } else {
+                    #[cfg(feature = "bytes")]
+                    {
+                        if let Some(b) = value.bytes() {
+                            out.write_u8(BYTES);
+                            b.hash(&mut out);
+                        }
+                    }
+                }
+            }
+            Todo::Key(key) => key.hash(&mut sink(&mut objects, state)),
+            Todo::EndEntry => {
+                if let Some((sum, entry)) = objects.last_mut() {
+                    *sum = sum.wrapping_add(entry.finish());
+                    *entry = EntryHasher::default();
+                }
+            }
+            Todo::EndObject => {
+                if let Some((sum, _)) = objects.pop() {
+                    sink(&mut objects, state).write_u64(sum);
+                }
+            }
+        }
+    }
+}
+
+/// Where `hash_value` writes to, the hasher of the entry of the innermost
+/// object or `state` outside of objects
+fn sink<'a, H: Hasher>(
+    objects: &'a mut [(u64, EntryHasher)],
+    state: &'a mut H,
+) -> &'a mut dyn Hasher {
+    match objects.last_mut() {
+        Some((_, entry)) => entry,
+        None => state,
+    }
+}
+
+/// Hashes a static node consistently with `static_eq`, integers are
+/// hashed by value so `I64(1)` and `U64(1)` hash the same. Floats are
+/// hashed by their bits with `-0.0` folded into `0.0`.
+fn hash_static<H: Hasher>(node: &StaticNode, state: &mut H) {
+    match node {
+        StaticNode::Null => state.write_u8(NULL),
+        StaticNode::Bool(b) => {
+            state.write_u8(BOOL);
+            b.hash(state);
+        }
+        StaticNode::I64(i) => hash_int(i128::from(*i), state),
+        StaticNode::U64(u) => hash_int(i128::from(*u), state),
+        #[cfg(feature = "128bit")]
+        StaticNode::I128(i) => hash_int(*i, state),
+        #[cfg(feature = "128bit")]
+        StaticNode::U128(u) => {
+            if let Ok(i) = core::convert::TryFrom::try_from(*u) {
+                hash_int(i, state);
+            } else {
+                state.write_u8(INT);
+                state.write_u128(*u);
+            }
+        }
+        StaticNode::F64(f) => {
+            state.write_u8(FLOAT);
+            state.write_u64(fold_zero(*f).to_bits());
+        }
+    }
+}
+
+fn hash_int<H: Hasher>(i: i128, state: &mut H) {
+    state.write_u8(INT);
+    state.write_i128(i);
+}
+
+/// FNV-1a, the entry hashes have to be the same no matter which hasher
+/// the value is hashed into, so they can't use that one
+struct EntryHasher(u64);
+
+impl Default for EntryHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for EntryHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= u64::from(*b);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ExactValue;
+    use crate::prelude::*;
+    use crate::{to_borrowed_value, to_owned_value, OwnedValue, StaticNode};
+    use core::hash::{Hash, Hasher};
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashSet;
+
+    fn hash<T: Hash>(v: &T) -> u64 {
+        let mut h = DefaultHasher::new();
+        v.hash(&mut h);
+        h.finish()
+    }
+
+    fn owned(s: &str) -> ExactValue<OwnedValue> {
+        ExactValue(to_owned_value(&mut s.as_bytes().to_vec()).unwrap())
+    }
+
+    #[test]
+    fn equal_values_hash_equal() {
+        let pairs = [
+            (
+                r#"{"a": 1, "b": [true, null], "c": {"d": "e", "f": 2.5}}"#,
+                r#"{"c": {"f": 2.5, "d": "e"}, "b": [true, null], "a": 1}"#,
+            ),
+            ("0.0", "-0.0"),
+            ("[]", "[ ]"),
+        ];
+        for (a, b) in &pairs {
+            let (a, b) = (owned(a), owned(b));
+            assert_eq!(a, b);
+            assert_eq!(hash(&a), hash(&b));
+            let mut d = b.0.encode().into_bytes();
+            let borrowed = ExactValue(to_borrowed_value(&mut d).unwrap());
+            assert_eq!(hash(&a), hash(&borrowed));
+        }
+        let i = ExactValue(OwnedValue::Static(StaticNode::I64(7)));
+        let u = ExactValue(OwnedValue::Static(StaticNode::U64(7)));
+        assert_eq!(i, u);
+        assert_eq!(hash(&i), hash(&u));
+    }
+
+    #[test]
+    fn floats_are_exact() {
+        // `==` on values takes these as the same, which isn't transitive
+        let a = owned("1.0");
+        let b = owned("1.0000000000000002");
+        assert_eq!(a.0, b.0);
+        assert_ne!(a, b);
+        let nan = ExactValue(OwnedValue::from(f64::NAN));
+        assert_eq!(nan, nan.clone());
+        assert_eq!(hash(&nan), hash(&nan.clone()));
+        assert_ne!(owned("1"), owned("1.0"));
+    }
+
+    #[test]
+    fn different_values_hash_different() {
+        let values = [
+            "null",
+            "false",
+            "true",
+            "0",
+            "1",
+            "-1",
+            "1.0",
+            "\"1\"",
+            "[]",
+            "{}",
+            "[null]",
+            "[[]]",
+            "[{}]",
+            r#"{"a": null}"#,
+            r#"{"a": 1}"#,
+            r#"{"b": 1}"#,
+            r#"{"a": 1, "b": 2}"#,
+            r#"{"a": 2, "b": 1}"#,
+            "[1, 2]",
+            "[2, 1]",
+            "18446744073709551615",
+        ];
+        let hashes: HashSet<u64> = values.iter().map(|v| hash(&owned(v))).collect();
+        assert_eq!(hashes.len(), values.len());
+    }
+
+    #[test]
+    fn dedup() {
+        let docs = [
+            r#"{"id": 1, "tags": ["a"]}"#,
+            r#"{"tags": ["a"], "id": 1}"#,
+            r#"{"id": 2, "tags": ["a"]}"#,
+            r#"{"id": 1, "tags": ["a"]}"#,
+        ];
+        let set: HashSet<ExactValue<OwnedValue>> = docs.iter().map(|d| owned(d)).collect();
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&owned(r#"{"tags": ["a"], "id": 2}"#)));
+    }
+
+    #[test]
+    fn deep_nesting() {
+        use crate::value::deep::{nested, DEPTH};
+        let d = nested(DEPTH, "null");
+        let a = owned(&d);
+        let b = owned(&d);
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+        let mut set = HashSet::new();
+        set.insert(a);
+        assert!(set.contains(&b));
+        set.into_iter().for_each(|v| v.0.dismantle());
+        b.0.dismantle();
+    }
+}
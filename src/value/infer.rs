@@ -0,0 +1,407 @@
+use super::builder::ObjectBuilder;
+use super::jtd::is_timestamp;
+use crate::prelude::*;
+use crate::OwnedValue;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+
+/// The integer types of JTD with their smallest and largest value, the
+/// first one an integer field fits is used
+const INT_TYPES: [(&str, f64, f64); 6] = [
+    ("uint8", 0.0, 255.0),
+    ("int8", -128.0, 127.0),
+    ("uint16", 0.0, 65_535.0),
+    ("int16", -32_768.0, 32_767.0),
+    ("uint32", 0.0, 4_294_967_295.0),
+    ("int32", -2_147_483_648.0, 2_147_483_647.0),
+];
+
+/// What was seen at one place of the samples
+#[derive(Debug, Clone, Default)]
+struct Shape {
+    nulls: usize,
+    bools: usize,
+    integers: usize,
+    /// Smallest and largest integer
+    range: (f64, f64),
+    floats: usize,
+    strings: usize,
+    /// Strings that are RFC 3339 timestamps
+    timestamps: usize,
+    arrays: usize,
+    /// The elements of all arrays, `None` if every array was empty
+    elements: Option<Box<Shape>>,
+    objects: usize,
+    /// The keys of all objects
+    fields: BTreeMap<String, Shape>,
+}
+
+impl Shape {
+    fn count(&self) -> usize {
+        self.nulls
+            + self.bools
+            + self.integers
+            + self.floats
+            + self.strings
+            + self.arrays
+            + self.objects
+    }
+
+    fn add<V>(&mut self, value: &V)
+    where
+        V: ValueTrait,
+        V::Key: Borrow<str>,
+    {
+        if let Some(array) = value.as_array() {
+            self.arrays += 1;
+            for v in array.iter() {
+                self.elements.get_or_insert_with(Box::default).add(v);
+            }
+        } else if let Some(object) = value.as_object() {
+            self.objects += 1;
+            for (key, v) in object.iter() {
+                let key: &str = key.borrow();
+                if let Some(field) = self.fields.get_mut(key) {
+                    field.add(v);
+                } else {
+                    let mut field = Self::default();
+                    field.add(v);
+                    self.fields.insert(key.to_string(), field);
+                }
+            }
+        } else if let Some(s) = value.as_str() {
+            self.strings += 1;
+            if is_timestamp(s) {
+                self.timestamps += 1;
+            }
+        } else if value.is_null() {
+            self.nulls += 1;
+        } else if value.is_bool() {
+            self.bools += 1;
+        } else if value.is_float() {
+            self.floats += 1;
+        } else if let Some(n) = value.cast_f64() {
+            self.range = if self.integers == 0 {
+                (n, n)
+            } else {
+                (self.range.0.min(n), self.range.1.max(n))
+            };
+            self.integers += 1;
+        }
+    }
+
+    fn json_schema(&self) -> OwnedValue {
+        let mut types = Vec::new();
+        for (count, name) in &[
+            (self.nulls, "null"),
+            (self.bools, "boolean"),
+            (self.integers, "integer"),
+            (self.floats, "number"),
+            (self.strings, "string"),
+            (self.arrays, "array"),
+            (self.objects, "object"),
+        ] {
+            if *count > 0 {
+                types.push(OwnedValue::from(*name));
+            }
+        }
+        // `number` takes integers as well
+        if self.floats > 0 && self.integers > 0 {
+            types.retain(|t| t != "integer");
+        }
+        let mut schema = match types.len() {
+            0 => ObjectBuilder::new(),
+            1 => ObjectBuilder::new().insert("type", types.remove(0)),
+            _ => ObjectBuilder::new().insert("type", types),
+        }
+        .insert_if(
+            self.strings > 0 && self.timestamps == self.strings,
+            "format",
+            "date-time",
+        )
+        .insert_opt("items", self.elements.as_ref().map(|e| e.json_schema()));
+        if self.objects > 0 {
+            let required: Vec<OwnedValue> = self
+                .fields
+                .iter()
+                .filter(|(_, field)| field.count() == self.objects)
+                .map(|(key, _)| OwnedValue::from(key.as_str()))
+                .collect();
+            schema = schema
+                .insert(
+                    "properties",
+                    properties(self.fields.iter(), Self::json_schema),
+                )
+                .insert("required", required);
+        }
+        schema.finish()
+    }
+
+    fn jtd(&self) -> OwnedValue {
+        let schema = ObjectBuilder::new();
+        let kinds = [
+            self.bools,
+            self.integers + self.floats,
+            self.strings,
+            self.arrays,
+            self.objects,
+        ];
+        // JTD has no unions, a place with several kinds takes anything
+        if kinds.iter().filter(|count| **count > 0).count() != 1 {
+            return schema.finish();
+        }
+        let schema = if self.bools > 0 {
+            schema.insert("type", "boolean")
+        } else if self.integers + self.floats > 0 {
+            let (min, max) = self.range;
+            let int = INT_TYPES
+                .iter()
+                .find(|(_, lo, hi)| self.floats == 0 && *lo <= min && max <= *hi);
+            schema.insert("type", int.map_or("float64", |(name, _, _)| *name))
+        } else if self.strings > 0 {
+            let timestamps = self.timestamps == self.strings;
+            schema.insert("type", if timestamps { "timestamp" } else { "string" })
+        } else if self.arrays > 0 {
+            let elements = self
+                .elements
+                .as_ref()
+                .map_or_else(OwnedValue::object, |e| e.jtd());
+            schema.insert("elements", elements)
+        } else {
+            let (required, optional): (Vec<_>, Vec<_>) = self
+                .fields
+                .iter()
+                .partition(|(_, field)| field.count() == self.objects);
+            // one of the two has to be there for the properties form
+            let schema = schema.insert_if(
+                !required.is_empty() || optional.is_empty(),
+                "properties",
+                properties(required.into_iter(), Self::jtd),
+            );
+            if optional.is_empty() {
+                schema
+            } else {
+                schema.insert(
+                    "optionalProperties",
+                    properties(optional.into_iter(), Self::jtd),
+                )
+            }
+        };
+        schema.insert_if(self.nulls > 0, "nullable", true).finish()
+    }
+}
+
+fn properties<'a, I, F>(fields: I, f: F) -> OwnedValue
+where
+    I: Iterator<Item = (&'a String, &'a Shape)>,
+    F: Fn(&Shape) -> OwnedValue,
+{
+    fields
+        .fold(ObjectBuilder::new(), |o, (key, field)| {
+            o.insert(key.as_str(), f(field))
+        })
+        .finish()
+}
+
+/// The structure of sample values, from `infer_schema`. It can be
+/// written as a JSON Schema or a JSON Type Definition.
+#[derive(Debug, Clone, Default)]
+pub struct InferredSchema {
+    shape: Shape,
+}
+
+impl InferredSchema {
+    /// Starts without any samples
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds one more sample
+    pub fn add<V>(&mut self, value: &V)
+    where
+        V: ValueTrait,
+        V::Key: Borrow<str>,
+    {
+        self.shape.add(value);
+    }
+
+    /// Number of samples added
+    #[must_use]
+    pub fn samples(&self) -> usize {
+        self.shape.count()
+    }
+
+    /// Writes the structure as a JSON Schema. Every place gets the
+    /// `type`s seen there, `number` if integers and floats were mixed,
+    /// and `"format": "date-time"` if all its strings were timestamps.
+    /// Objects list their keys under `properties` and the ones every
+    /// object had under `required`, arrays have the merged shape of all
+    /// their elements under `items`.
+    ///
+    /// ```rust
+    /// use simd_json::{infer_schema, json, Schema};
+    /// let samples = [json!({"id": 1, "tags": ["a"]}), json!({"id": 2.5})];
+    /// let schema = infer_schema(&samples).to_json_schema();
+    /// assert_eq!(
+    ///     schema,
+    ///     json!({
+    ///         "type": "object",
+    ///         "properties": {
+    ///             "id": {"type": "number"},
+    ///             "tags": {"type": "array", "items": {"type": "string"}}
+    ///         },
+    ///         "required": ["id"]
+    ///     })
+    /// );
+    /// assert!(Schema::compile(&schema).unwrap().is_valid(&json!({"id": 3})));
+    /// ```
+    #[must_use]
+    pub fn to_json_schema(&self) -> OwnedValue {
+        self.shape.json_schema()
+    }
+
+    /// Writes the structure as a JSON Type Definition. Integers get the
+    /// smallest integer type their range fits, other numbers `float64`,
+    /// places that were `null` at times are `nullable` and keys that not
+    /// every object had go to `optionalProperties`. JTD has no unions, so
+    /// a place that held values of different kinds, like strings and
+    /// numbers, becomes the empty schema that takes anything.
+    ///
+    /// ```rust
+    /// use simd_json::{infer_schema, json, JtdSchema};
+    /// let samples = [
+    ///     json!({"id": 1, "at": "2024-01-02T03:04:05Z", "v": "x"}),
+    ///     json!({"id": 300, "at": null, "v": 1}),
+    /// ];
+    /// let schema = infer_schema(&samples).to_jtd();
+    /// assert_eq!(
+    ///     schema,
+    ///     json!({"properties": {
+    ///         "id": {"type": "uint16"},
+    ///         "at": {"type": "timestamp", "nullable": true},
+    ///         "v": {}
+    ///     }})
+    /// );
+    /// assert!(JtdSchema::compile(&schema).unwrap().is_valid(&samples[1]));
+    /// ```
+    #[must_use]
+    pub fn to_jtd(&self) -> OwnedValue {
+        self.shape.jtd()
+    }
+}
+
+/// Infers the structure of sample values, owned or borrowed, by merging
+/// the types seen at every place, which keys are optional and the shape
+/// of array elements across all samples. Useful to get to know large
+/// datasets, the result can be written as a JSON Schema or a JSON Type
+/// Definition and compiled to validate more documents.
+///
+/// ```rust
+/// use simd_json::prelude::*;
+/// let mut d = br#"[{"name": "a", "age": 30}, {"name": "b", "age": null}]"#.to_vec();
+/// let v = simd_json::to_borrowed_value(&mut d).unwrap();
+/// let inferred = simd_json::infer_schema(v.as_array().unwrap());
+/// assert_eq!(inferred.samples(), 2);
+/// assert_eq!(
+///     inferred.to_json_schema()["properties"]["age"],
+///     simd_json::json!({"type": ["null", "integer"]})
+/// );
+/// ```
+pub fn infer_schema<'v, V, I>(values: I) -> InferredSchema
+where
+    I: IntoIterator<Item = &'v V>,
+    V: ValueTrait + 'v,
+    V::Key: Borrow<str>,
+{
+    let mut inferred = InferredSchema::new();
+    for value in values {
+        inferred.add(value);
+    }
+    inferred
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{JtdSchema, Schema};
+
+    #[test]
+    fn shapes() {
+        let samples = [
+            json!({"a": [1, -1], "b": {"c": true}, "d": "x", "e": []}),
+            json!({"a": [], "b": {"c": false, "f": 1.5}, "d": null, "e": [[1], "y"]}),
+        ];
+        let inferred = infer_schema(&samples);
+        assert_eq!(
+            inferred.to_json_schema(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "a": {"type": "array", "items": {"type": "integer"}},
+                    "b": {
+                        "type": "object",
+                        "properties": {"c": {"type": "boolean"}, "f": {"type": "number"}},
+                        "required": ["c"]
+                    },
+                    "d": {"type": ["null", "string"]},
+                    "e": {
+                        "type": "array",
+                        "items": {"type": ["string", "array"], "items": {"type": "integer"}}
+                    }
+                },
+                "required": ["a", "b", "d", "e"]
+            })
+        );
+        assert_eq!(
+            inferred.to_jtd(),
+            json!({"properties": {
+                "a": {"elements": {"type": "int8"}},
+                "b": {
+                    "properties": {"c": {"type": "boolean"}},
+                    "optionalProperties": {"f": {"type": "float64"}}
+                },
+                "d": {"type": "string", "nullable": true},
+                "e": {"elements": {}}
+            }})
+        );
+        let schema = Schema::compile(&inferred.to_json_schema()).unwrap();
+        let jtd = JtdSchema::compile(&inferred.to_jtd()).unwrap();
+        for sample in &samples {
+            assert!(schema.is_valid(sample));
+            assert!(jtd.is_valid(sample));
+        }
+    }
+
+    #[test]
+    fn edge_cases() {
+        let none: [OwnedValue; 0] = [];
+        let inferred = infer_schema(&none);
+        assert_eq!(inferred.samples(), 0);
+        assert_eq!(inferred.to_json_schema(), json!({}));
+        assert_eq!(inferred.to_jtd(), json!({}));
+
+        let mut inferred = infer_schema(&[json!({}), json!({"a": 1})]);
+        assert_eq!(
+            inferred.to_jtd(),
+            json!({"optionalProperties": {"a": {"type": "uint8"}}})
+        );
+        inferred.add(&json!(null));
+        assert_eq!(inferred.samples(), 3);
+        assert_eq!(inferred.to_jtd()["nullable"], true);
+
+        let numbers = [OwnedValue::from(u64::MAX), json!(-1), json!(70_000)];
+        assert_eq!(infer_schema(&numbers).to_jtd(), json!({"type": "float64"}));
+        assert_eq!(
+            infer_schema(&numbers[1..]).to_jtd(),
+            json!({"type": "int32"})
+        );
+        assert_eq!(
+            infer_schema(&[json!({})]).to_jtd(),
+            json!({"properties": {}})
+        );
+    }
+}
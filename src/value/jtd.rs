@@ -0,0 +1,896 @@
+//! Validation of values against a [JSON Type Definition](https://jsontypedef.com)
+//! as specified by RFC 8927.
+//!
+//! JTD is a lot simpler than JSON Schema: every schema has exactly one
+//! form, objects have a fixed set of properties and there is no
+//! combining of schemas, so checking a value is a single pass over it.
+//! Like `Schema` a `JtdSchema` is compiled once and checks owned or
+//! borrowed values or tapes as returned by `to_tape`, the tape is
+//! checked without building a DOM.
+//!
+//! Errors are reported the way the RFC describes them, as the JSON
+//! pointer of the rejected part of the value and of the part of the
+//! schema that rejected it.
+//!
+//! ```rust
+//! use simd_json::{json, JtdSchema};
+//! let schema = JtdSchema::compile(&json!({
+//!     "properties": {
+//!         "id": {"type": "uint32"},
+//!         "tags": {"elements": {"type": "string"}}
+//!     },
+//!     "optionalProperties": {"note": {"type": "string", "nullable": true}}
+//! }))
+//! .unwrap();
+//!
+//! let mut d = br#"{"id": 7, "tags": ["a"], "note": null}"#.to_vec();
+//! let tape = simd_json::to_tape(&mut d).unwrap();
+//! assert!(schema.is_valid_tape(&tape));
+//!
+//! let errors = schema.errors(&json!({"id": -1, "tags": ["a", 2], "x": 1}));
+//! let paths: Vec<_> = errors.iter().map(|e| (e.instance_path(), e.schema_path())).collect();
+//! assert_eq!(
+//!     paths,
+//!     [
+//!         ("/id", "/properties/id/type"),
+//!         ("/tags/1", "/properties/tags/elements/type"),
+//!         ("/x", ""),
+//!     ]
+//! );
+//! ```
+
+use super::flatten::{push_key, FlattenStyle};
+use super::schema::{is_whole, key_pointer, Instance, Kind, SchemaError, TapeValue};
+use crate::prelude::*;
+use crate::value::tape::Node;
+use crate::StaticNode;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use halfbrown::HashMap;
+
+/// Error for a value that doesn't match a JTD schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JtdError {
+    instance_path: String,
+    schema_path: String,
+}
+
+impl JtdError {
+    /// JSON pointer of the part of the value that was rejected, empty for
+    /// the value itself
+    #[must_use]
+    pub fn instance_path(&self) -> &str {
+        &self.instance_path
+    }
+
+    /// JSON pointer of the part of the schema that rejected it
+    #[must_use]
+    pub fn schema_path(&self) -> &str {
+        &self.schema_path
+    }
+}
+
+#[cfg_attr(tarpaulin, skip)]
+impl fmt::Display for JtdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Value at {:?} rejected by schema at {:?}",
+            self.instance_path, self.schema_path
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for JtdError {}
+
+/// Index of a compiled schema in `JtdSchema::nodes`
+type Id = usize;
+
+#[derive(Debug, Clone, Copy)]
+enum Type {
+    Boolean,
+    String,
+    Timestamp,
+    /// `float32` and `float64`, they take any number
+    Float,
+    /// The integer types with their smallest and largest value
+    Int(f64, f64),
+}
+
+impl Type {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "boolean" => Self::Boolean,
+            "string" => Self::String,
+            "timestamp" => Self::Timestamp,
+            "float32" | "float64" => Self::Float,
+            "int8" => Self::Int(-128.0, 127.0),
+            "uint8" => Self::Int(0.0, 255.0),
+            "int16" => Self::Int(-32_768.0, 32_767.0),
+            "uint16" => Self::Int(0.0, 65_535.0),
+            "int32" => Self::Int(-2_147_483_648.0, 2_147_483_647.0),
+            "uint32" => Self::Int(0.0, 4_294_967_295.0),
+            _ => return None,
+        })
+    }
+
+    fn accepts(self, kind: Kind) -> bool {
+        match (self, kind) {
+            (Self::Boolean, Kind::Static(StaticNode::Bool(_)))
+            | (Self::String, Kind::String(_)) => true,
+            (Self::Timestamp, Kind::String(s)) => is_timestamp(s),
+            (Self::Float, Kind::Static(s)) => s.cast_f64().is_some(),
+            (Self::Int(min, max), Kind::Static(s)) => {
+                matches!(s.cast_f64(), Some(n) if is_whole(n) && n >= min && n <= max)
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Form {
+    Empty,
+    Ref(Id),
+    Type(Type),
+    Enum(Vec<String>),
+    Elements(Id),
+    Properties {
+        required: Vec<(String, Id)>,
+        optional: Vec<(String, Id)>,
+        additional: bool,
+        /// If `properties` is given, errors for values that aren't
+        /// objects point to it instead of `optionalProperties`
+        has_required: bool,
+    },
+    Values(Id),
+    Discriminator {
+        tag: String,
+        mapping: Vec<(String, Id)>,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct JtdNode {
+    form: Form,
+    nullable: bool,
+    /// Where in the schema document the node comes from
+    pointer: String,
+}
+
+/// A compiled JSON Type Definition, see the module documentation
+#[derive(Debug, Clone)]
+pub struct JtdSchema {
+    nodes: Vec<JtdNode>,
+    root: Id,
+}
+
+impl JtdSchema {
+    /// Compiles a schema from its JSON document.
+    ///
+    /// # Errors
+    ///
+    /// if the document isn't a valid JTD schema, with the pointer of the
+    /// offending part, or a `ref` names a definition that doesn't exist
+    pub fn compile<V>(schema: &V) -> Result<Self, SchemaError>
+    where
+        V: ValueTrait,
+        V::Key: Borrow<str>,
+    {
+        let mut compiler = Compiler {
+            nodes: Vec::new(),
+            definitions: HashMap::new(),
+        };
+        if let Some(definitions) = schema.get("definitions") {
+            let definitions = definitions
+                .as_object()
+                .ok_or_else(|| SchemaError::Invalid("/definitions".to_string()))?;
+            // every definition gets its id first so `ref`s can point to
+            // the ones after them and to themselves
+            for name in definitions.keys() {
+                let name: &str = name.borrow();
+                let id = compiler.reserve(key_pointer("/definitions", name));
+                compiler.definitions.insert(name.to_string(), id);
+            }
+            for (name, definition) in definitions.iter() {
+                let name: &str = name.borrow();
+                let id = compiler.definitions[name];
+                let pointer = compiler.nodes[id].pointer.clone();
+                compiler.nodes[id] = compiler.node(definition, &pointer, false)?;
+            }
+        }
+        let root = compiler.reserve(String::new());
+        compiler.nodes[root] = compiler.node(schema, "", true)?;
+        compiler.check_refs()?;
+        Ok(Self {
+            nodes: compiler.nodes,
+            root,
+        })
+    }
+
+    /// Validates a value, owned or borrowed, and returns the first error.
+    ///
+    /// ```rust
+    /// use simd_json::{json, JtdSchema};
+    /// let schema = JtdSchema::compile(&json!({"enum": ["a", "b"]})).unwrap();
+    /// assert!(schema.validate(&json!("a")).is_ok());
+    /// let err = schema.validate(&json!("c")).unwrap_err();
+    /// assert_eq!(err.to_string(), r#"Value at "" rejected by schema at "/enum""#);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// if the value doesn't match
+    pub fn validate<V>(&self, value: &V) -> Result<(), JtdError>
+    where
+        V: ValueTrait,
+        V::Key: Borrow<str>,
+    {
+        first(self.run(value, 1))
+    }
+
+    /// Validates a value, owned or borrowed, and returns all errors, in
+    /// the order the value is visited
+    #[must_use]
+    pub fn errors<V>(&self, value: &V) -> Vec<JtdError>
+    where
+        V: ValueTrait,
+        V::Key: Borrow<str>,
+    {
+        self.run(value, usize::MAX)
+    }
+
+    /// Checks if a value, owned or borrowed, matches the schema
+    #[must_use]
+    pub fn is_valid<V>(&self, value: &V) -> bool
+    where
+        V: ValueTrait,
+        V::Key: Borrow<str>,
+    {
+        self.run(value, 1).is_empty()
+    }
+
+    /// Validates the document of a tape as returned by `to_tape` without
+    /// building a DOM, and returns the first error. With duplicate keys
+    /// the first value of a key is the one that is required.
+    ///
+    /// ```rust
+    /// use simd_json::{json, JtdSchema};
+    /// let schema = JtdSchema::compile(&json!({"values": {"type": "int8"}})).unwrap();
+    /// let mut d = br#"{"a": 1, "b": 300}"#.to_vec();
+    /// let tape = simd_json::to_tape(&mut d).unwrap();
+    /// assert_eq!(schema.validate_tape(&tape).unwrap_err().instance_path(), "/b");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// if the document doesn't match, or the tape holds no document
+    pub fn validate_tape(&self, tape: &[Node]) -> Result<(), JtdError> {
+        first(self.run_tape(tape, 1))
+    }
+
+    /// Validates the document of a tape and returns all errors
+    #[must_use]
+    pub fn errors_tape(&self, tape: &[Node]) -> Vec<JtdError> {
+        self.run_tape(tape, usize::MAX)
+    }
+
+    /// Checks if the document of a tape matches the schema
+    #[must_use]
+    pub fn is_valid_tape(&self, tape: &[Node]) -> bool {
+        self.run_tape(tape, 1).is_empty()
+    }
+
+    fn run<'a, I: Instance<'a>>(&self, value: I, max: usize) -> Vec<JtdError> {
+        let mut validator = Validator {
+            schema: self,
+            path: String::new(),
+            errors: Vec::new(),
+            max,
+        };
+        let _ = validator.check(self.root, value, None);
+        validator.errors
+    }
+
+    fn run_tape(&self, tape: &[Node], max: usize) -> Vec<JtdError> {
+        match TapeValue::root(tape) {
+            Some(root) => self.run(root, max),
+            None => alloc::vec![JtdError {
+                instance_path: String::new(),
+                schema_path: String::new(),
+            }],
+        }
+    }
+}
+
+fn first(errors: Vec<JtdError>) -> Result<(), JtdError> {
+    match errors.into_iter().next() {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+/// Returned once enough errors are collected, to stop early
+struct Full;
+
+struct Validator<'s> {
+    schema: &'s JtdSchema,
+    /// JSON pointer of the value being checked
+    path: String,
+    errors: Vec<JtdError>,
+    max: usize,
+}
+
+impl Validator<'_> {
+    fn fail(&mut self, pointer: &str, keyword: &str) -> Result<(), Full> {
+        let mut schema_path = pointer.to_string();
+        schema_path.push_str(keyword);
+        self.errors.push(JtdError {
+            instance_path: self.path.clone(),
+            schema_path,
+        });
+        if self.errors.len() >= self.max {
+            Err(Full)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Runs `f` with the path of the entry `key` of the current value
+    fn at_key<F>(&mut self, key: &str, f: F) -> Result<(), Full>
+    where
+        F: FnOnce(&mut Self) -> Result<(), Full>,
+    {
+        let len = self.path.len();
+        push_key(&mut self.path, key, FlattenStyle::Pointer);
+        let res = f(self);
+        self.path.truncate(len);
+        res
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn check<'a, I: Instance<'a>>(
+        &mut self,
+        id: Id,
+        value: I,
+        tag: Option<&str>,
+    ) -> Result<(), Full> {
+        let node = &self.schema.nodes[id];
+        let kind = value.kind();
+        if node.nullable && matches!(kind, Kind::Static(StaticNode::Null)) {
+            return Ok(());
+        }
+        let pointer = node.pointer.as_str();
+        match &node.form {
+            Form::Empty => Ok(()),
+            Form::Ref(id) => self.check(*id, value, None),
+            Form::Type(t) => {
+                if t.accepts(kind) {
+                    Ok(())
+                } else {
+                    self.fail(pointer, "/type")
+                }
+            }
+            Form::Enum(values) => match kind {
+                Kind::String(s) if values.iter().any(|v| v == s) => Ok(()),
+                _ => self.fail(pointer, "/enum"),
+            },
+            Form::Elements(schema) => {
+                if !matches!(kind, Kind::Array(_)) {
+                    return self.fail(pointer, "/elements");
+                }
+                for (idx, element) in value.elements().enumerate() {
+                    let len = self.path.len();
+                    self.path.push('/');
+                    self.path.push_str(&idx.to_string());
+                    let res = self.check(*schema, element, None);
+                    self.path.truncate(len);
+                    res?;
+                }
+                Ok(())
+            }
+            Form::Properties {
+                required,
+                optional,
+                additional,
+                has_required,
+            } => {
+                if !matches!(kind, Kind::Object(_)) {
+                    let keyword = if *has_required {
+                        "/properties"
+                    } else {
+                        "/optionalProperties"
+                    };
+                    return self.fail(pointer, keyword);
+                }
+                for (name, schema) in required {
+                    match value.field(name) {
+                        Some(v) => self.at_key(name, |this| this.check(*schema, v, None))?,
+                        None => {
+                            self.fail(&key_pointer(pointer, "properties"), &key_pointer("", name))?;
+                        }
+                    }
+                }
+                for (name, schema) in optional {
+                    if let Some(v) = value.field(name) {
+                        self.at_key(name, |this| this.check(*schema, v, None))?;
+                    }
+                }
+                if !additional {
+                    for (key, _) in value.entries() {
+                        let known = tag == Some(key)
+                            || required.iter().chain(optional).any(|(name, _)| name == key);
+                        if !known {
+                            self.at_key(key, |this| this.fail(pointer, ""))?;
+                        }
+                    }
+                }
+                Ok(())
+            }
+            Form::Values(schema) => {
+                if !matches!(kind, Kind::Object(_)) {
+                    return self.fail(pointer, "/values");
+                }
+                for (key, v) in value.entries() {
+                    self.at_key(key, |this| this.check(*schema, v, None))?;
+                }
+                Ok(())
+            }
+            Form::Discriminator { tag, mapping } => {
+                if !matches!(kind, Kind::Object(_)) {
+                    return self.fail(pointer, "/discriminator");
+                }
+                let tag_value = match value.field(tag) {
+                    Some(tag_value) => tag_value,
+                    None => return self.fail(pointer, "/discriminator"),
+                };
+                match tag_value.kind() {
+                    Kind::String(s) => match mapping.iter().find(|(name, _)| name == s) {
+                        Some((_, schema)) => self.check(*schema, value, Some(tag)),
+                        None => self.at_key(tag, |this| this.fail(pointer, "/mapping")),
+                    },
+                    _ => self.at_key(tag, |this| this.fail(pointer, "/discriminator")),
+                }
+            }
+        }
+    }
+}
+
+struct Compiler {
+    nodes: Vec<JtdNode>,
+    definitions: HashMap<String, Id>,
+}
+
+impl Compiler {
+    fn reserve(&mut self, pointer: String) -> Id {
+        self.nodes.push(JtdNode {
+            form: Form::Empty,
+            nullable: false,
+            pointer,
+        });
+        self.nodes.len() - 1
+    }
+
+    fn subschema<V>(&mut self, schema: &V, pointer: String) -> Result<Id, SchemaError>
+    where
+        V: ValueTrait,
+        V::Key: Borrow<str>,
+    {
+        let node = self.node(schema, &pointer, false)?;
+        let id = self.reserve(pointer);
+        self.nodes[id] = node;
+        Ok(id)
+    }
+
+    fn children<V>(&mut self, schemas: &V, pointer: &str) -> Result<Vec<(String, Id)>, SchemaError>
+    where
+        V: ValueTrait,
+        V::Key: Borrow<str>,
+    {
+        let schemas = schemas
+            .as_object()
+            .ok_or_else(|| SchemaError::Invalid(pointer.to_string()))?;
+        let mut children = Vec::with_capacity(schemas.len());
+        for (name, schema) in schemas.iter() {
+            let name: &str = name.borrow();
+            children.push((
+                name.to_string(),
+                self.subschema(schema, key_pointer(pointer, name))?,
+            ));
+        }
+        Ok(children)
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn node<V>(&mut self, schema: &V, pointer: &str, root: bool) -> Result<JtdNode, SchemaError>
+    where
+        V: ValueTrait,
+        V::Key: Borrow<str>,
+    {
+        let invalid = |key: &str| SchemaError::Invalid(key_pointer(pointer, key));
+        let object = schema
+            .as_object()
+            .ok_or_else(|| SchemaError::Invalid(pointer.to_string()))?;
+        let mut nullable = false;
+        // the forms that have keywords in the schema
+        let mut forms = 0;
+        for (key, value) in object.iter() {
+            match key.borrow() {
+                "definitions" if root => {}
+                "metadata" if value.is_object() => {}
+                "nullable" => nullable = value.as_bool().ok_or_else(|| invalid("nullable"))?,
+                "ref" | "type" | "enum" | "elements" | "properties" | "values"
+                | "discriminator" => forms += 1,
+                // these come with `properties` and `discriminator`
+                "optionalProperties" | "additionalProperties" | "mapping" => {}
+                key => return Err(invalid(key)),
+            }
+        }
+        let properties = schema.get("properties");
+        let optional = schema.get("optionalProperties");
+        if properties.is_none() && optional.is_some() {
+            forms += 1;
+        }
+        if forms > 1 {
+            return Err(SchemaError::Invalid(pointer.to_string()));
+        }
+        if properties.is_none()
+            && optional.is_none()
+            && schema.get("additionalProperties").is_some()
+        {
+            return Err(invalid("additionalProperties"));
+        }
+        if schema.get("discriminator").is_some() != schema.get("mapping").is_some() {
+            return Err(SchemaError::Invalid(pointer.to_string()));
+        }
+
+        let form = if let Some(name) = schema.get("ref") {
+            let name = name.as_str().ok_or_else(|| invalid("ref"))?;
+            let id = self
+                .definitions
+                .get(name)
+                .ok_or_else(|| SchemaError::Unresolved(name.to_string()))?;
+            Form::Ref(*id)
+        } else if let Some(name) = schema.get("type") {
+            Form::Type(
+                name.as_str()
+                    .and_then(Type::parse)
+                    .ok_or_else(|| invalid("type"))?,
+            )
+        } else if let Some(values) = schema.get("enum") {
+            let values = values
+                .as_array()
+                .filter(|values| !values.is_empty())
+                .and_then(|values| {
+                    values
+                        .iter()
+                        .map(|v| v.as_str().map(String::from))
+                        .collect::<Option<Vec<_>>>()
+                })
+                .ok_or_else(|| invalid("enum"))?;
+            if values
+                .iter()
+                .enumerate()
+                .any(|(idx, v)| values[..idx].contains(v))
+            {
+                return Err(invalid("enum"));
+            }
+            Form::Enum(values)
+        } else if let Some(elements) = schema.get("elements") {
+            Form::Elements(self.subschema(elements, key_pointer(pointer, "elements"))?)
+        } else if properties.is_some() || optional.is_some() {
+            let required = match properties {
+                Some(p) => self.children(p, &key_pointer(pointer, "properties"))?,
+                None => Vec::new(),
+            };
+            let optional = match optional {
+                Some(p) => self.children(p, &key_pointer(pointer, "optionalProperties"))?,
+                None => Vec::new(),
+            };
+            if let Some((name, _)) = optional
+                .iter()
+                .find(|(name, _)| required.iter().any(|(r, _)| r == name))
+            {
+                return Err(SchemaError::Invalid(key_pointer(
+                    &key_pointer(pointer, "optionalProperties"),
+                    name,
+                )));
+            }
+            let additional = match schema.get("additionalProperties") {
+                Some(a) => a.as_bool().ok_or_else(|| invalid("additionalProperties"))?,
+                None => false,
+            };
+            Form::Properties {
+                required,
+                optional,
+                additional,
+                has_required: properties.is_some(),
+            }
+        } else if let Some(values) = schema.get("values") {
+            Form::Values(self.subschema(values, key_pointer(pointer, "values"))?)
+        } else if let (Some(tag), Some(mapping)) =
+            (schema.get("discriminator"), schema.get("mapping"))
+        {
+            let tag = tag.as_str().ok_or_else(|| invalid("discriminator"))?;
+            let mapping_pointer = key_pointer(pointer, "mapping");
+            let mapping = self.children(mapping, &mapping_pointer)?;
+            for (name, id) in &mapping {
+                // the schemas of a mapping are objects that don't define the tag
+                let valid = match &self.nodes[*id] {
+                    JtdNode {
+                        form:
+                            Form::Properties {
+                                required, optional, ..
+                            },
+                        nullable: false,
+                        ..
+                    } => !required.iter().chain(optional).any(|(p, _)| p == tag),
+                    _ => false,
+                };
+                if !valid {
+                    return Err(SchemaError::Invalid(key_pointer(&mapping_pointer, name)));
+                }
+            }
+            Form::Discriminator {
+                tag: tag.to_string(),
+                mapping,
+            }
+        } else {
+            Form::Empty
+        };
+        Ok(JtdNode {
+            form,
+            nullable,
+            pointer: pointer.to_string(),
+        })
+    }
+
+    /// Rejects `ref`s that lead back to themselves without a schema that
+    /// checks anything in between
+    fn check_refs(&self) -> Result<(), SchemaError> {
+        for (start, node) in self.nodes.iter().enumerate() {
+            let mut id = start;
+            let mut steps = 0;
+            while let Form::Ref(next) = self.nodes[id].form {
+                id = next;
+                steps += 1;
+                if steps > self.nodes.len() {
+                    return Err(SchemaError::Invalid(node.pointer.clone()));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks for an RFC 3339 `date-time` like `1985-04-12T23:20:50.52Z`,
+/// leap seconds are accepted on any day
+pub(super) fn is_timestamp(s: &str) -> bool {
+    let b = s.as_bytes();
+    let number = |from: usize, to: usize| -> Option<u32> {
+        b.get(from..to)?.iter().try_fold(0, |n, c| {
+            if c.is_ascii_digit() {
+                Some(n * 10 + u32::from(c - b'0'))
+            } else {
+                None
+            }
+        })
+    };
+    let separators = b.len() > 19
+        && b[4] == b'-'
+        && b[7] == b'-'
+        && matches!(b[10], b'T' | b't')
+        && b[13] == b':'
+        && b[16] == b':';
+    if !separators {
+        return false;
+    }
+    let fields = [(0, 4), (5, 7), (8, 10), (11, 13), (14, 16), (17, 19)]
+        .iter()
+        .map(|(from, to)| number(*from, *to))
+        .collect::<Option<Vec<_>>>();
+    let (year, month, day, hour, minute, second) = match fields.as_deref() {
+        Some(&[year, month, day, hour, minute, second]) => (year, month, day, hour, minute, second),
+        _ => return false,
+    };
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        _ => return false,
+    };
+    if day == 0 || day > days || hour > 23 || minute > 59 || second > 60 {
+        return false;
+    }
+    let mut rest = &b[19..];
+    if let Some(fraction) = rest.strip_prefix(b".") {
+        let digits = fraction.iter().take_while(|c| c.is_ascii_digit()).count();
+        if digits == 0 {
+            return false;
+        }
+        rest = &fraction[digits..];
+    }
+    match rest {
+        [b'Z' | b'z'] => true,
+        [b'+' | b'-', h1, h2, b':', m1, m2] => {
+            let digits = [h1, h2, m1, m2].iter().all(|c| c.is_ascii_digit());
+            digits && (h1 - b'0') * 10 + (h2 - b'0') < 24 && (m1 - b'0') * 10 + (m2 - b'0') < 60
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::OwnedValue;
+
+    /// Checks the owned, borrowed and tape validation agree and returns
+    /// the instance and schema paths of the errors
+    fn errors(schema: &OwnedValue, json: &str) -> Vec<(String, String)> {
+        let schema = JtdSchema::compile(schema).unwrap();
+        let mut d = json.as_bytes().to_vec();
+        let owned = crate::to_owned_value(&mut d).unwrap();
+        let mut d = json.as_bytes().to_vec();
+        let borrowed = crate::to_borrowed_value(&mut d).unwrap();
+        let mut d = json.as_bytes().to_vec();
+        let tape = crate::to_tape(&mut d).unwrap();
+        let errors = schema.errors(&owned);
+        assert_eq!(errors, schema.errors(&borrowed), "{}", json);
+        assert_eq!(errors, schema.errors_tape(&tape), "{}", json);
+        assert_eq!(schema.validate(&owned).err(), errors.first().cloned());
+        errors
+            .into_iter()
+            .map(|e| (e.instance_path, e.schema_path))
+            .collect()
+    }
+
+    fn paths(paths: &[(&str, &str)]) -> Vec<(String, String)> {
+        paths
+            .iter()
+            .map(|(i, s)| ((*i).to_string(), (*s).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn types() {
+        let schema = json!({"elements": {"type": "uint8"}});
+        assert_eq!(errors(&schema, "[0, 255, 1.0]"), paths(&[]));
+        assert_eq!(
+            errors(&schema, "[-1, 256, 1.5, null]"),
+            paths(&[
+                ("/0", "/elements/type"),
+                ("/1", "/elements/type"),
+                ("/2", "/elements/type"),
+                ("/3", "/elements/type"),
+            ])
+        );
+        let schema = json!({"type": "timestamp", "nullable": true});
+        for ok in &[
+            r#""1985-04-12T23:20:50.52Z""#,
+            r#""1990-12-31t15:59:60-08:00""#,
+            r#""2000-02-29T00:00:00+00:00""#,
+            "null",
+        ] {
+            assert_eq!(errors(&schema, ok), paths(&[]), "{}", ok);
+        }
+        for bad in &[
+            r#""1985-04-12""#,
+            r#""1900-02-29T00:00:00Z""#,
+            r#""1985-04-12T24:00:00Z""#,
+            r#""1985-04-12T23:20:50.Z""#,
+            r#""1985-04-12T23:20:50+0100""#,
+            "1",
+        ] {
+            assert_eq!(errors(&schema, bad), paths(&[("", "/type")]), "{}", bad);
+        }
+        let schema = json!({"values": {"type": "float64"}});
+        assert_eq!(errors(&schema, r#"{"a": 1, "b": 1.5}"#), paths(&[]));
+        assert_eq!(
+            errors(&schema, r#"{"a/b": true}"#),
+            paths(&[("/a~1b", "/values/type")])
+        );
+        assert_eq!(errors(&schema, "[]"), paths(&[("", "/values")]));
+    }
+
+    #[test]
+    fn properties() {
+        let schema = json!({
+            "definitions": {"node": {
+                "properties": {"name": {"type": "string"}},
+                "optionalProperties": {"children": {"elements": {"ref": "node"}}}
+            }},
+            "ref": "node"
+        });
+        assert_eq!(
+            errors(&schema, r#"{"name": "a", "children": [{"name": "b"}]}"#),
+            paths(&[])
+        );
+        assert_eq!(
+            errors(&schema, r#"{"children": [{"name": 1, "x": 2}]}"#),
+            paths(&[
+                ("", "/definitions/node/properties/name"),
+                ("/children/0/name", "/definitions/node/properties/name/type"),
+                ("/children/0/x", "/definitions/node"),
+            ])
+        );
+        let schema = json!({"optionalProperties": {"a": {}}, "additionalProperties": true});
+        assert_eq!(errors(&schema, r#"{"b": 1}"#), paths(&[]));
+        assert_eq!(errors(&schema, "1"), paths(&[("", "/optionalProperties")]));
+    }
+
+    #[test]
+    fn discriminator() {
+        let schema = json!({
+            "discriminator": "kind",
+            "mapping": {
+                "a": {"properties": {"x": {"type": "int32"}}},
+                "b": {"properties": {}, "additionalProperties": true}
+            }
+        });
+        assert_eq!(errors(&schema, r#"{"kind": "a", "x": 1}"#), paths(&[]));
+        assert_eq!(errors(&schema, r#"{"kind": "b", "y": 1}"#), paths(&[]));
+        assert_eq!(errors(&schema, "{}"), paths(&[("", "/discriminator")]));
+        assert_eq!(
+            errors(&schema, r#"{"kind": 1}"#),
+            paths(&[("/kind", "/discriminator")])
+        );
+        assert_eq!(
+            errors(&schema, r#"{"kind": "c"}"#),
+            paths(&[("/kind", "/mapping")])
+        );
+        assert_eq!(
+            errors(&schema, r#"{"kind": "a", "x": "1", "y": 1}"#),
+            paths(&[("/x", "/mapping/a/properties/x/type"), ("/y", "/mapping/a")])
+        );
+        assert_eq!(errors(&schema, "[]"), paths(&[("", "/discriminator")]));
+    }
+
+    #[test]
+    fn compile_errors() {
+        let err = |schema: OwnedValue| JtdSchema::compile(&schema).unwrap_err();
+        let invalid = |pointer: &str| SchemaError::Invalid(pointer.to_string());
+        assert_eq!(err(json!([])), invalid(""));
+        assert_eq!(err(json!({"type": "int64"})), invalid("/type"));
+        assert_eq!(err(json!({"type": "string", "enum": ["a"]})), invalid(""));
+        assert_eq!(err(json!({"enum": ["a", "a"]})), invalid("/enum"));
+        assert_eq!(err(json!({"enum": []})), invalid("/enum"));
+        assert_eq!(err(json!({"format": "email"})), invalid("/format"));
+        assert_eq!(
+            err(json!({"additionalProperties": true})),
+            invalid("/additionalProperties")
+        );
+        assert_eq!(
+            err(json!({"elements": {"definitions": {}}})),
+            invalid("/elements/definitions")
+        );
+        assert_eq!(
+            err(json!({"properties": {"a": {}}, "optionalProperties": {"a": {}}})),
+            invalid("/optionalProperties/a")
+        );
+        assert_eq!(
+            err(json!({"discriminator": "t", "mapping": {"x": {"properties": {"t": {}}}}})),
+            invalid("/mapping/x")
+        );
+        assert_eq!(
+            err(json!({"discriminator": "t", "mapping": {"x": {"type": "string"}}})),
+            invalid("/mapping/x")
+        );
+        assert_eq!(
+            err(json!({"ref": "missing"})),
+            SchemaError::Unresolved("missing".to_string())
+        );
+        assert_eq!(
+            err(json!({"definitions": {"a": {"ref": "b"}, "b": {"ref": "a"}}})),
+            invalid("/definitions/a")
+        );
+        assert!(
+            JtdSchema::compile(&json!({"metadata": {"description": "x"}, "nullable": true}))
+                .is_ok()
+        );
+    }
+}
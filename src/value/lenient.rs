@@ -0,0 +1,210 @@
+use crate::prelude::*;
+use crate::{BorrowedValue, OwnedValue};
+
+/// `2^63`, the first float past the range of `i64`
+const I64_END: f64 = 9_223_372_036_854_775_808.0;
+/// `2^53`, integers up to this are exact as floats
+const F64_EXACT: u64 = 1 << 53;
+
+impl OwnedValue {
+    /// Gets the value as an `i64`, parsing strings and taking whole
+    /// floats, see `BorrowedValue::as_i64_lenient`.
+    ///
+    /// ```rust
+    /// use simd_json::json;
+    /// assert_eq!(json!("42").as_i64_lenient(), Some(42));
+    /// assert_eq!(json!(2.0).as_i64_lenient(), Some(2));
+    /// assert_eq!(json!(2.5).as_i64_lenient(), None);
+    /// ```
+    #[must_use]
+    pub fn as_i64_lenient(&self) -> Option<i64> {
+        i64_lenient(self)
+    }
+
+    /// Gets the value as a `f64`, parsing strings and taking integers
+    /// that are exact as floats, see `BorrowedValue::as_f64_lenient`.
+    ///
+    /// ```rust
+    /// use simd_json::json;
+    /// assert_eq!(json!("1.5").as_f64_lenient(), Some(1.5));
+    /// assert_eq!(json!(3).as_f64_lenient(), Some(3.0));
+    /// ```
+    #[must_use]
+    pub fn as_f64_lenient(&self) -> Option<f64> {
+        f64_lenient(self)
+    }
+
+    /// Gets the value as a boolean, taking `"true"` and `"false"` too,
+    /// see `BorrowedValue::as_bool_lenient`.
+    ///
+    /// ```rust
+    /// use simd_json::json;
+    /// assert_eq!(json!("true").as_bool_lenient(), Some(true));
+    /// assert_eq!(json!("yes").as_bool_lenient(), None);
+    /// ```
+    #[must_use]
+    pub fn as_bool_lenient(&self) -> Option<bool> {
+        bool_lenient(self)
+    }
+}
+
+impl BorrowedValue<'_> {
+    /// Gets the value as an `i64` like `as_i64`, but also parses strings
+    /// and takes floats without a fractional part, for APIs that send
+    /// `"42"` or `42.0`. Strings are parsed as they are, without
+    /// trimming, and may be floats as well. Anything that doesn't give
+    /// exactly an `i64` is `None`.
+    ///
+    /// ```rust
+    /// let mut d = br#"{"a": "-7", "b": "1e3", "c": 1.5, "d": " 1"}"#.to_vec();
+    /// let v = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// assert_eq!(v["a"].as_i64_lenient(), Some(-7));
+    /// assert_eq!(v["b"].as_i64_lenient(), Some(1000));
+    /// assert_eq!(v["c"].as_i64_lenient(), None);
+    /// assert_eq!(v["d"].as_i64_lenient(), None);
+    /// ```
+    #[must_use]
+    pub fn as_i64_lenient(&self) -> Option<i64> {
+        i64_lenient(self)
+    }
+
+    /// Gets the value as a `f64` like `as_f64`, but also parses strings
+    /// and takes integers up to `2^53` in magnitude, the ones that are
+    /// exact as floats. Strings holding infinity or NaN aren't numbers
+    /// in JSON and give `None`.
+    ///
+    /// ```rust
+    /// let mut d = br#"["0.25", 9007199254740993, "NaN"]"#.to_vec();
+    /// let v = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// assert_eq!(v[0].as_f64_lenient(), Some(0.25));
+    /// assert_eq!(v[1].as_f64_lenient(), None);
+    /// assert_eq!(v[2].as_f64_lenient(), None);
+    /// ```
+    #[must_use]
+    pub fn as_f64_lenient(&self) -> Option<f64> {
+        f64_lenient(self)
+    }
+
+    /// Gets the value as a boolean like `as_bool`, but also takes the
+    /// strings `"true"` and `"false"`. Other spellings and numbers are
+    /// `None`, there are too many conventions to guess one.
+    ///
+    /// ```rust
+    /// let mut d = br#"["false", "TRUE", 1]"#.to_vec();
+    /// let v = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// assert_eq!(v[0].as_bool_lenient(), Some(false));
+    /// assert_eq!(v[1].as_bool_lenient(), None);
+    /// assert_eq!(v[2].as_bool_lenient(), None);
+    /// ```
+    #[must_use]
+    pub fn as_bool_lenient(&self) -> Option<bool> {
+        bool_lenient(self)
+    }
+}
+
+#[allow(clippy::float_cmp, clippy::cast_possible_truncation)]
+fn float_to_i64(f: f64) -> Option<i64> {
+    if f.trunc() == f && (-I64_END..I64_END).contains(&f) {
+        Some(f as i64)
+    } else {
+        None
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn int_to_f64(i: i64) -> Option<f64> {
+    if i.unsigned_abs() <= F64_EXACT {
+        Some(i as f64)
+    } else {
+        None
+    }
+}
+
+fn i64_lenient<V: ValueTrait>(value: &V) -> Option<i64> {
+    if let Some(s) = value.as_str() {
+        s.parse()
+            .ok()
+            .or_else(|| s.parse().ok().and_then(float_to_i64))
+    } else if let Some(f) = value.as_f64() {
+        float_to_i64(f)
+    } else {
+        value.as_i64()
+    }
+}
+
+fn f64_lenient<V: ValueTrait>(value: &V) -> Option<f64> {
+    if let Some(s) = value.as_str() {
+        match s.parse() {
+            Ok(i) => int_to_f64(i),
+            Err(_) => s.parse().ok().filter(|f: &f64| f.is_finite()),
+        }
+    } else if let Some(f) = value.as_f64() {
+        Some(f)
+    } else if let Some(i) = value.as_i64() {
+        int_to_f64(i)
+    } else {
+        // a `u64` past `i64::MAX` is past `2^53` as well
+        None
+    }
+}
+
+fn bool_lenient<V: ValueTrait>(value: &V) -> Option<bool> {
+    match value.as_str() {
+        Some("true") => Some(true),
+        Some("false") => Some(false),
+        Some(_) => None,
+        None => value.as_bool(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn coercions() {
+        let mut v = json!({
+            "int": 42, "str": "42", "whole": 42.0, "frac": 0.5,
+            "huge": 1e19, "exp": "-2.5e1", "t": true, "ts": "true", "n": null, "x": "x"
+        });
+        v.insert("big", u64::MAX).unwrap();
+        let b = BorrowedValue::from(v.clone());
+        let i: Vec<_> = [
+            "int", "str", "whole", "frac", "big", "huge", "exp", "t", "n", "x",
+        ]
+        .iter()
+        .map(|k| b[*k].as_i64_lenient())
+        .collect();
+        assert_eq!(
+            i,
+            [
+                Some(42),
+                Some(42),
+                Some(42),
+                None,
+                None,
+                None,
+                Some(-25),
+                None,
+                None,
+                None
+            ]
+        );
+        assert_eq!(v["int"].as_f64_lenient(), Some(42.0));
+        assert_eq!(v["str"].as_f64_lenient(), Some(42.0));
+        assert_eq!(v["exp"].as_f64_lenient(), Some(-25.0));
+        assert_eq!(v["big"].as_f64_lenient(), None);
+        assert_eq!(v["t"].as_f64_lenient(), None);
+        assert_eq!(
+            json!(9_007_199_254_740_992_i64).as_f64_lenient(),
+            Some(9_007_199_254_740_992.0)
+        );
+        assert_eq!(json!(-9_007_199_254_740_993_i64).as_f64_lenient(), None);
+        assert_eq!(json!("inf").as_f64_lenient(), None);
+        assert_eq!(json!(-I64_END).as_i64_lenient(), Some(i64::MIN));
+        assert_eq!(b["t"].as_bool_lenient(), Some(true));
+        assert_eq!(v["ts"].as_bool_lenient(), Some(true));
+        assert_eq!(v["int"].as_bool_lenient(), None);
+        assert_eq!(v["x"].as_bool_lenient(), None);
+    }
+}
@@ -0,0 +1,508 @@
+use alloc::boxed::Box;
+use alloc::collections::btree_map::{self, BTreeMap};
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use value_trait::Object;
+
+/// Picks the map objects are stored in, for `GenericBorrowedValue` and
+/// `GenericOwnedValue`. `OwnedValue` uses `DefaultMaps` and
+/// `BorrowedValue` the same maps without going through a backend, so it
+/// stays covariant over its lifetime. The other backends trade some speed
+/// for an order of the keys, and implementing this for a map of your own
+/// plugs it into the parser, the serializer and the value traits.
+///
+/// ```rust
+/// use simd_json::{prelude::*, to_owned_value_with_map, SortedMaps};
+/// let mut d = br#"{"b": 1, "c": {"z": 2, "y": 3}, "a": 4}"#.to_vec();
+/// let v = to_owned_value_with_map::<SortedMaps>(&mut d).unwrap();
+/// assert_eq!(v["c"]["y"], 3);
+/// assert_eq!(v.encode(), r#"{"a":4,"b":1,"c":{"y":3,"z":2}}"#);
+/// ```
+pub trait MapBackend {
+    /// The map for keys `K` and values `V`
+    type Map<K: Hash + Ord + Send + Sync, V: Send + Sync>: ObjectMap<K, V> + Send + Sync;
+}
+
+/// A map the objects of the generic values can be stored in
+pub trait ObjectMap<K, V>:
+    Default + Object<Key = K, Element = V> + IntoIterator<Item = (K, V)>
+{
+    /// The iterator over the entries
+    type Iter<'i>: Iterator<Item = (&'i K, &'i V)>
+    where
+        Self: 'i,
+        K: 'i,
+        V: 'i;
+
+    /// An empty map with room for `capacity` entries
+    fn with_capacity(capacity: usize) -> Self;
+
+    /// The entries in the order of the map
+    fn entries(&self) -> Self::Iter<'_>;
+
+    /// Adds an entry the parser read. For documents with duplicate keys
+    /// the key can already be in the map, what happens then is up to the
+    /// map: the `halfbrown` maps don't check and keep both entries, the
+    /// sorted ones keep the last value.
+    fn insert_parsed(&mut self, key: K, value: V);
+
+    /// A copy of the map
+    #[must_use]
+    fn clone_map(&self) -> Self
+    where
+        K: Clone,
+        V: Clone;
+}
+
+/// The `halfbrown` maps `BorrowedValue` and `OwnedValue` use, a vector
+/// for small objects and a hash map for large ones
+#[derive(Debug, Clone, Copy)]
+pub struct DefaultMaps;
+
+impl MapBackend for DefaultMaps {
+    type Map<K: Hash + Ord + Send + Sync, V: Send + Sync> = halfbrown::HashMap<K, V>;
+}
+
+impl<K: Hash + Eq, V> ObjectMap<K, V> for halfbrown::HashMap<K, V> {
+    type Iter<'i>
+        = halfbrown::Iter<'i, K, V>
+    where
+        Self: 'i,
+        K: 'i,
+        V: 'i;
+
+    #[inline]
+    fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity(capacity)
+    }
+
+    #[inline]
+    fn entries(&self) -> Self::Iter<'_> {
+        self.iter()
+    }
+
+    #[inline]
+    fn insert_parsed(&mut self, key: K, value: V) {
+        self.insert_nocheck(key, value);
+    }
+
+    #[inline]
+    fn clone_map(&self) -> Self
+    where
+        K: Clone,
+        V: Clone,
+    {
+        self.clone()
+    }
+}
+
+/// `SortedMap`s, objects keep their keys sorted and are written sorted
+#[derive(Debug, Clone, Copy)]
+pub struct SortedMaps;
+
+impl MapBackend for SortedMaps {
+    type Map<K: Hash + Ord + Send + Sync, V: Send + Sync> = SortedMap<K, V>;
+}
+
+/// A `BTreeMap` that can be used for objects, see `SortedMaps`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortedMap<K, V>(pub BTreeMap<K, V>);
+
+impl<K, V> Default for SortedMap<K, V> {
+    fn default() -> Self {
+        Self(BTreeMap::new())
+    }
+}
+
+impl<K, V> Deref for SortedMap<K, V> {
+    type Target = BTreeMap<K, V>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<K, V> DerefMut for SortedMap<K, V> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<K, V> IntoIterator for SortedMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = btree_map::IntoIter<K, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<K: Ord, V> Object for SortedMap<K, V> {
+    type Key = K;
+    type Element = V;
+
+    fn get<Q>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q> + Hash + Eq,
+        Q: ?Sized + Hash + Eq + Ord,
+    {
+        self.0.get(k)
+    }
+
+    fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q> + Hash + Eq,
+        Q: ?Sized + Hash + Eq + Ord,
+    {
+        self.0.get_mut(k)
+    }
+
+    fn insert<K2, V2>(&mut self, k: K2, v: V2) -> Option<V>
+    where
+        K2: Into<K>,
+        V2: Into<V>,
+        K: Hash + Eq,
+    {
+        self.0.insert(k.into(), v.into())
+    }
+
+    fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq + Ord,
+    {
+        self.0.remove(k)
+    }
+
+    fn iter<'i>(&'i self) -> Box<dyn Iterator<Item = (&'i K, &'i V)> + 'i> {
+        Box::new(self.0.iter())
+    }
+
+    fn keys<'i>(&'i self) -> Box<dyn Iterator<Item = &'i K> + 'i> {
+        Box::new(self.0.keys())
+    }
+
+    fn values<'i>(&'i self) -> Box<dyn Iterator<Item = &'i V> + 'i> {
+        Box::new(self.0.values())
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl<K: Ord, V> ObjectMap<K, V> for SortedMap<K, V> {
+    type Iter<'i>
+        = btree_map::Iter<'i, K, V>
+    where
+        Self: 'i,
+        K: 'i,
+        V: 'i;
+
+    fn with_capacity(_capacity: usize) -> Self {
+        Self::default()
+    }
+
+    fn entries(&self) -> Self::Iter<'_> {
+        self.0.iter()
+    }
+
+    fn insert_parsed(&mut self, key: K, value: V) {
+        self.0.insert(key, value);
+    }
+
+    fn clone_map(&self) -> Self
+    where
+        K: Clone,
+        V: Clone,
+    {
+        self.clone()
+    }
+}
+
+/// `HashedMap`s, `halfbrown` maps like `DefaultMaps` hashing their keys
+/// with the hasher `S` builds. For documents from untrusted sources use
+/// a keyed hasher like `SipHashMaps` or `AHashMaps`, so keys can't be
+/// picked to collide; lookup heavy workloads on trusted data can go
+/// faster with `FxHashMaps`. `DefaultMaps` are keyed as well, unless the
+/// `known-key` feature switches them to fxhash.
+///
+/// ```rust
+/// use simd_json::{prelude::*, to_borrowed_value_with_map, SipHashMaps};
+/// let mut d = br#"{"user": {"name": "x"}}"#.to_vec();
+/// let v = to_borrowed_value_with_map::<SipHashMaps>(&mut d).unwrap();
+/// assert_eq!(v["user"]["name"], "x");
+/// ```
+pub struct HashedMaps<S>(PhantomData<S>);
+
+impl<S> MapBackend for HashedMaps<S>
+where
+    S: BuildHasher + Default + Clone + Send + Sync,
+{
+    type Map<K: Hash + Ord + Send + Sync, V: Send + Sync> = HashedMap<K, V, S>;
+}
+
+/// Maps keyed with std's randomly keyed `SipHash`, the hasher of
+/// `std::collections::HashMap`
+#[cfg(feature = "std")]
+pub type SipHashMaps = HashedMaps<std::collections::hash_map::RandomState>;
+
+/// Maps keyed with a randomly keyed [ahash](https://docs.rs/ahash)
+#[cfg(feature = "ahash")]
+pub type AHashMaps = HashedMaps<ahash::RandomState>;
+
+/// Maps hashed with [fxhash](https://docs.rs/fxhash), which is fast but
+/// not keyed
+#[cfg(feature = "fxhash")]
+pub type FxHashMaps = HashedMaps<fxhash::FxBuildHasher>;
+
+/// A `halfbrown` map with the hasher `S` that can be used for objects,
+/// see `HashedMaps`
+#[derive(Debug, Clone)]
+pub struct HashedMap<K, V, S>(pub halfbrown::HashMap<K, V, S>);
+
+impl<K, V, S: Default> Default for HashedMap<K, V, S> {
+    fn default() -> Self {
+        Self(halfbrown::HashMap::default())
+    }
+}
+
+impl<K, V, S> Deref for HashedMap<K, V, S> {
+    type Target = halfbrown::HashMap<K, V, S>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<K, V, S> DerefMut for HashedMap<K, V, S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<K, V, S> IntoIterator for HashedMap<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = halfbrown::IntoIter<K, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher + Default> Object for HashedMap<K, V, S> {
+    type Key = K;
+    type Element = V;
+
+    fn get<Q>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q> + Hash + Eq,
+        Q: ?Sized + Hash + Eq + Ord,
+    {
+        self.0.get(k)
+    }
+
+    fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q> + Hash + Eq,
+        Q: ?Sized + Hash + Eq + Ord,
+    {
+        self.0.get_mut(k)
+    }
+
+    fn insert<K2, V2>(&mut self, k: K2, v: V2) -> Option<V>
+    where
+        K2: Into<K>,
+        V2: Into<V>,
+        K: Hash + Eq,
+    {
+        self.0.insert(k.into(), v.into())
+    }
+
+    fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq + Ord,
+    {
+        self.0.remove(k)
+    }
+
+    fn iter<'i>(&'i self) -> Box<dyn Iterator<Item = (&'i K, &'i V)> + 'i> {
+        Box::new(self.0.iter())
+    }
+
+    fn keys<'i>(&'i self) -> Box<dyn Iterator<Item = &'i K> + 'i> {
+        Box::new(self.0.keys())
+    }
+
+    fn values<'i>(&'i self) -> Box<dyn Iterator<Item = &'i V> + 'i> {
+        Box::new(self.0.values())
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl<K, V, S> ObjectMap<K, V> for HashedMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher + Default + Clone,
+{
+    type Iter<'i>
+        = halfbrown::Iter<'i, K, V>
+    where
+        Self: 'i,
+        K: 'i,
+        V: 'i;
+
+    #[inline]
+    fn with_capacity(capacity: usize) -> Self {
+        Self(halfbrown::HashMap::with_capacity_and_hasher(
+            capacity,
+            S::default(),
+        ))
+    }
+
+    #[inline]
+    fn entries(&self) -> Self::Iter<'_> {
+        self.0.iter()
+    }
+
+    #[inline]
+    fn insert_parsed(&mut self, key: K, value: V) {
+        self.0.insert_nocheck(key, value);
+    }
+
+    #[inline]
+    fn clone_map(&self) -> Self
+    where
+        K: Clone,
+        V: Clone,
+    {
+        self.clone()
+    }
+}
+
+/// Formats a map like the `Debug` of the std maps
+pub(crate) struct DebugMap<'m, T>(pub(crate) &'m T);
+
+impl<T> fmt::Debug for DebugMap<'_, T>
+where
+    T: Object,
+    T::Key: fmt::Debug,
+    T::Element: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.0.iter()).finish()
+    }
+}
+
+/// Compares two maps with string keys, they are equal if they hold the
+/// same entries in any order
+pub(crate) fn eq<K1, V1, T1, K2, V2, T2>(left: &T1, right: &T2) -> bool
+where
+    K1: Hash + Ord + Borrow<str>,
+    K2: Hash + Ord + Borrow<str>,
+    V1: PartialEq<V2>,
+    T1: ObjectMap<K1, V1>,
+    T2: ObjectMap<K2, V2>,
+{
+    left.len() == right.len()
+        && left
+            .entries()
+            .all(|(k, v)| right.get(k.borrow()).is_some_and(|r| v == r))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+    use crate::{to_borrowed_value_with_map, to_owned_value_with_map};
+    use crate::{GenericBorrowedValue, GenericOwnedValue};
+    use alloc::format;
+
+    #[test]
+    fn sorted() {
+        let s = r#"{"b": [1, {"z": null, "y": true}], "c": "x", "a": {}, "c": 2}"#;
+        let mut d = s.as_bytes().to_vec();
+        let mut owned: GenericOwnedValue<SortedMaps> =
+            to_owned_value_with_map(&mut d).expect("failed to parse");
+        let mut d = s.as_bytes().to_vec();
+        let borrowed: GenericBorrowedValue<SortedMaps> =
+            to_borrowed_value_with_map(&mut d).expect("failed to parse");
+        let sorted = r#"{"a":{},"b":[1,{"y":true,"z":null}],"c":2}"#;
+        assert_eq!(owned.encode(), sorted);
+        assert_eq!(borrowed.encode(), sorted);
+        assert_eq!(owned, borrowed);
+        assert_eq!(owned["b"][1]["y"], true);
+        assert!(owned["b"][1]["x"].is_null());
+        assert_eq!(owned.as_object().map(Object::len), Some(3));
+
+        let copy = owned.clone();
+        owned.insert("0", "first").expect("not an object");
+        assert_eq!(owned.remove("c").expect("not an object"), Some(2.into()));
+        assert_eq!(
+            owned.encode(),
+            r#"{"0":"first","a":{},"b":[1,{"y":true,"z":null}]}"#
+        );
+        assert_ne!(owned, copy);
+        assert_eq!(format!("{:?}", owned["a"]), "Object({})");
+        assert_eq!(
+            format!("{:?}", owned["b"][1]),
+            r#"Object({"y": Static(Bool(true)), "z": Static(Null)})"#
+        );
+
+        let mut built = GenericOwnedValue::<SortedMaps>::object();
+        built.insert("k", vec![1, 2]).expect("not an object");
+        built.insert("e", "v").expect("not an object");
+        assert_eq!(built.encode(), r#"{"e":"v","k":[1,2]}"#);
+        let collected: GenericOwnedValue<SortedMaps> =
+            vec![("y", 1), ("x", 2)].into_iter().collect();
+        assert_eq!(collected.encode(), r#"{"x":2,"y":1}"#);
+    }
+
+    fn hashed<S>()
+    where
+        S: BuildHasher + Default + Clone + Send + Sync,
+    {
+        let s = r#"{"a": [1, {"b": "c", "d": {}}], "e": null}"#;
+        let mut d = s.as_bytes().to_vec();
+        let mut v: GenericOwnedValue<HashedMaps<S>> =
+            to_owned_value_with_map(&mut d).expect("failed to parse");
+        let mut d = s.as_bytes().to_vec();
+        let b: GenericBorrowedValue<HashedMaps<S>> =
+            to_borrowed_value_with_map(&mut d).expect("failed to parse");
+        assert_eq!(v, b);
+        assert_eq!(v.clone(), v);
+        assert_eq!(v["a"][1]["b"], "c");
+        assert!(v["e"].is_null());
+        v.insert("f", "g").expect("not an object");
+        let mut encoded = v.encode().into_bytes();
+        let again: GenericOwnedValue<HashedMaps<S>> =
+            to_owned_value_with_map(&mut encoded).expect("failed to parse");
+        assert_eq!(again, v);
+        assert_eq!(again.as_object().map(Object::len), Some(3));
+    }
+
+    #[test]
+    fn hashers() {
+        #[cfg(feature = "std")]
+        hashed::<std::collections::hash_map::RandomState>();
+        #[cfg(feature = "ahash")]
+        hashed::<ahash::RandomState>();
+        #[cfg(feature = "fxhash")]
+        hashed::<fxhash::FxBuildHasher>();
+        hashed::<halfbrown::DefaultHashBuilder>();
+    }
+
+    #[test]
+    fn default_maps() {
+        let mut d = br#"{"a": [1, {"b": "c"}]}"#.to_vec();
+        let v: GenericBorrowedValue<DefaultMaps> =
+            to_borrowed_value_with_map(&mut d).expect("failed to parse");
+        let mut d = br#"{"a": [1, {"b": "c"}]}"#.to_vec();
+        let b = crate::to_borrowed_value(&mut d).expect("failed to parse");
+        assert_eq!(v.encode(), b.encode());
+        assert_eq!(v.encode(), r#"{"a":[1,{"b":"c"}]}"#);
+    }
+}
@@ -0,0 +1,228 @@
+use crate::prelude::*;
+use crate::{BorrowedValue, OwnedValue};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::hash::Hash;
+use halfbrown::HashMap;
+
+/// What merging does with arrays that are in both values
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ArrayMerge {
+    /// The array of the merged in value replaces the other one
+    #[default]
+    Replace,
+    /// The elements of the merged in value are appended
+    Concat,
+}
+
+/// What merging does with `null`s in the fields of merged in objects
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NullMerge {
+    /// `null` replaces the value like any other value
+    #[default]
+    Overwrite,
+    /// Fields that are `null` are skipped, the value stays
+    Skip,
+    /// Fields that are `null` remove the field, like a JSON merge patch
+    Remove,
+}
+
+/// How `merge` combines two values. Objects are always merged field by
+/// field, anything else is replaced unless the options say otherwise.
+///
+/// ```rust
+/// use simd_json::{json, ArrayMerge, MergeStrategy, NullMerge};
+/// let mut config = json!({"log": {"level": "info", "file": "a.log"}, "plugins": ["a"]});
+/// let local = json!({"log": {"file": null}, "plugins": ["b"]});
+/// let strategy = MergeStrategy::default()
+///     .arrays(ArrayMerge::Concat)
+///     .nulls(NullMerge::Remove);
+/// config.merge(local, strategy);
+/// assert_eq!(config, json!({"log": {"level": "info"}, "plugins": ["a", "b"]}));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MergeStrategy {
+    pub(crate) arrays: ArrayMerge,
+    pub(crate) nulls: NullMerge,
+}
+
+impl MergeStrategy {
+    /// Sets how arrays are merged, `ArrayMerge::Replace` by default
+    #[must_use]
+    pub fn arrays(mut self, arrays: ArrayMerge) -> Self {
+        self.arrays = arrays;
+        self
+    }
+
+    /// Sets how `null` fields are merged, `NullMerge::Overwrite` by
+    /// default
+    #[must_use]
+    pub fn nulls(mut self, nulls: NullMerge) -> Self {
+        self.nulls = nulls;
+        self
+    }
+}
+
+impl OwnedValue {
+    /// Merges `other` into the value, see `BorrowedValue::merge`
+    pub fn merge(&mut self, other: Self, strategy: MergeStrategy) {
+        merge(self, other, strategy);
+    }
+}
+
+impl<'v> BorrowedValue<'v> {
+    /// Merges `other` into the value, recursing into the fields both have
+    /// when both are objects. A value that isn't an object is replaced by
+    /// the merged in one, arrays and `null`s are merged as `strategy`
+    /// says. A `null` that is merged in as a whole replaces the value
+    /// unless `null`s are skipped.
+    ///
+    /// ```rust
+    /// use simd_json::{json, BorrowedValue, MergeStrategy};
+    /// let mut d1 = br#"{"a": {"b": 1, "c": [1]}}"#.to_vec();
+    /// let mut d2 = br#"{"a": {"c": [2], "d": true}}"#.to_vec();
+    /// let mut v = simd_json::to_borrowed_value(&mut d1).unwrap();
+    /// let other = simd_json::to_borrowed_value(&mut d2).unwrap();
+    /// v.merge(other, MergeStrategy::default());
+    /// assert_eq!(v, json!({"a": {"b": 1, "c": [2], "d": true}}));
+    /// ```
+    pub fn merge(&mut self, other: BorrowedValue<'v>, strategy: MergeStrategy) {
+        merge(self, other, strategy);
+    }
+}
+
+/// A value taken apart by value, for merging and conversions
+pub(super) enum Parts<K, V> {
+    Object(HashMap<K, V>),
+    Array(Vec<V>),
+    Other(V),
+}
+
+pub(super) trait IntoParts: Sized + ValueTrait {
+    fn into_parts(self) -> Parts<Self::Key, Self>;
+}
+
+impl IntoParts for OwnedValue {
+    fn into_parts(self) -> Parts<Self::Key, Self> {
+        match self {
+            Self::Object(o) => Parts::Object(*o),
+            Self::Array(a) => Parts::Array(a),
+            other => Parts::Other(other),
+        }
+    }
+}
+
+impl IntoParts for BorrowedValue<'_> {
+    fn into_parts(self) -> Parts<Self::Key, Self> {
+        match self {
+            Self::Object(o) => Parts::Object(*o),
+            Self::Array(a) => Parts::Array(a),
+            other => Parts::Other(other),
+        }
+    }
+}
+
+fn merge<'v, V>(target: &mut V, other: V, strategy: MergeStrategy)
+where
+    V: IntoParts + Builder<'v> + Mutable + ValueTrait<Array = Vec<V>> + From<Vec<V>>,
+    V::Key: Borrow<str> + Hash + Eq,
+{
+    match other.into_parts() {
+        Parts::Object(entries) => {
+            if !target.is_object() {
+                *target = V::object();
+            }
+            if let Some(object) = target.as_object_mut() {
+                for (key, value) in entries {
+                    if value.is_null() {
+                        match strategy.nulls {
+                            NullMerge::Overwrite => {}
+                            NullMerge::Skip => continue,
+                            NullMerge::Remove => {
+                                let _ = object.remove(key.borrow());
+                                continue;
+                            }
+                        }
+                    }
+                    match object.get_mut(key.borrow()) {
+                        Some(field) => merge(field, value, strategy),
+                        None => {
+                            let _ = object.insert(key, value);
+                        }
+                    }
+                }
+            }
+        }
+        Parts::Array(elements) => match target.as_array_mut() {
+            Some(array) if strategy.arrays == ArrayMerge::Concat => array.extend(elements),
+            _ => *target = V::from(elements),
+        },
+        Parts::Other(value) => {
+            if !(value.is_null() && strategy.nulls == NullMerge::Skip) {
+                *target = value;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const BASE: &str = r#"{"a": {"b": 1, "c": [1, 2]}, "d": "x", "e": [1], "f": 3}"#;
+    const OTHER: &str = r#"{"a": {"c": [3], "g": null}, "d": {"h": null}, "e": 2, "f": null}"#;
+
+    fn merged(strategy: MergeStrategy) -> OwnedValue {
+        let mut v = crate::to_owned_value(&mut BASE.as_bytes().to_vec()).unwrap();
+        let other = crate::to_owned_value(&mut OTHER.as_bytes().to_vec()).unwrap();
+        v.merge(other, strategy);
+        let mut base = BASE.as_bytes().to_vec();
+        let mut other = OTHER.as_bytes().to_vec();
+        let mut b = crate::to_borrowed_value(&mut base).unwrap();
+        b.merge(crate::to_borrowed_value(&mut other).unwrap(), strategy);
+        assert_eq!(b, v);
+        v
+    }
+
+    #[test]
+    fn strategies() {
+        assert_eq!(
+            merged(MergeStrategy::default()),
+            json!({"a": {"b": 1, "c": [3], "g": null}, "d": {"h": null}, "e": 2, "f": null})
+        );
+        assert_eq!(
+            merged(MergeStrategy::default().arrays(ArrayMerge::Concat)),
+            json!({"a": {"b": 1, "c": [1, 2, 3], "g": null}, "d": {"h": null}, "e": 2, "f": null})
+        );
+        assert_eq!(
+            merged(MergeStrategy::default().nulls(NullMerge::Skip)),
+            json!({"a": {"b": 1, "c": [3]}, "d": {}, "e": 2, "f": 3})
+        );
+        assert_eq!(
+            merged(MergeStrategy::default().nulls(NullMerge::Remove)),
+            json!({"a": {"b": 1, "c": [3]}, "d": {}, "e": 2})
+        );
+    }
+
+    #[test]
+    fn whole_values() {
+        let mut v = json!([1]);
+        v.merge(
+            json!([2]),
+            MergeStrategy::default().arrays(ArrayMerge::Concat),
+        );
+        assert_eq!(v, json!([1, 2]));
+        v.merge(
+            OwnedValue::null(),
+            MergeStrategy::default().nulls(NullMerge::Skip),
+        );
+        assert_eq!(v, json!([1, 2]));
+        v.merge(OwnedValue::null(), MergeStrategy::default());
+        assert!(v.is_null());
+        v.merge(
+            json!({"a": null}),
+            MergeStrategy::default().nulls(NullMerge::Remove),
+        );
+        assert_eq!(v, json!({}));
+    }
+}
@@ -0,0 +1,365 @@
+use super::borrowed::{Object, Value};
+use super::build::{build, dismantle, Build, Nested};
+use super::emit::{Emit, Part};
+use crate::cow::Cow;
+#[cfg(feature = "std")]
+use crate::prelude::*;
+#[cfg(feature = "std")]
+use crate::value::encoder::write_json;
+use crate::{Deserializer, Node, ParseOptions, Result, StaticNode};
+#[cfg(feature = "std")]
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+#[cfg(feature = "std")]
+use value_trait::generator::{
+    DumpGenerator, PrettyGenerator, PrettyWriterGenerator, WriterGenerator,
+};
+
+/// Parses a slice of bytes into a multimap dom, whose objects keep
+/// every entry of keys that are repeated. Like with the borrowed dom
+/// strings are de-escaped in place and referenced from the slice.
+///
+/// # Errors
+///
+/// Will return `Err` if `s` is invalid JSON.
+pub fn to_value(s: &mut [u8]) -> Result<MultiValue<'_>> {
+    to_value_with_options(s, ParseOptions::default())
+}
+
+/// Parses a slice of bytes into a multimap dom using the given parse
+/// `options`, see `to_value` for details. With lossy UTF-8 mode, or when
+/// invalid escapes are rewritten, the strings are owned like for
+/// `to_borrowed_value_with_options`.
+///
+/// # Errors
+///
+/// Will return `Err` if `s` is invalid JSON.
+pub fn to_value_with_options(s: &mut [u8], options: ParseOptions) -> Result<MultiValue<'_>> {
+    if let Some(mut repaired) = options.repair(s) {
+        // The repaired input doesn't outlive this call so we can't
+        // borrow from it
+        return to_value_with_options(&mut repaired, options).map(MultiValue::into_static);
+    }
+    match Deserializer::from_slice_or_input(s, options) {
+        Ok(de) => Ok(build(&mut MultiDeserializer { de })),
+        Err((e, input)) => match options.repair_utf8(input) {
+            Some(mut repaired) => {
+                to_value_with_options(&mut repaired, options).map(MultiValue::into_static)
+            }
+            None => Err(e),
+        },
+    }
+}
+
+/// A JSON value whose objects are lists of entries instead of maps, so
+/// keys that are repeated keep all their values, in input order, and
+/// are written back as they were read. Some protocols carry repeated
+/// keys on purpose, like objects of HTTP headers.
+///
+/// ```rust
+/// use simd_json::{prelude::*, to_multi_value, MultiValue};
+/// let input = r#"{"Set-Cookie":"a=1","Host":"x","Set-Cookie":"b=2"}"#;
+/// let mut d = input.as_bytes().to_vec();
+/// let v = to_multi_value(&mut d).unwrap();
+/// let cookies: Vec<_> = v.get_all("Set-Cookie").filter_map(MultiValue::as_str).collect();
+/// assert_eq!(cookies, ["a=1", "b=2"]);
+/// assert_eq!(v.get("Set-Cookie").and_then(MultiValue::as_str), Some("a=1"));
+/// assert_eq!(v.encode(), input);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum MultiValue<'v> {
+    /// Static values
+    Static(StaticNode),
+    /// string type
+    String(Cow<'v, str>),
+    /// array type
+    Array(Vec<MultiValue<'v>>),
+    /// object type, entries are kept in input order
+    Object(Vec<(Cow<'v, str>, MultiValue<'v>)>),
+}
+
+impl<'v> MultiValue<'v> {
+    /// Drops the value without recursing into it, see
+    /// `OwnedValue::dismantle`.
+    pub fn dismantle(self) {
+        dismantle(self);
+    }
+
+    /// Gets the value of `key` if this is an object, for duplicate keys
+    /// the first one wins just like in the other doms
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&Self> {
+        if let Self::Object(o) = self {
+            o.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+        } else {
+            None
+        }
+    }
+
+    /// Gets every value of `key` in input order, nothing if this isn't
+    /// an object
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a Self> + 'a {
+        let entries: &[(Cow<'v, str>, Self)] = match self {
+            Self::Object(o) => o,
+            _ => &[],
+        };
+        entries
+            .iter()
+            .filter(move |(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+
+    /// Gets the element at `idx` if this is an array
+    #[must_use]
+    pub fn get_idx(&self, idx: usize) -> Option<&Self> {
+        if let Self::Array(a) = self {
+            a.get(idx)
+        } else {
+            None
+        }
+    }
+
+    /// The string if this is a string
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        if let Self::String(s) = self {
+            Some(s)
+        } else {
+            None
+        }
+    }
+
+    /// Enforces a static lifetime, all strings and keys become owned
+    #[must_use]
+    pub fn into_static(self) -> MultiValue<'static> {
+        match self {
+            Self::Static(s) => MultiValue::Static(s),
+            Self::String(s) => MultiValue::String(s.into_owned().into()),
+            Self::Array(a) => MultiValue::Array(a.into_iter().map(Self::into_static).collect()),
+            Self::Object(o) => MultiValue::Object(
+                o.into_iter()
+                    .map(|(k, v)| (Cow::from(k.into_owned()), v.into_static()))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Turns this into a borrowed value, of duplicate keys only the
+    /// first value is kept
+    #[must_use]
+    pub fn into_value(self) -> Value<'v> {
+        match self {
+            Self::Static(s) => Value::Static(s),
+            Self::String(s) => Value::String(s),
+            Self::Array(a) => Value::Array(a.into_iter().map(Self::into_value).collect()),
+            Self::Object(o) => {
+                let mut res = Object::with_capacity(o.len());
+                for (k, v) in o {
+                    res.entry(k).or_insert_with(|| v.into_value());
+                }
+                Value::from(res)
+            }
+        }
+    }
+}
+
+impl<'v> From<Value<'v>> for MultiValue<'v> {
+    fn from(v: Value<'v>) -> Self {
+        match v {
+            Value::Static(s) => Self::Static(s),
+            Value::String(s) => Self::String(s),
+            Value::Array(a) => Self::Array(a.into_iter().map(Self::from).collect()),
+            Value::Object(o) => Self::Object(o.into_iter().map(|(k, v)| (k, v.into())).collect()),
+            #[cfg(feature = "bytes")]
+            Value::Bytes(b) => Self::String(crate::value::to_base64(&b).into()),
+        }
+    }
+}
+
+impl Nested for MultiValue<'_> {
+    fn take_children(&mut self, out: &mut Vec<Self>) {
+        match self {
+            Self::Array(a) => out.append(a),
+            Self::Object(o) => out.extend(o.drain(..).map(|(_, v)| v)),
+            _ => (),
+        }
+    }
+}
+
+/// An entry as references to its key and value, like maps hand them out
+fn entry_refs<K, V>((k, v): &(K, V)) -> (&K, &V) {
+    (k, v)
+}
+
+impl<'v> Emit for MultiValue<'v> {
+    type Key = Cow<'v, str>;
+    type Entries<'a>
+        = core::iter::Map<
+        core::slice::Iter<'a, (Self::Key, Self)>,
+        fn(&'a (Self::Key, Self)) -> (&'a Self::Key, &'a Self),
+    >
+    where
+        Self: 'a;
+
+    #[inline(always)]
+    fn part(&self) -> Part<'_, Self> {
+        match self {
+            Self::Static(s) => Part::Static(*s),
+            Self::String(s) => Part::String(s),
+            Self::Array(a) => Part::Array(a),
+            // every entry is written, repeated keys included
+            Self::Object(o) => Part::Object(o.len(), o.iter().map(entry_refs as _)),
+        }
+    }
+}
+
+struct MultiDeserializer<'de> {
+    de: Deserializer<'de>,
+}
+
+impl<'de> Build<'de> for MultiDeserializer<'de> {
+    type Value = MultiValue<'de>;
+    type Key = Cow<'de, str>;
+    type Array = Vec<MultiValue<'de>>;
+    type Object = Vec<(Cow<'de, str>, MultiValue<'de>)>;
+
+    fn next_node(&mut self) -> Node<'de> {
+        self.de.next_()
+    }
+
+    fn static_node(&mut self, s: StaticNode) -> Self::Value {
+        MultiValue::Static(s)
+    }
+
+    fn string(&mut self, s: &'de str) -> Self::Value {
+        MultiValue::String(s.into())
+    }
+
+    fn key(&mut self, s: &'de str) -> Self::Key {
+        s.into()
+    }
+
+    fn array(&mut self, len: usize) -> Self::Array {
+        Vec::with_capacity(len)
+    }
+
+    fn push(&mut self, array: &mut Self::Array, value: Self::Value) {
+        array.push(value);
+    }
+
+    fn finish_array(&mut self, array: Self::Array) -> Self::Value {
+        MultiValue::Array(array)
+    }
+
+    fn object(&mut self, len: usize) -> Self::Object {
+        Vec::with_capacity(len)
+    }
+
+    fn insert(&mut self, object: &mut Self::Object, key: Self::Key, value: Self::Value) {
+        object.push((key, value));
+    }
+
+    fn finish_object(&mut self, object: Self::Object) -> Self::Value {
+        MultiValue::Object(object)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Writable for MultiValue<'_> {
+    #[inline]
+    fn encode(&self) -> String {
+        let mut g = DumpGenerator::<Value>::new();
+        let _ = write_json(&mut g, self);
+        g.consume()
+    }
+
+    #[inline]
+    fn encode_pp(&self) -> String {
+        let mut g = PrettyGenerator::<Value>::new(2);
+        let _ = write_json(&mut g, self);
+        g.consume()
+    }
+
+    #[inline]
+    fn write<'writer, W>(&self, w: &mut W) -> io::Result<()>
+    where
+        W: 'writer + Write,
+    {
+        write_json(&mut WriterGenerator::<_, Value>::new(w), self)
+    }
+
+    #[inline]
+    fn write_pp<'writer, W>(&self, w: &mut W) -> io::Result<()>
+    where
+        W: 'writer + Write,
+    {
+        write_json(&mut PrettyWriterGenerator::<_, Value>::new(w, 2), self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn keeps_duplicates() {
+        let input = r#"{"a":1,"b":[{"c":true,"c":null}],"a":"x","a":2.5}"#;
+        let mut d = input.as_bytes().to_vec();
+        let v = to_value(&mut d).expect("failed to parse");
+        let all: Vec<&MultiValue> = v.get_all("a").collect();
+        assert_eq!(
+            all,
+            [
+                &MultiValue::Static(StaticNode::I64(1)),
+                &MultiValue::String("x".into()),
+                &MultiValue::Static(StaticNode::F64(2.5)),
+            ]
+        );
+        assert_eq!(v.get("a"), Some(&MultiValue::Static(StaticNode::I64(1))));
+        let c = v
+            .get("b")
+            .and_then(|b| b.get_idx(0))
+            .expect("missing element");
+        assert_eq!(c.get_all("c").count(), 2);
+        assert_eq!(c.get_all("d").count(), 0);
+        assert_eq!(v.get_idx(0), None);
+        assert_eq!(MultiValue::Array(Vec::new()).get_all("a").count(), 0);
+        assert_eq!(v.encode(), input);
+        assert_eq!(v.clone().into_value(), json!({"a": 1, "b": [{"c": true}]}));
+        // a borrowed value turns into one without duplicates
+        let b = MultiValue::from(Value::from(json!({"k": [1, "v"]})));
+        assert_eq!(b.encode(), r#"{"k":[1,"v"]}"#);
+        assert_eq!(
+            v.encode_pp(),
+            "{\n  \"a\": 1,\n  \"b\": [\n    {\n      \"c\": true,\n      \"c\": null\n    }\n  ],\n  \"a\": \"x\",\n  \"a\": 2.5\n}"
+        );
+        let mut out = Vec::new();
+        v.write(&mut out).expect("failed to write");
+        assert_eq!(out, input.as_bytes());
+    }
+
+    #[test]
+    fn repaired_input() {
+        let mut d = b"{\"k\": \"caf\xe9\", \"k\": []}".to_vec();
+        let options = ParseOptions::default().lossy_utf8_fallback(true);
+        let v = to_value_with_options(&mut d, options).expect("failed to parse");
+        assert_eq!(v.get_all("k").count(), 2);
+        assert_eq!(
+            v.get_all("k").next().and_then(MultiValue::as_str),
+            Some("caf\u{fffd}")
+        );
+        assert!(to_value(&mut b"{\"k\": 1, \"k\"}".to_vec()).is_err());
+    }
+
+    #[test]
+    fn deep_nesting() {
+        use crate::value::deep::{nested, DEPTH};
+        let input = nested(DEPTH, "1");
+        let mut d = input.as_bytes().to_vec();
+        let v = to_value(&mut d).expect("failed to parse");
+        assert_eq!(v.encode(), input);
+        v.dismantle();
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{BorrowedValue, OwnedValue, StaticNode};
+#[cfg(feature = "128bit")]
+use core::convert::TryFrom;
+
+impl OwnedValue {
+    /// Gives all numbers in the value one representation per value, see
+    /// `BorrowedValue::normalize_numbers`.
+    ///
+    /// ```rust
+    /// use simd_json::{json, prelude::*};
+    /// let mut v = json!({"n": [2.0, 2.5, -3.0, 4]});
+    /// v.normalize_numbers();
+    /// assert_eq!(v["n"][0].value_type(), ValueType::U64);
+    /// assert_eq!(v["n"][1].value_type(), ValueType::F64);
+    /// assert_eq!(v["n"][2].value_type(), ValueType::I64);
+    /// assert_eq!(v["n"][3].value_type(), ValueType::U64);
+    /// ```
+    pub fn normalize_numbers(&mut self) {
+        match self {
+            Self::Static(s) => normalize(s),
+            Self::Object(o) => o.values_mut().for_each(Self::normalize_numbers),
+            Self::Array(a) => a.iter_mut().for_each(Self::normalize_numbers),
+            Self::String(_) => {}
+            #[cfg(feature = "bytes")]
+            Self::Bytes(_) => {}
+        }
+    }
+}
+
+impl BorrowedValue<'_> {
+    /// Gives all numbers in the value, nested ones included, one
+    /// representation per value, so values from producers that write
+    /// numbers differently compare and hash the same.
+    ///
+    /// Integers that aren't negative become `U64`s and negative ones
+    /// `I64`s, 128 bit integers included if they fit. Floats without a
+    /// fraction that fit one of them become integers the same way, other
+    /// floats, `-0.0` aside which becomes `0`, stay as they are.
+    ///
+    /// ```rust
+    /// use simd_json::prelude::*;
+    /// let mut d = br#"{"a": 1e3, "b": 1000, "c": 0.1}"#.to_vec();
+    /// let mut v = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// assert_ne!(v["a"], v["b"]);
+    /// v.normalize_numbers();
+    /// assert_eq!(v["a"], v["b"]);
+    /// assert_eq!(v["b"].value_type(), ValueType::U64);
+    /// assert_eq!(v["c"].value_type(), ValueType::F64);
+    /// ```
+    pub fn normalize_numbers(&mut self) {
+        match self {
+            Self::Static(s) => normalize(s),
+            Self::Object(o) => o.values_mut().for_each(Self::normalize_numbers),
+            Self::Array(a) => a.iter_mut().for_each(Self::normalize_numbers),
+            Self::String(_) => {}
+            #[cfg(feature = "bytes")]
+            Self::Bytes(_) => {}
+        }
+    }
+}
+
+/// 2^64, the first float above the `u64`s
+const U64_END: f64 = 18_446_744_073_709_551_616.0;
+/// -2^63, the smallest `i64`
+const I64_START: f64 = -9_223_372_036_854_775_808.0;
+
+#[allow(
+    clippy::float_cmp,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+fn normalize(node: &mut StaticNode) {
+    *node = match *node {
+        StaticNode::I64(i) if i >= 0 => StaticNode::U64(i as u64),
+        // the casts saturate, so a float is an integer if it survives them
+        StaticNode::F64(f) if (0.0..U64_END).contains(&f) && (f as u64) as f64 == f => {
+            StaticNode::U64(f as u64)
+        }
+        StaticNode::F64(f) if (I64_START..0.0).contains(&f) && (f as i64) as f64 == f => {
+            StaticNode::I64(f as i64)
+        }
+        #[cfg(feature = "128bit")]
+        StaticNode::I128(i) => match (u64::try_from(i), i64::try_from(i)) {
+            (Ok(u), _) => StaticNode::U64(u),
+            (_, Ok(i)) => StaticNode::I64(i),
+            _ => return,
+        },
+        #[cfg(feature = "128bit")]
+        StaticNode::U128(u) => match u64::try_from(u) {
+            Ok(u) => StaticNode::U64(u),
+            Err(_) => return,
+        },
+        _ => return,
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+
+    fn normalized(node: StaticNode) -> StaticNode {
+        let mut node = node;
+        normalize(&mut node);
+        node
+    }
+
+    // `StaticNode`s of different types compare equal, so these match on
+    // the variants
+    #[test]
+    fn numbers() {
+        assert!(matches!(normalized(StaticNode::I64(5)), StaticNode::U64(5)));
+        assert!(matches!(normalized(StaticNode::I64(0)), StaticNode::U64(0)));
+        assert!(matches!(
+            normalized(StaticNode::I64(-5)),
+            StaticNode::I64(-5)
+        ));
+        assert!(matches!(
+            normalized(StaticNode::U64(u64::MAX)),
+            StaticNode::U64(u64::MAX)
+        ));
+        assert!(matches!(
+            normalized(StaticNode::F64(2.0)),
+            StaticNode::U64(2)
+        ));
+        assert!(matches!(
+            normalized(StaticNode::F64(-2.0)),
+            StaticNode::I64(-2)
+        ));
+        assert!(matches!(
+            normalized(StaticNode::F64(-0.0)),
+            StaticNode::U64(0)
+        ));
+        assert!(matches!(
+            normalized(StaticNode::F64(1e19)),
+            StaticNode::U64(10_000_000_000_000_000_000)
+        ));
+        assert!(matches!(
+            normalized(StaticNode::F64(U64_END)),
+            StaticNode::F64(_)
+        ));
+        assert!(matches!(
+            normalized(StaticNode::F64(I64_START)),
+            StaticNode::I64(i64::MIN)
+        ));
+        assert!(matches!(
+            normalized(StaticNode::F64(I64_START * 2.0)),
+            StaticNode::F64(_)
+        ));
+        assert!(matches!(
+            normalized(StaticNode::F64(-2.5)),
+            StaticNode::F64(_)
+        ));
+        assert!(matches!(
+            normalized(StaticNode::F64(f64::INFINITY)),
+            StaticNode::F64(_)
+        ));
+        assert!(matches!(
+            normalized(StaticNode::F64(f64::NAN)),
+            StaticNode::F64(_)
+        ));
+        assert!(matches!(
+            normalized(StaticNode::Bool(true)),
+            StaticNode::Bool(true)
+        ));
+        assert!(matches!(normalized(StaticNode::Null), StaticNode::Null));
+    }
+
+    #[cfg(feature = "128bit")]
+    #[test]
+    fn wide_numbers() {
+        assert!(matches!(
+            normalized(StaticNode::I128(7)),
+            StaticNode::U64(7)
+        ));
+        assert!(matches!(
+            normalized(StaticNode::I128(-7)),
+            StaticNode::I64(-7)
+        ));
+        assert!(matches!(
+            normalized(StaticNode::I128(i128::MIN)),
+            StaticNode::I128(i128::MIN)
+        ));
+        assert!(matches!(
+            normalized(StaticNode::U128(7)),
+            StaticNode::U64(7)
+        ));
+        assert!(matches!(
+            normalized(StaticNode::U128(u128::MAX)),
+            StaticNode::U128(u128::MAX)
+        ));
+    }
+
+    #[test]
+    fn values() {
+        let mut v = json!({"a": [1.0, {"b": -1.0}], "c": "1.0", "d": 1});
+        let mut b = BorrowedValue::from(v.clone());
+        v.normalize_numbers();
+        b.normalize_numbers();
+        assert_eq!(b, v);
+        assert_eq!(v["a"][0].value_type(), ValueType::U64);
+        assert_eq!(v["a"][1]["b"].value_type(), ValueType::I64);
+        assert_eq!(v["c"], "1.0");
+        assert_eq!(v["d"].value_type(), ValueType::U64);
+        assert_eq!(b["a"][0].value_type(), ValueType::U64);
+        assert_eq!(b["d"].value_type(), ValueType::U64);
+    }
+}
@@ -21,17 +21,34 @@
 /// ```
 mod cmp;
 mod from;
+#[cfg(feature = "std")]
 mod serialize;
 
+#[cfg(feature = "rayon")]
+use crate::buffers::Buffers;
 use crate::prelude::*;
-use crate::{Deserializer, Node, Result, StaticNode};
+use crate::stats::Stopwatch;
+use crate::value::build::{build, dismantle, Build, Nested};
+use crate::value::emit::{Emit, Part};
+use crate::value::map::{DebugMap, DefaultMaps, MapBackend, ObjectMap};
+#[cfg(feature = "unicode")]
+use crate::Normalization;
+use crate::{
+    ArrayBuilder, Deserializer, Error, Node, ObjectBuilder, ParseOptions, ParseStats, Result,
+    StaticNode,
+};
+use alloc::{boxed::Box, string::String, vec, vec::Vec};
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::{Index, IndexMut, RangeBounds};
 use halfbrown::HashMap;
-use std::fmt;
-use std::ops::{Index, IndexMut};
 
 /// Representation of a JSON object
 pub type Object = HashMap<String, Value>;
 
+/// An entry of an `Object`, see `Value::entry`
+pub type Entry<'a> = halfbrown::Entry<'a, String, Value, halfbrown::DefaultHashBuilder>;
+
 /// Parses a slice of bytes into a Value dom. This function will
 /// rewrite the slice to de-escape strings.
 /// We do not keep any references to the raw data but re-allocate
@@ -48,23 +65,415 @@ pub fn to_value(s: &mut [u8]) -> Result<Value> {
     }
 }
 
+/// Parses a slice of bytes into a Value dom using the given parse
+/// `options`, see `to_value` for details.
+///
+/// # Errors
+///
+/// Will return `Err` if `s` is invalid JSON.
+pub fn to_value_with_options(s: &mut [u8], options: ParseOptions) -> Result<Value> {
+    if let Some(mut repaired) = options.repair(s) {
+        return to_value_with_options(&mut repaired, options);
+    }
+    match Deserializer::from_slice_or_input(s, options) {
+        Ok(de) => Ok(OwnedDeserializer::from_deserializer(de)
+            .with_options(options)
+            .parse()),
+        Err((e, input)) => match options.repair_utf8(input) {
+            Some(mut repaired) => to_value_with_options(&mut repaired, options),
+            None => Err(e),
+        },
+    }
+}
+
+/// Parses a slice of bytes into a value dom like `to_value` with the
+/// objects stored in the maps `M` picks, see `MapBackend`.
+///
+/// # Errors
+///
+/// Will return `Err` if `s` is invalid JSON.
+pub fn to_value_with_map<M: MapBackend>(s: &mut [u8]) -> Result<GenericValue<M>> {
+    match Deserializer::from_slice(s) {
+        Ok(de) => Ok(OwnedDeserializer::from_deserializer(de).parse()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Parses a slice of bytes into a Value dom like `to_value` but leaves
+/// the slice as it is, for callers with data they can't hand out as
+/// mutable. The bytes are copied and the copy is rewritten instead.
+///
+/// ```rust
+/// use simd_json::prelude::*;
+/// let d: &[u8] = br#"{"a": "\u00e9"}"#;
+/// let v = simd_json::to_owned_value_from_slice(d).unwrap();
+/// assert_eq!(v["a"], "é");
+/// assert_eq!(d, br#"{"a": "\u00e9"}"#);
+/// ```
+///
+/// # Errors
+///
+/// Will return `Err` if `s` is invalid JSON.
+pub fn to_value_from_slice(s: &[u8]) -> Result<Value> {
+    to_value(&mut s.to_vec())
+}
+
+/// Parses a string into a Value dom like `to_value_from_slice`, the
+/// string is copied and left as it is.
+///
+/// ```rust
+/// use simd_json::prelude::*;
+/// let v = simd_json::to_owned_value_from_str("[1, 2, 3]").unwrap();
+/// assert_eq!(v[2], 3);
+/// ```
+///
+/// # Errors
+///
+/// Will return `Err` if `s` is invalid JSON.
+pub fn to_value_from_str(s: &str) -> Result<Value> {
+    to_value_from_slice(s.as_bytes())
+}
+
+/// Reads the file at `path` and parses it into a Value dom.
+///
+/// ```rust
+/// let path = std::env::temp_dir().join("simd-json-from-file-doc.json");
+/// std::fs::write(&path, "[1, 2]").unwrap();
+/// let v = simd_json::to_owned_value_from_file(&path).unwrap();
+/// assert_eq!(v, simd_json::json!([1, 2]));
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+///
+/// # Errors
+///
+/// Will return `Err` if the file can't be read or if its content is
+/// invalid JSON.
+#[cfg(feature = "std")]
+pub fn to_value_from_file<P>(path: P) -> Result<Value>
+where
+    P: AsRef<std::path::Path>,
+{
+    match std::fs::read(path) {
+        Ok(mut data) => to_value(&mut data),
+        Err(e) => Err(Error::generic(crate::ErrorType::IO(e))),
+    }
+}
+
+/// Parses a slice of bytes into a Value dom like `to_value` and returns
+/// what the parse found and how long its stages took next to it, see
+/// `ParseStats`.
+///
+/// # Errors
+///
+/// Will return `Err` if `s` is invalid JSON.
+pub fn to_value_with_stats(s: &mut [u8]) -> Result<(Value, ParseStats)> {
+    let mut stats = ParseStats::default();
+    let de = Deserializer::from_slice_with_stats(s, ParseOptions::default(), &mut stats)?;
+    stats.count_allocations(&de.tape, true);
+    let mut watch = Stopwatch::start(true);
+    let value = OwnedDeserializer::from_deserializer(de).parse();
+    stats.build_done(&mut watch);
+    Ok((value, stats))
+}
+
+/// Like `to_value` but uses `buffers` as scratch space
+#[cfg(feature = "rayon")]
+pub(crate) fn to_value_with_buffers(s: &mut [u8], buffers: &mut Buffers) -> Result<Value> {
+    let de = Deserializer::from_slice_with_buffers(s, ParseOptions::default(), None, buffers)?;
+    Ok(OwnedDeserializer::from_deserializer(de).parse())
+}
+
+/// Parses a slice of bytes into a Value dom without stopping at the
+/// first error. Broken values are replaced with `null` and parsing
+/// resumes at the next structural character, every problem found along
+/// the way is returned next to the partial dom.
+///
+/// ```rust
+/// use simd_json::prelude::*;
+/// let mut d = br#"{"a": tru, "b" 2, "c": [1 2]}"#.to_vec();
+/// let (v, errors) = simd_json::to_owned_value_recovering(&mut d).unwrap();
+/// assert_eq!(errors.len(), 3);
+/// assert_eq!(v["b"], 2);
+/// assert_eq!(v["c"][1], 2);
+/// ```
+///
+/// # Errors
+///
+/// Will return `Err` if the structural scan of `s` fails, for example
+/// for invalid UTF-8 or an unterminated string.
+pub fn to_value_recovering(s: &mut [u8]) -> Result<(Value, Vec<Error>)> {
+    to_value_recovering_with_options(s, ParseOptions::default())
+}
+
+/// Like `to_value_recovering` but using the given parse `options`
+///
+/// # Errors
+///
+/// Will return `Err` if the structural scan of `s` fails, for example
+/// for invalid UTF-8 or an unterminated string.
+pub fn to_value_recovering_with_options(
+    s: &mut [u8],
+    options: ParseOptions,
+) -> Result<(Value, Vec<Error>)> {
+    crate::recover::recover(s, options)
+}
+
 /// Owned JSON-DOM Value, consider using the `ValueTrait`
 /// to access it's content.
 /// This is slower then the `BorrowedValue` as a tradeoff
 /// for getting rid of lifetimes.
-#[derive(Debug, Clone)]
-pub enum Value {
+pub type Value = GenericValue<DefaultMaps>;
+
+/// An owned value with its objects stored in the maps `M` picks, see
+/// `MapBackend`. `Value` uses the default maps.
+pub enum GenericValue<M: MapBackend = DefaultMaps> {
     /// Static values
     Static(StaticNode),
     /// string type
     String(String),
     /// array type
-    Array(Vec<Value>),
+    Array(Vec<GenericValue<M>>),
     /// object type
-    Object(Box<Object>),
+    Object(Box<M::Map<String, GenericValue<M>>>),
+    /// binary data, from or for formats other than JSON, written as a
+    /// base64 string in JSON
+    #[cfg(feature = "bytes")]
+    Bytes(Vec<u8>),
 }
 
-impl<'b> Builder<'b> for Value {
+impl Value {
+    /// Starts building an object, see `ObjectBuilder`
+    #[inline]
+    pub fn build_object() -> ObjectBuilder<Self> {
+        ObjectBuilder::new()
+    }
+
+    /// Starts building an array, see `ArrayBuilder`
+    #[inline]
+    pub fn build_array() -> ArrayBuilder<Self> {
+        ArrayBuilder::new()
+    }
+
+    /// Moves the value out, leaving `null` in its place, like
+    /// `mem::take`. Combined with `get_mut` this moves a nested value out
+    /// of a document without cloning it.
+    ///
+    /// ```rust
+    /// use simd_json::prelude::*;
+    /// let mut v = simd_json::json!({"a": [1, 2]});
+    /// let a = v.get_mut("a").unwrap().take();
+    /// assert_eq!(a, simd_json::json!([1, 2]));
+    /// assert!(v["a"].is_null());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn take(&mut self) -> Self {
+        core::mem::take(self)
+    }
+
+    /// Puts `value` in place of the value and returns the old one, like
+    /// `mem::replace`
+    #[inline]
+    #[must_use]
+    pub fn replace(&mut self, value: Self) -> Self {
+        core::mem::replace(self, value)
+    }
+
+    /// Gets the field `key` and panics if there is none, unlike indexing
+    /// with `v[key]` which gives `null`.
+    ///
+    /// ```rust
+    /// use simd_json::prelude::*;
+    /// let v = simd_json::json!({"a": 1});
+    /// assert_eq!(v.expect_key("a"), &1);
+    /// assert!(v["b"].is_null());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// if the value isn't an object or has no field `key`
+    #[inline]
+    #[must_use]
+    pub fn expect_key(&self, key: &str) -> &Self {
+        match self.get(key) {
+            Some(v) => v,
+            None => panic!("no key {:?} in {:?} value", key, self.value_type()),
+        }
+    }
+
+    /// Gets the element at `idx` and panics if there is none, unlike
+    /// indexing with `v[idx]` which gives `null`.
+    ///
+    /// # Panics
+    ///
+    /// if the value isn't an array or `idx` is out of bounds
+    #[inline]
+    #[must_use]
+    pub fn expect_idx(&self, idx: usize) -> &Self {
+        match self.get_idx(idx) {
+            Some(v) => v,
+            None => panic!("no index {} in {:?} value", idx, self.value_type()),
+        }
+    }
+
+    /// Gets the entry of `key` if the value is an object, so a field can
+    /// be inserted or updated with a single lookup.
+    ///
+    /// ```rust
+    /// use simd_json::prelude::*;
+    /// let mut v = simd_json::json!({"a": 1});
+    /// for key in &["a", "b", "a"] {
+    ///     let count = v.entry(*key).unwrap().or_insert_with(|| 0.into());
+    ///     *count = (count.as_u64().unwrap() + 1).into();
+    /// }
+    /// assert_eq!(v, simd_json::json!({"a": 3, "b": 1}));
+    /// ```
+    #[inline]
+    pub fn entry<K: Into<String>>(&mut self, key: K) -> Option<Entry<'_>> {
+        self.as_object_mut().map(|o| o.entry(key.into()))
+    }
+
+    /// Keeps only the elements for which `f` returns `true`, in place and
+    /// in order, like `Vec::retain`.
+    ///
+    /// ```rust
+    /// use simd_json::prelude::*;
+    /// let mut v = simd_json::json!({"a": [1, 2, 3, 4], "b": 2});
+    /// v["a"].retain_items(|x| x.as_u64().unwrap_or(1) % 2 == 0).unwrap();
+    /// assert_eq!(v["a"], simd_json::json!([2, 4]));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `self` is not an array.
+    #[inline]
+    pub fn retain_items<F: FnMut(&Self) -> bool>(
+        &mut self,
+        f: F,
+    ) -> core::result::Result<(), AccessError> {
+        match self {
+            Self::Array(a) => {
+                a.retain(f);
+                Ok(())
+            }
+            _ => Err(AccessError::NotAnArray),
+        }
+    }
+
+    /// Keeps only the entries for which `f` returns `true`, in place, like
+    /// `HashMap::retain`. `f` can change the values it keeps.
+    ///
+    /// ```rust
+    /// use simd_json::prelude::*;
+    /// let mut v = simd_json::json!({"a": [1, 2, 3, 4], "b": 2});
+    /// v.retain_entries(|k, _| k != "a").unwrap();
+    /// assert_eq!(v, simd_json::json!({"b": 2}));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `self` is not an object.
+    #[inline]
+    pub fn retain_entries<F: FnMut(&String, &mut Self) -> bool>(
+        &mut self,
+        f: F,
+    ) -> core::result::Result<(), AccessError> {
+        match self {
+            Self::Object(o) => {
+                o.retain(f);
+                Ok(())
+            }
+            _ => Err(AccessError::NotAnObject),
+        }
+    }
+
+    /// Removes the elements in `range` and returns them, like
+    /// `Vec::drain`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `self` is not an array.
+    ///
+    /// # Panics
+    ///
+    /// If `range` is out of bounds, like `Vec::drain`.
+    #[inline]
+    pub fn drain_items<R: RangeBounds<usize>>(
+        &mut self,
+        range: R,
+    ) -> core::result::Result<vec::Drain<'_, Self>, AccessError> {
+        match self {
+            Self::Array(a) => Ok(a.drain(range)),
+            _ => Err(AccessError::NotAnArray),
+        }
+    }
+
+    /// Removes all entries and returns them, the object stays allocated.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `self` is not an object.
+    #[inline]
+    pub fn drain_entries(
+        &mut self,
+    ) -> core::result::Result<halfbrown::Drain<'_, String, Self>, AccessError> {
+        match self {
+            Self::Object(o) => Ok(o.drain()),
+            _ => Err(AccessError::NotAnObject),
+        }
+    }
+}
+
+impl<M: MapBackend> GenericValue<M> {
+    /// Drops the value without recursing into it. Dropping a value the
+    /// usual way recurses once per level of nesting, for documents that
+    /// are nested many thousands of levels deep that can overflow the
+    /// stack.
+    ///
+    /// ```rust
+    /// use simd_json::{prelude::*, OwnedValue};
+    /// let mut v = OwnedValue::null();
+    /// for _ in 0..100_000 {
+    ///     v = OwnedValue::from(vec![v]);
+    /// }
+    /// v.dismantle();
+    /// ```
+    pub fn dismantle(self) {
+        dismantle(self);
+    }
+}
+
+impl<M: MapBackend> Nested for GenericValue<M> {
+    fn take_children(&mut self, out: &mut Vec<Self>) {
+        match self {
+            Self::Array(a) => out.extend(core::mem::take(a)),
+            Self::Object(o) => out.extend(core::mem::take(&mut **o).into_iter().map(|(_, v)| v)),
+            _ => (),
+        }
+    }
+}
+
+impl<M: MapBackend> Emit for GenericValue<M> {
+    type Key = String;
+    type Entries<'a>
+        = <M::Map<String, Self> as ObjectMap<String, Self>>::Iter<'a>
+    where
+        Self: 'a;
+
+    #[inline(always)]
+    fn part(&self) -> Part<'_, Self> {
+        match self {
+            Self::Static(s) => Part::Static(*s),
+            Self::String(s) => Part::String(s),
+            #[cfg(feature = "bytes")]
+            Self::Bytes(b) => Part::Bytes(b),
+            Self::Array(a) => Part::Array(a),
+            Self::Object(o) => Part::Object(value_trait::Object::len(&**o), o.entries()),
+        }
+    }
+}
+
+impl<M: MapBackend> Builder<'_> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn null() -> Self {
@@ -78,11 +487,11 @@ impl<'b> Builder<'b> for Value {
     #[inline]
     #[must_use]
     fn object_with_capacity(capacity: usize) -> Self {
-        Self::Object(Box::new(Object::with_capacity(capacity)))
+        Self::Object(Box::new(ObjectMap::with_capacity(capacity)))
     }
 }
 
-impl Mutable for Value {
+impl<M: MapBackend> Mutable for GenericValue<M> {
     #[inline]
     #[must_use]
     fn as_array_mut(&mut self) -> Option<&mut Vec<Self>> {
@@ -93,7 +502,7 @@ impl Mutable for Value {
     }
     #[inline]
     #[must_use]
-    fn as_object_mut(&mut self) -> Option<&mut HashMap<<Self as ValueTrait>::Key, Self>> {
+    fn as_object_mut(&mut self) -> Option<&mut <Self as ValueTrait>::Object> {
         match self {
             Self::Object(m) => Some(m),
             _ => None,
@@ -101,10 +510,10 @@ impl Mutable for Value {
     }
 }
 
-impl ValueTrait for Value {
+impl<M: MapBackend> ValueTrait for GenericValue<M> {
     type Key = String;
     type Array = Vec<Self>;
-    type Object = HashMap<Self::Key, Self>;
+    type Object = M::Map<Self::Key, Self>;
 
     #[inline]
     #[must_use]
@@ -114,6 +523,8 @@ impl ValueTrait for Value {
             Self::String(_) => ValueType::String,
             Self::Array(_) => ValueType::Array,
             Self::Object(_) => ValueType::Object,
+            #[cfg(feature = "bytes")]
+            Self::Bytes(_) => ValueType::Custom("bytes"),
         }
     }
 
@@ -213,7 +624,7 @@ impl ValueTrait for Value {
 
     #[inline]
     #[must_use]
-    fn as_object(&self) -> Option<&HashMap<Self::Key, Self>> {
+    fn as_object(&self) -> Option<&<Self as ValueTrait>::Object> {
         match self {
             Self::Object(m) => Some(m),
             _ => None,
@@ -221,37 +632,76 @@ impl ValueTrait for Value {
     }
 }
 
+impl<M: MapBackend> fmt::Debug for GenericValue<M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Static(s) => f.debug_tuple("Static").field(s).finish(),
+            Self::String(s) => f.debug_tuple("String").field(s).finish(),
+            Self::Array(a) => f.debug_tuple("Array").field(a).finish(),
+            Self::Object(o) => f.debug_tuple("Object").field(&DebugMap(&**o)).finish(),
+            #[cfg(feature = "bytes")]
+            Self::Bytes(b) => f.debug_tuple("Bytes").field(b).finish(),
+        }
+    }
+}
+
+impl<M: MapBackend> Clone for GenericValue<M> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Static(s) => Self::Static(*s),
+            Self::String(s) => Self::String(s.clone()),
+            Self::Array(a) => Self::Array(a.clone()),
+            Self::Object(o) => Self::Object(Box::new(o.clone_map())),
+            #[cfg(feature = "bytes")]
+            Self::Bytes(b) => Self::Bytes(b.clone()),
+        }
+    }
+}
+
 #[cfg_attr(tarpaulin, skip)]
-impl fmt::Display for Value {
+impl<M: MapBackend> fmt::Display for GenericValue<M> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Self::Static(s) => s.fmt(f),
             Self::String(s) => write!(f, "{}", s),
             Self::Array(a) => write!(f, "{:?}", a),
-            Self::Object(o) => write!(f, "{:?}", o),
+            Self::Object(o) => write!(f, "{:?}", DebugMap(&**o)),
+            #[cfg(feature = "bytes")]
+            Self::Bytes(b) => write!(f, "{}", crate::value::to_base64(b)),
         }
     }
 }
 
-impl Index<&str> for Value {
+impl<M: MapBackend> Index<&str> for GenericValue<M> {
     type Output = Self;
+    /// Gets the field `index`, or `null` if the value isn't an object or
+    /// has no such field so `v["a"]["b"]` doesn't panic. Use `expect_key`
+    /// to panic instead.
     #[inline]
     #[must_use]
     fn index(&self, index: &str) -> &Self::Output {
-        self.get(index).unwrap()
+        // missing fields and elements give a promoted static `null`
+        self.get(index)
+            .unwrap_or(&GenericValue::Static(StaticNode::Null))
     }
 }
 
-impl Index<usize> for Value {
+impl<M: MapBackend> Index<usize> for GenericValue<M> {
     type Output = Self;
+    /// Gets the element at `index`, or `null` if the value isn't an array
+    /// or is too short. Use `expect_idx` to panic instead.
     #[inline]
     #[must_use]
     fn index(&self, index: usize) -> &Self::Output {
-        self.get_idx(index).unwrap()
+        self.get_idx(index)
+            .unwrap_or(&GenericValue::Static(StaticNode::Null))
     }
 }
 
-impl IndexMut<&str> for Value {
+impl<M: MapBackend> IndexMut<&str> for GenericValue<M> {
+    /// Gets the field `index` mutably, there is no `null` to hand out
+    /// here so this panics if the value isn't an object or has no such
+    /// field
     #[inline]
     #[must_use]
     fn index_mut(&mut self, index: &str) -> &mut Self::Output {
@@ -259,7 +709,9 @@ impl IndexMut<&str> for Value {
     }
 }
 
-impl IndexMut<usize> for Value {
+impl<M: MapBackend> IndexMut<usize> for GenericValue<M> {
+    /// Gets the element at `index` mutably, panics if the value isn't an
+    /// array or is too short
     #[inline]
     #[must_use]
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
@@ -267,7 +719,7 @@ impl IndexMut<usize> for Value {
     }
 }
 
-impl Default for Value {
+impl<M: MapBackend> Default for GenericValue<M> {
     #[inline]
     #[must_use]
     fn default() -> Self {
@@ -275,53 +727,109 @@ impl Default for Value {
     }
 }
 
-struct OwnedDeserializer<'de> {
+struct OwnedDeserializer<'de, M> {
     de: Deserializer<'de>,
+    #[cfg(feature = "unicode")]
+    normalization: Normalization,
+    maps: PhantomData<M>,
 }
 
-impl<'de> OwnedDeserializer<'de> {
+impl<'de, M: MapBackend> OwnedDeserializer<'de, M> {
     pub fn from_deserializer(de: Deserializer<'de>) -> Self {
-        Self { de }
-    }
-    #[cfg_attr(not(feature = "no-inline"), inline(always))]
-    pub fn parse(&mut self) -> Value {
-        match self.de.next_() {
-            Node::Static(s) => Value::Static(s),
-            Node::String(s) => Value::from(s),
-            Node::Array(len, _) => self.parse_array(len),
-            Node::Object(len, _) => self.parse_map(len),
+        Self {
+            de,
+            #[cfg(feature = "unicode")]
+            normalization: Normalization::Off,
+            maps: PhantomData,
         }
     }
 
-    #[cfg_attr(not(feature = "no-inline"), inline(always))]
-    fn parse_array(&mut self, len: usize) -> Value {
-        // Rust doens't optimize the normal loop away here
-        // so we write our own avoiding the lenght
-        // checks during push
-        let mut res = Vec::with_capacity(len);
-        unsafe {
-            res.set_len(len);
-            for i in 0..len {
-                std::ptr::write(res.get_unchecked_mut(i), self.parse())
-            }
-        }
-        Value::Array(res)
+    /// Builds the value the way `options` ask for
+    #[cfg(feature = "unicode")]
+    fn with_options(mut self, options: ParseOptions) -> Self {
+        self.normalization = options.normalization;
+        self
+    }
+
+    /// Builds the value the way `options` ask for
+    #[cfg(not(feature = "unicode"))]
+    fn with_options(self, _options: ParseOptions) -> Self {
+        self
+    }
+
+    /// `s` as a string of the value, `key` tells if it is a key
+    #[cfg(feature = "unicode")]
+    fn owned(&self, s: &str, key: bool) -> String {
+        self.normalization.apply(s, key).into_owned()
+    }
+
+    /// `s` as a string of the value, `key` tells if it is a key
+    #[cfg(not(feature = "unicode"))]
+    #[allow(clippy::unused_self)]
+    fn owned(&self, s: &str, _key: bool) -> String {
+        s.into()
     }
 
     #[cfg_attr(not(feature = "no-inline"), inline(always))]
-    fn parse_map(&mut self, len: usize) -> Value {
-        let mut res = Object::with_capacity(len);
-
-        for _ in 0..len {
-            if let Node::String(key) = self.de.next_() {
-                // We have to call parse short str twice since parse_short_str
-                // does not move the cursor forward
-                res.insert_nocheck(key.into(), self.parse());
-            } else {
-                unreachable!()
-            }
-        }
-        Value::from(res)
+    pub fn parse(&mut self) -> GenericValue<M> {
+        build(self)
+    }
+}
+
+impl<'de, M: MapBackend> Build<'de> for OwnedDeserializer<'de, M> {
+    type Value = GenericValue<M>;
+    type Key = String;
+    type Array = Vec<Self::Value>;
+    type Object = M::Map<Self::Key, Self::Value>;
+
+    #[inline]
+    fn next_node(&mut self) -> Node<'de> {
+        self.de.next_()
+    }
+
+    #[inline]
+    fn static_node(&mut self, s: StaticNode) -> Self::Value {
+        GenericValue::Static(s)
+    }
+
+    #[inline]
+    fn string(&mut self, s: &'de str) -> Self::Value {
+        GenericValue::String(self.owned(s, false))
+    }
+
+    #[inline]
+    fn key(&mut self, s: &'de str) -> Self::Key {
+        self.owned(s, true)
+    }
+
+    #[inline]
+    fn array(&mut self, len: usize) -> Self::Array {
+        Vec::with_capacity(len)
+    }
+
+    #[inline]
+    fn push(&mut self, array: &mut Self::Array, value: Self::Value) {
+        array.push(value);
+    }
+
+    #[inline]
+    fn finish_array(&mut self, array: Self::Array) -> Self::Value {
+        GenericValue::Array(array)
+    }
+
+    #[inline]
+    fn object(&mut self, len: usize) -> Self::Object {
+        ObjectMap::with_capacity(len)
+    }
+
+    #[inline]
+    fn insert(&mut self, object: &mut Self::Object, key: Self::Key, value: Self::Value) {
+        object.insert_parsed(key, value);
+    }
+
+    #[inline]
+    fn finish_object(&mut self, object: Self::Object) -> Self::Value {
+        GenericValue::Object(Box::new(object))
     }
 }
 
@@ -330,6 +838,43 @@ mod test {
     #![allow(clippy::cognitive_complexity)]
     use super::*;
 
+    #[test]
+    fn deep_nesting() {
+        use crate::value::deep::{innermost, nested, DEPTH};
+        let mut d = nested(DEPTH, "null").into_bytes();
+        let v = to_value(&mut d).expect("failed to parse");
+        let (null, depth) = innermost(&v, |v| v.get_idx(0).or_else(|| v.get("a")));
+        assert!(null.is_null());
+        assert_eq!(depth, DEPTH * 2 + 1);
+        v.dismantle();
+    }
+
+    #[test]
+    fn from_slice_and_str() {
+        let src = r#"{"a\"b": ["\n", 1.5, null]}"#;
+        let v = to_value_from_str(src).expect("failed to parse");
+        assert_eq!(
+            v,
+            to_value(&mut src.as_bytes().to_vec()).expect("failed to parse")
+        );
+        assert_eq!(v["a\"b"][0], "\n");
+        assert_eq!(to_value_from_slice(src.as_bytes()), Ok(v));
+        let err = to_value_from_str("[1, }").expect_err("parsed");
+        assert_eq!(err.index(), 4);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn from_file() {
+        let path = std::env::temp_dir().join("simd-json-owned-from-file.json");
+        std::fs::write(&path, br#"{"a": [1, "b"]}"#).expect("failed to write");
+        let v = to_value_from_file(&path).expect("failed to parse");
+        std::fs::remove_file(&path).expect("failed to remove");
+        assert_eq!(v, json!({"a": [1, "b"]}));
+        let err = to_value_from_file(&path).expect_err("read a removed file");
+        assert!(matches!(err.error(), crate::ErrorType::IO(_)));
+    }
+
     #[test]
     fn object_access() {
         let mut v = Value::null();
@@ -633,13 +1178,13 @@ mod test {
 
     #[test]
     fn conversions_f64() {
-        let v = Value::from(std::f64::MAX);
+        let v = Value::from(f64::MAX);
         assert!(!v.is_i64());
         assert!(!v.is_u64());
         assert!(v.is_f64());
         assert!(!v.is_f32());
         assert!(v.is_f64_castable());
-        let v = Value::from(std::f64::MIN);
+        let v = Value::from(f64::MIN);
         assert!(!v.is_i64());
         assert!(!v.is_u64());
         assert!(v.is_f64());
@@ -651,13 +1196,13 @@ mod test {
 
     #[test]
     fn conversions_f32() {
-        let v = Value::from(std::f32::MAX);
+        let v = Value::from(f32::MAX);
         assert!(!v.is_i64());
         assert!(!v.is_u64());
         assert!(v.is_f64());
         assert!(v.is_f32());
         assert!(v.is_f64_castable());
-        let v = Value::from(std::f32::MIN);
+        let v = Value::from(f32::MIN);
         assert!(!v.is_i64());
         assert!(!v.is_u64());
         assert!(v.is_f64());
@@ -760,35 +1305,109 @@ mod test {
         assert_eq!(Value::default(), Value::null())
     }
 
+    #[test]
+    fn take_replace() {
+        let mut v = Value::from(vec![Value::from("a"), Value::from(1)]);
+        let a = v.get_idx_mut(0).unwrap().take();
+        assert_eq!(a, "a");
+        assert_eq!(v, Value::from(vec![Value::null(), Value::from(1)]));
+        let one = v.get_idx_mut(1).unwrap().replace(Value::from(2));
+        assert_eq!(one, 1);
+        assert_eq!(v, Value::from(vec![Value::null(), Value::from(2)]));
+    }
+
+    #[test]
+    fn index_missing() {
+        let v = Value::from(vec![Value::from(1)]);
+        assert_eq!(v[0], 1);
+        assert!(v[1].is_null());
+        assert!(v["a"]["b"][2].is_null());
+        assert_eq!(v.expect_idx(0), &1);
+    }
+
+    #[test]
+    #[should_panic(expected = "no key \"a\" in Array value")]
+    fn expect_key_missing() {
+        let _ = Value::array().expect_key("a");
+    }
+
+    #[test]
+    fn entry() {
+        let mut v = Value::object();
+        match v.entry("a") {
+            Some(halfbrown::Entry::Vacant(e)) => {
+                e.insert(Value::from(1));
+            }
+            _ => panic!("not vacant"),
+        }
+        match v.entry("a") {
+            Some(halfbrown::Entry::Occupied(e)) => assert_eq!(e.get(), &1),
+            _ => panic!("not occupied"),
+        }
+        *v.entry("b").unwrap().or_insert_with(Value::array) = Value::from(2);
+        assert_eq!(v["a"], 1);
+        assert_eq!(v["b"], 2);
+        assert!(Value::null().entry("a").is_none());
+    }
+
+    #[test]
+    fn retain_drain() {
+        let mut v = Value::from(vec![Value::from(1), Value::from(2), Value::from(3)]);
+        v.retain_items(|x| x != &2).unwrap();
+        assert_eq!(v, Value::from(vec![Value::from(1), Value::from(3)]));
+        let drained: Vec<_> = v.drain_items(..1).unwrap().collect();
+        assert_eq!(drained, vec![Value::from(1)]);
+        assert_eq!(v, Value::from(vec![Value::from(3)]));
+        assert_eq!(v.retain_entries(|_, _| true), Err(AccessError::NotAnObject));
+        assert!(v.drain_entries().is_err());
+
+        let mut v = Value::object();
+        v.insert("a", 1).unwrap();
+        v.insert("b", 2).unwrap();
+        v.retain_entries(|_, v| {
+            *v = Value::from(v.as_u64().unwrap() * 10);
+            v != &20
+        })
+        .unwrap();
+        assert_eq!(v["a"], 10);
+        assert!(v.get("b").is_none());
+        let drained: Vec<_> = v.drain_entries().unwrap().collect();
+        assert_eq!(drained.len(), 1);
+        assert_eq!(v, Value::object());
+        assert_eq!(v.retain_items(|_| true), Err(AccessError::NotAnArray));
+        assert!(v.drain_items(..).is_err());
+    }
+
+    #[test]
+    fn collect_extend() {
+        let mut v: Value = (1..3).collect();
+        v.extend(vec!["a"]);
+        assert_eq!(
+            v,
+            Value::from(vec![Value::from(1), Value::from(2), Value::from("a")])
+        );
+        let mut v: Value = vec![("a", 1), ("b", 2)].into_iter().collect();
+        v.extend(vec![("b", 3), ("c", 4)]);
+        assert_eq!(v.as_object().map(Object::len), Some(3));
+        assert_eq!(v["b"], 3);
+        assert_eq!(v["c"], 4);
+    }
+
+    #[test]
+    fn extend_other_values() {
+        let mut v = Value::object();
+        v.extend(vec![1]);
+        assert_eq!(v, Value::from(vec![1]));
+        let mut v = Value::from("a");
+        v.extend(vec![("b", 2)]);
+        assert_eq!(v, vec![("b", 2)].into_iter().collect::<Value>());
+        v.extend(Vec::<Value>::new());
+        assert_eq!(v, Value::array());
+    }
+
     use proptest::prelude::*;
     fn arb_value() -> BoxedStrategy<Value> {
-        let leaf = prop_oneof![
-            Just(Value::Static(StaticNode::Null)),
-            any::<bool>()
-                .prop_map(StaticNode::Bool)
-                .prop_map(Value::Static),
-            any::<i64>()
-                .prop_map(StaticNode::I64)
-                .prop_map(Value::Static),
-            any::<f64>()
-                .prop_map(StaticNode::F64)
-                .prop_map(Value::Static),
-            ".*".prop_map(Value::from),
-        ];
-        leaf.prop_recursive(
-            8,   // 8 levels deep
-            256, // Shoot for maximum size of 256 nodes
-            10,  // We put up to 10 items per collection
-            |inner| {
-                prop_oneof![
-                    // Take the inner strategy and make the two recursive cases.
-                    prop::collection::vec(inner.clone(), 0..10).prop_map(Value::Array),
-                    prop::collection::hash_map(".*", inner.clone(), 0..10)
-                        .prop_map(|m| m.into_iter().collect()),
-                ]
-            },
-        )
-        .boxed()
+        crate::proptest::owned_value(crate::proptest::ValueConfig::default())
     }
 
     proptest! {
@@ -803,6 +1422,7 @@ mod test {
             prop_assert_eq!(owned, borrowed);
         }
 
+        #[cfg(feature = "std")]
         #[test]
         fn prop_serialize_deserialize(owned in arb_value()) {
             let mut string = owned.encode();
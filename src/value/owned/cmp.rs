@@ -1,5 +1,24 @@
-use super::Value;
-use crate::{prelude::*, BorrowedValue};
+use super::{GenericValue, Value};
+use crate::value::map::{self, MapBackend};
+use crate::{prelude::*, BorrowedValue, GenericBorrowedValue};
+use alloc::string::String;
+
+#[allow(clippy::cast_sign_loss, clippy::default_trait_access)]
+impl<M: MapBackend> PartialEq<GenericBorrowedValue<'_, M>> for GenericValue<M> {
+    #[inline]
+    #[must_use]
+    fn eq(&self, other: &GenericBorrowedValue<'_, M>) -> bool {
+        match (self, other) {
+            (Self::Static(s1), GenericBorrowedValue::Static(s2)) => s1 == s2,
+            (Self::String(v1), GenericBorrowedValue::String(v2)) => v1.eq(v2),
+            #[cfg(feature = "bytes")]
+            (Self::Bytes(v1), GenericBorrowedValue::Bytes(v2)) => v1.as_slice() == &**v2,
+            (Self::Array(v1), GenericBorrowedValue::Array(v2)) => v1.eq(v2),
+            (Self::Object(v1), GenericBorrowedValue::Object(v2)) => map::eq(&**v1, &**v2),
+            _ => false,
+        }
+    }
+}
 
 #[allow(clippy::cast_sign_loss, clippy::default_trait_access)]
 impl PartialEq<BorrowedValue<'_>> for Value {
@@ -9,37 +28,35 @@ impl PartialEq<BorrowedValue<'_>> for Value {
         match (self, other) {
             (Self::Static(s1), BorrowedValue::Static(s2)) => s1 == s2,
             (Self::String(v1), BorrowedValue::String(v2)) => v1.eq(v2),
+            #[cfg(feature = "bytes")]
+            (Self::Bytes(v1), BorrowedValue::Bytes(v2)) => v1.as_slice() == &**v2,
             (Self::Array(v1), BorrowedValue::Array(v2)) => v1.eq(v2),
-            (Self::Object(v1), BorrowedValue::Object(v2)) => {
-                if v1.len() != v2.len() {
-                    return false;
-                }
-                v1.iter()
-                    .all(|(key, value)| v2.get(key.as_str()).map_or(false, |v| value == v))
-            }
+            (Self::Object(v1), BorrowedValue::Object(v2)) => map::eq(&**v1, &**v2),
             _ => false,
         }
     }
 }
 
 #[allow(clippy::cast_sign_loss, clippy::default_trait_access)]
-impl PartialEq for Value {
+impl<M: MapBackend> PartialEq for GenericValue<M> {
     #[inline]
     #[must_use]
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Self::Static(s1), Self::Static(s2)) => s1.eq(s2),
             (Self::String(v1), Self::String(v2)) => v1.eq(v2),
+            #[cfg(feature = "bytes")]
+            (Self::Bytes(v1), Self::Bytes(v2)) => v1.eq(v2),
             (Self::Array(v1), Self::Array(v2)) => v1.eq(v2),
-            (Self::Object(v1), Self::Object(v2)) => v1.eq(v2),
+            (Self::Object(v1), Self::Object(v2)) => map::eq(&**v1, &**v2),
             _ => false,
         }
     }
 }
 
-impl<T> PartialEq<&T> for Value
+impl<M: MapBackend, T> PartialEq<&T> for GenericValue<M>
 where
-    Value: PartialEq<T>,
+    GenericValue<M>: PartialEq<T>,
 {
     #[inline]
     #[must_use]
@@ -48,7 +65,7 @@ where
     }
 }
 
-impl PartialEq<()> for Value {
+impl<M: MapBackend> PartialEq<()> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn eq(&self, _other: &()) -> bool {
@@ -56,7 +73,7 @@ impl PartialEq<()> for Value {
     }
 }
 
-impl PartialEq<bool> for Value {
+impl<M: MapBackend> PartialEq<bool> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn eq(&self, other: &bool) -> bool {
@@ -64,7 +81,7 @@ impl PartialEq<bool> for Value {
     }
 }
 
-impl PartialEq<str> for Value {
+impl<M: MapBackend> PartialEq<str> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn eq(&self, other: &str) -> bool {
@@ -72,7 +89,7 @@ impl PartialEq<str> for Value {
     }
 }
 
-impl PartialEq<&str> for Value {
+impl<M: MapBackend> PartialEq<&str> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn eq(&self, other: &&str) -> bool {
@@ -80,7 +97,7 @@ impl PartialEq<&str> for Value {
     }
 }
 
-impl PartialEq<String> for Value {
+impl<M: MapBackend> PartialEq<String> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn eq(&self, other: &String) -> bool {
@@ -88,7 +105,7 @@ impl PartialEq<String> for Value {
     }
 }
 
-impl PartialEq<i8> for Value {
+impl<M: MapBackend> PartialEq<i8> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn eq(&self, other: &i8) -> bool {
@@ -96,7 +113,7 @@ impl PartialEq<i8> for Value {
     }
 }
 
-impl PartialEq<i16> for Value {
+impl<M: MapBackend> PartialEq<i16> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn eq(&self, other: &i16) -> bool {
@@ -104,7 +121,7 @@ impl PartialEq<i16> for Value {
     }
 }
 
-impl PartialEq<i32> for Value {
+impl<M: MapBackend> PartialEq<i32> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn eq(&self, other: &i32) -> bool {
@@ -112,7 +129,7 @@ impl PartialEq<i32> for Value {
     }
 }
 
-impl PartialEq<i64> for Value {
+impl<M: MapBackend> PartialEq<i64> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn eq(&self, other: &i64) -> bool {
@@ -120,7 +137,7 @@ impl PartialEq<i64> for Value {
     }
 }
 
-impl PartialEq<i128> for Value {
+impl<M: MapBackend> PartialEq<i128> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn eq(&self, other: &i128) -> bool {
@@ -128,7 +145,7 @@ impl PartialEq<i128> for Value {
     }
 }
 
-impl PartialEq<u8> for Value {
+impl<M: MapBackend> PartialEq<u8> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn eq(&self, other: &u8) -> bool {
@@ -136,7 +153,7 @@ impl PartialEq<u8> for Value {
     }
 }
 
-impl PartialEq<u16> for Value {
+impl<M: MapBackend> PartialEq<u16> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn eq(&self, other: &u16) -> bool {
@@ -144,7 +161,7 @@ impl PartialEq<u16> for Value {
     }
 }
 
-impl PartialEq<u32> for Value {
+impl<M: MapBackend> PartialEq<u32> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn eq(&self, other: &u32) -> bool {
@@ -152,7 +169,7 @@ impl PartialEq<u32> for Value {
     }
 }
 
-impl PartialEq<u64> for Value {
+impl<M: MapBackend> PartialEq<u64> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn eq(&self, other: &u64) -> bool {
@@ -160,7 +177,7 @@ impl PartialEq<u64> for Value {
     }
 }
 
-impl PartialEq<usize> for Value {
+impl<M: MapBackend> PartialEq<usize> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn eq(&self, other: &usize) -> bool {
@@ -168,7 +185,7 @@ impl PartialEq<usize> for Value {
     }
 }
 
-impl PartialEq<u128> for Value {
+impl<M: MapBackend> PartialEq<u128> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn eq(&self, other: &u128) -> bool {
@@ -176,14 +193,14 @@ impl PartialEq<u128> for Value {
     }
 }
 
-impl PartialEq<f32> for Value {
+impl<M: MapBackend> PartialEq<f32> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn eq(&self, other: &f32) -> bool {
         self.as_f32().map(|t| t.eq(other)).unwrap_or_default()
     }
 }
-impl PartialEq<f64> for Value {
+impl<M: MapBackend> PartialEq<f64> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn eq(&self, other: &f64) -> bool {
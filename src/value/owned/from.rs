@@ -1,6 +1,12 @@
-use super::{Object, Value};
+use super::{GenericValue, Object, Value};
+use crate::value::map::{MapBackend, ObjectMap};
 use crate::{BorrowedValue, StaticNode};
-use std::iter::FromIterator;
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::iter::FromIterator;
+use value_trait::Object as _;
 
 impl From<crate::BorrowedValue<'_>> for Value {
     #[inline]
@@ -11,26 +17,28 @@ impl From<crate::BorrowedValue<'_>> for Value {
             BorrowedValue::String(s) => Self::from(s.to_string()),
             BorrowedValue::Array(a) => a.into_iter().collect(),
             BorrowedValue::Object(m) => m.into_iter().collect(),
+            #[cfg(feature = "bytes")]
+            BorrowedValue::Bytes(b) => Self::Bytes(b.into_owned()),
         }
     }
 }
 
-impl<T> From<Option<T>> for Value
+impl<M: MapBackend, T> From<Option<T>> for GenericValue<M>
 where
-    Value: From<T>,
+    GenericValue<M>: From<T>,
 {
     #[inline]
     #[must_use]
     fn from(s: Option<T>) -> Self {
         if let Some(v) = s {
-            Value::from(v)
+            Self::from(v)
         } else {
-            Value::Static(StaticNode::Null)
+            Self::Static(StaticNode::Null)
         }
     }
 }
 
-impl From<StaticNode> for Value {
+impl<M: MapBackend> From<StaticNode> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn from(s: StaticNode) -> Self {
@@ -39,7 +47,7 @@ impl From<StaticNode> for Value {
 }
 /********* str_ **********/
 
-impl From<&str> for Value {
+impl<M: MapBackend> From<&str> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn from(s: &str) -> Self {
@@ -47,16 +55,16 @@ impl From<&str> for Value {
     }
 }
 
-impl<'v> From<std::borrow::Cow<'v, str>> for Value {
+impl<'v, M: MapBackend> From<alloc::borrow::Cow<'v, str>> for GenericValue<M> {
     #[inline]
     #[must_use]
-    fn from(c: std::borrow::Cow<'v, str>) -> Self {
+    fn from(c: alloc::borrow::Cow<'v, str>) -> Self {
         Self::String(c.to_string())
     }
 }
 
 #[cfg(feature = "beef")]
-impl<'v> From<beef::lean::Cow<'v, str>> for Value {
+impl<'v, M: MapBackend> From<beef::lean::Cow<'v, str>> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn from(c: beef::lean::Cow<'v, str>) -> Self {
@@ -64,7 +72,7 @@ impl<'v> From<beef::lean::Cow<'v, str>> for Value {
     }
 }
 
-impl From<String> for Value {
+impl<M: MapBackend> From<String> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn from(s: String) -> Self {
@@ -72,7 +80,7 @@ impl From<String> for Value {
     }
 }
 
-impl From<&String> for Value {
+impl<M: MapBackend> From<&String> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn from(s: &String) -> Self {
@@ -82,7 +90,7 @@ impl From<&String> for Value {
 
 /********* atoms **********/
 
-impl From<bool> for Value {
+impl<M: MapBackend> From<bool> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn from(b: bool) -> Self {
@@ -90,7 +98,7 @@ impl From<bool> for Value {
     }
 }
 
-impl From<()> for Value {
+impl<M: MapBackend> From<()> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn from(_b: ()) -> Self {
@@ -99,7 +107,7 @@ impl From<()> for Value {
 }
 
 /********* i_ **********/
-impl From<i8> for Value {
+impl<M: MapBackend> From<i8> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn from(i: i8) -> Self {
@@ -107,7 +115,7 @@ impl From<i8> for Value {
     }
 }
 
-impl From<i16> for Value {
+impl<M: MapBackend> From<i16> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn from(i: i16) -> Self {
@@ -115,7 +123,7 @@ impl From<i16> for Value {
     }
 }
 
-impl From<i32> for Value {
+impl<M: MapBackend> From<i32> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn from(i: i32) -> Self {
@@ -123,7 +131,7 @@ impl From<i32> for Value {
     }
 }
 
-impl From<i64> for Value {
+impl<M: MapBackend> From<i64> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn from(i: i64) -> Self {
@@ -131,7 +139,7 @@ impl From<i64> for Value {
     }
 }
 #[cfg(feature = "128bit")]
-impl From<i128> for Value {
+impl<M: MapBackend> From<i128> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn from(i: i128) -> Self {
@@ -140,7 +148,7 @@ impl From<i128> for Value {
 }
 
 /********* u_ **********/
-impl From<u8> for Value {
+impl<M: MapBackend> From<u8> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn from(i: u8) -> Self {
@@ -148,7 +156,7 @@ impl From<u8> for Value {
     }
 }
 
-impl From<u16> for Value {
+impl<M: MapBackend> From<u16> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn from(i: u16) -> Self {
@@ -156,7 +164,7 @@ impl From<u16> for Value {
     }
 }
 
-impl From<u32> for Value {
+impl<M: MapBackend> From<u32> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn from(i: u32) -> Self {
@@ -164,7 +172,7 @@ impl From<u32> for Value {
     }
 }
 
-impl From<u64> for Value {
+impl<M: MapBackend> From<u64> for GenericValue<M> {
     #[inline]
     #[must_use]
     #[allow(clippy::cast_possible_wrap)]
@@ -174,7 +182,7 @@ impl From<u64> for Value {
 }
 
 #[cfg(feature = "128bit")]
-impl From<u128> for Value {
+impl<M: MapBackend> From<u128> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn from(i: u128) -> Self {
@@ -182,7 +190,7 @@ impl From<u128> for Value {
     }
 }
 
-impl From<usize> for Value {
+impl<M: MapBackend> From<usize> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn from(i: usize) -> Self {
@@ -191,7 +199,7 @@ impl From<usize> for Value {
 }
 
 /********* f_ **********/
-impl From<f32> for Value {
+impl<M: MapBackend> From<f32> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn from(f: f32) -> Self {
@@ -199,7 +207,7 @@ impl From<f32> for Value {
     }
 }
 
-impl From<f64> for Value {
+impl<M: MapBackend> From<f64> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn from(f: f64) -> Self {
@@ -207,9 +215,9 @@ impl From<f64> for Value {
     }
 }
 
-impl<S> From<Vec<S>> for Value
+impl<M: MapBackend, S> From<Vec<S>> for GenericValue<M>
 where
-    Value: From<S>,
+    GenericValue<M>: From<S>,
 {
     #[inline]
     #[must_use]
@@ -218,7 +226,7 @@ where
     }
 }
 
-impl<V: Into<Value>> FromIterator<V> for Value {
+impl<M: MapBackend, V: Into<GenericValue<M>>> FromIterator<V> for GenericValue<M> {
     #[inline]
     #[must_use]
     fn from_iter<I: IntoIterator<Item = V>>(iter: I) -> Self {
@@ -226,15 +234,47 @@ impl<V: Into<Value>> FromIterator<V> for Value {
     }
 }
 
-impl<K: ToString, V: Into<Value>> FromIterator<(K, V)> for Value {
+impl<M: MapBackend, K: ToString, V: Into<GenericValue<M>>> FromIterator<(K, V)>
+    for GenericValue<M>
+{
     #[inline]
     #[must_use]
     fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
-        Self::Object(Box::new(
-            iter.into_iter()
-                .map(|(k, v)| (k.to_string(), Into::into(v)))
-                .collect(),
-        ))
+        let iter = iter.into_iter();
+        let mut res = M::Map::with_capacity(iter.size_hint().0);
+        for (k, v) in iter {
+            let _ = res.insert(k.to_string(), v);
+        }
+        Self::Object(Box::new(res))
+    }
+}
+
+/// Pushes the values onto an array. A value that isn't an array is
+/// replaced by the array `collect` makes of them.
+impl<M: MapBackend, V: Into<GenericValue<M>>> Extend<V> for GenericValue<M> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = V>>(&mut self, iter: I) {
+        match self {
+            Self::Array(a) => a.extend(iter.into_iter().map(Into::into)),
+            _ => *self = iter.into_iter().collect(),
+        }
+    }
+}
+
+/// Inserts the entries into an object, replacing existing keys. A value
+/// that isn't an object is replaced by the object `collect` makes of
+/// them.
+impl<M: MapBackend, K: ToString, V: Into<GenericValue<M>>> Extend<(K, V)> for GenericValue<M> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        match self {
+            Self::Object(o) => {
+                for (k, v) in iter {
+                    let _ = o.insert(k.to_string(), v.into());
+                }
+            }
+            _ => *self = <Self as FromIterator<(K, V)>>::from_iter(iter),
+        }
     }
 }
 
@@ -246,6 +286,7 @@ impl From<Object> for Value {
     }
 }
 
+#[cfg(feature = "std")]
 impl From<std::collections::HashMap<String, Value>> for Value {
     #[inline]
     #[must_use]
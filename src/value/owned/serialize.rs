@@ -4,29 +4,32 @@
 //
 // https://github.com/maciejhirsz/json-rust/blob/master/src/codegen.rs
 
-use super::{Object, Value};
+use super::{GenericValue, Value};
 use crate::prelude::*;
-use crate::{stry, StaticNode};
+use crate::value::encoder::{append_to, into_string, write_json, FmtWriter, OptionsGenerator};
+use crate::value::map::MapBackend;
+use crate::EncodeOptions;
+use core::fmt;
 use std::io;
 use std::io::Write;
 use value_trait::generator::{
-    BaseGenerator, DumpGenerator, PrettyGenerator, PrettyWriterGenerator, WriterGenerator,
+    DumpGenerator, PrettyGenerator, PrettyWriterGenerator, WriterGenerator,
 };
 
 //use util::print_dec;
 
-impl Writable for Value {
+impl<M: MapBackend> Writable for GenericValue<M> {
     #[inline]
     fn encode(&self) -> String {
-        let mut g = DumpGenerator::new();
-        let _ = g.write_json(&self);
+        let mut g = DumpGenerator::<Value>::new();
+        let _ = write_json(&mut g, self);
         g.consume()
     }
 
     #[inline]
     fn encode_pp(&self) -> String {
-        let mut g = PrettyGenerator::new(2);
-        let _ = g.write_json(&self);
+        let mut g = PrettyGenerator::<Value>::new(2);
+        let _ = write_json(&mut g, self);
         g.consume()
     }
 
@@ -35,8 +38,8 @@ impl Writable for Value {
     where
         W: 'writer + Write,
     {
-        let mut g = WriterGenerator::new(w);
-        g.write_json(self)
+        let mut g = WriterGenerator::<_, Value>::new(w);
+        write_json(&mut g, self)
     }
 
     #[inline]
@@ -44,177 +47,87 @@ impl Writable for Value {
     where
         W: 'writer + Write,
     {
-        let mut g = PrettyWriterGenerator::new(w, 2);
-        g.write_json(self)
+        let mut g = PrettyWriterGenerator::<_, Value>::new(w, 2);
+        write_json(&mut g, self)
     }
 }
 
-trait Generator: BaseGenerator {
-    type T: Write;
-
-    #[inline(always)]
-    fn write_object(&mut self, object: &Object) -> io::Result<()> {
-        if object.is_empty() {
-            self.write(b"{}")
-        } else {
-            let mut iter = object.iter();
-            self.write(b"{")
-                .and_then(|_| {
-                    // We know this exists since it's not empty
-                    let (key, value) = iter.next().unwrap();
-                    self.indent();
-                    self.new_line()
-                        .and_then(|_| self.write_simple_string(key))
-                        .and_then(|_| self.write_min(b": ", b':'))
-                        .and_then(|_| self.write_json(value))
-                })
-                .and_then(|_| {
-                    for (key, value) in iter {
-                        stry!(self
-                            .write(b",")
-                            .and_then(|_| self.new_line())
-                            .and_then(|_| self.write_simple_string(key))
-                            .and_then(|_| self.write_min(b": ", b':'))
-                            .and_then(|_| self.write_json(value)));
-                    }
-                    self.dedent();
-                    self.new_line().and_then(|_| self.write(b"}"))
-                })
-        }
+impl Value {
+    /// Encodes the value into a string honouring the given `options`
+    #[must_use]
+    pub fn encode_with(&self, options: EncodeOptions) -> String {
+        let mut g = OptionsGenerator::new(Vec::with_capacity(1024), options);
+        let _ = write_json(&mut g, self);
+        into_string(g.consume())
     }
 
-    #[inline(always)]
-    fn write_json(&mut self, json: &Value) -> io::Result<()> {
-        match *json {
-            Value::Static(StaticNode::Null) => self.write(b"null"),
-            Value::Static(StaticNode::I64(number)) => self.write_int(number),
-            #[cfg(feature = "128bit")]
-            Value::Static(StaticNode::I128(number)) => self.write_int(number),
-            Value::Static(StaticNode::U64(number)) => self.write_int(number),
-            #[cfg(feature = "128bit")]
-            Value::Static(StaticNode::U128(number)) => self.write_int(number),
-            Value::Static(StaticNode::F64(number)) => self.write_float(number),
-            Value::Static(StaticNode::Bool(true)) => self.write(b"true"),
-            Value::Static(StaticNode::Bool(false)) => self.write(b"false"),
-            Value::String(ref string) => self.write_string(string),
-            Value::Array(ref array) => {
-                if array.is_empty() {
-                    self.write(b"[]")
-                } else {
-                    let mut iter = <[Value]>::iter(array);
-                    // We know we have one item
-
-                    let item = iter.next().unwrap();
-                    self.write(b"[")
-                        .and_then(|_| {
-                            self.indent();
-                            self.new_line().and_then(|_| self.write_json(item))
-                        })
-                        .and_then(|_| {
-                            for item in iter {
-                                stry!(self
-                                    .write(b",")
-                                    .and_then(|_| self.new_line())
-                                    .and_then(|_| self.write_json(item)));
-                            }
-
-                            self.dedent();
-                            self.new_line().and_then(|_| self.write(b"]"))
-                        })
-                }
-            }
-            Value::Object(ref object) => self.write_object(object),
-        }
+    /// Appends the encoded value to `out`, like `encode` but reusing the
+    /// allocation of `out` for producers that write many values. `write`
+    /// does the same for a `Vec<u8>`.
+    ///
+    /// ```rust
+    /// use simd_json::{json, OwnedValue};
+    /// let mut out = String::with_capacity(64);
+    /// for i in 0..2 {
+    ///     OwnedValue::from(json!({"n": i})).encode_into(&mut out);
+    ///     out.push('\n');
+    /// }
+    /// assert_eq!(out, "{\"n\":0}\n{\"n\":1}\n");
+    /// ```
+    pub fn encode_into(&self, out: &mut String) {
+        append_to(out, |bytes| {
+            let _ = self.write(bytes);
+        });
     }
-}
-
-trait FastGenerator: BaseGenerator {
-    type T: Write;
 
-    #[inline(always)]
-    fn write_object(&mut self, object: &Object) -> io::Result<()> {
-        if object.is_empty() {
-            self.write(b"{}")
-        } else {
-            let mut iter = object.iter();
-            self.write(b"{")
-                .and_then(|_| {
-                    // We know this exists since it's not empty
-                    let (key, value) = iter.next().unwrap();
-                    self.write_simple_string(key)
-                        .and_then(|_| self.write(b":"))
-                        .and_then(|_| self.write_json(value))
-                })
-                .and_then(|_| {
-                    for (key, value) in iter {
-                        stry!(self
-                            .write(b",")
-                            .and_then(|_| self.write_simple_string(key))
-                            .and_then(|_| self.write(b":"))
-                            .and_then(|_| self.write_json(value)));
-                    }
-                    self.write(b"}")
-                })
-        }
+    /// Appends the value encoded with `options` to `out`, like
+    /// `encode_with` but reusing the allocation of `out`
+    pub fn encode_with_into(&self, out: &mut String, options: EncodeOptions) {
+        append_to(out, |bytes| {
+            let _ = self.write_with(bytes, options);
+        });
     }
 
-    #[inline(always)]
-    fn write_json(&mut self, json: &Value) -> io::Result<()> {
-        match *json {
-            Value::Static(StaticNode::Null) => self.write(b"null"),
-            Value::Static(StaticNode::I64(number)) => self.write_int(number),
-            #[cfg(feature = "128bit")]
-            Value::Static(StaticNode::I128(number)) => self.write_int(number),
-            Value::Static(StaticNode::U64(number)) => self.write_int(number),
-            #[cfg(feature = "128bit")]
-            Value::Static(StaticNode::U128(number)) => self.write_int(number),
-            Value::Static(StaticNode::F64(number)) => self.write_float(number),
-            Value::Static(StaticNode::Bool(true)) => self.write(b"true"),
-            Value::Static(StaticNode::Bool(false)) => self.write(b"false"),
-            Value::String(ref string) => self.write_string(string),
-            Value::Array(ref array) => {
-                if array.is_empty() {
-                    self.write(b"[]")
-                } else {
-                    let mut iter = <[Value]>::iter(array);
-                    // We know we have one item
-                    let item = iter.next().unwrap();
-
-                    self.write(b"[")
-                        .and_then(|_| self.write_json(item))
-                        .and_then(|_| {
-                            for item in iter {
-                                stry!(self.write(b",").and_then(|_| self.write_json(item)))
-                            }
-                            self.write(b"]")
-                        })
-                }
-            }
-            Value::Object(ref object) => self.write_object(object),
-        }
+    /// Writes the value encoded with `options` to a `fmt::Write`, like a
+    /// `fmt::Formatter`, without encoding it into a string first
+    ///
+    /// ```rust
+    /// use simd_json::{json, EncodeOptions, OwnedValue};
+    /// use std::fmt;
+    /// struct Event(OwnedValue);
+    /// impl fmt::Display for Event {
+    ///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    ///         f.write_str("event: ")?;
+    ///         self.0.encode_fmt(f, EncodeOptions::default())
+    ///     }
+    /// }
+    /// let event = Event(json!({"id": 1}).into());
+    /// assert_eq!(event.to_string(), "event: {\"id\":1}");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// if the write fails
+    pub fn encode_fmt<W>(&self, w: &mut W, options: EncodeOptions) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        self.write_with(&mut FmtWriter::new(w), options)
+            .map_err(|_| fmt::Error)
     }
-}
-
-impl FastGenerator for DumpGenerator<Value> {
-    type T = Vec<u8>;
-}
-
-impl Generator for PrettyGenerator<Value> {
-    type T = Vec<u8>;
-}
 
-impl<'w, W> FastGenerator for WriterGenerator<'w, W, Value>
-where
-    W: Write,
-{
-    type T = W;
-}
-
-impl<'w, W> Generator for PrettyWriterGenerator<'w, W, Value>
-where
-    W: Write,
-{
-    type T = W;
+    /// Writes the value to `w` honouring the given `options`
+    ///
+    /// # Errors
+    ///
+    /// if the write fails
+    pub fn write_with<W>(&self, w: &mut W, options: EncodeOptions) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let mut g = OptionsGenerator::new(w, options);
+        write_json(&mut g, self)
+    }
 }
 
 #[cfg(test)]
@@ -234,6 +147,266 @@ mod test {
     fn bool_false() {
         assert_eq!(Value::Static(StaticNode::Bool(false)).encode(), "false")
     }
+    #[test]
+    fn non_finite() {
+        use crate::{EncodeOptions, NonFiniteFloats};
+        let v = Value::from(vec![
+            Value::from(f64::NAN),
+            Value::from(f64::INFINITY),
+            Value::from(f64::NEG_INFINITY),
+        ]);
+        assert_eq!(v.encode(), "[null,null,null]");
+        assert_eq!(v.encode_with(EncodeOptions::default()), "[null,null,null]");
+        let options = EncodeOptions::default().non_finite(NonFiniteFloats::Literal);
+        assert_eq!(v.encode_with(options), "[NaN,Infinity,-Infinity]");
+        assert_eq!(
+            v.encode_with(options.pretty(2)),
+            "[\n  NaN,\n  Infinity,\n  -Infinity\n]"
+        );
+    }
+    #[test]
+    fn encode_into() {
+        use crate::EncodeOptions;
+        let v = Value::from(vec![Value::from("é"), Value::from(f64::NAN)]);
+        let mut out = String::from("x");
+        v.encode_into(&mut out);
+        v.encode_with_into(&mut out, EncodeOptions::default().ascii_only(true));
+        assert_eq!(out, "x[\"é\",null][\"\\u00e9\",null]");
+        let capacity = out.capacity();
+        out.clear();
+        v.encode_into(&mut out);
+        assert_eq!(out, v.encode());
+        assert_eq!(out.capacity(), capacity);
+        let mut bytes = b"y".to_vec();
+        v.write(&mut bytes).expect("failed to write");
+        assert_eq!(bytes, b"y[\"\xc3\xa9\",null]");
+    }
+
+    #[test]
+    fn encode_fmt() {
+        use crate::value::encoder::FmtWriter;
+        use crate::EncodeOptions;
+        use core::fmt::Write;
+        use std::io::Write as _;
+
+        struct Full;
+        impl Write for Full {
+            fn write_str(&mut self, _: &str) -> core::fmt::Result {
+                Err(core::fmt::Error)
+            }
+        }
+        // a string longer than the SIMD blocks, with characters of all
+        // lengths at every offset
+        let s: String = "aé€😀".repeat(40);
+        let v = Value::from(vec![Value::from(s.as_str()), Value::from(1.5)]);
+        let mut out = String::from("json: ");
+        v.encode_fmt(&mut out, EncodeOptions::default())
+            .expect("failed to write");
+        assert_eq!(out, alloc::format!("json: {}", v.encode()));
+        out.clear();
+        v.encode_fmt(&mut out, EncodeOptions::default().pretty(2))
+            .expect("failed to write");
+        assert_eq!(out, v.encode_pp());
+
+        assert!(v.encode_fmt(&mut Full, EncodeOptions::default()).is_err());
+
+        // characters split over writes
+        let mut out = String::new();
+        let mut w = FmtWriter::new(&mut out);
+        for chunk in "aé€😀".as_bytes().chunks(3) {
+            w.write_all(chunk).expect("failed to write");
+        }
+        assert!(w.write_all(b"\xff").is_err());
+        assert_eq!(out, "aé€😀");
+    }
+
+    #[test]
+    fn float_format() {
+        use crate::{EncodeOptions, FloatFormat, NegativeZero, NonFiniteFloats};
+        let v = Value::from(vec![
+            Value::from(-0.0),
+            Value::from(0.125),
+            Value::from(-123_456.789),
+            Value::from(1e-7),
+            Value::from(f64::NAN),
+            Value::from(3),
+        ]);
+        let options = EncodeOptions::default();
+        assert_eq!(
+            v.encode_with(options),
+            "[-0.0,0.125,-123456.789,1e-7,null,3]"
+        );
+        let fixed = options.float_format(FloatFormat::Fixed(2));
+        assert_eq!(v.encode_with(fixed), "[-0.00,0.12,-123456.79,0.00,null,3]");
+        assert_eq!(
+            v.encode_with(options.float_format(FloatFormat::Fixed(0))),
+            "[-0,0,-123457,0,null,3]"
+        );
+        let scientific = options
+            .float_format(FloatFormat::Scientific(3))
+            .non_finite(NonFiniteFloats::Literal);
+        assert_eq!(
+            v.encode_with(scientific),
+            "[-0.000e0,1.250e-1,-1.235e5,1.000e-7,NaN,3]"
+        );
+        let positive = fixed.negative_zero(NegativeZero::Positive);
+        assert_eq!(
+            v.encode_with(positive),
+            "[0.00,0.12,-123456.79,0.00,null,3]"
+        );
+        // everything but the literals reads back
+        let mut s = v
+            .encode_with(scientific.non_finite(NonFiniteFloats::Null))
+            .into_bytes();
+        let back = crate::to_owned_value(&mut s).expect("invalid JSON");
+        assert_eq!(back[2], -123_500.0);
+    }
+
+    #[test]
+    fn ascii_only() {
+        use crate::{BorrowedValue, EncodeOptions};
+        let mut v = Value::object();
+        v.insert("ключ\n", "a\"\\/\u{1}\u{7f}é€𝄞\t")
+            .expect("not an object");
+        let options = EncodeOptions::default().ascii_only(true);
+        let s = v.encode_with(options);
+        assert_eq!(
+            s,
+            "{\"\\u043a\\u043b\\u044e\\u0447\\n\":\
+             \"a\\\"\\\\/\\u0001\u{7f}\\u00e9\\u20ac\\ud834\\udd1e\\t\"}"
+        );
+        assert!(s.is_ascii());
+        assert_eq!(BorrowedValue::from(v.clone()).encode_with(options), s);
+        // the same string when read back
+        let back = crate::to_owned_value(&mut s.into_bytes()).expect("invalid JSON");
+        assert_eq!(back, v);
+        assert_eq!(
+            v.encode_with(options.ascii_only(false).pretty(2)),
+            v.encode_pp()
+        );
+    }
+
+    #[test]
+    fn html_safe() {
+        use crate::EncodeOptions;
+        let mut v = Value::object();
+        v.insert("<&>", "</script><!-- \u{2028}\u{2029}é")
+            .expect("not an object");
+        let options = EncodeOptions::default().html_safe(true);
+        let s = v.encode_with(options);
+        assert_eq!(
+            s,
+            "{\"\\u003c\\u0026\\u003e\":\"\\u003c/script\\u003e\\u003c!-- \\u2028\\u2029é\"}"
+        );
+        let back = crate::to_owned_value(&mut s.into_bytes()).expect("invalid JSON");
+        assert_eq!(back, v);
+        let s = v.encode_with(options.ascii_only(true));
+        assert!(s.ends_with("\\u2028\\u2029\\u00e9\"}"));
+    }
+
+    #[test]
+    fn escape_hook() {
+        use crate::{EncodeOptions, Escape};
+        fn hook(c: char) -> Escape {
+            match c {
+                '\u{1}' => Escape::Keep,
+                'x' => Escape::Unicode,
+                '"' => Escape::Text("\\u0022"),
+                _ => Escape::Default,
+            }
+        }
+        let v = Value::from(vec!["x\u{1}\"<\n€", "plain"]);
+        let options = EncodeOptions::default().escape_with(hook);
+        assert_eq!(
+            v.encode_with(options),
+            "[\"\\u0078\u{1}\\u0022<\\n€\",\"plain\"]"
+        );
+        // the hook goes first, the other options for what it leaves alone
+        assert_eq!(
+            v.encode_with(options.html_safe(true).ascii_only(true)),
+            "[\"\\u0078\u{1}\\u0022\\u003c\\n\\u20ac\",\"plain\"]"
+        );
+    }
+
+    #[cfg(feature = "color")]
+    #[test]
+    fn colors() {
+        use crate::{BorrowedValue, Colors, EncodeOptions, NonFiniteFloats};
+        let colors = Colors::default()
+            .key("k")
+            .string("s")
+            .number("n")
+            .boolean("b")
+            .null("z");
+        let v = Value::from(vec![
+            Value::from("a\"b"),
+            Value::from(-1),
+            Value::from(1.5),
+            Value::from(f64::NAN),
+            Value::from(false),
+            Value::object(),
+        ]);
+        let options = EncodeOptions::default().colors(colors);
+        let expected = "[\x1b[sm\"a\\\"b\"\x1b[0m,\x1b[nm-1\x1b[0m,\x1b[nm1.5\x1b[0m,\
+                        \x1b[zmnull\x1b[0m,\x1b[bmfalse\x1b[0m,{}]";
+        assert_eq!(v.encode_with(options), expected);
+        assert_eq!(
+            BorrowedValue::from(v.clone()).encode_with(options),
+            expected
+        );
+        let options = options.non_finite(NonFiniteFloats::Literal);
+        assert!(v.encode_with(options).contains("\x1b[nmNaN\x1b[0m"));
+        // without colors nothing changes
+        assert_eq!(
+            v.encode_with(EncodeOptions::default().pretty(2)),
+            v.encode_pp()
+        );
+    }
+
+    #[test]
+    fn deep_nesting() {
+        use crate::value::deep::{nested, DEPTH};
+        use crate::EncodeOptions;
+        let input = nested(DEPTH, "1");
+        let v = crate::to_owned_value(&mut input.clone().into_bytes()).expect("invalid json");
+        assert_eq!(v.encode(), input);
+        assert_eq!(v.encode_with(EncodeOptions::default()), input);
+        v.dismantle();
+    }
+
+    #[test]
+    fn deep_nesting_pretty() {
+        use crate::value::deep::nested;
+        // pretty output grows with the square of the depth, so we go less
+        // deep and use a stack that recursing per level would overflow
+        let input = nested(1_000, "1");
+        let v = crate::to_owned_value(&mut input.clone().into_bytes()).expect("invalid json");
+        let pretty = std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn(move || {
+                let s = v.encode_pp();
+                v.dismantle();
+                s
+            })
+            .expect("failed to spawn thread")
+            .join()
+            .expect("failed to encode");
+        assert!(pretty.starts_with("[\n  {\n    \"a\": [\n      {\n"));
+        let v = crate::to_owned_value(&mut pretty.into_bytes()).expect("invalid json");
+        assert_eq!(v.encode(), input);
+    }
+
+    #[test]
+    fn wide_indent() {
+        use crate::value::deep::nested;
+        use crate::EncodeOptions;
+        // the innermost line is indented by more than `u16::MAX` spaces
+        let v = crate::to_owned_value(&mut nested(40, "1").into_bytes()).expect("invalid json");
+        let s = v.encode_with(EncodeOptions::default().pretty(1_000));
+        let innermost = alloc::format!("\n{}\"a\": 1\n", " ".repeat(80_000));
+        assert!(s.contains(&innermost));
+    }
+
     fn assert_str(from: &str, to: &str) {
         assert_eq!(Value::String(from.into()).encode(), to)
     }
@@ -0,0 +1,352 @@
+use crate::prelude::*;
+use crate::{BorrowedValue, OwnedValue};
+use alloc::borrow::Cow;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::Hash;
+
+/// What `insert_at_with` does about the parents of a path that don't
+/// exist yet, `null` counts as missing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MissingParents {
+    /// Creates an array where the next step of the path is an index or
+    /// `-` and an object otherwise, the default
+    #[default]
+    Infer,
+    /// Always creates objects, so numbers become keys
+    Objects,
+    /// Doesn't create anything and fails with `PathError::Missing`
+    Fail,
+}
+
+/// Error for inserting at a path, each variant holds the position of
+/// the step of the path that couldn't be taken, starting at 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathError {
+    /// A parent is missing and `MissingParents::Fail` was used
+    Missing(usize),
+    /// A parent is neither an array nor an object
+    NotAContainer(usize),
+    /// A step into an array isn't a number or `-`, or is past the end
+    /// of the array
+    BadIndex(usize),
+}
+
+#[cfg_attr(tarpaulin, skip)]
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Missing(i) => write!(f, "Missing parent at step {}", i),
+            Self::NotAContainer(i) => write!(f, "Not an array or object at step {}", i),
+            Self::BadIndex(i) => write!(f, "Bad array index at step {}", i),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PathError {}
+
+impl OwnedValue {
+    /// Inserts `value` at `path`, creating the objects and arrays on the
+    /// way that don't exist yet, see `insert_at_with`.
+    ///
+    /// ```rust
+    /// use simd_json::{json, OwnedValue};
+    /// let mut v = OwnedValue::default();
+    /// v.insert_at("/server/ports/0", 80).unwrap();
+    /// v.insert_at("/server/ports/-", 443).unwrap();
+    /// let old = v.insert_at("/server/ports/0", 8080).unwrap();
+    /// assert_eq!(old, Some(OwnedValue::from(80)));
+    /// assert_eq!(v, json!({"server": {"ports": [8080, 443]}}));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// if a parent on the way can't hold the next step of `path`
+    pub fn insert_at<T: Into<Self>>(
+        &mut self,
+        path: &str,
+        value: T,
+    ) -> Result<Option<Self>, PathError> {
+        self.insert_at_with(path, value, MissingParents::Infer)
+    }
+
+    /// Inserts `value` at `path` and returns the value it replaced, see
+    /// `BorrowedValue::insert_at_with`.
+    ///
+    /// # Errors
+    ///
+    /// if a parent on the way can't hold the next step of `path`
+    pub fn insert_at_with<T: Into<Self>>(
+        &mut self,
+        path: &str,
+        value: T,
+        missing: MissingParents,
+    ) -> Result<Option<Self>, PathError> {
+        insert(self, &steps(path), 0, value.into(), missing)
+    }
+
+    /// Removes the value at `path` and returns it, see
+    /// `BorrowedValue::remove_at`.
+    ///
+    /// ```rust
+    /// use simd_json::{json, OwnedValue};
+    /// let mut v = json!({"a": [1, 2, 3]});
+    /// assert_eq!(v.remove_at("/a/0"), Some(OwnedValue::from(1)));
+    /// assert_eq!(v, json!({"a": [2, 3]}));
+    /// ```
+    pub fn remove_at(&mut self, path: &str) -> Option<Self> {
+        remove(self, &steps(path))
+    }
+}
+
+impl BorrowedValue<'_> {
+    /// Inserts `value` at `path`, creating the objects and arrays on the
+    /// way that don't exist yet, see `insert_at_with`.
+    ///
+    /// ```rust
+    /// use simd_json::{json, BorrowedValue};
+    /// let mut v = BorrowedValue::default();
+    /// v.insert_at("/a/0/b", true).unwrap();
+    /// assert_eq!(v, json!({"a": [{"b": true}]}));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// if a parent on the way can't hold the next step of `path`
+    pub fn insert_at<T: Into<Self>>(
+        &mut self,
+        path: &str,
+        value: T,
+    ) -> Result<Option<Self>, PathError> {
+        self.insert_at_with(path, value, MissingParents::Infer)
+    }
+
+    /// Inserts `value` at `path` and returns the value it replaced.
+    ///
+    /// `path` is a list of keys separated by `/` like a JSON pointer, the
+    /// leading `/` is optional and `~1` and `~0` escape `/` and `~`.
+    /// Steps into arrays are indexes, an index one past the end or `-`
+    /// appends. Parents that are missing or `null` are created as
+    /// `missing` says, the empty path replaces the whole value.
+    ///
+    /// ```rust
+    /// use simd_json::{json, BorrowedValue, MissingParents, PathError};
+    /// let mut v = BorrowedValue::default();
+    /// v.insert_at_with("/a/0", 1, MissingParents::Objects).unwrap();
+    /// assert_eq!(v, json!({"a": {"0": 1}}));
+    /// let err = v.insert_at_with("/b/c", 2, MissingParents::Fail);
+    /// assert_eq!(err, Err(PathError::Missing(1)));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// if a parent on the way can't hold the next step of `path`
+    pub fn insert_at_with<T: Into<Self>>(
+        &mut self,
+        path: &str,
+        value: T,
+        missing: MissingParents,
+    ) -> Result<Option<Self>, PathError> {
+        insert(self, &steps(path), 0, value.into(), missing)
+    }
+
+    /// Removes the value at `path` and returns it, `None` if there is no
+    /// value at `path`. `path` is written like for `insert_at_with`,
+    /// removing an element of an array moves the ones after it forward.
+    /// The empty path takes the whole value and leaves `null`.
+    ///
+    /// ```rust
+    /// use simd_json::{json, BorrowedValue};
+    /// let mut d = br#"{"a": {"b": 1, "c": 2}}"#.to_vec();
+    /// let mut v = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// assert_eq!(v.remove_at("/a/b"), Some(BorrowedValue::from(1)));
+    /// assert_eq!(v.remove_at("/a/b"), None);
+    /// assert_eq!(v, json!({"a": {"c": 2}}));
+    /// ```
+    pub fn remove_at(&mut self, path: &str) -> Option<Self> {
+        remove(self, &steps(path))
+    }
+}
+
+/// Splits `path` into its unescaped steps. The leading `/` is optional,
+/// `~1` and `~0` escape `/` and `~` like in a JSON pointer.
+pub(crate) fn steps(path: &str) -> Vec<Cow<'_, str>> {
+    let path = path.strip_prefix('/').unwrap_or(path);
+    if path.is_empty() {
+        return Vec::new();
+    }
+    path.split('/')
+        .map(|s| {
+            if s.contains('~') {
+                Cow::Owned(s.replace("~1", "/").replace("~0", "~"))
+            } else {
+                Cow::Borrowed(s)
+            }
+        })
+        .collect()
+}
+
+fn is_index(step: &str) -> bool {
+    step == "-" || (!step.is_empty() && step.bytes().all(|b| b.is_ascii_digit()))
+}
+
+fn insert<'v, V>(
+    target: &mut V,
+    steps: &[Cow<str>],
+    depth: usize,
+    value: V,
+    missing: MissingParents,
+) -> Result<Option<V>, PathError>
+where
+    V: Builder<'v> + Mutable,
+    V::Key: From<String> + Borrow<str> + Hash + Eq,
+{
+    let Some((step, rest)) = steps.split_first() else {
+        return Ok(Some(core::mem::replace(target, value)));
+    };
+    if target.is_null() {
+        *target = match missing {
+            MissingParents::Fail => return Err(PathError::Missing(depth)),
+            MissingParents::Infer if is_index(step) => V::array(),
+            MissingParents::Infer | MissingParents::Objects => V::object(),
+        };
+    }
+    if let Some(object) = target.as_object_mut() {
+        if rest.is_empty() {
+            return Ok(object.insert(V::Key::from(step.to_string()), value));
+        }
+        if object.get(step.as_ref()).is_none() {
+            if missing == MissingParents::Fail {
+                return Err(PathError::Missing(depth + 1));
+            }
+            let _ = object.insert(V::Key::from(step.to_string()), V::null());
+        }
+        match object.get_mut(step.as_ref()) {
+            Some(child) => insert(child, rest, depth + 1, value, missing),
+            None => Err(PathError::Missing(depth + 1)),
+        }
+    } else if let Some(array) = target.as_array_mut() {
+        let len = array.len();
+        let idx = if step == "-" {
+            len
+        } else {
+            step.parse().map_err(|_| PathError::BadIndex(depth))?
+        };
+        if idx > len {
+            return Err(PathError::BadIndex(depth));
+        }
+        if idx == len {
+            if rest.is_empty() {
+                array.push(value);
+                return Ok(None);
+            }
+            if missing == MissingParents::Fail {
+                return Err(PathError::Missing(depth + 1));
+            }
+            array.push(V::null());
+        }
+        match array.get_mut(idx) {
+            Some(child) if rest.is_empty() => Ok(Some(core::mem::replace(child, value))),
+            Some(child) => insert(child, rest, depth + 1, value, missing),
+            None => Err(PathError::BadIndex(depth)),
+        }
+    } else {
+        Err(PathError::NotAContainer(depth))
+    }
+}
+
+fn remove<V>(target: &mut V, steps: &[Cow<str>]) -> Option<V>
+where
+    V: Mutable + ValueTrait<Array = Vec<V>> + Default,
+    V::Key: Borrow<str> + Hash + Eq,
+{
+    let Some((last, parents)) = steps.split_last() else {
+        return Some(core::mem::take(target));
+    };
+    let mut parent = target;
+    for step in parents {
+        parent = if parent.is_array() {
+            parent.get_idx_mut(step.parse().ok()?)?
+        } else {
+            parent.get_mut(step.as_ref())?
+        };
+    }
+    if let Some(array) = parent.as_array_mut() {
+        match last.parse() {
+            Ok(idx) if idx < array.len() => Some(array.remove(idx)),
+            _ => None,
+        }
+    } else {
+        parent.as_object_mut()?.remove(last.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn vivify() {
+        let mut v = OwnedValue::default();
+        assert_eq!(v.insert_at("a/b/0/c", 1), Ok(None));
+        assert_eq!(v.insert_at("/a/b/-/c", 2), Ok(None));
+        assert_eq!(v.insert_at("/a/b/1/c", 3), Ok(Some(OwnedValue::from(2))));
+        assert_eq!(v.insert_at("/a/x~1y~0", ()), Ok(None));
+        assert_eq!(v.insert_at("/a/x~1y~0/z", 4), Ok(None));
+        assert_eq!(
+            v,
+            json!({"a": {"b": [{"c": 1}, {"c": 3}], "x/y~": {"z": 4}}})
+        );
+        let old = v.insert_at("", 5).unwrap();
+        assert_eq!(v, 5);
+        let mut b = BorrowedValue::default();
+        b.insert_at("a/b/0/c", 1).unwrap();
+        b.insert_at("/a/b/1/c", 3).unwrap();
+        b.insert_at("/a/x~1y~0/z", 4).unwrap();
+        assert_eq!(b, old.unwrap());
+    }
+
+    #[test]
+    fn errors() {
+        let mut v = json!({"a": [1], "b": "x"});
+        assert_eq!(v.insert_at("/b/c", 1), Err(PathError::NotAContainer(1)));
+        assert_eq!(v.insert_at("/a/x", 1), Err(PathError::BadIndex(1)));
+        assert_eq!(v.insert_at("/a/2", 1), Err(PathError::BadIndex(1)));
+        assert_eq!(v.insert_at("/a/0/b", 1), Err(PathError::NotAContainer(2)));
+        let fail = MissingParents::Fail;
+        assert_eq!(
+            v.insert_at_with("/c/d", 1, fail),
+            Err(PathError::Missing(1))
+        );
+        assert_eq!(
+            v.insert_at_with("/a/1/d", 1, fail),
+            Err(PathError::Missing(2))
+        );
+        assert_eq!(v.insert_at_with("/a/1", 2, fail), Ok(None));
+        assert_eq!(v.insert_at_with("/c", 3, fail), Ok(None));
+        assert_eq!(v, json!({"a": [1, 2], "b": "x", "c": 3}));
+    }
+
+    #[test]
+    fn remove() {
+        let mut v = json!({"a": [{"b": 1}, {"b": 2}], "c~/": 3, "d": "x"});
+        assert_eq!(v.remove_at("/a/0/b"), Some(OwnedValue::from(1)));
+        assert_eq!(v.remove_at("/a/0"), Some(json!({})));
+        assert_eq!(v.remove_at("/c~0~1"), Some(OwnedValue::from(3)));
+        for missing in &["/a/1", "/a/x", "/a/0/c", "/d/0", "/e/f", "a/-"] {
+            assert_eq!(v.remove_at(missing), None, "{}", missing);
+        }
+        assert_eq!(v, json!({"a": [{"b": 2}], "d": "x"}));
+        let mut d = br#"{"a": [{"b": 2}], "d": "x"}"#.to_vec();
+        let mut b = crate::to_borrowed_value(&mut d).unwrap();
+        assert_eq!(b.remove_at("a/0/b"), Some(BorrowedValue::from(2)));
+        assert_eq!(
+            b.remove_at(""),
+            Some(BorrowedValue::from(json!({"a": [{}], "d": "x"})))
+        );
+        assert!(b.is_null());
+    }
+}
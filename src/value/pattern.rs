@@ -0,0 +1,315 @@
+use super::approx::{approx_eq, leaf_eq, Tolerance};
+use crate::prelude::*;
+use crate::value::Dom;
+use crate::{BorrowedValue, OwnedValue};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+
+/// The values bound by the markers of a pattern that matched, created by
+/// `captures`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Captures<'v, V> {
+    bindings: Vec<(&'v str, &'v V)>,
+}
+
+impl<'v, V> Captures<'v, V> {
+    /// The value bound to `name`
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&'v V> {
+        self.bindings
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, v)| *v)
+    }
+
+    /// The names and values bound, in no particular order
+    pub fn iter(&self) -> core::slice::Iter<'_, (&'v str, &'v V)> {
+        self.bindings.iter()
+    }
+
+    /// The number of names bound
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.bindings.len()
+    }
+
+    /// Whether no names were bound
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.bindings.is_empty()
+    }
+}
+
+impl<'a, 'v, V> IntoIterator for &'a Captures<'v, V> {
+    type Item = &'a (&'v str, &'v V);
+    type IntoIter = core::slice::Iter<'a, (&'v str, &'v V)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// What a string in a pattern matches
+enum Marker<'p> {
+    /// A string starting with `$` that isn't a marker, or one escaped with
+    /// `$$`, matched as is
+    Literal(&'p str),
+    /// A marker, with the name it binds the value to
+    Kind(Kind, Option<&'p str>),
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Any,
+    String,
+    Number,
+    Bool,
+    Null,
+    Array,
+    Object,
+}
+
+impl<'p> Marker<'p> {
+    fn parse(s: &'p str) -> Option<Self> {
+        let rest = s.strip_prefix('$')?;
+        if rest.starts_with('$') {
+            return Some(Marker::Literal(rest));
+        }
+        let (kind, name) = match rest.find(':') {
+            Some(idx) if idx + 1 < rest.len() => (&rest[..idx], Some(&rest[idx + 1..])),
+            Some(_) => return None,
+            None => (rest, None),
+        };
+        let kind = match kind {
+            "any" => Kind::Any,
+            "string" => Kind::String,
+            "number" => Kind::Number,
+            "bool" => Kind::Bool,
+            "null" => Kind::Null,
+            "array" => Kind::Array,
+            "object" => Kind::Object,
+            _ => return None,
+        };
+        Some(Marker::Kind(kind, name))
+    }
+}
+
+impl Kind {
+    fn matches<V: Dom>(self, value: &V) -> bool {
+        #[cfg(feature = "bytes")]
+        {
+            if value.bytes().is_some() {
+                return matches!(self, Kind::Any);
+            }
+        }
+        match self {
+            Kind::Any => true,
+            Kind::String => value.is_str(),
+            Kind::Number => value.is_number(),
+            Kind::Bool => value.is_bool(),
+            Kind::Null => value.is_null(),
+            Kind::Array => value.is_array(),
+            Kind::Object => value.is_object(),
+        }
+    }
+}
+
+impl OwnedValue {
+    /// Whether the value has the shape of `pattern`, see
+    /// `BorrowedValue::captures`.
+    ///
+    /// ```rust
+    /// use simd_json::json;
+    /// let v = json!({"type": "ping", "id": 7});
+    /// assert!(v.matches(&json!({"type": "ping", "id": "$number"})));
+    /// assert!(!v.matches(&json!({"type": "pong"})));
+    /// ```
+    #[must_use]
+    pub fn matches<P>(&self, pattern: &P) -> bool
+    where
+        P: Dom,
+        P::Key: Borrow<str>,
+    {
+        captures(self, pattern).is_some()
+    }
+
+    /// Matches the value against `pattern` and returns the values its
+    /// markers bound, see `BorrowedValue::captures`.
+    #[must_use]
+    pub fn captures<'v, P>(&'v self, pattern: &'v P) -> Option<Captures<'v, Self>>
+    where
+        P: Dom,
+        P::Key: Borrow<str>,
+    {
+        captures(self, pattern)
+    }
+}
+
+impl BorrowedValue<'_> {
+    /// Whether the value has the shape of `pattern`, see `captures`.
+    #[must_use]
+    pub fn matches<P>(&self, pattern: &P) -> bool
+    where
+        P: Dom,
+        P::Key: Borrow<str>,
+    {
+        captures(self, pattern).is_some()
+    }
+
+    /// Matches the value against `pattern`, another value, and returns the
+    /// values its markers bound, or `None` if it doesn't match.
+    ///
+    /// Objects in the pattern match objects with at least their keys,
+    /// where the values match. A key ending in `?` is optional, it only
+    /// has to match if the value has it, `??` stands for a literal `?` at
+    /// the end of a key. Arrays match arrays of the same length, element
+    /// by element, and other values match equal values, with `1` and
+    /// `1.0` being equal.
+    ///
+    /// Strings starting with `$` are markers: `$any` matches anything,
+    /// `$string`, `$number`, `$bool`, `$null`, `$array` and `$object`
+    /// values of that type. A marker followed by `:name` binds what it
+    /// matched to `name`, a name bound more than once has to bind equal
+    /// values. `$$` stands for a literal `$` at the start of a string, and
+    /// other strings starting with `$` are matched as they are.
+    ///
+    /// ```rust
+    /// use simd_json::json;
+    /// let mut d = br#"{"op": "move", "to": {"x": 3, "y": 4}, "by": "bob"}"#.to_vec();
+    /// let v = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// let pattern = json!({
+    ///     "op": "move",
+    ///     "to": {"x": "$number:x", "y": "$number:y", "z?": "$number:z"},
+    ///     "by": "$string"
+    /// });
+    /// let captures = v.captures(&pattern).unwrap();
+    /// assert_eq!(captures.get("x"), Some(&3.into()));
+    /// assert_eq!(captures.get("y"), Some(&4.into()));
+    /// assert_eq!(captures.get("z"), None);
+    /// assert!(!v.matches(&json!({"op": "move", "to": "$array"})));
+    /// ```
+    #[must_use]
+    pub fn captures<'v, P>(&'v self, pattern: &'v P) -> Option<Captures<'v, Self>>
+    where
+        P: Dom,
+        P::Key: Borrow<str>,
+    {
+        captures(self, pattern)
+    }
+}
+
+fn captures<'v, V, P>(value: &'v V, pattern: &'v P) -> Option<Captures<'v, V>>
+where
+    V: Dom,
+    V::Key: Borrow<str>,
+    P: Dom,
+    P::Key: Borrow<str>,
+{
+    let mut bindings: Vec<(&'v str, &'v V)> = Vec::new();
+    // pairs still to match, kept on a stack so deep nesting can't overflow
+    // the call stack
+    let mut stack: Vec<(&'v V, &'v P)> = alloc::vec![(value, pattern)];
+    while let Some((value, pattern)) = stack.pop() {
+        if let Some(object) = pattern.as_object() {
+            let fields = value.as_object()?;
+            for (key, pattern) in object.iter() {
+                let key: &str = key.borrow();
+                let (key, optional) = match key.strip_suffix('?') {
+                    Some(k) if k.ends_with('?') => (k, false),
+                    Some(k) => (k, true),
+                    None => (key, false),
+                };
+                match fields.get(key) {
+                    Some(value) => stack.push((value, pattern)),
+                    None if optional => (),
+                    None => return None,
+                }
+            }
+        } else if let Some(array) = pattern.as_array() {
+            let elements = value.as_array()?;
+            if elements.len() != array.len() {
+                return None;
+            }
+            stack.extend(elements.iter().zip(array.iter()));
+        } else {
+            match pattern.as_str().and_then(Marker::parse) {
+                Some(Marker::Literal(s)) => {
+                    if value.as_str() != Some(s) {
+                        return None;
+                    }
+                }
+                Some(Marker::Kind(kind, name)) => {
+                    if !kind.matches(value) {
+                        return None;
+                    }
+                    if let Some(name) = name {
+                        match bindings.iter().find(|(n, _)| *n == name) {
+                            Some((_, bound)) => {
+                                if !approx_eq(*bound, value, Tolerance::default()) {
+                                    return None;
+                                }
+                            }
+                            None => bindings.push((name, value)),
+                        }
+                    }
+                }
+                None => {
+                    if !leaf_eq(value, pattern, Tolerance::default()) {
+                        return None;
+                    }
+                }
+            }
+        }
+    }
+    Some(Captures { bindings })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn matching() {
+        let v = json!({"a": [1, "x", null, true, {}], "b": "$5", "c?": 2.0});
+        assert!(v.matches(&json!({})));
+        assert!(v.matches(&json!({"a": "$array", "b": "$string"})));
+        assert!(v.matches(&json!({
+            "a": ["$number", "$string", "$null", "$bool", "$object"],
+            "b": "$5",
+            "c??": 2
+        })));
+        assert!(v.matches(&json!({"b": "$$5", "d?": "$any"})));
+        assert!(v.matches(&json!({"a": "$any", "x?": 1})));
+        assert!(!v.matches(&json!({"a": "$object"})));
+        assert!(!v.matches(&json!({"a": [1, "x", null, true]})));
+        assert!(!v.matches(&json!({"a": ["$string", "$any", "$any", "$any", "$any"]})));
+        assert!(!v.matches(&json!({"c": 2})));
+        assert!(!v.matches(&json!({"c??": 3})));
+        assert!(!v.matches(&json!({"b": "$$$5"})));
+        assert!(!v.matches(&json!({"z": "$any"})));
+        assert!(!json!([]).matches(&json!({})));
+        assert!(!json!({}).matches(&json!([])));
+        assert!(json!("$any").matches(&json!("$any")));
+        assert!(!json!(1).matches(&json!("$any:")));
+        let b = BorrowedValue::from(v.clone());
+        assert!(b.matches(&json!({"b": "$string"})));
+        assert!(!b.matches(&json!({"b": "$number"})));
+    }
+
+    #[test]
+    fn captures() {
+        let v = json!({"from": "a", "to": "b", "path": ["a", "c", "b"]});
+        let pattern = json!({"from": "$string:start", "path": ["$any:start", "$any:via", "$any"]});
+        let captures = v.captures(&pattern).expect("pattern matches");
+        assert_eq!(captures.len(), 2);
+        assert_eq!(captures.get("start"), Some(&json!("a")));
+        assert_eq!(captures.get("via"), Some(&json!("c")));
+        let mut names: Vec<&str> = captures.iter().map(|(n, _)| *n).collect();
+        names.sort_unstable();
+        assert_eq!(names, ["start", "via"]);
+        // a name has to bind the same value everywhere
+        assert!(!v.matches(&json!({"from": "$any:n", "to": "$any:n"})));
+        assert!(json!([1, 1.0]).matches(&json!(["$number:n", "$number:n"])));
+        assert!(v.captures(&json!({})).expect("pattern matches").is_empty());
+    }
+}
@@ -0,0 +1,520 @@
+use super::build::{build, Build};
+use super::path::steps;
+use crate::value::tape::Node;
+use crate::value::{Array, Value};
+use crate::{BorrowedValue, Deserializer, OwnedValue, Result, StaticNode};
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::hash::Hash;
+use core::marker::PhantomData;
+use halfbrown::HashMap;
+
+/// One step of a projection path
+#[derive(Debug, PartialEq)]
+enum Segment<'p> {
+    /// The value of a key, or the element at an index of an array
+    Key(Cow<'p, str>),
+    /// Every element of an array
+    Wildcard,
+}
+
+/// Splits a path like `/items/*/price` into its segments. The leading
+/// `/` is optional, `~1` and `~0` escape `/` and `~` like in a JSON
+/// pointer.
+fn segments(path: &str) -> Vec<Segment<'_>> {
+    steps(path)
+        .into_iter()
+        .map(|s| match s {
+            Cow::Borrowed("*") => Segment::Wildcard,
+            s => Segment::Key(s),
+        })
+        .collect()
+}
+
+/// Extracts one field from every element of an array in a single pass,
+/// converting it with `f`. `path` is a list of keys separated by `/`
+/// where a `*` stands for every element of an array and a number indexes
+/// into an array, so `/items/*/price` yields the price of every item.
+///
+/// There is one entry per element the wildcards match, in order, it is
+/// `None` where the element lacks the field or `f` rejects it. A value a
+/// wildcard is applied to that isn't an array, or that is missing, has no
+/// elements and adds no entries.
+///
+/// ```rust
+/// use simd_json::prelude::*;
+/// let mut d = br#"{"items": [{"name": "a"}, {"name": 1}, {}]}"#.to_vec();
+/// let v = simd_json::to_borrowed_value(&mut d).unwrap();
+/// let names = simd_json::project(&v, "/items/*/name", |v| v.as_str());
+/// assert_eq!(names, vec![Some("a"), None, None]);
+/// ```
+pub fn project<'v, V, T>(value: &'v V, path: &str, f: impl Fn(&'v V) -> Option<T>) -> Vec<Option<T>>
+where
+    V: Value,
+    V::Key: Borrow<str>,
+{
+    let mut res = Vec::new();
+    walk(Some(value), &segments(path), &f, &mut res);
+    res
+}
+
+/// Extracts one numeric field from every element of an array in a single
+/// pass, see `project` for the syntax of `path`. Integers are converted
+/// to `f64`, anything that isn't a number is `None`.
+///
+/// ```rust
+/// let mut d = br#"{"items": [{"price": 1.5}, {"price": 2}, {"price": "3"}]}"#.to_vec();
+/// let v = simd_json::to_owned_value(&mut d).unwrap();
+/// let prices = simd_json::project_column(&v, "/items/*/price");
+/// assert_eq!(prices, vec![Some(1.5), Some(2.0), None]);
+/// ```
+#[must_use]
+pub fn project_column<V>(value: &V, path: &str) -> Vec<Option<f64>>
+where
+    V: Value,
+    V::Key: Borrow<str>,
+{
+    project(value, path, V::cast_f64)
+}
+
+fn walk<'v, V, T>(
+    value: Option<&'v V>,
+    segments: &[Segment],
+    f: &impl Fn(&'v V) -> Option<T>,
+    res: &mut Vec<Option<T>>,
+) where
+    V: Value,
+    V::Key: Borrow<str>,
+{
+    match (segments.split_first(), value) {
+        (None, value) => res.push(value.and_then(f)),
+        (Some((Segment::Wildcard, rest)), value) => {
+            if let Some(array) = value.and_then(V::as_array) {
+                for value in array.iter() {
+                    walk(Some(value), rest, f, res);
+                }
+            }
+        }
+        (Some((Segment::Key(key), rest)), value) => {
+            let value = value.and_then(|v| {
+                if v.is_array() {
+                    key.parse().ok().and_then(|i| v.get_idx(i))
+                } else {
+                    v.get(&**key)
+                }
+            });
+            walk(value, rest, f, res);
+        }
+    }
+}
+
+/// Like `project` but reads the field straight from a tape as returned by
+/// `to_tape`, without building a DOM first. For documents with duplicate
+/// keys the first value of a key is used, like in the DOMs.
+///
+/// ```rust
+/// use simd_json::{Node, StaticNode};
+/// let mut d = br#"[{"ok": true}, {"ok": 1}, {"ok": false}]"#.to_vec();
+/// let tape = simd_json::to_tape(&mut d).unwrap();
+/// let ok = simd_json::project_tape(&tape, "/*/ok", |n| match n {
+///     Node::Static(StaticNode::Bool(b)) => Some(b),
+///     _ => None,
+/// });
+/// assert_eq!(ok, vec![Some(true), None, Some(false)]);
+/// ```
+pub fn project_tape<'input, T>(
+    tape: &[Node<'input>],
+    path: &str,
+    f: impl Fn(Node<'input>) -> Option<T>,
+) -> Vec<Option<T>> {
+    let mut res = Vec::new();
+    // the first node of a tape is a placeholder, the document starts after it
+    let root = if tape.len() > 1 { Some(1) } else { None };
+    walk_tape(tape, root, &segments(path), &f, &mut res);
+    res
+}
+
+/// Like `project_column` but reads the field straight from a tape as
+/// returned by `to_tape`, without building a DOM first.
+///
+/// ```rust
+/// let mut d = br#"[[1, 2], [3.5], [], "4", [5]]"#.to_vec();
+/// let tape = simd_json::to_tape(&mut d).unwrap();
+/// let first = simd_json::project_tape_column(&tape, "/*/0");
+/// assert_eq!(first, vec![Some(1.0), Some(3.5), None, None, Some(5.0)]);
+/// ```
+#[must_use]
+pub fn project_tape_column(tape: &[Node], path: &str) -> Vec<Option<f64>> {
+    project_tape(tape, path, |n| match n {
+        Node::Static(s) => s.cast_f64(),
+        _ => None,
+    })
+}
+
+/// The index of the node after the value starting at `idx`
+pub(super) fn skip(tape: &[Node], idx: usize) -> usize {
+    match tape[idx] {
+        Node::Array(_, end) | Node::Object(_, end) => end,
+        _ => idx + 1,
+    }
+}
+
+fn walk_tape<'input, T>(
+    tape: &[Node<'input>],
+    idx: Option<usize>,
+    segments: &[Segment],
+    f: &impl Fn(Node<'input>) -> Option<T>,
+    res: &mut Vec<Option<T>>,
+) {
+    match (segments.split_first(), idx) {
+        (None, idx) => res.push(idx.and_then(|idx| f(tape[idx]))),
+        (Some((Segment::Wildcard, rest)), idx) => {
+            if let Some(idx) = idx {
+                if let Node::Array(len, _) = tape[idx] {
+                    let mut element = idx + 1;
+                    for _ in 0..len {
+                        walk_tape(tape, Some(element), rest, f, res);
+                        element = skip(tape, element);
+                    }
+                }
+            }
+        }
+        (Some((Segment::Key(key), rest)), idx) => {
+            let found = idx.and_then(|idx| match tape[idx] {
+                Node::Object(len, _) => {
+                    let mut entry = idx + 1;
+                    for _ in 0..len {
+                        match tape[entry] {
+                            Node::String(k) if k == key => return Some(entry + 1),
+                            _ => entry = skip(tape, entry + 1),
+                        }
+                    }
+                    None
+                }
+                Node::Array(len, _) => {
+                    let i: usize = key.parse().ok().filter(|i| *i < len)?;
+                    let mut element = idx + 1;
+                    for _ in 0..i {
+                        element = skip(tape, element);
+                    }
+                    Some(element)
+                }
+                _ => None,
+            });
+            walk_tape(tape, found, rest, f, res);
+        }
+    }
+}
+
+/// Parses `s` like `to_owned_value` but only builds the parts of the
+/// value `paths` select, for wide documents where only a few branches
+/// matter. See `to_borrowed_value_projected`.
+///
+/// ```rust
+/// use simd_json::{json, OwnedValue};
+/// let mut d = br#"{"id": 7, "user": {"name": "a", "bio": "..."}, "log": [1, 2]}"#.to_vec();
+/// let v = simd_json::to_owned_value_projected(&mut d, &["/id", "/user/name"]).unwrap();
+/// assert_eq!(v, json!({"id": 7, "user": {"name": "a"}}));
+/// ```
+///
+/// # Errors
+///
+/// Will return `Err` if `s` is invalid JSON.
+pub fn to_owned_value_projected(s: &mut [u8], paths: &[&str]) -> Result<OwnedValue> {
+    let de = Deserializer::from_slice(s)?;
+    Ok(projected::<OwnedValue, _>(&de.tape, paths))
+}
+
+/// Parses `s` like `to_borrowed_value` but only builds the parts of the
+/// value `paths` select. The whole document is still parsed and
+/// validated, everything else is skipped on the tape without building
+/// values for it.
+///
+/// `paths` are written like for `project`, a `*` selects every element
+/// of an array. Objects keep only the keys on the way to a selected
+/// value and arrays the elements, with `null` in place of the elements
+/// before a selected one that aren't selected so indexes stay the same.
+/// Objects and arrays nothing is selected in are left out, and if
+/// nothing is selected at all the value is an empty object or array, or
+/// `null` for other documents. If an object has a key more than once the
+/// first one is used.
+///
+/// ```rust
+/// use simd_json::json;
+/// let mut d = br#"{"items": [{"id": 1, "price": 2.5}, {"id": 2}], "meta": {}}"#.to_vec();
+/// let v = simd_json::to_borrowed_value_projected(&mut d, &["/items/*/price"]).unwrap();
+/// assert_eq!(v, json!({"items": [{"price": 2.5}]}));
+/// let mut d = br#"[0, 1, [2, 3]]"#.to_vec();
+/// let v = simd_json::to_borrowed_value_projected(&mut d, &["/2/1", "/9"]).unwrap();
+/// assert_eq!(v, json!([null, null, [null, 3]]));
+/// ```
+///
+/// # Errors
+///
+/// Will return `Err` if `s` is invalid JSON.
+pub fn to_borrowed_value_projected<'v>(
+    s: &'v mut [u8],
+    paths: &[&str],
+) -> Result<BorrowedValue<'v>> {
+    let de = Deserializer::from_slice(s)?;
+    Ok(projected::<BorrowedValue, _>(&de.tape, paths))
+}
+
+fn projected<'de, V, K>(tape: &[Node<'de>], paths: &[&str]) -> V
+where
+    V: From<StaticNode> + From<&'de str> + From<Vec<V>> + From<HashMap<K, V>>,
+    K: Hash + Eq + Default + From<&'de str>,
+{
+    let segments: Vec<Vec<Segment>> = paths.iter().map(|p| segments(p)).collect();
+    let paths: Vec<&[Segment]> = segments.iter().map(Vec::as_slice).collect();
+    // the first node of a tape is a placeholder, the document starts after it
+    select(tape, 1, &paths).unwrap_or_else(|| match tape[1] {
+        Node::Object(..) => V::from(HashMap::new()),
+        Node::Array(..) => V::from(Vec::new()),
+        _ => V::from(StaticNode::Null),
+    })
+}
+
+/// Builds the parts of the value starting at `idx` that `paths` select,
+/// `None` if they select nothing in it
+fn select<'de, V, K>(tape: &[Node<'de>], idx: usize, paths: &[&[Segment]]) -> Option<V>
+where
+    V: From<StaticNode> + From<&'de str> + From<Vec<V>> + From<HashMap<K, V>>,
+    K: Hash + Eq + Default + From<&'de str>,
+{
+    if paths.iter().any(|p| p.is_empty()) {
+        return Some(build(&mut TapeBuilder {
+            tape,
+            idx,
+            dom: PhantomData,
+        }));
+    }
+    match tape[idx] {
+        Node::Object(len, _) => {
+            let mut res = HashMap::new();
+            // keys that were selected, later duplicates are skipped
+            let mut seen: Vec<&str> = Vec::new();
+            let mut entry = idx + 1;
+            for _ in 0..len {
+                let key = match tape[entry] {
+                    Node::String(key) => key,
+                    _ => unreachable!(),
+                };
+                let rest: Vec<&[Segment]> = paths
+                    .iter()
+                    .filter_map(|p| match p.split_first() {
+                        Some((Segment::Key(k), rest)) if k == key => Some(rest),
+                        _ => None,
+                    })
+                    .collect();
+                if !rest.is_empty() && !seen.contains(&key) {
+                    seen.push(key);
+                    if let Some(value) = select(tape, entry + 1, &rest) {
+                        res.insert_nocheck(K::from(key), value);
+                    }
+                }
+                entry = skip(tape, entry + 1);
+            }
+            if res.is_empty() {
+                None
+            } else {
+                Some(V::from(res))
+            }
+        }
+        Node::Array(len, _) => {
+            let mut res = Vec::new();
+            let mut element = idx + 1;
+            for i in 0..len {
+                let rest: Vec<&[Segment]> = paths
+                    .iter()
+                    .filter_map(|p| match p.split_first() {
+                        Some((Segment::Wildcard, rest)) => Some(rest),
+                        Some((Segment::Key(k), rest)) if k.parse() == Ok(i) => Some(rest),
+                        _ => None,
+                    })
+                    .collect();
+                if !rest.is_empty() {
+                    if let Some(value) = select(tape, element, &rest) {
+                        res.resize_with(i, || V::from(StaticNode::Null));
+                        res.push(value);
+                    }
+                }
+                element = skip(tape, element);
+            }
+            if res.is_empty() {
+                None
+            } else {
+                Some(V::from(res))
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Builds whole values from the nodes of a tape for `select`
+struct TapeBuilder<'t, 'de, V, K> {
+    tape: &'t [Node<'de>],
+    idx: usize,
+    dom: PhantomData<(V, K)>,
+}
+
+impl<'de, V, K> Build<'de> for TapeBuilder<'_, 'de, V, K>
+where
+    V: From<StaticNode> + From<&'de str> + From<Vec<V>> + From<HashMap<K, V>>,
+    K: Hash + Eq + Default + From<&'de str>,
+{
+    type Value = V;
+    type Key = K;
+    type Array = Vec<V>;
+    type Object = HashMap<K, V>;
+
+    fn next_node(&mut self) -> Node<'de> {
+        self.idx += 1;
+        self.tape[self.idx - 1]
+    }
+
+    fn static_node(&mut self, s: StaticNode) -> V {
+        V::from(s)
+    }
+
+    fn string(&mut self, s: &'de str) -> V {
+        V::from(s)
+    }
+
+    fn key(&mut self, s: &'de str) -> K {
+        K::from(s)
+    }
+
+    fn array(&mut self, len: usize) -> Vec<V> {
+        Vec::with_capacity(len)
+    }
+
+    fn push(&mut self, array: &mut Vec<V>, value: V) {
+        array.push(value);
+    }
+
+    fn finish_array(&mut self, array: Vec<V>) -> V {
+        V::from(array)
+    }
+
+    fn object(&mut self, len: usize) -> HashMap<K, V> {
+        HashMap::with_capacity(len)
+    }
+
+    fn insert(&mut self, object: &mut HashMap<K, V>, key: K, value: V) {
+        object.insert_nocheck(key, value);
+    }
+
+    fn finish_object(&mut self, object: HashMap<K, V>) -> V {
+        V::from(object)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{to_borrowed_value, to_owned_value, to_tape};
+    use alloc::string::String;
+
+    const DOC: &str = r#"{
+        "items": [
+            {"price": 1.5, "tags": [{"w": 1}, {"w": 2}]},
+            {"price": 2, "tags": []},
+            {"name": "no price", "tags": {"w": 3}},
+            {"price": null, "price": -3, "tags": [{"w": "x"}, {"v": 4}]},
+            7
+        ],
+        "a/b": {"~": [10, 20]}
+    }"#;
+
+    fn all(path: &str) -> Vec<Option<f64>> {
+        let owned = project_column(&to_owned_value(&mut DOC.as_bytes().to_vec()).unwrap(), path);
+        let mut d = DOC.as_bytes().to_vec();
+        let borrowed = project_column(&to_borrowed_value(&mut d).unwrap(), path);
+        let mut d = DOC.as_bytes().to_vec();
+        let tape = project_tape_column(&to_tape(&mut d).unwrap(), path);
+        assert_eq!(owned, borrowed, "{}", path);
+        assert_eq!(owned, tape, "{}", path);
+        owned
+    }
+
+    #[test]
+    fn paths() {
+        assert_eq!(
+            all("/items/*/price"),
+            vec![Some(1.5), Some(2.0), None, None, None]
+        );
+        assert_eq!(
+            all("items/*/tags/*/w"),
+            vec![Some(1.0), Some(2.0), None, None]
+        );
+        assert_eq!(all("/items/1/price"), vec![Some(2.0)]);
+        assert_eq!(all("/items/9/price"), vec![None]);
+        assert_eq!(all("/items/4"), vec![Some(7.0)]);
+        assert_eq!(all("/a~1b/~0/1"), vec![Some(20.0)]);
+        assert_eq!(all("/a~1b/~0/*"), vec![Some(10.0), Some(20.0)]);
+        assert_eq!(all(""), vec![None]);
+        assert_eq!(all("/missing/*/price"), vec![]);
+        assert_eq!(all("/a~1b/*"), vec![]);
+    }
+
+    #[test]
+    fn empty_tape() {
+        assert_eq!(project_tape_column(&[], "/*"), vec![]);
+        assert_eq!(project_tape_column(&[], ""), vec![None]);
+    }
+
+    #[test]
+    fn path_segments() {
+        assert_eq!(segments(""), vec![]);
+        assert_eq!(segments("/"), vec![]);
+        assert_eq!(
+            segments("/a/*/~01~10"),
+            vec![
+                Segment::Key(Cow::Borrowed("a")),
+                Segment::Wildcard,
+                Segment::Key(Cow::Owned(String::from("~1/0"))),
+            ]
+        );
+    }
+
+    fn projected_both(doc: &str, paths: &[&str]) -> OwnedValue {
+        let mut d = doc.as_bytes().to_vec();
+        let owned = to_owned_value_projected(&mut d, paths).unwrap();
+        let mut d = doc.as_bytes().to_vec();
+        let borrowed = to_borrowed_value_projected(&mut d, paths).unwrap();
+        assert_eq!(borrowed, owned, "{:?}", paths);
+        owned
+    }
+
+    #[test]
+    fn projected_parse() {
+        // objects with duplicate keys don't compare equal to themselves
+        let doc = r#"{"a": [1, {"b": null}], "c": "d"}"#;
+        let whole = to_owned_value(&mut doc.as_bytes().to_vec()).unwrap();
+        assert_eq!(projected_both(doc, &[""]), whole);
+        assert_eq!(
+            projected_both(doc, &["/a", "/a/1"]),
+            json!({"a": [1, {"b": null}]})
+        );
+        assert_eq!(
+            projected_both(DOC, &["/items/*/price"]),
+            json!({"items": [{"price": 1.5}, {"price": 2}, null, {"price": null}]})
+        );
+        assert_eq!(
+            projected_both(DOC, &["/a~1b/~0/1", "/items/1", "/items/1/price"]),
+            json!({"items": [null, {"price": 2, "tags": []}], "a/b": {"~": [null, 20]}})
+        );
+        assert_eq!(
+            projected_both(DOC, &["/items/3/tags/*/v", "/items/0/tags/1"]),
+            json!({"items": [{"tags": [null, {"w": 2}]}, null, null, {"tags": [null, {"v": 4}]}]})
+        );
+        assert_eq!(projected_both(DOC, &[]), json!({}));
+        assert_eq!(projected_both(DOC, &["/missing", "/items/9"]), json!({}));
+        assert_eq!(projected_both("[1, {}]", &["/1/a", "/0/b"]), json!([]));
+        assert_eq!(projected_both("3", &["/a"]), json!(null));
+        assert_eq!(projected_both(" \"s\" ", &[""]), json!("s"));
+        assert!(to_owned_value_projected(&mut b"[1, ".to_vec(), &["/0"]).is_err());
+    }
+}
@@ -0,0 +1,170 @@
+use crate::prelude::*;
+use crate::{BorrowedValue, OwnedValue};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::hash::Hash;
+use halfbrown::HashMap;
+
+/// What `prune` removes, by default `null`s and empty objects and arrays
+/// but not empty strings.
+///
+/// ```rust
+/// use simd_json::{json, PruneOptions};
+/// let mut v = json!({"a": "", "b": [null, {}], "c": {"d": []}});
+/// v.prune(PruneOptions::default().empty_strings(true));
+/// assert_eq!(v, json!({}));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct PruneOptions {
+    pub(crate) nulls: bool,
+    pub(crate) empty_objects: bool,
+    pub(crate) empty_arrays: bool,
+    pub(crate) empty_strings: bool,
+}
+
+impl Default for PruneOptions {
+    fn default() -> Self {
+        Self {
+            nulls: true,
+            empty_objects: true,
+            empty_arrays: true,
+            empty_strings: false,
+        }
+    }
+}
+
+impl PruneOptions {
+    /// Sets if `null`s are removed, `true` by default
+    #[must_use]
+    pub fn nulls(mut self, nulls: bool) -> Self {
+        self.nulls = nulls;
+        self
+    }
+
+    /// Sets if empty objects are removed, `true` by default
+    #[must_use]
+    pub fn empty_objects(mut self, empty_objects: bool) -> Self {
+        self.empty_objects = empty_objects;
+        self
+    }
+
+    /// Sets if empty arrays are removed, `true` by default
+    #[must_use]
+    pub fn empty_arrays(mut self, empty_arrays: bool) -> Self {
+        self.empty_arrays = empty_arrays;
+        self
+    }
+
+    /// Sets if empty strings are removed, `false` by default
+    #[must_use]
+    pub fn empty_strings(mut self, empty_strings: bool) -> Self {
+        self.empty_strings = empty_strings;
+        self
+    }
+
+    fn removes<V: ValueTrait>(self, value: &V) -> bool {
+        match value.value_type() {
+            ValueType::Null => self.nulls,
+            ValueType::Object => {
+                self.empty_objects && matches!(value.as_object(), Some(o) if o.is_empty())
+            }
+            ValueType::Array => {
+                self.empty_arrays && matches!(value.as_array(), Some(a) if a.is_empty())
+            }
+            ValueType::String => self.empty_strings && value.as_str() == Some(""),
+            _ => false,
+        }
+    }
+}
+
+impl OwnedValue {
+    /// Removes the fields and elements `options` says, see
+    /// `BorrowedValue::prune`.
+    pub fn prune(&mut self, options: PruneOptions) {
+        prune(self, options);
+    }
+}
+
+impl BorrowedValue<'_> {
+    /// Removes the fields of objects and the elements of arrays that
+    /// `options` says, in place. Containers are pruned before they are
+    /// looked at, so an object that only held `null`s is removed as well.
+    /// The value itself is kept even if it ends up empty.
+    ///
+    /// ```rust
+    /// use simd_json::{json, PruneOptions};
+    /// let mut d = br#"{"id": 1, "tags": [], "meta": {"a": null}, "list": [1, null]}"#.to_vec();
+    /// let mut v = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// v.prune(PruneOptions::default());
+    /// assert_eq!(v, json!({"id": 1, "list": [1]}));
+    /// ```
+    pub fn prune(&mut self, options: PruneOptions) {
+        prune(self, options);
+    }
+}
+
+fn prune<K, V>(value: &mut V, options: PruneOptions)
+where
+    V: Mutable + ValueTrait<Key = K, Array = Vec<V>, Object = HashMap<K, V>>,
+    K: Borrow<str> + Hash + Eq,
+{
+    if let Some(object) = value.as_object_mut() {
+        object.retain(|_, child| {
+            prune(child, options);
+            !options.removes(child)
+        });
+    } else if let Some(array) = value.as_array_mut() {
+        array.retain_mut(|child| {
+            prune(child, options);
+            !options.removes(child)
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn options() {
+        let v = json!({
+            "a": null,
+            "b": "",
+            "c": [null, "", [], {}, 0, false],
+            "d": {"e": {"f": [null]}},
+            "g": {}
+        });
+        let pruned = |options| {
+            let mut owned = v.clone();
+            owned.prune(options);
+            let mut borrowed = BorrowedValue::from(v.clone());
+            borrowed.prune(options);
+            assert_eq!(borrowed, owned);
+            owned
+        };
+        assert_eq!(
+            pruned(PruneOptions::default()),
+            json!({"b": "", "c": ["", 0, false]})
+        );
+        assert_eq!(
+            pruned(PruneOptions::default().empty_strings(true)),
+            json!({"c": [0, false]})
+        );
+        assert_eq!(
+            pruned(PruneOptions::default().nulls(false)),
+            json!({"a": null, "b": "", "c": [null, "", 0, false], "d": {"e": {"f": [null]}}})
+        );
+        assert_eq!(
+            pruned(PruneOptions::default().empty_objects(false)),
+            json!({"b": "", "c": ["", {}, 0, false], "d": {"e": {}}, "g": {}})
+        );
+        assert_eq!(
+            pruned(PruneOptions::default().empty_arrays(false)),
+            json!({"b": "", "c": ["", [], 0, false], "d": {"e": {"f": []}}})
+        );
+        let mut top = json!([null]);
+        top.prune(PruneOptions::default());
+        assert_eq!(top, json!([]));
+    }
+}
@@ -0,0 +1,288 @@
+use super::path::steps;
+use crate::prelude::*;
+use crate::{BorrowedValue, OwnedValue};
+use alloc::borrow::Cow;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::hash::Hash;
+use halfbrown::HashMap;
+
+/// What `redact` puts in place of the fields it matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Replacement<'r> {
+    /// Replaces the value with `null`
+    Null,
+    /// Replaces the value with a string
+    String(&'r str),
+    /// Removes the field, or the element from its array
+    Remove,
+}
+
+impl OwnedValue {
+    /// Masks the fields matching any of `patterns`, see
+    /// `BorrowedValue::redact`.
+    ///
+    /// ```rust
+    /// use simd_json::{json, Replacement};
+    /// let mut event = json!({
+    ///     "password": "hunter2",
+    ///     "user": {"name": "a", "ssn": "123"},
+    ///     "auth": {"token": "abc"}
+    /// });
+    /// event.redact(&["password", "/user/ssn", "*.token"], Replacement::String("***"));
+    /// assert_eq!(
+    ///     event,
+    ///     json!({"password": "***", "user": {"name": "a", "ssn": "***"}, "auth": {"token": "***"}})
+    /// );
+    /// ```
+    pub fn redact(&mut self, patterns: &[&str], replacement: Replacement) {
+        redact(self, patterns, replacement);
+    }
+}
+
+impl BorrowedValue<'_> {
+    /// Masks the fields matching any of `patterns` in place and doesn't
+    /// look into the values it masks.
+    ///
+    /// A pattern starting with `/` is a JSON pointer from the top of the
+    /// value. Any other pattern is a list of keys separated by `.` that
+    /// matches the end of a path, so `password` matches that field
+    /// anywhere and `user.ssn` matches `ssn` in any object that is the
+    /// field `user`. A `*` in a key matches any part of a key, a `*` on
+    /// its own also matches any array index.
+    ///
+    /// ```rust
+    /// use simd_json::{json, Replacement};
+    /// let mut d = br#"{"items": [{"api_key": "a", "id": 1}], "db": {"secret": "b"}}"#.to_vec();
+    /// let mut v = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// v.redact(&["/items/*/*_key", "secret"], Replacement::Remove);
+    /// assert_eq!(v, json!({"items": [{"id": 1}], "db": {}}));
+    /// ```
+    pub fn redact(&mut self, patterns: &[&str], replacement: Replacement) {
+        redact(self, patterns, replacement);
+    }
+}
+
+/// A pattern split into its steps
+struct Pattern<'p> {
+    anchored: bool,
+    steps: Vec<Cow<'p, str>>,
+}
+
+/// A step taken into a value
+#[derive(Clone, Copy)]
+enum Segment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+/// The path to a value, from the value up to the top
+struct Path<'a> {
+    segment: Segment<'a>,
+    depth: usize,
+    parent: Option<&'a Path<'a>>,
+}
+
+impl Pattern<'_> {
+    fn matches(&self, path: &Path) -> bool {
+        if self.steps.len() > path.depth || (self.anchored && self.steps.len() != path.depth) {
+            return false;
+        }
+        let mut node = Some(path);
+        for step in self.steps.iter().rev() {
+            match node {
+                Some(n) if step_matches(step, n.segment) => node = n.parent,
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+fn step_matches(step: &str, segment: Segment) -> bool {
+    match segment {
+        Segment::Key(key) => glob(step.as_bytes(), key.as_bytes()),
+        Segment::Index(idx) => step == "*" || step.parse() == Ok(idx),
+    }
+}
+
+/// Matches `s` against `pattern` where `*` matches any run of bytes
+fn glob(pattern: &[u8], s: &[u8]) -> bool {
+    let (mut p, mut i) = (0, 0);
+    // where to continue after the last `*` if the rest doesn't match
+    let mut backtrack = None;
+    while i < s.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                p += 1;
+                backtrack = Some((p, i));
+            }
+            Some(c) if *c == s[i] => {
+                p += 1;
+                i += 1;
+            }
+            _ => match backtrack {
+                Some((bp, bi)) => {
+                    p = bp;
+                    i = bi + 1;
+                    backtrack = Some((bp, bi + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+fn redact<'v, K, V>(value: &mut V, patterns: &[&str], replacement: Replacement)
+where
+    V: Builder<'v>
+        + Mutable
+        + ValueTrait<Key = K, Array = Vec<V>, Object = HashMap<K, V>>
+        + From<String>
+        + Clone,
+    K: Borrow<str> + Hash + Eq,
+{
+    let patterns: Vec<Pattern> = patterns
+        .iter()
+        .map(|p| {
+            if p.starts_with('/') {
+                Pattern {
+                    anchored: true,
+                    steps: steps(p),
+                }
+            } else {
+                Pattern {
+                    anchored: false,
+                    steps: p.split('.').map(Cow::Borrowed).collect(),
+                }
+            }
+        })
+        .collect();
+    let replacement = match replacement {
+        Replacement::Null => Some(V::null()),
+        Replacement::String(s) => Some(V::from(s.to_string())),
+        Replacement::Remove => None,
+    };
+    walk(value, None, &patterns, replacement.as_ref());
+}
+
+/// Redacts the children of `value`, `replacement` is `None` to remove
+/// them
+fn walk<K, V>(value: &mut V, parent: Option<&Path>, patterns: &[Pattern], replacement: Option<&V>)
+where
+    V: Mutable + ValueTrait<Key = K, Array = Vec<V>, Object = HashMap<K, V>> + Clone,
+    K: Borrow<str> + Hash + Eq,
+{
+    let depth = parent.map_or(1, |p| p.depth + 1);
+    if let Some(object) = value.as_object_mut() {
+        object.retain(|key, child| {
+            let path = Path {
+                segment: Segment::Key(key.borrow()),
+                depth,
+                parent,
+            };
+            visit(child, &path, patterns, replacement)
+        });
+    } else if let Some(array) = value.as_array_mut() {
+        let mut idx = 0;
+        array.retain_mut(|child| {
+            idx += 1;
+            let path = Path {
+                segment: Segment::Index(idx - 1),
+                depth,
+                parent,
+            };
+            visit(child, &path, patterns, replacement)
+        });
+    }
+}
+
+/// Redacts `value` at `path` if a pattern matches and its children
+/// otherwise, returns if `value` is kept
+fn visit<K, V>(value: &mut V, path: &Path, patterns: &[Pattern], replacement: Option<&V>) -> bool
+where
+    V: Mutable + ValueTrait<Key = K, Array = Vec<V>, Object = HashMap<K, V>> + Clone,
+    K: Borrow<str> + Hash + Eq,
+{
+    if !patterns.iter().any(|p| p.matches(path)) {
+        walk(value, Some(path), patterns, replacement);
+        return true;
+    }
+    match replacement {
+        Some(r) => {
+            *value = r.clone();
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn glob() {
+        assert!(super::glob(b"*", b""));
+        assert!(super::glob(b"a*c*", b"abcabc"));
+        assert!(super::glob(b"*_key", b"api_key"));
+        assert!(super::glob(b"a**b", b"ab"));
+        assert!(!super::glob(b"*_key", b"api_keys"));
+        assert!(!super::glob(b"a", b""));
+    }
+
+    #[test]
+    fn patterns() {
+        let event = json!({
+            "password": "x",
+            "user": {"password": "y", "ssn": "1", "cards": [{"pan": "2"}, {"pan": "3"}]},
+            "ssn": "4",
+            "list": [[1, 2], [3]]
+        });
+        let redacted = |patterns: &[&str], replacement| {
+            let mut v = event.clone();
+            v.redact(patterns, replacement);
+            let mut b = BorrowedValue::from(event.clone());
+            b.redact(patterns, replacement);
+            assert_eq!(b, v);
+            v
+        };
+        assert_eq!(
+            redacted(&["password", "/ssn", "cards.*.pan"], Replacement::Null),
+            json!({
+                "password": null,
+                "user": {"password": null, "ssn": "1", "cards": [{"pan": null}, {"pan": null}]},
+                "ssn": null,
+                "list": [[1, 2], [3]]
+            })
+        );
+        assert_eq!(
+            redacted(
+                &["/user/cards/1", "/list/*/0", "/user/cards"],
+                Replacement::Remove
+            ),
+            json!({
+                "password": "x",
+                "user": {"password": "y", "ssn": "1"},
+                "ssn": "4",
+                "list": [[2], []]
+            })
+        );
+        assert_eq!(
+            redacted(&["/user/cards/1", "list.0", "*.*.*"], Replacement::Remove),
+            json!({
+                "password": "x",
+                "user": {"password": "y", "ssn": "1", "cards": []},
+                "ssn": "4",
+                "list": [[]]
+            })
+        );
+        assert_eq!(redacted(&[], Replacement::Null), event);
+        assert_eq!(
+            redacted(&["missing", "/user/x/y"], Replacement::Null),
+            event
+        );
+    }
+}
@@ -0,0 +1,1222 @@
+//! Validation of values against a [JSON Schema](https://json-schema.org).
+//!
+//! A `Schema` is compiled once from a schema document and can then check
+//! any number of values, owned, borrowed or straight from a tape as
+//! returned by `to_tape`. Checking the tape rejects documents without
+//! building a DOM for them first, so parsing to a tape and validating it
+//! is the fast path for inputs that are thrown away when invalid.
+//!
+//! The keywords of draft 2020-12 that constrain values are supported:
+//!
+//! - `type`, `enum` and `const`
+//! - `minimum`, `maximum`, `exclusiveMinimum`, `exclusiveMaximum` and
+//!   `multipleOf`, comparing numbers as `f64`
+//! - `minLength` and `maxLength`, counting characters
+//! - `prefixItems`, `items`, `contains`, `minContains`, `maxContains`,
+//!   `minItems`, `maxItems` and `uniqueItems`
+//! - `properties`, `additionalProperties`, `propertyNames`, `required`,
+//!   `dependentRequired`, `minProperties` and `maxProperties`
+//! - `allOf`, `anyOf`, `oneOf`, `not` and `if` with `then` and `else`
+//! - `$ref` to a JSON pointer into the same document, like
+//!   `#/$defs/address`, references may be recursive
+//!
+//! Annotations like `title`, `default` or `format` and unknown keywords
+//! are ignored, as the specification asks for. Keywords that constrain
+//! values but aren't implemented, like `pattern` or
+//! `unevaluatedProperties`, fail the compilation instead of being
+//! ignored, so a schema never accepts more than it says.
+//!
+//! ```rust
+//! use simd_json::{json, Schema};
+//! let schema = Schema::compile(&json!({
+//!     "type": "object",
+//!     "properties": {"id": {"type": "integer", "minimum": 1}},
+//!     "required": ["id"]
+//! }))
+//! .unwrap();
+//!
+//! let mut d = br#"{"id": 7, "name": "x"}"#.to_vec();
+//! let tape = simd_json::to_tape(&mut d).unwrap();
+//! assert!(schema.is_valid_tape(&tape));
+//!
+//! let mut d = br#"{"id": 0}"#.to_vec();
+//! let v = simd_json::to_borrowed_value(&mut d).unwrap();
+//! let err = schema.validate(&v).unwrap_err();
+//! assert_eq!((err.path(), err.keyword()), ("/id", "minimum"));
+//! ```
+
+use super::flatten::{push_key, FlattenStyle};
+use super::path::steps;
+use super::project::skip;
+use crate::prelude::*;
+use crate::value::tape::Node;
+use crate::{OwnedValue, StaticNode};
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use halfbrown::HashMap;
+
+/// Error for a schema that can't be compiled, holds the JSON pointer of
+/// the offending part of the schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaError {
+    /// A schema or keyword has a value it can't have, like a negative
+    /// `minLength`, or `$ref`s loop without checking anything
+    Invalid(String),
+    /// A keyword that constrains values but isn't supported
+    Unsupported(String),
+    /// A `$ref` that doesn't point into the schema, holds the reference
+    Unresolved(String),
+}
+
+#[cfg_attr(tarpaulin, skip)]
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Invalid(pointer) => write!(f, "Invalid schema at {:?}", pointer),
+            Self::Unsupported(pointer) => write!(f, "Unsupported keyword at {:?}", pointer),
+            Self::Unresolved(reference) => write!(f, "Can't resolve $ref {:?}", reference),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SchemaError {}
+
+/// Error for a value that doesn't match a schema, says which keyword
+/// rejected which part of the value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    path: String,
+    keyword: &'static str,
+}
+
+impl ValidationError {
+    fn new(keyword: &'static str) -> Self {
+        Self {
+            path: String::new(),
+            keyword,
+        }
+    }
+
+    /// JSON pointer of the part of the value that was rejected, empty for
+    /// the value itself
+    #[must_use]
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The keyword that rejected it, like `required` or `maxLength`
+    #[must_use]
+    pub fn keyword(&self) -> &'static str {
+        self.keyword
+    }
+
+    fn at_index(mut self, idx: usize) -> Self {
+        self.path.insert_str(0, &idx.to_string());
+        self.path.insert(0, '/');
+        self
+    }
+
+    fn at_key(mut self, key: &str) -> Self {
+        let mut path = String::new();
+        push_key(&mut path, key, FlattenStyle::Pointer);
+        path.push_str(&self.path);
+        self.path = path;
+        self
+    }
+}
+
+#[cfg_attr(tarpaulin, skip)]
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Failed {:?} at {:?}", self.keyword, self.path)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ValidationError {}
+
+/// Index of a compiled schema in `Schema::rules`
+type Id = usize;
+
+const NULL: u8 = 1;
+const BOOLEAN: u8 = 1 << 1;
+const INTEGER: u8 = 1 << 2;
+const NUMBER: u8 = 1 << 3;
+const STRING: u8 = 1 << 4;
+const ARRAY: u8 = 1 << 5;
+const OBJECT: u8 = 1 << 6;
+
+/// Keywords that constrain values but aren't implemented
+const UNSUPPORTED: &[&str] = &[
+    "pattern",
+    "patternProperties",
+    "dependentSchemas",
+    "dependencies",
+    "unevaluatedItems",
+    "unevaluatedProperties",
+    "$anchor",
+    "$dynamicAnchor",
+    "$dynamicRef",
+    "$recursiveRef",
+];
+
+#[derive(Debug, Clone)]
+enum Keyword {
+    False,
+    Type(u8),
+    Const(OwnedValue),
+    Enum(Vec<OwnedValue>),
+    Minimum(f64),
+    ExclusiveMinimum(f64),
+    Maximum(f64),
+    ExclusiveMaximum(f64),
+    MultipleOf(f64),
+    MinLength(usize),
+    MaxLength(usize),
+    PrefixItems(Vec<Id>),
+    /// `items`, which applies after the `prefixItems`
+    Items {
+        skip: usize,
+        schema: Id,
+    },
+    Contains {
+        schema: Id,
+        min: usize,
+        max: Option<usize>,
+    },
+    MinItems(usize),
+    MaxItems(usize),
+    UniqueItems,
+    Properties(Vec<(String, Id)>),
+    /// `additionalProperties`, which applies to the keys `properties`
+    /// doesn't name
+    AdditionalProperties {
+        known: Vec<String>,
+        schema: Id,
+    },
+    PropertyNames(Id),
+    Required(Vec<String>),
+    DependentRequired(Vec<(String, Vec<String>)>),
+    MinProperties(usize),
+    MaxProperties(usize),
+    AllOf(Vec<Id>),
+    AnyOf(Vec<Id>),
+    OneOf(Vec<Id>),
+    Not(Id),
+    If {
+        condition: Id,
+        then: Option<Id>,
+        otherwise: Option<Id>,
+    },
+    Ref(Id),
+}
+
+impl Keyword {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::False => "false",
+            Self::Type(_) => "type",
+            Self::Const(_) => "const",
+            Self::Enum(_) => "enum",
+            Self::Minimum(_) => "minimum",
+            Self::ExclusiveMinimum(_) => "exclusiveMinimum",
+            Self::Maximum(_) => "maximum",
+            Self::ExclusiveMaximum(_) => "exclusiveMaximum",
+            Self::MultipleOf(_) => "multipleOf",
+            Self::MinLength(_) => "minLength",
+            Self::MaxLength(_) => "maxLength",
+            Self::PrefixItems(_) => "prefixItems",
+            Self::Items { .. } => "items",
+            Self::Contains { .. } => "contains",
+            Self::MinItems(_) => "minItems",
+            Self::MaxItems(_) => "maxItems",
+            Self::UniqueItems => "uniqueItems",
+            Self::Properties(_) => "properties",
+            Self::AdditionalProperties { .. } => "additionalProperties",
+            Self::PropertyNames(_) => "propertyNames",
+            Self::Required(_) => "required",
+            Self::DependentRequired(_) => "dependentRequired",
+            Self::MinProperties(_) => "minProperties",
+            Self::MaxProperties(_) => "maxProperties",
+            Self::AllOf(_) => "allOf",
+            Self::AnyOf(_) => "anyOf",
+            Self::OneOf(_) => "oneOf",
+            Self::Not(_) => "not",
+            Self::If { .. } => "if",
+            Self::Ref(_) => "$ref",
+        }
+    }
+
+    /// Schemas applied to the same value, not to a part of it
+    fn in_place(&self) -> Vec<Id> {
+        match self {
+            Self::AllOf(ids) | Self::AnyOf(ids) | Self::OneOf(ids) => ids.clone(),
+            Self::Not(id) | Self::Ref(id) => alloc::vec![*id],
+            Self::If {
+                condition,
+                then,
+                otherwise,
+            } => core::iter::once(*condition)
+                .chain(*then)
+                .chain(*otherwise)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// A compiled JSON Schema, see the module documentation for what is
+/// supported
+#[derive(Debug, Clone)]
+pub struct Schema {
+    /// The keywords of the schema and its subschemas, the schema itself
+    /// is the first
+    rules: Vec<Vec<Keyword>>,
+}
+
+impl Schema {
+    /// Compiles a schema from its JSON document, `$ref`s are resolved
+    /// against it.
+    ///
+    /// # Errors
+    ///
+    /// if the schema is malformed, uses a keyword that isn't supported or
+    /// has a `$ref` that can't be resolved
+    pub fn compile<V>(schema: &V) -> Result<Self, SchemaError>
+    where
+        V: ValueTrait,
+        V::Key: Borrow<str>,
+    {
+        let mut compiler = Compiler {
+            root: schema,
+            rules: Vec::new(),
+            pointers: Vec::new(),
+            refs: HashMap::new(),
+        };
+        compiler.subschema(schema, "")?;
+        compiler.check_cycles()?;
+        Ok(Self {
+            rules: compiler.rules,
+        })
+    }
+
+    /// Validates a value, owned or borrowed.
+    ///
+    /// ```rust
+    /// use simd_json::{json, Schema};
+    /// let schema = Schema::compile(&json!({"items": {"type": "string"}})).unwrap();
+    /// assert!(schema.validate(&json!(["a", "b"])).is_ok());
+    /// let err = schema.validate(&json!(["a", 2])).unwrap_err();
+    /// assert_eq!(err.to_string(), r#"Failed "type" at "/1""#);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// if the value doesn't match, for the first keyword that fails
+    pub fn validate<V>(&self, value: &V) -> Result<(), ValidationError>
+    where
+        V: ValueTrait,
+        V::Key: Borrow<str>,
+    {
+        self.check(0, value)
+    }
+
+    /// Checks if a value, owned or borrowed, matches the schema
+    #[must_use]
+    pub fn is_valid<V>(&self, value: &V) -> bool
+    where
+        V: ValueTrait,
+        V::Key: Borrow<str>,
+    {
+        self.validate(value).is_ok()
+    }
+
+    /// Validates the document of a tape as returned by `to_tape`, without
+    /// building a DOM. Objects are scanned for their keys, with duplicate
+    /// keys every value of a key has to match.
+    ///
+    /// ```rust
+    /// use simd_json::{json, Schema};
+    /// let schema = Schema::compile(&json!({"maxItems": 2})).unwrap();
+    /// let mut d = br#"[1, 2, 3]"#.to_vec();
+    /// let tape = simd_json::to_tape(&mut d).unwrap();
+    /// assert_eq!(schema.validate_tape(&tape).unwrap_err().keyword(), "maxItems");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// if the document doesn't match, for the first keyword that fails,
+    /// or for `type` if the tape holds no document
+    pub fn validate_tape(&self, tape: &[Node]) -> Result<(), ValidationError> {
+        let root = TapeValue::root(tape).ok_or_else(|| ValidationError::new("type"))?;
+        self.check(0, root)
+    }
+
+    /// Checks if the document of a tape matches the schema
+    #[must_use]
+    pub fn is_valid_tape(&self, tape: &[Node]) -> bool {
+        self.validate_tape(tape).is_ok()
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn check<'a, I: Instance<'a>>(&self, id: Id, value: I) -> Result<(), ValidationError> {
+        let kind = value.kind();
+        let number = match kind {
+            Kind::Static(s) => s.cast_f64(),
+            _ => None,
+        };
+        for keyword in &self.rules[id] {
+            let valid = match (keyword, kind) {
+                (Keyword::False, _) => false,
+                (Keyword::Type(types), kind) => types & type_bits(kind) != 0,
+                (Keyword::Const(expected), _) => same(value, expected),
+                (Keyword::Enum(options), _) => options.iter().any(|o| same(value, o)),
+                // numbers can't be NaN, so not below is at least
+                (Keyword::Minimum(min), _) => !matches!(number, Some(n) if n < *min),
+                (Keyword::ExclusiveMinimum(min), _) => !matches!(number, Some(n) if n <= *min),
+                (Keyword::Maximum(max), _) => !matches!(number, Some(n) if n > *max),
+                (Keyword::ExclusiveMaximum(max), _) => !matches!(number, Some(n) if n >= *max),
+                (Keyword::MultipleOf(factor), _) => {
+                    !matches!(number, Some(n) if !is_whole(n / factor))
+                }
+                (Keyword::MinLength(min), Kind::String(s)) => s.chars().count() >= *min,
+                (Keyword::MaxLength(max), Kind::String(s)) => s.chars().count() <= *max,
+                (Keyword::PrefixItems(ids), Kind::Array(_)) => {
+                    for (idx, (id, element)) in ids.iter().zip(value.elements()).enumerate() {
+                        self.check(*id, element).map_err(|e| e.at_index(idx))?;
+                    }
+                    true
+                }
+                (Keyword::Items { skip, schema }, Kind::Array(_)) => {
+                    for (idx, element) in value.elements().enumerate().skip(*skip) {
+                        self.check(*schema, element).map_err(|e| e.at_index(idx))?;
+                    }
+                    true
+                }
+                (Keyword::Contains { schema, min, max }, Kind::Array(_)) => {
+                    let found = value
+                        .elements()
+                        .filter(|element| self.check(*schema, *element).is_ok())
+                        .count();
+                    found >= *min && !matches!(max, Some(max) if found > *max)
+                }
+                (Keyword::MinItems(min), Kind::Array(len))
+                | (Keyword::MinProperties(min), Kind::Object(len)) => len >= *min,
+                (Keyword::MaxItems(max), Kind::Array(len))
+                | (Keyword::MaxProperties(max), Kind::Object(len)) => len <= *max,
+                (Keyword::UniqueItems, Kind::Array(_)) => {
+                    let elements: Vec<I> = value.elements().collect();
+                    elements
+                        .iter()
+                        .enumerate()
+                        .all(|(idx, a)| elements[idx + 1..].iter().all(|b| !same(*a, *b)))
+                }
+                (Keyword::Properties(properties), Kind::Object(_)) => {
+                    for (key, element) in value.entries() {
+                        if let Some((_, id)) = properties.iter().find(|(name, _)| name == key) {
+                            self.check(*id, element).map_err(|e| e.at_key(key))?;
+                        }
+                    }
+                    true
+                }
+                (Keyword::AdditionalProperties { known, schema }, Kind::Object(_)) => {
+                    for (key, element) in value.entries() {
+                        if !known.iter().any(|name| name == key) {
+                            self.check(*schema, element).map_err(|e| e.at_key(key))?;
+                        }
+                    }
+                    true
+                }
+                (Keyword::PropertyNames(schema), Kind::Object(_)) => {
+                    for (key, _) in value.entries() {
+                        self.check(*schema, Key(key)).map_err(|e| e.at_key(key))?;
+                    }
+                    true
+                }
+                (Keyword::Required(names), Kind::Object(_)) => {
+                    names.iter().all(|name| value.field(name).is_some())
+                }
+                (Keyword::DependentRequired(dependencies), Kind::Object(_)) => {
+                    dependencies.iter().all(|(name, required)| {
+                        value.field(name).is_none()
+                            || required.iter().all(|r| value.field(r).is_some())
+                    })
+                }
+                (Keyword::AllOf(ids), _) => {
+                    for id in ids {
+                        self.check(*id, value)?;
+                    }
+                    true
+                }
+                (Keyword::AnyOf(ids), _) => ids.iter().any(|id| self.check(*id, value).is_ok()),
+                (Keyword::OneOf(ids), _) => {
+                    ids.iter()
+                        .filter(|id| self.check(**id, value).is_ok())
+                        .count()
+                        == 1
+                }
+                (Keyword::Not(id), _) => self.check(*id, value).is_err(),
+                (
+                    Keyword::If {
+                        condition,
+                        then,
+                        otherwise,
+                    },
+                    _,
+                ) => {
+                    let branch = if self.check(*condition, value).is_ok() {
+                        then
+                    } else {
+                        otherwise
+                    };
+                    if let Some(branch) = branch {
+                        self.check(*branch, value)?;
+                    }
+                    true
+                }
+                (Keyword::Ref(id), _) => {
+                    self.check(*id, value)?;
+                    true
+                }
+                // keywords for other types than the one of the value
+                _ => true,
+            };
+            if !valid {
+                return Err(ValidationError::new(keyword.name()));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[allow(clippy::float_cmp)]
+pub(super) fn is_whole(f: f64) -> bool {
+    f.is_finite() && f.trunc() == f
+}
+
+fn type_bits(kind: Kind) -> u8 {
+    match kind {
+        Kind::Static(StaticNode::Null) => NULL,
+        Kind::Static(StaticNode::Bool(_)) => BOOLEAN,
+        Kind::Static(StaticNode::F64(f)) if !is_whole(f) => NUMBER,
+        Kind::Static(_) => INTEGER | NUMBER,
+        Kind::String(_) => STRING,
+        Kind::Array(_) => ARRAY,
+        Kind::Object(_) => OBJECT,
+    }
+}
+
+fn type_bit(name: &str) -> Option<u8> {
+    Some(match name {
+        "null" => NULL,
+        "boolean" => BOOLEAN,
+        "integer" => INTEGER,
+        "number" => NUMBER,
+        "string" => STRING,
+        "array" => ARRAY,
+        "object" => OBJECT,
+        _ => return None,
+    })
+}
+
+/// Equality of JSON Schema, numbers are equal if their values are, so
+/// `1` is `1.0`
+fn same<'a, 'b, A: Instance<'a>, B: Instance<'b>>(a: A, b: B) -> bool {
+    match (a.kind(), b.kind()) {
+        (Kind::Static(x), Kind::Static(y)) => match (x.as_i128(), y.as_i128()) {
+            (Some(x), Some(y)) => x == y,
+            #[allow(clippy::float_cmp)]
+            _ => match (x.cast_f64(), y.cast_f64()) {
+                (Some(x), Some(y)) => x == y,
+                _ => x == y,
+            },
+        },
+        (Kind::String(x), Kind::String(y)) => x == y,
+        (Kind::Array(x), Kind::Array(y)) => {
+            x == y && a.elements().zip(b.elements()).all(|(a, b)| same(a, b))
+        }
+        (Kind::Object(x), Kind::Object(y)) => {
+            x == y
+                && a.entries()
+                    .all(|(key, a)| matches!(b.field(key), Some(b) if same(a, b)))
+        }
+        _ => false,
+    }
+}
+
+/// Copies a part of the schema for `const` and `enum`
+fn literal<'a, I: Instance<'a>>(value: I) -> OwnedValue {
+    match value.kind() {
+        Kind::Static(s) => OwnedValue::from(s),
+        Kind::String(s) => OwnedValue::from(s),
+        Kind::Array(_) => OwnedValue::from(value.elements().map(literal).collect::<Vec<_>>()),
+        Kind::Object(_) => value
+            .entries()
+            .map(|(key, value)| (key, literal(value)))
+            .collect(),
+    }
+}
+
+struct Compiler<'s, V> {
+    root: &'s V,
+    rules: Vec<Vec<Keyword>>,
+    /// Where in the schema document the rules come from
+    pointers: Vec<String>,
+    /// Compiled `$ref`s, so recursive ones end
+    refs: HashMap<String, Id>,
+}
+
+impl<'s, V> Compiler<'s, V>
+where
+    V: ValueTrait,
+    V::Key: Borrow<str>,
+{
+    fn subschema(&mut self, schema: &'s V, pointer: &str) -> Result<Id, SchemaError> {
+        let id = self.reserve(pointer);
+        self.rules[id] = self.keywords(schema, pointer)?;
+        Ok(id)
+    }
+
+    fn reserve(&mut self, pointer: &str) -> Id {
+        self.rules.push(Vec::new());
+        self.pointers.push(pointer.to_string());
+        self.rules.len() - 1
+    }
+
+    fn subschemas(&mut self, schemas: &'s V, pointer: &str) -> Result<Vec<Id>, SchemaError> {
+        let schemas = schemas
+            .as_array()
+            .filter(|a| !a.is_empty())
+            .ok_or_else(|| SchemaError::Invalid(pointer.to_string()))?;
+        let mut ids = Vec::with_capacity(schemas.len());
+        for (idx, schema) in schemas.iter().enumerate() {
+            ids.push(self.subschema(schema, &index_pointer(pointer, idx))?);
+        }
+        Ok(ids)
+    }
+
+    fn optional(
+        &mut self,
+        schema: Option<&'s V>,
+        pointer: &str,
+    ) -> Result<Option<Id>, SchemaError> {
+        schema.map(|s| self.subschema(s, pointer)).transpose()
+    }
+
+    fn reference(&mut self, reference: &str) -> Result<Id, SchemaError> {
+        if let Some(id) = self.refs.get(reference) {
+            return Ok(*id);
+        }
+        let unresolved = || SchemaError::Unresolved(reference.to_string());
+        let pointer = reference
+            .strip_prefix('#')
+            .filter(|p| p.is_empty() || p.starts_with('/'))
+            .ok_or_else(unresolved)?;
+        let mut target = self.root;
+        for step in steps(pointer) {
+            target = if target.is_array() {
+                step.parse().ok().and_then(|idx| target.get_idx(idx))
+            } else {
+                target.get(&*step)
+            }
+            .ok_or_else(unresolved)?;
+        }
+        let id = self.reserve(pointer);
+        self.refs.insert(reference.to_string(), id);
+        self.rules[id] = self.keywords(target, pointer)?;
+        Ok(id)
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn keywords(&mut self, schema: &'s V, pointer: &str) -> Result<Vec<Keyword>, SchemaError> {
+        if let Some(valid) = schema.as_bool() {
+            return Ok(if valid {
+                Vec::new()
+            } else {
+                alloc::vec![Keyword::False]
+            });
+        }
+        let object = schema
+            .as_object()
+            .ok_or_else(|| SchemaError::Invalid(pointer.to_string()))?;
+        let mut keywords = Vec::new();
+        for (key, value) in object.iter() {
+            let key: &str = key.borrow();
+            let at = key_pointer(pointer, key);
+            let invalid = || SchemaError::Invalid(at.clone());
+            let keyword = match key {
+                "type" => Keyword::Type(types(value).ok_or_else(invalid)?),
+                "const" => Keyword::Const(literal(value)),
+                "enum" => Keyword::Enum(
+                    value
+                        .as_array()
+                        .ok_or_else(invalid)?
+                        .iter()
+                        .map(literal)
+                        .collect(),
+                ),
+                "minimum" => Keyword::Minimum(value.cast_f64().ok_or_else(invalid)?),
+                "exclusiveMinimum" => {
+                    Keyword::ExclusiveMinimum(value.cast_f64().ok_or_else(invalid)?)
+                }
+                "maximum" => Keyword::Maximum(value.cast_f64().ok_or_else(invalid)?),
+                "exclusiveMaximum" => {
+                    Keyword::ExclusiveMaximum(value.cast_f64().ok_or_else(invalid)?)
+                }
+                "multipleOf" => {
+                    Keyword::MultipleOf(value.cast_f64().filter(|f| *f > 0.0).ok_or_else(invalid)?)
+                }
+                "minLength" => Keyword::MinLength(count(value).ok_or_else(invalid)?),
+                "maxLength" => Keyword::MaxLength(count(value).ok_or_else(invalid)?),
+                "prefixItems" => Keyword::PrefixItems(self.subschemas(value, &at)?),
+                "items" => Keyword::Items {
+                    skip: schema
+                        .get("prefixItems")
+                        .and_then(ValueTrait::as_array)
+                        .map_or(0, Array::len),
+                    schema: self.subschema(value, &at)?,
+                },
+                "contains" => {
+                    let bound = |name| {
+                        schema
+                            .get(name)
+                            .map(|v| {
+                                count(v)
+                                    .ok_or_else(|| SchemaError::Invalid(key_pointer(pointer, name)))
+                            })
+                            .transpose()
+                    };
+                    Keyword::Contains {
+                        min: bound("minContains")?.unwrap_or(1),
+                        max: bound("maxContains")?,
+                        schema: self.subschema(value, &at)?,
+                    }
+                }
+                "minItems" => Keyword::MinItems(count(value).ok_or_else(invalid)?),
+                "maxItems" => Keyword::MaxItems(count(value).ok_or_else(invalid)?),
+                "uniqueItems" => match value.as_bool() {
+                    Some(true) => Keyword::UniqueItems,
+                    Some(false) => continue,
+                    None => return Err(invalid()),
+                },
+                "properties" => {
+                    let properties = value.as_object().ok_or_else(invalid)?;
+                    let mut ids = Vec::with_capacity(properties.len());
+                    for (name, schema) in properties.iter() {
+                        let name: &str = name.borrow();
+                        let id = self.subschema(schema, &key_pointer(&at, name))?;
+                        ids.push((name.to_string(), id));
+                    }
+                    Keyword::Properties(ids)
+                }
+                "additionalProperties" => Keyword::AdditionalProperties {
+                    known: schema
+                        .get("properties")
+                        .and_then(ValueTrait::as_object)
+                        .map(|p| p.keys().map(|k| k.borrow().to_string()).collect())
+                        .unwrap_or_default(),
+                    schema: self.subschema(value, &at)?,
+                },
+                "propertyNames" => Keyword::PropertyNames(self.subschema(value, &at)?),
+                "required" => Keyword::Required(strings(value).ok_or_else(invalid)?),
+                "dependentRequired" => {
+                    let dependencies = value.as_object().ok_or_else(invalid)?;
+                    let mut required = Vec::with_capacity(dependencies.len());
+                    for (name, names) in dependencies.iter() {
+                        let name: &str = name.borrow();
+                        required.push((name.to_string(), strings(names).ok_or_else(invalid)?));
+                    }
+                    Keyword::DependentRequired(required)
+                }
+                "minProperties" => Keyword::MinProperties(count(value).ok_or_else(invalid)?),
+                "maxProperties" => Keyword::MaxProperties(count(value).ok_or_else(invalid)?),
+                "allOf" => Keyword::AllOf(self.subschemas(value, &at)?),
+                "anyOf" => Keyword::AnyOf(self.subschemas(value, &at)?),
+                "oneOf" => Keyword::OneOf(self.subschemas(value, &at)?),
+                "not" => Keyword::Not(self.subschema(value, &at)?),
+                "if" => Keyword::If {
+                    condition: self.subschema(value, &at)?,
+                    then: self.optional(schema.get("then"), &key_pointer(pointer, "then"))?,
+                    otherwise: self.optional(schema.get("else"), &key_pointer(pointer, "else"))?,
+                },
+                "$ref" => Keyword::Ref(self.reference(value.as_str().ok_or_else(invalid)?)?),
+                key if UNSUPPORTED.contains(&key) => return Err(SchemaError::Unsupported(at)),
+                // annotations, unknown keywords and the ones read above
+                // with the keyword they belong to
+                _ => continue,
+            };
+            keywords.push(keyword);
+        }
+        Ok(keywords)
+    }
+
+    /// Rejects schemas that apply themselves to the same value without
+    /// end, like `{"$ref": "#"}`
+    fn check_cycles(&self) -> Result<(), SchemaError> {
+        // 0 is unvisited, 1 on the current path and 2 done
+        let mut state = alloc::vec![0_u8; self.rules.len()];
+        for id in 0..self.rules.len() {
+            self.visit(id, &mut state)?;
+        }
+        Ok(())
+    }
+
+    fn visit(&self, id: Id, state: &mut [u8]) -> Result<(), SchemaError> {
+        match state[id] {
+            1 => return Err(SchemaError::Invalid(self.pointers[id].clone())),
+            2 => return Ok(()),
+            _ => state[id] = 1,
+        }
+        for keyword in &self.rules[id] {
+            for next in keyword.in_place() {
+                self.visit(next, state)?;
+            }
+        }
+        state[id] = 2;
+        Ok(())
+    }
+}
+
+pub(super) fn key_pointer(pointer: &str, key: &str) -> String {
+    let mut pointer = pointer.to_string();
+    push_key(&mut pointer, key, FlattenStyle::Pointer);
+    pointer
+}
+
+fn index_pointer(pointer: &str, idx: usize) -> String {
+    let mut pointer = pointer.to_string();
+    pointer.push('/');
+    pointer.push_str(&idx.to_string());
+    pointer
+}
+
+fn count<V: ValueTrait>(value: &V) -> Option<usize> {
+    value.as_usize()
+}
+
+fn types<V: ValueTrait>(value: &V) -> Option<u8> {
+    if let Some(name) = value.as_str() {
+        type_bit(name)
+    } else {
+        value
+            .as_array()?
+            .iter()
+            .try_fold(0, |types, t| Some(types | type_bit(t.as_str()?)?))
+    }
+}
+
+fn strings<V: ValueTrait>(value: &V) -> Option<Vec<String>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|s| s.as_str().map(String::from))
+        .collect()
+}
+
+/// What the validator needs to know about a value
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Kind<'a> {
+    Static(StaticNode),
+    String(&'a str),
+    Array(usize),
+    Object(usize),
+}
+
+/// A value the validator can check, a DOM value or a position in a tape
+pub(super) trait Instance<'a>: Copy {
+    type Elements: Iterator<Item = Self>;
+    type Entries: Iterator<Item = (&'a str, Self)>;
+
+    fn kind(self) -> Kind<'a>;
+
+    /// The elements of an array, nothing for other values
+    fn elements(self) -> Self::Elements;
+
+    /// The entries of an object, nothing for other values
+    fn entries(self) -> Self::Entries;
+
+    fn field(self, key: &str) -> Option<Self> {
+        self.entries().find(|(k, _)| *k == key).map(|(_, v)| v)
+    }
+}
+
+impl<'a, V> Instance<'a> for &'a V
+where
+    V: ValueTrait,
+    V::Key: Borrow<str>,
+{
+    type Elements = Box<dyn Iterator<Item = Self> + 'a>;
+    type Entries = Box<dyn Iterator<Item = (&'a str, Self)> + 'a>;
+
+    fn kind(self) -> Kind<'a> {
+        if let Some(s) = self.as_str() {
+            Kind::String(s)
+        } else if let Some(array) = self.as_array() {
+            Kind::Array(array.len())
+        } else if let Some(object) = self.as_object() {
+            Kind::Object(object.len())
+        } else if self.is_null() {
+            Kind::Static(StaticNode::Null)
+        } else if let Some(b) = self.as_bool() {
+            Kind::Static(StaticNode::Bool(b))
+        } else if let Some(i) = self.as_i64() {
+            Kind::Static(StaticNode::I64(i))
+        } else if let Some(u) = self.as_u64() {
+            Kind::Static(StaticNode::U64(u))
+        } else {
+            // floats, and 128 bit integers which are compared as floats
+            Kind::Static(StaticNode::F64(self.cast_f64().unwrap_or_default()))
+        }
+    }
+
+    fn elements(self) -> Self::Elements {
+        match self.as_array() {
+            Some(array) => array.iter(),
+            None => Box::new(core::iter::empty()),
+        }
+    }
+
+    fn entries(self) -> Self::Entries {
+        match self.as_object() {
+            Some(object) => Box::new(object.iter().map(|(k, v)| (k.borrow(), v))),
+            None => Box::new(core::iter::empty()),
+        }
+    }
+
+    fn field(self, key: &str) -> Option<Self> {
+        ValueTrait::get(self, key)
+    }
+}
+
+/// A value in a tape
+#[derive(Clone, Copy)]
+pub(super) struct TapeValue<'a, 'input> {
+    tape: &'a [Node<'input>],
+    idx: usize,
+}
+
+impl<'a, 'input> TapeValue<'a, 'input> {
+    /// The document of a tape, `None` if it holds none
+    pub(super) fn root(tape: &'a [Node<'input>]) -> Option<Self> {
+        // the first node of a tape is a placeholder, the document starts after it
+        if tape.len() < 2 {
+            None
+        } else {
+            Some(Self { tape, idx: 1 })
+        }
+    }
+}
+
+/// The elements or entries of a value in a tape
+struct TapeIter<'a, 'input> {
+    tape: &'a [Node<'input>],
+    next: usize,
+    left: usize,
+}
+
+impl<'a, 'input> TapeIter<'a, 'input> {
+    fn new(value: TapeValue<'a, 'input>, object: bool) -> Self {
+        let left = match value.tape[value.idx] {
+            Node::Array(len, _) if !object => len,
+            Node::Object(len, _) if object => len,
+            _ => 0,
+        };
+        Self {
+            tape: value.tape,
+            next: value.idx + 1,
+            left,
+        }
+    }
+
+    fn value(&mut self) -> TapeValue<'a, 'input> {
+        let idx = self.next;
+        self.next = skip(self.tape, idx);
+        self.left -= 1;
+        TapeValue {
+            tape: self.tape,
+            idx,
+        }
+    }
+}
+
+/// The elements of an array in a tape
+pub(super) struct TapeElements<'a, 'input>(TapeIter<'a, 'input>);
+
+impl<'a, 'input> Iterator for TapeElements<'a, 'input> {
+    type Item = TapeValue<'a, 'input>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.left == 0 {
+            None
+        } else {
+            Some(self.0.value())
+        }
+    }
+}
+
+/// The entries of an object in a tape
+pub(super) struct TapeEntries<'a, 'input>(TapeIter<'a, 'input>);
+
+impl<'a, 'input> Iterator for TapeEntries<'a, 'input> {
+    type Item = (&'a str, TapeValue<'a, 'input>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.left == 0 {
+            return None;
+        }
+        let key = match self.0.tape[self.0.next] {
+            Node::String(key) => key,
+            _ => "",
+        };
+        self.0.next += 1;
+        Some((key, self.0.value()))
+    }
+}
+
+impl<'a, 'input: 'a> Instance<'a> for TapeValue<'a, 'input> {
+    type Elements = TapeElements<'a, 'input>;
+    type Entries = TapeEntries<'a, 'input>;
+
+    fn kind(self) -> Kind<'a> {
+        match self.tape[self.idx] {
+            Node::Static(s) => Kind::Static(s),
+            Node::String(s) => Kind::String(s),
+            Node::Array(len, _) => Kind::Array(len),
+            Node::Object(len, _) => Kind::Object(len),
+        }
+    }
+
+    fn elements(self) -> Self::Elements {
+        TapeElements(TapeIter::new(self, false))
+    }
+
+    fn entries(self) -> Self::Entries {
+        TapeEntries(TapeIter::new(self, true))
+    }
+}
+
+/// A key of an object, checked by `propertyNames`
+#[derive(Clone, Copy)]
+struct Key<'a>(&'a str);
+
+impl<'a> Instance<'a> for Key<'a> {
+    type Elements = core::iter::Empty<Self>;
+    type Entries = core::iter::Empty<(&'a str, Self)>;
+
+    fn kind(self) -> Kind<'a> {
+        Kind::String(self.0)
+    }
+
+    fn elements(self) -> Self::Elements {
+        core::iter::empty()
+    }
+
+    fn entries(self) -> Self::Entries {
+        core::iter::empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::BorrowedValue;
+
+    /// Checks the owned, borrowed and tape validation agree and returns
+    /// the error
+    fn validate(schema: &Schema, json: &str) -> Result<(), ValidationError> {
+        let mut d = json.as_bytes().to_vec();
+        let owned = crate::to_owned_value(&mut d).unwrap();
+        let mut d = json.as_bytes().to_vec();
+        let borrowed = crate::to_borrowed_value(&mut d).unwrap();
+        let mut d = json.as_bytes().to_vec();
+        let tape = crate::to_tape(&mut d).unwrap();
+        let res = schema.validate(&owned);
+        assert_eq!(res, schema.validate(&borrowed), "{}", json);
+        assert_eq!(res, schema.validate_tape(&tape), "{}", json);
+        res
+    }
+
+    fn failure(schema: &Schema, json: &str) -> (String, &'static str) {
+        let err = validate(schema, json).unwrap_err();
+        (err.path().to_string(), err.keyword())
+    }
+
+    #[test]
+    fn keywords() {
+        let schema = Schema::compile(&json!({
+            "type": "object",
+            "properties": {
+                "id": {"type": "integer", "minimum": 1, "multipleOf": 2},
+                "name": {"type": "string", "minLength": 1, "maxLength": 3},
+                "tags": {
+                    "type": "array",
+                    "prefixItems": [{"const": "first"}],
+                    "items": {"enum": ["a", "b", 1.0]},
+                    "uniqueItems": true,
+                    "maxItems": 3
+                },
+                "a/b": {"type": ["null", "boolean"]}
+            },
+            "required": ["id"],
+            "additionalProperties": false,
+            "dependentRequired": {"tags": ["name"]}
+        }))
+        .unwrap();
+        assert_eq!(validate(&schema, r#"{"id": 2.0}"#), Ok(()));
+        assert_eq!(
+            validate(
+                &schema,
+                r#"{"id": 4, "name": "äöü", "tags": ["first", "a", 1], "a/b": null}"#
+            ),
+            Ok(())
+        );
+        assert_eq!(failure(&schema, "[]"), (String::new(), "type"));
+        assert_eq!(failure(&schema, "{}"), (String::new(), "required"));
+        assert_eq!(
+            failure(&schema, r#"{"id": 0}"#),
+            ("/id".to_string(), "minimum")
+        );
+        assert_eq!(
+            failure(&schema, r#"{"id": 3}"#),
+            ("/id".to_string(), "multipleOf")
+        );
+        assert_eq!(
+            failure(&schema, r#"{"id": 2.5}"#),
+            ("/id".to_string(), "type")
+        );
+        assert_eq!(
+            failure(&schema, r#"{"id": 2, "name": "abcd"}"#),
+            ("/name".to_string(), "maxLength")
+        );
+        assert_eq!(
+            failure(&schema, r#"{"id": 2, "x": 1}"#),
+            ("/x".to_string(), "false")
+        );
+        assert_eq!(
+            failure(&schema, r#"{"id": 2, "a/b": 1}"#),
+            ("/a~1b".to_string(), "type")
+        );
+        assert_eq!(
+            failure(&schema, r#"{"id": 2, "tags": []}"#),
+            (String::new(), "dependentRequired")
+        );
+        let tags = |tags: &str| format!(r#"{{"id": 2, "name": "n", "tags": {}}}"#, tags);
+        assert_eq!(
+            failure(&schema, &tags(r#"["a"]"#)),
+            ("/tags/0".to_string(), "const")
+        );
+        assert_eq!(
+            failure(&schema, &tags(r#"["first", "c"]"#)),
+            ("/tags/1".to_string(), "enum")
+        );
+        assert_eq!(
+            failure(&schema, &tags(r#"["first", 1, 1.0]"#)),
+            ("/tags".to_string(), "uniqueItems")
+        );
+        assert_eq!(
+            failure(&schema, &tags(r#"["first", "a", "b", 1]"#)),
+            ("/tags".to_string(), "maxItems")
+        );
+    }
+
+    #[test]
+    fn applicators() {
+        let schema = Schema::compile(&json!({
+            "$defs": {
+                "node": {
+                    "type": "object",
+                    "properties": {"children": {"items": {"$ref": "#/$defs/node"}}},
+                    "propertyNames": {"maxLength": 8}
+                }
+            },
+            "allOf": [{"$ref": "#/$defs/node"}],
+            "anyOf": [{"required": ["a"]}, {"required": ["b"]}],
+            "oneOf": [{"required": ["a"]}, {"required": ["c"]}, {"not": {}}],
+            "if": {"required": ["b"]},
+            "then": {"minProperties": 3},
+            "else": {"maxProperties": 2},
+            "contains": false,
+            "properties": {"list": {"contains": {"type": "string"}, "minContains": 2}}
+        }))
+        .unwrap();
+        assert_eq!(validate(&schema, r#"{"a": 1}"#), Ok(()));
+        assert_eq!(
+            validate(&schema, r#"{"b": 1, "c": 2, "children": [{"d": {}}]}"#),
+            Ok(())
+        );
+        assert_eq!(failure(&schema, r#"{"c": 1}"#), (String::new(), "anyOf"));
+        assert_eq!(
+            failure(&schema, r#"{"a": 1, "b": 1, "c": 1}"#),
+            (String::new(), "oneOf")
+        );
+        assert_eq!(
+            failure(&schema, r#"{"b": 1, "c": 1}"#),
+            (String::new(), "minProperties")
+        );
+        assert_eq!(
+            failure(&schema, r#"{"a": 1, "d": 1, "e": 1}"#),
+            (String::new(), "maxProperties")
+        );
+        assert_eq!(
+            failure(
+                &schema,
+                r#"{"a": 1, "children": [{"children": [{"long key!": 1}]}]}"#
+            ),
+            ("/children/0/children/0/long key!".to_string(), "maxLength")
+        );
+        assert_eq!(
+            failure(&schema, r#"{"a": 1, "list": ["x", 1]}"#),
+            ("/list".to_string(), "contains")
+        );
+        assert_eq!(
+            validate(&schema, r#"{"a": 1, "list": ["x", 1, "y"]}"#),
+            Ok(())
+        );
+
+        let never = Schema::compile(&json!(false)).unwrap();
+        assert!(!never.is_valid(&json!(null)));
+        assert!(!never.is_valid_tape(&[]));
+        assert!(Schema::compile(&json!(true))
+            .unwrap()
+            .is_valid(&BorrowedValue::from(1)));
+    }
+
+    #[test]
+    fn compile_errors() {
+        let err = |schema: OwnedValue| Schema::compile(&schema).unwrap_err();
+        assert_eq!(err(json!(1)), SchemaError::Invalid(String::new()));
+        assert_eq!(
+            err(json!({"properties": {"a": {"minLength": -1}}})),
+            SchemaError::Invalid("/properties/a/minLength".to_string())
+        );
+        assert_eq!(
+            err(json!({"type": "text"})),
+            SchemaError::Invalid("/type".to_string())
+        );
+        assert_eq!(
+            err(json!({"anyOf": []})),
+            SchemaError::Invalid("/anyOf".to_string())
+        );
+        assert_eq!(
+            err(json!({"items": {"pattern": "^a"}})),
+            SchemaError::Unsupported("/items/pattern".to_string())
+        );
+        assert_eq!(
+            err(json!({"$ref": "#/$defs/missing"})),
+            SchemaError::Unresolved("#/$defs/missing".to_string())
+        );
+        assert_eq!(
+            err(json!({"$ref": "other.json"})),
+            SchemaError::Unresolved("other.json".to_string())
+        );
+        assert_eq!(
+            err(json!({"$defs": {"a": {"anyOf": [{"$ref": "#"}]}}, "$ref": "#/$defs/a"})),
+            SchemaError::Invalid("/$defs/a".to_string())
+        );
+        assert!(Schema::compile(&json!({"title": "x", "format": "email", "foo": 1})).is_ok());
+    }
+}
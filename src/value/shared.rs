@@ -0,0 +1,744 @@
+use super::build::{build, dismantle, Build, Nested};
+use super::emit::{Emit, Part};
+use super::owned::{Object as OwnedObject, Value as OwnedValue};
+#[cfg(feature = "std")]
+use crate::prelude::*;
+#[cfg(feature = "std")]
+use crate::value::encoder::write_json;
+use crate::{Deserializer, Node, Result, StaticNode};
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use halfbrown::HashMap;
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+#[cfg(feature = "std")]
+use value_trait::generator::{
+    DumpGenerator, PrettyGenerator, PrettyWriterGenerator, WriterGenerator,
+};
+use value_trait::AccessError;
+
+/// Parses a slice of bytes into a shared value, see `SharedValue`.
+///
+/// # Errors
+///
+/// Will return `Err` if `s` is invalid JSON.
+pub fn to_value(s: &mut [u8]) -> Result<SharedValue> {
+    let de = Deserializer::from_slice(s)?;
+    Ok(build(&mut SharedDeserializer { de, pool: None }))
+}
+
+/// Parses a slice of bytes into a shared value whose strings are taken
+/// from `pool`, so every occurrence of a string is one allocation no
+/// matter how often it is repeated, in this document and in every other
+/// one parsed with the same pool.
+///
+/// ```rust
+/// use simd_json::{to_shared_value_with_pool, StringPool};
+/// let mut pool = StringPool::new();
+/// let mut d = br#"[{"level":"INFO"},{"level":"WARN"},{"level":"INFO"}]"#.to_vec();
+/// let v = to_shared_value_with_pool(&mut d, &mut pool).unwrap();
+/// let level = |i| v.get_idx(i).and_then(|r| r.get("level")).unwrap();
+/// assert!(level(0).ptr_eq(level(2)));
+/// assert_eq!(pool.len(), 2);
+/// ```
+///
+/// # Errors
+///
+/// Will return `Err` if `s` is invalid JSON.
+pub fn to_value_with_pool(s: &mut [u8], pool: &mut StringPool) -> Result<SharedValue> {
+    let de = Deserializer::from_slice(s)?;
+    Ok(build(&mut SharedDeserializer {
+        de,
+        pool: Some(pool),
+    }))
+}
+
+/// Deduplicates the string values of shared values: every distinct
+/// string is allocated once and handed out as clones of the same `Arc`
+/// after that. Records with enum like fields, log levels, HTTP methods
+/// or country codes, then hold a pointer per field instead of a copy of
+/// the string.
+///
+/// Strings are kept as long as the pool is, so for documents full of
+/// unique strings a maximum length, see `with_max_len`, keeps the pool
+/// from holding on to all of them.
+#[derive(Debug, Clone, Default)]
+pub struct StringPool {
+    strings: HashMap<Arc<str>, ()>,
+    max_len: Option<usize>,
+}
+
+impl StringPool {
+    /// A pool that interns strings of any length
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A pool that only interns strings of at most `max_len` bytes, longer
+    /// ones are allocated every time
+    #[must_use]
+    pub fn with_max_len(max_len: usize) -> Self {
+        Self {
+            strings: HashMap::new(),
+            max_len: Some(max_len),
+        }
+    }
+
+    /// The string `s` from the pool, it is added if it isn't in it yet
+    pub fn intern(&mut self, s: &str) -> Arc<str> {
+        if matches!(self.max_len, Some(max_len) if s.len() > max_len) {
+            return s.into();
+        }
+        if let Some((interned, ())) = self.strings.raw_entry().from_key(s) {
+            return interned.clone();
+        }
+        let interned: Arc<str> = s.into();
+        self.strings.insert_nocheck(interned.clone(), ());
+        interned
+    }
+
+    /// The number of distinct strings in the pool
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Whether the pool holds no strings
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Drops the pool's references to its strings, values that use them
+    /// keep them alive
+    pub fn clear(&mut self) {
+        self.strings.clear();
+    }
+}
+
+/// Representation of a JSON object of a shared value
+pub type Object = HashMap<String, SharedValue>;
+
+/// A JSON value whose strings, arrays and objects are behind `Arc`s, so
+/// cloning it or any part of it is O(1) no matter how large it is and
+/// clones can be handed to other threads. Parsed documents become shared
+/// with `OwnedValue::into_shared`.
+///
+/// ```rust
+/// use simd_json::{json, prelude::*, SharedValue};
+/// let config = json!({"servers": [{"host": "a"}, {"host": "b"}]}).into_shared();
+/// let servers = config.get("servers").cloned().expect("no servers");
+/// let worker = std::thread::spawn(move || servers.get_idx(1).and_then(|s| s.get("host")).cloned());
+/// let host = worker.join().expect("worker panicked");
+/// assert_eq!(host.as_ref().and_then(SharedValue::as_str), Some("b"));
+/// assert_eq!(config.encode(), r#"{"servers":[{"host":"a"},{"host":"b"}]}"#);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum SharedValue {
+    /// Static values
+    Static(StaticNode),
+    /// string type
+    String(Arc<str>),
+    /// array type
+    Array(Arc<Vec<SharedValue>>),
+    /// object type
+    Object(Arc<Object>),
+    /// binary data, written as a base64 string
+    #[cfg(feature = "bytes")]
+    Bytes(Arc<[u8]>),
+}
+
+impl SharedValue {
+    /// Drops the value without recursing into it, see
+    /// `OwnedValue::dismantle`. Arrays and objects other values still
+    /// share are left to them.
+    pub fn dismantle(self) {
+        dismantle(self);
+    }
+
+    /// Gets the value of `key` if this is an object
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&Self> {
+        self.as_object().and_then(|o| o.get(key))
+    }
+
+    /// Gets the element at `idx` if this is an array
+    #[must_use]
+    pub fn get_idx(&self, idx: usize) -> Option<&Self> {
+        self.as_array().and_then(|a| a.get(idx))
+    }
+
+    /// The string if this is a string
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        if let Self::String(s) = self {
+            Some(s)
+        } else {
+            None
+        }
+    }
+
+    /// The elements if this is an array
+    #[must_use]
+    pub fn as_array(&self) -> Option<&[Self]> {
+        if let Self::Array(a) = self {
+            Some(a)
+        } else {
+            None
+        }
+    }
+
+    /// The entries if this is an object
+    #[must_use]
+    pub fn as_object(&self) -> Option<&Object> {
+        if let Self::Object(o) = self {
+            Some(o)
+        } else {
+            None
+        }
+    }
+
+    /// The static value if this is a `null`, a boolean or a number
+    #[must_use]
+    pub fn as_static(&self) -> Option<&StaticNode> {
+        if let Self::Static(s) = self {
+            Some(s)
+        } else {
+            None
+        }
+    }
+
+    /// The elements if this is an array, to change them. Arrays shared
+    /// with other values are copied first, their elements are not.
+    #[must_use]
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<Self>> {
+        if let Self::Array(a) = self {
+            Some(Arc::make_mut(a))
+        } else {
+            None
+        }
+    }
+
+    /// The entries if this is an object, to change them. Objects shared
+    /// with other values are copied first, their values are not.
+    #[must_use]
+    pub fn as_object_mut(&mut self) -> Option<&mut Object> {
+        if let Self::Object(o) = self {
+            Some(Arc::make_mut(o))
+        } else {
+            None
+        }
+    }
+
+    /// Gets the value of `key` to change it if this is an object, see
+    /// `as_object_mut`
+    #[must_use]
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Self> {
+        self.as_object_mut().and_then(|o| o.get_mut(key))
+    }
+
+    /// Gets the element at `idx` to change it if this is an array, see
+    /// `as_array_mut`
+    #[must_use]
+    pub fn get_idx_mut(&mut self, idx: usize) -> Option<&mut Self> {
+        self.as_array_mut().and_then(|a| a.get_mut(idx))
+    }
+
+    /// Inserts `value` at `key` and returns the value that was there
+    /// before. Inserting a clone of part of another value grafts it
+    /// without copying, both values share it until either changes it.
+    ///
+    /// ```rust
+    /// use simd_json::{json, prelude::*, SharedValue};
+    /// let template = json!({"headers": {"accept": "*/*"}, "retries": 3}).into_shared();
+    /// let mut request = template.clone();
+    /// request.insert("retries", 5_u64).unwrap();
+    /// request.get_mut("headers").unwrap().insert("host", "a").unwrap();
+    /// assert_eq!(template.encode(), r#"{"headers":{"accept":"*/*"},"retries":3}"#);
+    /// let mut other = json!({}).into_shared();
+    /// other.insert("headers", template.get("headers").cloned().unwrap()).unwrap();
+    /// assert!(other.get("headers").unwrap().ptr_eq(template.get("headers").unwrap()));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `self` is not an object.
+    pub fn insert<K, V>(
+        &mut self,
+        key: K,
+        value: V,
+    ) -> core::result::Result<Option<Self>, AccessError>
+    where
+        K: Into<String>,
+        V: Into<Self>,
+    {
+        self.as_object_mut()
+            .map(|o| o.insert(key.into(), value.into()))
+            .ok_or(AccessError::NotAnObject)
+    }
+
+    /// Removes `key` and returns its value
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `self` is not an object.
+    pub fn remove(&mut self, key: &str) -> core::result::Result<Option<Self>, AccessError> {
+        self.as_object_mut()
+            .map(|o| o.remove(key))
+            .ok_or(AccessError::NotAnObject)
+    }
+
+    /// Appends `value` to the elements
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `self` is not an array.
+    pub fn push<V: Into<Self>>(&mut self, value: V) -> core::result::Result<(), AccessError> {
+        self.as_array_mut()
+            .map(|a| a.push(value.into()))
+            .ok_or(AccessError::NotAnArray)
+    }
+
+    /// Whether `self` and `other` are clones of the same value, as
+    /// opposed to two values that are merely equal
+    #[must_use]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Static(s1), Self::Static(s2)) => s1 == s2,
+            (Self::String(s1), Self::String(s2)) => Arc::ptr_eq(s1, s2),
+            (Self::Array(a1), Self::Array(a2)) => Arc::ptr_eq(a1, a2),
+            (Self::Object(o1), Self::Object(o2)) => Arc::ptr_eq(o1, o2),
+            #[cfg(feature = "bytes")]
+            (Self::Bytes(b1), Self::Bytes(b2)) => Arc::ptr_eq(b1, b2),
+            _ => false,
+        }
+    }
+
+    /// A deep copy of the value that can be changed again
+    #[must_use]
+    pub fn to_owned_value(&self) -> OwnedValue {
+        match self {
+            Self::Static(s) => OwnedValue::Static(*s),
+            Self::String(s) => OwnedValue::String(String::from(&**s)),
+            Self::Array(a) => OwnedValue::Array(a.iter().map(Self::to_owned_value).collect()),
+            Self::Object(o) => {
+                let mut res = OwnedObject::with_capacity(o.len());
+                for (k, v) in o.iter() {
+                    res.insert(k.clone(), v.to_owned_value());
+                }
+                OwnedValue::from(res)
+            }
+            #[cfg(feature = "bytes")]
+            Self::Bytes(b) => OwnedValue::Bytes(b.to_vec()),
+        }
+    }
+}
+
+impl OwnedValue {
+    /// Turns this into a value that can be cloned in O(1), see
+    /// `SharedValue`
+    #[must_use]
+    pub fn into_shared(self) -> SharedValue {
+        SharedValue::from(self)
+    }
+
+    /// Turns this into a shared value whose strings are taken from
+    /// `pool`, see `StringPool`
+    #[must_use]
+    pub fn into_shared_with_pool(self, pool: &mut StringPool) -> SharedValue {
+        SharedValue::from_owned(self, &mut Some(pool))
+    }
+}
+
+impl SharedValue {
+    fn from_owned(v: OwnedValue, pool: &mut Option<&mut StringPool>) -> Self {
+        match v {
+            OwnedValue::Static(s) => Self::Static(s),
+            OwnedValue::String(s) => match pool {
+                Some(pool) => Self::String(pool.intern(&s)),
+                None => Self::String(s.into()),
+            },
+            OwnedValue::Array(a) => Self::Array(Arc::new(
+                a.into_iter().map(|v| Self::from_owned(v, pool)).collect(),
+            )),
+            OwnedValue::Object(o) => {
+                let mut res = Object::with_capacity(o.len());
+                for (k, v) in *o {
+                    res.insert(k, Self::from_owned(v, pool));
+                }
+                Self::Object(Arc::new(res))
+            }
+            #[cfg(feature = "bytes")]
+            OwnedValue::Bytes(b) => Self::Bytes(b.into()),
+        }
+    }
+}
+
+impl From<OwnedValue> for SharedValue {
+    fn from(v: OwnedValue) -> Self {
+        Self::from_owned(v, &mut None)
+    }
+}
+
+impl From<StaticNode> for SharedValue {
+    fn from(s: StaticNode) -> Self {
+        Self::Static(s)
+    }
+}
+
+impl From<bool> for SharedValue {
+    fn from(b: bool) -> Self {
+        Self::Static(StaticNode::Bool(b))
+    }
+}
+
+impl From<i64> for SharedValue {
+    fn from(i: i64) -> Self {
+        Self::Static(StaticNode::I64(i))
+    }
+}
+
+impl From<u64> for SharedValue {
+    fn from(u: u64) -> Self {
+        Self::Static(StaticNode::U64(u))
+    }
+}
+
+impl From<f64> for SharedValue {
+    fn from(f: f64) -> Self {
+        Self::Static(StaticNode::F64(f))
+    }
+}
+
+impl From<&str> for SharedValue {
+    fn from(s: &str) -> Self {
+        Self::String(s.into())
+    }
+}
+
+impl From<String> for SharedValue {
+    fn from(s: String) -> Self {
+        Self::String(s.into())
+    }
+}
+
+impl From<Vec<SharedValue>> for SharedValue {
+    fn from(a: Vec<Self>) -> Self {
+        Self::Array(Arc::new(a))
+    }
+}
+
+impl From<Object> for SharedValue {
+    fn from(o: Object) -> Self {
+        Self::Object(Arc::new(o))
+    }
+}
+
+impl From<&SharedValue> for OwnedValue {
+    fn from(v: &SharedValue) -> Self {
+        v.to_owned_value()
+    }
+}
+
+impl PartialEq<OwnedValue> for SharedValue {
+    fn eq(&self, other: &OwnedValue) -> bool {
+        match (self, other) {
+            (Self::Static(s1), OwnedValue::Static(s2)) => s1 == s2,
+            (Self::String(s1), OwnedValue::String(s2)) => **s1 == **s2,
+            (Self::Array(a1), OwnedValue::Array(a2)) => {
+                a1.len() == a2.len() && a1.iter().zip(a2.iter()).all(|(v1, v2)| v1 == v2)
+            }
+            (Self::Object(o1), OwnedValue::Object(o2)) => {
+                o1.len() == o2.len()
+                    && o1
+                        .iter()
+                        .all(|(k, v1)| matches!(o2.get(k.as_str()), Some(v2) if v1 == v2))
+            }
+            #[cfg(feature = "bytes")]
+            (Self::Bytes(b1), OwnedValue::Bytes(b2)) => **b1 == **b2,
+            _ => false,
+        }
+    }
+}
+
+impl Nested for SharedValue {
+    fn take_children(&mut self, out: &mut Vec<Self>) {
+        match self {
+            Self::Array(a) => {
+                if let Some(a) = Arc::get_mut(a) {
+                    out.append(a);
+                }
+            }
+            Self::Object(o) => {
+                if let Some(o) = Arc::get_mut(o) {
+                    out.extend(o.drain().map(|(_, v)| v));
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+impl Emit for SharedValue {
+    type Key = String;
+    type Entries<'a> = halfbrown::Iter<'a, String, Self>;
+
+    #[inline(always)]
+    fn part(&self) -> Part<'_, Self> {
+        match self {
+            Self::Static(s) => Part::Static(*s),
+            Self::String(s) => Part::String(s),
+            #[cfg(feature = "bytes")]
+            Self::Bytes(b) => Part::Bytes(b),
+            Self::Array(a) => Part::Array(a),
+            Self::Object(o) => Part::Object(o.len(), o.iter()),
+        }
+    }
+}
+
+struct SharedDeserializer<'de, 'p> {
+    de: Deserializer<'de>,
+    pool: Option<&'p mut StringPool>,
+}
+
+impl<'de> Build<'de> for SharedDeserializer<'de, '_> {
+    type Value = SharedValue;
+    type Key = String;
+    type Array = Vec<SharedValue>;
+    type Object = Object;
+
+    fn next_node(&mut self) -> Node<'de> {
+        self.de.next_()
+    }
+
+    fn static_node(&mut self, s: StaticNode) -> Self::Value {
+        SharedValue::Static(s)
+    }
+
+    fn string(&mut self, s: &'de str) -> Self::Value {
+        SharedValue::String(match &mut self.pool {
+            Some(pool) => pool.intern(s),
+            None => s.into(),
+        })
+    }
+
+    fn key(&mut self, s: &'de str) -> Self::Key {
+        s.into()
+    }
+
+    fn array(&mut self, len: usize) -> Self::Array {
+        Vec::with_capacity(len)
+    }
+
+    fn push(&mut self, array: &mut Self::Array, value: Self::Value) {
+        array.push(value);
+    }
+
+    fn finish_array(&mut self, array: Self::Array) -> Self::Value {
+        SharedValue::from(array)
+    }
+
+    fn object(&mut self, len: usize) -> Self::Object {
+        Object::with_capacity(len)
+    }
+
+    fn insert(&mut self, object: &mut Self::Object, key: Self::Key, value: Self::Value) {
+        object.insert(key, value);
+    }
+
+    fn finish_object(&mut self, object: Self::Object) -> Self::Value {
+        SharedValue::from(object)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Writable for SharedValue {
+    #[inline]
+    fn encode(&self) -> String {
+        let mut g = DumpGenerator::<OwnedValue>::new();
+        let _ = write_json(&mut g, self);
+        g.consume()
+    }
+
+    #[inline]
+    fn encode_pp(&self) -> String {
+        let mut g = PrettyGenerator::<OwnedValue>::new(2);
+        let _ = write_json(&mut g, self);
+        g.consume()
+    }
+
+    #[inline]
+    fn write<'writer, W>(&self, w: &mut W) -> io::Result<()>
+    where
+        W: 'writer + Write,
+    {
+        write_json(&mut WriterGenerator::<_, OwnedValue>::new(w), self)
+    }
+
+    #[inline]
+    fn write_pp<'writer, W>(&self, w: &mut W) -> io::Result<()>
+    where
+        W: 'writer + Write,
+    {
+        write_json(&mut PrettyWriterGenerator::<_, OwnedValue>::new(w, 2), self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let input = r#"{"a":[1,2.5,"x",null,true],"b":{"c":{}},"d":[]}"#;
+        let mut d = input.as_bytes().to_vec();
+        let owned = crate::to_owned_value(&mut d).expect("failed to parse");
+        let shared = owned.clone().into_shared();
+        assert_eq!(shared, owned);
+        assert_eq!(shared.to_owned_value(), owned);
+        assert_eq!(OwnedValue::from(&shared), owned);
+        assert_eq!(shared.encode(), owned.encode());
+        assert_eq!(shared.encode_pp(), owned.encode_pp());
+        let mut out = Vec::new();
+        shared.write(&mut out).expect("failed to write");
+        assert_eq!(out, owned.encode().into_bytes());
+        assert_eq!(shared.get_idx(0), None);
+        assert_eq!(
+            shared
+                .get("a")
+                .and_then(|a| a.get_idx(2))
+                .and_then(SharedValue::as_str),
+            Some("x")
+        );
+        assert_eq!(
+            shared
+                .get("b")
+                .and_then(|b| b.get("c"))
+                .and_then(SharedValue::as_object)
+                .map(Object::len),
+            Some(0)
+        );
+        assert_eq!(
+            shared
+                .get("a")
+                .and_then(|a| a.get_idx(4))
+                .and_then(SharedValue::as_static),
+            Some(&StaticNode::Bool(true))
+        );
+        assert_ne!(shared, OwnedValue::from(json!({"a": []})));
+    }
+
+    #[test]
+    fn clones_share() {
+        let shared = json!({"k": ["big", {"nested": 1}]}).into_shared();
+        let clone = shared.clone();
+        assert!(clone.ptr_eq(&shared));
+        let k = shared.get("k").expect("missing key");
+        assert!(k.ptr_eq(clone.get("k").expect("missing key")));
+        assert!(!k.ptr_eq(&json!(["big", {"nested": 1}]).into_shared()));
+        assert_eq!(k, &json!(["big", {"nested": 1}]).into_shared());
+    }
+
+    #[test]
+    fn copy_on_write() {
+        let template = json!({"a": {"b": [1, 2]}, "c": {"d": true}}).into_shared();
+        let mut v = template.clone();
+        v.get_mut("a")
+            .and_then(|a| a.get_mut("b"))
+            .expect("missing key")
+            .push("x")
+            .expect("not an array");
+        assert_eq!(
+            v,
+            OwnedValue::from(json!({"a": {"b": [1, 2, "x"]}, "c": {"d": true}}))
+        );
+        assert_eq!(
+            template,
+            OwnedValue::from(json!({"a": {"b": [1, 2]}, "c": {"d": true}}))
+        );
+        // the untouched subtree is still shared
+        assert!(v
+            .get("c")
+            .expect("missing key")
+            .ptr_eq(template.get("c").expect("missing key")));
+        assert!(!v
+            .get("a")
+            .expect("missing key")
+            .ptr_eq(template.get("a").expect("missing key")));
+        assert_eq!(v.remove("c"), Ok(template.get("c").cloned()));
+        assert_eq!(v.remove("c"), Ok(None));
+        assert!(template.get("c").is_some());
+        *v.get_mut("a")
+            .and_then(|a| a.get_mut("b"))
+            .and_then(|b| b.get_idx_mut(0))
+            .expect("missing element") = 7_u64.into();
+        assert_eq!(v.encode(), r#"{"a":{"b":[7,2,"x"]}}"#);
+        assert_eq!(v.push(1_u64), Err(AccessError::NotAnArray));
+        let mut s = SharedValue::from("s");
+        assert_eq!(s.insert("k", true), Err(AccessError::NotAnObject));
+        assert_eq!(s.remove("k"), Err(AccessError::NotAnObject));
+        // a value nothing else holds is changed in place
+        let mut unique = SharedValue::from(vec![SharedValue::from(false)]);
+        let before = unique.as_array().map(<[_]>::as_ptr);
+        *unique.get_idx_mut(0).expect("missing element") = true.into();
+        assert_eq!(unique.as_array().map(<[_]>::as_ptr), before);
+        assert_eq!(unique.encode(), "[true]");
+    }
+
+    #[test]
+    fn parse() {
+        let input = r#"{"a":[1,"x",{"b":null,"e":"y"}],"c":{},"d":[]}"#;
+        let mut d1 = input.as_bytes().to_vec();
+        let mut d2 = input.as_bytes().to_vec();
+        let v = to_value(&mut d1).expect("failed to parse");
+        assert_eq!(v, crate::to_owned_value(&mut d2).expect("failed to parse"));
+        assert!(to_value(&mut b"[1,".to_vec()).is_err());
+        let mut pool = StringPool::new();
+        assert!(to_value_with_pool(&mut b"{\"a\"".to_vec(), &mut pool).is_err());
+    }
+
+    #[test]
+    fn pooled_strings() {
+        let mut pool = StringPool::with_max_len(4);
+        assert!(pool.is_empty());
+        let input = r#"[{"m":"GET","u":"/index"},{"m":"GET","u":"/index"},{"m":"POST"}]"#;
+        let mut d = input.as_bytes().to_vec();
+        let v = to_value_with_pool(&mut d, &mut pool).expect("failed to parse");
+        assert_eq!(v.encode(), input);
+        let field = |i, k| v.get_idx(i).and_then(|r| r.get(k)).expect("missing field");
+        assert!(field(0, "m").ptr_eq(field(1, "m")));
+        assert!(!field(0, "m").ptr_eq(field(2, "m")));
+        // too long for the pool
+        assert!(!field(0, "u").ptr_eq(field(1, "u")));
+        assert_eq!(pool.len(), 2);
+        // the pool is shared with later documents and conversions
+        let w = OwnedValue::from(json!(["POST", "PUT"])).into_shared_with_pool(&mut pool);
+        assert!(w.get_idx(0).expect("missing element").ptr_eq(field(2, "m")));
+        assert_eq!(pool.len(), 3);
+        let interned = pool.intern("GET");
+        assert_eq!(field(0, "m").as_str(), Some(&*interned));
+        pool.clear();
+        assert!(pool.is_empty());
+        assert_eq!(field(1, "m").as_str(), Some("GET"));
+    }
+
+    #[test]
+    fn shared_between_threads() {
+        let shared = json!({"list": [1, 2, 3]}).into_shared();
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let v = shared.clone();
+                std::thread::spawn(move || {
+                    v.get("list")
+                        .and_then(SharedValue::as_array)
+                        .map(<[_]>::len)
+                })
+            })
+            .collect();
+        for h in handles {
+            assert_eq!(h.join().expect("thread panicked"), Some(3));
+        }
+    }
+}
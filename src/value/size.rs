@@ -0,0 +1,178 @@
+use crate::cow::Cow;
+use crate::prelude::*;
+use crate::{BorrowedValue, OwnedValue};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::hash::Hash;
+use core::mem::size_of;
+use halfbrown::HashMap;
+
+/// Heap bytes a part of a value owns itself, without its children
+trait OwnHeap {
+    fn own_heap(&self) -> usize;
+}
+
+impl OwnHeap for String {
+    fn own_heap(&self) -> usize {
+        self.capacity()
+    }
+}
+
+#[cfg(not(feature = "beef"))]
+impl OwnHeap for Cow<'_, str> {
+    fn own_heap(&self) -> usize {
+        match self {
+            Cow::Owned(s) => s.capacity(),
+            Cow::Borrowed(_) => 0,
+        }
+    }
+}
+
+#[cfg(feature = "beef")]
+impl OwnHeap for Cow<'_, str> {
+    fn own_heap(&self) -> usize {
+        // beef doesn't tell its capacity, the length is close enough
+        if self.is_owned() {
+            self.len()
+        } else {
+            0
+        }
+    }
+}
+
+impl OwnHeap for OwnedValue {
+    fn own_heap(&self) -> usize {
+        match self {
+            Self::Static(_) => 0,
+            Self::String(s) => s.own_heap(),
+            Self::Array(a) => vec_heap(a),
+            Self::Object(o) => size_of::<HashMap<String, Self>>() + map_heap(o),
+            #[cfg(feature = "bytes")]
+            Self::Bytes(b) => vec_heap(b),
+        }
+    }
+}
+
+impl<'v> OwnHeap for BorrowedValue<'v> {
+    fn own_heap(&self) -> usize {
+        match self {
+            Self::Static(_) => 0,
+            Self::String(s) => s.own_heap(),
+            Self::Array(a) => vec_heap(a),
+            Self::Object(o) => size_of::<HashMap<Cow<'v, str>, Self>>() + map_heap(o),
+            #[cfg(feature = "bytes")]
+            Self::Bytes(alloc::borrow::Cow::Owned(b)) => vec_heap(b),
+            #[cfg(feature = "bytes")]
+            Self::Bytes(alloc::borrow::Cow::Borrowed(_)) => 0,
+        }
+    }
+}
+
+fn vec_heap<T>(vec: &Vec<T>) -> usize {
+    vec.capacity() * size_of::<T>()
+}
+
+/// The table of a map and the keys, small maps are a `Vec` of entries
+/// and larger ones a hashbrown table with a control byte per bucket
+fn map_heap<K, V>(map: &HashMap<K, V>) -> usize
+where
+    K: OwnHeap + Hash + Eq,
+{
+    let capacity = map.capacity();
+    let table = if capacity == 0 {
+        0
+    } else if map.is_vec() {
+        capacity * size_of::<(K, V)>()
+    } else {
+        // hashbrown fills buckets up to 7/8 and has a group of control
+        // bytes more than buckets
+        let buckets = (capacity * 8 / 7).next_power_of_two();
+        buckets * (size_of::<(K, V)>() + 1) + 16
+    };
+    table + map.keys().map(OwnHeap::own_heap).sum::<usize>()
+}
+
+fn size_of_children<V>(value: &V) -> usize
+where
+    V: OwnHeap + ValueTrait<Array = Vec<V>>,
+{
+    let mut size = 0;
+    let mut stack = alloc::vec![value];
+    while let Some(value) = stack.pop() {
+        size += value.own_heap();
+        if let Some(array) = value.as_array() {
+            stack.extend(array);
+        } else if let Some(object) = value.as_object() {
+            stack.extend(object.values());
+        }
+    }
+    size
+}
+
+impl OwnedValue {
+    /// Estimates the heap memory the value holds, see
+    /// `BorrowedValue::size_of_children`.
+    ///
+    /// ```rust
+    /// use simd_json::json;
+    /// let small = json!({"a": 1});
+    /// let large = json!({"a": [1, 2, 3], "b": "some longer string"});
+    /// assert!(large.size_of_children() > small.size_of_children());
+    /// ```
+    #[must_use]
+    pub fn size_of_children(&self) -> usize {
+        size_of_children(self)
+    }
+}
+
+impl BorrowedValue<'_> {
+    /// Estimates the bytes of heap memory the value holds, not counting
+    /// the value itself, so the total is `size_of_children` plus
+    /// `core::mem::size_of_val`. Counts the capacity of arrays, the
+    /// tables and keys of objects and the strings that are owned, strings
+    /// borrowed from the input are free. Allocator overhead isn't
+    /// counted, so this is a lower bound that is good for budgets.
+    ///
+    /// ```rust
+    /// let mut d = br#"{"a": "x", "b": "\n"}"#.to_vec();
+    /// let v = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// let owned = simd_json::OwnedValue::from(v.clone());
+    /// // only the escaped string had to be copied
+    /// assert!(v.size_of_children() < owned.size_of_children());
+    /// ```
+    #[must_use]
+    pub fn size_of_children(&self) -> usize {
+        size_of_children(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn sizes() {
+        let value_size = size_of::<OwnedValue>();
+        assert_eq!(json!(1).size_of_children(), 0);
+        assert_eq!(OwnedValue::from("abc".to_string()).size_of_children(), 3);
+        let mut a = Vec::with_capacity(4);
+        a.push(OwnedValue::from(String::with_capacity(10)));
+        assert_eq!(OwnedValue::from(a).size_of_children(), 4 * value_size + 10);
+        let map = size_of::<HashMap<String, OwnedValue>>();
+        let entry = size_of::<(String, OwnedValue)>();
+        let mut o = HashMap::vec_with_capacity(2);
+        o.insert_nocheck("key".to_string(), OwnedValue::from(vec![1_i64]));
+        assert_eq!(
+            OwnedValue::from(o).size_of_children(),
+            map + 2 * entry + 3 + value_size
+        );
+        let large: OwnedValue = (0..100).map(|i| (i.to_string(), i)).collect();
+        assert!(large.size_of_children() > 100 * entry);
+
+        let mut d = br#"{"key": ["a", "\t"]}"#.to_vec();
+        let b = crate::to_borrowed_value(&mut d).unwrap();
+        let owned = OwnedValue::from(b.clone());
+        assert!(b.size_of_children() < owned.size_of_children());
+    }
+}
@@ -0,0 +1,94 @@
+use crate::{BorrowedValue, OwnedValue};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::hash::Hash;
+use halfbrown::HashMap;
+
+impl OwnedValue {
+    /// Sorts the keys of all objects in the value, see
+    /// `BorrowedValue::sort_keys`.
+    ///
+    /// ```rust
+    /// use simd_json::prelude::*;
+    /// let mut v = simd_json::json!({"b": 1, "a": [{"d": 2, "c": 3}]});
+    /// v.sort_keys();
+    /// assert_eq!(v.encode(), r#"{"a":[{"c":3,"d":2}],"b":1}"#);
+    /// ```
+    pub fn sort_keys(&mut self) {
+        match self {
+            Self::Object(o) => sort_object(o, Self::sort_keys),
+            Self::Array(a) => a.iter_mut().for_each(Self::sort_keys),
+            Self::Static(_) | Self::String(_) => {}
+            #[cfg(feature = "bytes")]
+            Self::Bytes(_) => {}
+        }
+    }
+}
+
+impl BorrowedValue<'_> {
+    /// Sorts the keys of all objects in the value by their bytes, nested
+    /// ones included, so documents with the same content serialize the
+    /// same. This normalizes documents before they are diffed or stored.
+    ///
+    /// Objects keep their order only as long as they stay lists of
+    /// entries: sorted objects are turned into one, which makes looking
+    /// up a key a scan, and inserting into one with more than 32 keys
+    /// turns it back into a hash map without an order.
+    ///
+    /// ```rust
+    /// use simd_json::prelude::*;
+    /// let mut d = br#"{"b": 1, "a": {"d": 2, "c": 3}}"#.to_vec();
+    /// let mut v = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// v.sort_keys();
+    /// assert_eq!(v.encode(), r#"{"a":{"c":3,"d":2},"b":1}"#);
+    /// ```
+    pub fn sort_keys(&mut self) {
+        match self {
+            Self::Object(o) => sort_object(o, Self::sort_keys),
+            Self::Array(a) => a.iter_mut().for_each(Self::sort_keys),
+            Self::Static(_) | Self::String(_) => {}
+            #[cfg(feature = "bytes")]
+            Self::Bytes(_) => {}
+        }
+    }
+}
+
+/// Rebuilds `object` as a list of entries sorted by key, calling `f` on
+/// every value
+fn sort_object<K, V>(object: &mut HashMap<K, V>, f: impl Fn(&mut V))
+where
+    K: Borrow<str> + Hash + Eq,
+{
+    let mut entries: Vec<(K, V)> = object.drain().collect();
+    entries.sort_by(|(k1, _), (k2, _)| k1.borrow().cmp(k2.borrow()));
+    let mut sorted = HashMap::vec_with_capacity(entries.len());
+    for (k, mut v) in entries {
+        f(&mut v);
+        sorted.insert_nocheck(k, v);
+    }
+    *object = sorted;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn large_objects() {
+        let mut v: OwnedValue = (0..100).rev().map(|i| (i.to_string(), i)).collect();
+        v.sort_keys();
+        let keys: Vec<&str> = match &v {
+            OwnedValue::Object(o) => o.keys().map(String::as_str).collect(),
+            _ => Vec::new(),
+        };
+        let mut expected: Vec<String> = (0..100).map(|i| i.to_string()).collect();
+        expected.sort();
+        assert_eq!(keys, expected);
+        assert_eq!(v["42"], 42);
+        let mut b = BorrowedValue::from(json!({"é": 1, "z": 2, "Z": 3}));
+        b.sort_keys();
+        assert_eq!(b.encode(), r#"{"Z":3,"z":2,"é":1}"#);
+    }
+}
@@ -0,0 +1,418 @@
+use super::borrowed::{Object, Value};
+use super::build::{build, dismantle, Build, Nested};
+use crate::{Deserializer, Node, ParseOptions, Result, StaticNode};
+use alloc::vec::{self, Vec};
+use core::ops::Range;
+
+/// A byte range in the input, `end` is exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Span {
+    /// Offset of the first byte
+    pub start: usize,
+    /// Offset one past the last byte
+    pub end: usize,
+}
+
+impl Span {
+    /// The span as a range that can be used to index the input
+    #[must_use]
+    pub fn range(self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+/// Parses a slice of bytes into a spanned dom. Like with the borrowed
+/// dom strings are de-escaped in place and referenced from the slice.
+///
+/// # Errors
+///
+/// Will return `Err` if `s` is invalid JSON.
+pub fn to_value(s: &mut [u8]) -> Result<SpannedValue<'_>> {
+    to_value_with_options(s, ParseOptions::default())
+}
+
+/// Parses a slice of bytes into a spanned dom using the given parse
+/// `options`, see `to_value` for details. Lossy UTF-8 mode is ignored
+/// as repaired input would no longer line up with the spans.
+///
+/// # Errors
+///
+/// Will return `Err` if `s` is invalid JSON.
+pub fn to_value_with_options(s: &mut [u8], options: ParseOptions) -> Result<SpannedValue<'_>> {
+    let (de, spans) = Deserializer::from_slice_with_spans(s, options)?;
+    Ok(build(&mut SpannedDeserializer { de, spans, idx: 0 }))
+}
+
+/// A JSON value along with the span of the input it was parsed from
+///
+/// ```rust
+/// use simd_json::{to_spanned_value, Span};
+/// let mut d = br#"{"name": "simd", "tags": [1, 2]}"#.to_vec();
+/// let v = to_spanned_value(&mut d).unwrap();
+/// assert_eq!(v.span(), Span { start: 0, end: 32 });
+/// assert_eq!(v.get("name").unwrap().span(), Span { start: 9, end: 15 });
+/// assert_eq!(v.key_span("tags"), Some(Span { start: 17, end: 23 }));
+/// assert_eq!(v.get("tags").unwrap().get_idx(1).unwrap().span(), Span { start: 29, end: 30 });
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpannedValue<'v> {
+    /// Static values
+    Static(StaticNode, Span),
+    /// string type
+    String(&'v str, Span),
+    /// array type
+    Array(Vec<SpannedValue<'v>>, Span),
+    /// object type, entries are kept in input order
+    Object(Vec<SpannedEntry<'v>>, Span),
+}
+
+/// A key value pair of a spanned object
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedEntry<'v> {
+    /// The key
+    pub key: &'v str,
+    /// Span of the key including its quotes
+    pub key_span: Span,
+    /// The value
+    pub value: SpannedValue<'v>,
+}
+
+impl<'v> SpannedValue<'v> {
+    /// The span of the input this value was parsed from
+    #[must_use]
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Static(_, span)
+            | Self::String(_, span)
+            | Self::Array(_, span)
+            | Self::Object(_, span) => *span,
+        }
+    }
+
+    /// Gets the value of `key` if this is an object, for duplicate keys
+    /// the first one wins just like in the other doms
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&Self> {
+        self.entry(key).map(|e| &e.value)
+    }
+
+    /// Gets the span of `key` if this is an object
+    #[must_use]
+    pub fn key_span(&self, key: &str) -> Option<Span> {
+        self.entry(key).map(|e| e.key_span)
+    }
+
+    /// Gets the element at `idx` if this is an array
+    #[must_use]
+    pub fn get_idx(&self, idx: usize) -> Option<&Self> {
+        if let Self::Array(a, _) = self {
+            a.get(idx)
+        } else {
+            None
+        }
+    }
+
+    /// Drops the value without recursing into it, see
+    /// `OwnedValue::dismantle`.
+    pub fn dismantle(self) {
+        dismantle(self);
+    }
+
+    /// Drops the spans and turns this into a borrowed value. Like `build`
+    /// we keep the containers that are being converted on a stack instead
+    /// of recursing.
+    #[must_use]
+    pub fn into_value(self) -> Value<'v> {
+        let mut stack: Vec<Frame<'v>> = Vec::new();
+        let mut next = self;
+        loop {
+            let mut value = match next {
+                Self::Static(s, _) => Value::Static(s),
+                Self::String(s, _) => Value::from(s),
+                Self::Array(a, _) => {
+                    let res = Vec::with_capacity(a.len());
+                    let mut todo = a.into_iter();
+                    match todo.next() {
+                        Some(first) => {
+                            stack.push(Frame::Array(res, todo));
+                            next = first;
+                            continue;
+                        }
+                        None => Value::Array(res),
+                    }
+                }
+                Self::Object(o, _) => {
+                    let res = Object::with_capacity(o.len());
+                    let mut todo = o.into_iter();
+                    match todo.next() {
+                        Some(first) => {
+                            stack.push(Frame::Object(res, todo, first.key));
+                            next = first.value;
+                            continue;
+                        }
+                        None => Value::from(res),
+                    }
+                }
+            };
+            // Hand the value to its parent, every parent that is complete
+            // with it becomes the value for its own parent in turn.
+            loop {
+                match stack.last_mut() {
+                    None => return value,
+                    Some(Frame::Array(res, todo)) => {
+                        res.push(value);
+                        if let Some(child) = todo.next() {
+                            next = child;
+                            break;
+                        }
+                    }
+                    Some(Frame::Object(res, todo, key)) => {
+                        // keeps duplicate keys like `to_borrowed_value` does
+                        res.insert_nocheck((*key).into(), value);
+                        if let Some(entry) = todo.next() {
+                            *key = entry.key;
+                            next = entry.value;
+                            break;
+                        }
+                    }
+                }
+                value = match stack.pop() {
+                    Some(Frame::Array(res, _)) => Value::Array(res),
+                    Some(Frame::Object(res, _, _)) => Value::from(res),
+                    None => unreachable!(),
+                };
+            }
+        }
+    }
+
+    fn entry(&self, key: &str) -> Option<&SpannedEntry<'v>> {
+        if let Self::Object(o, _) = self {
+            o.iter().find(|e| e.key == key)
+        } else {
+            None
+        }
+    }
+}
+
+impl Nested for SpannedValue<'_> {
+    fn take_children(&mut self, out: &mut Vec<Self>) {
+        match self {
+            Self::Array(a, _) => out.append(a),
+            Self::Object(o, _) => out.extend(o.drain(..).map(|e| e.value)),
+            _ => (),
+        }
+    }
+}
+
+/// A container `SpannedValue::into_value` is still converting, with the
+/// children that are left. Objects also keep the key of the value that
+/// is being converted.
+enum Frame<'v> {
+    Array(Vec<Value<'v>>, vec::IntoIter<SpannedValue<'v>>),
+    Object(Object<'v>, vec::IntoIter<SpannedEntry<'v>>, &'v str),
+}
+
+struct SpannedDeserializer<'de> {
+    de: Deserializer<'de>,
+    spans: Vec<Span>,
+    // index into the spans, 0 is the root element we skip
+    idx: usize,
+}
+
+impl SpannedDeserializer<'_> {
+    // The tape holds one node per value and key, the structural indexes
+    // additionally hold the `:`, `,` and closing brackets so we step
+    // through both in lock step.
+    fn next_span(&mut self) -> Span {
+        self.idx += 1;
+        self.spans[self.idx]
+    }
+
+    /// The span from `start` to the closing bracket we are at. Empty
+    /// containers haven't stepped over it yet.
+    fn close(&mut self, start: usize, empty: bool) -> Span {
+        if empty {
+            self.next_span();
+        }
+        let end = self.spans[self.idx].start + 1;
+        Span { start, end }
+    }
+}
+
+impl<'de> Build<'de> for SpannedDeserializer<'de> {
+    type Value = SpannedValue<'de>;
+    type Key = (&'de str, Span);
+    // the containers so far and the start of their span
+    type Array = (Vec<SpannedValue<'de>>, usize);
+    type Object = (Vec<SpannedEntry<'de>>, usize);
+
+    fn next_node(&mut self) -> Node<'de> {
+        self.de.next_()
+    }
+
+    fn static_node(&mut self, s: StaticNode) -> Self::Value {
+        SpannedValue::Static(s, self.next_span())
+    }
+
+    fn string(&mut self, s: &'de str) -> Self::Value {
+        SpannedValue::String(s, self.next_span())
+    }
+
+    fn key(&mut self, s: &'de str) -> Self::Key {
+        let key_span = self.next_span();
+        // `:`
+        self.next_span();
+        (s, key_span)
+    }
+
+    fn array(&mut self, len: usize) -> Self::Array {
+        (Vec::with_capacity(len), self.next_span().start)
+    }
+
+    fn push(&mut self, array: &mut Self::Array, value: Self::Value) {
+        array.0.push(value);
+        // `,` or `]`
+        self.next_span();
+    }
+
+    fn finish_array(&mut self, (array, start): Self::Array) -> Self::Value {
+        let span = self.close(start, array.is_empty());
+        SpannedValue::Array(array, span)
+    }
+
+    fn object(&mut self, len: usize) -> Self::Object {
+        (Vec::with_capacity(len), self.next_span().start)
+    }
+
+    fn insert(
+        &mut self,
+        object: &mut Self::Object,
+        (key, key_span): Self::Key,
+        value: Self::Value,
+    ) {
+        object.0.push(SpannedEntry {
+            key,
+            key_span,
+            value,
+        });
+        // `,` or `}`
+        self.next_span();
+    }
+
+    fn finish_object(&mut self, (object, start): Self::Object) -> Self::Value {
+        let span = self.close(start, object.is_empty());
+        SpannedValue::Object(object, span)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+
+    fn span_of(input: &str, span: Span) -> &str {
+        &input[span.range()]
+    }
+
+    #[test]
+    fn spans() {
+        let input = "\n [ 1 , \"two\" ,{ \"a\" : null , \"b\":[ ] } , -2.5e1 ]  \n";
+        let mut d = input.as_bytes().to_vec();
+        let v = to_value(&mut d).expect("failed to parse");
+        assert_eq!(
+            span_of(input, v.span()),
+            "[ 1 , \"two\" ,{ \"a\" : null , \"b\":[ ] } , -2.5e1 ]"
+        );
+        assert_eq!(
+            span_of(input, v.get_idx(0).expect("missing element").span()),
+            "1"
+        );
+        assert_eq!(
+            span_of(input, v.get_idx(1).expect("missing element").span()),
+            "\"two\""
+        );
+        let o = v.get_idx(2).expect("missing element");
+        assert_eq!(span_of(input, o.span()), "{ \"a\" : null , \"b\":[ ] }");
+        assert_eq!(
+            span_of(input, o.key_span("a").expect("missing key")),
+            "\"a\""
+        );
+        assert_eq!(
+            span_of(input, o.get("a").expect("missing key").span()),
+            "null"
+        );
+        assert_eq!(
+            span_of(input, o.key_span("b").expect("missing key")),
+            "\"b\""
+        );
+        assert_eq!(
+            span_of(input, o.get("b").expect("missing key").span()),
+            "[ ]"
+        );
+        assert_eq!(
+            span_of(input, v.get_idx(3).expect("missing element").span()),
+            "-2.5e1"
+        );
+        assert_eq!(v.get_idx(4), None);
+
+        let expected = json!([1, "two", {"a": null, "b": []}, -25.0]);
+        assert_eq!(v.into_value(), expected);
+    }
+
+    #[test]
+    fn scalar_root() {
+        let input = "  \"snow \\\" man\"  ";
+        let mut d = input.as_bytes().to_vec();
+        let v = to_value(&mut d).expect("failed to parse");
+        assert_eq!(span_of(input, v.span()), "\"snow \\\" man\"");
+        assert_eq!(
+            v,
+            SpannedValue::String("snow \" man", Span { start: 2, end: 15 })
+        );
+    }
+
+    #[test]
+    fn deep_nesting() {
+        use crate::value::deep::{innermost, nested, DEPTH};
+        let input = nested(DEPTH, "null");
+        let mut d = input.as_bytes().to_vec();
+        let v = to_value(&mut d).expect("failed to parse");
+        let end = input.len();
+        assert_eq!(v.span(), Span { start: 0, end });
+        let (null, _) = innermost(&v, |v| v.get_idx(0).or_else(|| v.get("a")));
+        assert_eq!(span_of(&input, null.span()), "null");
+        let v = v.into_value();
+        let (_, depth) = innermost(&v, |v| v.get_idx(0).or_else(|| v.get("a")));
+        assert_eq!(depth, DEPTH * 2 + 1);
+        v.dismantle();
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let input = r#"{"a": 1, "a": 2}"#;
+        let mut d = input.as_bytes().to_vec();
+        let v = to_value(&mut d).expect("failed to parse");
+        assert_eq!(span_of(input, v.get("a").expect("missing key").span()), "1");
+        assert_eq!(v.key_span("a"), Some(Span { start: 1, end: 4 }));
+        assert_eq!(v.into_value()["a"], 1);
+    }
+
+    #[test]
+    fn byte_order_mark() {
+        let input = "\u{feff}{\"a\": [1, \"b\"]}";
+        let mut d = input.as_bytes().to_vec();
+        let options = ParseOptions::default().skip_bom(true);
+        let v = to_value_with_options(&mut d, options).expect("failed to parse");
+        assert_eq!(span_of(input, v.span()), "{\"a\": [1, \"b\"]}");
+        assert_eq!(
+            span_of(input, v.key_span("a").expect("missing key")),
+            "\"a\""
+        );
+        let a = v.get("a").expect("missing key");
+        assert_eq!(span_of(input, a.span()), "[1, \"b\"]");
+        assert_eq!(
+            span_of(input, a.get_idx(1).expect("missing element").span()),
+            "\"b\""
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use crate::prelude::*;
+use crate::{BorrowedValue, OwnedValue};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::max;
+
+/// Statistics about the shape of a value, from `stats`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ValueStats {
+    /// Number of `null`s
+    pub nulls: usize,
+    /// Number of booleans
+    pub bools: usize,
+    /// Number of integers
+    pub integers: usize,
+    /// Number of floats
+    pub floats: usize,
+    /// Number of strings, keys aren't counted
+    pub strings: usize,
+    /// Number of arrays
+    pub arrays: usize,
+    /// Number of objects
+    pub objects: usize,
+    /// How deep arrays and objects are nested, `0` for a value that is
+    /// neither and `1` for one that holds no arrays or objects
+    pub max_depth: usize,
+    /// Bytes of all strings
+    pub string_bytes: usize,
+    /// Bytes of all keys of objects
+    pub key_bytes: usize,
+    /// Length of the longest array
+    pub max_array_len: usize,
+    /// Number of entries of the largest object
+    pub max_object_len: usize,
+}
+
+impl ValueStats {
+    /// Number of values, counting everything in arrays and objects and
+    /// the value itself
+    #[must_use]
+    pub fn nodes(&self) -> usize {
+        self.nulls
+            + self.bools
+            + self.integers
+            + self.floats
+            + self.strings
+            + self.arrays
+            + self.objects
+    }
+}
+
+impl OwnedValue {
+    /// Collects statistics about the value, see `BorrowedValue::stats`.
+    ///
+    /// ```rust
+    /// use simd_json::json;
+    /// let stats = json!({"a": [1, 2.5, "xy"], "b": null}).stats();
+    /// assert_eq!(stats.nodes(), 6);
+    /// assert_eq!(stats.max_depth, 2);
+    /// assert_eq!(stats.string_bytes, 2);
+    /// assert_eq!(stats.max_array_len, 3);
+    /// ```
+    #[must_use]
+    pub fn stats(&self) -> ValueStats {
+        stats(self)
+    }
+}
+
+impl BorrowedValue<'_> {
+    /// Collects statistics about the value and everything in it, like how
+    /// many values of each type there are, how deep it is nested and the
+    /// largest arrays and objects. Useful to understand payloads and to
+    /// reject pathological ones, the traversal doesn't recurse so it
+    /// handles any depth.
+    ///
+    /// ```rust
+    /// let mut d = br#"[{"id": 1, "ok": true}, {"id": 2, "ok": false}]"#.to_vec();
+    /// let v = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// let stats = v.stats();
+    /// assert_eq!((stats.arrays, stats.objects, stats.bools), (1, 2, 2));
+    /// assert_eq!(stats.key_bytes, 8);
+    /// assert_eq!(stats.max_object_len, 2);
+    /// ```
+    #[must_use]
+    pub fn stats(&self) -> ValueStats {
+        stats(self)
+    }
+}
+
+fn stats<V>(value: &V) -> ValueStats
+where
+    V: ValueTrait,
+    V::Key: Borrow<str>,
+{
+    let mut stats = ValueStats::default();
+    // values with how many arrays and objects they are in
+    let mut stack: Vec<(&V, usize)> = alloc::vec![(value, 0)];
+    while let Some((value, depth)) = stack.pop() {
+        if let Some(array) = value.as_array() {
+            stats.arrays += 1;
+            stats.max_depth = max(stats.max_depth, depth + 1);
+            stats.max_array_len = max(stats.max_array_len, array.len());
+            stack.extend(array.iter().map(|v| (v, depth + 1)));
+        } else if let Some(object) = value.as_object() {
+            stats.objects += 1;
+            stats.max_depth = max(stats.max_depth, depth + 1);
+            stats.max_object_len = max(stats.max_object_len, object.len());
+            for (key, v) in object.iter() {
+                let key: &str = key.borrow();
+                stats.key_bytes += key.len();
+                stack.push((v, depth + 1));
+            }
+        } else if let Some(s) = value.as_str() {
+            stats.strings += 1;
+            stats.string_bytes += s.len();
+        } else if value.is_null() {
+            stats.nulls += 1;
+        } else if value.is_bool() {
+            stats.bools += 1;
+        } else if value.is_float() {
+            stats.floats += 1;
+        } else if value.is_integer() {
+            stats.integers += 1;
+        }
+    }
+    stats
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn counts() {
+        let v = json!({
+            "a": [[[]], {}, "abc", "", 1, -1, 1.5, true, null],
+            "bc": {"d": {"e": null}}
+        });
+        let expected = ValueStats {
+            nulls: 2,
+            bools: 1,
+            integers: 2,
+            floats: 1,
+            strings: 2,
+            arrays: 3,
+            objects: 4,
+            max_depth: 4,
+            string_bytes: 3,
+            key_bytes: 5,
+            max_array_len: 9,
+            max_object_len: 2,
+        };
+        assert_eq!(v.stats(), expected);
+        assert_eq!(BorrowedValue::from(v).stats(), expected);
+        assert_eq!(expected.nodes(), 15);
+        assert_eq!(json!(1).stats().max_depth, 0);
+        assert_eq!(json!([]).stats().max_depth, 1);
+    }
+}
@@ -1,5 +1,8 @@
 /// A tape of a parsed json, all values are extracted and validated and
 /// can be used without further computation.
+use crate::{Error, ErrorType, Result};
+use alloc::vec::Vec;
+use core::convert::TryFrom;
 use value_trait::StaticNode;
 /// `Tape`
 pub struct Tape<'input>(Vec<Node<'input>>);
@@ -22,6 +25,279 @@ pub enum Node<'input> {
     Static(StaticNode),
 }
 
+/// Start of every snapshot, the last byte is the version of the format
+const MAGIC: &[u8; 4] = b"SJT\x01";
+
+const TAG_NULL: u8 = 0;
+const TAG_FALSE: u8 = 1;
+const TAG_TRUE: u8 = 2;
+const TAG_I64: u8 = 3;
+const TAG_U64: u8 = 4;
+const TAG_F64: u8 = 5;
+#[cfg(feature = "128bit")]
+const TAG_I128: u8 = 6;
+#[cfg(feature = "128bit")]
+const TAG_U128: u8 = 7;
+const TAG_STRING: u8 = 8;
+const TAG_ARRAY: u8 = 9;
+const TAG_OBJECT: u8 = 10;
+
+impl<'input> Tape<'input> {
+    /// The nodes of the tape
+    #[must_use]
+    pub fn as_nodes(&self) -> &[Node<'input>] {
+        &self.0
+    }
+
+    /// Turns the tape back into its nodes
+    #[must_use]
+    pub fn into_nodes(self) -> Vec<Node<'input>> {
+        self.0
+    }
+
+    /// Writes the tape, its nodes and their strings, into a snapshot
+    /// that `from_bytes` reads back, so a document parsed once can be
+    /// queried again later or in another process without parsing it
+    /// again. Numbers are stored little endian, snapshots can be moved
+    /// between machines.
+    ///
+    /// ```rust
+    /// use simd_json::{project_tape_column, to_tape, Tape};
+    /// let mut d = br#"[{"t": 1.5}, {"t": 2}]"#.to_vec();
+    /// let bytes = Tape::from(to_tape(&mut d).unwrap()).to_bytes();
+    /// let tape = Tape::from_bytes(&bytes).unwrap();
+    /// assert_eq!(project_tape_column(tape.as_nodes(), "/*/t"), [Some(1.5), Some(2.0)]);
+    /// ```
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(MAGIC.len() + 8 + self.0.len() * 9);
+        out.extend_from_slice(MAGIC);
+        write_usize(&mut out, self.0.len());
+        for node in &self.0 {
+            match *node {
+                Node::Static(StaticNode::Null) => out.push(TAG_NULL),
+                Node::Static(StaticNode::Bool(false)) => out.push(TAG_FALSE),
+                Node::Static(StaticNode::Bool(true)) => out.push(TAG_TRUE),
+                Node::Static(StaticNode::I64(i)) => {
+                    out.push(TAG_I64);
+                    out.extend_from_slice(&i.to_le_bytes());
+                }
+                Node::Static(StaticNode::U64(u)) => {
+                    out.push(TAG_U64);
+                    out.extend_from_slice(&u.to_le_bytes());
+                }
+                Node::Static(StaticNode::F64(f)) => {
+                    out.push(TAG_F64);
+                    out.extend_from_slice(&f.to_bits().to_le_bytes());
+                }
+                #[cfg(feature = "128bit")]
+                Node::Static(StaticNode::I128(i)) => {
+                    out.push(TAG_I128);
+                    out.extend_from_slice(&i.to_le_bytes());
+                }
+                #[cfg(feature = "128bit")]
+                Node::Static(StaticNode::U128(u)) => {
+                    out.push(TAG_U128);
+                    out.extend_from_slice(&u.to_le_bytes());
+                }
+                Node::String(s) => {
+                    out.push(TAG_STRING);
+                    write_usize(&mut out, s.len());
+                    out.extend_from_slice(s.as_bytes());
+                }
+                Node::Array(len, end) => {
+                    out.push(TAG_ARRAY);
+                    write_usize(&mut out, len);
+                    write_usize(&mut out, end);
+                }
+                Node::Object(len, end) => {
+                    out.push(TAG_OBJECT);
+                    write_usize(&mut out, len);
+                    write_usize(&mut out, end);
+                }
+            }
+        }
+        out
+    }
+
+    /// Reads a snapshot written by `to_bytes`, the strings of the tape
+    /// point into `bytes`. The snapshot is checked to be a tape the
+    /// parser could have made, so a corrupted one is an error and not a
+    /// panic later on.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `bytes` is cut off, or isn't a snapshot of a
+    /// tape of this version of the format.
+    pub fn from_bytes(bytes: &'input [u8]) -> Result<Self> {
+        let mut r = SnapshotReader { bytes, idx: 0 };
+        if r.take(MAGIC.len())? != MAGIC {
+            return Err(Error::new(0, bytes[0] as char, ErrorType::InvalidSnapshot));
+        }
+        let count_at = r.idx;
+        let count = r.usize()?;
+        // the parser never makes an empty tape
+        if count == 0 {
+            return Err(Error::new(count_at, '\0', ErrorType::InvalidSnapshot));
+        }
+        // every node takes at least one byte, this keeps a corrupted
+        // count from allocating more than the snapshot could hold
+        let capacity = count.min(bytes.len() - r.idx);
+        let mut nodes = Vec::with_capacity(capacity);
+        // where each node starts, to report invalid ones by their offset
+        let mut starts = Vec::with_capacity(capacity);
+        for _ in 0..count {
+            let start = r.idx;
+            let node = match r.take(1)?[0] {
+                TAG_NULL => Node::Static(StaticNode::Null),
+                TAG_FALSE => Node::Static(StaticNode::Bool(false)),
+                TAG_TRUE => Node::Static(StaticNode::Bool(true)),
+                TAG_I64 => Node::Static(StaticNode::I64(i64::from_le_bytes(r.array()?))),
+                TAG_U64 => Node::Static(StaticNode::U64(u64::from_le_bytes(r.array()?))),
+                TAG_F64 => Node::Static(StaticNode::F64(f64::from_bits(u64::from_le_bytes(
+                    r.array()?,
+                )))),
+                #[cfg(feature = "128bit")]
+                TAG_I128 => Node::Static(StaticNode::I128(i128::from_le_bytes(r.array()?))),
+                #[cfg(feature = "128bit")]
+                TAG_U128 => Node::Static(StaticNode::U128(u128::from_le_bytes(r.array()?))),
+                TAG_STRING => {
+                    let len = r.usize()?;
+                    let at = r.idx;
+                    let s = r.take(len)?;
+                    Node::String(core::str::from_utf8(s).map_err(|e| {
+                        let idx = at + e.valid_up_to();
+                        Error::new(idx, s[e.valid_up_to()] as char, ErrorType::InvalidUTF8)
+                    })?)
+                }
+                TAG_ARRAY => Node::Array(r.usize()?, r.usize()?),
+                TAG_OBJECT => Node::Object(r.usize()?, r.usize()?),
+                tag => return Err(Error::new(start, tag as char, ErrorType::InvalidSnapshot)),
+            };
+            nodes.push(node);
+            starts.push(start);
+        }
+        if r.idx != bytes.len() {
+            return Err(Error::new(
+                r.idx,
+                bytes[r.idx] as char,
+                ErrorType::InvalidSnapshot,
+            ));
+        }
+        if let Some(idx) = first_invalid_node(&nodes) {
+            // a missing node is missing at the end of the snapshot
+            return Err(match starts.get(idx) {
+                Some(&at) => Error::new(at, bytes[at] as char, ErrorType::InvalidSnapshot),
+                None => Error::new(bytes.len(), '\0', ErrorType::InvalidSnapshot),
+            });
+        }
+        Ok(Self(nodes))
+    }
+}
+
+impl<'input> From<Vec<Node<'input>>> for Tape<'input> {
+    fn from(nodes: Vec<Node<'input>>) -> Self {
+        Self(nodes)
+    }
+}
+
+fn write_usize(out: &mut Vec<u8>, n: usize) {
+    out.extend_from_slice(&(n as u64).to_le_bytes());
+}
+
+/// Reads the parts of a snapshot one after the other
+struct SnapshotReader<'input> {
+    bytes: &'input [u8],
+    idx: usize,
+}
+
+impl<'input> SnapshotReader<'input> {
+    fn take(&mut self, len: usize) -> Result<&'input [u8]> {
+        match self.idx.checked_add(len) {
+            Some(end) if end <= self.bytes.len() => {
+                let res = &self.bytes[self.idx..end];
+                self.idx = end;
+                Ok(res)
+            }
+            _ => Err(Error::new(self.bytes.len(), '\0', ErrorType::EarlyEnd)),
+        }
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut res = [0; N];
+        res.copy_from_slice(self.take(N)?);
+        Ok(res)
+    }
+
+    fn usize(&mut self) -> Result<usize> {
+        let at = self.idx;
+        usize::try_from(u64::from_le_bytes(self.array()?))
+            .map_err(|_| Error::new(at, '\0', ErrorType::InvalidSnapshot))
+    }
+}
+
+/// The index of the first node that doesn't fit the structure around
+/// it: arrays and objects have to hold as many values as they say and
+/// end where they say, and every key has to be a string.
+fn first_invalid_node(nodes: &[Node]) -> Option<usize> {
+    /// A container we are in
+    struct Open {
+        end: usize,
+        missing: usize,
+        object: bool,
+        key_next: bool,
+    }
+    let mut stack: Vec<Open> = Vec::new();
+    for (idx, node) in nodes.iter().enumerate() {
+        if let Some(open) = stack.last_mut() {
+            if idx >= open.end {
+                return Some(idx);
+            }
+            if open.key_next {
+                if !matches!(node, Node::String(_)) {
+                    return Some(idx);
+                }
+                open.key_next = false;
+                continue;
+            }
+            open.missing -= 1;
+        }
+        if let Node::Array(len, end) | Node::Object(len, end) = *node {
+            let parent_end = stack.last().map_or(nodes.len(), |open| open.end);
+            if end <= idx || end > parent_end || (len == 0 && end != idx + 1) {
+                return Some(idx);
+            }
+            if len > 0 {
+                let object = matches!(node, Node::Object(..));
+                stack.push(Open {
+                    end,
+                    missing: len,
+                    object,
+                    key_next: object,
+                });
+                continue;
+            }
+        }
+        // the value is complete, so is every container it was the last
+        // value of
+        while let Some(open) = stack.last_mut() {
+            if open.missing > 0 {
+                open.key_next = open.object;
+                break;
+            }
+            if open.end != idx + 1 {
+                return Some(idx);
+            }
+            stack.pop();
+        }
+    }
+    if stack.is_empty() {
+        None
+    } else {
+        Some(nodes.len())
+    }
+}
+
 #[cfg(test)]
 mod test {
     #![allow(clippy::cognitive_complexity)]
@@ -352,13 +628,13 @@ mod test {
 
     #[test]
     fn conversions_f64() {
-        let v = Value::from(std::f64::MAX);
+        let v = Value::from(f64::MAX);
         assert!(!v.is_i64());
         assert!(!v.is_u64());
         assert!(v.is_f64());
         assert!(!v.is_f32());
         assert!(v.is_f64_castable());
-        let v = Value::from(std::f64::MIN);
+        let v = Value::from(f64::MIN);
         assert!(!v.is_i64());
         assert!(!v.is_u64());
         assert!(v.is_f64());
@@ -370,13 +646,13 @@ mod test {
 
     #[test]
     fn conversions_f32() {
-        let v = Value::from(std::f32::MAX);
+        let v = Value::from(f32::MAX);
         assert!(!v.is_i64());
         assert!(!v.is_u64());
         assert!(v.is_f64());
         assert!(v.is_f32());
         assert!(v.is_f64_castable());
-        let v = Value::from(std::f32::MIN);
+        let v = Value::from(f32::MIN);
         assert!(!v.is_i64());
         assert!(!v.is_u64());
         assert!(v.is_f64());
@@ -487,4 +763,111 @@ mod test {
         let v: Value = false.into();
         assert_eq!(v, false);
     }
+
+    #[test]
+    fn snapshot_round_trip() {
+        let input = r#"[{"a":[1,-2,3.5,"x\"y"],"b":{},"c":[]},null,true,false,"snow \u2603"]"#;
+        let mut d = input.as_bytes().to_vec();
+        let tape = Tape::from(crate::to_tape(&mut d).expect("failed to parse"));
+        let bytes = tape.to_bytes();
+        let read = Tape::from_bytes(&bytes).expect("failed to read snapshot");
+        assert_eq!(read.as_nodes(), tape.as_nodes());
+        assert_eq!(read.into_nodes().len(), tape.as_nodes().len());
+        let mut d = br#"{"k":1}"#.to_vec();
+        let tape = Tape::from(crate::to_tape(&mut d).expect("failed to parse"));
+        assert_eq!(
+            Tape::from_bytes(&tape.to_bytes())
+                .expect("failed to read snapshot")
+                .as_nodes(),
+            tape.as_nodes()
+        );
+    }
+
+    #[cfg(feature = "128bit")]
+    #[test]
+    fn snapshot_128bit() {
+        let tape = Tape::from(vec![
+            Node::Array(2, 3),
+            Node::Static(StaticNode::I128(i128::MIN)),
+            Node::Static(StaticNode::U128(u128::MAX)),
+        ]);
+        let bytes = tape.to_bytes();
+        assert_eq!(
+            Tape::from_bytes(&bytes)
+                .expect("failed to read snapshot")
+                .as_nodes(),
+            tape.as_nodes()
+        );
+    }
+
+    #[test]
+    fn snapshot_errors() {
+        let invalid = |bytes: &[u8]| {
+            Tape::from_bytes(bytes)
+                .err()
+                .filter(|e| e.error() == &ErrorType::InvalidSnapshot)
+                .map(|e| e.index())
+        };
+        let mut d = br#"[{"a":"b"},2]"#.to_vec();
+        let bytes = Tape::from(crate::to_tape(&mut d).expect("failed to parse")).to_bytes();
+        // cut off anywhere
+        for len in 0..bytes.len() {
+            let err = Tape::from_bytes(&bytes[..len])
+                .err()
+                .expect("accepted a cut off snapshot");
+            assert_eq!(err.error(), &ErrorType::EarlyEnd);
+        }
+        // another format or version
+        let mut other = bytes.clone();
+        other[3] = 2;
+        assert_eq!(invalid(&other), Some(0));
+        // no nodes at all, at the node count
+        assert_eq!(invalid(&Tape::from(Vec::new()).to_bytes()), Some(4));
+        // trailing bytes
+        let mut other = bytes.clone();
+        other.push(0);
+        assert_eq!(invalid(&other), Some(bytes.len()));
+        // an unknown tag, after the magic and the node count
+        let mut other = Tape::from(vec![Node::Static(StaticNode::Null)]).to_bytes();
+        other[12] = 42;
+        assert_eq!(invalid(&other), Some(12));
+        // strings that aren't UTF-8, after the tag and the length
+        let mut other = Tape::from(vec![Node::String("ab")]).to_bytes();
+        other[22] = 0xff;
+        let err = Tape::from_bytes(&other)
+            .err()
+            .expect("accepted invalid UTF-8");
+        assert_eq!((err.index(), err.error()), (22, &ErrorType::InvalidUTF8));
+        // structures the parser can't make
+        let null = Node::Static(StaticNode::Null);
+        let structures: [&[Node]; 6] = [
+            &[Node::Array(1, 1)],
+            &[Node::Array(2, 2), null],
+            &[Node::Array(1, 3), null, null],
+            &[Node::Object(1, 3), null, null],
+            &[Node::Array(0, 2), null],
+            &[Node::Array(1, 3), Node::Array(1, 4), null],
+        ];
+        for nodes in &structures {
+            let bytes = Tape::from(nodes.to_vec()).to_bytes();
+            assert!(invalid(&bytes).is_some(), "accepted {:?}", nodes);
+        }
+        // reported at the byte offset of the node, the array ends after
+        // its first element so that is the one in the way
+        let bytes = Tape::from(vec![Node::Array(1, 3), null, null]).to_bytes();
+        assert_eq!(invalid(&bytes), Some(12 + 17));
+        // an array that ends past the last node is the one in the way
+        let bytes = Tape::from(vec![Node::Array(1, 2)]).to_bytes();
+        assert_eq!(invalid(&bytes), Some(12));
+        let valid = [
+            null,
+            Node::Object(2, 7),
+            Node::String("a"),
+            Node::Array(1, 5),
+            null,
+            Node::String("b"),
+            Node::Object(0, 7),
+        ];
+        assert!(Tape::from_bytes(&Tape::from(valid.to_vec()).to_bytes()).is_ok());
+    }
 }
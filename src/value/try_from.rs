@@ -0,0 +1,295 @@
+#[cfg(feature = "std")]
+use super::flatten::{push_key, FlattenStyle};
+use super::merge::{IntoParts, Parts};
+#[cfg(feature = "std")]
+use crate::cow::Cow;
+use crate::prelude::*;
+use crate::{BorrowedValue, OwnedValue};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::borrow::Borrow;
+use core::convert::TryFrom;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::{collections::HashMap, hash::BuildHasher};
+
+/// Error for converting a value into a Rust type with `TryFrom`, says
+/// what was expected, what was found and where.
+///
+/// ```rust
+/// use simd_json::{json, ValueType};
+/// use std::convert::TryFrom;
+/// let v = json!({"a": [1, "2"]});
+/// let err = Vec::<u64>::try_from(&v["a"]).unwrap_err();
+/// assert_eq!(err.expected(), ValueType::U64);
+/// assert_eq!(err.found(), ValueType::String);
+/// assert_eq!(err.path(), "/1");
+/// assert_eq!(err.to_string(), "Expected U64 but found String at /1");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TryFromValueError {
+    expected: ValueType,
+    found: ValueType,
+    path: String,
+}
+
+impl TryFromValueError {
+    fn new(expected: ValueType, found: ValueType) -> Self {
+        Self {
+            expected,
+            found,
+            path: String::new(),
+        }
+    }
+
+    /// The type that was asked for
+    #[must_use]
+    pub fn expected(&self) -> ValueType {
+        self.expected
+    }
+
+    /// The type that was found
+    #[must_use]
+    pub fn found(&self) -> ValueType {
+        self.found
+    }
+
+    /// JSON pointer of the value that couldn't be converted, relative to
+    /// the converted value, empty if it's the value itself
+    #[must_use]
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn at_index(mut self, idx: usize) -> Self {
+        self.path.insert_str(0, &idx.to_string());
+        self.path.insert(0, '/');
+        self
+    }
+
+    #[cfg(feature = "std")]
+    fn at_key(mut self, key: &str) -> Self {
+        let mut path = String::new();
+        push_key(&mut path, key, FlattenStyle::Pointer);
+        path.push_str(&self.path);
+        self.path = path;
+        self
+    }
+}
+
+#[cfg_attr(tarpaulin, skip)]
+impl fmt::Display for TryFromValueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Expected {:?} but found {:?}", self.expected, self.found)?;
+        if !self.path.is_empty() {
+            write!(f, " at {}", self.path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TryFromValueError {}
+
+/// Keys of objects taken out of a value
+#[cfg(feature = "std")]
+trait IntoKey {
+    fn into_key(self) -> String;
+}
+
+#[cfg(feature = "std")]
+impl IntoKey for String {
+    fn into_key(self) -> String {
+        self
+    }
+}
+
+#[cfg(feature = "std")]
+impl IntoKey for Cow<'_, str> {
+    fn into_key(self) -> String {
+        self.into_owned()
+    }
+}
+
+fn convert<'a, V, T, F>(value: &'a V, expected: ValueType, f: F) -> Result<T, TryFromValueError>
+where
+    V: ValueTrait,
+    F: FnOnce(&'a V) -> Option<T>,
+{
+    f(value).ok_or_else(|| TryFromValueError::new(expected, value.value_type()))
+}
+
+macro_rules! scalar {
+    ($($lt:lifetime),* ; $value:ty; $target:ty => $expected:ident, $f:expr) => {
+        impl<'a, $($lt),*> TryFrom<&'a $value> for $target {
+            type Error = TryFromValueError;
+            fn try_from(value: &'a $value) -> Result<Self, Self::Error> {
+                convert(value, ValueType::$expected, $f)
+            }
+        }
+
+        impl<$($lt),*> TryFrom<$value> for $target {
+            type Error = TryFromValueError;
+            fn try_from(value: $value) -> Result<Self, Self::Error> {
+                convert(&value, ValueType::$expected, $f)
+            }
+        }
+    };
+}
+
+macro_rules! conversions {
+    ($($lt:lifetime),* ; $value:ty; $string:pat => $owned:expr) => {
+        scalar!($($lt),* ; $value; i64 => I64, ValueTrait::as_i64);
+        scalar!($($lt),* ; $value; u64 => U64, ValueTrait::as_u64);
+        // integers are accepted as well, like `cast_f64` does
+        scalar!($($lt),* ; $value; f64 => F64, ValueTrait::cast_f64);
+        scalar!($($lt),* ; $value; bool => Bool, ValueTrait::as_bool);
+
+        impl<'a, $($lt),*> TryFrom<&'a $value> for String {
+            type Error = TryFromValueError;
+            fn try_from(value: &'a $value) -> Result<Self, Self::Error> {
+                convert(value, ValueType::String, |v| v.as_str().map(String::from))
+            }
+        }
+
+        impl<$($lt),*> TryFrom<$value> for String {
+            type Error = TryFromValueError;
+            fn try_from(value: $value) -> Result<Self, Self::Error> {
+                match value {
+                    $string => Ok($owned),
+                    other => Err(TryFromValueError::new(ValueType::String, other.value_type())),
+                }
+            }
+        }
+
+        impl<'a, $($lt,)* T> TryFrom<&'a $value> for Vec<T>
+        where
+            T: TryFrom<&'a $value, Error = TryFromValueError>,
+        {
+            type Error = TryFromValueError;
+            fn try_from(value: &'a $value) -> Result<Self, Self::Error> {
+                let array = convert(value, ValueType::Array, ValueTrait::as_array)?;
+                array
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, v)| T::try_from(v).map_err(|e| e.at_index(idx)))
+                    .collect()
+            }
+        }
+
+        impl<$($lt,)* T> TryFrom<$value> for Vec<T>
+        where
+            T: TryFrom<$value, Error = TryFromValueError>,
+        {
+            type Error = TryFromValueError;
+            fn try_from(value: $value) -> Result<Self, Self::Error> {
+                let found = value.value_type();
+                match value.into_parts() {
+                    Parts::Array(array) => array
+                        .into_iter()
+                        .enumerate()
+                        .map(|(idx, v)| T::try_from(v).map_err(|e| e.at_index(idx)))
+                        .collect(),
+                    Parts::Object(_) | Parts::Other(_) => {
+                        Err(TryFromValueError::new(ValueType::Array, found))
+                    }
+                }
+            }
+        }
+
+        #[cfg(feature = "std")]
+        impl<'a, $($lt,)* T, S> TryFrom<&'a $value> for HashMap<String, T, S>
+        where
+            T: TryFrom<&'a $value, Error = TryFromValueError>,
+            S: BuildHasher + Default,
+        {
+            type Error = TryFromValueError;
+            fn try_from(value: &'a $value) -> Result<Self, Self::Error> {
+                let object = convert(value, ValueType::Object, ValueTrait::as_object)?;
+                object
+                    .iter()
+                    .map(|(k, v)| {
+                        let k: &str = k.borrow();
+                        match T::try_from(v) {
+                            Ok(v) => Ok((k.to_string(), v)),
+                            Err(e) => Err(e.at_key(k)),
+                        }
+                    })
+                    .collect()
+            }
+        }
+
+        #[cfg(feature = "std")]
+        impl<$($lt,)* T, S> TryFrom<$value> for HashMap<String, T, S>
+        where
+            T: TryFrom<$value, Error = TryFromValueError>,
+            S: BuildHasher + Default,
+        {
+            type Error = TryFromValueError;
+            fn try_from(value: $value) -> Result<Self, Self::Error> {
+                let found = value.value_type();
+                match value.into_parts() {
+                    Parts::Object(object) => object
+                        .into_iter()
+                        .map(|(k, v)| match T::try_from(v) {
+                            Ok(v) => Ok((k.into_key(), v)),
+                            Err(e) => Err(e.at_key(k.borrow())),
+                        })
+                        .collect(),
+                    Parts::Array(_) | Parts::Other(_) => {
+                        Err(TryFromValueError::new(ValueType::Object, found))
+                    }
+                }
+            }
+        }
+    };
+}
+
+conversions!(; OwnedValue; OwnedValue::String(s) => s);
+conversions!('v; BorrowedValue<'v>; BorrowedValue::String(s) => s.into_owned());
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn conversions() {
+        let v = json!({"i": -1, "u": 2, "f": 1.5, "b": true, "s": "x", "a": [1, 2]});
+        assert_eq!(i64::try_from(&v["i"]), Ok(-1));
+        assert_eq!(u64::try_from(&v["u"]), Ok(2));
+        assert_eq!(f64::try_from(&v["f"]), Ok(1.5));
+        assert_eq!(f64::try_from(&v["u"]), Ok(2.0));
+        assert_eq!(bool::try_from(&v["b"]), Ok(true));
+        assert_eq!(String::try_from(&v["s"]), Ok("x".to_string()));
+        assert_eq!(Vec::<u64>::try_from(&v["a"]), Ok(vec![1, 2]));
+        assert_eq!(
+            u64::try_from(&v["i"]),
+            Err(TryFromValueError::new(ValueType::U64, ValueType::I64))
+        );
+
+        let b = BorrowedValue::from(v.clone());
+        assert_eq!(String::try_from(b["s"].clone()), Ok("x".to_string()));
+        assert_eq!(Vec::<i64>::try_from(b["a"].clone()), Ok(vec![1, 2]));
+        let flags = json!({"x": true, "y": false});
+        let map = HashMap::<String, bool>::try_from(BorrowedValue::from(flags)).unwrap();
+        assert_eq!(map.get("y"), Some(&false));
+        let err = HashMap::<String, bool>::try_from(&b["a"]).unwrap_err();
+        assert_eq!(err.to_string(), "Expected Object but found Array");
+    }
+
+    #[test]
+    fn nested_errors() {
+        type Nested = HashMap<String, Vec<HashMap<String, Vec<u64>>>>;
+        let v = json!({"a/b": [{"c": [1]}, {"c": [2, null]}]});
+        let err = Nested::try_from(&v).unwrap_err();
+        assert_eq!(err.path(), "/a~1b/1/c/1");
+        assert_eq!(err.found(), ValueType::Null);
+        let err = Nested::try_from(BorrowedValue::from(v)).unwrap_err();
+        assert_eq!(err.path(), "/a~1b/1/c/1");
+        assert_eq!(
+            err.to_string(),
+            "Expected U64 but found Null at /a~1b/1/c/1"
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use crate::prelude::*;
+use alloc::string::{String, ToString};
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::Hash;
+
+/// Error for getting a field of a certain type with the `try_get_*`
+/// methods of `TryGet`
+#[derive(Debug, Clone, PartialEq)]
+pub enum TryGetError {
+    /// The value isn't an object, holds its type
+    NotAnObject(ValueType),
+    /// The object has no such key
+    Missing(String),
+    /// The field has another type than the one asked for
+    WrongType {
+        /// The key of the field
+        key: String,
+        /// The type asked for
+        expected: ValueType,
+        /// The type of the field
+        found: ValueType,
+    },
+}
+
+impl TryGetError {
+    /// The key that was asked for, `None` if the value isn't an object
+    #[must_use]
+    pub fn key(&self) -> Option<&str> {
+        match self {
+            Self::NotAnObject(_) => None,
+            Self::Missing(key) | Self::WrongType { key, .. } => Some(key),
+        }
+    }
+}
+
+#[cfg_attr(tarpaulin, skip)]
+impl fmt::Display for TryGetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotAnObject(found) => write!(f, "Expected an object but found {:?}", found),
+            Self::Missing(key) => write!(f, "Missing key {:?}", key),
+            Self::WrongType {
+                key,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Expected {:?} for key {:?} but found {:?}",
+                expected, key, found
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TryGetError {}
+
+/// Getters for fields of objects that say what went wrong, instead of
+/// chaining `get` and `as_*` and losing track of why there is no value.
+/// Implemented for all values, the conversions are the ones of the
+/// `as_*` methods so `try_get_u64` takes any integer that fits.
+///
+/// ```rust
+/// use simd_json::{json, prelude::*, TryGetError, ValueType};
+/// let v = json!({"name": "a", "port": "80"});
+/// assert_eq!(v.try_get_str("name"), Ok("a"));
+/// assert_eq!(
+///     v.try_get_u64("port"),
+///     Err(TryGetError::WrongType {
+///         key: "port".to_string(),
+///         expected: ValueType::U64,
+///         found: ValueType::String,
+///     })
+/// );
+/// let err = v.try_get_bool("tls").unwrap_err();
+/// assert_eq!(err.to_string(), r#"Missing key "tls""#);
+/// ```
+pub trait TryGet: ValueTrait {
+    /// Gets the field `key`
+    ///
+    /// # Errors
+    ///
+    /// if the value isn't an object or there is no field `key`
+    fn try_get(&self, key: &str) -> Result<&Self, TryGetError>;
+
+    /// Gets the field `key` as a string
+    ///
+    /// # Errors
+    ///
+    /// if the value isn't an object or the field is missing or isn't a
+    /// string
+    fn try_get_str(&self, key: &str) -> Result<&str, TryGetError> {
+        typed(self, key, ValueType::String, ValueTrait::as_str)
+    }
+
+    /// Gets the field `key` as a boolean
+    ///
+    /// # Errors
+    ///
+    /// if the value isn't an object or the field is missing or isn't a
+    /// boolean
+    fn try_get_bool(&self, key: &str) -> Result<bool, TryGetError> {
+        typed(self, key, ValueType::Bool, ValueTrait::as_bool)
+    }
+
+    /// Gets the field `key` as an `i64`
+    ///
+    /// # Errors
+    ///
+    /// if the value isn't an object or the field is missing or isn't an
+    /// integer that fits
+    fn try_get_i64(&self, key: &str) -> Result<i64, TryGetError> {
+        typed(self, key, ValueType::I64, ValueTrait::as_i64)
+    }
+
+    /// Gets the field `key` as a `u64`
+    ///
+    /// # Errors
+    ///
+    /// if the value isn't an object or the field is missing or isn't an
+    /// integer that fits
+    fn try_get_u64(&self, key: &str) -> Result<u64, TryGetError> {
+        typed(self, key, ValueType::U64, ValueTrait::as_u64)
+    }
+
+    /// Gets the field `key` as a `f64`
+    ///
+    /// # Errors
+    ///
+    /// if the value isn't an object or the field is missing or isn't a
+    /// float
+    fn try_get_f64(&self, key: &str) -> Result<f64, TryGetError> {
+        typed(self, key, ValueType::F64, ValueTrait::as_f64)
+    }
+
+    /// Gets the field `key` as an array
+    ///
+    /// # Errors
+    ///
+    /// if the value isn't an object or the field is missing or isn't an
+    /// array
+    fn try_get_array(&self, key: &str) -> Result<&Self::Array, TryGetError> {
+        typed(self, key, ValueType::Array, ValueTrait::as_array)
+    }
+
+    /// Gets the field `key` as an object
+    ///
+    /// # Errors
+    ///
+    /// if the value isn't an object or the field is missing or isn't an
+    /// object
+    fn try_get_object(&self, key: &str) -> Result<&Self::Object, TryGetError> {
+        typed(self, key, ValueType::Object, ValueTrait::as_object)
+    }
+}
+
+impl<V> TryGet for V
+where
+    V: ValueTrait,
+    V::Key: Borrow<str> + Hash + Eq,
+{
+    fn try_get(&self, key: &str) -> Result<&Self, TryGetError> {
+        let object = self
+            .as_object()
+            .ok_or_else(|| TryGetError::NotAnObject(self.value_type()))?;
+        object
+            .get(key)
+            .ok_or_else(|| TryGetError::Missing(key.to_string()))
+    }
+}
+
+fn typed<'v, V, T, F>(value: &'v V, key: &str, expected: ValueType, f: F) -> Result<T, TryGetError>
+where
+    V: TryGet,
+    F: FnOnce(&'v V) -> Option<T>,
+{
+    let field = value.try_get(key)?;
+    f(field).ok_or_else(|| TryGetError::WrongType {
+        key: key.to_string(),
+        expected,
+        found: field.value_type(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{BorrowedValue, OwnedValue};
+
+    #[test]
+    fn errors() {
+        let v = json!({"s": "x", "n": -1, "f": 1.5, "a": [1], "o": {}, "b": true});
+        let b = BorrowedValue::from(v.clone());
+        assert_eq!(v.try_get_str("s"), Ok("x"));
+        assert_eq!(b.try_get_i64("n"), Ok(-1));
+        assert_eq!(v.try_get_f64("f"), Ok(1.5));
+        assert_eq!(b.try_get_bool("b"), Ok(true));
+        assert_eq!(v.try_get_array("a").map(Vec::len), Ok(1));
+        assert_eq!(b.try_get_object("o").map(halfbrown::HashMap::len), Ok(0));
+        assert_eq!(
+            v.try_get_u64("n"),
+            Err(TryGetError::WrongType {
+                key: "n".to_string(),
+                expected: ValueType::U64,
+                found: ValueType::I64,
+            })
+        );
+        assert_eq!(
+            b.try_get_object("a").unwrap_err().to_string(),
+            r#"Expected Object for key "a" but found Array"#
+        );
+        let err = v.try_get("x").unwrap_err();
+        assert_eq!(err, TryGetError::Missing("x".to_string()));
+        assert_eq!(err.key(), Some("x"));
+        let err = OwnedValue::from(1).try_get_str("s").unwrap_err();
+        assert_eq!(err, TryGetError::NotAnObject(ValueType::I64));
+        assert_eq!(err.key(), None);
+    }
+}
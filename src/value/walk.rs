@@ -0,0 +1,287 @@
+use super::flatten::{push_key, FlattenStyle};
+use crate::prelude::*;
+use crate::{BorrowedValue, OwnedValue, StaticNode};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::hash::Hash;
+use halfbrown::HashMap;
+
+/// The DOMs, with access to their static nodes and to the bytes of `Bytes`
+/// values that `Value` doesn't know about, for code that walks either of
+/// them. It is public
+/// in a private module so public functions can take it as a bound
+/// without it becoming part of the API.
+pub trait Dom: ValueTrait {
+    /// The node of a `Static` value
+    fn static_node(&self) -> Option<&StaticNode>;
+    /// The data of a `Bytes` value
+    #[cfg(feature = "bytes")]
+    fn bytes(&self) -> Option<&[u8]>;
+}
+
+impl Dom for OwnedValue {
+    fn static_node(&self) -> Option<&StaticNode> {
+        match self {
+            OwnedValue::Static(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "bytes")]
+    fn bytes(&self) -> Option<&[u8]> {
+        self.as_bytes()
+    }
+}
+
+impl Dom for BorrowedValue<'_> {
+    fn static_node(&self) -> Option<&StaticNode> {
+        match self {
+            BorrowedValue::Static(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "bytes")]
+    fn bytes(&self) -> Option<&[u8]> {
+        self.as_bytes()
+    }
+}
+
+/// Depth first iterator over a value and everything in it, together
+/// with their JSON pointers, created by `walk`
+#[derive(Debug, Clone)]
+pub struct Walk<'a, V> {
+    stack: Vec<(String, &'a V)>,
+}
+
+impl<'a, V> Iterator for Walk<'a, V>
+where
+    V: ValueTrait,
+    V::Key: Borrow<str>,
+{
+    type Item = (String, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, value) = self.stack.pop()?;
+        // children are pushed in order and reversed so the first one is
+        // popped first
+        let start = self.stack.len();
+        if let Some(object) = value.as_object() {
+            for (key, child) in object.iter() {
+                let mut child_path = path.clone();
+                push_key(&mut child_path, key.borrow(), FlattenStyle::Pointer);
+                self.stack.push((child_path, child));
+            }
+        } else if let Some(array) = value.as_array() {
+            for (idx, child) in array.iter().enumerate() {
+                self.stack.push((index_path(&path, idx), child));
+            }
+        }
+        self.stack[start..].reverse();
+        Some((path, value))
+    }
+}
+
+impl OwnedValue {
+    /// Iterates over the value and everything in it, see
+    /// `BorrowedValue::walk`.
+    ///
+    /// ```rust
+    /// use simd_json::json;
+    /// let v = json!({"a": [1, {"b": 2}]});
+    /// let paths: Vec<String> = v.walk().map(|(path, _)| path).collect();
+    /// assert_eq!(paths, ["", "/a", "/a/0", "/a/1", "/a/1/b"]);
+    /// ```
+    #[must_use]
+    pub fn walk(&self) -> Walk<'_, Self> {
+        Walk {
+            stack: alloc::vec![(String::new(), self)],
+        }
+    }
+
+    /// Calls `f` with the value and everything in it, see
+    /// `BorrowedValue::walk_mut`.
+    pub fn walk_mut<F: FnMut(&str, &mut Self)>(&mut self, f: F) {
+        transform(self, f);
+    }
+
+    /// Lets `f` rewrite the value and everything in it, see
+    /// `BorrowedValue::transform`.
+    ///
+    /// ```rust
+    /// use simd_json::{json, prelude::*, OwnedValue};
+    /// let mut v = json!({"name": "  a ", "sizes": ["1", "x", 2]});
+    /// v.transform(|_, node| {
+    ///     if let Some(s) = node.as_str() {
+    ///         let s = s.trim();
+    ///         *node = s.parse::<i64>().map_or_else(|_| OwnedValue::from(s), OwnedValue::from);
+    ///     }
+    /// });
+    /// assert_eq!(v, json!({"name": "a", "sizes": [1, "x", 2]}));
+    /// ```
+    pub fn transform<F: FnMut(&str, &mut Self)>(&mut self, f: F) {
+        transform(self, f);
+    }
+}
+
+impl BorrowedValue<'_> {
+    /// Iterates depth first over the value and everything in it, parents
+    /// before their children, together with their JSON pointers. The
+    /// value itself comes first with the empty pointer.
+    ///
+    /// ```rust
+    /// use simd_json::prelude::*;
+    /// let mut d = br#"{"user": {"name": "a", "tags": ["x"]}}"#.to_vec();
+    /// let v = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// let strings: Vec<(String, &str)> = v
+    ///     .walk()
+    ///     .filter_map(|(path, v)| Some((path, v.as_str()?)))
+    ///     .collect();
+    /// assert_eq!(
+    ///     strings,
+    ///     [("/user/name".to_string(), "a"), ("/user/tags/0".to_string(), "x")]
+    /// );
+    /// ```
+    #[must_use]
+    pub fn walk(&self) -> Walk<'_, Self> {
+        Walk {
+            stack: alloc::vec![(String::new(), self)],
+        }
+    }
+
+    /// Calls `f` depth first with the value and everything in it and
+    /// their JSON pointers, like `walk`. This is the same traversal as
+    /// `transform`, it takes a closure because an iterator couldn't hand
+    /// out a parent and its children mutably at the same time.
+    ///
+    /// ```rust
+    /// use simd_json::{json, BorrowedValue};
+    /// let mut d = br#"{"a": {"id": 1}, "b": [{"id": 2}]}"#.to_vec();
+    /// let mut v = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// v.walk_mut(|path, v| {
+    ///     if path.ends_with("/id") {
+    ///         *v = BorrowedValue::from(path.to_string());
+    ///     }
+    /// });
+    /// assert_eq!(v, json!({"a": {"id": "/a/id"}, "b": [{"id": "/b/0/id"}]}));
+    /// ```
+    pub fn walk_mut<F: FnMut(&str, &mut Self)>(&mut self, f: F) {
+        transform(self, f);
+    }
+
+    /// Lets `f` rewrite the value and everything in it in place, `f` is
+    /// called with the JSON pointer of every node and can change or
+    /// replace it. Parents are seen before their children, so the
+    /// children visited are the ones `f` left in the parent. The
+    /// traversal keeps its own stack, deeply nested values don't
+    /// overflow the call stack.
+    ///
+    /// ```rust
+    /// use simd_json::{json, BorrowedValue};
+    /// let mut d = br#"{"a": {"note": " x "}, "b": [" y"]}"#.to_vec();
+    /// let mut v = simd_json::to_borrowed_value(&mut d).unwrap();
+    /// v.transform(|_, node| {
+    ///     if let BorrowedValue::String(s) = node {
+    ///         *s = s.trim().to_string().into();
+    ///     }
+    /// });
+    /// assert_eq!(v, json!({"a": {"note": "x"}, "b": ["y"]}));
+    /// ```
+    pub fn transform<F: FnMut(&str, &mut Self)>(&mut self, f: F) {
+        transform(self, f);
+    }
+}
+
+fn index_path(path: &str, idx: usize) -> String {
+    let mut path = path.to_string();
+    path.push('/');
+    path.push_str(&idx.to_string());
+    path
+}
+
+fn transform<K, V, F>(value: &mut V, mut f: F)
+where
+    V: Mutable + ValueTrait<Key = K, Array = Vec<V>, Object = HashMap<K, V>>,
+    K: Borrow<str> + Hash + Eq,
+    F: FnMut(&str, &mut V),
+{
+    let mut stack = alloc::vec![(String::new(), value)];
+    while let Some((path, value)) = stack.pop() {
+        f(&path, value);
+        // like in `Walk`, children are reversed so the first one is next
+        let start = stack.len();
+        if value.is_object() {
+            if let Some(object) = value.as_object_mut() {
+                for (key, child) in object.iter_mut() {
+                    let mut child_path = path.clone();
+                    push_key(&mut child_path, key.borrow(), FlattenStyle::Pointer);
+                    stack.push((child_path, child));
+                }
+            }
+        } else if let Some(array) = value.as_array_mut() {
+            for (idx, child) in array.iter_mut().enumerate() {
+                stack.push((index_path(&path, idx), child));
+            }
+        }
+        stack[start..].reverse();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn order() {
+        let v = json!({"a/b": [[1], {}], "c~": {"d": null}, "e": 2});
+        let walked: Vec<(String, OwnedValue)> =
+            v.walk().map(|(path, v)| (path, v.clone())).collect();
+        assert_eq!(
+            walked,
+            vec![
+                (String::new(), v.clone()),
+                ("/a~1b".to_string(), json!([[1], {}])),
+                ("/a~1b/0".to_string(), json!([1])),
+                ("/a~1b/0/0".to_string(), json!(1)),
+                ("/a~1b/1".to_string(), json!({})),
+                ("/c~0".to_string(), json!({"d": null})),
+                ("/c~0/d".to_string(), json!(null)),
+                ("/e".to_string(), json!(2)),
+            ]
+        );
+
+        let mut paths = Vec::new();
+        let mut b = BorrowedValue::from(v.clone());
+        b.walk_mut(|path, value| {
+            paths.push(path.to_string());
+            if value.is_array() {
+                *value = BorrowedValue::from(vec![3]);
+            }
+        });
+        assert_eq!(paths, ["", "/a~1b", "/a~1b/0", "/c~0", "/c~0/d", "/e"]);
+        assert_eq!(b, json!({"a/b": [3], "c~": {"d": null}, "e": 2}));
+    }
+
+    #[test]
+    fn transform_deep() {
+        let mut v = OwnedValue::from(0);
+        for _ in 0..100_000 {
+            v = OwnedValue::from(vec![v]);
+        }
+        let mut depth = 0;
+        v.transform(|path, node| {
+            if let OwnedValue::Static(_) = node {
+                depth = path.len() / 2;
+                *node = OwnedValue::from("leaf");
+            }
+        });
+        assert_eq!(depth, 100_000);
+        let mut leaf = &v;
+        while let Some(next) = leaf.get_idx(0) {
+            leaf = next;
+        }
+        assert_eq!(leaf, "leaf");
+        v.dismantle();
+    }
+}
@@ -0,0 +1,324 @@
+use crate::value::Dom;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt::Write;
+use value_trait::{Array, Object, ValueType};
+
+/// Writes a value as a YAML document in block style, for tools that read
+/// JSON but have to write YAML for people to edit.
+///
+/// Objects become mappings and arrays sequences, indented by two spaces,
+/// with their entries in the order of the value. Empty ones are written
+/// as `{}` and `[]`. Strings are written plain where YAML reads them back
+/// as the same string and double quoted otherwise, so `"true"`, `"1.0"`
+/// or `"a: b"` stay strings. Floats that aren't finite are written as
+/// `.inf`, `-.inf` and `.nan`, `Bytes` values as `!!binary` base64.
+///
+/// ```rust
+/// let v = simd_json::json!({
+///     "name": "app",
+///     "version": "1.0",
+///     "ports": [80, 443],
+///     "env": {"debug": false, "empty": []},
+///     "routes": [{"path": "/", "note": "a: b"}]
+/// });
+/// assert_eq!(
+///     simd_json::to_yaml_string(&v),
+///     r#"name: app
+/// version: "1.0"
+/// ports:
+///   - 80
+///   - 443
+/// env:
+///   debug: false
+///   empty: []
+/// routes:
+///   - path: /
+///     note: "a: b"
+/// "#
+/// );
+/// ```
+#[must_use]
+pub fn to_yaml_string<V>(value: &V) -> String
+where
+    V: Dom,
+    V::Key: Borrow<str>,
+{
+    let mut out = String::new();
+    let mut todo = Vec::new();
+    write_node(&mut out, &mut todo, value, 0);
+    while let Some(next) = todo.pop() {
+        let (value, indent) = match next {
+            Todo::Element(value, indent, first) => {
+                if !first {
+                    new_line(&mut out, indent);
+                }
+                out.push_str("- ");
+                (value, indent + 2)
+            }
+            Todo::Entry(key, value, indent, first) => {
+                if !first {
+                    new_line(&mut out, indent);
+                }
+                write_str(&mut out, key.borrow());
+                out.push(':');
+                if is_collection(value) {
+                    new_line(&mut out, indent + 2);
+                    (value, indent + 2)
+                } else {
+                    out.push(' ');
+                    (value, indent)
+                }
+            }
+        };
+        write_node(&mut out, &mut todo, value, indent);
+    }
+    out.push('\n');
+    out
+}
+
+/// An entry of a sequence or mapping that is still to be written, with
+/// the column the collection is at and whether it is the first entry
+enum Todo<'v, V: Dom> {
+    Element(&'v V, usize, bool),
+    Entry(&'v V::Key, &'v V, usize, bool),
+}
+
+/// Writes a node that starts at column `indent`, either at the start of a
+/// line or right after the `- ` of a sequence entry. The entries of
+/// mappings and sequences are put on `todo` instead of being written
+/// right away, so deeply nested values don't recurse.
+fn write_node<'v, V>(out: &mut String, todo: &mut Vec<Todo<'v, V>>, value: &'v V, indent: usize)
+where
+    V: Dom,
+{
+    // the entries are pushed in order and reversed so the first one is
+    // popped first
+    let start = todo.len();
+    if let Some(array) = value.as_array().filter(|a| !a.is_empty()) {
+        for (i, v) in array.iter().enumerate() {
+            todo.push(Todo::Element(v, indent, i == 0));
+        }
+    } else if let Some(object) = value.as_object().filter(|o| !o.is_empty()) {
+        for (i, (k, v)) in object.iter().enumerate() {
+            todo.push(Todo::Entry(k, v, indent, i == 0));
+        }
+    } else {
+        write_scalar(out, value);
+    }
+    todo[start..].reverse();
+}
+
+fn new_line(out: &mut String, indent: usize) {
+    out.push('\n');
+    for _ in 0..indent {
+        out.push(' ');
+    }
+}
+
+/// Mappings and sequences that are written in block style
+fn is_collection<V: Dom>(value: &V) -> bool {
+    match (value.as_array(), value.as_object()) {
+        (Some(a), _) => !a.is_empty(),
+        (_, Some(o)) => !o.is_empty(),
+        _ => false,
+    }
+}
+
+fn write_scalar<V: Dom>(out: &mut String, value: &V) {
+    #[cfg(feature = "bytes")]
+    {
+        if let Some(bytes) = value.bytes() {
+            out.push_str("!!binary ");
+            out.push_str(&crate::value::to_base64(bytes));
+            return;
+        }
+    }
+    // writing to a `String` can't fail
+    let _ = match value.value_type() {
+        ValueType::Null => write!(out, "null"),
+        ValueType::Bool => write!(out, "{}", value.as_bool().unwrap_or_default()),
+        ValueType::I64 => write!(out, "{}", value.as_i64().unwrap_or_default()),
+        ValueType::U64 => write!(out, "{}", value.as_u64().unwrap_or_default()),
+        #[cfg(feature = "128bit")]
+        ValueType::I128 => write!(out, "{}", value.as_i128().unwrap_or_default()),
+        #[cfg(feature = "128bit")]
+        ValueType::U128 => write!(out, "{}", value.as_u128().unwrap_or_default()),
+        ValueType::F64 => {
+            let f = value.as_f64().unwrap_or_default();
+            if f.is_nan() {
+                write!(out, ".nan")
+            } else if f.is_infinite() {
+                write!(out, "{}.inf", if f < 0.0 { "-" } else { "" })
+            } else {
+                write!(out, "{}", ryu::Buffer::new().format_finite(f))
+            }
+        }
+        ValueType::String => {
+            write_str(out, value.as_str().unwrap_or_default());
+            Ok(())
+        }
+        ValueType::Array => write!(out, "[]"),
+        ValueType::Object => write!(out, "{{}}"),
+        #[cfg(feature = "bytes")]
+        ValueType::Custom(_) => write!(out, "null"),
+    };
+}
+
+/// Writes a string plain if YAML reads it back as that string and double
+/// quoted otherwise
+fn write_str(out: &mut String, s: &str) {
+    if is_plain(s) {
+        out.push_str(s);
+        return;
+    }
+    out.push('"');
+    for c in s.chars() {
+        let _ = match c {
+            '"' => write!(out, "\\\""),
+            '\\' => write!(out, "\\\\"),
+            '\n' => write!(out, "\\n"),
+            '\r' => write!(out, "\\r"),
+            '\t' => write!(out, "\\t"),
+            // line breaks YAML would fold and the byte order mark
+            '\u{85}' => write!(out, "\\N"),
+            '\u{2028}' => write!(out, "\\L"),
+            '\u{2029}' => write!(out, "\\P"),
+            '\u{feff}' => write!(out, "\\uFEFF"),
+            c if c.is_control() => write!(out, "\\x{:02X}", u32::from(c)),
+            c => write!(out, "{}", c),
+        };
+    }
+    out.push('"');
+}
+
+/// Whether a string can be written without quotes. This is stricter than
+/// YAML needs: anything that could be read as another type, by YAML 1.1
+/// or 1.2, or that starts like a number is quoted.
+fn is_plain(s: &str) -> bool {
+    const RESERVED: [&str; 10] = [
+        "null", "~", "true", "false", "yes", "no", "on", "off", "y", "n",
+    ];
+    let first = match s.chars().next() {
+        Some(c) => c,
+        None => return false,
+    };
+    let last = s.chars().next_back().unwrap_or(first);
+    !(first.is_whitespace()
+        || first.is_ascii_digit()
+        || "-?:,[]{}#&*!|>'\"%@`~+.".contains(first)
+        || last.is_whitespace()
+        || last == ':'
+        || s.contains(": ")
+        || s.contains(" #")
+        || s.chars().any(|c| {
+            c.is_control() || matches!(c, '\u{85}' | '\u{2028}' | '\u{2029}' | '\u{feff}')
+        })
+        || RESERVED.iter().any(|r| r.eq_ignore_ascii_case(s)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{BorrowedValue, OwnedValue};
+
+    fn yaml(value: &OwnedValue) -> String {
+        let borrowed = to_yaml_string(&BorrowedValue::from(value.clone()));
+        let owned = to_yaml_string(value);
+        assert_eq!(owned, borrowed);
+        owned
+    }
+
+    #[test]
+    fn scalars() {
+        assert_eq!(yaml(&json!(null)), "null\n");
+        assert_eq!(yaml(&json!(true)), "true\n");
+        assert_eq!(yaml(&json!(-3)), "-3\n");
+        assert_eq!(yaml(&OwnedValue::from(u64::MAX)), "18446744073709551615\n");
+        assert_eq!(yaml(&json!(1.0)), "1.0\n");
+        assert_eq!(yaml(&json!(0.5)), "0.5\n");
+        assert_eq!(yaml(&OwnedValue::from(f64::NAN)), ".nan\n");
+        assert_eq!(yaml(&OwnedValue::from(f64::INFINITY)), ".inf\n");
+        assert_eq!(yaml(&OwnedValue::from(f64::NEG_INFINITY)), "-.inf\n");
+        assert_eq!(yaml(&json!([])), "[]\n");
+        assert_eq!(yaml(&json!({})), "{}\n");
+    }
+
+    #[test]
+    fn strings() {
+        let plain = ["a", "hello world", "a:b", "a#b", "/path", "yes sir", "ü"];
+        for s in &plain {
+            assert_eq!(yaml(&json!(s)), format!("{}\n", s));
+        }
+        let quoted = [
+            ("", r#""""#),
+            ("true", r#""true""#),
+            ("No", r#""No""#),
+            ("~", r#""~""#),
+            ("1", r#""1""#),
+            ("-1", r#""-1""#),
+            (".5", r#"".5""#),
+            ("12:30", r#""12:30""#),
+            ("- a", r#""- a""#),
+            ("a: b", r#""a: b""#),
+            ("a #b", r#""a #b""#),
+            ("a:", r#""a:""#),
+            (" a", r#"" a""#),
+            ("a ", r#""a ""#),
+            ("*ref", r#""*ref""#),
+            ("say \"hi\"\n", r#""say \"hi\"\n""#),
+            ("\\\t\u{7}", r#""\\\t\x07""#),
+            ("a\u{2028}b", r#""a\Lb""#),
+        ];
+        for (s, expected) in &quoted {
+            assert_eq!(yaml(&json!(s)), format!("{}\n", expected));
+        }
+    }
+
+    #[test]
+    fn nesting() {
+        let v = json!({
+            "a": [[1, 2], [], {"b": {"c": [true]}}],
+            "with space": {"x": {}},
+            "1": null
+        });
+        // converting to a borrowed value doesn't keep the order of the keys
+        assert_eq!(
+            to_yaml_string(&v),
+            r#"a:
+  - - 1
+    - 2
+  - []
+  - b:
+      c:
+        - true
+with space:
+  x: {}
+"1": null
+"#
+        );
+        assert_eq!(yaml(&json!([{"a": 1, "b": 2}])), "- a: 1\n  b: 2\n");
+    }
+
+    #[test]
+    fn deep_nesting() {
+        const DEPTH: usize = 100_000;
+        let mut v = OwnedValue::from(1);
+        for _ in 0..DEPTH {
+            v = OwnedValue::from(vec![v]);
+        }
+        let yaml = to_yaml_string(&v);
+        assert_eq!(yaml.len(), DEPTH * 2 + 2);
+        assert!(yaml.starts_with("- - - ") && yaml.ends_with("- 1\n"));
+        v.dismantle();
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn bytes() {
+        let mut o = crate::owned::Object::new();
+        o.insert("b".to_string(), OwnedValue::Bytes(vec![0, 255]));
+        assert_eq!(yaml(&OwnedValue::from(o)), "b: !!binary AP8=\n");
+    }
+}